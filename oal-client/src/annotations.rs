@@ -0,0 +1,67 @@
+//! An inventory of the annotation keys used by the modules of a program,
+//! e.g. to audit and standardize the vocabulary of metadata across modules.
+//!
+//! Keys that are neither interpreted by the compiler nor vendor extensions are flagged as unknown.
+
+use crate::fixture::Position;
+use crate::FileSystem;
+use anyhow::anyhow;
+use oal_compiler::annotation::{is_known, Annotation};
+use oal_compiler::module::ModuleSet;
+use oal_model::grammar::AbstractSyntaxNode;
+use oal_model::locator::Locator;
+use oal_syntax::parser as syn;
+use std::collections::BTreeMap;
+
+/// The uses of an annotation key.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Usage {
+    pub key: String,
+    /// Whether the key is interpreted by the compiler or is a vendor extension.
+    pub known: bool,
+    /// The module and position of each annotation using the key.
+    pub locations: Vec<(Locator, Position)>,
+}
+
+impl Usage {
+    pub fn count(&self) -> usize {
+        self.locations.len()
+    }
+}
+
+/// Returns the uses of each annotation key in the modules of a program, by key.
+///
+/// The modules of the standard library are left out.
+pub fn inventory<F: FileSystem + ?Sized>(fs: &F, mods: &ModuleSet) -> anyhow::Result<Vec<Usage>> {
+    let mut locs = mods
+        .locators()
+        .filter(|l| oal_compiler::library::source(l).is_none())
+        .collect::<Vec<_>>();
+    // The main module comes first, then the others in a stable order.
+    locs.sort_by_key(|l| (*l != mods.base(), l.to_string()));
+
+    let mut usages: BTreeMap<String, Usage> = BTreeMap::new();
+    for loc in locs {
+        let text = fs.read_file(loc)?;
+        let tree = mods.get(loc).expect("module should exist");
+        for node in tree.root().descendants() {
+            let Some(ann) = syn::Annotation::cast(node) else {
+                continue;
+            };
+            let start = node.span().map_or(0, |s| s.start());
+            let position = Position::from_index(&text, start);
+            let props = Annotation::try_from(ann.as_str())
+                .map_err(|err| anyhow!("{loc}:{position}: {err}"))?
+                .props;
+            for key in props.keys().filter_map(|k| k.as_str()) {
+                let usage = usages.entry(key.to_owned()).or_insert_with(|| Usage {
+                    key: key.to_owned(),
+                    known: is_known(key),
+                    locations: Vec::new(),
+                });
+                usage.locations.push((loc.clone(), position));
+            }
+        }
+    }
+    Ok(usages.into_values().collect())
+}
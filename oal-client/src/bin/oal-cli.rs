@@ -1,35 +1,411 @@
+use anyhow::anyhow;
 use log::{debug, error, info};
 use oal_client::cli::Processor;
-use oal_client::{config, DefaultFileSystem, FileSystem};
+use oal_client::repl::Session;
+use oal_client::{checksum, config, DefaultFileSystem, FileSystem, InMemoryFileSystem};
+use oal_compiler::module::ModuleCache;
+use std::io::Write;
+use std::path::Path;
 use std::process::ExitCode;
 
-fn run(config: config::Config) -> anyhow::Result<()> {
-    let main = config.main()?;
-    let target = config.target()?;
-    let base = config.base()?;
+/// Returns the processor of the program, with the configured settings and remote modules.
+fn processor(config: &config::Config) -> anyhow::Result<Processor> {
+    Ok(Processor::new()
+        .with_settings(config.settings())
+        .with_file_system(config.remote()?))
+}
 
-    let proc = Processor::new();
-    let mods = proc.load(&main)?;
+/// Compiles the main program of a build target into an OpenAPI description,
+/// returning the number of modules.
+///
+/// The modules are taken from and returned to the cache shared by the build targets.
+fn generate(
+    config: &config::Config,
+    build: &config::Build,
+    cache: &mut ModuleCache,
+) -> anyhow::Result<(usize, serde_yaml::Value)> {
+    let main = &build.main;
+    let base = &build.base;
+    let settings = config.settings();
+
+    let mut proc = Processor::new()
+        .with_settings(settings.clone())
+        .with_file_system(config.remote()?);
+    // The progress bar would get in the way of verbose logging.
+    if !config.is_quiet() && config.verbosity() == 0 {
+        proc = proc.with_progress();
+    }
+    let mods = proc.load_cached(main, cache)?;
+    proc.finish();
 
     debug!("Generating API definition");
-    let spec = proc.eval(&mods)?;
-    let mut builder = oal_openapi::Builder::new(spec);
+    let mut spec = proc.eval(&mods)?;
+    proc.check_limits(&mods, &spec)?;
+    if let Some(envelope) = settings.envelope()? {
+        oal_compiler::envelope::apply(&mut spec, envelope);
+    }
+    let mut builder = settings.configure(oal_openapi::Builder::new(spec))?;
 
-    if let Some(ref loc) = base {
+    if let Some(loc) = base {
         let file = DefaultFileSystem.open_file(loc)?;
         let base = serde_yaml::from_reader(file)?;
         builder = builder.with_base(base);
     }
+    let mut api = builder.into_document()?;
 
-    let api = builder.into_openapi();
-    let api_yaml = serde_yaml::to_string(&api)?;
+    if settings.metadata.unwrap_or(true) {
+        let mut sources = Vec::new();
+        for tree in mods.modules() {
+            let loc = tree.locator();
+            if oal_compiler::library::source(loc).is_none() {
+                sources.push((config.display(loc), proc.file_system().read_file(loc)?));
+            }
+        }
+        sources.sort();
+        let sources = sources
+            .iter()
+            .map(|(name, text)| (name.clone(), text.as_str()));
+        checksum::embed_metadata(&mut api, sources);
+    }
 
-    info!("Writing OpenAPI definition to {target}");
-    DefaultFileSystem.write_file(&target, api_yaml)?;
+    if settings.checksum.unwrap_or(false) {
+        checksum::embed(&mut api);
+    }
+
+    let modules = mods.len();
+    cache.release(mods);
+    Ok((modules, api))
+}
 
+/// Compiles and writes all the build targets, sharing the modules they import.
+fn run(config: config::Config) -> anyhow::Result<()> {
+    let mut cache = ModuleCache::new();
+    for build in config.builds()?.iter() {
+        write(&config, build, &mut cache)?;
+    }
     Ok(())
 }
 
+fn write(
+    config: &config::Config,
+    build: &config::Build,
+    cache: &mut ModuleCache,
+) -> anyhow::Result<()> {
+    let Some(ref target) = build.target else {
+        return Err(anyhow!("target not specified"));
+    };
+    let (modules, api) = generate(config, build, cache)?;
+    let output = match build.format {
+        config::Format::Yaml => serde_yaml::to_string(&api)?,
+        config::Format::Json => serde_json::to_string_pretty(&api)? + "\n",
+    };
+
+    let paths = api["paths"].as_mapping().map_or(0, |p| p.len());
+    let components = api["components"]["schemas"]
+        .as_mapping()
+        .map_or(0, |s| s.len());
+    let bytes = output.len();
+
+    let name = config.display(target);
+    info!("Writing OpenAPI definition to {name}");
+    DefaultFileSystem.write_file(target, output)?;
+
+    if !config.is_quiet() {
+        eprintln!(
+            "Compiled {modules} module(s): {paths} path(s), {components} component(s), {bytes} byte(s) written to {name}"
+        );
+    }
+
+    Ok(())
+}
+
+/// Compiles the build targets without writing them, verifying a description if requested.
+///
+/// The description is verified against the build target it was written to, if any,
+/// or else against the first one.
+fn check(config: config::Config) -> anyhow::Result<()> {
+    let verify = config.verify()?;
+    let builds = config.builds()?;
+    let mut cache = ModuleCache::new();
+    let mut results = Vec::new();
+    for build in builds.iter() {
+        results.push(generate(&config, build, &mut cache)?);
+    }
+
+    if let Some(ref loc) = verify {
+        let index = builds
+            .iter()
+            .position(|b| b.target.as_ref() == Some(loc))
+            .unwrap_or(0);
+        let (modules, ref api) = results[index];
+        let name = config.display(loc);
+        info!("Verifying OpenAPI definition {name}");
+        let committed = DefaultFileSystem.read_file(loc)?;
+        checksum::verify(&committed, api).map_err(|err| anyhow!("{name}: {err}"))?;
+        if !config.is_quiet() {
+            eprintln!("Verified {name} against {modules} module(s)");
+        }
+    } else if !config.is_quiet() {
+        for (build, (modules, _)) in builds.iter().zip(results.iter()) {
+            if builds.len() > 1 {
+                let name = config.display(build.target.as_ref().unwrap_or(&build.main));
+                eprintln!("Checked {modules} module(s) for {name}");
+            } else {
+                eprintln!("Checked {modules} module(s)");
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Prints the results of a query over the program, failing if there are none.
+fn query(config: config::Config, text: &str) -> anyhow::Result<()> {
+    let query = text.parse::<oal_compiler::query::Query>()?;
+    let main = config.main()?;
+    let proc = processor(&config)?;
+    let mods = proc.load(&main)?;
+    let spec = proc.eval(&mods)?;
+    let matches = query.run(&spec);
+    for m in matches.iter() {
+        println!("{m}");
+    }
+    if matches.is_empty() {
+        return Err(anyhow!("no match for {text}"));
+    }
+    Ok(())
+}
+
+/// Checks the program for likely mistakes, failing on denied warnings.
+fn lint(config: config::Config) -> anyhow::Result<()> {
+    let main = config.main()?;
+    let proc = processor(&config)?;
+    let mods = proc.load(&main)?;
+    let spec = proc.eval(&mods)?;
+    proc.check_limits(&mods, &spec)?;
+    proc.lint(&mods, &spec)?;
+    if !config.is_quiet() {
+        eprintln!("Linted {} module(s)", mods.len());
+    }
+    Ok(())
+}
+
+/// Prints the evaluated program in a readable text format.
+fn print(config: config::Config) -> anyhow::Result<()> {
+    let main = config.main()?;
+    let proc = processor(&config)?;
+    let mods = proc.load(&main)?;
+    let spec = proc.eval(&mods)?;
+    print!("{spec}");
+    Ok(())
+}
+
+/// Lists the annotation keys used by the program modules, flagging unknown keys.
+fn annotations(config: config::Config) -> anyhow::Result<()> {
+    let main = config.main()?;
+    let proc = processor(&config)?;
+    let mods = proc.load(&main)?;
+    let usages = oal_client::annotations::inventory(proc.file_system(), &mods)?;
+    for usage in usages.iter() {
+        let flag = if usage.known { "" } else { " (unknown)" };
+        println!("{}: {}{flag}", usage.key, usage.count());
+        for (loc, position) in usage.locations.iter() {
+            println!("    {}:{position}", config.display(loc));
+        }
+    }
+    if !config.is_quiet() {
+        let unknown = usages.iter().filter(|u| !u.known).count();
+        eprintln!("{} key(s), {unknown} unknown", usages.len());
+    }
+    Ok(())
+}
+
+/// Applies the fixes of diagnostics to the program modules, then reports the remaining diagnostics.
+fn fix(config: config::Config) -> anyhow::Result<()> {
+    let main = config.main()?;
+    let resolver = config.settings().resolver()?;
+    let applied = oal_client::fix::apply(&config.remote()?, &main, &resolver)?;
+    for fix in applied.iter() {
+        println!(
+            "{}:{}: {}",
+            config.display(&fix.loc),
+            fix.position,
+            fix.title
+        );
+    }
+    if !config.is_quiet() {
+        eprintln!("Applied {} fix(es)", applied.len());
+    }
+    let proc = processor(&config)?;
+    proc.load(&main)?;
+    Ok(())
+}
+
+/// Evaluates the expressions read from the standard input, until the end of input or `:quit`.
+fn repl<F: FileSystem>(session: &mut Session<F>) -> anyhow::Result<()> {
+    let stdin = std::io::stdin();
+    loop {
+        print!("> ");
+        std::io::stdout().flush()?;
+        let mut line = String::new();
+        if stdin.read_line(&mut line)? == 0 || line.trim() == ":quit" {
+            return Ok(());
+        }
+        match session.eval(&line) {
+            Ok(out) => print!("{out}"),
+            Err(err) => eprintln!("error: {err}"),
+        }
+    }
+}
+
+/// Renders the documentation of the program modules into the output directory.
+fn docs(config: config::Config, out: &Path) -> anyhow::Result<()> {
+    let main = config.main()?;
+    let proc = processor(&config)?;
+    let mods = proc.load(&main)?;
+    let pages = oal_client::docs::render(proc.file_system(), &mods, config.root())?;
+    for page in pages.iter() {
+        let path = out.join(&page.path);
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(&path, &page.text)?;
+    }
+    if !config.is_quiet() {
+        eprintln!(
+            "Documented {} module(s) in {}",
+            pages.len() - 1,
+            out.display()
+        );
+    }
+    Ok(())
+}
+
+/// Exports the schema components of the program as a JSON Schema bundle.
+fn schemas(config: config::Config, out: Option<&Path>) -> anyhow::Result<()> {
+    let main = config.main()?;
+    let settings = config.settings();
+    let proc = processor(&config)?;
+    let mods = proc.load(&main)?;
+    let spec = proc.eval(&mods)?;
+    let builder = settings.configure(oal_openapi::Builder::new(spec))?;
+    let bundle = builder.into_json_schema()?;
+    let output = serde_json::to_string_pretty(&bundle)? + "\n";
+    match out {
+        Some(path) => {
+            std::fs::write(path, output)?;
+            if !config.is_quiet() {
+                let defs = bundle["$defs"].as_mapping().map_or(0, |d| d.len());
+                eprintln!("Exported {defs} schema(s) to {}", path.display());
+            }
+        }
+        None => print!("{output}"),
+    }
+    Ok(())
+}
+
+/// Renders the API reference of the program.
+fn api_reference(
+    config: config::Config,
+    format: oal_client::reference::Format,
+    out: Option<&Path>,
+) -> anyhow::Result<()> {
+    let main = config.main()?;
+    let proc = processor(&config)?;
+    let mods = proc.load(&main)?;
+    let spec = proc.eval(&mods)?;
+    let output = oal_client::reference::render(&spec, format);
+    match out {
+        Some(path) => {
+            std::fs::write(path, output)?;
+            if !config.is_quiet() {
+                eprintln!(
+                    "Documented {} relation(s) in {}",
+                    spec.rels.len(),
+                    path.display()
+                );
+            }
+        }
+        None => print!("{output}"),
+    }
+    Ok(())
+}
+
+/// Exports the data models of the program as Protocol Buffers messages.
+fn proto(config: config::Config, out: Option<&Path>) -> anyhow::Result<()> {
+    let main = config.main()?;
+    let proc = processor(&config)?;
+    let mods = proc.load(&main)?;
+    let spec = proc.eval(&mods)?;
+    let output = oal_client::proto::render(&spec);
+    match out {
+        Some(path) => {
+            std::fs::write(path, output)?;
+            if !config.is_quiet() {
+                eprintln!("Exported the data models to {}", path.display());
+            }
+        }
+        None => print!("{output}"),
+    }
+    Ok(())
+}
+
+/// Compares two versions of a program, returning whether no change is breaking.
+fn diff(
+    config: &config::Config,
+    old: &oal_model::locator::Locator,
+    new: &oal_model::locator::Locator,
+) -> anyhow::Result<bool> {
+    let proc = processor(config)?;
+    let old_spec = proc.eval(&proc.load(old)?)?;
+    let new_spec = proc.eval(&proc.load(new)?)?;
+    let changes = oal_compiler::diff::diff(&old_spec, &new_spec);
+    for change in changes.iter() {
+        let flag = if change.breaking { "breaking" } else { "info" };
+        println!("{flag}: {change}");
+    }
+    let breaking = changes.iter().filter(|c| c.breaking).count();
+    if !config.is_quiet() {
+        eprintln!("Found {} change(s), {breaking} breaking", changes.len());
+    }
+    Ok(breaking == 0)
+}
+
+/// Checks fixtures against their expected diagnostics, returning whether all of them passed.
+fn test(config: &config::Config, fixtures: Vec<oal_model::locator::Locator>) -> bool {
+    let settings = config.settings();
+    let fs = match config.remote() {
+        Ok(fs) => fs,
+        Err(err) => {
+            eprintln!("Error: {err}");
+            return false;
+        }
+    };
+    let mut failed = 0;
+    for loc in fixtures.iter() {
+        let name = config.display(loc);
+        match oal_client::fixture::check(&fs, loc, &settings) {
+            Ok(outcome) if outcome.is_ok() => println!("ok: {name}"),
+            Ok(outcome) => {
+                failed += 1;
+                println!("FAILED: {name}");
+                for expect in outcome.missing.iter() {
+                    println!("    missing {expect}");
+                }
+                for diag in outcome.unexpected.iter() {
+                    println!("    unexpected {diag}");
+                }
+            }
+            Err(err) => {
+                failed += 1;
+                println!("FAILED: {name}: {err}");
+            }
+        }
+    }
+    println!("{} passed, {failed} failed", fixtures.len() - failed);
+    failed == 0
+}
+
 fn main() -> ExitCode {
     let config = match config::Config::new(None) {
         Ok(config) => config,
@@ -39,14 +415,166 @@ fn main() -> ExitCode {
         }
     };
 
+    if let Some(code) = config.explain() {
+        return match oal_compiler::errors::explain(code) {
+            Some(text) => {
+                println!("{text}");
+                ExitCode::SUCCESS
+            }
+            None => {
+                eprintln!("Error: unknown diagnostic code {code}");
+                ExitCode::FAILURE
+            }
+        };
+    }
+
+    if config.doc() {
+        let docs = match oal_compiler::stdlib::documentation() {
+            Ok(docs) => docs,
+            Err(err) => {
+                eprintln!("Error: {}", err);
+                return ExitCode::FAILURE;
+            }
+        };
+        let text = docs
+            .iter()
+            .map(|d| d.to_string())
+            .collect::<Vec<_>>()
+            .join("\n\n");
+        println!("{text}");
+        return ExitCode::SUCCESS;
+    }
+
+    match config.fixtures() {
+        Ok(Some(fixtures)) => {
+            return if test(&config, fixtures) {
+                ExitCode::SUCCESS
+            } else {
+                ExitCode::FAILURE
+            };
+        }
+        Ok(None) => {}
+        Err(err) => {
+            eprintln!("Error: {}", err);
+            return ExitCode::FAILURE;
+        }
+    }
+
+    match config.diff() {
+        Ok(Some((old, new))) => {
+            return match diff(&config, &old, &new) {
+                Ok(true) => ExitCode::SUCCESS,
+                Ok(false) => ExitCode::FAILURE,
+                Err(err) => {
+                    eprintln!("Error: {}", err);
+                    ExitCode::FAILURE
+                }
+            };
+        }
+        Ok(None) => {}
+        Err(err) => {
+            eprintln!("Error: {}", err);
+            return ExitCode::FAILURE;
+        }
+    }
+
+    if let Some(pkg) = config.playground() {
+        let source = match config.main() {
+            Ok(main) => DefaultFileSystem
+                .read_file(&main)
+                .map_err(anyhow::Error::from),
+            Err(_) => Ok(oal_client::playground::starter().to_owned()),
+        };
+        return match source.and_then(|s| oal_client::playground::render(pkg, &s)) {
+            Ok(page) => {
+                print!("{page}");
+                ExitCode::SUCCESS
+            }
+            Err(err) => {
+                eprintln!("Error: {}", err);
+                ExitCode::FAILURE
+            }
+        };
+    }
+
+    if let Some((dir, layout)) = config.init() {
+        return match oal_client::init::scaffold(dir, layout) {
+            Ok(files) => {
+                for file in files.iter() {
+                    println!("Created {}", file.display());
+                }
+                if let Some(conf) = files.first() {
+                    println!(
+                        "Run `oal-cli --conf {}` to generate the API definition",
+                        conf.display()
+                    );
+                }
+                ExitCode::SUCCESS
+            }
+            Err(err) => {
+                eprintln!("Error: {}", err);
+                ExitCode::FAILURE
+            }
+        };
+    }
+
+    // Errors are always reported, even in quiet mode.
     stderrlog::new()
-        .quiet(config.is_quiet())
         .verbosity(config.verbosity())
         .timestamp(stderrlog::Timestamp::Off)
         .init()
         .unwrap();
 
-    if let Err(err) = run(config) {
+    if let Some(addr) = config.serve() {
+        return match oal_client::serve::serve(addr, config.settings()) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(err) => {
+                error!("{}", err);
+                ExitCode::FAILURE
+            }
+        };
+    }
+
+    let result = if let Some(out) = config.docs().map(Path::to_path_buf) {
+        docs(config, &out)
+    } else if let Some(out) = config.schemas().map(|o| o.map(Path::to_path_buf)) {
+        schemas(config, out.as_deref())
+    } else if let Some((format, out)) = config
+        .api_reference()
+        .map(|(f, o)| (f, o.map(Path::to_path_buf)))
+    {
+        api_reference(config, format, out.as_deref())
+    } else if let Some(out) = config.proto().map(|o| o.map(Path::to_path_buf)) {
+        proto(config, out.as_deref())
+    } else if let Some(text) = config.query().map(ToOwned::to_owned) {
+        query(config, &text)
+    } else if config.lint() {
+        lint(config)
+    } else if config.print() {
+        print(config)
+    } else if config.annotations() {
+        annotations(config)
+    } else if config.fix() {
+        fix(config)
+    } else if config.repl() {
+        match config.main() {
+            Ok(main) => config.remote().and_then(|fs| {
+                let resolver = config.settings().resolver()?;
+                repl(&mut Session::new(fs, main).with_resolver(resolver))
+            }),
+            // Without a program, expressions are evaluated on their own.
+            Err(_) => {
+                let main = InMemoryFileSystem::locator("main.oal");
+                repl(&mut Session::new(InMemoryFileSystem::new(), main))
+            }
+        }
+    } else if config.check() {
+        check(config)
+    } else {
+        run(config)
+    };
+
+    if let Err(err) = result {
         error!("{}", err);
         ExitCode::FAILURE
     } else {
@@ -3,19 +3,25 @@ use crossbeam_channel::select;
 use log::info;
 use lsp_server::{Connection, Message, Notification};
 use lsp_types::notification::{
-    DidChangeTextDocument, DidChangeWorkspaceFolders, DidCloseTextDocument, DidOpenTextDocument,
-    PublishDiagnostics,
+    DidChangeConfiguration, DidChangeTextDocument, DidChangeWorkspaceFolders, DidCloseTextDocument,
+    DidOpenTextDocument, PublishDiagnostics,
+};
+use lsp_types::request::{
+    CodeActionRequest, Completion, GotoDefinition, HoverRequest, PrepareRenameRequest, References,
+    Rename, SemanticTokensFullRequest,
+};
+use lsp_types::{
+    CodeActionProviderCapability, CompletionOptions, HoverProviderCapability, OneOf, RenameOptions,
+    SemanticTokensFullOptions, SemanticTokensOptions, SemanticTokensServerCapabilities,
 };
-use lsp_types::request::{GotoDefinition, PrepareRenameRequest, References, Rename};
 use lsp_types::{
     InitializeParams, PositionEncodingKind, PublishDiagnosticsParams, ServerCapabilities,
     TextDocumentSyncCapability, TextDocumentSyncKind, WorkspaceFileOperationsServerCapabilities,
     WorkspaceFoldersServerCapabilities, WorkspaceServerCapabilities,
 };
-use lsp_types::{OneOf, RenameOptions};
 use oal_client::lsp::dispatcher::{NotificationDispatcher, RequestDispatcher};
 use oal_client::lsp::state::GlobalState;
-use oal_client::lsp::{handlers, Folder, Workspace};
+use oal_client::lsp::{client_settings, handlers, semantic, Folder, Workspace};
 use std::collections::HashMap;
 use std::time::Duration;
 
@@ -42,10 +48,20 @@ fn main() -> anyhow::Result<()> {
         position_encoding: Some(PositionEncodingKind::UTF16),
         definition_provider: Some(OneOf::Left(true)),
         references_provider: Some(OneOf::Left(true)),
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        completion_provider: Some(CompletionOptions::default()),
+        code_action_provider: Some(CodeActionProviderCapability::Simple(true)),
         rename_provider: Some(OneOf::Right(RenameOptions {
             prepare_provider: Some(true),
             work_done_progress_options: Default::default(),
         })),
+        semantic_tokens_provider: Some(SemanticTokensServerCapabilities::SemanticTokensOptions(
+            SemanticTokensOptions {
+                legend: semantic::legend(),
+                full: Some(SemanticTokensFullOptions::Bool(true)),
+                ..Default::default()
+            },
+        )),
         workspace: Some(WorkspaceServerCapabilities {
             workspace_folders: Some(WorkspaceFoldersServerCapabilities {
                 supported: Some(true),
@@ -70,10 +86,12 @@ fn main() -> anyhow::Result<()> {
         .and_then(|e| e.contains(&PositionEncodingKind::UTF16).then_some(()))
         .ok_or_else(|| anyhow!("UTF-16 not supported by client"))?;
 
+    let settings = client_settings(params.initialization_options);
+
     let mut folders = HashMap::new();
     for f in params.workspace_folders.unwrap_or_default().into_iter() {
         let uri = f.uri.clone();
-        if let Ok(folder) = Folder::new(f) {
+        if let Ok(folder) = Folder::new(f, &settings) {
             folders.insert(uri, folder);
         }
     }
@@ -84,6 +102,7 @@ fn main() -> anyhow::Result<()> {
         conn,
         workspace,
         folders,
+        settings,
         is_stale: true,
     };
 
@@ -139,7 +158,11 @@ fn main_loop(state: &mut GlobalState) -> anyhow::Result<()> {
                         .on::<GotoDefinition, _>(handlers::go_to_definition)?
                         .on::<References, _>(handlers::references)?
                         .on::<PrepareRenameRequest, _>(handlers::prepare_rename)?
-                        .on::<Rename, _>(handlers::rename)?;
+                        .on::<Rename, _>(handlers::rename)?
+                        .on::<HoverRequest, _>(handlers::hover)?
+                        .on::<Completion, _>(handlers::completion)?
+                        .on::<CodeActionRequest, _>(handlers::code_action)?
+                        .on::<SemanticTokensFullRequest, _>(handlers::semantic_tokens_full)?;
                     }
                     Message::Response(_resp) => {}
                     Message::Notification(not) => {
@@ -165,12 +188,20 @@ fn main_loop(state: &mut GlobalState) -> anyhow::Result<()> {
                             }
                             for f in params.event.added {
                                 let uri = f.uri.clone();
-                                if let Ok(folder) = Folder::new(f) {
+                                if let Ok(folder) = Folder::new(f, &state.settings) {
                                     state.folders.insert(uri, folder);
                                 }
                             }
                             state.is_stale = true;
                             Ok(())
+                        })?
+                        .on::<DidChangeConfiguration>(|state, params| {
+                            state.settings = client_settings(Some(params.settings));
+                            for f in state.folders.values_mut() {
+                                f.configure(&state.settings);
+                            }
+                            state.is_stale = true;
+                            Ok(())
                         })?;
                     }
                 }
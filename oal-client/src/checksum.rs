@@ -0,0 +1,104 @@
+//! Content hashes of generated OpenAPI descriptions, to detect drift from their sources.
+//!
+//! The hash is embedded as a vendor extension of the document, e.g.:
+//! ```yaml
+//! x-oal-checksum:
+//!   sha256: 5d41402abc4b2a76b9719d911017c592...
+//!   version: 0.1.0
+//! ```
+//! It covers the whole document except the extension itself, in a form independent of the
+//! layout of the YAML file.
+//!
+//! The version of the compiler and the hashes of the sources are embedded
+//! as a vendor extension of the document information, e.g.:
+//! ```yaml
+//! info:
+//!   x-oal-metadata:
+//!     version: 0.1.0
+//!     sources:
+//!       main.oal: 2c26b46b68ffc68ff99b453c1d304134...
+//! ```
+
+use anyhow::anyhow;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+
+/// The name of the vendor extension holding the checksum.
+pub const EXTENSION: &str = "x-oal-checksum";
+
+/// The name of the vendor extension of the document information holding the metadata.
+pub const METADATA_EXTENSION: &str = "x-oal-metadata";
+
+/// The version of the tool generating the document.
+const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Returns the hexadecimal SHA-256 hash of a document, ignoring any embedded checksum.
+fn digest(mut doc: Value) -> String {
+    if let Some(obj) = doc.as_object_mut() {
+        obj.remove(EXTENSION);
+    }
+    // Object keys are sorted, which makes the serialization canonical.
+    let bytes = serde_json::to_vec(&doc).expect("document should serialize");
+    sha256(&bytes)
+}
+
+/// Returns the hexadecimal SHA-256 hash of bytes.
+pub(crate) fn sha256(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+/// Returns the checksum of a serialized OpenAPI description.
+pub fn checksum(doc: &serde_yaml::Value) -> String {
+    digest(serde_json::to_value(doc).expect("document should convert"))
+}
+
+/// Embeds the checksum of the description along with the tool version.
+pub fn embed(doc: &mut serde_yaml::Value) {
+    let value = json!({ "sha256": checksum(doc), "version": VERSION });
+    if let Some(obj) = doc.as_mapping_mut() {
+        let value = serde_yaml::to_value(value).expect("checksum should serialize");
+        obj.insert(EXTENSION.into(), value);
+    }
+}
+
+/// Embeds the version of the tool and the hashes of the given sources, by module name.
+pub fn embed_metadata<'a, I>(doc: &mut serde_yaml::Value, sources: I)
+where
+    I: IntoIterator<Item = (String, &'a str)>,
+{
+    let sources = sources
+        .into_iter()
+        .map(|(name, text)| (name.into(), sha256(text.as_bytes()).into()))
+        .collect::<serde_yaml::Mapping>();
+    let mut value = serde_yaml::Mapping::new();
+    value.insert("version".into(), VERSION.into());
+    value.insert("sources".into(), sources.into());
+    if let Some(info) = doc.get_mut("info").and_then(|i| i.as_mapping_mut()) {
+        info.insert(METADATA_EXTENSION.into(), value.into());
+    }
+}
+
+/// Verifies a committed description against its embedded checksum and the expected description.
+///
+/// Fails if the committed description has no checksum, was modified after being generated,
+/// or differs from the description generated from the current sources.
+pub fn verify(committed: &str, expected: &serde_yaml::Value) -> anyhow::Result<()> {
+    let doc: Value = serde_yaml::from_str(committed)?;
+    let Some(embedded) = doc[EXTENSION]["sha256"].as_str() else {
+        return Err(anyhow!("no {EXTENSION} extension found"));
+    };
+    let embedded = embedded.to_owned();
+    let version = doc[EXTENSION]["version"].as_str().unwrap_or("unknown");
+    if digest(doc.clone()) != embedded {
+        return Err(anyhow!("description was modified after being generated"));
+    }
+    if checksum(expected) != embedded {
+        return Err(anyhow!(
+            "description is out of date with its sources (generated by version {version})"
+        ));
+    }
+    Ok(())
+}
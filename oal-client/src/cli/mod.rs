@@ -1,110 +1,281 @@
-use crate::{DefaultFileSystem, FileSystem};
+use crate::config::{LintLevel, Settings};
+use crate::{DefaultFileSystem, DiagnosticLoader, FileSystem};
 use anyhow::anyhow;
-use ariadne::{ColorGenerator, Label, Report, ReportKind, Source};
+use ariadne::{ColorGenerator, Label, Report, ReportKind};
+use indicatif::{ProgressBar, ProgressStyle};
 use log::debug;
-use oal_compiler::module::{Loader, ModuleSet};
+use oal_compiler::errors::Error;
+use oal_compiler::module::{LoadLimits, Loader, ModuleCache, ModuleSet};
 use oal_compiler::spec::Spec;
 use oal_compiler::tree::Tree;
 use oal_model::locator::Locator;
 use oal_model::span::Span;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
 
-#[derive(Default)]
 /// The CLI compilation processor.
-pub struct Processor;
+pub struct Processor {
+    progress: ProgressBar,
+    settings: Settings,
+    fs: Box<dyn FileSystem>,
+}
+
+impl Default for Processor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl Processor {
     pub fn new() -> Self {
-        Processor
+        Processor {
+            progress: ProgressBar::hidden(),
+            settings: Settings::default(),
+            fs: Box::new(DefaultFileSystem),
+        }
+    }
+
+    /// Displays a progress bar while loading and compiling modules.
+    pub fn with_progress(mut self) -> Self {
+        let style = ProgressStyle::with_template("{spinner} [{pos}/{len}] {msg}")
+            .expect("progress template should be valid");
+        self.progress = ProgressBar::new(0).with_style(style);
+        self
+    }
+
+    /// Applies the compilation settings, e.g. strictness and lint levels.
+    pub fn with_settings(mut self, settings: Settings) -> Self {
+        self.settings = settings;
+        self
+    }
+
+    /// Reads the modules from the given file system, e.g. with remote modules.
+    pub fn with_file_system<F: FileSystem + 'static>(mut self, fs: F) -> Self {
+        self.fs = Box::new(fs);
+        self
+    }
+
+    /// Returns the file system the modules are read from.
+    pub fn file_system(&self) -> &dyn FileSystem {
+        self.fs.as_ref()
+    }
+
+    /// Clears the progress bar, if any.
+    pub fn finish(&self) {
+        self.progress.finish_and_clear();
     }
 }
 
 impl Processor {
-    /// Reports an error.
-    pub fn report<M: ToString>(&self, span: Span, msg: M) -> anyhow::Result<()> {
+    /// Reports an error with its diagnostic code and secondary labels.
+    pub fn report<M: ToString>(
+        &self,
+        span: Span,
+        code: &str,
+        msg: M,
+        labels: &[(Span, String)],
+    ) -> anyhow::Result<()> {
+        self.report_kind(ReportKind::Error, span, code, msg, labels)
+    }
+
+    /// Reports a warning with its diagnostic code and secondary labels.
+    pub fn warn<M: ToString>(
+        &self,
+        span: Span,
+        code: &str,
+        msg: M,
+        labels: &[(Span, String)],
+    ) -> anyhow::Result<()> {
+        self.report_kind(ReportKind::Warning, span, code, msg, labels)
+    }
+
+    fn report_kind<M: ToString>(
+        &self,
+        kind: ReportKind,
+        span: Span,
+        code: &str,
+        msg: M,
+        labels: &[(Span, String)],
+    ) -> anyhow::Result<()> {
+        // Make sure the progress bar does not overwrite the report.
+        self.progress.finish_and_clear();
         let mut colors = ColorGenerator::new();
         let color = colors.next();
         let loc = span.locator().clone();
-        let input = DefaultFileSystem.read_file(&loc)?;
+        let input = self.read_source(&loc)?;
         let char_span = CharSpan::from(&input, span);
-        let mut builder = Report::build(ReportKind::Error, char_span.clone()).with_message(msg);
+        let mut builder = Report::build(kind, char_span.clone())
+            .with_code(code)
+            .with_message(msg);
         if !ariadne::Span::is_empty(&char_span) {
             builder.add_label(Label::new(char_span).with_color(color))
         }
-        builder.finish().eprint((loc, Source::from(input)))?;
+        let mut sources = HashMap::from([(loc, input)]);
+        for (span, msg) in labels {
+            let loc = span.locator().clone();
+            let text = match sources.entry(loc) {
+                Entry::Occupied(e) => e.into_mut(),
+                Entry::Vacant(e) => {
+                    let text = self.read_source(e.key())?;
+                    e.insert(text)
+                }
+            };
+            let char_span = CharSpan::from(text, span.clone());
+            builder.add_label(
+                Label::new(char_span)
+                    .with_message(msg)
+                    .with_color(colors.next()),
+            )
+        }
+        builder.finish().eprint(ariadne::sources(sources))?;
         Ok(())
     }
 
     pub fn load(&self, main: &Locator) -> anyhow::Result<ModuleSet> {
-        let mods = oal_compiler::module::load(&mut self.loader(), main)?;
+        let limits = self.settings.load_limits(LoadLimits::default());
+        let mods = oal_compiler::module::load_with_limits(&mut self.loader()?, main, &limits)?;
+        Ok(mods)
+    }
+
+    /// Loads a program, reusing the modules compiled for previous programs.
+    pub fn load_cached(
+        &self,
+        main: &Locator,
+        cache: &mut ModuleCache,
+    ) -> anyhow::Result<ModuleSet> {
+        let limits = self.settings.load_limits(LoadLimits::default());
+        let mods = oal_compiler::module::load_cached(&mut self.loader()?, main, &limits, cache)?;
         Ok(mods)
     }
 
     /// Evaluates a program.
     pub fn eval(&self, mods: &ModuleSet) -> anyhow::Result<Spec> {
-        match oal_compiler::eval::eval(mods) {
+        match oal_compiler::eval::eval_with_warnings(mods) {
             Err(err) => {
                 let span = match err.span() {
                     Some(s) => s.clone(),
                     None => Span::new(mods.base().clone(), 0..0),
                 };
-                self.report(span, &err)?;
+                self.report(span, err.code(), &err, err.labels())?;
                 Err(anyhow!("evaluation failed"))
             }
-            Ok(spec) => Ok(spec),
+            Ok((spec, warnings)) => {
+                self.report_warnings(mods.base(), warnings)?;
+                Ok(spec)
+            }
         }
     }
 
-    pub fn loader(&self) -> impl Loader<anyhow::Error> + '_ {
-        ProcLoader(self)
+    /// Reports warnings according to the level of their lint.
+    ///
+    /// Returns an error if any of the warnings is denied.
+    fn report_warnings(&self, loc: &Locator, warnings: Vec<Error>) -> anyhow::Result<()> {
+        let mut denied = false;
+        for err in warnings {
+            let span = match err.span() {
+                Some(s) => s.clone(),
+                None => Span::new(loc.clone(), 0..0),
+            };
+            match self.settings.lint_level(&err) {
+                LintLevel::Allow => {}
+                LintLevel::Warn => self.warn(span, err.code(), &err, err.labels())?,
+                LintLevel::Deny => {
+                    self.report(span, err.code(), &err, err.labels())?;
+                    denied = true;
+                }
+            }
+        }
+        if denied {
+            Err(anyhow!("denied warnings"))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Checks the specification against the configured limits, reporting warnings.
+    pub fn check_limits(&self, mods: &ModuleSet, spec: &Spec) -> anyhow::Result<()> {
+        let limits = self.settings.limits();
+        let warnings = oal_compiler::limits::check_limits(mods, spec, &limits);
+        self.report_warnings(mods.base(), warnings)
+    }
+
+    /// Checks the program and its specification for likely mistakes, reporting warnings.
+    pub fn lint(&self, mods: &ModuleSet, spec: &Spec) -> anyhow::Result<()> {
+        let warnings = oal_compiler::lint::lint(mods, spec);
+        self.report_warnings(mods.base(), warnings)
+    }
+
+    pub fn loader(&self) -> anyhow::Result<impl Loader<anyhow::Error> + '_> {
+        let strict = self.settings.strict.unwrap_or(false);
+        let loader = DiagnosticLoader::new(self.fs.as_ref(), strict)
+            .with_resolver(self.settings.resolver()?);
+        Ok(ProcLoader(self, loader))
+    }
+
+    /// Reads the source of a module, either from the library of standard modules or from a file.
+    fn read_source(&self, loc: &Locator) -> anyhow::Result<String> {
+        match oal_compiler::library::source(loc) {
+            Some(code) => Ok(code.to_owned()),
+            None => Ok(self.fs.read_file(loc)?),
+        }
     }
 }
 
-struct ProcLoader<'a>(&'a Processor);
+struct ProcLoader<'a>(&'a Processor, DiagnosticLoader<'a, dyn FileSystem>);
 
 impl Loader<anyhow::Error> for ProcLoader<'_> {
     /// Returns true if the given locator points to a valid source file.
     fn is_valid(&mut self, loc: &Locator) -> bool {
-        DefaultFileSystem.is_valid(loc)
+        self.0.fs.is_valid(loc)
     }
 
     /// Loads a source file.
     fn load(&mut self, loc: &Locator) -> anyhow::Result<String> {
-        let code = DefaultFileSystem.read_file(loc)?;
+        let code = self.0.fs.read_file(loc)?;
         Ok(code)
     }
 
     /// Parses a source file into a concrete syntax tree.
     fn parse(&mut self, loc: Locator, input: String) -> anyhow::Result<Tree> {
         debug!("Parsing module {loc}");
-        let (tree, mut errs) = oal_syntax::parse(loc.clone(), input);
-        if let Some(err) = errs.pop() {
-            // We don't care about error recovery for the command line interface.
-            let span = match err {
-                oal_syntax::errors::Error::Grammar(ref err) => err.span(),
-                oal_syntax::errors::Error::Lexicon(ref err) => err.span(),
-                _ => Span::new(loc, 0..0),
-            };
-            self.0.report(span, &err)?;
+        self.0.progress.inc_length(1);
+        self.0.progress.set_message(format!("Parsing {loc}"));
+        // We don't care about error recovery for the command line interface.
+        self.1.parse(loc.clone(), input).or_else(|err| {
+            let span = err.span().cloned().unwrap_or_else(|| Span::new(loc, 0..0));
+            self.0.report(span, err.code(), &err, err.labels())?;
             Err(anyhow!("parsing failed"))
-        } else {
-            tree.ok_or_else(|| anyhow!("parsing failed"))
-        }
+        })
     }
 
     /// Compiles a program.
     fn compile(&mut self, mods: &ModuleSet, loc: &Locator) -> anyhow::Result<()> {
         debug!("Compiling module {loc}");
-        if let Err(err) = oal_compiler::compile::compile(mods, loc) {
-            let span = match err.span() {
-                Some(s) => s.clone(),
-                None => Span::new(loc.clone(), 0..0),
-            };
-            self.0.report(span, &err)?;
-            Err(anyhow!("compilation failed"))
-        } else {
-            Ok(())
+        self.0.progress.set_message(format!("Compiling {loc}"));
+        let result = self.1.compile(mods, loc);
+        self.0.progress.inc(1);
+        match result {
+            Err(err) => {
+                let span = match err.span() {
+                    Some(s) => s.clone(),
+                    None => Span::new(loc.clone(), 0..0),
+                };
+                self.0.report(span, err.code(), &err, err.labels())?;
+                Err(anyhow!("compilation failed"))
+            }
+            Ok(()) => self.0.report_warnings(loc, self.1.take_warnings()),
         }
     }
+
+    /// Resolves an import through the aliases of module paths.
+    fn resolve(&mut self, loc: &Locator, import: &str) -> oal_compiler::errors::Result<Locator> {
+        self.1.resolve(loc, import)
+    }
+
+    /// Returns the canonical locator of a source file.
+    fn canonicalize(&mut self, loc: &Locator) -> Locator {
+        self.0.fs.canonicalize(loc).unwrap_or_else(|_| loc.clone())
+    }
 }
 
 #[derive(Clone)]
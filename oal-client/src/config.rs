@@ -1,6 +1,16 @@
-use clap::Parser as ClapParser;
+use crate::reference;
+use crate::remote::RemoteFileSystem;
+use crate::resolver::Resolver;
+use anyhow::anyhow;
+use clap::{Parser as ClapParser, Subcommand, ValueEnum};
+use oal_compiler::envelope::Envelope;
+use oal_compiler::errors::Error;
+use oal_compiler::limits::Limits;
+use oal_compiler::module::LoadLimits;
 use oal_model::locator::Locator;
+use oal_openapi::{Builder, IdScheme, LabelCase, OpenApiVersion};
 use serde::Deserialize;
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use url::Url;
 
@@ -19,6 +29,10 @@ struct Args {
     #[arg(short = 'b', long)]
     base: Option<String>,
 
+    /// The format of the target OpenAPI description, by default from its extension
+    #[arg(short = 'f', long, value_enum)]
+    format: Option<Format>,
+
     /// The path to the configuration file
     #[arg(short = 'c', long = "conf")]
     config: Option<PathBuf>,
@@ -27,14 +41,155 @@ struct Args {
     #[arg(short = 'v', long, action = clap::ArgAction::Count)]
     verbose: u8,
 
-    /// Silence all output
+    /// Omit the compiler version and the hashes of the sources from the description
+    #[arg(long)]
+    no_metadata: bool,
+
+    /// The language of descriptions, e.g. "fr" for `description@fr` annotations
+    #[arg(long)]
+    lang: Option<String>,
+
+    /// Only print errors
     #[arg(short = 'q', long, conflicts_with = "verbose")]
     quiet: bool,
+
+    /// Only read remote modules from the cache, without fetching them
+    #[arg(long)]
+    offline: bool,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Print the extended description of a diagnostic code
+    Explain {
+        /// The diagnostic code (e.g. E0001)
+        code: String,
+    },
+    /// Print the documentation of the standard library functions, or document the program
+    #[command(group = clap::ArgGroup::new("program").args(["api", "modules"]))]
+    Doc {
+        /// Render the API reference of the program instead, from its relations and schemas
+        #[arg(long)]
+        api: bool,
+        /// Render the documentation of the program modules as Markdown pages instead
+        #[arg(long)]
+        modules: bool,
+        /// Render the API reference as static HTML rather than Markdown
+        #[arg(long, requires = "api")]
+        html: bool,
+        /// The output file of the API reference, or else the standard output,
+        /// or the output directory of the module pages, by default "docs"
+        #[arg(requires = "program")]
+        out: Option<PathBuf>,
+    },
+    /// Export the schema components as a standalone JSON Schema bundle
+    Schemas {
+        /// The output file, or else the standard output
+        out: Option<PathBuf>,
+    },
+    /// Export the data models as Protocol Buffers messages (experimental)
+    Proto {
+        /// The output file, or else the standard output
+        out: Option<PathBuf>,
+    },
+    /// Compare two versions of a program, failing on changes that may break existing clients
+    Diff {
+        /// The main program of the old version
+        old: PathBuf,
+        /// The main program of the new version
+        new: PathBuf,
+    },
+    /// Check fixture programs against the diagnostics they expect
+    Test {
+        /// The fixture programs
+        #[arg(required = true)]
+        fixtures: Vec<PathBuf>,
+    },
+    /// Print a self-contained HTML playground, with the main program in the editor
+    Playground {
+        /// The directory of the WebAssembly package built by `wasm-pack build oal-wasm --target web`
+        #[arg(long, default_value = "oal-wasm/pkg")]
+        pkg: PathBuf,
+    },
+    /// Check the program for likely mistakes, e.g. duplicate operation ids or unused declarations
+    Lint,
+    /// Compile the program without writing the target
+    Check {
+        /// A previously generated description to verify against its checksum and the sources
+        #[arg(long)]
+        verify: Option<PathBuf>,
+    },
+    /// Print the paths or schemas of the program matching a query, e.g. "paths(/users/*).get"
+    Query {
+        /// The query
+        query: String,
+    },
+    /// Print the evaluated program in a readable text format, e.g. to debug evaluation
+    Print,
+    /// List the annotation keys used by the program modules, with their locations
+    Annotations,
+    /// Apply the machine-applicable fixes of diagnostics to the program modules
+    Fix,
+    /// Evaluate expressions interactively in the context of the program, e.g. "f {} | str"
+    Repl,
+    /// Serve compilation requests over HTTP
+    Serve {
+        /// The address to listen on
+        #[arg(long, default_value = "127.0.0.1")]
+        host: String,
+
+        /// The port to listen on
+        #[arg(long, default_value_t = 8080)]
+        port: u16,
+    },
+    /// Create a starter project
+    Init {
+        /// The project directory
+        #[arg(default_value = ".")]
+        dir: PathBuf,
+
+        /// Put the program into src/ and the generated description into spec/
+        #[arg(long)]
+        layout: bool,
+    },
 }
 
 #[derive(Deserialize, Default, Debug)]
 struct File {
-    api: Api,
+    api: Apis,
+    #[serde(default)]
+    settings: Settings,
+}
+
+/// Either a single `[api]` section or several `[[api]]` build targets.
+#[derive(Deserialize, Debug)]
+#[serde(untagged)]
+enum Apis {
+    One(Api),
+    Many(Vec<Api>),
+}
+
+impl Default for Apis {
+    fn default() -> Self {
+        Apis::One(Api::default())
+    }
+}
+
+impl Apis {
+    fn as_slice(&self) -> &[Api] {
+        match self {
+            Apis::One(api) => std::slice::from_ref(api),
+            Apis::Many(apis) => apis,
+        }
+    }
+
+    /// Returns the first build target, used by the commands working on a single program.
+    fn first(&self) -> Option<&Api> {
+        self.as_slice().first()
+    }
 }
 
 #[derive(Deserialize, Default, Debug)]
@@ -42,6 +197,241 @@ struct Api {
     main: Option<String>,
     target: Option<String>,
     base: Option<String>,
+    format: Option<Format>,
+}
+
+/// The format of a generated OpenAPI description.
+#[derive(Deserialize, ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    Yaml,
+    Json,
+}
+
+/// A build target, compiling a main program into a description.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Build {
+    pub main: Locator,
+    /// The output of the description, only required to write it.
+    pub target: Option<Locator>,
+    pub base: Option<Locator>,
+    pub format: Format,
+}
+
+/// The severity level of a lint.
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LintLevel {
+    Allow,
+    Warn,
+    Deny,
+}
+
+/// The compilation and generation settings.
+#[derive(Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct Settings {
+    /// The level of each lint by name.
+    pub lints: HashMap<String, LintLevel>,
+    /// The media type of contents without an explicit one.
+    pub media_type: Option<String>,
+    /// Whether incomplete type inference is an error.
+    pub strict: Option<bool>,
+    /// The maximum nesting depth of schemas before warning.
+    pub max_schema_depth: Option<usize>,
+    /// The maximum number of schema components before warning.
+    pub max_components: Option<usize>,
+    /// Whether to generate URI examples without an explicit one.
+    pub uri_examples: Option<bool>,
+    /// The maximum size of a module source in bytes.
+    pub max_input_size: Option<usize>,
+    /// The maximum number of modules of a program.
+    pub max_modules: Option<usize>,
+    /// Whether to embed a checksum of the generated description.
+    pub checksum: Option<bool>,
+    /// The version of the OpenAPI specification to target, either 3.0 or 3.1.
+    pub openapi_version: Option<String>,
+    /// Whether to share identical parameters as components.
+    pub parameter_components: Option<bool>,
+    /// Whether to share named examples used more than once as components.
+    pub example_components: Option<bool>,
+    /// Whether to synthesize examples for contents without any, from their schemas.
+    pub sample_examples: Option<bool>,
+    /// The seed of synthesized examples, for reproducible output.
+    pub example_seed: Option<u64>,
+    /// The template of references to schema components, e.g. "./schemas/{name}.yaml".
+    pub schema_ref: Option<String>,
+    /// Whether to keep the schema components in the description when referenced outside of it.
+    pub schema_components: Option<bool>,
+    /// The language of descriptions, e.g. "fr" for `description@fr` annotations.
+    pub language: Option<String>,
+    /// Whether to emit the descriptions in other languages as vendor extensions.
+    pub translations: Option<bool>,
+    /// Whether to emit unknown schema annotations as custom keywords, with OpenAPI 3.1 only.
+    pub custom_keywords: Option<bool>,
+    /// Whether to embed the compiler version and the hashes of the sources, by default true.
+    pub metadata: Option<bool>,
+    /// Whether the compiler service hardens compilation against untrusted sources.
+    pub safe_mode: Option<bool>,
+    /// The envelope transformation of success response schemas, either wrap or unwrap.
+    pub envelope: Option<String>,
+    /// Whether to group the tags of operations by the modules declaring them.
+    pub tag_groups: Option<bool>,
+    /// The case of path segments in default operation ids, one of lower, ascii-lower or preserve.
+    pub label_case: Option<String>,
+    /// The scheme of default operation ids, one of kebab, camel or snake.
+    pub operation_ids: Option<String>,
+    /// Whether default operation ids include the names of path variables, by default true.
+    pub operation_id_params: Option<bool>,
+    /// The aliases of module paths in imports, e.g. "shared" for "./lib".
+    pub paths: HashMap<String, String>,
+}
+
+impl Settings {
+    /// Merges with other settings of lower precedence.
+    pub fn merge(mut self, other: Settings) -> Self {
+        for (name, level) in other.lints {
+            self.lints.entry(name).or_insert(level);
+        }
+        for (alias, path) in other.paths {
+            self.paths.entry(alias).or_insert(path);
+        }
+        self.media_type = self.media_type.or(other.media_type);
+        self.strict = self.strict.or(other.strict);
+        self.max_schema_depth = self.max_schema_depth.or(other.max_schema_depth);
+        self.max_components = self.max_components.or(other.max_components);
+        self.uri_examples = self.uri_examples.or(other.uri_examples);
+        self.max_input_size = self.max_input_size.or(other.max_input_size);
+        self.max_modules = self.max_modules.or(other.max_modules);
+        self.checksum = self.checksum.or(other.checksum);
+        self.openapi_version = self.openapi_version.or(other.openapi_version);
+        self.parameter_components = self.parameter_components.or(other.parameter_components);
+        self.example_components = self.example_components.or(other.example_components);
+        self.sample_examples = self.sample_examples.or(other.sample_examples);
+        self.example_seed = self.example_seed.or(other.example_seed);
+        self.schema_ref = self.schema_ref.or(other.schema_ref);
+        self.schema_components = self.schema_components.or(other.schema_components);
+        self.language = self.language.or(other.language);
+        self.translations = self.translations.or(other.translations);
+        self.custom_keywords = self.custom_keywords.or(other.custom_keywords);
+        self.metadata = self.metadata.or(other.metadata);
+        self.safe_mode = self.safe_mode.or(other.safe_mode);
+        self.envelope = self.envelope.or(other.envelope);
+        self.tag_groups = self.tag_groups.or(other.tag_groups);
+        self.label_case = self.label_case.or(other.label_case);
+        self.operation_ids = self.operation_ids.or(other.operation_ids);
+        self.operation_id_params = self.operation_id_params.or(other.operation_id_params);
+        self
+    }
+
+    /// Returns the version of the OpenAPI specification to target, by default 3.0.
+    pub fn openapi_version(&self) -> anyhow::Result<OpenApiVersion> {
+        match self.openapi_version {
+            Some(ref v) => v.parse().map_err(|err: String| anyhow!(err)),
+            None => Ok(OpenApiVersion::default()),
+        }
+    }
+
+    /// Returns the case of path segments in default operation ids, by default lowercase.
+    pub fn label_case(&self) -> anyhow::Result<LabelCase> {
+        match self.label_case {
+            Some(ref c) => c.parse().map_err(|err: String| anyhow!(err)),
+            None => Ok(LabelCase::default()),
+        }
+    }
+
+    /// Returns the scheme of default operation ids, by default kebab case.
+    pub fn id_scheme(&self) -> anyhow::Result<IdScheme> {
+        match self.operation_ids {
+            Some(ref s) => s.parse().map_err(|err: String| anyhow!(err)),
+            None => Ok(IdScheme::default()),
+        }
+    }
+
+    /// Returns the envelope transformation of success response schemas, if any.
+    pub fn envelope(&self) -> anyhow::Result<Option<Envelope>> {
+        self.envelope
+            .as_deref()
+            .map(|e| e.parse().map_err(|err: String| anyhow!(err)))
+            .transpose()
+    }
+
+    /// Returns the level of the lint controlling the given warning, by default a warning.
+    pub fn lint_level(&self, err: &Error) -> LintLevel {
+        err.kind
+            .lint()
+            .and_then(|name| self.lints.get(name).copied())
+            .unwrap_or(LintLevel::Warn)
+    }
+
+    /// Returns the resolver of imports through the aliases of module paths.
+    pub fn resolver(&self) -> oal_compiler::errors::Result<Resolver> {
+        Resolver::new(&self.paths)
+    }
+
+    /// Returns the limits on the shape of schemas.
+    pub fn limits(&self) -> Limits {
+        Limits {
+            max_depth: self.max_schema_depth,
+            max_components: self.max_components,
+        }
+    }
+
+    /// Applies the generation settings to a description builder.
+    pub fn configure(&self, builder: Builder) -> anyhow::Result<Builder> {
+        let mut builder = builder
+            .with_version(self.openapi_version()?)
+            .with_label_case(self.label_case()?)
+            .with_id_scheme(self.id_scheme()?);
+        if let Some(ref media_type) = self.media_type {
+            builder = builder.with_media_type(media_type.clone());
+        }
+        if let Some(enabled) = self.uri_examples {
+            builder = builder.with_uri_examples(enabled);
+        }
+        if let Some(enabled) = self.parameter_components {
+            builder = builder.with_parameter_components(enabled);
+        }
+        if let Some(enabled) = self.example_components {
+            builder = builder.with_example_components(enabled);
+        }
+        if let Some(enabled) = self.sample_examples {
+            builder = builder.with_sample_examples(enabled);
+        }
+        if let Some(seed) = self.example_seed {
+            builder = builder.with_example_seed(seed);
+        }
+        if let Some(ref template) = self.schema_ref {
+            builder = builder.with_schema_ref(template.clone());
+        }
+        if let Some(enabled) = self.schema_components {
+            builder = builder.with_schema_components(enabled);
+        }
+        if let Some(ref lang) = self.language {
+            builder = builder.with_language(lang.clone());
+        }
+        if let Some(enabled) = self.translations {
+            builder = builder.with_translation_extensions(enabled);
+        }
+        if let Some(enabled) = self.custom_keywords {
+            builder = builder.with_custom_keywords(enabled);
+        }
+        if let Some(enabled) = self.tag_groups {
+            builder = builder.with_tag_groups(enabled);
+        }
+        if let Some(enabled) = self.operation_id_params {
+            builder = builder.with_id_params(enabled);
+        }
+        Ok(builder)
+    }
+
+    /// Returns the limits on loading modules, falling back to the given defaults.
+    pub fn load_limits(&self, defaults: LoadLimits) -> LoadLimits {
+        LoadLimits {
+            max_input_size: self.max_input_size.or(defaults.max_input_size),
+            max_modules: self.max_modules.or(defaults.max_modules),
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -49,6 +439,8 @@ pub struct Config {
     args: Args,
     file: File,
     root: Locator,
+    /// The settings provided by the client (e.g. the editor), if any.
+    client: Settings,
 }
 
 fn path_locator(p: &Path) -> anyhow::Result<Locator> {
@@ -59,8 +451,19 @@ fn path_locator(p: &Path) -> anyhow::Result<Locator> {
 
 impl Config {
     pub fn new(cfg: Option<&Path>) -> anyhow::Result<Self> {
-        let args: Args = Args::parse();
+        Self::with_args(Args::parse(), cfg)
+    }
+
+    /// Creates a configuration from the given command line arguments, the first being the program name.
+    pub fn parse_from<I, T>(args: I, cfg: Option<&Path>) -> anyhow::Result<Self>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<std::ffi::OsString> + Clone,
+    {
+        Self::with_args(Args::try_parse_from(args)?, cfg)
+    }
 
+    fn with_args(args: Args, cfg: Option<&Path>) -> anyhow::Result<Self> {
         let config = cfg.or(args.config.as_deref());
 
         let (root, file) = if let Some(path) = config {
@@ -76,30 +479,323 @@ impl Config {
             (root, file)
         };
 
-        Ok(Config { args, file, root })
+        Ok(Config {
+            args,
+            file,
+            root,
+            client: Settings::default(),
+        })
+    }
+
+    /// Returns a short form of the locator for display, relative to the configuration root if possible.
+    pub fn display(&self, loc: &Locator) -> String {
+        match loc.relative_to(&self.root) {
+            Some(rel) if !rel.starts_with("../") => rel,
+            _ => loc.to_string(),
+        }
     }
 
     pub fn main(&self) -> anyhow::Result<Locator> {
-        match self.args.main.as_ref().or(self.file.api.main.as_ref()) {
+        self.api_main(self.file.api.first())
+    }
+
+    pub fn target(&self) -> anyhow::Result<Locator> {
+        self.api_target(self.file.api.first())?
+            .ok_or_else(|| anyhow::Error::msg("target not specified"))
+    }
+
+    pub fn base(&self) -> anyhow::Result<Option<Locator>> {
+        self.api_base(self.file.api.first())
+    }
+
+    /// Returns the format of the target, falling back to its extension and then to YAML.
+    pub fn format(&self, target: &Locator) -> Format {
+        self.api_format(self.file.api.first(), Some(target))
+    }
+
+    /// Returns the build targets, either the one given on the command line
+    /// or all the ones declared in the configuration file.
+    pub fn builds(&self) -> anyhow::Result<Vec<Build>> {
+        let apis = self.file.api.as_slice();
+        if self.args.main.is_some() || self.args.target.is_some() || apis.is_empty() {
+            Ok(vec![self.build(apis.first())?])
+        } else {
+            apis.iter().map(|api| self.build(Some(api))).collect()
+        }
+    }
+
+    fn build(&self, api: Option<&Api>) -> anyhow::Result<Build> {
+        let target = self.api_target(api)?;
+        Ok(Build {
+            main: self.api_main(api)?,
+            format: self.api_format(api, target.as_ref()),
+            base: self.api_base(api)?,
+            target,
+        })
+    }
+
+    fn api_main(&self, api: Option<&Api>) -> anyhow::Result<Locator> {
+        match self
+            .args
+            .main
+            .as_ref()
+            .or(api.and_then(|a| a.main.as_ref()))
+        {
             Some(p) => Ok(self.root.join(p)?),
             None => Err(anyhow::Error::msg("main module not specified")),
         }
     }
 
-    pub fn target(&self) -> anyhow::Result<Locator> {
-        match self.args.target.as_ref().or(self.file.api.target.as_ref()) {
-            Some(p) => Ok(self.root.join(p)?),
-            None => Err(anyhow::Error::msg("target not specified")),
+    fn api_target(&self, api: Option<&Api>) -> anyhow::Result<Option<Locator>> {
+        match self
+            .args
+            .target
+            .as_ref()
+            .or(api.and_then(|a| a.target.as_ref()))
+        {
+            Some(p) => Ok(Some(self.root.join(p)?)),
+            None => Ok(None),
         }
     }
 
-    pub fn base(&self) -> anyhow::Result<Option<Locator>> {
-        match self.args.base.as_ref().or(self.file.api.base.as_ref()) {
+    fn api_base(&self, api: Option<&Api>) -> anyhow::Result<Option<Locator>> {
+        match self
+            .args
+            .base
+            .as_ref()
+            .or(api.and_then(|a| a.base.as_ref()))
+        {
             Some(p) => Ok(Some(self.root.join(p)?)),
             None => Ok(None),
         }
     }
 
+    fn api_format(&self, api: Option<&Api>, target: Option<&Locator>) -> Format {
+        self.args
+            .format
+            .or(api.and_then(|a| a.format))
+            .unwrap_or_else(|| {
+                if target.is_some_and(|t| t.url().path().ends_with(".json")) {
+                    Format::Json
+                } else {
+                    Format::Yaml
+                }
+            })
+    }
+
+    /// Sets the settings provided by the client, of lower precedence than the configuration file.
+    pub fn with_client_settings(mut self, settings: Settings) -> Self {
+        self.client = settings;
+        self
+    }
+
+    /// Replaces the settings provided by the client.
+    pub fn set_client_settings(&mut self, settings: Settings) {
+        self.client = settings;
+    }
+
+    /// Returns the effective settings.
+    pub fn settings(&self) -> Settings {
+        let mut settings = self.file.settings.clone().merge(self.client.clone());
+        if self.args.no_metadata {
+            settings.metadata = Some(false);
+        }
+        if self.args.lang.is_some() {
+            settings.language.clone_from(&self.args.lang);
+        }
+        // Aliases are relative to the configuration file.
+        for path in settings.paths.values_mut() {
+            if let Ok(loc) = self.root.join(path) {
+                *path = loc.to_string();
+            }
+        }
+        settings
+    }
+
+    /// Returns the diagnostic code to explain, if requested.
+    pub fn explain(&self) -> Option<&str> {
+        match self.args.command {
+            Some(Command::Explain { ref code }) => Some(code),
+            _ => None,
+        }
+    }
+
+    /// Returns whether the documentation of the standard library is requested.
+    pub fn doc(&self) -> bool {
+        matches!(
+            self.args.command,
+            Some(Command::Doc {
+                api: false,
+                modules: false,
+                ..
+            })
+        )
+    }
+
+    /// Returns the format and the output file of the API reference, if requested.
+    ///
+    /// The reference is in HTML if requested or if the output file has an HTML extension.
+    pub fn api_reference(&self) -> Option<(reference::Format, Option<&Path>)> {
+        match self.args.command {
+            Some(Command::Doc {
+                api: true,
+                html,
+                ref out,
+                ..
+            }) => {
+                let is_html = out
+                    .as_ref()
+                    .and_then(|p| p.extension())
+                    .is_some_and(|e| e == "html" || e == "htm");
+                let format = if html || is_html {
+                    reference::Format::Html
+                } else {
+                    reference::Format::Markdown
+                };
+                Some((format, out.as_deref()))
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the output directory of the module documentation, if requested.
+    pub fn docs(&self) -> Option<&Path> {
+        match self.args.command {
+            Some(Command::Doc {
+                modules: true,
+                ref out,
+                ..
+            }) => Some(out.as_deref().unwrap_or(Path::new("docs"))),
+            _ => None,
+        }
+    }
+
+    /// Returns whether the JSON Schema bundle is requested, along with its output file if any.
+    pub fn schemas(&self) -> Option<Option<&Path>> {
+        match self.args.command {
+            Some(Command::Schemas { ref out }) => Some(out.as_deref()),
+            _ => None,
+        }
+    }
+
+    /// Returns whether the Protocol Buffers export is requested, along with its output file if any.
+    pub fn proto(&self) -> Option<Option<&Path>> {
+        match self.args.command {
+            Some(Command::Proto { ref out }) => Some(out.as_deref()),
+            _ => None,
+        }
+    }
+
+    /// Returns the base of relative paths, i.e. the configuration file or the current directory.
+    pub fn root(&self) -> &Locator {
+        &self.root
+    }
+
+    /// Returns the file system of the modules, with the remote modules pinned by the lockfile
+    /// next to the configuration file.
+    pub fn remote(&self) -> anyhow::Result<RemoteFileSystem> {
+        let dir = self
+            .root
+            .join(".")?
+            .url()
+            .to_file_path()
+            .map_err(|_| anyhow!("invalid configuration directory"))?;
+        Ok(RemoteFileSystem::new(&dir).with_offline(self.args.offline))
+    }
+
+    /// Returns the locators of the fixtures to check, if requested.
+    pub fn fixtures(&self) -> anyhow::Result<Option<Vec<Locator>>> {
+        match self.args.command {
+            Some(Command::Test { ref fixtures }) => {
+                let locs = fixtures.iter().map(|p| path_locator(p));
+                locs.collect::<anyhow::Result<_>>().map(Some)
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Returns the main programs of the old and new versions to compare, if requested.
+    pub fn diff(&self) -> anyhow::Result<Option<(Locator, Locator)>> {
+        match self.args.command {
+            Some(Command::Diff { ref old, ref new }) => {
+                Ok(Some((path_locator(old)?, path_locator(new)?)))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Returns the directory of the WebAssembly package for the playground, if requested.
+    pub fn playground(&self) -> Option<&Path> {
+        match self.args.command {
+            Some(Command::Playground { ref pkg }) => Some(pkg),
+            _ => None,
+        }
+    }
+
+    /// Returns whether to only check the program, without writing the target.
+    pub fn check(&self) -> bool {
+        matches!(self.args.command, Some(Command::Check { .. }))
+    }
+
+    /// Returns the locator of the description to verify, if requested.
+    pub fn verify(&self) -> anyhow::Result<Option<Locator>> {
+        match self.args.command {
+            Some(Command::Check {
+                verify: Some(ref path),
+            }) => path_locator(path).map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    /// Returns the query to run over the program, if requested.
+    pub fn query(&self) -> Option<&str> {
+        match self.args.command {
+            Some(Command::Query { ref query }) => Some(query),
+            _ => None,
+        }
+    }
+
+    /// Returns whether linting the program is requested.
+    pub fn lint(&self) -> bool {
+        matches!(self.args.command, Some(Command::Lint))
+    }
+
+    /// Returns whether printing the evaluated program is requested.
+    pub fn print(&self) -> bool {
+        matches!(self.args.command, Some(Command::Print))
+    }
+
+    /// Returns whether the inventory of annotation keys is requested.
+    pub fn annotations(&self) -> bool {
+        matches!(self.args.command, Some(Command::Annotations))
+    }
+
+    /// Returns whether the fixes of diagnostics are to be applied.
+    pub fn fix(&self) -> bool {
+        matches!(self.args.command, Some(Command::Fix))
+    }
+
+    /// Returns whether an interactive session is requested.
+    pub fn repl(&self) -> bool {
+        matches!(self.args.command, Some(Command::Repl))
+    }
+
+    /// Returns the address to serve compilation requests on, if requested.
+    pub fn serve(&self) -> Option<(&str, u16)> {
+        match self.args.command {
+            Some(Command::Serve { ref host, port }) => Some((host, port)),
+            _ => None,
+        }
+    }
+
+    /// Returns the project directory to initialize and whether to use the layout, if requested.
+    pub fn init(&self) -> Option<(&Path, bool)> {
+        match self.args.command {
+            Some(Command::Init { ref dir, layout }) => Some((dir, layout)),
+            _ => None,
+        }
+    }
+
     pub fn is_quiet(&self) -> bool {
         self.args.quiet
     }
@@ -0,0 +1,193 @@
+//! Documentation pages of the modules of a program, in Markdown.
+//!
+//! Each module gets a page with its imports, linking to the pages of the imported modules,
+//! and its declarations along with their annotations and source.
+//! An index page links to every module, starting with the main one.
+
+use crate::FileSystem;
+use oal_compiler::annotation::compose_annotations;
+use oal_compiler::module::ModuleSet;
+use oal_compiler::tree::Tree;
+use oal_model::grammar::AbstractSyntaxNode;
+use oal_model::locator::Locator;
+use oal_syntax::parser as syn;
+use serde_yaml::Value;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+
+/// The path of the index page.
+pub const INDEX: &str = "index.md";
+
+/// A documentation page.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Page {
+    /// The path of the page, relative to the documentation root.
+    pub path: String,
+    pub text: String,
+}
+
+/// The paths of the pages by module.
+type Paths = HashMap<Locator, String>;
+
+/// Returns the preferred path of the page of a module, relative to the documentation root.
+fn page_path(loc: &Locator, root: &Locator) -> String {
+    let rel = match loc.relative_to(root) {
+        Some(rel) if !rel.starts_with("../") => rel,
+        // Modules outside of the root are documented at the top level.
+        _ => loc
+            .url()
+            .path_segments()
+            .and_then(|mut s| s.next_back())
+            .unwrap_or("module")
+            .to_owned(),
+    };
+    match rel.strip_suffix(".oal") {
+        Some(stem) => format!("{stem}.md"),
+        None => format!("{rel}.md"),
+    }
+}
+
+/// Assigns a distinct page path to each module, in order,
+/// numbering the pages of modules that would otherwise share a path, e.g. `common-2.md`.
+fn page_paths<'a>(locs: impl Iterator<Item = &'a Locator>, root: &Locator) -> Paths {
+    // A module named like the index page must not replace it.
+    let mut used = HashSet::from([INDEX.to_owned()]);
+    let mut paths = Paths::new();
+    for loc in locs {
+        let preferred = page_path(loc, root);
+        let stem = preferred.trim_end_matches(".md");
+        let mut path = preferred.clone();
+        let mut n = 1;
+        while !used.insert(path.clone()) {
+            n += 1;
+            path = format!("{stem}-{n}.md");
+        }
+        paths.insert(loc.clone(), path);
+    }
+    paths
+}
+
+/// Returns the relative link from a page to another.
+fn link(from: &str, to: &str, root: &Locator) -> String {
+    let base = root.join(from).expect("page path should be valid");
+    let target = root.join(to).expect("page path should be valid");
+    target.relative_to(&base).unwrap_or_else(|| to.to_owned())
+}
+
+/// Formats an annotation value on a single line.
+fn inline_value(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        v => serde_json::to_string(v).unwrap_or_default(),
+    }
+}
+
+/// Renders the page of a module.
+fn module_page(tree: &Tree, text: &str, mods: &ModuleSet, paths: &Paths, root: &Locator) -> Page {
+    let loc = tree.locator();
+    let path = paths[loc].clone();
+    let prog = syn::Program::cast(tree.root()).expect("expected a program");
+    let mut out = String::new();
+
+    let name = loc.relative_to(root).unwrap_or_else(|| loc.to_string());
+    writeln!(out, "# {name}").unwrap();
+
+    let imports = prog.imports().collect::<Vec<_>>();
+    if !imports.is_empty() {
+        writeln!(out, "\n## Imports\n").unwrap();
+    }
+    for import in imports {
+        let module = import.module();
+        let target = mods
+            .resolve_import(loc, module)
+            .ok()
+            .and_then(|l| paths.get(&l));
+        match target {
+            Some(target) => {
+                let href = link(&path, target, root);
+                write!(out, "- [{module}]({href})").unwrap();
+            }
+            None => write!(out, "- `{module}`").unwrap(),
+        }
+        match import.qualifier() {
+            Some(q) => writeln!(out, " as `{q}`").unwrap(),
+            None => writeln!(out).unwrap(),
+        }
+    }
+
+    let decls = prog.declarations().collect::<Vec<_>>();
+    if !decls.is_empty() {
+        writeln!(out, "\n## Declarations").unwrap();
+    }
+    for decl in decls {
+        writeln!(out, "\n### `{}`\n", decl.ident()).unwrap();
+        let mut ann = compose_annotations(decl.annotations()).unwrap_or_default();
+        for key in ["title", "description"] {
+            if let Some(s) = ann.get_string(key) {
+                writeln!(out, "{s}\n").unwrap();
+            }
+            ann.props.remove(key);
+        }
+        for (key, value) in ann.props.iter() {
+            writeln!(out, "- `{}`: {}", inline_value(key), inline_value(value)).unwrap();
+        }
+        if !ann.props.is_empty() {
+            writeln!(out).unwrap();
+        }
+        let source = decl
+            .node()
+            .span()
+            .and_then(|s| text.get(s.range()))
+            .unwrap_or_default();
+        // Annotation lines are rendered above, only the declaration itself remains.
+        let source = source
+            .lines()
+            .skip_while(|l| l.trim_start().starts_with('#'))
+            .collect::<Vec<_>>()
+            .join("\n");
+        writeln!(out, "```\n{};\n```", source.trim_end_matches(';')).unwrap();
+    }
+
+    Page { path, text: out }
+}
+
+/// Renders the documentation pages of the modules of a program, with paths relative to the given root.
+///
+/// Modules of the standard library are not documented.
+pub fn render<F: FileSystem + ?Sized>(
+    fs: &F,
+    mods: &ModuleSet,
+    root: &Locator,
+) -> anyhow::Result<Vec<Page>> {
+    let mut trees = mods
+        .modules()
+        .filter(|t| oal_compiler::library::source(t.locator()).is_none())
+        .collect::<Vec<_>>();
+    // The main module comes first, the others in the order of their paths.
+    trees.sort_by_key(|t| {
+        (
+            t.locator() != mods.base(),
+            page_path(t.locator(), root),
+            t.locator().clone(),
+        )
+    });
+    let paths = page_paths(trees.iter().map(|t| t.locator()), root);
+
+    let mut index = String::from("# Modules\n\n");
+    let mut pages = Vec::new();
+    for tree in trees {
+        let text = fs.read_file(tree.locator())?;
+        let page = module_page(tree, &text, mods, &paths, root);
+        let name = page.path.trim_end_matches(".md");
+        writeln!(index, "- [{name}]({})", link(INDEX, &page.path, root)).unwrap();
+        pages.push(page);
+    }
+    pages.insert(
+        0,
+        Page {
+            path: INDEX.to_owned(),
+            text: index,
+        },
+    );
+    Ok(pages)
+}
@@ -0,0 +1,130 @@
+//! Applies the machine-applicable fixes of diagnostics to the source files of a program,
+//! e.g. inserting a missing semicolon, adding the missing `!` to a discriminator property
+//! or qualifying a variable declared by a qualified import.
+//!
+//! Fixes are applied one at a time, reloading the program after each, until none is left.
+//! Diagnostics offering alternative fixes are left to the user.
+
+use crate::fixture::Position;
+use crate::resolver::Resolver;
+use crate::FileSystem;
+use anyhow::anyhow;
+use oal_compiler::fix::Fix;
+use oal_compiler::module::{Loader, ModuleSet};
+use oal_compiler::tree::Tree;
+use oal_model::locator::Locator;
+
+/// The maximum number of fixes applied, in case fixes keep producing new diagnostics.
+const MAX_FIXES: usize = 64;
+
+/// A fix applied to a module.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Applied {
+    pub title: String,
+    pub loc: Locator,
+    /// The position of the first edit, before applying the fix.
+    pub position: Position,
+}
+
+/// A module loader keeping the fix of the first failure, if any.
+struct FixLoader<'a, F: FileSystem> {
+    fs: &'a F,
+    resolver: &'a Resolver,
+    fix: Option<Fix>,
+}
+
+impl<F: FileSystem> Loader<anyhow::Error> for FixLoader<'_, F> {
+    fn is_valid(&mut self, loc: &Locator) -> bool {
+        self.fs.is_valid(loc)
+    }
+
+    fn load(&mut self, loc: &Locator) -> anyhow::Result<String> {
+        Ok(self.fs.read_file(loc)?)
+    }
+
+    fn parse(&mut self, loc: Locator, input: String) -> anyhow::Result<Tree> {
+        let (tree, errs) = oal_syntax::parse(loc.clone(), &input);
+        if let Some(err) = errs.first() {
+            self.fix = oal_compiler::fix::syntax_fix(&loc, &input, err);
+            Err(anyhow!("parsing failed"))
+        } else {
+            tree.ok_or_else(|| anyhow!("parsing failed"))
+        }
+    }
+
+    fn compile(&mut self, mods: &ModuleSet, loc: &Locator) -> anyhow::Result<()> {
+        if let Err(err) = oal_compiler::compile::compile(mods, loc) {
+            if let [fix] = err.fixes() {
+                self.fix = Some(fix.clone());
+            }
+            return Err(err.into());
+        }
+        Ok(())
+    }
+
+    fn resolve(&mut self, loc: &Locator, import: &str) -> oal_compiler::errors::Result<Locator> {
+        self.resolver.resolve(loc, import)
+    }
+
+    fn canonicalize(&mut self, loc: &Locator) -> Locator {
+        self.fs.canonicalize(loc).unwrap_or_else(|_| loc.clone())
+    }
+}
+
+/// Applies the edits of a fix to the files of a file system.
+fn apply_fix<F: FileSystem>(fs: &F, fix: &Fix) -> anyhow::Result<Applied> {
+    let first = fix.edits.first().ok_or_else(|| anyhow!("empty fix"))?;
+    let mut locs = Vec::new();
+    for edit in fix.edits.iter() {
+        if !locs.contains(&edit.span.locator()) {
+            locs.push(edit.span.locator());
+        }
+    }
+    let mut position = None;
+    for loc in locs {
+        if oal_compiler::library::source(loc).is_some() {
+            return Err(anyhow!("cannot fix the standard library module {loc}"));
+        }
+        let text = fs.read_file(loc)?;
+        if loc == first.span.locator() {
+            position = Some(Position::from_index(&text, first.span.start()));
+        }
+        let edits = fix
+            .edits
+            .iter()
+            .filter(|e| e.span.locator() == loc)
+            .collect::<Vec<_>>();
+        let (text, _) = oal_compiler::fix::apply(&text, &edits);
+        fs.write_file(loc, text)?;
+    }
+    Ok(Applied {
+        title: fix.title.clone(),
+        loc: first.span.locator().clone(),
+        position: position.expect("first edit should be applied"),
+    })
+}
+
+/// Applies the fixes of the diagnostics of a program, returning the fixes in the order applied.
+///
+/// Imports are resolved through the aliases of module paths of the given resolver.
+pub fn apply<F: FileSystem>(
+    fs: &F,
+    main: &Locator,
+    resolver: &Resolver,
+) -> anyhow::Result<Vec<Applied>> {
+    let mut applied = Vec::new();
+    while applied.len() < MAX_FIXES {
+        let loader = &mut FixLoader {
+            fs,
+            resolver,
+            fix: None,
+        };
+        // The remaining diagnostics are reported by the caller.
+        let _ = oal_compiler::module::load(loader, main);
+        let Some(fix) = loader.fix.take() else {
+            break;
+        };
+        applied.push(apply_fix(fs, &fix)?);
+    }
+    Ok(applied)
+}
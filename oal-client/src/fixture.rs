@@ -0,0 +1,256 @@
+//! Negative tests of programs declaring their expected diagnostics.
+//!
+//! A fixture is a program with directives in `//@` comments, e.g.:
+//! ```text
+//! //@ expect-error E0006 at 3:5
+//! //@ expect-warning E0015
+//! ```
+//! Positions are 1-based line and column numbers in the fixture file.
+//! Every expectation must match a distinct diagnostic and every diagnostic must be expected.
+
+use crate::config::{LintLevel, Settings};
+use crate::{DiagnosticLoader, FileSystem};
+use anyhow::anyhow;
+use oal_compiler::errors::Error;
+use oal_compiler::module::LoadLimits;
+use oal_model::locator::Locator;
+use oal_model::span::Span;
+use std::fmt::{Display, Formatter};
+
+/// The prefix of fixture directives, distinct from that of ordinary comments.
+const DIRECTIVE: &str = "//@";
+
+/// The severity of a diagnostic.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+/// A 1-based line and column position.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Position {
+    /// Returns the position of the given UTF-8 index in the text.
+    pub(crate) fn from_index(text: &str, index: usize) -> Self {
+        let before = &text[..index.min(text.len())];
+        let line = before.matches('\n').count() + 1;
+        let column = before.rsplit('\n').next().unwrap_or("").chars().count() + 1;
+        Position { line, column }
+    }
+}
+
+impl Display for Position {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+/// A diagnostic declared as expected by a fixture directive.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Expectation {
+    pub severity: Severity,
+    pub code: String,
+    pub position: Option<Position>,
+    /// The line of the directive in the fixture.
+    pub line: usize,
+}
+
+impl Display for Expectation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.severity, self.code)?;
+        if let Some(pos) = self.position {
+            write!(f, " at {pos}")?;
+        }
+        write!(f, " (declared on line {})", self.line)
+    }
+}
+
+/// A diagnostic reported while compiling a fixture.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub code: String,
+    /// The position in the fixture file, if reported there.
+    pub position: Option<Position>,
+    pub message: String,
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.severity, self.code)?;
+        if let Some(pos) = self.position {
+            write!(f, " at {pos}")?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+impl Expectation {
+    fn matches(&self, diag: &Diagnostic) -> bool {
+        self.severity == diag.severity
+            && self.code == diag.code
+            && self.position.is_none_or(|p| diag.position == Some(p))
+    }
+}
+
+fn parse_position(s: &str) -> Option<Position> {
+    let (line, column) = s.split_once(':')?;
+    let line = line.parse().ok().filter(|n| *n > 0)?;
+    let column = column.parse().ok().filter(|n| *n > 0)?;
+    Some(Position { line, column })
+}
+
+/// Parses the expectations declared by the directives of a fixture.
+pub fn expectations(source: &str) -> anyhow::Result<Vec<Expectation>> {
+    let mut expects = Vec::new();
+    for (n, text) in source.lines().enumerate() {
+        let line = n + 1;
+        let Some(directive) = text.trim_start().strip_prefix(DIRECTIVE) else {
+            continue;
+        };
+        let words = directive.split_whitespace().collect::<Vec<_>>();
+        let severity = match words.first() {
+            Some(&"expect-error") => Severity::Error,
+            Some(&"expect-warning") => Severity::Warning,
+            _ => return Err(anyhow!("unknown directive on line {line}")),
+        };
+        let code = match words.get(1) {
+            Some(code) if oal_compiler::errors::explain(code).is_some() => code.to_uppercase(),
+            _ => return Err(anyhow!("expected a diagnostic code on line {line}")),
+        };
+        let position = match words[2..] {
+            [] => None,
+            ["at", pos] => match parse_position(pos) {
+                Some(p) => Some(p),
+                None => return Err(anyhow!("invalid position on line {line}")),
+            },
+            _ => return Err(anyhow!("unexpected trailing input on line {line}")),
+        };
+        expects.push(Expectation {
+            severity,
+            code,
+            position,
+            line,
+        });
+    }
+    Ok(expects)
+}
+
+/// The outcome of checking a fixture against its expectations.
+#[derive(Debug, Default)]
+pub struct Outcome {
+    /// The expectations without a matching diagnostic.
+    pub missing: Vec<Expectation>,
+    /// The diagnostics without a matching expectation.
+    pub unexpected: Vec<Diagnostic>,
+}
+
+impl Outcome {
+    pub fn is_ok(&self) -> bool {
+        self.missing.is_empty() && self.unexpected.is_empty()
+    }
+}
+
+/// Collects the diagnostics of a fixture, stopping at the first error.
+struct Collector<'a> {
+    settings: &'a Settings,
+    main: Locator,
+    text: String,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Collector<'_> {
+    fn position(&self, span: Option<&Span>) -> Option<Position> {
+        span.filter(|s| *s.locator() == self.main)
+            .map(|s| Position::from_index(&self.text, s.start()))
+    }
+
+    fn push(&mut self, severity: Severity, err: &Error) {
+        let diag = Diagnostic {
+            severity,
+            code: err.code().to_owned(),
+            position: self.position(err.span()),
+            message: err.to_string(),
+        };
+        self.diagnostics.push(diag);
+    }
+
+    fn warn(&mut self, warnings: Vec<Error>) {
+        for err in warnings {
+            match self.settings.lint_level(&err) {
+                LintLevel::Allow => {}
+                LintLevel::Warn => self.push(Severity::Warning, &err),
+                LintLevel::Deny => self.push(Severity::Error, &err),
+            }
+        }
+    }
+}
+
+/// Compiles the fixture at the given location and returns its diagnostics.
+pub fn diagnostics<F: FileSystem>(
+    fs: &F,
+    main: &Locator,
+    settings: &Settings,
+) -> anyhow::Result<Vec<Diagnostic>> {
+    let mut collector = Collector {
+        settings,
+        main: main.clone(),
+        text: fs.read_file(main)?,
+        diagnostics: Vec::new(),
+    };
+    let mut loader = DiagnosticLoader::new(fs, settings.strict.unwrap_or(false))
+        .with_resolver(settings.resolver()?);
+    let limits = settings.load_limits(LoadLimits::default());
+    let result = oal_compiler::module::load_with_limits(&mut loader, main, &limits);
+    collector.warn(loader.take_warnings());
+    match result {
+        Err(err) => collector.push(Severity::Error, &err),
+        Ok(mods) => match oal_compiler::eval::eval_with_warnings(&mods) {
+            Err(err) => collector.push(Severity::Error, &err),
+            Ok((spec, warnings)) => {
+                collector.warn(warnings);
+                let limits = settings.limits();
+                let warnings = oal_compiler::limits::check_limits(&mods, &spec, &limits);
+                collector.warn(warnings);
+            }
+        },
+    }
+    Ok(collector.diagnostics)
+}
+
+/// Checks the fixture at the given location against the diagnostics it expects.
+pub fn check<F: FileSystem>(
+    fs: &F,
+    main: &Locator,
+    settings: &Settings,
+) -> anyhow::Result<Outcome> {
+    let expects = expectations(&fs.read_file(main)?)?;
+    let mut unexpected = diagnostics(fs, main, settings)?;
+    let mut missing = Vec::new();
+    for expect in expects {
+        match unexpected.iter().position(|d| expect.matches(d)) {
+            Some(i) => {
+                unexpected.remove(i);
+            }
+            None => missing.push(expect),
+        }
+    }
+    Ok(Outcome {
+        missing,
+        unexpected,
+    })
+}
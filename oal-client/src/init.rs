@@ -0,0 +1,57 @@
+use anyhow::anyhow;
+use std::path::{Path, PathBuf};
+
+/// The starter configuration file, with the main program and target placeholders.
+const CONFIG_TEMPLATE: &str = r#"[api]
+main = "{main}"
+target = "{target}"
+"#;
+
+/// The starter main program.
+pub(crate) const MAIN_TEMPLATE: &str = r#"// A record of the resource representation.
+# description: "a greeting"
+let greeting = {
+  'message str `example: "Hello, world!"`
+};
+
+// A resource with a single operation, returning a greeting.
+res /hello on
+  # summary: "say hello"
+  get -> <status=200, greeting>;
+"#;
+
+/// Creates a starter project in the given directory, returning the paths of the created files.
+///
+/// With the layout option, the program goes into a `src` directory
+/// and the generated OpenAPI description into a `spec` directory.
+/// Existing files are never overwritten.
+pub fn scaffold(dir: &Path, layout: bool) -> anyhow::Result<Vec<PathBuf>> {
+    let (main, target) = if layout {
+        ("src/main.oal", "spec/openapi.yaml")
+    } else {
+        ("main.oal", "openapi.yaml")
+    };
+    let config = CONFIG_TEMPLATE
+        .replace("{main}", main)
+        .replace("{target}", target);
+    let files = [
+        (dir.join("oal.toml"), config.as_str()),
+        (dir.join(main), MAIN_TEMPLATE),
+    ];
+
+    if let Some((path, _)) = files.iter().find(|(p, _)| p.exists()) {
+        return Err(anyhow!("file already exists: {}", path.display()));
+    }
+
+    for (path, contents) in files.iter() {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, contents)?;
+    }
+    if let Some(parent) = dir.join(target).parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    Ok(files.into_iter().map(|(p, _)| p).collect())
+}
@@ -1,10 +1,35 @@
+pub mod annotations;
+pub mod checksum;
 pub mod cli;
 pub mod config;
+pub mod docs;
+pub mod fix;
+pub mod fixture;
+pub mod init;
 pub mod lsp;
+pub mod playground;
+pub mod proto;
+pub mod reference;
+pub mod remote;
+pub mod repl;
+pub mod resolver;
+pub mod serve;
 
+#[cfg(test)]
+mod tests;
+
+use crate::resolver::Resolver;
+use anyhow::anyhow;
+use oal_compiler::errors::Category;
+use oal_compiler::module::{Loader, ModuleSet};
+use oal_compiler::tree::Tree;
 use oal_model::locator::Locator;
+use oal_model::span::Span;
+use std::cell::RefCell;
+use std::collections::HashMap;
 use std::io;
-use std::path::PathBuf;
+use std::path::{Component, Path, PathBuf};
+use url::Url;
 
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
@@ -12,6 +37,23 @@ pub enum Error {
     InvalidPath(String),
     #[error("input/output error")]
     IO(#[from] std::io::Error),
+    #[error("remote module: {0}")]
+    Remote(String),
+}
+
+/// Returns the category of an error from loading, compiling or evaluating a program, if known.
+pub fn error_category(err: &anyhow::Error) -> Option<Category> {
+    err.chain().find_map(|e| {
+        if let Some(e) = e.downcast_ref::<oal_compiler::errors::Error>() {
+            Some(e.category())
+        } else if e.is::<oal_syntax::errors::Error>() {
+            Some(Category::Syntax)
+        } else if e.is::<Error>() || e.is::<io::Error>() {
+            Some(Category::Io)
+        } else {
+            None
+        }
+    })
 }
 
 pub trait FileSystem {
@@ -19,6 +61,13 @@ pub trait FileSystem {
     fn open_file(&self, loc: &Locator) -> Result<Box<dyn io::Read>, Error>;
     fn read_file(&self, loc: &Locator) -> Result<String, Error>;
     fn write_file(&self, loc: &Locator, buf: String) -> Result<(), Error>;
+    /// Returns the canonical locator of an existing file.
+    ///
+    /// Distinct locators of the same file, e.g. differing only by case on a case-insensitive
+    /// file system, have the same canonical locator. By default, locators are canonical.
+    fn canonicalize(&self, loc: &Locator) -> Result<Locator, Error> {
+        Ok(loc.clone())
+    }
 }
 
 pub struct DefaultFileSystem;
@@ -58,4 +107,236 @@ impl FileSystem for DefaultFileSystem {
         std::fs::write(path, buf)?;
         Ok(())
     }
+
+    fn canonicalize(&self, loc: &Locator) -> Result<Locator, Error> {
+        let path = disk_case(&std::fs::canonicalize(locator_path(loc)?)?);
+        let url = Url::from_file_path(&path)
+            .map_err(|_| Error::InvalidPath(path.display().to_string()))?;
+        Ok(Locator::from(url))
+    }
+}
+
+/// Returns the path with each component spelled as stored on disk.
+///
+/// Resolving symbolic links does not restore the case of path components on all platforms.
+fn disk_case(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for comp in path.components() {
+        let Component::Normal(name) = comp else {
+            out.push(comp);
+            continue;
+        };
+        let stored = std::fs::read_dir(&out).ok().and_then(|entries| {
+            let names = entries.filter_map(|e| e.ok().map(|e| e.file_name()));
+            let mut found = None;
+            for n in names {
+                if n == name {
+                    return Some(n);
+                }
+                if found.is_none()
+                    && n.to_string_lossy().to_lowercase() == name.to_string_lossy().to_lowercase()
+                {
+                    found = Some(n);
+                }
+            }
+            found
+        });
+        out.push(stored.as_deref().unwrap_or(name));
+    }
+    out
+}
+
+/// An in-memory file system, e.g. for compiling programs in tests without touching the disk.
+#[derive(Default, Debug)]
+pub struct InMemoryFileSystem {
+    files: RefCell<HashMap<Locator, String>>,
+    case_insensitive: bool,
+}
+
+impl InMemoryFileSystem {
+    /// The root of all locators in the file system.
+    const ROOT: &'static str = "file:///";
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the deterministic locator of the given path, relative to the file system root.
+    pub fn locator(path: &str) -> Locator {
+        let root = Locator::try_from(Self::ROOT).expect("root should be a valid locator");
+        root.join(path).expect("path should be valid")
+    }
+
+    /// Adds a file at the given path, relative to the file system root.
+    pub fn with_file<S: Into<String>>(self, path: &str, code: S) -> Self {
+        self.files
+            .borrow_mut()
+            .insert(Self::locator(path), code.into());
+        self
+    }
+
+    /// Makes paths case-insensitive, like the default file systems of macOS and Windows.
+    pub fn case_insensitive(mut self) -> Self {
+        self.case_insensitive = true;
+        self
+    }
+
+    /// Returns the locator of the stored file at the given location, if any.
+    fn stored(&self, loc: &Locator) -> Option<Locator> {
+        let files = self.files.borrow();
+        if files.contains_key(loc) {
+            Some(loc.clone())
+        } else if self.case_insensitive {
+            let path = loc.url().as_str().to_lowercase();
+            files
+                .keys()
+                .find(|k| k.url().as_str().to_lowercase() == path)
+                .cloned()
+        } else {
+            None
+        }
+    }
+}
+
+impl FileSystem for InMemoryFileSystem {
+    fn is_valid(&self, loc: &Locator) -> bool {
+        self.stored(loc).is_some()
+    }
+
+    fn open_file(&self, loc: &Locator) -> Result<Box<dyn io::Read>, Error> {
+        let text = self.read_file(loc)?;
+        Ok(Box::new(io::Cursor::new(text)))
+    }
+
+    fn read_file(&self, loc: &Locator) -> Result<String, Error> {
+        let text = self
+            .stored(loc)
+            .and_then(|l| self.files.borrow().get(&l).cloned());
+        text.ok_or_else(|| Error::InvalidPath(loc.url().as_str().to_owned()))
+    }
+
+    fn write_file(&self, loc: &Locator, buf: String) -> Result<(), Error> {
+        let loc = self.stored(loc).unwrap_or_else(|| loc.clone());
+        self.files.borrow_mut().insert(loc, buf);
+        Ok(())
+    }
+
+    fn canonicalize(&self, loc: &Locator) -> Result<Locator, Error> {
+        self.stored(loc)
+            .ok_or_else(|| Error::InvalidPath(loc.url().as_str().to_owned()))
+    }
+}
+
+/// A module loader on top of a file system, without error reporting.
+pub struct FileSystemLoader<'a, F: FileSystem>(pub &'a F);
+
+impl<F: FileSystem> Loader<anyhow::Error> for FileSystemLoader<'_, F> {
+    fn is_valid(&mut self, loc: &Locator) -> bool {
+        self.0.is_valid(loc)
+    }
+
+    fn load(&mut self, loc: &Locator) -> anyhow::Result<String> {
+        let code = self.0.read_file(loc)?;
+        Ok(code)
+    }
+
+    fn parse(&mut self, loc: Locator, input: String) -> anyhow::Result<Tree> {
+        let (tree, mut errs) = oal_syntax::parse(loc, input);
+        if let Some(err) = errs.pop() {
+            Err(err.into())
+        } else {
+            tree.ok_or_else(|| anyhow!("parsing failed"))
+        }
+    }
+
+    fn compile(&mut self, mods: &ModuleSet, loc: &Locator) -> anyhow::Result<()> {
+        oal_compiler::compile::compile(mods, loc)?;
+        Ok(())
+    }
+
+    fn canonicalize(&mut self, loc: &Locator) -> Locator {
+        self.0.canonicalize(loc).unwrap_or_else(|_| loc.clone())
+    }
+}
+
+/// A module loader on top of a file system, failing with the compiler error of the first
+/// invalid module and keeping the warnings of the others.
+pub struct DiagnosticLoader<'a, F: FileSystem + ?Sized> {
+    fs: &'a F,
+    strict: bool,
+    resolver: Resolver,
+    warnings: Vec<oal_compiler::errors::Error>,
+}
+
+impl<'a, F: FileSystem + ?Sized> DiagnosticLoader<'a, F> {
+    /// Creates a loader rejecting incomplete type inference in strict mode.
+    pub fn new(fs: &'a F, strict: bool) -> Self {
+        DiagnosticLoader {
+            fs,
+            strict,
+            resolver: Resolver::default(),
+            warnings: Vec::new(),
+        }
+    }
+
+    /// Resolves imports through the aliases of module paths.
+    pub fn with_resolver(mut self, resolver: Resolver) -> Self {
+        self.resolver = resolver;
+        self
+    }
+
+    /// Returns the warnings of the modules compiled so far, leaving none.
+    pub fn take_warnings(&mut self) -> Vec<oal_compiler::errors::Error> {
+        std::mem::take(&mut self.warnings)
+    }
+}
+
+impl<F: FileSystem + ?Sized> Loader<oal_compiler::errors::Error> for DiagnosticLoader<'_, F> {
+    fn is_valid(&mut self, loc: &Locator) -> bool {
+        self.fs.is_valid(loc)
+    }
+
+    fn load(&mut self, loc: &Locator) -> oal_compiler::errors::Result<String> {
+        self.fs.read_file(loc).map_err(|_| {
+            oal_compiler::errors::Error::from(oal_compiler::errors::Kind::InvalidModule(
+                loc.clone(),
+            ))
+        })
+    }
+
+    fn parse(&mut self, loc: Locator, input: String) -> oal_compiler::errors::Result<Tree> {
+        let (tree, mut errs) = oal_syntax::parse(loc.clone(), input);
+        if let Some(err) = errs.pop() {
+            let span = match err {
+                oal_syntax::errors::Error::Grammar(ref err) => err.span(),
+                oal_syntax::errors::Error::Lexicon(ref err) => err.span(),
+                _ => Span::new(loc, 0..0),
+            };
+            Err(oal_compiler::errors::Error::from(err).at(Some(span)))
+        } else {
+            tree.ok_or_else(|| {
+                oal_compiler::errors::Error::new(
+                    oal_compiler::errors::Kind::InvalidModule(loc),
+                    "parsing failed",
+                )
+            })
+        }
+    }
+
+    fn compile(&mut self, mods: &ModuleSet, loc: &Locator) -> oal_compiler::errors::Result<()> {
+        let mut warnings = oal_compiler::compile::compile_with_warnings(mods, loc)?;
+        if self.strict {
+            oal_compiler::compile::check_complete(mods, loc)?;
+        }
+        self.warnings.append(&mut warnings);
+        Ok(())
+    }
+
+    fn resolve(&mut self, loc: &Locator, import: &str) -> oal_compiler::errors::Result<Locator> {
+        self.resolver.resolve(loc, import)
+    }
+
+    fn canonicalize(&mut self, loc: &Locator) -> Locator {
+        self.fs.canonicalize(loc).unwrap_or_else(|_| loc.clone())
+    }
 }
@@ -1,14 +1,22 @@
+use super::semantic;
 use super::state::GlobalState;
 use super::unicode::position_to_utf8;
-use super::{utf8_range_to_position, Folder, Workspace};
+use super::{utf8_range_to_position, FixData, Folder, Workspace};
 use lsp_types::{
-    GotoDefinitionParams, GotoDefinitionResponse, Location, Range, ReferenceParams, RenameParams,
-    TextDocumentPositionParams, TextEdit, WorkspaceEdit,
+    CodeAction, CodeActionKind, CodeActionOrCommand, CodeActionParams, CodeActionResponse,
+    CompletionItem, CompletionItemKind, CompletionParams, CompletionResponse, Documentation,
+    GotoDefinitionParams, GotoDefinitionResponse, Hover, HoverContents, HoverParams, Location,
+    MarkupContent, MarkupKind, Range, ReferenceParams, RenameParams, SemanticTokens,
+    SemanticTokensParams, SemanticTokensResult, TextDocumentPositionParams, TextEdit,
+    WorkspaceEdit,
 };
 use oal_compiler::definition::{Definition, External};
+use oal_compiler::stdlib;
 use oal_compiler::tree::{Core, NRef, Tree};
 use oal_model::grammar::AbstractSyntaxNode;
+use oal_model::lexicon::Lexeme;
 use oal_model::locator::Locator;
+use oal_syntax::lexer::{tokenize, TokenKind};
 use oal_syntax::parser::{Declaration, Gram, Identifier, Qualifier, Variable};
 use std::collections::hash_map::Entry;
 use std::collections::HashMap;
@@ -47,7 +55,7 @@ fn find_definition(tree: &Tree, index: usize) -> Option<Definition> {
 }
 
 /// Finds the qualifier at the given identifier location, if any.
-fn find_qualifier(tree: &Tree, index: usize) -> Option<Qualifier<Core>> {
+fn find_qualifier(tree: &Tree, index: usize) -> Option<Qualifier<'_, Core>> {
     let ident = syntax_at::<Identifier<_>>(tree, index)?;
     let parent = ident.node().ancestors().nth(1).unwrap();
     Qualifier::cast(parent)
@@ -76,9 +84,7 @@ fn find_folders<'a>(
     folders: &'a HashMap<Url, Folder>,
     loc: &'a Locator,
 ) -> impl Iterator<Item = &'a Folder> + 'a {
-    folders
-        .iter()
-        .filter_map(|(_, f)| if f.contains(loc) { Some(f) } else { None })
+    folders.values().filter(|f| f.contains(loc))
 }
 
 /// Implements the go-to-definition capability.
@@ -105,6 +111,137 @@ pub fn go_to_definition(
     Ok(Some(GotoDefinitionResponse::Array(Vec::new())))
 }
 
+/// Formats the documentation of an internal definition as Markdown.
+fn internal_markdown(doc: &stdlib::Documentation) -> String {
+    format!("```\n{}\n```\n`{}`\n\n{}", doc.signature, doc.tag, doc.doc)
+}
+
+/// Implements the hover capability.
+pub fn hover(state: &mut GlobalState, params: HoverParams) -> anyhow::Result<Option<Hover>> {
+    let pos = params.text_document_position_params.position;
+    let loc = Locator::from(params.text_document_position_params.text_document.uri);
+    let text = state.workspace.read_file(&loc)?;
+    let index = position_to_utf8(&text, pos);
+
+    for folder in find_folders(&state.folders, &loc) {
+        let tree = folder.module(&loc).unwrap();
+        if let Some(v) = syntax_at::<Variable<_>>(tree, index) {
+            if let Some(Definition::Internal(internal)) = v.node().syntax().core_ref().definition()
+            {
+                let doc = stdlib::Documentation::new(internal.as_ref())?;
+                let range = utf8_range_to_position(&text, v.node().span().unwrap().range());
+                return Ok(Some(Hover {
+                    contents: HoverContents::Markup(MarkupContent {
+                        kind: MarkupKind::Markdown,
+                        value: internal_markdown(&doc),
+                    }),
+                    range: Some(range),
+                }));
+            }
+        }
+    }
+
+    Ok(None)
+}
+
+/// Returns the prefix of the identifier being typed before the given UTF-8 index,
+/// or none if the position does not expect a value, e.g. within a comment, a literal
+/// or an annotation, or when naming a declaration.
+pub fn completion_prefix(loc: Locator, text: &str, index: usize) -> Option<&str> {
+    let (Some(list), _) = tokenize(loc, text) else {
+        return None;
+    };
+    // The last significant token before the one at the cursor.
+    let mut last = None;
+    let mut cursor = list.head();
+    while cursor.is_valid() {
+        let (token, span) = list.token_span(cursor);
+        let kind = token.kind();
+        let range = span.range();
+        if range.start >= index {
+            break;
+        }
+        if range.end >= index {
+            let typed = &text[range.start..index];
+            let prefix = match kind {
+                TokenKind::IdentifierValue => typed,
+                TokenKind::Space => "",
+                // Line comments and annotations end with the line.
+                _ if typed.ends_with('\n') => "",
+                k if k.is_comment()
+                    || k.is_literal()
+                    || k.is_primitive()
+                    || matches!(
+                        k,
+                        TokenKind::Property
+                            | TokenKind::IdentifierReference
+                            | TokenKind::AnnotationLine
+                            | TokenKind::AnnotationDocument
+                            | TokenKind::AnnotationInline
+                    ) =>
+                {
+                    return None
+                }
+                _ => {
+                    last = Some(kind);
+                    ""
+                }
+            };
+            return (!is_naming(last)).then_some(prefix);
+        }
+        if !kind.is_trivia() {
+            last = Some(kind);
+        }
+        cursor = list.advance(cursor);
+    }
+    (!is_naming(last)).then_some("")
+}
+
+/// Returns whether an identifier following the given token names a declaration.
+fn is_naming(last: Option<TokenKind>) -> bool {
+    matches!(
+        last,
+        Some(
+            TokenKind::KeywordLet
+                | TokenKind::KeywordAs
+                | TokenKind::KeywordUse
+                | TokenKind::KeywordRec
+        )
+    )
+}
+
+/// Implements the completion capability for the standard library,
+/// proposing the definitions matching the identifier being typed.
+pub fn completion(
+    state: &mut GlobalState,
+    params: CompletionParams,
+) -> anyhow::Result<Option<CompletionResponse>> {
+    let pos = params.text_document_position.position;
+    let loc = Locator::from(params.text_document_position.text_document.uri);
+    let text = state.workspace.read_file(&loc)?;
+    let index = position_to_utf8(&text, pos);
+
+    let Some(prefix) = completion_prefix(loc, &text, index) else {
+        return Ok(None);
+    };
+
+    let items = stdlib::documentation()?
+        .iter()
+        .filter(|doc| doc.name.starts_with(prefix))
+        .map(|doc| CompletionItem {
+            label: doc.name.to_owned(),
+            kind: Some(CompletionItemKind::FUNCTION),
+            detail: Some(doc.tag.clone()),
+            documentation: Some(Documentation::MarkupContent(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value: internal_markdown(doc),
+            })),
+            ..Default::default()
+        })
+        .collect();
+    Ok(Some(CompletionResponse::Array(items)))
+}
+
 /// Implements the references capability.
 pub fn references(
     state: &mut GlobalState,
@@ -278,3 +415,43 @@ fn rename_variable(
 
     Ok(())
 }
+
+/// Implements the code action capability, offering the fixes attached to diagnostics.
+pub fn code_action(
+    _state: &mut GlobalState,
+    params: CodeActionParams,
+) -> anyhow::Result<Option<CodeActionResponse>> {
+    let mut actions = Vec::new();
+    for diag in params.context.diagnostics.iter() {
+        let Some(data) = &diag.data else {
+            continue;
+        };
+        let fixes: Vec<FixData> = serde_json::from_value(data.clone())?;
+        let is_preferred = fixes.len() == 1;
+        for fix in fixes {
+            actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+                title: fix.title,
+                kind: Some(CodeActionKind::QUICKFIX),
+                diagnostics: Some(vec![diag.clone()]),
+                edit: Some(WorkspaceEdit::new(fix.changes)),
+                is_preferred: Some(is_preferred),
+                ..Default::default()
+            }));
+        }
+    }
+    Ok(Some(actions))
+}
+
+/// Implements the semantic tokens capability for a full document.
+pub fn semantic_tokens_full(
+    state: &mut GlobalState,
+    params: SemanticTokensParams,
+) -> anyhow::Result<Option<SemanticTokensResult>> {
+    let loc = Locator::from(params.text_document.uri);
+    let text = state.workspace.read_file(&loc)?;
+    let data = semantic::tokens(loc, &text);
+    Ok(Some(SemanticTokensResult::Tokens(SemanticTokens {
+        result_id: None,
+        data,
+    })))
+}
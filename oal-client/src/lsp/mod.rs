@@ -1,25 +1,32 @@
 pub mod dispatcher;
 pub mod handlers;
+pub mod semantic;
 pub mod state;
 pub mod unicode;
 
 #[cfg(test)]
 mod tests;
 
-use crate::config::Config;
+use crate::config::{Config, LintLevel, Settings};
+use crate::resolver::Resolver;
 use crate::{DefaultFileSystem, FileSystem};
 use anyhow::anyhow;
-use log::debug;
+use log::{debug, warn};
 use lsp_types::{
-    Diagnostic, DidChangeTextDocumentParams, DidCloseTextDocumentParams, DidOpenTextDocumentParams,
+    Diagnostic, DiagnosticRelatedInformation, DiagnosticSeverity, DidChangeTextDocumentParams,
+    DidCloseTextDocumentParams, DidOpenTextDocumentParams, Location, NumberOrString, TextEdit,
 };
-use oal_compiler::module::{Loader, ModuleSet};
+use oal_compiler::errors::Error;
+use oal_compiler::fix::Fix;
+use oal_compiler::module::{LoadLimits, Loader, ModuleSet};
 use oal_compiler::spec::Spec;
 use oal_compiler::tree::Tree;
 use oal_model::{locator::Locator, span::Span};
+use serde::{Deserialize, Serialize};
 use std::collections::hash_map::Entry;
 use std::collections::HashMap;
 use unicode::{position_to_utf8, utf8_range_to_position};
+use url::Url;
 
 /// A folder in the workspace.
 #[derive(Debug)]
@@ -30,8 +37,8 @@ pub struct Folder {
 }
 
 impl Folder {
-    /// Creates a new workspace folder.
-    pub fn new(folder: lsp_types::WorkspaceFolder) -> anyhow::Result<Self> {
+    /// Creates a new workspace folder, with the settings provided by the client.
+    pub fn new(folder: lsp_types::WorkspaceFolder, settings: &Settings) -> anyhow::Result<Self> {
         const DEFAULT_CONFIG_FILE: &str = "oal.toml";
         if folder.uri.scheme() != "file" {
             Err(anyhow!("not a file"))
@@ -40,7 +47,7 @@ impl Folder {
             // The original URL can be a base so path_segments_mut should never fail.
             uri.path_segments_mut().unwrap().push(DEFAULT_CONFIG_FILE);
             let path = uri.to_file_path().map_err(|_| anyhow!("not a path"))?;
-            let config = Config::new(Some(path.as_path()))?;
+            let config = Config::new(Some(path.as_path()))?.with_client_settings(settings.clone());
             Ok(Folder {
                 config,
                 mods: None,
@@ -49,6 +56,11 @@ impl Folder {
         }
     }
 
+    /// Updates the settings provided by the client.
+    pub fn configure(&mut self, settings: &Settings) {
+        self.config.set_client_settings(settings.clone());
+    }
+
     /// Returns the compiled modules for the folder, if any.
     pub fn modules(&self) -> Option<&ModuleSet> {
         self.mods.as_ref()
@@ -70,8 +82,16 @@ impl Folder {
         self.spec = None;
         if let Ok(main) = self.config.main() {
             debug!("evaluating {}", main);
-            if let Ok(mods) = ws.load(&main) {
-                self.spec = ws.eval(&mods).ok();
+            let settings = self.config.settings();
+            let Ok(fs) = self.config.remote() else {
+                return;
+            };
+            if let Ok(mods) = ws.load(&main, &settings, &fs) {
+                self.spec = ws.eval(&mods, &settings).ok();
+                if let Some(spec) = &self.spec {
+                    ws.check_limits(&mods, spec, &settings);
+                    ws.lint(&mods, spec, &settings);
+                }
                 self.mods = Some(mods);
             }
         }
@@ -80,11 +100,42 @@ impl Folder {
 
 pub type Diagnostics = HashMap<Locator, Vec<Diagnostic>>;
 
+/// An error logged in the workspace, pending conversion into a diagnostic.
+struct LoggedError {
+    span: Span,
+    code: &'static str,
+    msg: String,
+    labels: Vec<(Span, String)>,
+    fixes: Vec<Fix>,
+    severity: DiagnosticSeverity,
+}
+
+/// A fix attached as data to a diagnostic, to be offered as a code action.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FixData {
+    pub title: String,
+    pub changes: HashMap<Url, Vec<TextEdit>>,
+}
+
+/// Extracts the settings provided by the client, either nested under an `oal` section or not.
+pub fn client_settings(value: Option<serde_json::Value>) -> Settings {
+    let Some(mut value) = value else {
+        return Settings::default();
+    };
+    if let Some(section) = value.get_mut("oal") {
+        value = section.take();
+    }
+    serde_json::from_value(value).unwrap_or_else(|err| {
+        warn!("invalid client settings: {err}");
+        Settings::default()
+    })
+}
+
 /// A workspace.
 #[derive(Default)]
 pub struct Workspace {
     docs: HashMap<Locator, String>,
-    errors: Option<Vec<(Span, String)>>,
+    errors: Option<Vec<LoggedError>>,
 }
 
 impl Workspace {
@@ -119,10 +170,26 @@ impl Workspace {
         Ok(loc)
     }
 
-    /// Loads, parses and compiles a program.
-    pub fn load(&mut self, loc: &Locator) -> anyhow::Result<ModuleSet> {
-        let loader = &mut WorkspaceLoader(self);
-        let mods = oal_compiler::module::load(loader, loc).map_err(|err| {
+    /// Loads, parses and compiles a program, reading the files not open in the workspace
+    /// from the given file system.
+    pub fn load(
+        &mut self,
+        loc: &Locator,
+        settings: &Settings,
+        fs: &dyn FileSystem,
+    ) -> anyhow::Result<ModuleSet> {
+        let resolver = settings.resolver().map_err(|err| {
+            self.log_compiler_error(loc, &err);
+            anyhow!("loading failed")
+        })?;
+        let loader = &mut WorkspaceLoader {
+            ws: self,
+            settings,
+            resolver,
+            fs,
+        };
+        let limits = settings.load_limits(LoadLimits::INTERACTIVE);
+        let mods = oal_compiler::module::load_with_limits(loader, loc, &limits).map_err(|err| {
             if let Ok(err) = err.downcast::<oal_compiler::errors::Error>() {
                 self.log_compiler_error(loc, &err)
             }
@@ -131,9 +198,9 @@ impl Workspace {
         Ok(mods)
     }
 
-    /// Evaluates a program.
-    pub fn eval(&mut self, mods: &ModuleSet) -> anyhow::Result<Spec> {
-        match oal_compiler::eval::eval(mods) {
+    /// Evaluates a program, logging warnings.
+    pub fn eval(&mut self, mods: &ModuleSet, settings: &Settings) -> anyhow::Result<Spec> {
+        match oal_compiler::eval::eval_with_warnings(mods) {
             Err(err) => {
                 let loc = match err.span() {
                     Some(s) => s.locator().clone(),
@@ -142,44 +209,140 @@ impl Workspace {
                 self.log_compiler_error(&loc, &err);
                 Err(anyhow!("evaluation failed"))
             }
-            Ok(spec) => Ok(spec),
+            Ok((spec, warnings)) => {
+                if self.log_warnings(mods.base(), warnings, settings) {
+                    Err(anyhow!("denied warnings"))
+                } else {
+                    Ok(spec)
+                }
+            }
         }
     }
 
-    /// Logs an error.
-    fn log_error(&mut self, span: Span, err: String) {
-        self.errors
-            .get_or_insert_with(Default::default)
-            .push((span, err));
+    /// Checks a specification against the given limits, logging warnings.
+    pub fn check_limits(&mut self, mods: &ModuleSet, spec: &Spec, settings: &Settings) {
+        let warnings = oal_compiler::limits::check_limits(mods, spec, &settings.limits());
+        self.log_warnings(mods.base(), warnings, settings);
     }
 
-    /// Logs a collection of syntax errors.
-    fn log_syntax_errors<'a>(&mut self, loc: &'a Locator, errs: &'a [oal_syntax::errors::Error]) {
+    /// Checks a program and its specification for likely mistakes, logging warnings.
+    pub fn lint(&mut self, mods: &ModuleSet, spec: &Spec, settings: &Settings) {
+        let warnings = oal_compiler::lint::lint(mods, spec);
+        self.log_warnings(mods.base(), warnings, settings);
+    }
+
+    /// Logs warnings according to the level of their lint.
+    ///
+    /// Returns true if any of the warnings is denied.
+    fn log_warnings(&mut self, loc: &Locator, warnings: Vec<Error>, settings: &Settings) -> bool {
+        let mut denied = false;
+        for err in warnings {
+            let severity = match settings.lint_level(&err) {
+                LintLevel::Allow => continue,
+                LintLevel::Warn => DiagnosticSeverity::WARNING,
+                LintLevel::Deny => {
+                    denied = true;
+                    DiagnosticSeverity::ERROR
+                }
+            };
+            self.log_compiler_diagnostic(loc, &err, severity);
+        }
+        denied
+    }
+
+    /// Logs an error with its diagnostic code.
+    fn log_error(&mut self, err: LoggedError) {
+        self.errors.get_or_insert_with(Default::default).push(err);
+    }
+
+    /// Logs a collection of syntax errors in the given source text.
+    fn log_syntax_errors<'a>(
+        &mut self,
+        loc: &'a Locator,
+        input: &str,
+        errs: &'a [oal_syntax::errors::Error],
+    ) {
         for err in errs.iter() {
             let span = match err {
                 oal_syntax::errors::Error::Grammar(ref err) => err.span(),
                 oal_syntax::errors::Error::Lexicon(ref err) => err.span(),
                 _ => Span::new(loc.clone(), 0..0),
             };
-            self.log_error(span, err.to_string())
+            self.log_error(LoggedError {
+                span,
+                code: err.code(),
+                msg: err.to_string(),
+                labels: Vec::new(),
+                fixes: oal_compiler::fix::syntax_fix(loc, input, err)
+                    .into_iter()
+                    .collect(),
+                severity: DiagnosticSeverity::ERROR,
+            })
         }
     }
 
     /// Logs a compiler error.
     fn log_compiler_error(&mut self, loc: &Locator, err: &oal_compiler::errors::Error) {
+        self.log_compiler_diagnostic(loc, err, DiagnosticSeverity::ERROR)
+    }
+
+    /// Logs a compiler error with the given severity.
+    fn log_compiler_diagnostic(
+        &mut self,
+        loc: &Locator,
+        err: &oal_compiler::errors::Error,
+        severity: DiagnosticSeverity,
+    ) {
         let span = err
             .span()
             .cloned()
             .unwrap_or_else(|| Span::new(loc.clone(), 0..0));
-        self.log_error(span, err.to_string())
+        self.log_error(LoggedError {
+            span,
+            code: err.code(),
+            msg: err.to_string(),
+            labels: err.labels().to_vec(),
+            fixes: err.fixes().to_vec(),
+            severity,
+        })
     }
 
-    /// Creates an LSP diagnostic from the given span and error.
-    fn diagnostic<E: ToString>(&mut self, span: &Span, err: E) -> anyhow::Result<Diagnostic> {
-        let text = self.read_file(span.locator())?;
-        let range = utf8_range_to_position(&text, span.range());
+    /// Creates an LSP diagnostic from a logged error.
+    fn diagnostic(&mut self, err: LoggedError) -> anyhow::Result<Diagnostic> {
+        let text = self.read_file(err.span.locator())?;
+        let range = utf8_range_to_position(&text, err.span.range());
+        let mut related = Vec::new();
+        for (span, msg) in err.labels {
+            let text = self.read_file(span.locator())?;
+            let range = utf8_range_to_position(&text, span.range());
+            let location = Location::new(span.locator().url().clone(), range);
+            related.push(DiagnosticRelatedInformation {
+                location,
+                message: msg,
+            });
+        }
+        let mut fixes = Vec::new();
+        for fix in err.fixes {
+            let mut changes: HashMap<Url, Vec<TextEdit>> = HashMap::new();
+            for edit in fix.edits {
+                let text = self.read_file(edit.span.locator())?;
+                let range = utf8_range_to_position(&text, edit.span.range());
+                changes
+                    .entry(edit.span.locator().url().clone())
+                    .or_default()
+                    .push(TextEdit::new(range, edit.text));
+            }
+            fixes.push(FixData {
+                title: fix.title,
+                changes,
+            });
+        }
         Ok(Diagnostic {
-            message: err.to_string(),
+            message: err.msg,
+            code: Some(NumberOrString::String(err.code.to_owned())),
+            related_information: (!related.is_empty()).then_some(related),
+            data: (!fixes.is_empty()).then(|| serde_json::to_value(fixes).unwrap()),
+            severity: Some(err.severity),
             range,
             ..Default::default()
         })
@@ -195,9 +358,9 @@ impl Workspace {
             .map(|loc| (loc.clone(), Default::default()))
             .collect::<Diagnostics>();
         let errs = self.errors.take().unwrap_or_default();
-        for (span, msg) in errs {
-            let diag = self.diagnostic(&span, msg)?;
-            let loc = span.locator().clone();
+        for err in errs {
+            let loc = err.span.locator().clone();
+            let diag = self.diagnostic(err)?;
             match diags.entry(loc) {
                 Entry::Occupied(mut e) => {
                     e.get_mut().push(diag);
@@ -212,10 +375,18 @@ impl Workspace {
 
     /// Reads a file from the workspace.
     fn read_file(&mut self, loc: &Locator) -> anyhow::Result<String> {
+        self.read_file_from(loc, &DefaultFileSystem)
+    }
+
+    /// Reads a file from the workspace, or else from the given file system.
+    fn read_file_from(&mut self, loc: &Locator, fs: &dyn FileSystem) -> anyhow::Result<String> {
+        if let Some(code) = oal_compiler::library::source(loc) {
+            return Ok(code.to_owned());
+        }
         match self.docs.entry(loc.clone()) {
             Entry::Occupied(e) => Ok(e.get().clone()),
             Entry::Vacant(e) => {
-                let file = DefaultFileSystem.read_file(loc)?;
+                let file = fs.read_file(loc)?;
                 e.insert(file.clone());
                 Ok(file)
             }
@@ -223,37 +394,65 @@ impl Workspace {
     }
 }
 
-struct WorkspaceLoader<'a>(&'a mut Workspace);
+/// The workspace loader, applying the compilation settings.
+struct WorkspaceLoader<'a> {
+    ws: &'a mut Workspace,
+    settings: &'a Settings,
+    resolver: Resolver,
+    fs: &'a dyn FileSystem,
+}
 
 impl Loader<anyhow::Error> for WorkspaceLoader<'_> {
     /// Returns true if the given locator points to a valid source file.
     fn is_valid(&mut self, loc: &Locator) -> bool {
-        DefaultFileSystem.is_valid(loc)
+        self.fs.is_valid(loc)
     }
 
     /// Loads a source file.
     fn load(&mut self, loc: &Locator) -> anyhow::Result<String> {
-        self.0.read_file(loc)
+        self.ws.read_file_from(loc, self.fs)
     }
 
     /// Loads and parses a source file into a concrete syntax tree.
     fn parse(&mut self, loc: Locator, input: String) -> anyhow::Result<Tree> {
-        let (tree, errs) = oal_syntax::parse(loc.clone(), input);
-        self.0.log_syntax_errors(&loc, &errs);
+        let (tree, errs) = oal_syntax::parse(loc.clone(), &input);
+        self.ws.log_syntax_errors(&loc, &input, &errs);
         tree.ok_or_else(|| anyhow!("parsing failed"))
     }
 
     /// Compiles a program.
     fn compile(&mut self, mods: &ModuleSet, loc: &Locator) -> anyhow::Result<()> {
-        if let Err(err) = oal_compiler::compile::compile(mods, loc) {
-            let loc = match err.span() {
-                Some(s) => s.locator().clone(),
-                None => loc.clone(),
-            };
-            self.0.log_compiler_error(&loc, &err);
-            Err(anyhow!("compilation failed"))
-        } else {
-            Ok(())
+        let mut result = oal_compiler::compile::compile_with_warnings(mods, loc);
+        if self.settings.strict.unwrap_or(false) {
+            result =
+                result.and_then(|w| oal_compiler::compile::check_complete(mods, loc).map(|_| w));
         }
+        match result {
+            Err(err) => {
+                let loc = match err.span() {
+                    Some(s) => s.locator().clone(),
+                    None => loc.clone(),
+                };
+                self.ws.log_compiler_error(&loc, &err);
+                Err(anyhow!("compilation failed"))
+            }
+            Ok(warnings) => {
+                if self.ws.log_warnings(loc, warnings, self.settings) {
+                    Err(anyhow!("compilation failed"))
+                } else {
+                    Ok(())
+                }
+            }
+        }
+    }
+
+    /// Resolves an import through the aliases of module paths.
+    fn resolve(&mut self, loc: &Locator, import: &str) -> oal_compiler::errors::Result<Locator> {
+        self.resolver.resolve(loc, import)
+    }
+
+    /// Returns the canonical locator of a source file.
+    fn canonicalize(&mut self, loc: &Locator) -> Locator {
+        self.fs.canonicalize(loc).unwrap_or_else(|_| loc.clone())
     }
 }
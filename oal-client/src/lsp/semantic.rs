@@ -0,0 +1,161 @@
+use lsp_types::{SemanticToken, SemanticTokenType, SemanticTokensLegend};
+use oal_model::lexicon::Lexeme;
+use oal_model::locator::Locator;
+use oal_syntax::lexer::{tokenize, TokenKind};
+
+/// The token types reported to the client, indexed by the encoded tokens.
+const TOKEN_TYPES: [SemanticTokenType; 11] = [
+    SemanticTokenType::KEYWORD,
+    SemanticTokenType::TYPE,
+    SemanticTokenType::METHOD,
+    SemanticTokenType::PROPERTY,
+    SemanticTokenType::DECORATOR,
+    SemanticTokenType::COMMENT,
+    SemanticTokenType::NUMBER,
+    SemanticTokenType::ENUM_MEMBER,
+    SemanticTokenType::STRING,
+    SemanticTokenType::VARIABLE,
+    SemanticTokenType::OPERATOR,
+];
+
+/// Returns the legend of the semantic tokens, as declared in the server capabilities.
+pub fn legend() -> SemanticTokensLegend {
+    SemanticTokensLegend {
+        token_types: TOKEN_TYPES.to_vec(),
+        token_modifiers: Vec::new(),
+    }
+}
+
+/// Returns the index in the legend of the given token type.
+fn type_index(ty: SemanticTokenType) -> u32 {
+    TOKEN_TYPES.iter().position(|t| *t == ty).unwrap() as u32
+}
+
+/// Returns the semantic token type of a lexer token, if it is highlighted.
+///
+/// Numbers assigned to a `status` content tag are reported as HTTP statuses.
+fn token_type(kind: TokenKind, is_status: bool) -> Option<SemanticTokenType> {
+    let ty = match kind {
+        TokenKind::KeywordLet
+        | TokenKind::KeywordRes
+        | TokenKind::KeywordUse
+        | TokenKind::KeywordAs
+        | TokenKind::KeywordOn
+        | TokenKind::KeywordRec => SemanticTokenType::KEYWORD,
+        k if k.is_content() => SemanticTokenType::KEYWORD,
+        k if k.is_primitive() => SemanticTokenType::TYPE,
+        k if k.is_method() => SemanticTokenType::METHOD,
+        k if k.is_comment() => SemanticTokenType::COMMENT,
+        k if k.is_operator() => SemanticTokenType::OPERATOR,
+        TokenKind::Property => SemanticTokenType::PROPERTY,
+        TokenKind::AnnotationLine | TokenKind::AnnotationDocument | TokenKind::AnnotationInline => {
+            SemanticTokenType::DECORATOR
+        }
+        TokenKind::LiteralHttpStatus => SemanticTokenType::ENUM_MEMBER,
+        TokenKind::LiteralNumber if is_status => SemanticTokenType::ENUM_MEMBER,
+        TokenKind::LiteralNumber => SemanticTokenType::NUMBER,
+        TokenKind::LiteralString => SemanticTokenType::STRING,
+        TokenKind::IdentifierReference => SemanticTokenType::TYPE,
+        TokenKind::IdentifierValue => SemanticTokenType::VARIABLE,
+        _ => return None,
+    };
+    Some(ty)
+}
+
+/// Encodes highlighted ranges as semantic tokens, relative to each other,
+/// with UTF-16 positions as negotiated with the client.
+struct Encoder<'a> {
+    text: &'a str,
+    /// The UTF-8 index, line and UTF-16 character of the scanning position.
+    index: usize,
+    line: u32,
+    character: u32,
+    /// The position of the start of the previous token.
+    prev_line: u32,
+    prev_start: u32,
+    tokens: Vec<SemanticToken>,
+}
+
+impl<'a> Encoder<'a> {
+    fn new(text: &'a str) -> Self {
+        Encoder {
+            text,
+            index: 0,
+            line: 0,
+            character: 0,
+            prev_line: 0,
+            prev_start: 0,
+            tokens: Vec::new(),
+        }
+    }
+
+    /// Moves the scanning position forward to the given UTF-8 index.
+    fn seek(&mut self, index: usize) {
+        for c in self.text[self.index..index].chars() {
+            if c == '\n' {
+                self.line += 1;
+                self.character = 0;
+            } else {
+                self.character += c.len_utf16() as u32;
+            }
+        }
+        self.index = index;
+    }
+
+    /// Pushes a token for each line of the given range,
+    /// as multiline tokens are not supported by all clients.
+    fn push(&mut self, range: std::ops::Range<usize>, token_type: u32) {
+        let mut start = range.start;
+        for line in self.text[range].split_inclusive('\n') {
+            let content = line.trim_end_matches(['\r', '\n']);
+            if !content.is_empty() {
+                self.seek(start);
+                let length = content.chars().map(|c| c.len_utf16() as u32).sum();
+                let delta_line = self.line - self.prev_line;
+                let delta_start = if delta_line == 0 {
+                    self.character - self.prev_start
+                } else {
+                    self.character
+                };
+                self.tokens.push(SemanticToken {
+                    delta_line,
+                    delta_start,
+                    length,
+                    token_type,
+                    token_modifiers_bitset: 0,
+                });
+                self.prev_line = self.line;
+                self.prev_start = self.character;
+            }
+            start += line.len();
+        }
+    }
+}
+
+/// Returns the semantic tokens of a document, computed from the lexer.
+pub fn tokens(loc: Locator, text: &str) -> Vec<SemanticToken> {
+    let (Some(list), _) = tokenize(loc, text) else {
+        return Vec::new();
+    };
+    let mut encoder = Encoder::new(text);
+    // The last two significant tokens, to recognize `status=` assignments.
+    let mut last = [None, None];
+    let mut cursor = list.head();
+    while cursor.is_valid() {
+        let (token, span) = list.token_span(cursor);
+        let kind = token.kind();
+        let is_status = last
+            == [
+                Some(TokenKind::ContentStatus),
+                Some(TokenKind::OperatorEqual),
+            ];
+        if let Some(ty) = token_type(kind, is_status) {
+            encoder.push(span.range(), type_index(ty));
+        }
+        if !kind.is_trivia() {
+            last = [last[1], Some(kind)];
+        }
+        cursor = list.advance(cursor);
+    }
+    encoder.tokens
+}
@@ -1,4 +1,5 @@
 use super::{Folder, Workspace};
+use crate::config::Settings;
 use lsp_server::Connection;
 use std::collections::HashMap;
 use url::Url;
@@ -7,5 +8,7 @@ pub struct GlobalState {
     pub conn: Connection,
     pub workspace: Workspace,
     pub folders: HashMap<Url, Folder>,
+    /// The settings provided by the client.
+    pub settings: Settings,
     pub is_stale: bool,
 }
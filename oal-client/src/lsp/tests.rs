@@ -1 +1,105 @@
+use super::client_settings;
+use crate::config::{LintLevel, Settings};
+use serde_json::json;
 
+#[test]
+fn lsp_client_settings() {
+    assert_eq!(client_settings(None), Settings::default());
+
+    let nested = json!({ "oal": { "strict": true, "lints": { "shadowing": "deny" } } });
+    let settings = client_settings(Some(nested));
+    assert_eq!(settings.strict, Some(true));
+    assert_eq!(settings.lints["shadowing"], LintLevel::Deny);
+
+    let flat = json!({ "media_type": "application/xml", "strict": "invalid" });
+    assert_eq!(client_settings(Some(flat)), Settings::default());
+
+    let file = Settings {
+        strict: Some(false),
+        ..Default::default()
+    };
+    let merged = file.merge(client_settings(Some(json!({
+        "media_type": "application/xml",
+        "strict": true
+    }))));
+    assert_eq!(
+        merged.strict,
+        Some(false),
+        "expected file settings to take precedence"
+    );
+    assert_eq!(merged.media_type.as_deref(), Some("application/xml"));
+}
+
+#[test]
+fn lsp_semantic_tokens() {
+    use super::semantic::{legend, tokens};
+    use lsp_types::{SemanticTokenType, SemanticTokensLegend};
+    use oal_model::locator::Locator;
+
+    let SemanticTokensLegend { token_types, .. } = legend();
+    let loc = Locator::try_from("file:///main.oal").unwrap();
+    let text = "// 😉\nlet r = <status=200, 'id str> `title: \"a\"`;\nres / on get -> 4XX;";
+    let (mut line, mut start) = (0, 0);
+    let decoded = tokens(loc, text)
+        .into_iter()
+        .map(|t| {
+            if t.delta_line > 0 {
+                start = 0;
+            }
+            line += t.delta_line;
+            start += t.delta_start;
+            (
+                line,
+                start,
+                t.length,
+                token_types[t.token_type as usize].clone(),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        decoded,
+        [
+            (0, 0, 5, SemanticTokenType::COMMENT),
+            (1, 0, 3, SemanticTokenType::KEYWORD),
+            (1, 4, 1, SemanticTokenType::VARIABLE),
+            (1, 6, 1, SemanticTokenType::OPERATOR),
+            (1, 9, 6, SemanticTokenType::KEYWORD),
+            (1, 15, 1, SemanticTokenType::OPERATOR),
+            (1, 16, 3, SemanticTokenType::ENUM_MEMBER),
+            (1, 21, 3, SemanticTokenType::PROPERTY),
+            (1, 25, 3, SemanticTokenType::TYPE),
+            (1, 30, 12, SemanticTokenType::DECORATOR),
+            (2, 0, 3, SemanticTokenType::KEYWORD),
+            (2, 6, 2, SemanticTokenType::KEYWORD),
+            (2, 9, 3, SemanticTokenType::METHOD),
+            (2, 13, 2, SemanticTokenType::OPERATOR),
+            (2, 16, 3, SemanticTokenType::ENUM_MEMBER),
+        ]
+    );
+}
+
+#[test]
+fn lsp_completion_prefix() {
+    use super::handlers::completion_prefix;
+    use oal_model::locator::Locator;
+
+    let loc = Locator::try_from("file:///main.oal").unwrap();
+    let prefix = |text: &str| {
+        let index = text.find('|').unwrap();
+        let text = text.replace('|', "");
+        completion_prefix(loc.clone(), &text, index).map(ToOwned::to_owned)
+    };
+
+    assert_eq!(prefix("let a = con|").as_deref(), Some("con"));
+    assert_eq!(prefix("let a = |").as_deref(), Some(""));
+    assert_eq!(prefix("let a = {|").as_deref(), Some(""));
+    assert_eq!(prefix("// a comment\n|").as_deref(), Some(""));
+    assert_eq!(prefix("let a = b c|;").as_deref(), Some("c"));
+    assert_eq!(prefix("let con|"), None);
+    assert_eq!(prefix("let |"), None);
+    assert_eq!(prefix("// con|"), None);
+    assert_eq!(prefix("let a = \"con|\";"), None);
+    assert_eq!(prefix("let a = { 'con| str };"), None);
+    assert_eq!(prefix("let a = {} `title: con|`;"), None);
+}
@@ -0,0 +1,73 @@
+use anyhow::anyhow;
+use std::path::Path;
+
+/// The page template, with placeholders for the compiler and the initial program.
+const TEMPLATE: &str = include_str!("playground.html");
+
+/// The JavaScript bindings generated by `wasm-pack build --target web`.
+const GLUE_FILE: &str = "oal_wasm.js";
+
+/// The WebAssembly compiler generated by `wasm-pack build --target web`.
+const WASM_FILE: &str = "oal_wasm_bg.wasm";
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Encodes bytes in standard base64 with padding.
+fn base64(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let b = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(BASE64_ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+/// Quotes a string as a JavaScript literal that is safe to inline into a script element.
+fn js_string(s: &str) -> String {
+    let quoted = serde_json::to_string(s).expect("string should serialize");
+    quoted.replace("</", "<\\/")
+}
+
+/// Returns the starter program of new projects, for an empty playground.
+pub fn starter() -> &'static str {
+    crate::init::MAIN_TEMPLATE
+}
+
+/// Renders a self-contained playground page, with the given program in the editor.
+///
+/// The package directory must contain the output of `wasm-pack build oal-wasm --target web`.
+pub fn render(pkg: &Path, source: &str) -> anyhow::Result<String> {
+    let read = |name: &str| {
+        let path = pkg.join(name);
+        std::fs::read(&path).map_err(|err| anyhow!("cannot read {}: {err}", path.display()))
+    };
+    let glue = String::from_utf8(read(GLUE_FILE)?)?;
+    let wasm = read(WASM_FILE)?;
+    let page = TEMPLATE
+        .replace("{{GLUE}}", &js_string(&glue))
+        .replace("{{WASM}}", &js_string(&base64(&wasm)))
+        .replace("{{SOURCE}}", &js_string(source));
+    Ok(page)
+}
+
+#[test]
+fn test_base64() {
+    assert_eq!(base64(b""), "");
+    assert_eq!(base64(b"f"), "Zg==");
+    assert_eq!(base64(b"fo"), "Zm8=");
+    assert_eq!(base64(b"foo"), "Zm9v");
+    assert_eq!(base64(b"foobar"), "Zm9vYmFy");
+    assert_eq!(base64(&[0xff, 0xfe]), "//4=");
+}
@@ -0,0 +1,408 @@
+//! An experimental export of the data models of a program as Protocol Buffers messages,
+//! so that they can be reused in gRPC services.
+//!
+//! Each schema component holding an object, a sum or a join becomes a message,
+//! while relations are ignored. Inline objects become nested messages, sums and
+//! alternatives become `oneof` fields, and schemas without a Protobuf counterpart
+//! fall back to `google.protobuf.Value`.
+
+use oal_compiler::spec::{Object, Reference, Schema, SchemaExpr, Spec, VariadicOp};
+use oal_syntax::atom::{Ident, VariadicOperator};
+use std::fmt::Write;
+
+/// The type of schemas without a Protobuf counterpart.
+const VALUE_TYPE: &str = "google.protobuf.Value";
+
+/// The import declaring the type of schemas without a Protobuf counterpart.
+const VALUE_IMPORT: &str = "google/protobuf/struct.proto";
+
+/// The name of the field of single-field wrapper messages.
+const WRAPPER_FIELD: &str = "value";
+
+/// The name of the `oneof` of messages for a sum component.
+const ONEOF_FIELD: &str = "kind";
+
+/// A field of a message, either single or a group of alternatives.
+enum Field {
+    Single {
+        repeated: bool,
+        ty: String,
+        name: String,
+    },
+    OneOf {
+        name: String,
+        members: Vec<(String, String)>,
+    },
+}
+
+/// A message, along with the messages nested into it.
+#[derive(Default)]
+struct Message {
+    name: String,
+    fields: Vec<Field>,
+    nested: Vec<Message>,
+}
+
+impl Message {
+    fn new(name: String) -> Self {
+        Message {
+            name,
+            ..Default::default()
+        }
+    }
+
+    /// Returns true if a field or a member of a `oneof` of the message has the given name.
+    fn has_field(&self, name: &str) -> bool {
+        self.fields.iter().any(|field| match field {
+            Field::Single { name: n, .. } => n == name,
+            Field::OneOf { name: n, members } => {
+                n == name || members.iter().any(|(_, m)| m == name)
+            }
+        })
+    }
+
+    /// Returns a field name not used by the message yet, with a numeric suffix if needed.
+    fn unique_field_name(&self, name: String) -> String {
+        if !self.has_field(&name) {
+            return name;
+        }
+        (2..)
+            .map(|n| format!("{name}_{n}"))
+            .find(|n| !self.has_field(n))
+            .expect("a free name should exist")
+    }
+
+    fn render(&self, out: &mut String, depth: usize) {
+        let indent = "  ".repeat(depth);
+        writeln!(out, "{indent}message {} {{", self.name).unwrap();
+        let mut number = 1;
+        for field in self.fields.iter() {
+            match field {
+                Field::Single { repeated, ty, name } => {
+                    let label = if *repeated { "repeated " } else { "" };
+                    writeln!(out, "{indent}  {label}{ty} {name} = {number};").unwrap();
+                    number += 1;
+                }
+                Field::OneOf { name, members } => {
+                    writeln!(out, "{indent}  oneof {name} {{").unwrap();
+                    for (ty, member) in members.iter() {
+                        writeln!(out, "{indent}    {ty} {member} = {number};").unwrap();
+                        number += 1;
+                    }
+                    writeln!(out, "{indent}  }}").unwrap();
+                }
+            }
+        }
+        for nested in self.nested.iter() {
+            nested.render(out, depth + 1);
+        }
+        writeln!(out, "{indent}}}").unwrap();
+    }
+}
+
+/// The type of a field, i.e. a single value, a repeated value or alternatives.
+enum FieldType {
+    Single(String),
+    Repeated(String),
+    OneOf(Vec<Schema>),
+}
+
+/// Converts a name to PascalCase, as expected of message names.
+fn message_name(name: &str) -> String {
+    let mut s = String::new();
+    for word in name.split(|c: char| !c.is_ascii_alphanumeric()) {
+        let mut chars = word.chars();
+        if let Some(first) = chars.next() {
+            s.push(first.to_ascii_uppercase());
+            s.push_str(chars.as_str());
+        }
+    }
+    if !s.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        s.insert(0, 'M');
+    }
+    s
+}
+
+/// Converts a name to snake_case, as expected of field names.
+fn field_name(name: &str) -> String {
+    let mut s = String::new();
+    let mut prev_lower = false;
+    for c in name.chars() {
+        if c.is_ascii_uppercase() {
+            if prev_lower {
+                s.push('_');
+            }
+            s.push(c.to_ascii_lowercase());
+            prev_lower = false;
+        } else if c.is_ascii_alphanumeric() {
+            s.push(c);
+            prev_lower = true;
+        } else {
+            if !s.is_empty() && !s.ends_with('_') {
+                s.push('_');
+            }
+            prev_lower = false;
+        }
+    }
+    let s = s.trim_end_matches('_').to_owned();
+    if s.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        s
+    } else {
+        format!("f_{s}")
+    }
+}
+
+struct Exporter<'a> {
+    spec: &'a Spec,
+    /// Whether a field falls back to `google.protobuf.Value`.
+    uses_value: bool,
+    /// The references being inlined, to break recursive schemas.
+    expanding: Vec<Ident>,
+}
+
+impl<'a> Exporter<'a> {
+    fn reference(&self, ident: &Ident) -> Option<&'a Schema> {
+        match self.spec.refs.get(ident) {
+            Some(Reference::Schema(s)) => Some(s),
+            None => None,
+        }
+    }
+
+    /// Returns the schema of a reference to another reference, followed to its end.
+    fn resolve(&self, mut schema: &'a Schema) -> &'a Schema {
+        let mut depth = 0;
+        while let SchemaExpr::Ref(ident) = &schema.expr {
+            match self.reference(ident) {
+                Some(s) if depth < self.spec.refs.len() => schema = s,
+                _ => break,
+            }
+            depth += 1;
+        }
+        schema
+    }
+
+    /// Returns true if a schema component becomes a message of its own.
+    fn is_message(schema: &Schema) -> bool {
+        match &schema.expr {
+            SchemaExpr::Object(_) => true,
+            SchemaExpr::Op(op) => op.op != VariadicOperator::Range,
+            _ => false,
+        }
+    }
+
+    fn ref_message_name(&self, ident: &Ident) -> String {
+        match self.spec.component_names.get(ident) {
+            Some(renamed) => message_name(renamed),
+            None => message_name(&ident.untagged()),
+        }
+    }
+
+    fn value_type(&mut self) -> String {
+        self.uses_value = true;
+        VALUE_TYPE.to_owned()
+    }
+
+    /// Returns the type of a field, adding nested messages to the parent as needed.
+    fn field_type(&mut self, name: &str, schema: &Schema, parent: &mut Message) -> FieldType {
+        match &schema.expr {
+            SchemaExpr::Str(s) => match s.format.as_deref() {
+                Some("binary") | Some("byte") => FieldType::Single("bytes".to_owned()),
+                _ => FieldType::Single("string".to_owned()),
+            },
+            SchemaExpr::Int(_) => FieldType::Single("int64".to_owned()),
+            SchemaExpr::Num(_) => FieldType::Single("double".to_owned()),
+            SchemaExpr::Bool(_) => FieldType::Single("bool".to_owned()),
+            SchemaExpr::Uri(_) | SchemaExpr::Rel(_) => FieldType::Single("string".to_owned()),
+            SchemaExpr::Ref(ident) => match self.reference(ident) {
+                Some(s) if Self::is_message(s) => FieldType::Single(self.ref_message_name(ident)),
+                // Recursive schemas cannot be inlined.
+                Some(_) if self.expanding.contains(ident) => FieldType::Single(self.value_type()),
+                // References to other schemas are inlined.
+                Some(s) => {
+                    self.expanding.push(ident.clone());
+                    let ty = self.field_type(name, s, parent);
+                    self.expanding.pop();
+                    ty
+                }
+                None => FieldType::Single(self.value_type()),
+            },
+            SchemaExpr::Array(array) => {
+                match self.field_type(&format!("{name} item"), &array.item, parent) {
+                    FieldType::Single(ty) => FieldType::Repeated(ty),
+                    // Nested arrays and arrays of alternatives need a message per item.
+                    item => {
+                        let mut msg = Message::new(message_name(&format!("{name} item")));
+                        self.push_field(&mut msg, WRAPPER_FIELD, item);
+                        let ty = msg.name.clone();
+                        parent.nested.push(msg);
+                        FieldType::Repeated(ty)
+                    }
+                }
+            }
+            SchemaExpr::Object(obj) => {
+                let mut msg = Message::new(message_name(name));
+                self.add_props(&mut msg, obj);
+                let ty = msg.name.clone();
+                parent.nested.push(msg);
+                FieldType::Single(ty)
+            }
+            SchemaExpr::Op(op) => match op.op {
+                VariadicOperator::Sum | VariadicOperator::Any => {
+                    FieldType::OneOf(op.schemas.clone())
+                }
+                VariadicOperator::Join => match self.join_props(op) {
+                    Some(obj) => {
+                        let mut msg = Message::new(message_name(name));
+                        self.add_props(&mut msg, &obj);
+                        let ty = msg.name.clone();
+                        parent.nested.push(msg);
+                        FieldType::Single(ty)
+                    }
+                    None => FieldType::Single(self.value_type()),
+                },
+                VariadicOperator::Range => FieldType::Single(self.value_type()),
+            },
+        }
+    }
+
+    /// Adds a field of the given type to a message.
+    ///
+    /// Distinct names may have the same field name, e.g. `fooBar` and `foo_bar`,
+    /// in which case a numeric suffix is added.
+    fn push_field(&mut self, msg: &mut Message, name: &str, ty: FieldType) {
+        let name = msg.unique_field_name(field_name(name));
+        let field = match ty {
+            FieldType::Single(ty) => Field::Single {
+                repeated: false,
+                ty,
+                name,
+            },
+            FieldType::Repeated(ty) => Field::Single {
+                repeated: true,
+                ty,
+                name,
+            },
+            FieldType::OneOf(schemas) => {
+                let members = self.oneof_members(&name, &schemas, msg);
+                Field::OneOf { name, members }
+            }
+        };
+        msg.fields.push(field);
+    }
+
+    /// Returns the type and name of each alternative of a `oneof`.
+    fn oneof_members(
+        &mut self,
+        name: &str,
+        schemas: &[Schema],
+        msg: &mut Message,
+    ) -> Vec<(String, String)> {
+        let mut members: Vec<(String, String)> = Vec::new();
+        for (index, schema) in schemas.iter().enumerate() {
+            let option = format!("{name} {}", index + 1);
+            let ty = match self.field_type(&option, schema, msg) {
+                FieldType::Single(ty) => ty,
+                // Repeated fields and alternatives cannot be members of a `oneof`.
+                ty => {
+                    let mut wrapper = Message::new(message_name(&option));
+                    self.push_field(&mut wrapper, WRAPPER_FIELD, ty);
+                    let ty = wrapper.name.clone();
+                    msg.nested.push(wrapper);
+                    ty
+                }
+            };
+            let member = match &schema.expr {
+                SchemaExpr::Ref(_) => field_name(&ty),
+                _ => field_name(&option),
+            };
+            let member = if members.iter().any(|(_, m)| *m == member) {
+                field_name(&option)
+            } else {
+                member
+            };
+            members.push((ty, member));
+        }
+        members
+    }
+
+    /// Returns the properties of the objects of a join, if all of them are objects.
+    fn join_props(&self, op: &VariadicOp) -> Option<Object> {
+        let mut joined = Object::default();
+        for schema in op.schemas.iter() {
+            match &self.resolve(schema).expr {
+                SchemaExpr::Object(obj) => {
+                    for prop in obj.props.iter() {
+                        joined.props.retain(|p| p.name != prop.name);
+                        joined.props.push(prop.clone());
+                    }
+                }
+                SchemaExpr::Op(op) if op.op == VariadicOperator::Join => {
+                    for prop in self.join_props(op)?.props {
+                        joined.props.retain(|p| p.name != prop.name);
+                        joined.props.push(prop);
+                    }
+                }
+                _ => return None,
+            }
+        }
+        Some(joined)
+    }
+
+    fn add_props(&mut self, msg: &mut Message, obj: &Object) {
+        for prop in obj.props.iter() {
+            let ty = self.field_type(prop.name.as_ref(), &prop.schema, msg);
+            self.push_field(msg, prop.name.as_ref(), ty);
+        }
+    }
+
+    /// Returns the message of a schema component, if any.
+    fn component(&mut self, ident: &Ident, schema: &Schema) -> Option<Message> {
+        let mut msg = Message::new(self.ref_message_name(ident));
+        match &schema.expr {
+            SchemaExpr::Object(obj) => self.add_props(&mut msg, obj),
+            SchemaExpr::Op(op) => match op.op {
+                VariadicOperator::Sum | VariadicOperator::Any => {
+                    let ty = FieldType::OneOf(op.schemas.clone());
+                    self.push_field(&mut msg, ONEOF_FIELD, ty);
+                }
+                VariadicOperator::Join => match self.join_props(op) {
+                    Some(obj) => self.add_props(&mut msg, &obj),
+                    None => {
+                        let ty = FieldType::Single(self.value_type());
+                        self.push_field(&mut msg, WRAPPER_FIELD, ty);
+                    }
+                },
+                VariadicOperator::Range => return None,
+            },
+            _ => return None,
+        }
+        Some(msg)
+    }
+}
+
+/// Renders the schema components of a program as a Protocol Buffers file, in `proto3` syntax.
+pub fn render(spec: &Spec) -> String {
+    let mut exporter = Exporter {
+        spec,
+        uses_value: false,
+        expanding: Vec::new(),
+    };
+    let messages = spec
+        .refs
+        .iter()
+        .filter_map(|(ident, reference)| match reference {
+            Reference::Schema(schema) => exporter.component(ident, schema),
+        })
+        .collect::<Vec<_>>();
+
+    let mut out = String::new();
+    writeln!(out, "syntax = \"proto3\";").unwrap();
+    if exporter.uses_value {
+        writeln!(out, "\nimport \"{VALUE_IMPORT}\";").unwrap();
+    }
+    for msg in messages.iter() {
+        out.push('\n');
+        msg.render(&mut out, 0);
+    }
+    out
+}
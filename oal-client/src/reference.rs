@@ -0,0 +1,451 @@
+//! An API reference of the program, rendered from the evaluated specification
+//! in Markdown or static HTML without going through OpenAPI.
+//!
+//! The reference has a section per relation, listing the parameters, request and responses
+//! of each operation, followed by a section per schema component.
+
+use oal_compiler::query::{method_label, status};
+use oal_compiler::spec::{
+    Content, Object, Property, Reference, Relation, Schema, SchemaExpr, Spec, Transfer, UriSegment,
+};
+use oal_syntax::atom::{Ident, VariadicOperator};
+use std::collections::HashSet;
+use std::fmt::Write;
+use std::iter::once;
+
+/// The format of the API reference.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    Markdown,
+    Html,
+}
+
+/// The title of the reference when the program declares none.
+const DEFAULT_TITLE: &str = "API reference";
+
+/// The label of responses without a status, as in OpenAPI.
+const DEFAULT_STATUS: &str = "default";
+
+const PARAMETER_COLUMNS: &[&str] = &["Name", "In", "Type", "Required", "Description"];
+const PROPERTY_COLUMNS: &[&str] = &["Name", "Type", "Required", "Description"];
+const REQUEST_COLUMNS: &[&str] = &["Media type", "Schema", "Description"];
+const RESPONSE_COLUMNS: &[&str] = &["Status", "Media type", "Schema", "Description"];
+
+/// The kinds of headings prefixing their identifiers in HTML,
+/// so that a path, an operation and a schema of the same name link to different sections.
+const PATH_ANCHOR: &str = "path";
+const OPERATION_ANCHOR: &str = "operation";
+const SCHEMA_ANCHOR: &str = "schema";
+
+/// A block of the reference, where text may hold code spans between backticks.
+enum Block {
+    /// A heading of the given level, with the kind prefixing its identifier in HTML, if any.
+    Heading(usize, String, Option<&'static str>),
+    Paragraph(String),
+    Table(&'static [&'static str], Vec<Vec<String>>),
+}
+
+/// Returns the identifier of a heading in HTML, e.g. `get-users-id` for `GET /users/{id}`.
+fn slug(text: &str) -> String {
+    let mut s = String::new();
+    for c in text.chars() {
+        if c.is_alphanumeric() {
+            s.extend(c.to_lowercase());
+        } else if !s.is_empty() && !s.ends_with('-') {
+            s.push('-');
+        }
+    }
+    s.trim_end_matches('-').to_owned()
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Escapes text for HTML, turning code spans between backticks into `code` elements.
+fn inline_html(text: &str) -> String {
+    let escaped = escape_html(text);
+    if !escaped.matches('`').count().is_multiple_of(2) {
+        return escaped;
+    }
+    escaped
+        .split('`')
+        .enumerate()
+        .map(|(i, part)| {
+            if i % 2 == 1 {
+                format!("<code>{part}</code>")
+            } else {
+                part.to_owned()
+            }
+        })
+        .collect()
+}
+
+/// Escapes the text of a Markdown table cell.
+fn markdown_cell(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', "<br>")
+}
+
+fn render_markdown(blocks: &[Block]) -> String {
+    let mut out = String::new();
+    for block in blocks.iter() {
+        match block {
+            Block::Heading(level, text, _) => {
+                writeln!(out, "{} {text}\n", "#".repeat(*level)).unwrap();
+            }
+            Block::Paragraph(text) => writeln!(out, "{text}\n").unwrap(),
+            Block::Table(columns, rows) => {
+                writeln!(out, "| {} |", columns.join(" | ")).unwrap();
+                let rule = columns.iter().map(|_| "---").collect::<Vec<_>>();
+                writeln!(out, "| {} |", rule.join(" | ")).unwrap();
+                for row in rows.iter() {
+                    let cells = row.iter().map(|c| markdown_cell(c)).collect::<Vec<_>>();
+                    writeln!(out, "| {} |", cells.join(" | ")).unwrap();
+                }
+                out.push('\n');
+            }
+        }
+    }
+    out.truncate(out.trim_end().len());
+    out.push('\n');
+    out
+}
+
+fn render_html(title: &str, blocks: &[Block]) -> String {
+    let mut out = String::new();
+    writeln!(out, "<!DOCTYPE html>").unwrap();
+    writeln!(out, "<html>").unwrap();
+    writeln!(out, "<head>").unwrap();
+    writeln!(out, "<meta charset=\"utf-8\">").unwrap();
+    writeln!(out, "<title>{}</title>", escape_html(title)).unwrap();
+    writeln!(out, "</head>").unwrap();
+    writeln!(out, "<body>").unwrap();
+    let mut ids = HashSet::new();
+    for block in blocks.iter() {
+        match block {
+            Block::Heading(level, text, kind) => {
+                let base = match kind {
+                    Some(kind) => slug(&format!("{kind} {text}")),
+                    None => slug(text),
+                };
+                // Headings repeated across sections, e.g. `Request`, are numbered from the second.
+                let mut id = base.clone();
+                let mut n = 1;
+                while !ids.insert(id.clone()) {
+                    n += 1;
+                    id = format!("{base}-{n}");
+                }
+                writeln!(
+                    out,
+                    "<h{level} id=\"{id}\">{}</h{level}>",
+                    inline_html(text)
+                )
+                .unwrap()
+            }
+            Block::Paragraph(text) => writeln!(out, "<p>{}</p>", inline_html(text)).unwrap(),
+            Block::Table(columns, rows) => {
+                writeln!(out, "<table>").unwrap();
+                let headers = columns.iter().map(|c| format!("<th>{c}</th>"));
+                writeln!(out, "<tr>{}</tr>", headers.collect::<String>()).unwrap();
+                for row in rows.iter() {
+                    let cells = row.iter().map(|c| format!("<td>{}</td>", inline_html(c)));
+                    writeln!(out, "<tr>{}</tr>", cells.collect::<String>()).unwrap();
+                }
+                writeln!(out, "</table>").unwrap();
+            }
+        }
+    }
+    writeln!(out, "</body>").unwrap();
+    writeln!(out, "</html>").unwrap();
+    out
+}
+
+/// Returns the description of a property, or else that of its schema.
+fn property_desc(prop: &Property) -> String {
+    prop.desc
+        .as_ref()
+        .or(prop.schema.desc.as_ref())
+        .cloned()
+        .unwrap_or_default()
+}
+
+fn required(required: Option<bool>) -> String {
+    if required == Some(true) { "yes" } else { "no" }.to_owned()
+}
+
+struct Document<'a> {
+    spec: &'a Spec,
+    blocks: Vec<Block>,
+}
+
+impl<'a> Document<'a> {
+    fn component_name(&self, ident: &Ident) -> String {
+        match self.spec.component_names.get(ident) {
+            Some(renamed) => renamed.clone(),
+            None => ident.untagged(),
+        }
+    }
+
+    /// Returns a short description of the type of a schema.
+    fn type_name(&self, schema: &Schema) -> String {
+        match &schema.expr {
+            SchemaExpr::Str(s) if !s.enumeration.is_empty() => {
+                let values = s.enumeration.iter().map(|v| format!("`{v}`"));
+                format!("one of {}", values.collect::<Vec<_>>().join(", "))
+            }
+            SchemaExpr::Str(s) => match &s.format {
+                Some(format) => format!("string ({format})"),
+                None => "string".to_owned(),
+            },
+            SchemaExpr::Int(_) => "integer".to_owned(),
+            SchemaExpr::Num(_) => "number".to_owned(),
+            SchemaExpr::Bool(_) => "boolean".to_owned(),
+            SchemaExpr::Uri(_) => "URI".to_owned(),
+            SchemaExpr::Rel(rel) => format!("relation `{}`", rel.uri.pattern()),
+            SchemaExpr::Array(array) => format!("array of {}", self.type_name(&array.item)),
+            SchemaExpr::Object(_) => "object".to_owned(),
+            SchemaExpr::Ref(ident) => format!("`{}`", self.component_name(ident)),
+            SchemaExpr::Op(op) => {
+                let names = op.schemas.iter().map(|s| match s.expr {
+                    // Nested operations are parenthesized to keep their grouping.
+                    SchemaExpr::Op(_) => format!("({})", self.type_name(s)),
+                    _ => self.type_name(s),
+                });
+                let names = names.collect::<Vec<_>>();
+                match op.op {
+                    VariadicOperator::Sum => names.join(" or "),
+                    VariadicOperator::Any => format!("any of {}", names.join(", ")),
+                    VariadicOperator::Join => format!("all of {}", names.join(", ")),
+                    VariadicOperator::Range => names.join(", "),
+                }
+            }
+        }
+    }
+
+    fn property_row(&self, prop: &Property) -> Vec<String> {
+        vec![
+            format!("`{}`", prop.name),
+            self.type_name(&prop.schema),
+            required(prop.required),
+            property_desc(prop),
+        ]
+    }
+
+    fn push_properties(&mut self, intro: String, obj: &Object) {
+        if obj.props.is_empty() {
+            return;
+        }
+        let rows = obj.props.iter().map(|p| self.property_row(p)).collect();
+        self.blocks.push(Block::Paragraph(intro));
+        self.blocks.push(Block::Table(PROPERTY_COLUMNS, rows));
+    }
+
+    /// Adds the properties of an inline object schema, referenced components having their own section.
+    fn push_inline_properties(&mut self, intro: String, content: &Content) {
+        if let Some(SchemaExpr::Object(obj)) = content.schema.as_ref().map(|s| &s.expr) {
+            self.push_properties(intro, obj);
+        }
+    }
+
+    fn push_parameters(&mut self, rel: &Relation, xfer: &Transfer) {
+        let mut rows = Vec::new();
+        for s in rel.uri.path.iter() {
+            if let UriSegment::Variable(p) = s {
+                let mut row = self.property_row(p);
+                row.insert(1, "path".to_owned());
+                row[3] = required(Some(true));
+                rows.push(row);
+            }
+        }
+        let queries = rel.uri.params.iter().chain(xfer.params.iter());
+        for p in queries.flat_map(|o| o.props.iter()) {
+            let mut row = self.property_row(p);
+            row.insert(1, "query".to_owned());
+            rows.push(row);
+        }
+        // The request headers of contents in several media types are listed once.
+        for p in xfer.request_headers() {
+            let mut row = self.property_row(p);
+            row.insert(1, "header".to_owned());
+            rows.push(row);
+        }
+        if !rows.is_empty() {
+            self.blocks
+                .push(Block::Heading(4, "Parameters".to_owned(), None));
+            self.blocks.push(Block::Table(PARAMETER_COLUMNS, rows));
+        }
+    }
+
+    fn content_row(&self, content: &Content) -> Vec<String> {
+        let media = content
+            .media
+            .as_ref()
+            .map(|m| format!("`{m}`"))
+            .unwrap_or_default();
+        let schema = content
+            .schema
+            .as_ref()
+            .map(|s| self.type_name(s))
+            .unwrap_or_default();
+        vec![media, schema, content.desc.clone().unwrap_or_default()]
+    }
+
+    fn push_request(&mut self, xfer: &Transfer) {
+        let domains = xfer
+            .domains()
+            .filter(|d| d.schema.is_some())
+            .collect::<Vec<_>>();
+        if domains.is_empty() {
+            return;
+        }
+        self.blocks
+            .push(Block::Heading(4, "Request".to_owned(), None));
+        let rows = domains.iter().map(|d| self.content_row(d)).collect();
+        self.blocks.push(Block::Table(REQUEST_COLUMNS, rows));
+        self.push_inline_properties("Properties of the request body:".to_owned(), domains[0]);
+    }
+
+    fn push_responses(&mut self, xfer: &Transfer) {
+        if xfer.ranges.is_empty() {
+            return;
+        }
+        self.blocks
+            .push(Block::Heading(4, "Responses".to_owned(), None));
+        let rows = xfer
+            .ranges
+            .values()
+            .map(|c| {
+                let code = c
+                    .status
+                    .as_ref()
+                    .map_or_else(|| DEFAULT_STATUS.to_owned(), status);
+                once(code).chain(self.content_row(c)).collect()
+            })
+            .collect();
+        self.blocks.push(Block::Table(RESPONSE_COLUMNS, rows));
+        for c in xfer.ranges.values() {
+            let name = match &c.status {
+                Some(s) => format!("the {} response", status(s)),
+                None => "the default response".to_owned(),
+            };
+            self.push_inline_properties(format!("Properties of {name}:"), c);
+            if let Some(headers) = &c.headers {
+                self.push_properties(format!("Headers of {name}:"), headers);
+            }
+        }
+    }
+
+    fn push_transfer(&mut self, rel: &Relation, method: &str, xfer: &Transfer) {
+        let path = rel.uri.pattern();
+        let method = xfer.verb.as_deref().unwrap_or(method).to_uppercase();
+        self.blocks.push(Block::Heading(
+            3,
+            format!("{method} {path}"),
+            Some(OPERATION_ANCHOR),
+        ));
+        if let Some(summary) = &xfer.summary {
+            self.blocks.push(Block::Paragraph(summary.clone()));
+        }
+        if let Some(desc) = &xfer.desc {
+            self.blocks.push(Block::Paragraph(desc.clone()));
+        }
+        if xfer.deprecated == Some(true) {
+            self.blocks
+                .push(Block::Paragraph("This operation is deprecated.".to_owned()));
+        }
+        if let Some(id) = &xfer.id {
+            self.blocks
+                .push(Block::Paragraph(format!("Operation id: `{id}`")));
+        }
+        if !xfer.tags.is_empty() {
+            self.blocks
+                .push(Block::Paragraph(format!("Tags: {}", xfer.tags.join(", "))));
+        }
+        self.push_parameters(rel, xfer);
+        self.push_request(xfer);
+        self.push_responses(xfer);
+    }
+
+    fn push_relation(&mut self, rel: &Relation) {
+        self.blocks
+            .push(Block::Heading(2, rel.uri.pattern(), Some(PATH_ANCHOR)));
+        if let Some(name) = &rel.webhook {
+            self.blocks
+                .push(Block::Paragraph(format!("Webhook `{name}`.")));
+        }
+        if let Some(name) = &rel.callback {
+            self.blocks
+                .push(Block::Paragraph(format!("Callback `{name}`.")));
+        }
+        for (method, xfer) in rel.xfers.iter() {
+            if let Some(xfer) = xfer {
+                self.push_transfer(rel, method_label(method), xfer);
+            }
+        }
+        for (method, xfer) in rel.custom_xfers.iter() {
+            self.push_transfer(rel, method, xfer);
+        }
+    }
+
+    fn push_schema(&mut self, ident: &Ident, schema: &Schema) {
+        self.blocks.push(Block::Heading(
+            3,
+            self.component_name(ident),
+            Some(SCHEMA_ANCHOR),
+        ));
+        if let Some(title) = &schema.title {
+            self.blocks.push(Block::Paragraph(title.clone()));
+        }
+        if let Some(desc) = &schema.desc {
+            self.blocks.push(Block::Paragraph(desc.clone()));
+        }
+        if schema.deprecated == Some(true) {
+            self.blocks
+                .push(Block::Paragraph("This schema is deprecated.".to_owned()));
+        }
+        match &schema.expr {
+            SchemaExpr::Object(obj) if !obj.props.is_empty() => {
+                let rows = obj.props.iter().map(|p| self.property_row(p)).collect();
+                self.blocks.push(Block::Table(PROPERTY_COLUMNS, rows));
+            }
+            _ => {
+                let ty = self.type_name(schema);
+                self.blocks.push(Block::Paragraph(format!("Type: {ty}")));
+            }
+        }
+    }
+}
+
+/// Renders the API reference of a program in the given format.
+pub fn render(spec: &Spec, format: Format) -> String {
+    let mut doc = Document {
+        spec,
+        blocks: Vec::new(),
+    };
+    let title = spec.info.title.as_deref().unwrap_or(DEFAULT_TITLE);
+    doc.blocks.push(Block::Heading(1, title.to_owned(), None));
+    if let Some(version) = &spec.info.version {
+        doc.blocks
+            .push(Block::Paragraph(format!("Version {version}")));
+    }
+    if let Some(desc) = &spec.info.desc {
+        doc.blocks.push(Block::Paragraph(desc.clone()));
+    }
+    for rel in spec.rels.iter() {
+        doc.push_relation(rel);
+    }
+    if !spec.refs.is_empty() {
+        doc.blocks
+            .push(Block::Heading(2, "Schemas".to_owned(), None));
+        for (ident, Reference::Schema(schema)) in spec.refs.iter() {
+            doc.push_schema(ident, schema);
+        }
+    }
+    match format {
+        Format::Markdown => render_markdown(&doc.blocks),
+        Format::Html => render_html(title, &doc.blocks),
+    }
+}
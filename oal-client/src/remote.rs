@@ -0,0 +1,200 @@
+//! Remote modules imported over HTTPS, e.g. `use "https://example.com/models/common.oal";`.
+//!
+//! The content hash of each remote module is recorded in a lockfile on first fetch,
+//! and the module is kept in a local cache, so that later builds are reproducible
+//! and work offline. A fetched module not matching its recorded hash is an error.
+
+use crate::checksum::sha256;
+use crate::{DefaultFileSystem, Error, FileSystem};
+use oal_model::locator::Locator;
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use url::Url;
+
+/// The name of the lockfile, next to the configuration file.
+pub const LOCKFILE: &str = "oal.lock";
+
+/// The directory of the cached remote modules, next to the configuration file.
+pub const CACHE_DIR: &str = ".oal/modules";
+
+/// The prefix of content hashes in the lockfile.
+const HASH_PREFIX: &str = "sha256:";
+
+/// The maximum duration of a fetch.
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The content hashes of the remote modules, by URL.
+#[derive(Serialize, Deserialize, Default, Debug, PartialEq)]
+pub struct Lockfile {
+    #[serde(default)]
+    pub modules: BTreeMap<String, String>,
+}
+
+impl Lockfile {
+    fn read(path: &Path) -> Result<Self, Error> {
+        if !path.exists() {
+            return Ok(Lockfile::default());
+        }
+        let text = std::fs::read_to_string(path)?;
+        toml::from_str(&text).map_err(|err| Error::Remote(format!("invalid lockfile: {err}")))
+    }
+
+    fn write(&self, path: &Path) -> Result<(), Error> {
+        let text = toml::to_string(self).expect("lockfile should serialize");
+        std::fs::write(path, text)?;
+        Ok(())
+    }
+}
+
+/// Fetches the source of a remote module.
+pub trait Fetcher {
+    fn fetch(&self, url: &Url) -> Result<String, Error>;
+}
+
+/// Fetches remote modules over HTTPS.
+pub struct HttpsFetcher;
+
+impl Fetcher for HttpsFetcher {
+    fn fetch(&self, url: &Url) -> Result<String, Error> {
+        let response = ureq::get(url.as_str())
+            .timeout(TIMEOUT)
+            .call()
+            .map_err(|err| Error::Remote(format!("cannot fetch {url}: {err}")))?;
+        Ok(response.into_string()?)
+    }
+}
+
+/// The local file system, along with the remote modules imported over HTTPS.
+pub struct RemoteFileSystem {
+    fetcher: Box<dyn Fetcher>,
+    lockfile: PathBuf,
+    cache: PathBuf,
+    offline: bool,
+    /// The lockfile, once read.
+    lock: RefCell<Option<Lockfile>>,
+}
+
+impl RemoteFileSystem {
+    /// Creates a file system keeping the lockfile and the cache in the given directory.
+    pub fn new(dir: &Path) -> Self {
+        RemoteFileSystem {
+            fetcher: Box::new(HttpsFetcher),
+            lockfile: dir.join(LOCKFILE),
+            cache: dir.join(CACHE_DIR),
+            offline: false,
+            lock: RefCell::new(None),
+        }
+    }
+
+    /// Replaces the fetcher of remote modules.
+    pub fn with_fetcher<F: Fetcher + 'static>(mut self, fetcher: F) -> Self {
+        self.fetcher = Box::new(fetcher);
+        self
+    }
+
+    /// Only reads remote modules from the cache, without fetching them.
+    pub fn with_offline(mut self, offline: bool) -> Self {
+        self.offline = offline;
+        self
+    }
+
+    fn is_remote(loc: &Locator) -> bool {
+        loc.url().scheme() == "https"
+    }
+
+    /// Returns the path of a cached module by content hash.
+    fn cached(&self, hash: &str) -> PathBuf {
+        self.cache.join(format!("{hash}.oal"))
+    }
+
+    /// Reads a remote module from the cache, or else fetches it and records its hash.
+    fn read_remote(&self, loc: &Locator) -> Result<String, Error> {
+        let url = loc.url();
+        let mut lock = self.lock.borrow_mut();
+        let lock = match lock.as_mut() {
+            Some(lock) => lock,
+            None => lock.insert(Lockfile::read(&self.lockfile)?),
+        };
+
+        let locked = lock
+            .modules
+            .get(url.as_str())
+            .map(|h| h.trim_start_matches(HASH_PREFIX).to_owned());
+        if let Some(ref hash) = locked {
+            let path = self.cached(hash);
+            if let Ok(text) = std::fs::read_to_string(&path) {
+                if sha256(text.as_bytes()) == *hash {
+                    return Ok(text);
+                }
+            }
+        }
+
+        if self.offline {
+            return Err(Error::Remote(format!(
+                "{url} is not in the cache and fetching is disabled"
+            )));
+        }
+        let text = self.fetcher.fetch(url)?;
+        let hash = sha256(text.as_bytes());
+        match locked {
+            Some(locked) if locked != hash => {
+                return Err(Error::Remote(format!(
+                    "{url} does not match the hash of the lockfile, \
+                    remove its entry from {LOCKFILE} to accept the change"
+                )));
+            }
+            Some(_) => {}
+            None => {
+                lock.modules
+                    .insert(url.to_string(), format!("{HASH_PREFIX}{hash}"));
+                lock.write(&self.lockfile)?;
+            }
+        }
+        std::fs::create_dir_all(&self.cache)?;
+        std::fs::write(self.cached(&hash), &text)?;
+        Ok(text)
+    }
+}
+
+impl FileSystem for RemoteFileSystem {
+    fn is_valid(&self, loc: &Locator) -> bool {
+        Self::is_remote(loc) || DefaultFileSystem.is_valid(loc)
+    }
+
+    fn open_file(&self, loc: &Locator) -> Result<Box<dyn io::Read>, Error> {
+        if Self::is_remote(loc) {
+            let text = self.read_remote(loc)?;
+            Ok(Box::new(io::Cursor::new(text)))
+        } else {
+            DefaultFileSystem.open_file(loc)
+        }
+    }
+
+    fn read_file(&self, loc: &Locator) -> Result<String, Error> {
+        if Self::is_remote(loc) {
+            self.read_remote(loc)
+        } else {
+            DefaultFileSystem.read_file(loc)
+        }
+    }
+
+    fn write_file(&self, loc: &Locator, buf: String) -> Result<(), Error> {
+        if Self::is_remote(loc) {
+            Err(Error::InvalidPath(loc.url().as_str().to_owned()))
+        } else {
+            DefaultFileSystem.write_file(loc, buf)
+        }
+    }
+
+    fn canonicalize(&self, loc: &Locator) -> Result<Locator, Error> {
+        if Self::is_remote(loc) {
+            Ok(loc.clone())
+        } else {
+            DefaultFileSystem.canonicalize(loc)
+        }
+    }
+}
@@ -0,0 +1,166 @@
+//! An interactive session evaluating expressions in the context of a program.
+//!
+//! Each expression is evaluated as a declaration appended to the main module,
+//! along with the declarations previously entered in the session, e.g.:
+//! ```text
+//! > let f x = x & { 'id int };
+//! > f {} | str
+//! object
+//! oneOf:
+//! ...
+//! ```
+
+use crate::resolver::Resolver;
+use crate::{FileSystem, FileSystemLoader};
+use oal_compiler::eval::Inspection;
+use oal_compiler::module::{Loader, ModuleSet};
+use oal_compiler::tree::Tree;
+use oal_model::grammar::AbstractSyntaxNode;
+use oal_model::locator::Locator;
+use oal_syntax::atom;
+use oal_syntax::parser as syn;
+use serde::Serialize;
+
+/// The name of the declaration holding the expression under evaluation.
+const BINDING: &str = "_";
+
+/// The keywords starting the statements that are added to the session.
+const STATEMENTS: &[&str] = &["let", "use", "res"];
+
+/// A loader reading the main module from the session rather than from the file system.
+struct SessionLoader<'a, F: FileSystem> {
+    fs: FileSystemLoader<'a, F>,
+    resolver: &'a Resolver,
+    main: &'a Locator,
+    text: String,
+}
+
+impl<F: FileSystem> Loader<anyhow::Error> for SessionLoader<'_, F> {
+    fn is_valid(&mut self, loc: &Locator) -> bool {
+        loc == self.main || self.fs.is_valid(loc)
+    }
+
+    fn load(&mut self, loc: &Locator) -> anyhow::Result<String> {
+        if loc == self.main {
+            Ok(self.text.clone())
+        } else {
+            self.fs.load(loc)
+        }
+    }
+
+    fn parse(&mut self, loc: Locator, input: String) -> anyhow::Result<Tree> {
+        self.fs.parse(loc, input)
+    }
+
+    fn compile(&mut self, mods: &ModuleSet, loc: &Locator) -> anyhow::Result<()> {
+        self.fs.compile(mods, loc)
+    }
+
+    fn resolve(&mut self, loc: &Locator, import: &str) -> oal_compiler::errors::Result<Locator> {
+        self.resolver.resolve(loc, import)
+    }
+
+    fn canonicalize(&mut self, loc: &Locator) -> Locator {
+        if loc == self.main {
+            loc.clone()
+        } else {
+            self.fs.canonicalize(loc)
+        }
+    }
+}
+
+/// An interactive session over a program.
+pub struct Session<F: FileSystem> {
+    fs: F,
+    main: Locator,
+    resolver: Resolver,
+    /// The statements entered in the session so far.
+    statements: String,
+}
+
+fn to_yaml<T: Serialize>(value: &T) -> anyhow::Result<String> {
+    Ok(serde_yaml::to_string(value)?)
+}
+
+impl<F: FileSystem> Session<F> {
+    /// Starts a session over the program of the given main module, if it exists.
+    pub fn new(fs: F, main: Locator) -> Self {
+        Session {
+            fs,
+            main,
+            resolver: Resolver::default(),
+            statements: String::new(),
+        }
+    }
+
+    /// Resolves imports through the aliases of module paths.
+    pub fn with_resolver(mut self, resolver: Resolver) -> Self {
+        self.resolver = resolver;
+        self
+    }
+
+    /// Loads the program extended with the given statements.
+    fn load(&self, statements: &str) -> anyhow::Result<ModuleSet> {
+        let mut text = if self.fs.is_valid(&self.main) {
+            self.fs.read_file(&self.main)?
+        } else {
+            String::new()
+        };
+        text.push('\n');
+        text.push_str(statements);
+        let mut loader = SessionLoader {
+            fs: FileSystemLoader(&self.fs),
+            resolver: &self.resolver,
+            main: &self.main,
+            text,
+        };
+        oal_compiler::module::load(&mut loader, &self.main)
+    }
+
+    /// Evaluates an input line, returning the text to print.
+    ///
+    /// Statements are added to the session. Expressions are printed with their inferred type,
+    /// followed by their OpenAPI representation if they have one.
+    pub fn eval(&mut self, input: &str) -> anyhow::Result<String> {
+        let input = input.trim().trim_end_matches(';').trim_end();
+        if input.is_empty() {
+            return Ok(String::new());
+        }
+
+        let keyword = input.split_whitespace().next().unwrap_or_default();
+        if STATEMENTS.contains(&keyword) {
+            let statements = format!("{}{input};\n", self.statements);
+            self.load(&statements)?;
+            self.statements = statements;
+            return Ok(String::new());
+        }
+
+        let statements = format!("{}let {BINDING} = {input};\n", self.statements);
+        let mods = self.load(&statements)?;
+        let ident = atom::Ident::from(BINDING);
+        let program = syn::Program::cast(mods.main().root()).expect("expected a program");
+        let decl = program
+            .declarations()
+            .find(|d| d.ident() == ident)
+            .expect("evaluated declaration should exist");
+        let tag = oal_compiler::tree::get_tag(decl.rhs()).to_string();
+
+        let (spec, inspection) = oal_compiler::eval::eval_inspection(&mods, &ident)?;
+        let builder = oal_openapi::Builder::new(spec);
+        let repr = match inspection {
+            Some(Inspection::Schema(s)) => Some(to_yaml(&builder.schema_object(&s)?)?),
+            Some(Inspection::Relation(r)) => Some(to_yaml(&builder.path_item(&r)?)?),
+            Some(Inspection::Transfer(x)) => Some(to_yaml(&builder.operation(&x)?)?),
+            Some(Inspection::Content(c)) => match c.schema {
+                Some(s) => Some(to_yaml(&builder.schema_object(&s)?)?),
+                None => None,
+            },
+            Some(Inspection::Property(p)) => Some(to_yaml(&builder.schema_object(&p.schema)?)?),
+            Some(Inspection::Other) | None => None,
+        };
+        Ok(match repr {
+            Some(repr) => format!("{tag}\n{repr}"),
+            None => format!("{tag}\n"),
+        })
+    }
+}
@@ -0,0 +1,71 @@
+//! Resolves imports through the module aliases of the configuration, e.g. `use "shared/pagination";`
+//! with `paths = { "shared" = "./lib" }` imports `./lib/pagination.oal` from any module.
+//!
+//! Imports not starting with an alias are resolved relative to the importing module,
+//! or to the library of standard modules.
+
+use oal_compiler::errors::{Error, Kind, Result};
+use oal_compiler::library;
+use oal_compiler::module::{check_import, import_locator};
+use oal_model::locator::{self, Locator};
+use std::collections::HashMap;
+
+/// The extension of module files, added to aliased imports without one.
+const EXTENSION: &str = ".oal";
+
+/// The aliases of module paths, longest first so that nested aliases take precedence.
+#[derive(Clone, Debug, Default)]
+pub struct Resolver {
+    aliases: Vec<(String, Locator)>,
+}
+
+impl Resolver {
+    /// Creates a resolver from aliases to the locators of directories or modules.
+    ///
+    /// Aliases cannot shadow the standard modules, e.g. `std`.
+    pub fn new(paths: &HashMap<String, String>) -> Result<Self> {
+        let mut aliases = paths
+            .iter()
+            .map(|(alias, path)| {
+                if library::is_reserved(alias) {
+                    let msg = format!("alias {alias} collides with the standard modules");
+                    return Err(Error::new(Kind::ModuleCollision, msg));
+                }
+                let alias = alias.trim_end_matches('/').to_owned();
+                let target = Locator::try_from(path.as_str()).map_err(locator::Error::from)?;
+                Ok((alias, target))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        aliases.sort_by(|(a, _), (b, _)| b.len().cmp(&a.len()).then_with(|| a.cmp(b)));
+        Ok(Resolver { aliases })
+    }
+
+    /// Returns the locator of a module imported from the given module.
+    pub fn resolve(&self, loc: &Locator, import: &str) -> Result<Locator> {
+        for (alias, target) in self.aliases.iter() {
+            let Some(rest) = import.strip_prefix(alias.as_str()) else {
+                continue;
+            };
+            if rest.is_empty() {
+                check_import(loc, target)?;
+                return Ok(target.clone());
+            }
+            if let Some(rest) = rest.strip_prefix('/') {
+                let has_extension = rest.rsplit('/').next().is_some_and(|n| n.contains('.'));
+                let target = if target.url().path().ends_with('/') {
+                    target.clone()
+                } else {
+                    target.as_base()
+                };
+                let target = if has_extension {
+                    target.join(rest)?
+                } else {
+                    target.join(&format!("{rest}{EXTENSION}"))?
+                };
+                check_import(loc, &target)?;
+                return Ok(target);
+            }
+        }
+        import_locator(loc, import)
+    }
+}
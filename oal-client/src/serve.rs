@@ -0,0 +1,391 @@
+//! A compiler service over HTTP, for integrations that cannot link to the compiler.
+//!
+//! `POST /compile` accepts a JSON request with either a single program:
+//! ```json
+//! { "source": "res /a on get -> {};" }
+//! ```
+//! or an archive of modules by relative path, along with the path of the main module:
+//! ```json
+//! { "main": "main.oal", "modules": { "main.oal": "...", "lib/common.oal": "..." } }
+//! ```
+//! Both accept optional `settings`, of lower precedence than those of the service.
+//! The response holds the OpenAPI description, if the program compiles, and the diagnostics:
+//! ```json
+//! { "api": {}, "diagnostics": [{ "severity": "warning", "code": "E0015", "category": "type",
+//!   "message": "...", "module": "main.oal", "line": 1, "column": 5 }] }
+//! ```
+//! Modules are only ever read from the request, never from the file system of the service.
+//!
+//! In safe mode, the service caps the size of modules, the number of modules, the number of
+//! evaluation steps and the size of the description, whatever the settings of the request,
+//! and refuses to synthesize sample examples.
+
+use crate::config::{LintLevel, Settings};
+use crate::fixture::{Position, Severity};
+use crate::{DiagnosticLoader, FileSystem, InMemoryFileSystem};
+use anyhow::anyhow;
+use log::{debug, info, warn};
+use oal_compiler::errors::{Error, Kind};
+use oal_compiler::eval::EvalLimits;
+use oal_compiler::module::LoadLimits;
+use oal_model::locator::Locator;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::panic::AssertUnwindSafe;
+use std::sync::mpsc::{sync_channel, Receiver};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// The path of the main module of single program requests.
+const DEFAULT_MAIN: &str = "main.oal";
+
+/// The maximum size of a request body in bytes.
+const MAX_BODY_SIZE: u64 = 8 * 1024 * 1024;
+
+/// The maximum size of the request line and headers in bytes.
+const MAX_HEAD_SIZE: u64 = 16 * 1024;
+
+/// The maximum size of a generated description in bytes, in safe mode.
+const MAX_SAFE_OUTPUT_SIZE: usize = 4 * 1024 * 1024;
+
+/// The time allowed to receive a request or send a response.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The stack size of the request handlers, deep enough for the nesting limits.
+const STACK_SIZE: usize = 64 * 1024 * 1024;
+
+/// The maximum number of accepted connections waiting for a request handler.
+const QUEUE_SIZE: usize = 64;
+
+/// A compilation request.
+#[derive(Deserialize, Default, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct Request {
+    /// The source of a single program.
+    pub source: Option<String>,
+    /// The path of the main module in the archive.
+    pub main: Option<String>,
+    /// The archive of modules by relative path.
+    pub modules: BTreeMap<String, String>,
+    /// The compilation and generation settings.
+    pub settings: Settings,
+}
+
+/// A response with its HTTP status code.
+#[derive(Debug, PartialEq)]
+pub struct Response {
+    pub status: u16,
+    pub body: Value,
+}
+
+impl Response {
+    fn error(status: u16, msg: impl ToString) -> Self {
+        Response {
+            status,
+            body: json!({ "error": msg.to_string() }),
+        }
+    }
+
+    fn reason(&self) -> &'static str {
+        match self.status {
+            200 => "OK",
+            400 => "Bad Request",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            411 => "Length Required",
+            413 => "Content Too Large",
+            422 => "Unprocessable Content",
+            _ => "Internal Server Error",
+        }
+    }
+}
+
+/// Collects the diagnostics of a program, stopping at the first error.
+struct Collector<'a> {
+    fs: &'a InMemoryFileSystem,
+    settings: &'a Settings,
+    root: Locator,
+    diagnostics: Vec<Value>,
+    failed: bool,
+}
+
+impl Collector<'_> {
+    fn push(&mut self, severity: Severity, err: &Error) {
+        let mut diag = json!({
+            "severity": severity.to_string(),
+            "code": err.code(),
+            "category": err.category().as_str(),
+            "message": err.to_string(),
+        });
+        if let Some(span) = err.span() {
+            let loc = span.locator();
+            diag["module"] = match loc.relative_to(&self.root) {
+                Some(rel) if loc.url().scheme() == self.root.url().scheme() => rel.into(),
+                _ => loc.to_string().into(),
+            };
+            let text = oal_compiler::library::source(loc)
+                .map(ToOwned::to_owned)
+                .or_else(|| self.fs.read_file(loc).ok());
+            if let Some(text) = text {
+                let pos = Position::from_index(&text, span.start());
+                diag["line"] = pos.line.into();
+                diag["column"] = pos.column.into();
+            }
+        }
+        if severity == Severity::Error {
+            self.failed = true;
+        }
+        self.diagnostics.push(diag);
+    }
+
+    fn warn(&mut self, warnings: Vec<Error>) {
+        for err in warnings {
+            match self.settings.lint_level(&err) {
+                LintLevel::Allow => {}
+                LintLevel::Warn => self.push(Severity::Warning, &err),
+                LintLevel::Deny => self.push(Severity::Error, &err),
+            }
+        }
+    }
+}
+
+/// Compiles the program of a request, with the settings of the service.
+pub fn compile(req: Request, settings: &Settings) -> Response {
+    let mut settings = settings.clone().merge(req.settings);
+    let (main, modules) = match (req.source, req.main) {
+        (Some(source), None) if req.modules.is_empty() => (
+            DEFAULT_MAIN.to_owned(),
+            BTreeMap::from([(DEFAULT_MAIN.to_owned(), source)]),
+        ),
+        (None, Some(main)) => (main, req.modules),
+        _ => return Response::error(400, "expected either a source or a main module"),
+    };
+
+    let root = Locator::try_from("file:///").expect("root should be a valid locator");
+    let mut fs = InMemoryFileSystem::new();
+    for (path, code) in modules {
+        if let Err(err) = root.join(&path) {
+            return Response::error(400, format!("invalid module path {path}: {err}"));
+        }
+        fs = fs.with_file(&path, code);
+    }
+    let main = match root.join(&main) {
+        Ok(loc) if fs.is_valid(&loc) => loc,
+        _ => return Response::error(400, format!("main module {main} not found")),
+    };
+    // Aliases are relative to the root of the request modules.
+    for path in settings.paths.values_mut() {
+        match root.join(path) {
+            Ok(loc) => *path = loc.to_string(),
+            Err(err) => return Response::error(400, format!("invalid alias path {path}: {err}")),
+        }
+    }
+    let resolver = match settings.resolver() {
+        Ok(resolver) => resolver,
+        Err(err) => return Response::error(400, err),
+    };
+
+    let mut collector = Collector {
+        fs: &fs,
+        settings: &settings,
+        root,
+        diagnostics: Vec::new(),
+        failed: false,
+    };
+    let safe = settings.safe_mode.unwrap_or(false);
+    let (limits, eval_limits) = if safe {
+        let limits = settings.load_limits(LoadLimits::UNTRUSTED);
+        (
+            limits.stricter(&LoadLimits::UNTRUSTED),
+            EvalLimits::UNTRUSTED,
+        )
+    } else {
+        (
+            settings.load_limits(LoadLimits::INTERACTIVE),
+            EvalLimits::default(),
+        )
+    };
+    let envelope = match settings.envelope() {
+        Ok(e) => e,
+        Err(err) => return Response::error(400, err),
+    };
+    if safe && settings.sample_examples == Some(true) {
+        return Response::error(400, "sample examples are not available in safe mode");
+    }
+    // Invalid generation settings are rejected before compiling.
+    if let Err(err) = settings.configure(oal_openapi::Builder::new(Default::default())) {
+        return Response::error(400, err);
+    }
+    let mut api = None;
+    let mut loader =
+        DiagnosticLoader::new(&fs, settings.strict.unwrap_or(false)).with_resolver(resolver);
+    let result = oal_compiler::module::load_with_limits(&mut loader, &main, &limits);
+    collector.warn(loader.take_warnings());
+    match result {
+        Err(err) => collector.push(Severity::Error, &err),
+        Ok(mods) => match oal_compiler::eval::eval_with_limits(&mods, &eval_limits) {
+            Err(err) => collector.push(Severity::Error, &err),
+            Ok((mut spec, warnings)) => {
+                collector.warn(warnings);
+                let warnings = oal_compiler::limits::check_limits(&mods, &spec, &settings.limits());
+                collector.warn(warnings);
+                if let Some(envelope) = envelope {
+                    oal_compiler::envelope::apply(&mut spec, envelope);
+                }
+                let mut builder = oal_openapi::Builder::new(spec);
+                if safe {
+                    builder = builder.with_max_schemas(oal_openapi::MAX_UNTRUSTED_SCHEMAS);
+                }
+                match settings.configure(builder) {
+                    Err(err) => return Response::error(400, err),
+                    Ok(builder) => match builder.into_document() {
+                        Ok(doc) => api = Some(doc),
+                        Err(err) => collector.push(Severity::Error, &err),
+                    },
+                }
+            }
+        },
+    }
+
+    if let Some(doc) = api.as_ref().filter(|_| safe) {
+        let size = serde_json::to_vec(doc).map_or(usize::MAX, |v| v.len());
+        if size > MAX_SAFE_OUTPUT_SIZE {
+            let msg = format!(
+                "the description has {size} bytes, more than the limit of {MAX_SAFE_OUTPUT_SIZE} bytes"
+            );
+            collector.push(Severity::Error, &Error::new(Kind::ResourceExceeded, msg));
+        }
+    }
+
+    let status = if collector.failed { 422 } else { 200 };
+    let api = api.filter(|_| !collector.failed);
+    Response {
+        status,
+        body: json!({ "api": api, "diagnostics": collector.diagnostics }),
+    }
+}
+
+/// Reads an HTTP request and returns the response to it.
+pub fn respond<R: BufRead>(input: &mut R, settings: &Settings) -> Response {
+    let mut head = input.by_ref().take(MAX_HEAD_SIZE);
+    let mut line = String::new();
+    if head.read_line(&mut line).is_err() || !line.ends_with('\n') {
+        return Response::error(400, "invalid request line");
+    }
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(path)) = (parts.next(), parts.next()) else {
+        return Response::error(400, "invalid request line");
+    };
+    let (method, path) = (method.to_owned(), path.to_owned());
+
+    let mut length = None;
+    loop {
+        line.clear();
+        if head.read_line(&mut line).is_err() || !line.ends_with('\n') {
+            return Response::error(400, "invalid request headers");
+        }
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                match value.trim().parse::<u64>() {
+                    Ok(n) => length = Some(n),
+                    Err(_) => return Response::error(400, "invalid content length"),
+                }
+            }
+        }
+    }
+
+    if path != "/compile" {
+        return Response::error(404, format!("no such endpoint {path}"));
+    }
+    if method != "POST" {
+        return Response::error(405, "expected a POST request");
+    }
+    let Some(length) = length else {
+        return Response::error(411, "expected a content length");
+    };
+    if length > MAX_BODY_SIZE {
+        return Response::error(413, format!("request exceeds {MAX_BODY_SIZE} bytes"));
+    }
+
+    let mut body = Vec::new();
+    if input.by_ref().take(length).read_to_end(&mut body).is_err() || body.len() as u64 != length {
+        return Response::error(400, "incomplete request body");
+    }
+    match serde_json::from_slice::<Request>(&body) {
+        // A panic must not take the service down, whatever the source.
+        Ok(req) => std::panic::catch_unwind(AssertUnwindSafe(|| compile(req, settings)))
+            .unwrap_or_else(|_| Response::error(500, "internal compiler error")),
+        Err(err) => Response::error(400, format!("invalid request: {err}")),
+    }
+}
+
+fn handle(stream: TcpStream, settings: &Settings) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+    let res = respond(&mut BufReader::new(&stream), settings);
+    debug!("responding with status {}", res.status);
+    let body = serde_json::to_vec(&res.body).expect("response should serialize");
+    let mut out = &stream;
+    write!(
+        out,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        res.status,
+        res.reason(),
+        body.len()
+    )?;
+    out.write_all(&body)?;
+    out.flush()
+}
+
+/// Handles the connections received from the queue until it is closed.
+fn work(queue: &Mutex<Receiver<TcpStream>>, settings: &Settings) {
+    loop {
+        let stream = match queue.lock() {
+            Ok(receiver) => receiver.recv(),
+            Err(_) => return,
+        };
+        let Ok(stream) = stream else {
+            return;
+        };
+        if let Err(err) = handle(stream, settings) {
+            warn!("cannot handle request: {err}");
+        }
+    }
+}
+
+/// Serves compilation requests at the given address until the process terminates.
+///
+/// Connections are handled by a fixed pool of threads, one per available core,
+/// and wait in a bounded queue when all of them are busy.
+pub fn serve<A: ToSocketAddrs>(addr: A, settings: Settings) -> anyhow::Result<()> {
+    let listener = TcpListener::bind(addr)?;
+    info!("Serving compilation requests on {}", listener.local_addr()?);
+    let workers = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let (sender, receiver) = sync_channel(QUEUE_SIZE);
+    let queue = Arc::new(Mutex::new(receiver));
+    for _ in 0..workers {
+        let queue = Arc::clone(&queue);
+        let settings = settings.clone();
+        std::thread::Builder::new()
+            .stack_size(STACK_SIZE)
+            .spawn(move || work(&queue, &settings))?;
+    }
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                if sender.send(stream).is_err() {
+                    return Err(anyhow!("no request handler left"));
+                }
+            }
+            Err(err) => warn!("cannot accept connection: {err}"),
+        }
+    }
+    Ok(())
+}
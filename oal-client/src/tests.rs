@@ -0,0 +1,2640 @@
+use crate::annotations;
+use crate::checksum;
+use crate::config::{Build, Config, Format as TargetFormat, Settings};
+use crate::docs;
+use crate::fix;
+use crate::fixture::{self, Position, Severity};
+use crate::proto;
+use crate::reference::{self, Format};
+use crate::repl::Session;
+use crate::serve;
+use crate::{FileSystem, FileSystemLoader, InMemoryFileSystem};
+use oal_compiler::spec::{Reference, Schema, Spec};
+use oal_syntax::atom::Ident;
+use std::collections::HashMap;
+
+/// Compiles a program made of a single main module.
+fn compile(code: &str) -> anyhow::Result<Spec> {
+    let fs = InMemoryFileSystem::new().with_file("main.oal", code);
+    let main = InMemoryFileSystem::locator("main.oal");
+    let mods = oal_compiler::module::load(&mut FileSystemLoader(&fs), &main)?;
+    Ok(oal_compiler::eval::eval(&mods)?)
+}
+
+/// Returns the schema of a reference of a specification.
+fn schema(spec: &Spec, ident: &str) -> Schema {
+    match spec.refs.get(&Ident::from(ident)) {
+        Some(Reference::Schema(s)) => s.clone(),
+        None => panic!("expected a schema {ident}"),
+    }
+}
+
+#[test]
+fn in_memory_compile() -> anyhow::Result<()> {
+    let fs = InMemoryFileSystem::new()
+        .with_file("main.oal", r#"use "lib/module.oal" as m; res m.r;"#)
+        .with_file("lib/module.oal", "let r = /a on get -> {};");
+
+    let main = InMemoryFileSystem::locator("main.oal");
+    assert_eq!(main.url().as_str(), "file:///main.oal");
+
+    let mods = oal_compiler::module::load(&mut FileSystemLoader(&fs), &main)?;
+    assert_eq!(mods.len(), 2);
+
+    let spec = oal_compiler::eval::eval(&mods)?;
+    assert_eq!(spec.rels.len(), 1);
+
+    let target = InMemoryFileSystem::locator("openapi.yaml");
+    fs.write_file(&target, "openapi: 3.0.3".to_owned())?;
+    assert_eq!(fs.read_file(&target)?, "openapi: 3.0.3");
+
+    let missing = InMemoryFileSystem::locator("missing.oal");
+    assert!(!fs.is_valid(&missing));
+    assert!(fs.read_file(&missing).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn uri_example_from_parameter() -> anyhow::Result<()> {
+    let code = r#"
+        let r = /a/{ 'id int `example: 42` }/{ 'x str };
+        res r on get -> { 'self r };
+    "#;
+    let spec = compile(code)?;
+
+    let example = |builder: oal_openapi::Builder| {
+        let yaml = serde_yaml::to_string(&builder.into_openapi().unwrap()).unwrap();
+        yaml.lines()
+            .find_map(|l| l.trim().strip_prefix("example: /a/").map(ToOwned::to_owned))
+    };
+
+    let builder = oal_openapi::Builder::new(spec.clone());
+    assert_eq!(example(builder).as_deref(), Some("42/_x_string_"));
+
+    let builder = oal_openapi::Builder::new(spec).with_uri_examples(false);
+    assert_eq!(example(builder), None);
+
+    Ok(())
+}
+
+#[test]
+fn uri_example_from_format() -> anyhow::Result<()> {
+    let code = r#"
+        let r = /a/{ 'id str `format: uuid` }/{ 'day str `format: date` }/{ 'x str `format: email` };
+        res r on get -> { 'self r };
+    "#;
+    let spec = compile(code)?;
+    let api = serde_json::to_value(oal_openapi::Builder::new(spec).into_openapi()?)?;
+
+    let path = &api["paths"]["/a/{id}/{day}/{x}"];
+    let schema = &path["get"]["responses"]["default"]["content"]["application/json"]["schema"];
+    assert_eq!(
+        schema["properties"]["self"]["example"],
+        "/a/00000000-0000-0000-0000-000000000000/2000-01-01/_x_string_"
+    );
+    assert_eq!(path["parameters"][0]["schema"]["format"], "uuid");
+    assert_eq!(path["parameters"][1]["schema"]["format"], "date");
+
+    Ok(())
+}
+
+#[test]
+fn openapi_version() -> anyhow::Result<()> {
+    let code = r#"
+        # webhook: petAdded
+        let hook = /pets on post : <{ 'id int }> -> <status=204>;
+        res hook;
+        res /pets on get -> <[{ 'id int }]>;
+    "#;
+    let spec = compile(code)?;
+    let base: openapiv3::OpenAPI = serde_yaml::from_str(
+        r#"
+        openapi: 3.0.3
+        info: { title: Test, version: 1.0.0 }
+        paths: {}
+        components:
+          parameters:
+            limit:
+              name: limit
+              in: query
+              schema: { type: integer, nullable: true, minimum: 1, exclusiveMinimum: true }
+        "#,
+    )?;
+    let builder = || oal_openapi::Builder::new(spec.clone()).with_base(base.clone());
+
+    let doc = builder().into_document()?;
+    let keys = doc.as_mapping().unwrap().keys();
+    assert_eq!(keys.take(2).collect::<Vec<_>>(), ["openapi", "info"]);
+    let api = serde_json::to_value(doc)?;
+    assert_eq!(api["openapi"], "3.0.3");
+    assert!(api["paths"]["/pets"]["get"].is_object());
+    assert!(api["paths"]["/pets"]["post"].is_null());
+    assert!(api["x-webhooks"]["petAdded"]["post"].is_object());
+
+    let doc = builder()
+        .with_version(oal_openapi::OpenApiVersion::V3_1)
+        .into_document()?;
+    let api = serde_json::to_value(doc)?;
+    assert_eq!(api["openapi"], "3.1.0");
+    assert!(api.get("x-webhooks").is_none());
+    assert!(api["webhooks"]["petAdded"]["post"].is_object());
+    let schema = &api["components"]["parameters"]["limit"]["schema"];
+    assert_eq!(schema["type"], serde_json::json!(["integer", "null"]));
+    assert_eq!(schema["exclusiveMinimum"], 1);
+    assert!(schema.get("nullable").is_none());
+    assert!(schema.get("minimum").is_none());
+
+    let settings = Settings {
+        openapi_version: Some("3.2".to_owned()),
+        ..Default::default()
+    };
+    assert!(settings.openapi_version().is_err());
+
+    Ok(())
+}
+
+#[test]
+fn schema_depth_exceeded() -> anyhow::Result<()> {
+    let schema = format!("{}str{}", "[".repeat(40), "]".repeat(40));
+    let code = format!("res /a on get -> <{schema}>;");
+    let spec = compile(&code)?;
+
+    assert!(oal_openapi::Builder::new(spec.clone())
+        .into_openapi()
+        .is_ok());
+    let err = oal_openapi::Builder::new(spec)
+        .with_max_depth(16)
+        .into_openapi()
+        .expect_err("schema generation should exceed the depth limit");
+    assert!(matches!(
+        err.kind,
+        oal_compiler::errors::Kind::DepthExceeded
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn schema_count_exceeded() -> anyhow::Result<()> {
+    let code = "let f x = { 'a x, 'b x }; res /a on get -> f (f (f (f {})));";
+    let spec = compile(code)?;
+
+    assert!(oal_openapi::Builder::new(spec.clone())
+        .with_max_schemas(64)
+        .into_openapi()
+        .is_ok());
+    let err = oal_openapi::Builder::new(spec)
+        .with_max_schemas(16)
+        .into_openapi()
+        .expect_err("schema generation should exceed the schema limit");
+    assert!(matches!(
+        err.kind,
+        oal_compiler::errors::Kind::ResourceExceeded
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn label_case() -> anyhow::Result<()> {
+    let code = "res /ÉTÉ/Menus/{ 'Id int } on get -> {};";
+    let spec = compile(code)?;
+
+    for (case, id) in [
+        (oal_openapi::LabelCase::Lower, "get-été-menus-id"),
+        (oal_openapi::LabelCase::AsciiLower, "get-ÉtÉ-menus-id"),
+        (oal_openapi::LabelCase::Preserve, "get-ÉTÉ-Menus-Id"),
+    ] {
+        let builder = oal_openapi::Builder::new(spec.clone()).with_label_case(case);
+        let api = serde_json::to_value(builder.into_openapi()?)?;
+        let get = &api["paths"]["/ÉTÉ/Menus/{Id}"]["get"];
+        assert_eq!(get["operationId"], id);
+    }
+    assert!("upper".parse::<oal_openapi::LabelCase>().is_err());
+
+    Ok(())
+}
+
+#[test]
+fn operation_id_schemes() -> anyhow::Result<()> {
+    let code = "res /user-profiles/{ 'id int } on get, put -> {};";
+    let spec = compile(code)?;
+
+    for (scheme, params, id) in [
+        (oal_openapi::IdScheme::Kebab, true, "get-user-profiles-id"),
+        (oal_openapi::IdScheme::Camel, true, "getUserProfilesId"),
+        (oal_openapi::IdScheme::Snake, true, "get_user_profiles_id"),
+        (oal_openapi::IdScheme::Camel, false, "getUserProfiles"),
+    ] {
+        let builder = oal_openapi::Builder::new(spec.clone())
+            .with_id_scheme(scheme)
+            .with_id_params(params);
+        let api = serde_json::to_value(builder.into_openapi()?)?;
+        let get = &api["paths"]["/user-profiles/{id}"]["get"];
+        assert_eq!(get["operationId"], id);
+    }
+    assert!("pascal".parse::<oal_openapi::IdScheme>().is_err());
+
+    Ok(())
+}
+
+#[test]
+fn parameter_components() -> anyhow::Result<()> {
+    let code = r#"
+        res /a?{ 'limit int, 'offset int } on get -> {};
+        res /b?{ 'limit int, 'offset int } on get -> {}, put : {} -> {};
+        res /c/{ 'limit str } on get -> {};
+    "#;
+    let spec = compile(code)?;
+
+    let api = serde_json::to_value(oal_openapi::Builder::new(spec.clone()).into_openapi()?)?;
+    assert_eq!(api["paths"]["/a"]["parameters"][0]["name"], "limit");
+    assert!(api["components"].get("parameters").is_none());
+
+    let builder = oal_openapi::Builder::new(spec).with_parameter_components(true);
+    let api = serde_json::to_value(builder.into_openapi()?)?;
+    let params = &api["components"]["parameters"];
+    assert_eq!(params.as_object().unwrap().len(), 2);
+    assert_eq!(params["limit"]["in"], "query");
+    assert_eq!(params["offset"]["name"], "offset");
+    for path in ["/a", "/b"] {
+        let refs = &api["paths"][path]["parameters"];
+        assert_eq!(refs[0]["$ref"], "#/components/parameters/limit");
+        assert_eq!(refs[1]["$ref"], "#/components/parameters/offset");
+    }
+    // Parameters used once remain inline.
+    let inline = &api["paths"]["/c/{limit}"]["parameters"][0];
+    assert_eq!(inline["in"], "path");
+
+    Ok(())
+}
+
+#[test]
+fn example_components() -> anyhow::Result<()> {
+    let code = r#"
+        # examples: { ada: !example { summary: Ada, description: A mathematician, value: { name: Ada } } }
+        let @user = { 'name str };
+        res /users on post : @user -> <status=201, @user>;
+        res /users/{ 'id int } on get -> <
+          # examples: { ada: !example { summary: Ada by id, value: { id: 1, name: Ada } } }
+          @user
+        >;
+    "#;
+    let spec = compile(code)?;
+
+    let api = serde_json::to_value(oal_openapi::Builder::new(spec.clone()).into_openapi()?)?;
+    let post = &api["paths"]["/users"]["post"];
+    let ada = &post["requestBody"]["content"]["application/json"]["examples"]["ada"];
+    assert_eq!(ada["summary"], "Ada");
+    assert_eq!(ada["description"], "A mathematician");
+    assert!(api["components"].get("examples").is_none());
+
+    let builder = oal_openapi::Builder::new(spec).with_example_components(true);
+    let api = serde_json::to_value(builder.into_openapi()?)?;
+    let examples = &api["components"]["examples"];
+    assert_eq!(examples.as_object().unwrap().len(), 1);
+    assert_eq!(
+        examples["ada"]["value"],
+        serde_json::json!({ "name": "Ada" })
+    );
+    let post = &api["paths"]["/users"]["post"];
+    let request = &post["requestBody"]["content"]["application/json"]["examples"]["ada"];
+    let response = &post["responses"]["201"]["content"]["application/json"]["examples"]["ada"];
+    assert_eq!(request["$ref"], "#/components/examples/ada");
+    assert_eq!(response["$ref"], "#/components/examples/ada");
+    // Examples used once remain inline.
+    let get = &api["paths"]["/users/{id}"]["get"];
+    let inline = &get["responses"]["default"]["content"]["application/json"]["examples"]["ada"];
+    assert_eq!(inline["summary"], "Ada by id");
+
+    Ok(())
+}
+
+#[test]
+fn parameter_groups() -> anyhow::Result<()> {
+    let code = r#"
+        let filters = { 'limit int, 'offset int };
+        res /a on get filters -> {};
+        res /b on get (filters & { 'q str }) -> {}, put { 'dry bool } : {} -> {};
+    "#;
+    let spec = compile(code)?;
+
+    let api = serde_json::to_value(oal_openapi::Builder::new(spec).into_openapi()?)?;
+    let params = &api["components"]["parameters"];
+    assert_eq!(params.as_object().unwrap().len(), 2);
+    assert_eq!(params["limit"]["in"], "query");
+    for path in ["/a", "/b"] {
+        let refs = &api["paths"][path]["get"]["parameters"];
+        assert_eq!(refs[0]["$ref"], "#/components/parameters/limit");
+        assert_eq!(refs[1]["$ref"], "#/components/parameters/offset");
+    }
+    // Parameters outside of named groups remain inline.
+    assert_eq!(api["paths"]["/b"]["get"]["parameters"][2]["name"], "q");
+    assert_eq!(api["paths"]["/b"]["put"]["parameters"][0]["name"], "dry");
+
+    Ok(())
+}
+
+#[test]
+fn primitive_facets() -> anyhow::Result<()> {
+    let code = r#"
+        res /a on get -> {
+            'at str `format: date-time`,
+            'id str `format: uuid`,
+            'rate num `minimum: 0, exclusiveMinimum: true, exclusiveMaximum: 1`
+        };
+    "#;
+    let spec = compile(code)?;
+
+    let api = serde_json::to_value(oal_openapi::Builder::new(spec.clone()).into_openapi()?)?;
+    let props = &api["paths"]["/a"]["get"]["responses"]["default"]["content"]["application/json"]
+        ["schema"]["properties"];
+    assert_eq!(props["at"]["format"], "date-time");
+    assert_eq!(props["id"]["format"], "uuid");
+    assert_eq!(props["rate"]["minimum"], 0.0);
+    assert_eq!(props["rate"]["exclusiveMinimum"], true);
+    assert_eq!(props["rate"]["maximum"], 1.0);
+    assert_eq!(props["rate"]["exclusiveMaximum"], true);
+
+    let doc = oal_openapi::Builder::new(spec)
+        .with_version(oal_openapi::OpenApiVersion::V3_1)
+        .into_document()?;
+    let api = serde_json::to_value(doc)?;
+    let props = &api["paths"]["/a"]["get"]["responses"]["default"]["content"]["application/json"]
+        ["schema"]["properties"];
+    assert_eq!(props["rate"]["exclusiveMinimum"], 0.0);
+    assert_eq!(props["rate"]["exclusiveMaximum"], 1.0);
+    assert!(props["rate"].get("minimum").is_none());
+
+    Ok(())
+}
+
+#[test]
+fn upgrade_schema_positions_only() -> anyhow::Result<()> {
+    let code = r#"
+        let @rate = num `minimum: 0, exclusiveMinimum: true`;
+        res /a on get -> {
+            'rate num `minimum: 0, exclusiveMinimum: true`,
+            'obj { 'nullable bool, 'type str } `example: { nullable: true, type: string }`,
+            'opt str `nullable: true, default: { nullable: true }, x-keep: { nullable: true, type: string }`,
+            'ref @rate
+        };
+    "#;
+    let spec = compile(code)?;
+
+    let doc = oal_openapi::Builder::new(spec)
+        .with_version(oal_openapi::OpenApiVersion::V3_1)
+        .into_document()?;
+    let api = serde_json::to_value(doc)?;
+    let props = &api["paths"]["/a"]["get"]["responses"]["default"]["content"]["application/json"]
+        ["schema"]["properties"];
+    assert_eq!(props["rate"]["exclusiveMinimum"], 0.0);
+    assert_eq!(
+        props["obj"]["example"],
+        serde_json::json!({ "nullable": true, "type": "string" })
+    );
+    assert_eq!(props["opt"]["type"], serde_json::json!(["string", "null"]));
+    assert_eq!(
+        props["opt"]["default"],
+        serde_json::json!({ "nullable": true })
+    );
+    assert_eq!(
+        props["opt"]["x-keep"],
+        serde_json::json!({ "nullable": true, "type": "string" })
+    );
+    assert_eq!(
+        api["components"]["schemas"]["rate"]["exclusiveMinimum"],
+        0.0
+    );
+
+    Ok(())
+}
+
+#[test]
+fn upgrade_nullable_enum() -> anyhow::Result<()> {
+    let code = r#"
+        res /a on get -> {
+            'kind ("a" | "b") `nullable: true`,
+            'size int `enum: [1, 2], nullable: true`,
+            'tag str `enum: [x]`
+        };
+    "#;
+    let spec = compile(code)?;
+
+    let doc = oal_openapi::Builder::new(spec)
+        .with_version(oal_openapi::OpenApiVersion::V3_1)
+        .into_document()?;
+    let api = serde_json::to_value(doc)?;
+    let props = &api["paths"]["/a"]["get"]["responses"]["default"]["content"]["application/json"]
+        ["schema"]["properties"];
+    assert_eq!(props["kind"]["type"], serde_json::json!(["string", "null"]));
+    assert_eq!(props["kind"]["enum"], serde_json::json!(["a", "b", null]));
+    assert_eq!(props["size"]["enum"], serde_json::json!([1, 2, null]));
+    assert_eq!(props["tag"]["enum"], serde_json::json!(["x"]));
+
+    Ok(())
+}
+
+#[test]
+fn default_values() -> anyhow::Result<()> {
+    let code = r#"
+        let @sort = { 'by str };
+        res /a?{ # default: 20
+        'limit int } on get -> {
+            'tags [str] `default: []`,
+            # default: { by: name }
+            'sort @sort
+        };
+    "#;
+    let spec = compile(code)?;
+
+    let api = serde_json::to_value(oal_openapi::Builder::new(spec).into_openapi()?)?;
+    let param = &api["paths"]["/a"]["parameters"][0];
+    assert_eq!(param["schema"]["default"], 20);
+    let props = &api["paths"]["/a"]["get"]["responses"]["default"]["content"]["application/json"]
+        ["schema"]["properties"];
+    assert_eq!(props["tags"]["default"], serde_json::json!([]));
+    assert_eq!(
+        props["sort"]["allOf"][0]["$ref"],
+        "#/components/schemas/sort"
+    );
+    assert_eq!(
+        props["sort"]["default"],
+        serde_json::json!({ "by": "name" })
+    );
+
+    Ok(())
+}
+
+#[test]
+fn literal_values() -> anyhow::Result<()> {
+    let code = r#"
+        let state = "active" | "inactive";
+        res /a on get -> {
+            'state state,
+            'code (1 | 2 | "other"),
+            'version str `const: v1`,
+            'level int `const: 3`,
+            'origin { 'x int } `const: { x: 0 }`
+        };
+    "#;
+    let spec = compile(code)?;
+
+    let api = serde_json::to_value(oal_openapi::Builder::new(spec.clone()).into_openapi()?)?;
+    let props = &api["paths"]["/a"]["get"]["responses"]["default"]["content"]["application/json"]
+        ["schema"]["properties"];
+    assert_eq!(props["state"]["type"], "string");
+    assert_eq!(
+        props["state"]["enum"],
+        serde_json::json!(["active", "inactive"])
+    );
+    assert_eq!(props["code"]["oneOf"][1]["enum"], serde_json::json!([1, 2]));
+    assert_eq!(
+        props["code"]["oneOf"][0]["enum"],
+        serde_json::json!(["other"])
+    );
+    assert_eq!(props["version"]["enum"], serde_json::json!(["v1"]));
+    assert_eq!(props["level"]["enum"], serde_json::json!([3]));
+    assert_eq!(props["origin"]["type"], "object");
+    assert_eq!(props["origin"]["enum"], serde_json::json!([{ "x": 0 }]));
+    // The constants are part of the schemas rather than extensions dropped by a round trip.
+    let parsed: openapiv3::OpenAPI = serde_json::from_value(api.clone())?;
+    assert_eq!(serde_json::to_value(parsed)?, api);
+
+    let doc = oal_openapi::Builder::new(spec)
+        .with_version(oal_openapi::OpenApiVersion::V3_1)
+        .into_document()?;
+    let api = serde_json::to_value(doc)?;
+    let props = &api["paths"]["/a"]["get"]["responses"]["default"]["content"]["application/json"]
+        ["schema"]["properties"];
+    assert_eq!(props["version"]["const"], "v1");
+    assert!(props["version"].get("enum").is_none());
+
+    Ok(())
+}
+
+#[test]
+fn discriminator() -> anyhow::Result<()> {
+    let code = r##"
+        let @cat = { 'kind! str, 'lives int };
+        let @dog = { 'kind! str, 'bark bool };
+        # discriminator: { propertyName: kind, mapping: { cat: "@cat", dog: "#/components/schemas/dog" } }
+        let @pet = @cat | @dog;
+        res /pets on get -> @pet;
+    "##;
+    let spec = compile(code)?;
+
+    let api = serde_json::to_value(oal_openapi::Builder::new(spec).into_openapi()?)?;
+    let pet = &api["components"]["schemas"]["pet"];
+    assert_eq!(pet["oneOf"][0]["$ref"], "#/components/schemas/cat");
+    assert_eq!(pet["discriminator"]["propertyName"], "kind");
+    assert_eq!(
+        pet["discriminator"]["mapping"],
+        serde_json::json!({ "cat": "#/components/schemas/cat", "dog": "#/components/schemas/dog" })
+    );
+
+    Ok(())
+}
+
+#[test]
+fn tag_groups() -> anyhow::Result<()> {
+    let main = r#"
+        use "users.oal" as u;
+        # tagGroup: Administration
+        let admin = /admin on delete -> {};
+        res u.users;
+        res u.user;
+        res admin;
+        res /health on get -> {};
+    "#;
+    let users = r#"
+        let users = /users on (get -> {}) `tags: [list]`;
+        let user = /users/{ 'id str } on get -> {};
+    "#;
+    let fs = InMemoryFileSystem::new()
+        .with_file("main.oal", main)
+        .with_file("users.oal", users);
+    let main = InMemoryFileSystem::locator("main.oal");
+    let mods = oal_compiler::module::load(&mut FileSystemLoader(&fs), &main)?;
+    let spec = oal_compiler::eval::eval(&mods)?;
+
+    let api = serde_json::to_value(oal_openapi::Builder::new(spec.clone()).into_openapi()?)?;
+    assert!(api.get("x-tagGroups").is_none());
+    assert!(api["paths"]["/health"]["get"].get("tags").is_none());
+
+    let builder = oal_openapi::Builder::new(spec).with_tag_groups(true);
+    let api = serde_json::to_value(builder.into_openapi()?)?;
+    assert_eq!(
+        api["x-tagGroups"],
+        serde_json::json!([
+            { "name": "users", "tags": ["list", "users"] },
+            { "name": "Administration", "tags": ["Administration"] },
+            { "name": "main", "tags": ["main"] },
+        ])
+    );
+    assert_eq!(
+        api["paths"]["/users"]["get"]["tags"],
+        serde_json::json!(["list"])
+    );
+    assert_eq!(
+        api["paths"]["/users/{id}"]["get"]["tags"],
+        serde_json::json!(["users"])
+    );
+    assert_eq!(
+        api["paths"]["/health"]["get"]["tags"],
+        serde_json::json!(["main"])
+    );
+
+    // Modules of the same name in different folders get distinct groups.
+    let main = r#"
+        use "users.oal" as u;
+        use "admin/users.oal" as a;
+        res u.user;
+        res a.staff;
+    "#;
+    let fs = InMemoryFileSystem::new()
+        .with_file("api/main.oal", main)
+        .with_file("api/users.oal", users)
+        .with_file("api/admin/users.oal", "let staff = /staff on get -> {};");
+    let main = InMemoryFileSystem::locator("api/main.oal");
+    let mods = oal_compiler::module::load(&mut FileSystemLoader(&fs), &main)?;
+    let spec = oal_compiler::eval::eval(&mods)?;
+    let builder = oal_openapi::Builder::new(spec).with_tag_groups(true);
+    let api = serde_json::to_value(builder.into_openapi()?)?;
+    assert_eq!(
+        api["x-tagGroups"],
+        serde_json::json!([
+            { "name": "users", "tags": ["users"] },
+            { "name": "admin/users", "tags": ["admin/users"] },
+        ])
+    );
+
+    Ok(())
+}
+
+#[test]
+fn stability() -> anyhow::Result<()> {
+    let code = r#"
+        # stability: beta
+        let @user = { 'id int };
+        res /a on (get -> @user) `stability: alpha`;
+    "#;
+    let spec = compile(code)?;
+
+    let api = serde_json::to_value(oal_openapi::Builder::new(spec).into_openapi()?)?;
+    assert_eq!(api["paths"]["/a"]["get"]["x-stability"], "alpha");
+    assert_eq!(api["components"]["schemas"]["user"]["x-stability"], "beta");
+
+    Ok(())
+}
+
+#[test]
+fn inline_examples() -> anyhow::Result<()> {
+    let code = r#"
+        # examples: { hosted: !example { externalValue: a.json }, inline: { id: 1, tags: [a] }, named: !example { summary: Empty, value: "" } }
+        # examples: { url: "a.json", text: !example { value: "a.json" }, object: { value: 1 } }
+        let @item = { 'id int, 'tags [str] };
+        res /a on get -> @item;
+    "#;
+    let spec = compile(code)?;
+
+    let api = serde_json::to_value(oal_openapi::Builder::new(spec).into_openapi()?)?;
+    let examples = &api["paths"]["/a"]["get"]["responses"]["default"]["content"]
+        ["application/json"]["examples"];
+    assert_eq!(
+        examples["hosted"],
+        serde_json::json!({ "externalValue": "a.json" })
+    );
+    assert_eq!(
+        examples["inline"],
+        serde_json::json!({ "value": { "id": 1, "tags": ["a"] } })
+    );
+    assert_eq!(
+        examples["named"],
+        serde_json::json!({ "summary": "Empty", "value": "" })
+    );
+    // Strings are hosted separately, and only tagged example objects carry fields.
+    assert_eq!(
+        examples["url"],
+        serde_json::json!({ "externalValue": "a.json" })
+    );
+    assert_eq!(examples["text"], serde_json::json!({ "value": "a.json" }));
+    assert_eq!(
+        examples["object"],
+        serde_json::json!({ "value": { "value": 1 } })
+    );
+
+    let code =
+        "res /a on get -> {} `examples: { bad: !example { value: 1, externalValue: a.json } }`;";
+    let err = compile(code).expect_err("expected an error");
+    let err = err.downcast::<oal_compiler::errors::Error>()?;
+    assert!(matches!(
+        err.kind,
+        oal_compiler::errors::Kind::InvalidLiteral
+    ));
+    assert!(err.span().is_some());
+
+    Ok(())
+}
+
+#[test]
+fn callbacks() -> anyhow::Result<()> {
+    let code = r#"
+        let @event = { 'id str };
+        # callback: events
+        let events = /events on post : @event -> <status=204>;
+        # callback: events
+        let probe = / on get -> <status=200, {}>;
+        # callbacks: { events: "{$request.body#/callbackUrl}" }
+        let subscribe = post : { 'callbackUrl! str } -> <status=201, {}>;
+        res /subscriptions on subscribe;
+        res events;
+        res probe;
+    "#;
+    let spec = compile(code)?;
+    let api = serde_json::to_value(oal_openapi::Builder::new(spec).into_openapi()?)?;
+
+    let paths = api["paths"].as_object().unwrap();
+    assert_eq!(paths.keys().collect::<Vec<_>>(), ["/subscriptions"]);
+    let callback = &paths["/subscriptions"]["post"]["callbacks"]["events"];
+    let items = callback.as_object().unwrap();
+    assert_eq!(
+        items.keys().collect::<Vec<_>>(),
+        [
+            "{$request.body#/callbackUrl}",
+            "{$request.body#/callbackUrl}/events"
+        ]
+    );
+    let post = &callback["{$request.body#/callbackUrl}/events"]["post"];
+    assert_eq!(
+        post["requestBody"]["content"]["application/json"]["schema"]["$ref"],
+        "#/components/schemas/event"
+    );
+    assert!(callback["{$request.body#/callbackUrl}"]["get"]["responses"]["200"].is_object());
+
+    Ok(())
+}
+
+#[test]
+fn error_categories() -> anyhow::Result<()> {
+    use oal_compiler::errors::Category;
+
+    let category = |code: &str| {
+        let err = compile(code).expect_err("expected an error");
+        crate::error_category(&err)
+    };
+
+    assert_eq!(category("res / on get ->"), Some(Category::Syntax));
+    assert_eq!(category("res a on get -> {};"), Some(Category::Type));
+    assert_eq!(category(r#"use "missing.oal";"#), Some(Category::Io));
+    assert_eq!(
+        category("res /a on get -> <status=200, {}>;\nres /a on get -> <status=201, {}>;"),
+        Some(Category::Evaluation)
+    );
+    assert_eq!(
+        category("#! empty-status: 1000\nres /a on get -> <>;"),
+        Some(Category::Annotation)
+    );
+    assert_eq!(crate::error_category(&anyhow::anyhow!("unknown")), None);
+
+    Ok(())
+}
+
+#[test]
+fn fix_program() -> anyhow::Result<()> {
+    let fs = InMemoryFileSystem::new()
+        .with_file(
+            "main.oal",
+            "use \"lib.oal\" as l;\nlet a = {}\nres r on get -> a;\n",
+        )
+        .with_file("lib.oal", "let r = /a;");
+    let main = InMemoryFileSystem::locator("main.oal");
+
+    let applied = fix::apply(&fs, &main, &Default::default())?;
+    let titles = applied.iter().map(|f| f.title.as_str()).collect::<Vec<_>>();
+    assert_eq!(titles, ["insert missing semicolon", "qualify as l.r"]);
+    assert_eq!(
+        applied[0].position,
+        Position {
+            line: 2,
+            column: 11
+        }
+    );
+    assert_eq!(applied[1].position, Position { line: 3, column: 5 });
+    assert_eq!(
+        fs.read_file(&main)?,
+        "use \"lib.oal\" as l;\nlet a = {};\nres l.r on get -> a;\n"
+    );
+    oal_compiler::module::load(&mut FileSystemLoader(&fs), &main)?;
+
+    // Variables declared under several qualifiers are left to the user.
+    let fs = InMemoryFileSystem::new()
+        .with_file(
+            "main.oal",
+            "use \"lib.oal\" as a;\nuse \"lib.oal\" as b;\nres r;\n",
+        )
+        .with_file("lib.oal", "let r = /a;");
+    assert!(fix::apply(&fs, &main, &Default::default())?.is_empty());
+    let err = oal_compiler::module::load(&mut FileSystemLoader(&fs), &main).unwrap_err();
+    let err = err.downcast::<oal_compiler::errors::Error>()?;
+    let titles = err
+        .fixes()
+        .iter()
+        .map(|f| f.title.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(titles, ["qualify as a.r", "qualify as b.r"]);
+
+    Ok(())
+}
+
+#[test]
+fn schema_ref() -> anyhow::Result<()> {
+    let code = r#"
+        let @a = { 'id int };
+        let @b = { 'a @a };
+        res /a on get -> @b;
+    "#;
+    let spec = compile(code)?;
+    let response = "/paths/~1a/get/responses/default/content/application~1json/schema/$ref";
+
+    let api = serde_json::to_value(oal_openapi::Builder::new(spec.clone()).into_openapi()?)?;
+    assert_eq!(api.pointer(response).unwrap(), "#/components/schemas/b");
+
+    let builder = oal_openapi::Builder::new(spec.clone())
+        .with_schema_ref("#/components/schemas/{name}".to_owned());
+    let local = serde_json::to_value(builder.into_openapi()?)?;
+    assert_eq!(local, api);
+
+    let builder =
+        oal_openapi::Builder::new(spec.clone()).with_schema_ref("./schemas/{name}.yaml".to_owned());
+    let api = serde_json::to_value(builder.into_openapi()?)?;
+    assert_eq!(api.pointer(response).unwrap(), "./schemas/b.yaml");
+    // Components hosted separately are left out of the description.
+    assert!(api["components"].get("schemas").is_none());
+
+    let builder = oal_openapi::Builder::new(spec)
+        .with_schema_ref("./schemas/{name}.yaml".to_owned())
+        .with_schema_components(true);
+    let api = serde_json::to_value(builder.into_openapi()?)?;
+    assert_eq!(api.pointer(response).unwrap(), "./schemas/b.yaml");
+    assert!(api["components"]["schemas"]["b"].is_object());
+
+    Ok(())
+}
+
+#[test]
+fn component_names() -> anyhow::Result<()> {
+    let code = r#"
+        # componentName: UserAccount
+        let @user = { 'id int };
+        res /a on get -> @user;
+    "#;
+    let spec = compile(code)?;
+    let response = "/paths/~1a/get/responses/default/content/application~1json/schema/$ref";
+
+    let api = serde_json::to_value(oal_openapi::Builder::new(spec).into_openapi()?)?;
+    assert_eq!(
+        api.pointer(response).unwrap(),
+        "#/components/schemas/UserAccount"
+    );
+    assert!(api.pointer("/components/schemas/UserAccount").is_some());
+    assert!(api.pointer("/components/schemas/user").is_none());
+
+    Ok(())
+}
+
+#[test]
+fn translated_descriptions() -> anyhow::Result<()> {
+    let code = r#"
+        # description: a user
+        # description@fr: un utilisateur
+        # description@de: ein Benutzer
+        let @user = {};
+        # description: get a user, description@fr: obtenir un utilisateur
+        let op = get -> @user;
+        res /a on op;
+    "#;
+    let spec = compile(code)?;
+
+    let api = serde_json::to_value(oal_openapi::Builder::new(spec.clone()).into_openapi()?)?;
+    let user = &api["components"]["schemas"]["user"];
+    assert_eq!(user["description"], "a user");
+    assert!(user.get("x-description-fr").is_none());
+    assert_eq!(api["paths"]["/a"]["get"]["description"], "get a user");
+
+    let builder = oal_openapi::Builder::new(spec)
+        .with_language("fr".to_owned())
+        .with_translation_extensions(true);
+    let api = serde_json::to_value(builder.into_openapi()?)?;
+    let user = &api["components"]["schemas"]["user"];
+    assert_eq!(user["description"], "un utilisateur");
+    assert_eq!(user["x-description-de"], "ein Benutzer");
+    assert!(user.get("x-description-fr").is_none());
+    let op = &api["paths"]["/a"]["get"];
+    assert_eq!(op["description"], "obtenir un utilisateur");
+    assert_eq!(op["summary"], "obtenir un utilisateur");
+
+    Ok(())
+}
+
+#[test]
+fn deprecated() -> anyhow::Result<()> {
+    let code = r#"
+        # deprecated: true
+        let @old = { 'id int };
+        let @user = {
+            'id int `deprecated: true`,
+            # deprecated: true
+            'old @old,
+            'name str
+        };
+        res /a?{
+            # deprecated: true
+            'limit int
+        } on
+            # deprecated: true
+            get -> @user,
+            put : @user -> {};
+    "#;
+    let spec = compile(code)?;
+    let api = serde_json::to_value(oal_openapi::Builder::new(spec).into_openapi()?)?;
+
+    let get = &api["paths"]["/a"]["get"];
+    assert_eq!(get["deprecated"], true);
+    assert_eq!(api["paths"]["/a"]["parameters"][0]["deprecated"], true);
+    assert!(api["paths"]["/a"]["put"].get("deprecated").is_none());
+
+    let schemas = &api["components"]["schemas"];
+    assert_eq!(schemas["old"]["deprecated"], true);
+    assert!(schemas["user"].get("deprecated").is_none());
+    let props = &schemas["user"]["properties"];
+    assert_eq!(props["id"]["deprecated"], true);
+    // A reference is wrapped, as the siblings of `$ref` are ignored.
+    assert_eq!(props["old"]["deprecated"], true);
+    assert_eq!(props["old"]["allOf"][0]["$ref"], "#/components/schemas/old");
+    assert!(props["name"].get("deprecated").is_none());
+
+    Ok(())
+}
+
+#[test]
+fn object_facets() -> anyhow::Result<()> {
+    let code = r#"
+        # additionalProperties: false, minProperties: 1
+        let @closed = { 'id int, 'name str };
+        let @labels = {} `additionalProperties: { type: string }, maxProperties: 8`;
+        res /a on get -> { 'closed @closed, 'labels @labels };
+    "#;
+    let spec = compile(code)?;
+    let api = serde_json::to_value(oal_openapi::Builder::new(spec).into_openapi()?)?;
+
+    let closed = &api["components"]["schemas"]["closed"];
+    assert_eq!(closed["additionalProperties"], false);
+    assert_eq!(closed["minProperties"], 1);
+    assert!(closed.get("maxProperties").is_none());
+    let labels = &api["components"]["schemas"]["labels"];
+    assert_eq!(labels["additionalProperties"]["type"], "string");
+    assert_eq!(labels["maxProperties"], 8);
+
+    let code = "res /a on get -> {} `additionalProperties: yes`;";
+    let err = compile(code).expect_err("expected an error");
+    let err = err.downcast::<oal_compiler::errors::Error>()?;
+    assert!(matches!(
+        err.kind,
+        oal_compiler::errors::Kind::InvalidLiteral
+    ));
+
+    let code = "res /a on get -> {} `additionalProperties: { type: 42 }`;";
+    let spec = compile(code)?;
+    let err = oal_openapi::Builder::new(spec)
+        .into_openapi()
+        .expect_err("expected an error");
+    assert!(matches!(
+        err.kind,
+        oal_compiler::errors::Kind::InvalidLiteral
+    ));
+    assert!(err.to_string().contains("additionalProperties"), "{err}");
+
+    Ok(())
+}
+
+#[test]
+fn custom_keywords() -> anyhow::Result<()> {
+    let code = r#"
+        # x-owner: accounts, validator: strict
+        let @user = { 'id! int `x-internal: true, unique: true`, 'name str };
+        res /a on get -> @user;
+    "#;
+    let spec = compile(code)?;
+
+    let api = serde_json::to_value(oal_openapi::Builder::new(spec.clone()).into_openapi()?)?;
+    let user = &api["components"]["schemas"]["user"];
+    assert_eq!(user["x-owner"], "accounts");
+    assert!(user.get("validator").is_none());
+    let id = &user["properties"]["id"];
+    assert_eq!(id["x-internal"], true);
+    assert!(id.get("unique").is_none());
+
+    // Custom keywords are not allowed by OpenAPI 3.0.
+    let builder = oal_openapi::Builder::new(spec.clone()).with_custom_keywords(true);
+    let api = serde_json::to_value(builder.into_openapi()?)?;
+    assert!(api["components"]["schemas"]["user"]
+        .get("validator")
+        .is_none());
+
+    let builder = oal_openapi::Builder::new(spec)
+        .with_version(oal_openapi::OpenApiVersion::V3_1)
+        .with_custom_keywords(true);
+    let api = builder.into_document()?;
+    let user = &api["components"]["schemas"]["user"];
+    assert_eq!(user["x-owner"], "accounts");
+    assert_eq!(user["validator"], "strict");
+    assert_eq!(user["properties"]["id"]["unique"], true);
+
+    Ok(())
+}
+
+#[test]
+fn security_schemes() -> anyhow::Result<()> {
+    let code = r#"
+        #! securitySchemes: { oauth: { type: oauth2, description: OAuth } }
+        #! securitySchemes: { oauth: { flows: { authorizationCode: { scopes: { read: Read } } } } }
+        #! securitySchemes: { oauth: { flows: { authorizationCode: { authorizationUrl: "/auth", tokenUrl: "/token" } } } }
+        #! security: [ { oauth: [read] } ]
+        res /a on get -> {};
+    "#;
+    let spec = compile(code)?;
+    let api = serde_json::to_value(oal_openapi::Builder::new(spec).into_openapi()?)?;
+
+    let scheme = &api["components"]["securitySchemes"]["oauth"];
+    assert_eq!(scheme["type"], "oauth2");
+    assert_eq!(scheme["description"], "OAuth");
+    let flow = &scheme["flows"]["authorizationCode"];
+    assert_eq!(flow["authorizationUrl"], "/auth");
+    assert_eq!(flow["tokenUrl"], "/token");
+    assert_eq!(flow["scopes"]["read"], "Read");
+    assert_eq!(api["security"][0]["oauth"][0], "read");
+
+    Ok(())
+}
+
+#[test]
+fn document_metadata() -> anyhow::Result<()> {
+    let code = r#"
+        #! info: { title: Pet store, version: 2.0.0, description: The pet store API }
+        #! servers: [ { url: "https://api.example.com", description: Production } ]
+        #! tags: [ { name: pets, description: Pets for sale }, users ]
+        res /a on get -> {};
+    "#;
+    let spec = compile(code)?;
+
+    let base = serde_json::from_value(serde_json::json!({
+        "openapi": "3.0.3",
+        "info": { "title": "Base", "version": "1.0.0", "termsOfService": "/terms" },
+        "servers": [{ "url": "/" }],
+        "tags": [{ "name": "pets" }, { "name": "admin", "description": "Administration" }],
+        "paths": {}
+    }))?;
+    let builder = oal_openapi::Builder::new(spec).with_base(base);
+    let api = serde_json::to_value(builder.into_openapi()?)?;
+
+    assert_eq!(api["info"]["title"], "Pet store");
+    assert_eq!(api["info"]["version"], "2.0.0");
+    assert_eq!(api["info"]["description"], "The pet store API");
+    assert_eq!(api["info"]["termsOfService"], "/terms");
+    assert_eq!(
+        api["servers"],
+        serde_json::json!([{ "url": "https://api.example.com", "description": "Production" }])
+    );
+    assert_eq!(
+        api["tags"],
+        serde_json::json!([
+            { "name": "pets", "description": "Pets for sale" },
+            { "name": "users" },
+            { "name": "admin", "description": "Administration" }
+        ])
+    );
+
+    Ok(())
+}
+
+#[test]
+fn transfer_servers() -> anyhow::Result<()> {
+    let code = r#"
+        #! servers: [ "https://api.example.com" ]
+        # servers: [ "https://a.example.com" ]
+        let a = /a on
+            get -> {},
+            # servers: []
+            put : {} -> {};
+        res a;
+        res /b on get -> {};
+    "#;
+    let spec = compile(code)?;
+    let api = serde_json::to_value(oal_openapi::Builder::new(spec).into_openapi()?)?;
+
+    let a = &api["paths"]["/a"];
+    assert_eq!(
+        a["get"]["servers"],
+        serde_json::json!([{ "url": "https://a.example.com" }])
+    );
+    assert_eq!(a["put"]["servers"], serde_json::json!([{ "url": "/" }]));
+    assert!(api["paths"]["/b"]["get"].get("servers").is_none());
+
+    Ok(())
+}
+
+#[test]
+fn document_tags() -> anyhow::Result<()> {
+    let code = r#"
+        #! tags: [ { name: users, description: User accounts } ]
+        res /a on (get -> {}) `tags: [orders, users]`, (put : {} -> {}) `tags: [billing]`;
+        res /b on (get -> {}) `tags: [orders]`;
+        res /c on get -> {};
+    "#;
+    let spec = compile(code)?;
+    let api = serde_json::to_value(oal_openapi::Builder::new(spec).into_openapi()?)?;
+
+    assert_eq!(
+        api["tags"],
+        serde_json::json!([
+            { "name": "users", "description": "User accounts" },
+            { "name": "orders" },
+            { "name": "billing" }
+        ])
+    );
+
+    Ok(())
+}
+
+#[test]
+fn form_encoding() -> anyhow::Result<()> {
+    let code = r#"
+        let form = {
+            # style: pipeDelimited
+            # explode: false
+            'scopes [str],
+            # explode: true
+            'ids [int],
+            # explode: false
+            'names [str],
+            'grant_type str
+        };
+        let @form = form;
+        res /token?{
+            # style: spaceDelimited
+            'tags [str]
+        } on post : <media="application/x-www-form-urlencoded", @form> -> {};
+    "#;
+    let spec = compile(code)?;
+    let api = serde_json::to_value(oal_openapi::Builder::new(spec).into_openapi()?)?;
+
+    let op = &api["paths"]["/token"]["post"];
+    let encoding = &op["requestBody"]["content"]["application/x-www-form-urlencoded"]["encoding"];
+    assert_eq!(
+        *encoding,
+        serde_json::json!({
+            "scopes": { "contentType": "text/plain", "style": "pipeDelimited" },
+            "ids": { "contentType": "text/plain", "explode": true },
+            "names": { "contentType": "text/plain", "explode": false }
+        })
+    );
+    let param = &api["paths"]["/token"]["parameters"][0];
+    assert_eq!(param["style"], "spaceDelimited");
+
+    Ok(())
+}
+
+#[test]
+fn parameter_styles() -> anyhow::Result<()> {
+    let code = r#"
+        # style: label
+        let id = 'id str;
+        res /items/{id}?{
+            # style: pipeDelimited
+            # explode: false
+            'fields [str]
+        } on get -> {};
+    "#;
+    let spec = compile(code)?;
+    let api = serde_json::to_value(oal_openapi::Builder::new(spec).into_openapi()?)?;
+
+    let params = &api["paths"]["/items/{id}"]["parameters"];
+    assert_eq!(params[0]["in"], "path");
+    assert_eq!(params[0]["style"], "label");
+    assert_eq!(params[1]["in"], "query");
+    assert_eq!(params[1]["style"], "pipeDelimited");
+    assert_eq!(params[1]["explode"], false);
+
+    Ok(())
+}
+
+#[test]
+fn multipart_encoding() -> anyhow::Result<()> {
+    let code = r#"
+        let @upload = {
+            # contentType: "image/png, image/jpeg"
+            'avatar str `format: binary`,
+            # style: pipeDelimited
+            'tags [str],
+            # contentType: application/xml
+            'meta { 'owner str }
+        };
+        res /uploads on post : <media="multipart/form-data", @upload> -> {};
+    "#;
+    let spec = compile(code)?;
+    let api = serde_json::to_value(oal_openapi::Builder::new(spec).into_openapi()?)?;
+
+    let op = &api["paths"]["/uploads"]["post"];
+    let encoding = &op["requestBody"]["content"]["multipart/form-data"]["encoding"];
+    assert_eq!(
+        *encoding,
+        serde_json::json!({
+            "avatar": { "contentType": "image/png, image/jpeg" },
+            "meta": { "contentType": "application/xml" }
+        })
+    );
+
+    let code = r#"
+        let form = {
+            # contentType: image
+            'f str
+        };
+        res /a on post : <media="multipart/form-data", form> -> {};
+    "#;
+    assert!(compile(code).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn request_media_types() -> anyhow::Result<()> {
+    let code = r#"
+        let @user = { 'name str };
+        let bodies = <media="application/json", headers={ 'x-trace str }, @user> `description: "the user"`
+                  :: <media="application/xml", @user>;
+        res /users on post : bodies -> {};
+        res /files on put : (<media="image/png", str> :: <media="image/jpeg", str>) -> {};
+    "#;
+    let spec = compile(code)?;
+    let api = serde_json::to_value(oal_openapi::Builder::new(spec).into_openapi()?)?;
+
+    let body = &api["paths"]["/users"]["post"]["requestBody"];
+    let media = body["content"]
+        .as_object()
+        .unwrap()
+        .keys()
+        .collect::<Vec<_>>();
+    assert_eq!(media, ["application/json", "application/xml"]);
+    assert_eq!(body["description"], "the user");
+    assert_eq!(
+        body["content"]["application/xml"]["schema"]["$ref"],
+        "#/components/schemas/user"
+    );
+    let params = &api["paths"]["/users"]["post"]["parameters"];
+    assert_eq!(params[0]["name"], "x-trace");
+
+    let body = &api["paths"]["/files"]["put"]["requestBody"];
+    let media = body["content"]
+        .as_object()
+        .unwrap()
+        .keys()
+        .collect::<Vec<_>>();
+    assert_eq!(media, ["image/jpeg", "image/png"]);
+
+    Ok(())
+}
+
+#[test]
+fn content_encoding() -> anyhow::Result<()> {
+    let code = r#"
+        res /a on put : <{}> `content-encoding: [gzip]`
+               -> <status=200, {}> `content-encoding: [gzip, br]`
+               :: <status=206, headers={ 'content-encoding str }, {}> `content-encoding: [br]`;
+    "#;
+    let spec = compile(code)?;
+    let api = serde_json::to_value(oal_openapi::Builder::new(spec).into_openapi()?)?;
+
+    let op = &api["paths"]["/a"]["put"];
+    let request = &op["requestBody"]["content"]["application/json"];
+    assert_eq!(request["x-content-encoding"], serde_json::json!(["gzip"]));
+
+    let ok = &op["responses"]["200"];
+    assert_eq!(
+        ok["content"]["application/json"]["x-content-encoding"],
+        serde_json::json!(["gzip", "br"])
+    );
+    let header = &ok["headers"]["Content-Encoding"];
+    assert_eq!(header["schema"]["enum"], serde_json::json!(["gzip", "br"]));
+
+    let partial = &op["responses"]["206"]["headers"];
+    let names: Vec<_> = partial.as_object().unwrap().keys().collect();
+    assert_eq!(
+        names,
+        ["content-encoding"],
+        "expected the declared header only"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn operation_extensions() -> anyhow::Result<()> {
+    let code = r#"
+        res /a on
+            # x-timeout-ms: 5000
+            # maxBodyBytes: 1024
+            # x-gateway: { pool: upload }
+            put : <{}> -> <>;
+    "#;
+    let spec = compile(code)?;
+    let api = serde_json::to_value(oal_openapi::Builder::new(spec).into_openapi()?)?;
+
+    let op = &api["paths"]["/a"]["put"];
+    assert_eq!(op["x-timeout-ms"], serde_json::json!(5000));
+    assert_eq!(op["x-max-body-bytes"], serde_json::json!(1024));
+    assert_eq!(op["x-gateway"], serde_json::json!({ "pool": "upload" }));
+    assert!(op.get("maxBodyBytes").is_none());
+
+    // A mapping with a sequence as key has no JSON equivalent.
+    let code = r#"res /a on (get -> {}) `x-gateway: { [1, 2]: upload }`;"#;
+    let spec = compile(code)?;
+    let err = oal_openapi::Builder::new(spec)
+        .into_openapi()
+        .expect_err("expected an error");
+    assert!(matches!(
+        err.kind,
+        oal_compiler::errors::Kind::InvalidLiteral
+    ));
+    assert!(err.to_string().contains("x-gateway"));
+
+    Ok(())
+}
+
+#[test]
+fn vendor_extensions() -> anyhow::Result<()> {
+    let code = r#"
+        # x-amazon-apigateway-any-method: { isDefaultRoute: true }
+        let items = /items on get -> <status=200, { 'id int `x-order: 1` }> `x-codegen-type: Page`;
+        # x-internal: true
+        let @item = { 'id int };
+        res items;
+        res /items/{ 'id int } on (put : <@item> `x-max-size: 10` -> <>) `x-timeout-ms: 5000`;
+    "#;
+    let spec = compile(code)?;
+    let api = serde_json::to_value(oal_openapi::Builder::new(spec).into_openapi()?)?;
+
+    let path = &api["paths"]["/items"];
+    assert_eq!(
+        path["x-amazon-apigateway-any-method"],
+        serde_json::json!({ "isDefaultRoute": true })
+    );
+    let content = &path["get"]["responses"]["200"]["content"]["application/json"];
+    assert_eq!(content["x-codegen-type"], "Page");
+    assert_eq!(content["schema"]["properties"]["id"]["x-order"], 1);
+
+    let op = &api["paths"]["/items/{id}"]["put"];
+    assert_eq!(op["x-timeout-ms"], 5000);
+    assert_eq!(
+        op["requestBody"]["content"]["application/json"]["x-max-size"],
+        10
+    );
+    assert_eq!(api["components"]["schemas"]["item"]["x-internal"], true);
+
+    // Values without a JSON equivalent are reported rather than serialized.
+    for code in [
+        "res /a on get -> <status=200, {}> `x-foo: { [1, 2]: a }`;",
+        "# x-foo: { [1, 2]: a }\nlet a = /a on get -> {};\nres a;",
+    ] {
+        let spec = compile(code)?;
+        let err = oal_openapi::Builder::new(spec)
+            .into_openapi()
+            .expect_err("expected an error");
+        assert!(err.to_string().contains("x-foo"), "{err}");
+    }
+
+    Ok(())
+}
+
+#[test]
+fn json_schema_bundle() -> anyhow::Result<()> {
+    let code = r#"
+        let @address = { 'city str `example: Paris` };
+        # nullable: true
+        let @person = { 'name! str, 'home @address, 'tags [str] };
+        res /people on get -> [@person];
+    "#;
+    let spec = compile(code)?;
+    let bundle = serde_json::to_value(oal_openapi::Builder::new(spec).into_json_schema()?)?;
+
+    assert_eq!(
+        bundle["$schema"],
+        "https://json-schema.org/draft/2020-12/schema"
+    );
+    assert!(bundle.get("openapi").is_none());
+    let defs = bundle["$defs"].as_object().unwrap();
+    assert_eq!(defs.len(), 2);
+    let person = &defs["person"];
+    assert_eq!(person["type"], serde_json::json!(["object", "null"]));
+    assert_eq!(person["properties"]["home"]["$ref"], "#/$defs/address");
+    assert_eq!(
+        defs["address"]["properties"]["city"]["examples"],
+        serde_json::json!(["Paris"])
+    );
+    assert!(defs["address"]["properties"]["city"]
+        .get("example")
+        .is_none());
+
+    Ok(())
+}
+
+#[test]
+fn proto_messages() -> anyhow::Result<()> {
+    let code = r#"
+        let @id = str;
+        let @cat = { 'name str };
+        let @dog = { 'barks bool };
+        let @pet = @cat | @dog;
+        let @owner = {
+            'ownerId! @id,
+            'address { 'city str, 'zipCode int },
+            'pets [@pet],
+            'scores [[num]],
+            'contact str ~ { 'email str }
+        };
+        res /owners on get -> [@owner];
+    "#;
+    let spec = compile(code)?;
+    let text = proto::render(&spec);
+
+    let expected = r#"syntax = "proto3";
+
+message Owner {
+  string owner_id = 1;
+  Address address = 2;
+  repeated Pet pets = 3;
+  repeated ScoresItem scores = 4;
+  oneof contact {
+    string contact_1 = 5;
+    Contact2 contact_2 = 6;
+  }
+  message Address {
+    string city = 1;
+    int64 zip_code = 2;
+  }
+  message ScoresItem {
+    repeated double value = 1;
+  }
+  message Contact2 {
+    string email = 1;
+  }
+}
+
+message Pet {
+  oneof kind {
+    Cat cat = 1;
+    Dog dog = 2;
+  }
+}
+
+message Cat {
+  string name = 1;
+}
+
+message Dog {
+  bool barks = 1;
+}
+"#;
+    assert_eq!(text, expected);
+
+    Ok(())
+}
+
+#[test]
+fn proto_recursion_and_collisions() -> anyhow::Result<()> {
+    let code = r#"
+        let @tree = { 'children rec x [x], 'fooBar str, 'foo_bar int };
+        res /trees on get -> @tree;
+    "#;
+    let spec = compile(code)?;
+    let text = proto::render(&spec);
+
+    let expected = r#"syntax = "proto3";
+
+import "google/protobuf/struct.proto";
+
+message Tree {
+  repeated google.protobuf.Value children = 1;
+  string foo_bar = 2;
+  int64 foo_bar_2 = 3;
+}
+"#;
+    assert_eq!(text, expected);
+
+    Ok(())
+}
+
+#[test]
+fn api_reference() -> anyhow::Result<()> {
+    let code = r#"
+        # title: "The user"
+        let @user = { 'name! str `description: "The name"`, 'role str `enum: [admin, guest]` };
+        # summary: "Get a user", operationId: getUser
+        let op = get -> <status=200, @user> `description: "A user"` :: <status=404, { 'reason str }>;
+        res /users/{ 'id int `description: "The identifier"` } on op;
+    "#;
+    let spec = compile(code)?;
+
+    let text = reference::render(&spec, Format::Markdown);
+    let expected = r#"# API reference
+
+## /users/{id}
+
+### GET /users/{id}
+
+Get a user
+
+Operation id: `getUser`
+
+#### Parameters
+
+| Name | In | Type | Required | Description |
+| --- | --- | --- | --- | --- |
+| `id` | path | integer | yes | The identifier |
+
+#### Responses
+
+| Status | Media type | Schema | Description |
+| --- | --- | --- | --- |
+| 200 |  | `user` | A user |
+| 404 |  | object |  |
+
+Properties of the 404 response:
+
+| Name | Type | Required | Description |
+| --- | --- | --- | --- |
+| `reason` | string | no |  |
+
+## Schemas
+
+### user
+
+The user
+
+| Name | Type | Required | Description |
+| --- | --- | --- | --- |
+| `name` | string | yes | The name |
+| `role` | one of `admin`, `guest` | no |  |
+"#;
+    assert_eq!(text, expected);
+
+    let html = reference::render(&spec, Format::Html);
+    assert!(html.starts_with("<!DOCTYPE html>"));
+    assert!(html.contains("<h2 id=\"path-users-id\">/users/{id}</h2>"));
+    assert!(html.contains("<h3 id=\"operation-get-users-id\">GET /users/{id}</h3>"));
+    assert!(html.contains("<h3 id=\"schema-user\">user</h3>"));
+    assert!(html.contains("<td>one of <code>admin</code>, <code>guest</code></td>"));
+
+    Ok(())
+}
+
+#[test]
+fn sample_payloads() -> anyhow::Result<()> {
+    let code = r#"
+        let @tag = { 'label! str `pattern: "^[a-z]{3}-\\d{2}$"` };
+        let @item = {
+            'id! int `minimum: 10, maximum: 20`,
+            'kind str `enum: [book, film]`,
+            'price num `minimum: 1, maximum: 2`,
+            'code str `minLength: 2, maxLength: 4`,
+            'email str `format: email`,
+            'tags [@tag] `minItems: 2, maxItems: 2`,
+            'size str `example: large`
+        };
+        res /items on get -> @item, post : <{ 'name str }> `examples: { a: !example { value: { name: x } } }` -> @item;
+    "#;
+    let spec = compile(code)?;
+
+    let item = schema(&spec, "@item");
+    let sample = oal_openapi::sample::sample(&spec, &item, 7)?;
+    assert_eq!(sample, oal_openapi::sample::sample(&spec, &item, 7)?);
+
+    let id = sample["id"].as_i64().unwrap();
+    assert!((10..=20).contains(&id), "id out of bounds: {id}");
+    assert!(["book", "film"].contains(&sample["kind"].as_str().unwrap()));
+    let price = sample["price"].as_f64().unwrap();
+    assert!((1.0..=2.0).contains(&price), "price out of bounds: {price}");
+    let code = sample["code"].as_str().unwrap();
+    assert!((2..=4).contains(&code.len()), "code out of bounds: {code}");
+    assert_eq!(sample["email"], "user@example.com");
+    assert_eq!(sample["size"], "large");
+    let tags = sample["tags"].as_array().unwrap();
+    assert_eq!(tags.len(), 2);
+    for tag in tags {
+        let label = tag["label"].as_str().unwrap().as_bytes();
+        assert_eq!(label.len(), 6, "label does not match: {tag}");
+        assert!(label[..3].iter().all(u8::is_ascii_lowercase));
+        assert_eq!(label[3], b'-');
+        assert!(label[4..].iter().all(u8::is_ascii_digit));
+    }
+
+    let api = oal_openapi::Builder::new(spec.clone())
+        .with_sample_examples(true)
+        .with_example_seed(7)
+        .into_openapi()?;
+    let get = api.paths.paths["/items"]
+        .as_item()
+        .unwrap()
+        .get
+        .as_ref()
+        .unwrap();
+    let res = get.responses.default.as_ref().unwrap().as_item().unwrap();
+    assert_eq!(res.content["application/json"].example, Some(sample));
+    // Contents with examples of their own are left as is.
+    let post = api.paths.paths["/items"]
+        .as_item()
+        .unwrap()
+        .post
+        .as_ref()
+        .unwrap();
+    let body = post.request_body.as_ref().unwrap().as_item().unwrap();
+    assert!(body.content["application/json"].example.is_none());
+
+    let api = oal_openapi::Builder::new(spec).into_openapi()?;
+    let get = api.paths.paths["/items"]
+        .as_item()
+        .unwrap()
+        .get
+        .as_ref()
+        .unwrap();
+    let res = get.responses.default.as_ref().unwrap().as_item().unwrap();
+    assert!(res.content["application/json"].example.is_none());
+
+    Ok(())
+}
+
+#[test]
+fn sample_limits() -> anyhow::Result<()> {
+    let code = r#"
+        let @big = {
+            'items! [int] `minItems: 1000000`,
+            'text! str `minLength: 1000000`,
+            'exact! str `pattern: "^a{1000000}$"`,
+            'nested! str `pattern: "^(a{16}){16}$"`,
+            'top! int `minimum: 9223372036854775807, exclusiveMinimum: true`,
+            'step! int `minimum: 9223372036854775800, multipleOf: 1000`,
+            'deep! [[[[int]]]] `minItems: 1000`
+        };
+        res /big on get -> @big;
+    "#;
+    let spec = compile(code)?;
+
+    let big = schema(&spec, "@big");
+    let sample = oal_openapi::sample::sample(&spec, &big, 7)?;
+    assert!(sample["items"].as_array().unwrap().len() <= 8);
+    assert!(sample["text"].as_str().unwrap().len() <= 64);
+    assert!(sample["exact"].as_str().unwrap().len() <= 64);
+    assert!(sample["nested"].as_str().unwrap().len() <= 64);
+    assert!(sample["top"].is_null());
+    assert!(sample["step"].is_null());
+    assert!(serde_json::to_string(&sample)?.len() < 64 * 1024);
+
+    Ok(())
+}
+
+#[test]
+fn schema_examples() -> anyhow::Result<()> {
+    let code = r#"
+        # example: { id: 1, tags: [new] }
+        let @item = {
+            'id int `enum: [1, 2]`,
+            'tags [str `enum: [new, old]`] `example: [old, new]`,
+            'size int `enum: [1, 2], example: 2`
+        };
+        res /items on get -> <[@item] `example: []`>;
+    "#;
+    let spec = compile(code)?;
+    let api = serde_json::to_value(oal_openapi::Builder::new(spec).into_openapi()?)?;
+
+    let item = &api["components"]["schemas"]["item"];
+    assert_eq!(
+        item["example"],
+        serde_json::json!({ "id": 1, "tags": ["new"] })
+    );
+    let props = &item["properties"];
+    assert_eq!(
+        props["id"]["example"], 1,
+        "expected the first enumerated value"
+    );
+    assert_eq!(props["tags"]["example"], serde_json::json!(["old", "new"]));
+    assert_eq!(props["tags"]["items"]["example"], "new");
+    assert_eq!(props["size"]["example"], 2);
+
+    let list = &api["paths"]["/items"]["get"]["responses"]["default"]["content"];
+    assert_eq!(
+        list["application/json"]["schema"]["example"],
+        serde_json::json!([])
+    );
+
+    // Mappings with a sequence as key have no JSON equivalent.
+    for ann in ["example", "default", "x-meta"] {
+        let code = format!("res /a on get -> {{}} `{ann}: {{ [1, 2]: a }}`;");
+        let spec = compile(&code)?;
+        let err = oal_openapi::Builder::new(spec)
+            .into_openapi()
+            .expect_err("expected an error");
+        assert!(matches!(
+            err.kind,
+            oal_compiler::errors::Kind::InvalidLiteral
+        ));
+        assert!(err.to_string().contains(ann), "{err}");
+    }
+
+    Ok(())
+}
+
+#[test]
+fn rate_limit_headers() -> anyhow::Result<()> {
+    let code = r#"
+        use "std/ratelimit" as rl;
+        res /a on get -> <status=200, headers=rl.rate & { 'ETag str }, {}> :: rl.too_many_requests;
+    "#;
+    let spec = compile(code)?;
+    let api = serde_json::to_value(oal_openapi::Builder::new(spec).into_openapi()?)?;
+
+    let responses = &api["paths"]["/a"]["get"]["responses"];
+    // JSON objects are sorted by key.
+    let names = |status: &str| -> Vec<String> {
+        let headers = responses[status]["headers"].as_object().unwrap();
+        headers.keys().cloned().collect()
+    };
+    assert_eq!(
+        names("200"),
+        [
+            "ETag",
+            "X-RateLimit-Limit",
+            "X-RateLimit-Remaining",
+            "X-RateLimit-Reset"
+        ]
+    );
+    assert_eq!(
+        names("429"),
+        [
+            "Retry-After",
+            "X-RateLimit-Limit",
+            "X-RateLimit-Remaining",
+            "X-RateLimit-Reset"
+        ]
+    );
+    assert_eq!(responses["429"]["description"], "too many requests");
+
+    Ok(())
+}
+
+#[test]
+fn header_components() -> anyhow::Result<()> {
+    let code = r#"
+        let @rateLimitHeaders = { 'X-Rate-Limit int };
+        let limit = { 'X-Rate-Limit str };
+        res /a on get -> <status=200, headers=@rateLimitHeaders & { 'ETag str }, {}>;
+        res /b on get -> <status=200, headers=@rateLimitHeaders, {}>
+                       :: <status=400, headers=limit, {}>;
+    "#;
+    let spec = compile(code)?;
+    let api = serde_json::to_value(oal_openapi::Builder::new(spec).into_openapi()?)?;
+
+    let headers = &api["components"]["headers"];
+    assert_eq!(headers.as_object().unwrap().len(), 2);
+    assert_eq!(headers["X-Rate-Limit"]["schema"]["type"], "integer");
+    assert_eq!(headers["X-Rate-Limit_2"]["schema"]["type"], "string");
+
+    let a = &api["paths"]["/a"]["get"]["responses"]["200"]["headers"];
+    assert_eq!(
+        a["X-Rate-Limit"]["$ref"],
+        "#/components/headers/X-Rate-Limit"
+    );
+    // Headers declared inline are not shared.
+    assert_eq!(a["ETag"]["schema"]["type"], "string");
+    let b = &api["paths"]["/b"]["get"]["responses"];
+    assert_eq!(
+        b["200"]["headers"]["X-Rate-Limit"]["$ref"],
+        "#/components/headers/X-Rate-Limit"
+    );
+    assert_eq!(
+        b["400"]["headers"]["X-Rate-Limit"]["$ref"],
+        "#/components/headers/X-Rate-Limit_2"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn recursive_uri_component() -> anyhow::Result<()> {
+    let code = r#"
+        let a = /b?{ 'next b };
+        let b = concat /a a;
+        res b on get -> {};
+    "#;
+    let spec = compile(code)?;
+    let api = serde_json::to_value(oal_openapi::Builder::new(spec).into_openapi()?)?;
+
+    let schemas = api["components"]["schemas"].as_object().unwrap();
+    assert_eq!(schemas.len(), 1);
+    let (name, schema) = schemas.iter().next().unwrap();
+    assert_eq!(schema["format"], "uri-reference");
+
+    let params = &api["paths"]["/a/b"]["parameters"];
+    assert_eq!(params[0]["name"], "next");
+    assert_eq!(
+        params[0]["schema"]["$ref"],
+        format!("#/components/schemas/{name}")
+    );
+
+    Ok(())
+}
+
+#[test]
+fn init_scaffold() -> anyhow::Result<()> {
+    let dir = std::env::temp_dir().join(format!("oal-init-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+
+    let files = crate::init::scaffold(&dir, true)?;
+    assert_eq!(files, vec![dir.join("oal.toml"), dir.join("src/main.oal")]);
+    assert!(dir.join("spec").is_dir());
+    assert!(
+        crate::init::scaffold(&dir, true).is_err(),
+        "expected no overwrite"
+    );
+
+    let code = std::fs::read_to_string(dir.join("src/main.oal"))?;
+    let spec = compile(&code)?;
+    assert_eq!(spec.rels.len(), 1);
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+#[test]
+fn fixtures() -> anyhow::Result<()> {
+    let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures");
+    let mut count = 0;
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path().canonicalize()?;
+        if path.extension().is_none_or(|e| e != "oal") {
+            continue;
+        }
+        let loc = oal_model::locator::Locator::from(url::Url::from_file_path(&path).unwrap());
+        let outcome = fixture::check(&crate::DefaultFileSystem, &loc, &Settings::default())?;
+        assert!(outcome.is_ok(), "fixture {loc} failed: {outcome:?}");
+        count += 1;
+    }
+    assert!(count > 0, "expected fixtures");
+    Ok(())
+}
+
+#[test]
+fn fixture_mismatches() -> anyhow::Result<()> {
+    let code = r#"//@ expect-error E0006 at 2:1
+//@ expect-warning E0015
+res /a on get -> { 'b c };
+"#;
+    let expects = fixture::expectations(code)?;
+    assert_eq!(expects.len(), 2);
+    assert_eq!(expects[0].severity, Severity::Error);
+    assert_eq!(expects[0].position, Some(Position { line: 2, column: 1 }));
+    assert_eq!(expects[1].severity, Severity::Warning);
+    assert_eq!(expects[1].line, 2);
+
+    let fs = InMemoryFileSystem::new().with_file("main.oal", code);
+    let main = InMemoryFileSystem::locator("main.oal");
+    let outcome = fixture::check(&fs, &main, &Settings::default())?;
+    assert!(!outcome.is_ok());
+    assert_eq!(outcome.missing.len(), 2);
+    assert_eq!(outcome.unexpected.len(), 1);
+    assert_eq!(outcome.unexpected[0].code, "E0006");
+    assert_eq!(
+        outcome.unexpected[0].position,
+        Some(Position {
+            line: 3,
+            column: 23
+        })
+    );
+
+    assert!(fixture::expectations("//@ expect-error E9999").is_err());
+    assert!(fixture::expectations("//@ expect-error E0006 at 0:1").is_err());
+    assert!(fixture::expectations("//@ expect-nothing").is_err());
+    assert!(fixture::expectations("//! A documentation comment.")?.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn playground_render() -> anyhow::Result<()> {
+    let pkg = std::env::temp_dir().join(format!("oal-playground-{}", std::process::id()));
+    std::fs::create_dir_all(&pkg)?;
+    std::fs::write(
+        pkg.join("oal_wasm.js"),
+        "export function compile() {} // </script>",
+    )?;
+    std::fs::write(pkg.join("oal_wasm_bg.wasm"), b"\0asm")?;
+
+    let page = crate::playground::render(&pkg, "res /a on get -> {};")?;
+    assert!(
+        !page.contains("{{"),
+        "expected all placeholders to be replaced"
+    );
+    assert!(
+        page.contains(r#""AGFzbQ==""#),
+        "expected the inlined module"
+    );
+    assert!(page.contains(r#""res /a on get -> {};""#));
+    assert!(page.contains(r"<\/script>"));
+    assert_eq!(page.matches("</script>").count(), 1);
+
+    std::fs::remove_file(pkg.join("oal_wasm_bg.wasm"))?;
+    assert!(crate::playground::render(&pkg, "").is_err());
+
+    std::fs::remove_dir_all(&pkg)?;
+    Ok(())
+}
+
+#[test]
+fn case_insensitive_collision() -> anyhow::Result<()> {
+    let fs = InMemoryFileSystem::new()
+        .case_insensitive()
+        .with_file(
+            "main.oal",
+            r#"use "common.oal" as a; use "lib/module.oal" as m;"#,
+        )
+        .with_file("common.oal", "let r = {};")
+        .with_file("lib/module.oal", r#"use "../Common.oal" as c;"#);
+
+    let common = InMemoryFileSystem::locator("COMMON.oal");
+    assert!(fs.is_valid(&common));
+    assert_eq!(
+        fs.canonicalize(&common)?,
+        InMemoryFileSystem::locator("common.oal")
+    );
+
+    let main = InMemoryFileSystem::locator("main.oal");
+    let settings = Settings::default();
+    let diags = fixture::diagnostics(&fs, &main, &settings)?;
+    assert_eq!(diags.len(), 1);
+    assert_eq!(diags[0].code, "E0020");
+    assert_eq!(diags[0].severity, Severity::Error);
+
+    Ok(())
+}
+
+#[test]
+fn checksum_verify() -> anyhow::Result<()> {
+    let document = |code: &str| -> anyhow::Result<serde_yaml::Value> {
+        Ok(oal_openapi::Builder::new(compile(code)?).into_document()?)
+    };
+
+    let mut api = document("res /a on get -> <{ 'id int }>;")?;
+    let sum = checksum::checksum(&api);
+    checksum::embed(&mut api);
+    assert_eq!(
+        checksum::checksum(&api),
+        sum,
+        "expected the extension to be ignored"
+    );
+    let committed = serde_yaml::to_string(&api)?;
+
+    checksum::verify(&committed, &api)?;
+
+    let changed = document("res /a on get -> <{ 'id str }>;")?;
+    let err = checksum::verify(&committed, &changed).expect_err("expected drift");
+    assert!(err.to_string().contains("out of date"));
+
+    let edited = committed.replace("type: integer", "type: number");
+    let err = checksum::verify(&edited, &api).expect_err("expected an edit");
+    assert!(err.to_string().contains("modified"));
+
+    let unsigned = serde_yaml::to_string(&document("res /a on get -> <{}>;")?)?;
+    assert!(checksum::verify(&unsigned, &api).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn source_metadata() {
+    let mut api = serde_yaml::from_str("{ openapi: 3.0.3, info: { title: Test } }").unwrap();
+    let sources = [("main.oal".to_owned(), "res /a on get -> {};")];
+    checksum::embed_metadata(&mut api, sources);
+    let meta = &api["info"][checksum::METADATA_EXTENSION];
+    assert_eq!(meta["version"], env!("CARGO_PKG_VERSION"));
+    let hash = meta["sources"]["main.oal"].as_str().unwrap();
+    assert_eq!(hash.len(), 64);
+
+    let mut other = api.clone();
+    let sources = [("main.oal".to_owned(), "res /b on get -> {};")];
+    checksum::embed_metadata(&mut other, sources);
+    assert_ne!(
+        other["info"][checksum::METADATA_EXTENSION]["sources"]["main.oal"],
+        hash
+    );
+}
+
+#[test]
+fn reproducible_output() -> anyhow::Result<()> {
+    let code = r#"
+        # examples: { zeta: "z.json", alpha: "a.json", mu: "m.json", beta: "b.json" }
+        let @item = { 'id int `minimum: 0.5, multipleOf: 0.25`, 'name str };
+        res /items/{ 'id int } on
+            get -> <status=200, headers={ 'ETag str, 'Age int }, @item>
+                :: <status=404, {}>,
+            # x-timeout-ms: 100
+            put : <@item> -> <status=204>;
+    "#;
+    let generate = || -> anyhow::Result<String> {
+        let spec = compile(code)?;
+        let api = oal_openapi::Builder::new(spec).into_openapi()?;
+        Ok(serde_yaml::to_string(&api)?)
+    };
+
+    let first = generate()?;
+    for _ in 0..8 {
+        assert_eq!(generate()?, first, "expected byte-identical output");
+    }
+
+    let api: serde_yaml::Value = serde_yaml::from_str(&first)?;
+    let content = &api["paths"]["/items/{id}"]["get"]["responses"]["200"]["content"];
+    let examples = content["application/json"]["examples"]
+        .as_mapping()
+        .expect("expected examples");
+    let names: Vec<_> = examples.keys().filter_map(|k| k.as_str()).collect();
+    assert_eq!(names, ["zeta", "alpha", "mu", "beta"]);
+
+    Ok(())
+}
+
+#[test]
+fn serve_compile() -> anyhow::Result<()> {
+    let settings = Settings::default();
+
+    let req = serde_json::from_value(serde_json::json!({
+        "main": "main.oal",
+        "modules": {
+            "main.oal": r#"use "lib/common.oal" as c; res /a on get -> c.ok;"#,
+            "lib/common.oal": "let ok = <status=200, {}>;",
+        },
+    }))?;
+    let res = serve::compile(req, &settings);
+    assert_eq!(res.status, 200);
+    assert!(res.body["api"]["paths"]["/a"]["get"].is_object());
+    assert_eq!(res.body["diagnostics"], serde_json::json!([]));
+
+    let req = serde_json::from_value(serde_json::json!({
+        "source": "let x = y;\nres /a on get -> x;",
+    }))?;
+    let res = serve::compile(req, &settings);
+    assert_eq!(res.status, 422);
+    assert!(res.body["api"].is_null());
+    let diag = &res.body["diagnostics"][0];
+    assert_eq!(diag["code"], "E0006");
+    assert_eq!(diag["category"], "type");
+    assert_eq!(diag["module"], "main.oal");
+    assert_eq!((&diag["line"], &diag["column"]), (&1.into(), &9.into()));
+
+    let req = serde_json::from_value(serde_json::json!({
+        "main": "main.oal",
+        "modules": { "main.oal": r#"use "/etc/hosts.oal" as h; res /a on get -> {};"# },
+    }))?;
+    let res = serve::compile(req, &settings);
+    assert_eq!(res.status, 422, "expected no access to the file system");
+
+    let req = serde_json::from_value(serde_json::json!({ "main": "main.oal" }))?;
+    assert_eq!(serve::compile(req, &settings).status, 400);
+
+    let modules = serde_json::json!({
+        "main.oal": r#"use "shared/common" as c; res /a on get -> c.ok;"#,
+        "lib/common.oal": "let ok = <status=200, {}>;",
+    });
+    let req = serde_json::from_value(serde_json::json!({
+        "main": "main.oal",
+        "modules": modules,
+        "settings": { "paths": { "shared": "lib" } },
+    }))?;
+    let res = serve::compile(req, &settings);
+    assert_eq!(res.status, 200, "expected aliases of request modules");
+
+    let req = serde_json::from_value(serde_json::json!({
+        "main": "main.oal",
+        "modules": modules,
+        "settings": { "paths": { "std": "lib" } },
+    }))?;
+    assert_eq!(serve::compile(req, &settings).status, 400);
+
+    Ok(())
+}
+
+#[test]
+fn serve_envelope() -> anyhow::Result<()> {
+    let settings = Settings {
+        envelope: Some("wrap".to_owned()),
+        ..Default::default()
+    };
+    let req = serde_json::from_value(serde_json::json!({
+        "source": "res /a on get -> <status=200, { 'id int }> :: <status=404, {}>;",
+    }))?;
+    let res = serve::compile(req, &settings);
+    assert_eq!(res.status, 200);
+    let responses = &res.body["api"]["paths"]["/a"]["get"]["responses"];
+    let schema = &responses["200"]["content"]["application/json"]["schema"];
+    assert_eq!(schema["required"], serde_json::json!(["data"]));
+    assert_eq!(
+        schema["properties"]["data"]["properties"]["id"]["type"],
+        "integer"
+    );
+    assert_eq!(schema["properties"]["meta"]["type"], "object");
+    let schema = &responses["404"]["content"]["application/json"]["schema"];
+    assert!(schema["properties"].get("data").is_none());
+
+    let settings = Settings {
+        envelope: Some("other".to_owned()),
+        ..Default::default()
+    };
+    let req = serde_json::from_value(serde_json::json!({ "source": "res /a on get -> {};" }))?;
+    assert_eq!(serve::compile(req, &settings).status, 400);
+
+    Ok(())
+}
+
+#[test]
+fn serve_safe_mode() -> anyhow::Result<()> {
+    // A large but valid program, with settings attempting to lift the limits.
+    let source = format!("{}res /a on get -> {{}};", "// padding\n".repeat(32 * 1024));
+    let request = || {
+        serde_json::from_value::<serve::Request>(serde_json::json!({
+            "source": source,
+            "settings": { "max_input_size": 1 << 30 },
+        }))
+    };
+
+    let res = serve::compile(request()?, &Settings::default());
+    assert_eq!(res.status, 200);
+
+    let settings = Settings {
+        safe_mode: Some(true),
+        ..Default::default()
+    };
+    let res = serve::compile(request()?, &settings);
+    assert_eq!(res.status, 422);
+    assert_eq!(res.body["diagnostics"][0]["code"], "E0021");
+
+    let req = serde_json::from_value(serde_json::json!({
+        "source": "res /a on get -> {};",
+        "settings": { "safe_mode": false },
+    }))?;
+    assert_eq!(serve::compile(req, &settings).status, 200);
+
+    let req = serde_json::from_value(serde_json::json!({
+        "source": "res /a on get -> {};",
+        "settings": { "sample_examples": true },
+    }))?;
+    assert_eq!(serve::compile(req, &settings).status, 400);
+
+    Ok(())
+}
+
+#[test]
+fn serve_respond() {
+    let settings = Settings::default();
+    let respond = |input: &str| serve::respond(&mut input.as_bytes(), &settings).status;
+
+    let body = r#"{ "source": "res /a on get -> {};" }"#;
+    let req = format!(
+        "POST /compile HTTP/1.1\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{body}",
+        body.len()
+    );
+    assert_eq!(respond(&req), 200);
+    assert_eq!(respond("GET /compile HTTP/1.1\r\n\r\n"), 405);
+    assert_eq!(respond("POST /other HTTP/1.1\r\n\r\n"), 404);
+    assert_eq!(respond("POST /compile HTTP/1.1\r\n\r\n"), 411);
+    assert_eq!(
+        respond("POST /compile HTTP/1.1\r\nContent-Length: 999999999\r\n\r\n"),
+        413
+    );
+    assert_eq!(
+        respond("POST /compile HTTP/1.1\r\nContent-Length: 10\r\n\r\n{}"),
+        400
+    );
+    assert_eq!(
+        respond("POST /compile HTTP/1.1\r\nContent-Length: 2\r\n\r\n{}"),
+        400
+    );
+}
+
+#[test]
+fn module_docs() -> anyhow::Result<()> {
+    let fs = InMemoryFileSystem::new()
+        .with_file(
+            "main.oal",
+            r#"
+use "lib/common.oal" as c;
+use "std/ratelimit" as rl;
+res /a on get -> c.ok;
+"#,
+        )
+        .with_file(
+            "lib/common.oal",
+            r#"
+use "types.oal";
+# description: a successful response
+# deprecated: true
+let ok = <status=200, {}>;
+"#,
+        )
+        .with_file("lib/types.oal", "let id = int;");
+    let main = InMemoryFileSystem::locator("main.oal");
+    let mods = oal_compiler::module::load(&mut FileSystemLoader(&fs), &main)?;
+    let root = InMemoryFileSystem::locator("./");
+    let pages = docs::render(&fs, &mods, &root)?;
+
+    let paths: Vec<_> = pages.iter().map(|p| p.path.as_str()).collect();
+    assert_eq!(
+        paths,
+        ["index.md", "main.md", "lib/common.md", "lib/types.md"]
+    );
+    assert!(pages[0].text.contains("- [lib/common](lib/common.md)"));
+
+    let main = &pages[1].text;
+    assert!(main.contains("- [lib/common.oal](lib/common.md) as `c`"));
+    assert!(main.contains("- `std/ratelimit` as `rl`"));
+
+    let common = &pages[2].text;
+    assert!(common.contains("- [types.oal](types.md)\n"));
+    assert!(common.contains("### `ok`\n\na successful response\n\n- `deprecated`: true\n"));
+    assert!(common.contains("```\nlet ok = <status=200, {}>;\n```"));
+
+    Ok(())
+}
+
+#[test]
+fn module_docs_unique_paths() -> anyhow::Result<()> {
+    let fs = InMemoryFileSystem::new()
+        .with_file(
+            "api/main.oal",
+            r#"
+use "common.oal";
+use "index.oal";
+use "../shared/common.oal" as shared;
+"#,
+        )
+        .with_file("api/common.oal", "let a = int;")
+        .with_file("api/index.oal", "let b = int;")
+        .with_file("shared/common.oal", "let c = int;");
+    let main = InMemoryFileSystem::locator("api/main.oal");
+    let mods = oal_compiler::module::load(&mut FileSystemLoader(&fs), &main)?;
+    let root = InMemoryFileSystem::locator("api/");
+    let pages = docs::render(&fs, &mods, &root)?;
+
+    let mut paths: Vec<_> = pages.iter().map(|p| p.path.as_str()).collect();
+    paths.sort();
+    assert_eq!(
+        paths,
+        [
+            "common-2.md",
+            "common.md",
+            "index-2.md",
+            "index.md",
+            "main.md"
+        ]
+    );
+    assert!(pages[0].text.starts_with("# "));
+    assert!(pages.iter().any(|p| p.text.contains("let c = int;")));
+
+    Ok(())
+}
+
+#[test]
+fn repl_session() -> anyhow::Result<()> {
+    let fs = InMemoryFileSystem::new().with_file("main.oal", "let id = 'id int;");
+    let mut session = Session::new(fs, InMemoryFileSystem::locator("main.oal"));
+
+    assert_eq!(session.eval("let f x = x & { id };")?, "");
+    let out = session.eval("f { 'name str }")?;
+    assert!(out.starts_with("object\nallOf:\n"), "{out}");
+    assert!(out.contains("name:\n      type: string"), "{out}");
+    assert!(out.contains("id:\n      type: integer"), "{out}");
+
+    assert_eq!(session.eval("f")?, "function[object -> object]\n");
+    assert!(session
+        .eval("/a on get -> str")?
+        .starts_with("relation\nget:\n"));
+
+    // Invalid declarations are not kept in the session.
+    assert!(session.eval("let g = undefined;").is_err());
+    assert!(session.eval("g").is_err());
+    assert_eq!(session.eval("  ")?, "");
+
+    Ok(())
+}
+
+#[test]
+fn annotations_inventory() -> anyhow::Result<()> {
+    let fs = InMemoryFileSystem::new()
+        .with_file(
+            "main.oal",
+            "use \"module.oal\" as m;\n# description: a\nlet a = m.b `owner: team`;\nres /a on get -> a;",
+        )
+        .with_file(
+            "module.oal",
+            "use \"std/ratelimit\";\n# description: b, x-internal: true\nlet b = {};",
+        );
+    let main = InMemoryFileSystem::locator("main.oal");
+    let mods = oal_compiler::module::load(&mut FileSystemLoader(&fs), &main)?;
+    let usages = annotations::inventory(&fs, &mods)?;
+
+    let keys: Vec<_> = usages.iter().map(|u| (u.key.as_str(), u.count())).collect();
+    assert_eq!(keys, [("description", 2), ("owner", 1), ("x-internal", 1)]);
+    assert!(usages[0].known);
+    assert!(!usages[1].known);
+    assert!(usages[2].known);
+
+    let module = InMemoryFileSystem::locator("module.oal");
+    assert_eq!(
+        usages[0].locations,
+        [
+            (main.clone(), Position { line: 2, column: 1 }),
+            (module, Position { line: 2, column: 1 })
+        ]
+    );
+    assert_eq!(
+        usages[1].locations,
+        [(
+            main,
+            Position {
+                line: 3,
+                column: 13
+            }
+        )]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn import_aliases() -> anyhow::Result<()> {
+    let dir = std::env::temp_dir().join(format!("oal-aliases-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("app/nested"))?;
+    std::fs::create_dir_all(dir.join("lib"))?;
+    std::fs::write(
+        dir.join("app/nested/main.oal"),
+        r#"use "shared/pagination" as p; use "common" as c; res p.r; res c.r;"#,
+    )?;
+    std::fs::write(dir.join("lib/pagination.oal"), "let r = /a on get -> {};")?;
+    std::fs::write(dir.join("lib/common.oal"), "let r = /b on get -> {};")?;
+
+    let root = oal_model::locator::Locator::from(url::Url::from_directory_path(&dir).unwrap());
+    let settings = Settings {
+        paths: HashMap::from([
+            ("shared".to_owned(), root.join("lib")?.to_string()),
+            (
+                "common".to_owned(),
+                root.join("lib/common.oal")?.to_string(),
+            ),
+        ]),
+        ..Default::default()
+    };
+
+    let resolver = settings.resolver()?;
+    let main = root.join("app/nested/main.oal")?;
+    assert_eq!(
+        resolver.resolve(&main, "shared/pagination")?,
+        root.join("lib/pagination.oal")?
+    );
+    assert_eq!(
+        resolver.resolve(&main, "shared/v1/pagination.oal")?,
+        root.join("lib/v1/pagination.oal")?
+    );
+    assert_eq!(
+        resolver.resolve(&main, "sharedx.oal")?,
+        root.join("app/nested/sharedx.oal")?
+    );
+    assert_eq!(
+        resolver.resolve(&main, "std/pagination")?.url().as_str(),
+        "oal:std/pagination"
+    );
+    for alias in ["std", "std/", "std/pagination"] {
+        let paths = HashMap::from([(alias.to_owned(), root.join("lib")?.to_string())]);
+        let err = crate::resolver::Resolver::new(&paths).expect_err("expected a collision");
+        assert!(matches!(
+            err.kind,
+            oal_compiler::errors::Kind::ModuleCollision
+        ));
+    }
+
+    let proc = crate::cli::Processor::new().with_settings(settings);
+    let mods = proc.load(&main)?;
+    assert_eq!(mods.len(), 3);
+    assert_eq!(
+        mods.imports(&main),
+        vec![
+            root.join("lib/pagination.oal")?,
+            root.join("lib/common.oal")?
+        ]
+    );
+    let spec = oal_compiler::eval::eval(&mods)?;
+    assert_eq!(spec.rels.len(), 2);
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+#[test]
+fn import_aliases_in_fix_and_repl() -> anyhow::Result<()> {
+    let paths = HashMap::from([(
+        "shared".to_owned(),
+        InMemoryFileSystem::locator("lib/").to_string(),
+    )]);
+    let resolver = crate::resolver::Resolver::new(&paths)?;
+    let main = InMemoryFileSystem::locator("app/main.oal");
+
+    let fs = InMemoryFileSystem::new()
+        .with_file(
+            "app/main.oal",
+            "use \"shared/lib\" as l;\nlet a = {};\nres r on get -> a;\n",
+        )
+        .with_file("lib/lib.oal", "let r = /a;");
+    let applied = fix::apply(&fs, &main, &resolver)?;
+    let titles = applied.iter().map(|f| f.title.as_str()).collect::<Vec<_>>();
+    assert_eq!(titles, ["qualify as l.r"]);
+
+    let fs = InMemoryFileSystem::new()
+        .with_file("app/main.oal", "use \"shared/lib\" as l;")
+        .with_file("lib/lib.oal", "let r = /a;");
+    let mut session = Session::new(fs, main).with_resolver(resolver);
+    assert!(session.eval("l.r")?.starts_with("uri\n"));
+
+    Ok(())
+}
+
+/// Serves remote modules from memory, counting the fetches.
+struct StaticFetcher {
+    modules: HashMap<String, String>,
+    fetches: std::rc::Rc<std::cell::Cell<usize>>,
+}
+
+impl crate::remote::Fetcher for StaticFetcher {
+    fn fetch(&self, url: &url::Url) -> Result<String, crate::Error> {
+        self.fetches.set(self.fetches.get() + 1);
+        self.modules
+            .get(url.as_str())
+            .cloned()
+            .ok_or_else(|| crate::Error::Remote(format!("not found: {url}")))
+    }
+}
+
+#[test]
+fn remote_imports() -> anyhow::Result<()> {
+    use crate::remote::{Lockfile, RemoteFileSystem, CACHE_DIR, LOCKFILE};
+
+    let dir = std::env::temp_dir().join(format!("oal-remote-{}", std::process::id()));
+    std::fs::create_dir_all(&dir)?;
+    std::fs::write(
+        dir.join("main.oal"),
+        r#"use "https://example.com/models/common.oal" as c; res c.r;"#,
+    )?;
+    let url = "https://example.com/models/common.oal";
+    let fetcher = |code: &str, fetches: &std::rc::Rc<std::cell::Cell<usize>>| StaticFetcher {
+        modules: HashMap::from([
+            (url.to_owned(), code.to_owned()),
+            (
+                "https://example.com/models/nested.oal".to_owned(),
+                "let s = {};".to_owned(),
+            ),
+        ]),
+        fetches: fetches.clone(),
+    };
+    let main =
+        oal_model::locator::Locator::from(url::Url::from_file_path(dir.join("main.oal")).unwrap());
+    let fetches = std::rc::Rc::new(std::cell::Cell::new(0));
+
+    // The first load fetches the remote modules, relative imports included.
+    let code = r#"use "nested.oal" as n; let r = /a on get -> n.s;"#;
+    let fs = RemoteFileSystem::new(&dir).with_fetcher(fetcher(code, &fetches));
+    let proc = crate::cli::Processor::new().with_file_system(fs);
+    let mods = proc.load(&main)?;
+    assert_eq!(mods.len(), 3);
+    assert_eq!(oal_compiler::eval::eval(&mods)?.rels.len(), 1);
+    assert_eq!(fetches.get(), 2);
+
+    let lock: Lockfile = toml::from_str(&std::fs::read_to_string(dir.join(LOCKFILE))?)?;
+    assert_eq!(lock.modules.len(), 2);
+    assert!(lock.modules[url].starts_with("sha256:"));
+
+    // Later loads read the cache, even offline.
+    let fs = RemoteFileSystem::new(&dir)
+        .with_fetcher(fetcher(code, &fetches))
+        .with_offline(true);
+    let proc = crate::cli::Processor::new().with_file_system(fs);
+    assert_eq!(proc.load(&main)?.len(), 3);
+    assert_eq!(fetches.get(), 2);
+
+    // A module changed since it was locked is rejected once out of the cache.
+    std::fs::remove_dir_all(dir.join(CACHE_DIR))?;
+    let fs = RemoteFileSystem::new(&dir).with_fetcher(fetcher("let r = /b;", &fetches));
+    let err = fs.read_file(&oal_model::locator::Locator::try_from(url)?);
+    assert!(matches!(err, Err(crate::Error::Remote(ref msg)) if msg.contains("lockfile")));
+
+    let fs = RemoteFileSystem::new(&dir).with_offline(true);
+    let err = fs.read_file(&oal_model::locator::Locator::try_from(url)?);
+    assert!(matches!(err, Err(crate::Error::Remote(ref msg)) if msg.contains("disabled")));
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+#[test]
+fn config_format_inference() -> anyhow::Result<()> {
+    let dir = std::env::temp_dir().join(format!("oal-format-{}", std::process::id()));
+    std::fs::create_dir_all(&dir)?;
+    let cfg = dir.join("oal.toml");
+    std::fs::write(
+        &cfg,
+        r#"
+[[api]]
+main = "main.oal"
+target = "openapi.json"
+
+[[api]]
+main = "main.oal"
+target = "openapi.yaml"
+
+[[api]]
+main = "main.oal"
+target = "openapi.txt"
+format = "json"
+"#,
+    )?;
+
+    let config = Config::parse_from(["oal-cli"], Some(&cfg))?;
+    let formats: Vec<_> = config.builds()?.iter().map(|b| b.format).collect();
+    assert_eq!(
+        formats,
+        [TargetFormat::Json, TargetFormat::Yaml, TargetFormat::Json]
+    );
+
+    // The command line target takes precedence, with the format inferred from its extension.
+    let config = Config::parse_from(["oal-cli", "-t", "out.json"], Some(&cfg))?;
+    let builds: Vec<Build> = config.builds()?;
+    assert_eq!(builds.len(), 1);
+    assert_eq!(builds[0].format, TargetFormat::Json);
+
+    let config = Config::parse_from(["oal-cli", "-t", "out.json", "-f", "yaml"], Some(&cfg))?;
+    assert_eq!(config.builds()?[0].format, TargetFormat::Yaml);
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+#[test]
+fn config_target_format() -> anyhow::Result<()> {
+    let dir = std::env::temp_dir().join(format!("oal-target-format-{}", std::process::id()));
+    std::fs::create_dir_all(&dir)?;
+    let cfg = dir.join("oal.toml");
+    let json = InMemoryFileSystem::locator("openapi.json");
+    let yaml = InMemoryFileSystem::locator("openapi.yaml");
+
+    std::fs::write(
+        &cfg,
+        "[api]\nmain = \"main.oal\"\ntarget = \"openapi.json\"\n",
+    )?;
+    let config = Config::parse_from(["oal-cli"], Some(&cfg))?;
+    assert_eq!(config.format(&json), TargetFormat::Json);
+    assert_eq!(config.format(&yaml), TargetFormat::Yaml);
+    let config = Config::parse_from(["oal-cli", "--format", "json"], Some(&cfg))?;
+    assert_eq!(config.format(&yaml), TargetFormat::Json);
+
+    // The configuration key takes precedence over the extension, and the option over both.
+    std::fs::write(
+        &cfg,
+        "[api]\nmain = \"main.oal\"\ntarget = \"openapi.json\"\nformat = \"yaml\"\n",
+    )?;
+    let config = Config::parse_from(["oal-cli"], Some(&cfg))?;
+    assert_eq!(config.format(&json), TargetFormat::Yaml);
+    let config = Config::parse_from(["oal-cli", "-f", "json"], Some(&cfg))?;
+    assert_eq!(config.format(&json), TargetFormat::Json);
+
+    std::fs::write(&cfg, "[api]\nmain = \"main.oal\"\nformat = \"xml\"\n")?;
+    assert!(Config::parse_from(["oal-cli"], Some(&cfg)).is_err());
+    assert!(Config::parse_from(["oal-cli", "--format", "xml"], None).is_err());
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+#[test]
+fn config_optional_target() -> anyhow::Result<()> {
+    let config = Config::parse_from(["oal-cli", "-m", "main.oal", "check"], None)?;
+    let builds = config.builds()?;
+    assert_eq!(builds.len(), 1);
+    assert!(builds[0].target.is_none());
+    assert_eq!(builds[0].format, TargetFormat::Yaml);
+    assert!(config.target().is_err());
+
+    Ok(())
+}
+
+/// Loads a program importing the given number of modules, each declaring the given code.
+fn load_modules(count: usize, code: &str) -> anyhow::Result<oal_compiler::module::ModuleSet> {
+    let mut main = String::new();
+    let mut fs = InMemoryFileSystem::new();
+    for i in 0..count {
+        main.push_str(&format!("use \"m{i}.oal\" as m{i};\n"));
+        fs = fs.with_file(&format!("m{i}.oal"), code.replace("{i}", &i.to_string()));
+    }
+    main.push_str("res /a on get -> {};\n");
+    let fs = fs.with_file("main.oal", main);
+    let main = InMemoryFileSystem::locator("main.oal");
+    oal_compiler::module::load(&mut FileSystemLoader(&fs), &main)
+}
+
+/// Returns the locators of the modules of the given warnings.
+fn warning_modules(warnings: &[oal_compiler::errors::Error]) -> Vec<String> {
+    warnings
+        .iter()
+        .filter_map(|w| w.span())
+        .map(|s| s.locator().url().path().to_owned())
+        .collect()
+}
+
+#[test]
+fn limits_warning_order() -> anyhow::Result<()> {
+    let mods = load_modules(8, "let @d{i} = { 'a { 'b {} } };")?;
+    let spec = oal_compiler::eval::eval(&mods)?;
+    let limits = oal_compiler::limits::Limits {
+        max_depth: Some(1),
+        ..Default::default()
+    };
+    let warnings = oal_compiler::limits::check_limits(&mods, &spec, &limits);
+    let modules = warning_modules(&warnings);
+    assert_eq!(modules.len(), 8);
+    let mut sorted = modules.clone();
+    sorted.sort();
+    assert_eq!(modules, sorted, "expected the warnings in module order");
+
+    Ok(())
+}
+
+#[test]
+fn lint_warning_order() -> anyhow::Result<()> {
+    let mods = load_modules(8, "let unused{i} = {};")?;
+    let spec = oal_compiler::eval::eval(&mods)?;
+    let warnings = oal_compiler::lint::lint(&mods, &spec);
+    let warnings = warnings
+        .into_iter()
+        .filter(|w| matches!(w.kind, oal_compiler::errors::Kind::UnusedDeclaration))
+        .collect::<Vec<_>>();
+    let modules = warning_modules(&warnings);
+    assert_eq!(modules.len(), 8);
+    let mut sorted = modules.clone();
+    sorted.sort();
+    assert_eq!(modules, sorted, "expected the warnings in module order");
+
+    Ok(())
+}
@@ -0,0 +1,210 @@
+//! Compiles every project of the examples directory and compares the generated descriptions
+//! with the committed ones.
+//!
+//! A project is a directory with an `oal.toml` configuration. Set `OAL_UPDATE_EXAMPLES=1`
+//! to overwrite the committed descriptions with the generated ones.
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// The environment variable requesting the committed descriptions to be updated.
+const UPDATE: &str = "OAL_UPDATE_EXAMPLES";
+
+/// The prefix of the identifiers generated for anonymous schema components.
+const HASH_PREFIX: &str = "hash-";
+
+/// A build target of an example project.
+struct Target {
+    main: String,
+    target: String,
+    base: Option<String>,
+    format: Option<String>,
+}
+
+/// Returns the configuration files of the projects under the given directory, recursively.
+fn projects(dir: &Path, out: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    let mut entries = std::fs::read_dir(dir)?
+        .map(|e| e.map(|e| e.path()))
+        .collect::<std::io::Result<Vec<_>>>()?;
+    entries.sort();
+    for path in entries {
+        if path.is_dir() {
+            projects(&path, out)?;
+        } else if path.file_name().is_some_and(|n| n == "oal.toml") {
+            out.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Returns the build targets declared by a project configuration.
+fn targets(config: &Path) -> anyhow::Result<Vec<Target>> {
+    let file: toml::Table = std::fs::read_to_string(config)?.parse()?;
+    let apis = match file.get("api") {
+        Some(toml::Value::Table(api)) => vec![api.clone()],
+        Some(toml::Value::Array(apis)) => {
+            apis.iter().filter_map(|a| a.as_table().cloned()).collect()
+        }
+        _ => Vec::new(),
+    };
+    let get =
+        |api: &toml::Table, key: &str| api.get(key).and_then(|v| v.as_str()).map(String::from);
+    apis.iter()
+        .map(|api| {
+            Ok(Target {
+                main: get(api, "main").ok_or_else(|| anyhow::anyhow!("main not specified"))?,
+                target: get(api, "target")
+                    .ok_or_else(|| anyhow::anyhow!("target not specified"))?,
+                base: get(api, "base"),
+                format: get(api, "format"),
+            })
+        })
+        .collect()
+}
+
+/// Replaces the generated identifiers, which depend on the location of the sources,
+/// by their order of appearance.
+fn normalize(text: &str) -> String {
+    let mut seen: Vec<&str> = Vec::new();
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find(HASH_PREFIX) {
+        out.push_str(&rest[..start]);
+        let tail = &rest[start + HASH_PREFIX.len()..];
+        let len = tail
+            .find(|c: char| !c.is_ascii_hexdigit())
+            .unwrap_or(tail.len());
+        let hash = &tail[..len];
+        let n = match seen.iter().position(|h| *h == hash) {
+            Some(n) => n,
+            None => {
+                seen.push(hash);
+                seen.len() - 1
+            }
+        };
+        out.push_str(&format!("{HASH_PREFIX}{n}"));
+        rest = &tail[len..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Checks the structure of a generated description.
+fn validate(text: &str) -> anyhow::Result<()> {
+    let doc: serde_yaml::Value = serde_yaml::from_str(text)?;
+    let version = doc["openapi"]
+        .as_str()
+        .ok_or_else(|| anyhow::anyhow!("no openapi version"))?;
+    if !doc["info"].is_mapping() {
+        anyhow::bail!("no info object");
+    }
+    // The model of descriptions only covers OpenAPI 3.0.
+    if version.starts_with("3.0") {
+        serde_yaml::from_value::<openapiv3::OpenAPI>(doc)?;
+    }
+    Ok(())
+}
+
+/// Returns the first line differing between the committed and the generated descriptions.
+fn first_difference(committed: &str, generated: &str) -> String {
+    let mut committed_lines = committed.lines();
+    let mut generated_lines = generated.lines();
+    for n in 1.. {
+        match (committed_lines.next(), generated_lines.next()) {
+            (Some(c), Some(g)) if c == g => continue,
+            (None, None) => break,
+            (c, g) => {
+                return format!(
+                    "line {n}:\n  committed: {}\n  generated: {}",
+                    c.unwrap_or("<end of file>"),
+                    g.unwrap_or("<end of file>")
+                )
+            }
+        }
+    }
+    String::new()
+}
+
+/// Compiles a build target of a project into a temporary file, returning its content.
+fn compile(config: &Path, target: &Target, n: usize) -> anyhow::Result<String> {
+    let ext = Path::new(&target.target)
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("yaml");
+    let out = std::env::temp_dir().join(format!("oal-example-{}-{n}.{ext}", std::process::id()));
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_oal-cli"));
+    cmd.arg("--quiet")
+        .arg("--no-metadata")
+        .arg("--conf")
+        .arg(config)
+        .arg("--main")
+        .arg(&target.main)
+        .arg("--target")
+        .arg(&out);
+    if let Some(ref base) = target.base {
+        cmd.arg("--base").arg(base);
+    }
+    if let Some(ref format) = target.format {
+        cmd.arg("--format").arg(format);
+    }
+    let output = cmd.output()?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "compilation failed:\n{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+    let text = std::fs::read_to_string(&out)?;
+    std::fs::remove_file(&out)?;
+    Ok(text)
+}
+
+#[test]
+fn examples() -> anyhow::Result<()> {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("../examples")
+        .canonicalize()?;
+    let mut configs = Vec::new();
+    projects(&root, &mut configs)?;
+    assert!(!configs.is_empty(), "no example project found");
+
+    let update = std::env::var_os(UPDATE).is_some();
+    let mut failures = Vec::new();
+    let mut n = 0;
+    for config in configs.iter() {
+        let dir = config
+            .parent()
+            .expect("configuration should have a directory");
+        for target in targets(config)? {
+            n += 1;
+            let name = dir.join(&target.target);
+            let generated = match compile(config, &target, n) {
+                Ok(text) => text,
+                Err(err) => {
+                    failures.push(format!("{}: {err}", name.display()));
+                    continue;
+                }
+            };
+            if let Err(err) = validate(&generated) {
+                failures.push(format!("{}: invalid description: {err}", name.display()));
+                continue;
+            }
+            if update {
+                std::fs::write(&name, &generated)?;
+                continue;
+            }
+            let committed = std::fs::read_to_string(&name).unwrap_or_default();
+            let (committed, generated) = (normalize(&committed), normalize(&generated));
+            if committed != generated {
+                failures.push(format!(
+                    "{}: out of date, run with {UPDATE}=1 to update, first difference at {}",
+                    name.display(),
+                    first_difference(&committed, &generated)
+                ));
+            }
+        }
+    }
+
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+    Ok(())
+}
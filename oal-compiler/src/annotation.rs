@@ -1,6 +1,29 @@
+use crate::errors::{Error, Kind, Result};
+use crate::spec::{
+    ApiKeyLocation, Callbacks, Example, Examples, Extensions, Info, Keywords, OAuthFlow,
+    OAuthFlowKind, SecurityRequirement, SecurityScheme, SecuritySchemeKind, SecuritySchemes,
+    Server, Servers, Tag, Translations,
+};
+use crate::tree::Core;
+use indexmap::IndexMap;
+use oal_model::grammar::AbstractSyntaxNode;
+use oal_syntax::parser as syn;
 use serde_yaml::{Mapping, Sequence, Value};
 use std::collections::HashMap;
 
+/// The fields of the general information of the document.
+const INFO_FIELDS: [&str; 3] = ["title", "version", "description"];
+
+/// Returns the text of a scalar value, e.g. of a version given as a number.
+fn scalar_string(v: &Value) -> Option<String> {
+    match v {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
 /// An indexed annotation set.
 #[derive(Clone, Debug, PartialEq, Eq, Default)]
 pub struct Annotation {
@@ -48,12 +71,110 @@ fn test_deep_extend() {
     assert_eq!(m1, exp);
 }
 
+/// The annotation controlling inheritance from a reused definition.
+const INHERIT: &str = "inherit";
+
+/// The annotations subject to inheritance control.
+const INHERITABLE: [&str; 3] = ["title", "description", "examples"];
+
+/// The annotations interpreted by the compiler, as opposed to custom keywords.
+const KNOWN: &[&str] = &[
+    INHERIT,
+    "title",
+    "description",
+    "summary",
+    "required",
+    "deprecated",
+    "nullable",
+    "default",
+    "discriminator",
+    "const",
+    "stability",
+    "examples",
+    "example",
+    "enum",
+    "minimum",
+    "maximum",
+    "exclusiveMinimum",
+    "exclusiveMaximum",
+    "multipleOf",
+    "pattern",
+    "format",
+    "minLength",
+    "maxLength",
+    "minItems",
+    "maxItems",
+    "uniqueItems",
+    "additionalProperties",
+    "minProperties",
+    "maxProperties",
+    "style",
+    "explode",
+    "contentType",
+    "content-encoding",
+    "tags",
+    "operationId",
+    "servers",
+    "security",
+    "securitySchemes",
+    "info",
+    "webhook",
+    "callback",
+    "callbacks",
+    "tagGroup",
+    "maxBodyBytes",
+    "shadows",
+    "empty-status",
+    "componentName",
+];
+
+/// Returns true if the annotation key is interpreted by the compiler or is a vendor extension.
+///
+/// Translations such as `description@fr` are known along with their annotation.
+pub fn is_known(key: &str) -> bool {
+    let base = key.split('@').next().unwrap_or(key);
+    key.starts_with("x-") || KNOWN.contains(&base)
+}
+
 impl Annotation {
     /// Extends the set by consuming annotations from the other set.
     pub fn extend(&mut self, other: Self) {
         deep_extend_mapping(&mut self.props, other.props);
     }
 
+    /// Extends the annotations of a definition with the annotations at its usage site.
+    ///
+    /// Usage site annotations always take precedence: scalars such as `title` and `description`
+    /// are overwritten, mappings such as `examples` are merged and sequences are concatenated.
+    /// With `inherit: false` at the usage site, the title, description and examples of the
+    /// definition are discarded instead, so that only those of the usage site remain.
+    pub fn extend_usage(&mut self, usage: Self) {
+        let inherit = usage
+            .props
+            .get(Value::String(INHERIT.to_owned()))
+            .and_then(Value::as_bool)
+            .unwrap_or(true);
+        if !inherit {
+            // Translations such as `description@fr` go along with their annotation.
+            self.props.retain(|key, _| {
+                let name = key.as_str().map(|k| k.split('@').next().unwrap_or(k));
+                !name.is_some_and(|n| INHERITABLE.contains(&n))
+            });
+        }
+        self.extend(usage);
+    }
+
+    /// Moves the annotations with the given keys into a new set.
+    pub fn take(&mut self, keys: &[&str]) -> Self {
+        let mut taken = Mapping::new();
+        for key in keys {
+            if let Some(value) = self.props.remove(*key) {
+                taken.insert(Value::String((*key).to_owned()), value);
+            }
+        }
+        Annotation { props: taken }
+    }
+
     pub fn get_str(&self, s: &str) -> Option<&str> {
         self.props
             .get(Value::String(s.to_owned()))
@@ -64,6 +185,18 @@ impl Annotation {
         self.get_str(s).map(ToOwned::to_owned)
     }
 
+    /// Returns the translations of a text annotation by language tag, e.g. `fr` for `description@fr`.
+    pub fn get_translations(&self, s: &str) -> Translations {
+        let prefix = format!("{s}@");
+        self.props
+            .iter()
+            .filter_map(|(key, value)| {
+                let lang = key.as_str()?.strip_prefix(&prefix)?;
+                Some((lang.to_owned(), value.as_str()?.to_owned()))
+            })
+            .collect()
+    }
+
     pub fn get_bool(&self, s: &str) -> Option<bool> {
         self.props
             .get(Value::String(s.to_owned()))
@@ -89,32 +222,458 @@ impl Annotation {
             .map(|u| u as usize)
     }
 
-    pub fn get_enum(&self, s: &str) -> Option<Vec<String>> {
+    /// Returns the sequence of values convertible by the given function.
+    fn get_sequence<T, F>(&self, s: &str, f: F) -> Option<Vec<T>>
+    where
+        F: Fn(&Value) -> Option<T>,
+    {
         self.props
             .get(Value::String(s.to_owned()))
             .and_then(Value::as_sequence)
-            .map(|seq| {
-                seq.iter()
-                    .flat_map(Value::as_str)
-                    .map(ToOwned::to_owned)
-                    .collect()
+            .map(|seq| seq.iter().flat_map(f).collect())
+    }
+
+    pub fn get_enum(&self, s: &str) -> Option<Vec<String>> {
+        self.get_sequence(s, |v| v.as_str().map(ToOwned::to_owned))
+    }
+
+    /// Returns the servers, each given either as a URL or as a mapping with a `url`
+    /// and an optional `description`.
+    pub fn get_servers(&self, s: &str) -> Option<Servers> {
+        self.get_sequence(s, |v| match v {
+            Value::String(url) => Some(Server {
+                url: url.clone(),
+                desc: None,
+            }),
+            Value::Mapping(m) => Some(Server {
+                url: m.get("url")?.as_str()?.to_owned(),
+                desc: m
+                    .get("description")
+                    .and_then(Value::as_str)
+                    .map(ToOwned::to_owned),
+            }),
+            _ => None,
+        })
+    }
+
+    /// Returns the security requirements, each given either as a scheme name without scopes
+    /// or as a mapping from scheme names to scopes.
+    pub fn get_security(&self, s: &str) -> Option<Vec<SecurityRequirement>> {
+        self.get_sequence(s, |v| match v {
+            Value::String(name) => Some(SecurityRequirement::from([(name.clone(), Vec::new())])),
+            Value::Mapping(m) => m
+                .iter()
+                .map(|(k, v)| {
+                    let scopes = v
+                        .as_sequence()?
+                        .iter()
+                        .map(|s| s.as_str().map(ToOwned::to_owned))
+                        .collect::<Option<Vec<_>>>()?;
+                    Some((k.as_str()?.to_owned(), scopes))
+                })
+                .collect(),
+            _ => None,
+        })
+    }
+
+    /// Returns the security schemes by name, each given as an OpenAPI security scheme object.
+    pub fn get_security_schemes(&self, s: &str) -> std::result::Result<SecuritySchemes, String> {
+        let Some(value) = self.props.get(Value::String(s.to_owned())) else {
+            return Ok(SecuritySchemes::new());
+        };
+        let schemes = value
+            .as_mapping()
+            .ok_or("expected a mapping of security schemes")?;
+        schemes
+            .iter()
+            .map(|(k, v)| {
+                let name = k.as_str().ok_or("expected a security scheme name")?;
+                let scheme =
+                    security_scheme(v).map_err(|e| format!("security scheme {name}: {e}"))?;
+                Ok((name.to_owned(), scheme))
+            })
+            .collect()
+    }
+
+    /// Returns the general information of the document, given as a mapping
+    /// with an optional `title`, `version` and `description`.
+    pub fn get_info(&self, s: &str) -> std::result::Result<Info, String> {
+        let Some(value) = self.props.get(Value::String(s.to_owned())) else {
+            return Ok(Info::default());
+        };
+        let info = value
+            .as_mapping()
+            .ok_or("expected a mapping of information")?;
+        if let Some(key) = info
+            .keys()
+            .find(|k| !k.as_str().is_some_and(|k| INFO_FIELDS.contains(&k)))
+        {
+            let key = serde_yaml::to_string(key).unwrap_or_default();
+            return Err(format!("unexpected information field {}", key.trim()));
+        }
+        let field = |name: &str| match info.get(name) {
+            None => Ok(None),
+            Some(v) => scalar_string(v)
+                .map(Some)
+                .ok_or_else(|| format!("expected a scalar for the information field {name}")),
+        };
+        Ok(Info {
+            title: field("title")?,
+            version: field("version")?,
+            desc: field("description")?,
+        })
+    }
+
+    /// Returns the tags, each given either as a name or as a mapping with a `name`
+    /// and an optional `description`.
+    pub fn get_tags(&self, s: &str) -> Option<Vec<Tag>> {
+        self.get_sequence(s, |v| match v {
+            Value::String(name) => Some(Tag {
+                name: name.clone(),
+                desc: None,
+            }),
+            Value::Mapping(m) => Some(Tag {
+                name: m.get("name")?.as_str()?.to_owned(),
+                desc: m
+                    .get("description")
+                    .and_then(Value::as_str)
+                    .map(ToOwned::to_owned),
+            }),
+            _ => None,
+        })
+    }
+
+    /// Returns the runtime expressions of callback URLs, given as a mapping by callback name.
+    pub fn get_callbacks(&self, s: &str) -> std::result::Result<Callbacks, String> {
+        let Some(value) = self.props.get(Value::String(s.to_owned())) else {
+            return Ok(Callbacks::new());
+        };
+        let callbacks = value
+            .as_mapping()
+            .ok_or("expected a mapping of callbacks")?;
+        callbacks
+            .iter()
+            .map(|(k, v)| {
+                let name = k.as_str().ok_or("expected a callback name")?;
+                let expr = v
+                    .as_str()
+                    .ok_or_else(|| format!("callback {name}: expected a runtime expression"))?;
+                Ok((name.to_owned(), expr.to_owned()))
+            })
+            .collect()
+    }
+
+    /// Returns the vendor extensions, i.e. the annotations with a name starting with `x-`.
+    pub fn get_extensions(&self) -> Extensions {
+        self.props
+            .iter()
+            .filter_map(|(k, v)| {
+                let name = k.as_str().filter(|n| n.starts_with("x-"))?;
+                Some((name.to_owned(), v.clone()))
             })
+            .collect()
     }
 
-    pub fn get_props(&self, s: &str) -> Option<HashMap<String, String>> {
+    /// Returns the custom keywords, i.e. the annotations unknown to the compiler
+    /// with a name not starting with `x-`.
+    pub fn get_keywords(&self) -> Keywords {
+        self.props
+            .iter()
+            .filter_map(|(k, v)| {
+                let name = k.as_str().filter(|n| !is_known(n))?;
+                Some((name.to_owned(), v.clone()))
+            })
+            .collect()
+    }
+
+    /// Returns the named examples.
+    ///
+    /// A string is the URL of an example hosted separately, a mapping tagged as an example object,
+    /// e.g. `!example { summary: Empty, value: "" }`, gives the fields of the example,
+    /// and any other value is an inline example.
+    pub fn get_examples(&self, s: &str) -> Option<Examples> {
         self.props
             .get(Value::String(s.to_owned()))
             .and_then(Value::as_mapping)
             .map(|m| {
                 m.iter()
-                    .flat_map(|(k, v)| {
-                        let key = k.as_str().map(ToOwned::to_owned);
-                        let val = v.as_str().map(ToOwned::to_owned);
-                        key.and_then(|k| val.map(|v| (k, v)))
-                    })
+                    .filter_map(|(k, v)| Some((k.as_str()?.to_owned(), example(v).ok()?)))
                     .collect()
             })
     }
+
+    /// Checks that the example objects of the named examples are well-formed.
+    fn check_examples(&self) -> std::result::Result<(), String> {
+        let examples = self.props.get(EXAMPLES).and_then(Value::as_mapping);
+        examples
+            .into_iter()
+            .flat_map(|m| m.values())
+            .try_for_each(|v| example(v).map(|_| ()))
+    }
+}
+
+/// The annotation giving named examples.
+const EXAMPLES: &str = "examples";
+
+/// The tag of example objects, e.g. `!example { summary: Empty, value: "" }`.
+pub const EXAMPLE_TAG: &str = "example";
+
+/// The fields of an example object.
+const EXAMPLE_FIELDS: [&str; 4] = ["value", "externalValue", "summary", "description"];
+
+fn example(value: &Value) -> std::result::Result<Example, String> {
+    let tagged = match value {
+        Value::Tagged(tagged) => tagged,
+        Value::String(url) => {
+            return Ok(Example {
+                external: Some(url.clone()),
+                ..Default::default()
+            })
+        }
+        _ => {
+            return Ok(Example {
+                value: Some(value.clone()),
+                ..Default::default()
+            })
+        }
+    };
+    if tagged.tag != EXAMPLE_TAG {
+        return Err(format!("unexpected tag {} in examples", tagged.tag));
+    }
+    let Some(m) = tagged.value.as_mapping() else {
+        return Err("an example object must be a mapping".to_owned());
+    };
+    if let Some(key) = m
+        .keys()
+        .find(|k| !k.as_str().is_some_and(|k| EXAMPLE_FIELDS.contains(&k)))
+    {
+        return Err(format!("unexpected example field {key:?}"));
+    }
+    let external = get_text(m, "externalValue");
+    if m.contains_key("value") == external.is_some() {
+        return Err("an example object must have either a value or an externalValue".to_owned());
+    }
+    Ok(Example {
+        summary: get_text(m, "summary"),
+        desc: get_text(m, "description"),
+        value: m.get("value").cloned(),
+        external,
+    })
+}
+
+/// Parses an annotation, checking the fields that cannot be checked once composed.
+fn parse_annotation(text: &str, span: Option<oal_model::span::Span>) -> Result<Annotation> {
+    let ann = Annotation::try_from(text).map_err(|err| Error::from(err).at(span.clone()))?;
+    ann.check_examples()
+        .map_err(|msg| Error::new(Kind::InvalidLiteral, msg).at(span))?;
+    Ok(ann)
+}
+
+/// Returns the string value of a field, if any.
+fn get_text(m: &Mapping, key: &str) -> Option<String> {
+    m.get(key).and_then(Value::as_str).map(ToOwned::to_owned)
+}
+
+/// Returns the string value of a required field.
+fn require_text(m: &Mapping, key: &str) -> std::result::Result<String, String> {
+    get_text(m, key).ok_or_else(|| format!("expected a {key}"))
+}
+
+fn oauth_flow(kind: OAuthFlowKind, value: &Value) -> std::result::Result<OAuthFlow, String> {
+    let m = value.as_mapping().ok_or("expected a flow object")?;
+    let scopes = m
+        .get("scopes")
+        .and_then(Value::as_mapping)
+        .ok_or("expected scopes")?
+        .iter()
+        .map(|(k, v)| Some((k.as_str()?.to_owned(), v.as_str()?.to_owned())))
+        .collect::<Option<IndexMap<_, _>>>()
+        .ok_or("expected scope descriptions")?;
+    let (needs_authorization, needs_token) = match kind {
+        OAuthFlowKind::Implicit => (true, false),
+        OAuthFlowKind::Password | OAuthFlowKind::ClientCredentials => (false, true),
+        OAuthFlowKind::AuthorizationCode => (true, true),
+    };
+    let url = |key: &str, required: bool| {
+        if required {
+            require_text(m, key).map(Some)
+        } else {
+            Ok(get_text(m, key))
+        }
+    };
+    Ok(OAuthFlow {
+        authorization_url: url("authorizationUrl", needs_authorization)?,
+        token_url: url("tokenUrl", needs_token)?,
+        refresh_url: get_text(m, "refreshUrl"),
+        scopes,
+    })
+}
+
+fn security_scheme(value: &Value) -> std::result::Result<SecurityScheme, String> {
+    let m = value
+        .as_mapping()
+        .ok_or("expected a security scheme object")?;
+    let kind = match get_text(m, "type").as_deref() {
+        Some("apiKey") => {
+            let location = match get_text(m, "in").as_deref() {
+                Some("query") => ApiKeyLocation::Query,
+                Some("header") => ApiKeyLocation::Header,
+                Some("cookie") => ApiKeyLocation::Cookie,
+                _ => return Err("expected the key in a query, header or cookie".to_owned()),
+            };
+            SecuritySchemeKind::ApiKey {
+                name: require_text(m, "name")?,
+                location,
+            }
+        }
+        Some("http") => SecuritySchemeKind::Http {
+            scheme: require_text(m, "scheme")?,
+            bearer_format: get_text(m, "bearerFormat"),
+        },
+        Some("oauth2") => {
+            let flows = m
+                .get("flows")
+                .and_then(Value::as_mapping)
+                .ok_or("expected flows")?;
+            let flows = flows
+                .iter()
+                .map(|(k, v)| {
+                    let kind = match k.as_str() {
+                        Some("implicit") => OAuthFlowKind::Implicit,
+                        Some("password") => OAuthFlowKind::Password,
+                        Some("clientCredentials") => OAuthFlowKind::ClientCredentials,
+                        Some("authorizationCode") => OAuthFlowKind::AuthorizationCode,
+                        _ => return Err("unknown OAuth flow".to_owned()),
+                    };
+                    Ok((kind, oauth_flow(kind, v)?))
+                })
+                .collect::<std::result::Result<_, String>>()?;
+            SecuritySchemeKind::OAuth2 { flows }
+        }
+        Some("openIdConnect") => SecuritySchemeKind::OpenIdConnect {
+            url: require_text(m, "openIdConnectUrl")?,
+        },
+        _ => return Err("expected a type of apiKey, http, oauth2 or openIdConnect".to_owned()),
+    };
+    Ok(SecurityScheme {
+        kind,
+        desc: get_text(m, "description"),
+    })
+}
+
+#[test]
+fn test_security_schemes() {
+    let props = r#"{ s: {
+        key: { type: apiKey, in: header, name: X-API-Key },
+        oauth: { type: oauth2, flows: { clientCredentials: { tokenUrl: "/token", scopes: { read: r } } } },
+        bad: { type: http }
+    } }"#;
+    let mut ann = Annotation {
+        props: serde_yaml::from_str(props).unwrap(),
+    };
+    let err = ann
+        .get_security_schemes("s")
+        .expect_err("expected an error");
+    assert_eq!(err, "security scheme bad: expected a scheme");
+
+    ann.props["s"].as_mapping_mut().unwrap().remove("bad");
+    let schemes = ann.get_security_schemes("s").unwrap();
+    assert_eq!(schemes.len(), 2);
+    assert_eq!(
+        schemes["key"].kind,
+        SecuritySchemeKind::ApiKey {
+            name: "X-API-Key".to_owned(),
+            location: ApiKeyLocation::Header
+        }
+    );
+    let SecuritySchemeKind::OAuth2 { ref flows } = schemes["oauth"].kind else {
+        panic!("expected an OAuth 2.0 scheme");
+    };
+    let flow = &flows[&OAuthFlowKind::ClientCredentials];
+    assert_eq!(flow.token_url.as_deref(), Some("/token"));
+    assert_eq!(flow.scopes["read"], "r");
+
+    assert!(ann.get_security_schemes("none").unwrap().is_empty());
+}
+
+#[test]
+fn test_extend_usage() {
+    let defn = r#"{ title: a, description: b, examples: { x: c }, minimum: 0 }"#;
+    let usage = r#"{ description: d, examples: { y: e } }"#;
+
+    let mut ann = Annotation {
+        props: serde_yaml::from_str(defn).unwrap(),
+    };
+    ann.extend_usage(Annotation {
+        props: serde_yaml::from_str(usage).unwrap(),
+    });
+    let exp = r#"{ title: a, description: d, examples: { x: c, y: e }, minimum: 0 }"#;
+    assert_eq!(ann.props, serde_yaml::from_str::<Mapping>(exp).unwrap());
+
+    let mut ann = Annotation {
+        props: serde_yaml::from_str(defn).unwrap(),
+    };
+    ann.extend_usage(Annotation {
+        props: serde_yaml::from_str(r#"{ inherit: false, description: d }"#).unwrap(),
+    });
+    let exp = r#"{ minimum: 0, inherit: false, description: d }"#;
+    assert_eq!(ann.props, serde_yaml::from_str::<Mapping>(exp).unwrap());
+}
+
+/// Composes a sequence of syntax annotations into a single annotation set.
+pub fn compose_annotations<'a, I>(anns: I) -> Result<Annotation>
+where
+    I: Iterator<Item = syn::Annotation<'a, Core>>,
+{
+    let mut ann = Annotation::default();
+    for a in anns {
+        let other = parse_annotation(a.as_str(), a.node().span())?;
+        ann.extend(other);
+    }
+    Ok(ann)
+}
+
+/// The parsed annotations by source text.
+pub type AnnotationCache = HashMap<String, Annotation>;
+
+/// Composes a sequence of syntax annotations into a single annotation set,
+/// parsing each distinct source text only once across calls sharing the cache.
+pub fn compose_cached_annotations<'a, I>(cache: &mut AnnotationCache, anns: I) -> Result<Annotation>
+where
+    I: Iterator<Item = syn::Annotation<'a, Core>>,
+{
+    let mut ann = Annotation::default();
+    for a in anns {
+        let other = match cache.get(a.as_str()) {
+            Some(other) => other.clone(),
+            None => {
+                let other = parse_annotation(a.as_str(), a.node().span())?;
+                cache.insert(a.as_str().to_owned(), other.clone());
+                other
+            }
+        };
+        ann.extend(other);
+    }
+    Ok(ann)
+}
+
+#[test]
+fn test_compose_cached() {
+    let code = "# a: 1\nlet x = {};\n# a: 1\n# b: 2\nlet y = {};";
+    let loc = oal_model::locator::Locator::try_from("file:main.oal").unwrap();
+    let (tree, errs) = oal_syntax::parse::<_, Core>(loc, code);
+    assert!(errs.is_empty());
+    let tree = tree.unwrap();
+    let prog = syn::Program::cast(tree.root()).unwrap();
+
+    let mut cache = AnnotationCache::new();
+    let anns = prog
+        .declarations()
+        .map(|d| compose_cached_annotations(&mut cache, d.annotations()).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(cache.len(), 2);
+    assert_eq!(anns[0].get_int("a"), Some(1));
+    assert_eq!(anns[1].get_int("b"), Some(2));
 }
 
 impl TryFrom<&str> for Annotation {
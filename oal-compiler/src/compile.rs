@@ -1,14 +1,19 @@
-use crate::errors::Result;
-use crate::inference::{constrain, substitute, tag};
+use crate::errors::{Error, Result};
+use crate::inference::{self, constrain, substitute, tag};
 use crate::module::ModuleSet;
-use crate::resolve::resolve;
+use crate::resolve::resolve_with_warnings;
 use crate::typecheck::{cycles_check, type_check};
 use oal_model::locator::Locator;
 
 /// Runs all compilation phases.
 pub fn compile(mods: &ModuleSet, loc: &Locator) -> Result<()> {
+    compile_with_warnings(mods, loc).map(|_| ())
+}
+
+/// Runs all compilation phases, returning the warnings.
+pub fn compile_with_warnings(mods: &ModuleSet, loc: &Locator) -> Result<Vec<Error>> {
     // Resolve variable and function references. Returns the graph of definitions.
-    let graph = resolve(mods, loc)?;
+    let (graph, warnings) = resolve_with_warnings(mods, loc)?;
     // Tag expressions with concrete and variable types.
     let _nvars = tag(mods, loc)?;
     // Collect the set of type inference equations.
@@ -21,5 +26,10 @@ pub fn compile(mods: &ModuleSet, loc: &Locator) -> Result<()> {
     cycles_check(graph, mods)?;
     // Check type tags against expectations.
     type_check(mods, loc)?;
-    Ok(())
+    Ok(warnings)
+}
+
+/// Checks that type inference left no unresolved type, as required in strict mode.
+pub fn check_complete(mods: &ModuleSet, loc: &Locator) -> Result<()> {
+    inference::check_complete(mods, loc)
 }
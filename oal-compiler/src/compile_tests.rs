@@ -56,3 +56,18 @@ fn compile_cycles() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn compile_error_code() -> anyhow::Result<()> {
+    let mods = mods_from("res / on get -> r;")?;
+
+    let err = compile(&mods, mods.base()).expect_err("expected an error");
+    assert_eq!(err.code(), "E0006");
+
+    let text = crate::errors::explain("e0006").expect("expected an explanation");
+    assert!(text.starts_with("A variable is referenced but not defined"));
+
+    assert!(crate::errors::explain("E9999").is_none());
+
+    Ok(())
+}
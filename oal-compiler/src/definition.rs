@@ -2,9 +2,12 @@ use crate::errors::Result;
 use crate::eval;
 use crate::inference::tag::{Seq, Tag};
 use crate::module::ModuleSet;
-use crate::tree::NRef;
+use crate::tree::{Core, NRef};
+use oal_model::grammar::AbstractSyntaxNode;
 use oal_model::grammar::NodeIdx;
 use oal_model::locator::Locator;
+use oal_model::span::Span;
+use oal_syntax::parser as syn;
 use sha2::{Digest, Sha256};
 use std::fmt::{Debug, Formatter, LowerHex};
 use std::rc::Rc;
@@ -15,6 +18,12 @@ pub trait Internal: Debug {
     fn eval<'a>(&self, args: Vec<eval::Value<'a>>, ann: eval::AnnRef) -> Result<eval::Value<'a>>;
     fn has_bindings(&self) -> bool;
     fn id(&self) -> u32;
+    /// Returns the identifier under which the definition is declared.
+    fn name(&self) -> &'static str;
+    /// Returns a usage example of the definition.
+    fn signature(&self) -> &'static str;
+    /// Returns a short description of what the definition does.
+    fn doc(&self) -> &'static str;
 }
 
 impl PartialEq for dyn Internal {
@@ -95,3 +104,41 @@ impl PartialEq for Definition {
 }
 
 impl Eq for Definition {}
+
+/// Returns the span of the value behind the given expression, following variables
+/// through terminals, sub-expressions and declarations, if any.
+///
+/// For a variable bound to a function parameter, returns the span of the binding.
+pub fn value_span<'a>(mods: &'a ModuleSet, node: NRef<'a>) -> Option<Span> {
+    value_span_with(mods, node, |_| None)
+}
+
+/// Returns the span of the value behind the given expression like [`value_span`],
+/// asking `argument` for the span of the value passed to a function parameter.
+pub fn value_span_with<'a, F>(mods: &'a ModuleSet, mut node: NRef<'a>, argument: F) -> Option<Span>
+where
+    F: Fn(syn::Binding<'a, Core>) -> Option<Span>,
+{
+    let mut is_variable = false;
+    loop {
+        if let Some(term) = syn::Terminal::cast(node) {
+            node = term.inner();
+        } else if let Some(expr) = syn::SubExpression::cast(node) {
+            node = expr.inner();
+        } else if let Some(var) = syn::Variable::cast(node) {
+            let core = var.node().syntax().core_ref();
+            let Some(Definition::External(ext)) = core.definition() else {
+                return None;
+            };
+            let defn = ext.node(mods);
+            is_variable = true;
+            match syn::Declaration::cast(defn) {
+                Some(decl) if !decl.has_bindings() => node = decl.rhs(),
+                _ => return syn::Binding::cast(defn).and_then(&argument).or(defn.span()),
+            }
+        } else {
+            // The expression itself is the value when not reached through a variable.
+            return if is_variable { node.span() } else { None };
+        }
+    }
+}
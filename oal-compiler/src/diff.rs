@@ -0,0 +1,726 @@
+//! Comparison of two versions of an evaluated specification, e.g. to gate breaking changes
+//! of an API in continuous integration.
+//!
+//! Changes to what clients send, i.e. parameters and request bodies, are breaking when they
+//! restrict what was accepted, e.g. a new required property or a lower maximum.
+//! Changes to what clients receive, i.e. responses, are breaking when they remove or extend
+//! what was returned, e.g. a removed property or a new enumeration value.
+
+use crate::query::{method_label, status};
+use crate::spec::{
+    Content, Object, PrimString, Property, Reference, Relation, Schema, SchemaExpr, Spec, Transfer,
+    UriSegment,
+};
+use oal_syntax::atom;
+use std::fmt::{Display, Formatter};
+
+/// The kind of a change between two versions of a specification.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ChangeKind {
+    AddedPath,
+    RemovedPath,
+    AddedOperation,
+    RemovedOperation,
+    AddedParameter,
+    RemovedParameter,
+    AddedRequestBody,
+    RemovedRequestBody,
+    AddedResponse,
+    RemovedResponse,
+    AddedProperty,
+    RemovedProperty,
+    /// A property or a parameter was added as required, or became required.
+    NewRequired,
+    /// A property or a parameter is no longer required.
+    NoLongerRequired,
+    /// The schema changed to another type, from the old to the new.
+    ChangedType(String, String),
+    /// The schema accepts fewer values, e.g. with a lower maximum.
+    NarrowedType(String),
+    /// The schema accepts more values, e.g. with a new enumeration value.
+    WidenedType(String),
+}
+
+impl Display for ChangeKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChangeKind::AddedPath => write!(f, "added path"),
+            ChangeKind::RemovedPath => write!(f, "removed path"),
+            ChangeKind::AddedOperation => write!(f, "added operation"),
+            ChangeKind::RemovedOperation => write!(f, "removed operation"),
+            ChangeKind::AddedParameter => write!(f, "added parameter"),
+            ChangeKind::RemovedParameter => write!(f, "removed parameter"),
+            ChangeKind::AddedRequestBody => write!(f, "added request body"),
+            ChangeKind::RemovedRequestBody => write!(f, "removed request body"),
+            ChangeKind::AddedResponse => write!(f, "added response"),
+            ChangeKind::RemovedResponse => write!(f, "removed response"),
+            ChangeKind::AddedProperty => write!(f, "added property"),
+            ChangeKind::RemovedProperty => write!(f, "removed property"),
+            ChangeKind::NewRequired => write!(f, "new required field"),
+            ChangeKind::NoLongerRequired => write!(f, "no longer required"),
+            ChangeKind::ChangedType(old, new) => write!(f, "changed type from {old} to {new}"),
+            ChangeKind::NarrowedType(detail) => write!(f, "narrowed type, {detail}"),
+            ChangeKind::WidenedType(detail) => write!(f, "widened type, {detail}"),
+        }
+    }
+}
+
+/// A change between two versions of a specification.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Change {
+    pub kind: ChangeKind,
+    /// Where the change occurs, e.g. `get /users/{id}: response 200: address.city`.
+    pub location: String,
+    /// Whether the change may break existing clients.
+    pub breaking: bool,
+}
+
+impl Display for Change {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.location, self.kind)
+    }
+}
+
+/// Whether a schema describes what clients send or what they receive.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Direction {
+    Request,
+    Response,
+}
+
+/// The location of a schema, as the element holding it and the path to the field within.
+#[derive(Clone)]
+struct Location {
+    base: String,
+    field: String,
+}
+
+impl Location {
+    fn new(base: String) -> Self {
+        Location {
+            base,
+            field: String::new(),
+        }
+    }
+
+    fn prop(&self, name: &str) -> Self {
+        let field = if self.field.is_empty() {
+            name.to_owned()
+        } else {
+            format!("{}.{name}", self.field)
+        };
+        Location {
+            base: self.base.clone(),
+            field,
+        }
+    }
+
+    fn items(&self) -> Self {
+        Location {
+            base: self.base.clone(),
+            field: format!("{}[]", self.field),
+        }
+    }
+}
+
+impl Display for Location {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.field.is_empty() {
+            write!(f, "{}", self.base)
+        } else {
+            write!(f, "{}: {}", self.base, self.field)
+        }
+    }
+}
+
+/// How a bound or a set of values changed, in terms of the values accepted.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Variance {
+    Narrowed,
+    Widened,
+}
+
+/// Returns how an upper bound changed, e.g. a maximum.
+fn upper_bound<T: PartialOrd>(old: Option<T>, new: Option<T>) -> Option<Variance> {
+    match (old, new) {
+        (None, Some(_)) => Some(Variance::Narrowed),
+        (Some(_), None) => Some(Variance::Widened),
+        (Some(a), Some(b)) if b < a => Some(Variance::Narrowed),
+        (Some(a), Some(b)) if b > a => Some(Variance::Widened),
+        _ => None,
+    }
+}
+
+/// Returns how a lower bound changed, e.g. a minimum.
+fn lower_bound<T: PartialOrd>(old: Option<T>, new: Option<T>) -> Option<Variance> {
+    upper_bound(old, new).map(|v| match v {
+        Variance::Narrowed => Variance::Widened,
+        Variance::Widened => Variance::Narrowed,
+    })
+}
+
+fn list<T: Display>(values: &[&T]) -> String {
+    let values = values.iter().map(|v| v.to_string());
+    values.collect::<Vec<_>>().join(", ")
+}
+
+/// Returns a short name of the type of a schema.
+fn type_name(expr: &SchemaExpr) -> String {
+    match expr {
+        SchemaExpr::Num(_) => "number".to_owned(),
+        SchemaExpr::Str(s) => match &s.format {
+            Some(format) => format!("string ({format})"),
+            None => "string".to_owned(),
+        },
+        SchemaExpr::Bool(_) => "boolean".to_owned(),
+        SchemaExpr::Int(_) => "integer".to_owned(),
+        SchemaExpr::Rel(_) => "relation".to_owned(),
+        SchemaExpr::Uri(_) => "uri".to_owned(),
+        SchemaExpr::Array(_) => "array".to_owned(),
+        SchemaExpr::Object(_) => "object".to_owned(),
+        SchemaExpr::Op(op) => match op.op {
+            atom::VariadicOperator::Join => "join".to_owned(),
+            atom::VariadicOperator::Any => "alternatives".to_owned(),
+            atom::VariadicOperator::Sum => "sum".to_owned(),
+            atom::VariadicOperator::Range => "ranges".to_owned(),
+        },
+        SchemaExpr::Ref(ident) => ident.to_string(),
+    }
+}
+
+/// Returns the alternatives of a sum or of alternatives, e.g. `a | b`, if the schema is one.
+fn alternatives(schema: &Schema) -> Option<&[Schema]> {
+    match &schema.expr {
+        SchemaExpr::Op(op)
+            if matches!(
+                op.op,
+                atom::VariadicOperator::Sum | atom::VariadicOperator::Any
+            ) =>
+        {
+            Some(&op.schemas)
+        }
+        _ => None,
+    }
+}
+
+/// Returns true if two schemas have the same structure, i.e. could be versions of one another,
+/// e.g. two objects or two references to the same component.
+fn same_structure(a: &SchemaExpr, b: &SchemaExpr) -> bool {
+    match (a, b) {
+        (SchemaExpr::Ref(a), SchemaExpr::Ref(b)) => a == b,
+        (SchemaExpr::Op(a), SchemaExpr::Op(b)) => a.op == b.op,
+        _ => std::mem::discriminant(a) == std::mem::discriminant(b),
+    }
+}
+
+/// Returns the key of a relation, regardless of the names of its path variables.
+fn relation_key(rel: &Relation) -> String {
+    rel.uri.pattern_with(|_| "{}".to_owned())
+}
+
+/// Returns the transfers of a relation by method, custom methods included.
+fn operations(rel: &Relation) -> Vec<(&str, &Transfer)> {
+    let standard = rel
+        .xfers
+        .iter()
+        .filter_map(|(m, x)| x.as_ref().map(|x| (method_label(m), x)));
+    let custom = rel.custom_xfers.iter().map(|(v, x)| (v.as_str(), x));
+    standard.chain(custom).collect()
+}
+
+/// Returns the query and header parameters of a transfer, keyed by location and name.
+///
+/// Header names are case-insensitive and keyed in lowercase.
+fn parameters<'a>(rel: &'a Relation, xfer: &'a Transfer) -> Vec<(String, &'a Property)> {
+    let mut params = Vec::new();
+    let queries = rel.uri.params.iter().chain(xfer.params.iter());
+    for p in queries.flat_map(|o| o.props.iter()) {
+        params.push((format!("query parameter {}", p.name), p));
+    }
+    for p in xfer.request_headers() {
+        params.push((format!("header {}", p.name.as_ref().to_lowercase()), p));
+    }
+    params
+}
+
+/// Returns the contents of the request body of a transfer with a schema, keyed by media type.
+fn request_bodies(xfer: &Transfer) -> Vec<(String, &Content)> {
+    xfer.domains()
+        .filter(|c| c.schema.is_some())
+        .map(|c| {
+            (
+                c.media.as_ref().map(|m| m.to_string()).unwrap_or_default(),
+                c,
+            )
+        })
+        .collect()
+}
+
+/// Returns the label of a response by status and media type, e.g. `response 200 (application/json)`.
+fn response_label(content: &Content) -> String {
+    let status = content
+        .status
+        .as_ref()
+        .map_or_else(|| "default".to_owned(), status);
+    match &content.media {
+        Some(media) => format!("response {status} ({media})"),
+        None => format!("response {status}"),
+    }
+}
+
+struct Differ<'a> {
+    old: &'a Spec,
+    new: &'a Spec,
+    changes: Vec<Change>,
+    /// The pairs of references being compared, to stop at recursive schemas.
+    stack: Vec<(atom::Ident, atom::Ident, Direction)>,
+}
+
+impl Differ<'_> {
+    fn report(&mut self, location: &dyn Display, kind: ChangeKind, breaking: bool) {
+        self.changes.push(Change {
+            kind,
+            location: location.to_string(),
+            breaking,
+        });
+    }
+
+    /// Reports a change of the values accepted by a schema, breaking depending on the direction.
+    fn report_variance(&mut self, loc: &Location, dir: Direction, var: Variance, detail: String) {
+        match var {
+            Variance::Narrowed => {
+                let breaking = dir == Direction::Request;
+                self.report(loc, ChangeKind::NarrowedType(detail), breaking)
+            }
+            Variance::Widened => {
+                let breaking = dir == Direction::Response;
+                self.report(loc, ChangeKind::WidenedType(detail), breaking)
+            }
+        }
+    }
+
+    fn bound(&mut self, loc: &Location, dir: Direction, var: Option<Variance>, name: &str) {
+        if let Some(var) = var {
+            let detail = match var {
+                Variance::Narrowed => format!("stricter {name}"),
+                Variance::Widened => format!("looser {name}"),
+            };
+            self.report_variance(loc, dir, var, detail);
+        }
+    }
+
+    fn enumeration<T: PartialEq + Display>(
+        &mut self,
+        loc: &Location,
+        dir: Direction,
+        old: &[T],
+        new: &[T],
+    ) {
+        match (old.is_empty(), new.is_empty()) {
+            (true, true) => {}
+            (true, false) => {
+                let detail = format!("restricted to {}", list(&new.iter().collect::<Vec<_>>()));
+                self.report_variance(loc, dir, Variance::Narrowed, detail);
+            }
+            (false, true) => {
+                let detail = "no longer restricted to values".to_owned();
+                self.report_variance(loc, dir, Variance::Widened, detail);
+            }
+            (false, false) => {
+                let removed = old.iter().filter(|v| !new.contains(v)).collect::<Vec<_>>();
+                if !removed.is_empty() {
+                    let detail = format!("removed values {}", list(&removed));
+                    self.report_variance(loc, dir, Variance::Narrowed, detail);
+                }
+                let added = new.iter().filter(|v| !old.contains(v)).collect::<Vec<_>>();
+                if !added.is_empty() {
+                    let detail = format!("added values {}", list(&added));
+                    self.report_variance(loc, dir, Variance::Widened, detail);
+                }
+            }
+        }
+    }
+
+    fn diff_string(&mut self, loc: &Location, dir: Direction, old: &PrimString, new: &PrimString) {
+        if old.format != new.format {
+            let kind = ChangeKind::ChangedType(
+                type_name(&SchemaExpr::Str(old.clone())),
+                type_name(&SchemaExpr::Str(new.clone())),
+            );
+            self.report(loc, kind, true);
+        }
+        match (&old.pattern, &new.pattern) {
+            (None, Some(_)) => {
+                let detail = "added pattern".to_owned();
+                self.report_variance(loc, dir, Variance::Narrowed, detail);
+            }
+            (Some(_), None) => {
+                let detail = "removed pattern".to_owned();
+                self.report_variance(loc, dir, Variance::Widened, detail);
+            }
+            // Values matching one pattern may not match the other, whatever the direction.
+            (Some(a), Some(b)) if a != b => {
+                let kind = ChangeKind::ChangedType(format!("pattern {a}"), format!("pattern {b}"));
+                self.report(loc, kind, true);
+            }
+            _ => {}
+        }
+        let var = lower_bound(old.min_length, new.min_length);
+        self.bound(loc, dir, var, "minimum length");
+        let var = upper_bound(old.max_length, new.max_length);
+        self.bound(loc, dir, var, "maximum length");
+        self.enumeration(loc, dir, &old.enumeration, &new.enumeration);
+    }
+
+    /// Resolves a reference in the given version of the specification.
+    fn resolve<'b>(spec: &'b Spec, schema: &'b Schema) -> &'b Schema {
+        match &schema.expr {
+            SchemaExpr::Ref(ident) => match spec.refs.get(ident) {
+                Some(Reference::Schema(s)) => s,
+                None => schema,
+            },
+            _ => schema,
+        }
+    }
+
+    fn diff_schema(&mut self, loc: &Location, dir: Direction, old: &Schema, new: &Schema) {
+        let key = match (&old.expr, &new.expr) {
+            (SchemaExpr::Ref(a), SchemaExpr::Ref(b)) => Some((a.clone(), b.clone(), dir)),
+            _ => None,
+        };
+        if let Some(ref key) = key {
+            if self.stack.contains(key) {
+                return;
+            }
+        }
+        let resolved_old = Self::resolve(self.old, old);
+        let resolved_new = Self::resolve(self.new, new);
+        if !std::ptr::eq(resolved_old, old) || !std::ptr::eq(resolved_new, new) {
+            if let Some(key) = key.clone() {
+                self.stack.push(key);
+            }
+            self.diff_schema(loc, dir, resolved_old, resolved_new);
+            if key.is_some() {
+                self.stack.pop();
+            }
+            return;
+        }
+
+        let old_nullable = old.nullable == Some(true);
+        let new_nullable = new.nullable == Some(true);
+        if old_nullable && !new_nullable {
+            let detail = "no longer nullable".to_owned();
+            self.report_variance(loc, dir, Variance::Narrowed, detail);
+        } else if !old_nullable && new_nullable {
+            let detail = "now nullable".to_owned();
+            self.report_variance(loc, dir, Variance::Widened, detail);
+        }
+
+        let same_op = match (&old.expr, &new.expr) {
+            (SchemaExpr::Op(a), SchemaExpr::Op(b)) => a.op == b.op,
+            _ => false,
+        };
+        // A schema compared with alternatives is taken as a single alternative.
+        match (alternatives(old), alternatives(new)) {
+            (Some(a), Some(b)) if same_op => return self.diff_alternatives(loc, dir, a, b),
+            (Some(a), None) if !same_op => {
+                return self.diff_alternatives(loc, dir, a, std::slice::from_ref(new))
+            }
+            (None, Some(b)) if !same_op => {
+                return self.diff_alternatives(loc, dir, std::slice::from_ref(old), b)
+            }
+            _ => {}
+        }
+
+        match (&old.expr, &new.expr) {
+            (SchemaExpr::Object(a), SchemaExpr::Object(b)) => self.diff_object(loc, dir, a, b),
+            (SchemaExpr::Array(a), SchemaExpr::Array(b)) => {
+                let var = lower_bound(a.min_items, b.min_items);
+                self.bound(loc, dir, var, "minimum of items");
+                let var = upper_bound(a.max_items, b.max_items);
+                self.bound(loc, dir, var, "maximum of items");
+                self.diff_schema(&loc.items(), dir, &a.item, &b.item);
+            }
+            (SchemaExpr::Str(a), SchemaExpr::Str(b)) => self.diff_string(loc, dir, a, b),
+            (SchemaExpr::Int(a), SchemaExpr::Int(b)) => {
+                self.bound(loc, dir, lower_bound(a.minimum, b.minimum), "minimum");
+                self.bound(loc, dir, upper_bound(a.maximum, b.maximum), "maximum");
+                self.enumeration(loc, dir, &a.enumeration, &b.enumeration);
+            }
+            (SchemaExpr::Num(a), SchemaExpr::Num(b)) => {
+                self.bound(loc, dir, lower_bound(a.minimum, b.minimum), "minimum");
+                self.bound(loc, dir, upper_bound(a.maximum, b.maximum), "maximum");
+                self.enumeration(loc, dir, &a.enumeration, &b.enumeration);
+            }
+            (SchemaExpr::Bool(a), SchemaExpr::Bool(b)) => {
+                self.enumeration(loc, dir, &a.enumeration, &b.enumeration);
+            }
+            (SchemaExpr::Int(_), SchemaExpr::Num(_)) => {
+                let detail = "from integer to number".to_owned();
+                self.report_variance(loc, dir, Variance::Widened, detail);
+            }
+            (SchemaExpr::Num(_), SchemaExpr::Int(_)) => {
+                let detail = "from number to integer".to_owned();
+                self.report_variance(loc, dir, Variance::Narrowed, detail);
+            }
+            (SchemaExpr::Uri(_), SchemaExpr::Uri(_)) | (SchemaExpr::Rel(_), SchemaExpr::Rel(_)) => {
+            }
+            (SchemaExpr::Op(a), SchemaExpr::Op(b))
+                if a.op == b.op && a.schemas.len() == b.schemas.len() =>
+            {
+                for (a, b) in a.schemas.iter().zip(b.schemas.iter()) {
+                    self.diff_schema(loc, dir, a, b);
+                }
+            }
+            (a, b) => {
+                let kind = ChangeKind::ChangedType(type_name(a), type_name(b));
+                self.report(loc, kind, true);
+            }
+        }
+    }
+
+    /// Compares alternatives regardless of their order, pairing identical alternatives first,
+    /// then alternatives of the same structure. Other alternatives are added or removed,
+    /// which widens or narrows the values accepted.
+    fn diff_alternatives(
+        &mut self,
+        loc: &Location,
+        dir: Direction,
+        old: &[Schema],
+        new: &[Schema],
+    ) {
+        let mut pairs: Vec<Option<usize>> = vec![None; old.len()];
+        let mut matched = vec![false; new.len()];
+        let passes: [fn(&Schema, &Schema) -> bool; 2] =
+            [|a, b| a == b, |a, b| same_structure(&a.expr, &b.expr)];
+        for pass in passes {
+            for (i, a) in old.iter().enumerate() {
+                if pairs[i].is_some() {
+                    continue;
+                }
+                if let Some(j) = (0..new.len()).find(|&j| !matched[j] && pass(a, &new[j])) {
+                    pairs[i] = Some(j);
+                    matched[j] = true;
+                }
+            }
+        }
+        for (a, pair) in old.iter().zip(pairs) {
+            match pair {
+                Some(j) => self.diff_schema(loc, dir, a, &new[j]),
+                None => {
+                    let detail = format!("removed alternative {}", type_name(&a.expr));
+                    self.report_variance(loc, dir, Variance::Narrowed, detail);
+                }
+            }
+        }
+        for (b, _) in new.iter().zip(matched).filter(|(_, m)| !m) {
+            let detail = format!("added alternative {}", type_name(&b.expr));
+            self.report_variance(loc, dir, Variance::Widened, detail);
+        }
+    }
+
+    fn diff_object(&mut self, loc: &Location, dir: Direction, old: &Object, new: &Object) {
+        for a in old.props.iter() {
+            let prop_loc = loc.prop(a.name.as_ref());
+            match new.props.iter().find(|b| b.name == a.name) {
+                None => {
+                    let breaking = dir == Direction::Response;
+                    self.report(&prop_loc, ChangeKind::RemovedProperty, breaking);
+                }
+                Some(b) => {
+                    self.diff_required(&prop_loc, dir, a.required, b.required);
+                    self.diff_schema(&prop_loc, dir, &a.schema, &b.schema);
+                }
+            }
+        }
+        for b in new.props.iter() {
+            if !old.props.iter().any(|a| a.name == b.name) {
+                let prop_loc = loc.prop(b.name.as_ref());
+                if b.required == Some(true) && dir == Direction::Request {
+                    self.report(&prop_loc, ChangeKind::NewRequired, true);
+                } else {
+                    self.report(&prop_loc, ChangeKind::AddedProperty, false);
+                }
+            }
+        }
+    }
+
+    fn diff_required(
+        &mut self,
+        loc: &dyn Display,
+        dir: Direction,
+        old: Option<bool>,
+        new: Option<bool>,
+    ) {
+        let old = old == Some(true);
+        let new = new == Some(true);
+        if !old && new {
+            self.report(loc, ChangeKind::NewRequired, dir == Direction::Request);
+        } else if old && !new {
+            self.report(
+                loc,
+                ChangeKind::NoLongerRequired,
+                dir == Direction::Response,
+            );
+        }
+    }
+
+    fn diff_parameters(
+        &mut self,
+        op: &str,
+        (old_rel, old): (&Relation, &Transfer),
+        (new_rel, new): (&Relation, &Transfer),
+    ) {
+        let dir = Direction::Request;
+        // Path variables are compared by position, as relations are matched regardless of their names.
+        let old_vars = old_rel.uri.path.iter().filter_map(|s| match s {
+            UriSegment::Variable(p) => Some(p),
+            _ => None,
+        });
+        let new_vars = new_rel.uri.path.iter().filter_map(|s| match s {
+            UriSegment::Variable(p) => Some(p),
+            _ => None,
+        });
+        for (a, b) in old_vars.zip(new_vars) {
+            let loc = Location::new(format!("{op}: path parameter {}", b.name));
+            self.diff_schema(&loc, dir, &a.schema, &b.schema);
+        }
+
+        let old_params = parameters(old_rel, old);
+        let new_params = parameters(new_rel, new);
+        for (key, a) in old_params.iter() {
+            let loc = Location::new(format!("{op}: {key}"));
+            match new_params.iter().find(|(k, _)| k == key) {
+                None => self.report(&loc, ChangeKind::RemovedParameter, false),
+                Some((_, b)) => {
+                    self.diff_required(&loc, dir, a.required, b.required);
+                    self.diff_schema(&loc, dir, &a.schema, &b.schema);
+                }
+            }
+        }
+        for (key, b) in new_params.iter() {
+            if !old_params.iter().any(|(k, _)| k == key) {
+                let loc = Location::new(format!("{op}: {key}"));
+                if b.required == Some(true) {
+                    self.report(&loc, ChangeKind::NewRequired, true);
+                } else {
+                    self.report(&loc, ChangeKind::AddedParameter, false);
+                }
+            }
+        }
+    }
+
+    fn diff_request(&mut self, op: &str, old: &Transfer, new: &Transfer) {
+        let dir = Direction::Request;
+        let label = |media: &str| {
+            if media.is_empty() {
+                format!("{op}: request body")
+            } else {
+                format!("{op}: request body ({media})")
+            }
+        };
+        let old_bodies = request_bodies(old);
+        let new_bodies = request_bodies(new);
+        for (media, a) in old_bodies.iter() {
+            let loc = Location::new(label(media));
+            match new_bodies.iter().find(|(m, _)| m == media) {
+                // Clients still sending the body in this media type are rejected.
+                None => self.report(&loc, ChangeKind::RemovedRequestBody, true),
+                Some((_, b)) => {
+                    if let (Some(a), Some(b)) = (&a.schema, &b.schema) {
+                        self.diff_schema(&loc, dir, a, b);
+                    }
+                }
+            }
+        }
+        for (media, _) in new_bodies.iter() {
+            if !old_bodies.iter().any(|(m, _)| m == media) {
+                let loc = Location::new(label(media));
+                self.report(&loc, ChangeKind::AddedRequestBody, old_bodies.is_empty());
+            }
+        }
+    }
+
+    fn diff_responses(&mut self, op: &str, old: &Transfer, new: &Transfer) {
+        let dir = Direction::Response;
+        for (key, a) in old.ranges.iter() {
+            let loc = Location::new(format!("{op}: {}", response_label(a)));
+            match new.ranges.get(key) {
+                None => self.report(&loc, ChangeKind::RemovedResponse, true),
+                Some(b) => {
+                    match (&a.schema, &b.schema) {
+                        (Some(a), Some(b)) => self.diff_schema(&loc, dir, a, b),
+                        (Some(a), None) => {
+                            let kind = ChangeKind::ChangedType(
+                                type_name(&a.expr),
+                                "no content".to_owned(),
+                            );
+                            self.report(&loc, kind, true);
+                        }
+                        _ => {}
+                    }
+                    if let (Some(a), Some(b)) = (&a.headers, &b.headers) {
+                        let loc = Location::new(format!("{loc}: headers"));
+                        self.diff_object(&loc, dir, a, b);
+                    } else if let Some(a) = &a.headers {
+                        let loc = Location::new(format!("{loc}: headers"));
+                        self.diff_object(&loc, dir, a, &Object::default());
+                    }
+                }
+            }
+        }
+        for (key, b) in new.ranges.iter() {
+            if !old.ranges.contains_key(key) {
+                let loc = format!("{op}: {}", response_label(b));
+                self.report(&loc, ChangeKind::AddedResponse, false);
+            }
+        }
+    }
+
+    fn diff_relation(&mut self, old: &Relation, new: &Relation) {
+        let path = new.uri.pattern();
+        let old_ops = operations(old);
+        let new_ops = operations(new);
+        for (method, a) in old_ops.iter() {
+            let op = format!("{method} {path}");
+            match new_ops.iter().find(|(m, _)| m == method) {
+                None => self.report(&op, ChangeKind::RemovedOperation, true),
+                Some((_, b)) => {
+                    self.diff_parameters(&op, (old, a), (new, b));
+                    self.diff_request(&op, a, b);
+                    self.diff_responses(&op, a, b);
+                }
+            }
+        }
+        for (method, _) in new_ops.iter() {
+            if !old_ops.iter().any(|(m, _)| m == method) {
+                let op = format!("{method} {path}");
+                self.report(&op, ChangeKind::AddedOperation, false);
+            }
+        }
+    }
+}
+
+/// Returns the changes from an old to a new version of a specification, in order of the old one.
+pub fn diff(old: &Spec, new: &Spec) -> Vec<Change> {
+    let mut differ = Differ {
+        old,
+        new,
+        changes: Vec::new(),
+        stack: Vec::new(),
+    };
+    for a in old.rels.iter() {
+        let key = relation_key(a);
+        match new.rels.iter().find(|b| relation_key(b) == key) {
+            None => differ.report(&a.uri.pattern(), ChangeKind::RemovedPath, true),
+            Some(b) => differ.diff_relation(a, b),
+        }
+    }
+    for b in new.rels.iter() {
+        let key = relation_key(b);
+        if !old.rels.iter().any(|a| relation_key(a) == key) {
+            differ.report(&b.uri.pattern(), ChangeKind::AddedPath, false);
+        }
+    }
+    differ.changes
+}
@@ -0,0 +1,184 @@
+use crate::compile::compile;
+use crate::diff::{diff, Change};
+use crate::spec::Spec;
+use crate::tests::mods_from;
+
+fn eval_code(code: &str) -> anyhow::Result<Spec> {
+    let mods = mods_from(code)?;
+    compile(&mods, mods.base())?;
+    Ok(crate::eval::eval(&mods)?)
+}
+
+fn diff_code(old: &str, new: &str) -> anyhow::Result<Vec<Change>> {
+    Ok(diff(&eval_code(old)?, &eval_code(new)?))
+}
+
+/// Formats changes as they are reported, flagging the breaking ones.
+fn report(changes: &[Change]) -> Vec<String> {
+    changes
+        .iter()
+        .map(|c| {
+            let flag = if c.breaking { "breaking" } else { "info" };
+            format!("{flag}: {c}")
+        })
+        .collect()
+}
+
+#[test]
+fn diff_unchanged() -> anyhow::Result<()> {
+    let code = r#"
+        let @user = { 'id! int, 'name str };
+        res /users/{ 'id int } on get -> @user;
+    "#;
+    assert!(diff_code(code, code)?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn diff_paths() -> anyhow::Result<()> {
+    let changes = diff_code(
+        r#"
+        res /users/{ 'id int } on get, delete -> {};
+        res /groups on get -> {};
+    "#,
+        r#"
+        res /users/{ 'userId int } on get -> {}, put : {} -> {};
+        res /teams on get -> {};
+    "#,
+    )?;
+    assert_eq!(
+        report(&changes),
+        vec![
+            "breaking: delete /users/{userId}: removed operation",
+            "info: put /users/{userId}: added operation",
+            "breaking: /groups: removed path",
+            "info: /teams: added path",
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn diff_request() -> anyhow::Result<()> {
+    let changes = diff_code(
+        r#"
+        let @form = { 'name str, 'age int `maximum: 150`, 'kind str `enum: [a, b]` };
+        res /forms?{ 'q str } on post : @form -> {};
+    "#,
+        r#"
+        let @form = { 'name! str, 'age int `maximum: 120`, 'kind str `enum: [a, b, c]`, 'email! str };
+        res /forms?{ 'q! str, 'page int } on post : @form -> {};
+    "#,
+    )?;
+    assert_eq!(
+        report(&changes),
+        vec![
+            "breaking: post /forms: query parameter q: new required field",
+            "info: post /forms: query parameter page: added parameter",
+            "breaking: post /forms: request body: name: new required field",
+            "breaking: post /forms: request body: age: narrowed type, stricter maximum",
+            "info: post /forms: request body: kind: widened type, added values c",
+            "breaking: post /forms: request body: email: new required field",
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn diff_request_patterns() -> anyhow::Result<()> {
+    let changes = diff_code(
+        r#"
+        let @form = { 'a str `pattern: "^[a-z]+$"`, 'b str, 'c str `pattern: "^x$"` };
+        res /forms on post : @form -> {}, put : <{}> -> {};
+    "#,
+        r#"
+        let @form = { 'a str `pattern: "^[0-9]+$"`, 'b str `pattern: "^y$"`, 'c str };
+        res /forms on post : @form -> {}, put -> {};
+    "#,
+    )?;
+    assert_eq!(
+        report(&changes),
+        vec![
+            "breaking: put /forms: request body: removed request body",
+            "breaking: post /forms: request body: a: changed type from pattern ^[a-z]+$ to pattern ^[0-9]+$",
+            "breaking: post /forms: request body: b: narrowed type, added pattern",
+            "info: post /forms: request body: c: widened type, removed pattern",
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn diff_responses() -> anyhow::Result<()> {
+    let changes = diff_code(
+        r#"
+        let @user = { 'id! int, 'name str, 'role str `enum: [admin, guest]`, 'tags [str] };
+        res /users on get -> <status=200, @user> :: <status=404, {}>;
+    "#,
+        r#"
+        let @user = { 'id int, 'role str `enum: [admin, guest, owner]`, 'tags [int], 'email str };
+        res /users on get -> <status=200, @user>;
+    "#,
+    )?;
+    assert_eq!(
+        report(&changes),
+        vec![
+            "breaking: get /users: response 200: id: no longer required",
+            "breaking: get /users: response 200: name: removed property",
+            "breaking: get /users: response 200: role: widened type, added values owner",
+            "breaking: get /users: response 200: tags[]: changed type from string to integer",
+            "info: get /users: response 200: email: added property",
+            "breaking: get /users: response 404: removed response",
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn diff_recursive_schema() -> anyhow::Result<()> {
+    let old = r#"
+        let @node = rec x { 'name str, 'children [x] };
+        res /tree on get -> @node;
+    "#;
+    let new = r#"
+        let @node = rec x { 'name str, 'children [x], 'size int };
+        res /tree on get -> @node;
+    "#;
+    let changes = diff_code(old, new)?;
+    assert_eq!(
+        report(&changes),
+        vec!["info: get /tree: response default: size: added property"]
+    );
+    Ok(())
+}
+
+#[test]
+fn diff_alternatives() -> anyhow::Result<()> {
+    let changes = diff_code(
+        r#"
+        let @card = { 'number! str };
+        let @bank = { 'iban! str };
+        let @cash = { 'amount! int };
+        res /payments on post : { 'method! (@card | @bank | @cash), 'note str } -> (@card | @bank);
+    "#,
+        r#"
+        let @card = { 'number! str };
+        let @bank = { 'iban! str `maxLength: 34` };
+        let @wallet = { 'id! str };
+        res /payments on post : { 'method! (@bank | @card | @wallet), 'note (str | int) }
+            -> (@bank | @card | @wallet);
+    "#,
+    )?;
+    assert_eq!(
+        report(&changes),
+        vec![
+            "breaking: post /payments: request body: method.iban: narrowed type, stricter maximum length",
+            "breaking: post /payments: request body: method: narrowed type, removed alternative @cash",
+            "info: post /payments: request body: method: widened type, added alternative @wallet",
+            "info: post /payments: request body: note: widened type, added alternative integer",
+            "info: post /payments: response default: iban: narrowed type, stricter maximum length",
+            "breaking: post /payments: response default: widened type, added alternative @wallet",
+        ]
+    );
+    Ok(())
+}
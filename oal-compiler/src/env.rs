@@ -47,6 +47,20 @@ impl Env {
             .next()
     }
 
+    /// Returns the sorted qualifiers under which the identifier is declared.
+    pub fn qualifiers(&self, ident: &Ident) -> Vec<Ident> {
+        let mut qualifiers = self
+            .0
+            .iter()
+            .flat_map(|s| s.keys())
+            .filter(|Entry(i, _)| i == ident)
+            .filter_map(|Entry(_, q)| q.clone())
+            .collect::<Vec<_>>();
+        qualifiers.sort_by(|a, b| a.as_ref().cmp(b.as_ref()));
+        qualifiers.dedup();
+        qualifiers
+    }
+
     pub fn open(&mut self) {
         self.0.push(Scope::new());
     }
@@ -0,0 +1,117 @@
+use crate::eval::{cast_schema, AnnRef, Expr};
+use crate::spec::{MediaType, Object, Property, Reference, Schema, SchemaExpr, Spec};
+use oal_syntax::atom::HttpStatus;
+use std::str::FromStr;
+
+/// The name of the envelope property holding the response data.
+const DATA: &str = "data";
+
+/// The name of the envelope property holding the response metadata.
+const META: &str = "meta";
+
+/// A transformation of the success response schemas, to enforce a platform convention.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Envelope {
+    /// Wraps each schema as the `data` property of an object, along with a `meta` object.
+    Wrap,
+    /// Unwraps each schema from the `data` property of an envelope object.
+    Unwrap,
+}
+
+impl FromStr for Envelope {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "wrap" => Ok(Envelope::Wrap),
+            "unwrap" => Ok(Envelope::Unwrap),
+            _ => Err(format!(
+                "unsupported envelope transformation {s}, expected wrap or unwrap"
+            )),
+        }
+    }
+}
+
+/// Returns the schema of an object without annotations.
+fn bare(obj: Object) -> Schema {
+    cast_schema((Expr::Object(Box::new(obj)), AnnRef::default()))
+}
+
+fn property(name: &str, schema: Schema, required: bool) -> Property {
+    Property {
+        name: name.into(),
+        schema,
+        desc: None,
+        translations: Default::default(),
+        required: Some(required),
+        style: None,
+        explode: None,
+        content_type: None,
+        deprecated: None,
+    }
+}
+
+/// Returns whether the range is a success response with a JSON body,
+/// including the default response, which stands for success when no status is given.
+fn is_success(status: Option<HttpStatus>, media: Option<&MediaType>) -> bool {
+    let success = status.is_none_or(|s| s.is_success());
+    // Contents without an explicit media type get the default one, assumed to be JSON.
+    let json = media.is_none_or(|m| m.sub == "json" || m.suffix.as_deref() == Some("json"));
+    success && json
+}
+
+/// Returns the data schema of an envelope object, i.e. with a `data` and an optional `meta` property.
+fn unwrapped(spec: &Spec, schema: &Schema) -> Option<Schema> {
+    let obj = match &schema.expr {
+        SchemaExpr::Object(obj) => obj,
+        SchemaExpr::Ref(ident) => match spec.refs.get(ident) {
+            Some(Reference::Schema(s)) => match &s.expr {
+                SchemaExpr::Object(obj) => obj,
+                _ => return None,
+            },
+            None => return None,
+        },
+        _ => return None,
+    };
+    if !obj.props.iter().all(|p| p.name == DATA || p.name == META) {
+        return None;
+    }
+    obj.props
+        .iter()
+        .find(|p| p.name == DATA)
+        .map(|p| p.schema.clone())
+}
+
+/// Transforms the schemas of all the success and default responses with a JSON body.
+///
+/// Unwrapping leaves the schemas that are not envelopes unchanged.
+pub fn apply(spec: &mut Spec, envelope: Envelope) {
+    let mut rels = std::mem::take(&mut spec.rels);
+    let xfers = rels.iter_mut().flat_map(|rel| {
+        let xfers = rel.xfers.values_mut().flatten();
+        xfers.chain(rel.custom_xfers.values_mut())
+    });
+    for xfer in xfers {
+        for ((status, media), content) in xfer.ranges.iter_mut() {
+            if !is_success(*status, media.as_ref()) {
+                continue;
+            }
+            let Some(schema) = content.schema.take() else {
+                continue;
+            };
+            let schema = match envelope {
+                Envelope::Wrap => {
+                    let meta = bare(Object::default());
+                    let obj = Object {
+                        props: vec![property(DATA, *schema, true), property(META, meta, false)],
+                        ..Default::default()
+                    };
+                    bare(obj)
+                }
+                Envelope::Unwrap => unwrapped(spec, &schema).unwrap_or(*schema),
+            };
+            content.schema = Some(schema.into());
+        }
+    }
+    spec.rels = rels;
+}
@@ -0,0 +1,71 @@
+use crate::compile::compile;
+use crate::envelope::{apply, Envelope};
+use crate::spec::{SchemaExpr, Spec};
+use crate::tests::mods_from;
+use oal_syntax::atom::Method;
+
+fn eval(code: &str) -> anyhow::Result<Spec> {
+    let mods = mods_from(code)?;
+    compile(&mods, mods.base())?;
+    let spec = crate::eval::eval(&mods)?;
+    Ok(spec)
+}
+
+fn response_props(spec: &Spec, status: usize) -> Vec<String> {
+    let xfer = spec.rels[0].xfers[Method::Get].as_ref().unwrap();
+    let content = xfer.ranges.values().nth(status).unwrap();
+    match &content.schema.as_ref().unwrap().expr {
+        SchemaExpr::Object(obj) => obj.props.iter().map(|p| p.name.to_string()).collect(),
+        SchemaExpr::Ref(ident) => vec![ident.to_string()],
+        _ => vec![],
+    }
+}
+
+#[test]
+fn envelope_wrap() -> anyhow::Result<()> {
+    let code = r#"
+        let @user = { 'id int };
+        res /a on get -> <status=200, @user> :: <status=404, { 'reason str }>;
+    "#;
+    let mut spec = eval(code)?;
+    apply(&mut spec, Envelope::Wrap);
+    assert_eq!(response_props(&spec, 0), ["data", "meta"]);
+    assert_eq!(response_props(&spec, 1), ["reason"]);
+
+    let xfer = spec.rels[0].xfers[Method::Get].as_ref().unwrap();
+    let SchemaExpr::Object(obj) = &xfer.ranges[0].schema.as_ref().unwrap().expr else {
+        panic!("expected an object");
+    };
+    assert_eq!(obj.props[0].required, Some(true));
+    assert_eq!(obj.props[0].schema.expr, SchemaExpr::Ref("@user".into()));
+
+    apply(&mut spec, Envelope::Unwrap);
+    assert_eq!(response_props(&spec, 0), ["@user"]);
+
+    // The default response stands for success.
+    let mut spec = eval("res /a on get -> { 'id int };")?;
+    apply(&mut spec, Envelope::Wrap);
+    assert_eq!(response_props(&spec, 0), ["data", "meta"]);
+
+    Ok(())
+}
+
+#[test]
+fn envelope_unwrap() -> anyhow::Result<()> {
+    let code = r#"
+        let @page = { 'data [{ 'id int }], 'meta { 'total int } };
+        res /a on get -> <status=200, @page> :: <status=201, { 'data str, 'other int }>;
+    "#;
+    let mut spec = eval(code)?;
+    apply(&mut spec, Envelope::Unwrap);
+    let xfer = spec.rels[0].xfers[Method::Get].as_ref().unwrap();
+    let schema = xfer.ranges[0].schema.as_ref().unwrap();
+    assert!(matches!(schema.expr, SchemaExpr::Array(_)));
+    // Objects with other properties are not envelopes.
+    assert_eq!(response_props(&spec, 1), ["data", "other"]);
+
+    assert!("wrap".parse::<Envelope>().is_ok());
+    assert!("envelope".parse::<Envelope>().is_err());
+
+    Ok(())
+}
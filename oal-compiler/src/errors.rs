@@ -1,3 +1,4 @@
+use crate::fix::Fix;
 use oal_model::locator::Locator;
 use oal_model::span::Span;
 use std::fmt::{Debug, Display, Formatter};
@@ -22,13 +23,221 @@ pub enum Kind {
     InvalidIdentifier,
     #[error("invalid module: {0}")]
     InvalidModule(Locator),
+    #[error("depth exceeded")]
+    DepthExceeded,
+    #[error("conflicting definitions")]
+    Conflict,
+    #[error("limit exceeded")]
+    LimitExceeded,
+    #[error("shadowed identifier")]
+    Shadowed,
+    #[error("unexpected content")]
+    UnexpectedContent,
+    #[error("unsatisfiable schema")]
+    Unsatisfiable,
+    #[error("duplicate header")]
+    DuplicateHeader,
+    #[error("reserved header")]
+    ReservedHeader,
+    #[error("module collision")]
+    ModuleCollision,
+    #[error("resource limit exceeded")]
+    ResourceExceeded,
+    #[error("invalid example")]
+    InvalidExample,
+    #[error("default-only headers")]
+    DefaultHeaders,
+    #[error("unstable reference")]
+    UnstableReference,
+    #[error("duplicate schema")]
+    DuplicateSchema,
+    #[error("duplicate operation id")]
+    DuplicateOperationId,
+    #[error("invalid path parameter")]
+    PathParameter,
+    #[error("missing description")]
+    MissingDescription,
+    #[error("unused declaration")]
+    UnusedDeclaration,
+    #[error("unreachable media type")]
+    UnreachableMediaType,
+    #[error("invalid discriminator")]
+    InvalidDiscriminator,
+}
+
+/// The broad category of errors, stable across releases for embedders,
+/// e.g. to offer quick fixes for syntax errors only.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Category {
+    /// The source does not parse.
+    Syntax,
+    /// The program does not resolve or type check.
+    Type,
+    /// The program fails to evaluate into a consistent description.
+    Evaluation,
+    /// A module cannot be located or loaded.
+    Io,
+    /// An annotation is malformed or holds an invalid value.
+    Annotation,
+}
+
+impl Category {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Category::Syntax => "syntax",
+            Category::Type => "type",
+            Category::Evaluation => "evaluation",
+            Category::Io => "io",
+            Category::Annotation => "annotation",
+        }
+    }
+}
+
+impl Display for Category {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl Kind {
+    /// Returns the category of the error kind.
+    pub fn category(&self) -> Category {
+        match self {
+            Kind::Syntax(_) => Category::Syntax,
+            Kind::Locator(_) | Kind::InvalidModule(_) | Kind::ModuleCollision => Category::Io,
+            Kind::Yaml(_) | Kind::InvalidLiteral | Kind::InvalidExample => Category::Annotation,
+            Kind::NotInScope
+            | Kind::InvalidType
+            | Kind::CycleDetected
+            | Kind::InvalidIdentifier
+            | Kind::Shadowed => Category::Type,
+            Kind::DepthExceeded
+            | Kind::Conflict
+            | Kind::LimitExceeded
+            | Kind::UnexpectedContent
+            | Kind::Unsatisfiable
+            | Kind::DuplicateHeader
+            | Kind::ReservedHeader
+            | Kind::ResourceExceeded
+            | Kind::DefaultHeaders
+            | Kind::UnstableReference
+            | Kind::DuplicateSchema
+            | Kind::DuplicateOperationId
+            | Kind::PathParameter
+            | Kind::MissingDescription
+            | Kind::UnusedDeclaration
+            | Kind::UnreachableMediaType
+            | Kind::InvalidDiscriminator => Category::Evaluation,
+        }
+    }
+
+    /// Returns the stable diagnostic code of the error kind.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Kind::Syntax(err) => err.code(),
+            Kind::Locator(_) => "E0004",
+            Kind::Yaml(_) => "E0005",
+            Kind::NotInScope => "E0006",
+            Kind::InvalidType => "E0007",
+            Kind::CycleDetected => "E0008",
+            Kind::InvalidLiteral => "E0009",
+            Kind::InvalidIdentifier => "E0010",
+            Kind::InvalidModule(_) => "E0011",
+            Kind::DepthExceeded => "E0012",
+            Kind::Conflict => "E0013",
+            Kind::LimitExceeded => "E0014",
+            Kind::Shadowed => "E0015",
+            Kind::UnexpectedContent => "E0016",
+            Kind::Unsatisfiable => "E0017",
+            Kind::DuplicateHeader => "E0018",
+            Kind::ReservedHeader => "E0019",
+            Kind::ModuleCollision => "E0020",
+            Kind::ResourceExceeded => "E0021",
+            Kind::InvalidExample => "E0022",
+            Kind::DefaultHeaders => "E0023",
+            Kind::UnstableReference => "E0024",
+            Kind::DuplicateSchema => "E0025",
+            Kind::DuplicateOperationId => "E0026",
+            Kind::PathParameter => "E0027",
+            Kind::MissingDescription => "E0028",
+            Kind::UnusedDeclaration => "E0029",
+            Kind::UnreachableMediaType => "E0030",
+            Kind::InvalidDiscriminator => "E0031",
+        }
+    }
+
+    /// Returns the name of the lint controlling warnings of this kind, if any.
+    pub fn lint(&self) -> Option<&'static str> {
+        match self {
+            Kind::LimitExceeded => Some("limits"),
+            Kind::Shadowed => Some("shadowing"),
+            Kind::UnexpectedContent => Some("content"),
+            Kind::Unsatisfiable => Some("unsatisfiable"),
+            Kind::DuplicateHeader | Kind::DefaultHeaders => Some("headers"),
+            Kind::InvalidExample => Some("examples"),
+            Kind::UnstableReference => Some("stability"),
+            Kind::DuplicateSchema => Some("duplicates"),
+            Kind::DuplicateOperationId => Some("operation-ids"),
+            Kind::PathParameter => Some("path-parameters"),
+            Kind::MissingDescription => Some("descriptions"),
+            Kind::UnusedDeclaration => Some("unused"),
+            Kind::UnreachableMediaType => Some("media-types"),
+            _ => None,
+        }
+    }
+}
+
+/// The extended descriptions of diagnostic codes.
+const EXPLANATIONS: &[(&str, &str)] = &[
+    ("E0001", include_str!("explain/E0001.md")),
+    ("E0002", include_str!("explain/E0002.md")),
+    ("E0003", include_str!("explain/E0003.md")),
+    ("E0004", include_str!("explain/E0004.md")),
+    ("E0005", include_str!("explain/E0005.md")),
+    ("E0006", include_str!("explain/E0006.md")),
+    ("E0007", include_str!("explain/E0007.md")),
+    ("E0008", include_str!("explain/E0008.md")),
+    ("E0009", include_str!("explain/E0009.md")),
+    ("E0010", include_str!("explain/E0010.md")),
+    ("E0011", include_str!("explain/E0011.md")),
+    ("E0012", include_str!("explain/E0012.md")),
+    ("E0013", include_str!("explain/E0013.md")),
+    ("E0014", include_str!("explain/E0014.md")),
+    ("E0015", include_str!("explain/E0015.md")),
+    ("E0016", include_str!("explain/E0016.md")),
+    ("E0017", include_str!("explain/E0017.md")),
+    ("E0018", include_str!("explain/E0018.md")),
+    ("E0019", include_str!("explain/E0019.md")),
+    ("E0020", include_str!("explain/E0020.md")),
+    ("E0021", include_str!("explain/E0021.md")),
+    ("E0022", include_str!("explain/E0022.md")),
+    ("E0023", include_str!("explain/E0023.md")),
+    ("E0024", include_str!("explain/E0024.md")),
+    ("E0025", include_str!("explain/E0025.md")),
+    ("E0026", include_str!("explain/E0026.md")),
+    ("E0027", include_str!("explain/E0027.md")),
+    ("E0028", include_str!("explain/E0028.md")),
+    ("E0029", include_str!("explain/E0029.md")),
+    ("E0030", include_str!("explain/E0030.md")),
+    ("E0031", include_str!("explain/E0031.md")),
+];
+
+/// Returns the extended description of the given diagnostic code, if any.
+pub fn explain(code: &str) -> Option<&'static str> {
+    let code = code.trim().to_ascii_uppercase();
+    EXPLANATIONS
+        .iter()
+        .find_map(|(c, text)| (*c == code).then_some(*text))
 }
 
 #[derive(Debug)]
 pub struct Error {
-    msg: String,
-    details: Vec<String>,
-    span: Option<Span>,
+    // Messages, details, spans, labels and fixes are boxed to keep results with this error type small.
+    msg: Box<str>,
+    details: Box<[String]>,
+    span: Option<Box<Span>>,
+    labels: Box<[(Span, String)]>,
+    fixes: Box<[Fix]>,
     pub kind: Kind,
 }
 
@@ -38,6 +247,8 @@ impl<E: Into<Kind>> From<E> for Error {
             msg: Default::default(),
             details: Default::default(),
             span: Default::default(),
+            labels: Default::default(),
+            fixes: Default::default(),
             kind: e.into(),
         }
     }
@@ -46,25 +257,67 @@ impl<E: Into<Kind>> From<E> for Error {
 impl Error {
     pub fn new<S: Into<String>>(kind: Kind, msg: S) -> Self {
         Error {
-            msg: msg.into(),
-            details: Vec::new(),
+            msg: msg.into().into_boxed_str(),
+            details: Default::default(),
             span: None,
+            labels: Default::default(),
+            fixes: Default::default(),
             kind,
         }
     }
 
     pub fn with<T: Debug>(mut self, e: &T) -> Self {
-        self.details.push(format!("{e:?}"));
+        let mut details = std::mem::take(&mut self.details).into_vec();
+        details.push(format!("{e:?}"));
+        self.details = details.into_boxed_slice();
         self
     }
 
     pub fn at(mut self, span: Option<Span>) -> Self {
-        self.span = span;
+        self.span = span.map(Box::new);
         self
     }
 
     pub fn span(&self) -> Option<&Span> {
-        self.span.as_ref()
+        self.span.as_deref()
+    }
+
+    /// Adds a secondary label, possibly in another module, if the span is known.
+    pub fn with_label<S: Into<String>>(mut self, span: Option<Span>, msg: S) -> Self {
+        if let Some(span) = span {
+            let mut labels = std::mem::take(&mut self.labels).into_vec();
+            labels.push((span, msg.into()));
+            self.labels = labels.into_boxed_slice();
+        }
+        self
+    }
+
+    /// Returns the secondary labels.
+    pub fn labels(&self) -> &[(Span, String)] {
+        &self.labels
+    }
+
+    /// Adds a machine-applicable fix.
+    pub fn with_fix(mut self, fix: Fix) -> Self {
+        let mut fixes = std::mem::take(&mut self.fixes).into_vec();
+        fixes.push(fix);
+        self.fixes = fixes.into_boxed_slice();
+        self
+    }
+
+    /// Returns the machine-applicable fixes.
+    pub fn fixes(&self) -> &[Fix] {
+        &self.fixes
+    }
+
+    /// Returns the stable diagnostic code of the error.
+    pub fn code(&self) -> &'static str {
+        self.kind.code()
+    }
+
+    /// Returns the category of the error.
+    pub fn category(&self) -> Category {
+        self.kind.category()
     }
 }
 
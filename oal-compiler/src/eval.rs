@@ -1,23 +1,55 @@
-use crate::annotation::Annotation;
-use crate::definition::{Definition, InternalRef};
+use crate::annotation::{compose_cached_annotations, Annotation, AnnotationCache};
+use crate::definition::{value_span_with, Definition, InternalRef};
 use crate::errors::{Error, Kind, Result};
+use crate::fix;
+use crate::library;
 use crate::module::ModuleSet;
 use crate::spec::{
-    Array, Content, Object, PrimBoolean, PrimInteger, PrimNumber, PrimString, Property, Ranges,
-    Reference, Relation, Schema, SchemaExpr, Spec, Transfer, Transfers, Uri, UriSegment,
+    AdditionalProperties, Array, Content, CustomTransfers, Discriminator, Extensions, MediaType,
+    Object, ParamStyle, PrimBoolean, PrimInteger, PrimNumber, PrimString, Property, Ranges,
+    Reference, Relation, Schema, SchemaExpr, Spec, Stability, Transfer, Transfers, Uri, UriSegment,
     VariadicOp,
 };
 use crate::tree::{Core, NRef};
 use enum_map::EnumMap;
 use indexmap::IndexMap;
 use oal_model::grammar::AbstractSyntaxNode;
+use oal_model::span::Span;
 use oal_syntax::atom;
 use oal_syntax::lexer as lex;
 use oal_syntax::parser as syn;
 use sha2::{Digest, Sha256};
-use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
 use std::rc::Rc;
 
+/// The document annotation setting the status of contents without a schema.
+const EMPTY_STATUS: &str = "empty-status";
+
+/// The document annotation declaring security schemes.
+const SECURITY_SCHEMES: &str = "securitySchemes";
+
+/// The document annotation declaring the general information of the document.
+const INFO: &str = "info";
+
+/// The annotation renaming the schema component of a reference.
+const COMPONENT_NAME: &str = "componentName";
+
+/// The annotation of sums giving the property that distinguishes their alternatives.
+const DISCRIMINATOR: &str = "discriminator";
+
+/// The status of contents without a schema, unless overridden by the document.
+const DEFAULT_EMPTY_STATUS: u64 = 204;
+
+/// The annotation providing an example value.
+const EXAMPLE: &str = "example";
+
+/// The annotation providing the maturity of an operation or a schema.
+const STABILITY: &str = "stability";
+
+/// The maximum number of comparisons made while checking an example against its schema.
+const MAX_EXAMPLE_STEPS: usize = 1 << 12;
+
 // AnnRef is the type of references to annotations.
 pub type AnnRef = Rc<Annotation>;
 
@@ -83,16 +115,60 @@ impl Expr<'_> {
 }
 
 type Scope<'a> = HashMap<atom::Ident, Value<'a>>;
+/// The spans of the values passed to the bindings of a scope, e.g. for diagnostics.
+type Origins = HashMap<atom::Ident, Span>;
 type ScopeId = u64;
 
+/// The default maximum depth of nested evaluations before giving up.
+pub const MAX_EVAL_DEPTH: usize = 1024;
+
+/// Limits on the resources used to evaluate a program, e.g. for untrusted sources.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct EvalLimits {
+    /// The maximum number of nested and sequential evaluation steps.
+    pub max_steps: Option<usize>,
+    /// The maximum depth of nested evaluations, by default [`MAX_EVAL_DEPTH`].
+    pub max_depth: Option<usize>,
+    /// The maximum number of nodes copied into values, e.g. when passing arguments to functions,
+    /// as repeated applications can grow values exponentially in few steps.
+    pub max_nodes: Option<usize>,
+}
+
+impl EvalLimits {
+    /// The limits of programs from untrusted sources, e.g. submitted to a public service.
+    pub const UNTRUSTED: EvalLimits = EvalLimits {
+        max_steps: Some(1 << 20),
+        max_depth: Some(256),
+        max_nodes: Some(1 << 20),
+    };
+}
+
 pub struct Context<'a> {
     mods: &'a ModuleSet,
     /// The explicit and implicit (e.g. recursive) references.
     refs: IndexMap<atom::Ident, Option<Value<'a>>>,
     /// The stack of evaluation scopes.
-    scopes: Vec<(ScopeId, Scope<'a>)>,
+    scopes: Vec<(ScopeId, Scope<'a>, Origins)>,
     /// The sequence of unique scope identifiers in the evaluation tree.
     scope_id_seq: ScopeId,
+    /// The current depth of nested evaluations.
+    depth: usize,
+    /// The number of evaluation steps so far.
+    steps: usize,
+    /// The number of nodes copied into values so far.
+    nodes: usize,
+    /// The limits on the resources used by the evaluation.
+    limits: EvalLimits,
+    /// The status of contents without a schema.
+    empty_status: atom::HttpStatus,
+    /// The warnings raised during evaluation.
+    warnings: Vec<Error>,
+    /// The annotations parsed so far, e.g. for lambdas applied repeatedly.
+    annotations: AnnotationCache,
+    /// The component names of renamed references, with the span of their declaration.
+    component_names: IndexMap<atom::Ident, (String, Option<Span>)>,
+    /// The span of the declaration of each reference.
+    ref_spans: HashMap<atom::Ident, Option<Span>>,
 }
 
 impl<'a> Context<'a> {
@@ -102,13 +178,35 @@ impl<'a> Context<'a> {
             refs: IndexMap::new(),
             scopes: Vec::new(),
             scope_id_seq: 0,
+            depth: 0,
+            steps: 0,
+            nodes: 0,
+            limits: EvalLimits::default(),
+            empty_status: atom::HttpStatus::try_from(DEFAULT_EMPTY_STATUS).unwrap(),
+            warnings: Vec::new(),
+            annotations: AnnotationCache::new(),
+            component_names: IndexMap::new(),
+            ref_spans: HashMap::new(),
+        }
+    }
+
+    /// Adds a warning, unless the same warning was already raised at the same location,
+    /// e.g. when evaluating a shared declaration again.
+    fn warn(&mut self, warning: Error) {
+        let raised = self.warnings.iter().any(|w| {
+            w.code() == warning.code()
+                && w.span() == warning.span()
+                && w.to_string() == warning.to_string()
+        });
+        if !raised {
+            self.warnings.push(warning);
         }
     }
 
     /// Adds a new scope to the top of the stack.
-    fn push_scope(&mut self, scope: Scope<'a>) {
+    fn push_scope(&mut self, scope: Scope<'a>, origins: Origins) {
         self.scope_id_seq += 1;
-        self.scopes.push((self.scope_id_seq, scope));
+        self.scopes.push((self.scope_id_seq, scope, origins));
     }
 
     /// Removes the last scope from the top of the stack.
@@ -117,7 +215,7 @@ impl<'a> Context<'a> {
     }
 
     /// Looks for a matching binding in the stack of scopes.
-    fn lookup_binding(&self, ident: &atom::Ident) -> Option<(Expr<'a>, AnnRef)> {
+    fn lookup_binding(&self, ident: &atom::Ident) -> Option<&Value<'a>> {
         self.scopes
             .iter()
             .rev()
@@ -125,7 +223,34 @@ impl<'a> Context<'a> {
             .skip_while(Option::is_none)
             .map(|s| s.unwrap())
             .next()
-            .cloned()
+    }
+
+    /// Accounts for the nodes of a value about to be copied, failing beyond the limit.
+    fn charge(&mut self, nodes: usize, span: Option<Span>) -> Result<()> {
+        self.nodes = self.nodes.saturating_add(nodes);
+        match self.limits.max_nodes {
+            Some(max) if self.nodes > max => Err(Error::new(
+                Kind::ResourceExceeded,
+                format!("the evaluation builds values of more than the limit of {max} nodes"),
+            )
+            .at(span)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Looks for the span of the value passed to a binding in the stack of scopes.
+    fn lookup_origin(&self, ident: &atom::Ident) -> Option<Span> {
+        self.scopes
+            .iter()
+            .rev()
+            .find(|s| s.1.contains_key(ident))
+            .and_then(|s| s.2.get(ident).cloned())
+    }
+
+    /// Returns the span of the value behind the given expression,
+    /// following function parameters to the arguments of the current applications.
+    fn value_span(&self, node: NRef<'a>) -> Option<Span> {
+        value_span_with(self.mods, node, |b| self.lookup_origin(&b.ident()))
     }
 
     /// Returns a unique identifier for the given node.
@@ -134,7 +259,7 @@ impl<'a> Context<'a> {
     fn node_identifier(&self, node: NRef, scoped: bool) -> atom::Ident {
         let mut hash = Sha256::new();
         if scoped {
-            let scope_id = self.scopes.last().map_or(0, |(id, _)| *id);
+            let scope_id = self.scopes.last().map_or(0, |(id, _, _)| *id);
             hash.update(scope_id.to_be_bytes());
         }
         node.digest(&mut hash);
@@ -142,25 +267,25 @@ impl<'a> Context<'a> {
     }
 }
 
-fn compose_annotations<'a, I>(anns: I) -> Result<Annotation>
-where
-    I: Iterator<Item = syn::Annotation<'a, Core>>,
-{
-    let mut ann = Annotation::default();
-    for a in anns {
-        let other =
-            Annotation::try_from(a.as_str()).map_err(|err| Error::from(err).at(a.node().span()))?;
-        ann.extend(other);
-    }
-    Ok(ann)
-}
-
+/// Casts an expression into a schema.
+///
+/// The title, description and examples are those of the annotations accumulated
+/// from the definition to the usage site, see [`Annotation::extend_usage`].
 pub fn cast_schema(from: (Expr, AnnRef)) -> Schema {
     let ann = from.1;
     let desc = ann.get_string("description");
+    let translations = ann.get_translations("description");
     let title = ann.get_string("title");
     let required = ann.get_bool("required");
-    let examples = ann.get_props("examples");
+    let deprecated = ann.get_bool("deprecated");
+    let nullable = ann.get_bool("nullable");
+    let examples = ann.get_examples("examples");
+    let default = ann.props.get("default").cloned();
+    let constant = ann.props.get("const").cloned();
+    // Stability annotations are validated along with the annotations they come from.
+    let stability = ann.get_str(STABILITY).and_then(|s| s.parse().ok());
+    let extensions = ann.get_extensions();
+    let keywords = ann.get_keywords();
 
     let expr = match from.0 {
         Expr::Object(o) => SchemaExpr::Object(*o),
@@ -176,13 +301,28 @@ pub fn cast_schema(from: (Expr, AnnRef)) -> Schema {
         Expr::Recursion(r) => SchemaExpr::Ref(r),
         e => panic!("not a schema: {e:?}"),
     };
+    let example = match expr {
+        SchemaExpr::Object(_) | SchemaExpr::Array(_) | SchemaExpr::Op(_) => {
+            ann.props.get("example").cloned()
+        }
+        _ => None,
+    };
 
     Schema {
         expr,
         desc,
+        translations,
         title,
         required,
         examples,
+        example,
+        extensions,
+        keywords,
+        deprecated,
+        nullable,
+        default,
+        constant,
+        stability,
     }
 }
 
@@ -247,6 +387,28 @@ pub fn cast_object(from: (Expr, AnnRef)) -> Object {
     }
 }
 
+/// Casts the value of headers, merging the properties of a join of objects.
+pub(crate) fn cast_headers(from: (Expr, AnnRef)) -> Option<Object> {
+    match from.0 {
+        Expr::Object(o) => Some(*o),
+        Expr::Reference(_, v) => cast_headers(*v),
+        Expr::VariadicOp(op) if op.op == atom::VariadicOperator::Join => {
+            let mut props = Vec::new();
+            for schema in op.schemas {
+                let SchemaExpr::Object(o) = schema.expr else {
+                    return None;
+                };
+                props.extend(o.props);
+            }
+            Some(Object {
+                props,
+                ..Default::default()
+            })
+        }
+        _ => None,
+    }
+}
+
 pub fn cast_transfer(from: (Expr, AnnRef)) -> Transfer {
     match from.0 {
         Expr::Transfer(x) => *x,
@@ -290,53 +452,503 @@ pub fn eval_terminal<'a>(
     ann: AnnRef,
 ) -> Result<(Expr<'a>, AnnRef)> {
     let mut next_ann = ann.as_ref().clone();
-    next_ann.extend(compose_annotations(terminal.annotations())?);
+    let own = compose_cached_annotations(&mut ctx.annotations, terminal.annotations())?;
+    get_stability(&own, terminal.annotations())?;
+    let example = own.props.get(EXAMPLE).cloned();
+    next_ann.extend(own);
     let next_ann = AnnRef::new(next_ann);
-    eval_any(ctx, terminal.inner(), next_ann)
+    let value = eval_any(ctx, terminal.inner(), next_ann)?;
+    if let Some(example) = example {
+        check_example(ctx, &value, &example, example_span(terminal.annotations()))?;
+    }
+    Ok(value)
+}
+
+/// Returns the kind of an example value, e.g. `an object`.
+fn value_kind(value: &serde_yaml::Value) -> &'static str {
+    match value {
+        serde_yaml::Value::Null => "null",
+        serde_yaml::Value::Bool(_) => "a boolean",
+        serde_yaml::Value::Number(n) if n.is_f64() => "a number",
+        serde_yaml::Value::Number(_) => "an integer",
+        serde_yaml::Value::String(_) => "a string",
+        serde_yaml::Value::Sequence(_) => "an array",
+        serde_yaml::Value::Mapping(_) => "an object",
+        serde_yaml::Value::Tagged(_) => "a tagged value",
+    }
+}
+
+/// Returns the first structural mismatch between an example value and a schema, if any.
+///
+/// Only the kind of values and the presence of required properties are checked,
+/// with null accepted for nullable schemas and optional properties.
+/// Each comparison is an evaluation step, and the check gives up once `budget` comparisons
+/// are made, shared by all alternatives, as recursive sums may match values many ways.
+fn example_mismatch(
+    ctx: &mut Context,
+    schema: &Schema,
+    value: &serde_yaml::Value,
+    path: &str,
+    budget: &mut usize,
+    span: Option<Span>,
+) -> Result<Option<String>> {
+    if *budget == 0 {
+        return Ok(None);
+    }
+    *budget -= 1;
+    count_step(ctx, span.clone())?;
+    let at = if path.is_empty() {
+        String::new()
+    } else {
+        format!(" at {path}")
+    };
+    if value.is_null() && schema.nullable == Some(true) {
+        return Ok(None);
+    }
+    let expected = match &schema.expr {
+        SchemaExpr::Int(_) => "an integer",
+        SchemaExpr::Num(_) => "a number",
+        SchemaExpr::Str(_) | SchemaExpr::Uri(_) => "a string",
+        SchemaExpr::Bool(_) => "a boolean",
+        SchemaExpr::Object(_) => "an object",
+        SchemaExpr::Array(_) => "an array",
+        SchemaExpr::Rel(_) => return Ok(None),
+        SchemaExpr::Ref(ident) => {
+            let Some(Some(referenced)) = ctx.refs.get(ident) else {
+                return Ok(None);
+            };
+            let schema = cast_schema(referenced.clone());
+            return example_mismatch(ctx, &schema, value, path, budget, span);
+        }
+        SchemaExpr::Op(op) => {
+            for s in op.schemas.iter() {
+                let mismatch = example_mismatch(ctx, s, value, path, budget, span.clone())?;
+                match (op.op, mismatch) {
+                    (atom::VariadicOperator::Join, Some(m)) => return Ok(Some(m)),
+                    (atom::VariadicOperator::Join, None) => {}
+                    (_, None) => return Ok(None),
+                    (_, Some(_)) => {}
+                }
+            }
+            return Ok(if op.op == atom::VariadicOperator::Join {
+                None
+            } else {
+                Some(format!("example{at} matches none of the alternatives"))
+            });
+        }
+    };
+    let kind = value_kind(value);
+    let conforms = match (&schema.expr, value) {
+        (SchemaExpr::Num(_), serde_yaml::Value::Number(_)) => true,
+        _ => kind == expected,
+    };
+    if !conforms {
+        return Ok(Some(format!("example{at} is {kind} instead of {expected}")));
+    }
+    match (&schema.expr, value) {
+        (SchemaExpr::Object(o), serde_yaml::Value::Mapping(m)) => {
+            for p in o.props.iter() {
+                let name = p.name.as_ref();
+                let required = p.required.or(p.schema.required).unwrap_or(false);
+                let mismatch = match m.get(name) {
+                    Some(v) if v.is_null() && !required => None,
+                    Some(v) => {
+                        let path = format!("{path}/{name}");
+                        example_mismatch(ctx, &p.schema, v, &path, budget, span.clone())?
+                    }
+                    None if required => Some(format!(
+                        "example{at} is missing the required property {name}"
+                    )),
+                    None => None,
+                };
+                if mismatch.is_some() {
+                    return Ok(mismatch);
+                }
+            }
+            Ok(None)
+        }
+        (SchemaExpr::Array(a), serde_yaml::Value::Sequence(items)) => {
+            for (i, v) in items.iter().enumerate() {
+                let path = format!("{path}/{i}");
+                let mismatch = example_mismatch(ctx, &a.item, v, &path, budget, span.clone())?;
+                if mismatch.is_some() {
+                    return Ok(mismatch);
+                }
+            }
+            Ok(None)
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Returns the stability given by annotations, if any and valid.
+fn get_stability<'a, I>(ann: &Annotation, mut anns: I) -> Result<Option<Stability>>
+where
+    I: Iterator<Item = syn::Annotation<'a, Core>>,
+{
+    let Some(value) = ann.props.get(STABILITY) else {
+        return Ok(None);
+    };
+    let span = anns
+        .find(|a| a.as_str().contains("stability:"))
+        .and_then(|a| a.node().span());
+    match value.as_str() {
+        Some(s) => s.parse().map(Some).map_err(|err: Error| err.at(span)),
+        None => Err(Error::new(Kind::InvalidLiteral, "stability must be a string").at(span)),
+    }
+}
+
+/// Returns the span of the first annotation among the given ones providing an example.
+fn example_span<'a, I>(mut anns: I) -> Option<Span>
+where
+    I: Iterator<Item = syn::Annotation<'a, Core>>,
+{
+    anns.find(|a| a.as_str().contains("example:"))
+        .and_then(|a| a.node().span())
+}
+
+/// Warns if an example does not match the structure of the value it annotates.
+fn check_example(
+    ctx: &mut Context,
+    value: &Value,
+    example: &serde_yaml::Value,
+    span: Option<Span>,
+) -> Result<()> {
+    if !value.0.is_schema_like() {
+        return Ok(());
+    }
+    let schema = cast_schema(value.clone());
+    let mut budget = MAX_EXAMPLE_STEPS;
+    if let Some(msg) = example_mismatch(ctx, &schema, example, "", &mut budget, span.clone())? {
+        ctx.warn(Error::new(Kind::InvalidExample, msg).at(span));
+    }
+    Ok(())
+}
+
+/// Checks whether the given name is a valid HTTP method token that is not one of the standard methods.
+fn is_custom_method(name: &str) -> bool {
+    const STANDARD: [&str; 8] = [
+        "get", "put", "post", "patch", "delete", "options", "head", "trace",
+    ];
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(c))
+        && !STANDARD.contains(&name.to_lowercase().as_str())
+}
+
+/// The headers that cannot be declared as response headers, in lowercase.
+const RESERVED_RESPONSE_HEADERS: &[&str] = &["content-type", "authorization"];
+
+/// Removes headers whose names only differ by case from a previous one, with a warning.
+fn dedup_headers(
+    ctx: &mut Context,
+    headers: Object,
+    span: Option<Span>,
+    label: Option<Span>,
+) -> Object {
+    let mut props: Vec<Property> = Vec::new();
+    for prop in headers.props {
+        let name = prop.name.as_ref();
+        match props
+            .iter()
+            .find(|p| p.name.as_ref().eq_ignore_ascii_case(name))
+        {
+            Some(first) => ctx.warn(
+                Error::new(
+                    Kind::DuplicateHeader,
+                    format!("header '{name}' is already declared as '{}'", first.name),
+                )
+                .at(span.clone())
+                .with_label(label.clone(), "headers defined here"),
+            ),
+            None => props.push(prop),
+        }
+    }
+    Object {
+        props,
+        ..Default::default()
+    }
+}
+
+/// Checks that no response declares a reserved header.
+pub(crate) fn check_response_headers(ranges: &Ranges, span: Option<Span>) -> Result<()> {
+    let headers = ranges
+        .values()
+        .filter_map(|c| c.headers.as_ref())
+        .flat_map(|h| h.props.iter());
+    for prop in headers {
+        let name = prop.name.as_ref();
+        if RESERVED_RESPONSE_HEADERS.contains(&name.to_ascii_lowercase().as_str()) {
+            return Err(Error::new(
+                Kind::ReservedHeader,
+                format!("header '{name}' cannot be declared in a response"),
+            )
+            .at(span));
+        }
+    }
+    Ok(())
+}
+
+/// Warns about headers declared on the default response only, alongside responses with a status.
+fn check_default_headers(ctx: &mut Context, ranges: &Ranges, span: Option<Span>) {
+    let has_status = ranges.keys().any(|(status, _)| status.is_some());
+    let default_headers = ranges.iter().any(|((status, _), content)| {
+        status.is_none()
+            && content
+                .headers
+                .as_ref()
+                .is_some_and(|h| !h.props.is_empty())
+    });
+    if has_status && default_headers {
+        ctx.warn(
+            Error::new(
+                Kind::DefaultHeaders,
+                "headers of the default response do not apply to the responses with a status",
+            )
+            .at(span),
+        );
+    }
+}
+
+/// The annotations of transfers emitted as vendor extensions of operations, by extension name.
+const TRANSFER_EXTENSIONS: &[(&str, &str)] = &[("maxBodyBytes", "x-max-body-bytes")];
+
+/// The vendor extensions of transfers that must be sizes, e.g. a number of bytes or milliseconds.
+const SIZE_EXTENSIONS: &[&str] = &["x-max-body-bytes", "x-timeout-ms"];
+
+/// Returns the vendor extensions of a transfer from its annotations.
+fn transfer_extensions(ann: &Annotation) -> Result<Extensions> {
+    let mut extensions = ann.get_extensions();
+    // The custom method is emitted as the transfer verb instead.
+    extensions.shift_remove("x-http-method");
+    for (name, ext) in TRANSFER_EXTENSIONS {
+        if let Some(value) = ann.props.get(*name) {
+            extensions.insert(ext.to_string(), value.clone());
+        }
+    }
+    for name in SIZE_EXTENSIONS {
+        if let Some(value) = extensions.get(*name) {
+            if value.as_u64().is_none() {
+                return Err(Error::new(
+                    Kind::InvalidLiteral,
+                    format!("{name} must be a non-negative integer"),
+                ));
+            }
+        }
+    }
+    Ok(extensions)
+}
+
+/// Returns the operands of a join of parameter groups, or the parameters themselves.
+fn param_groups(node: NRef) -> Vec<NRef> {
+    if let Some(term) = syn::Terminal::cast(node) {
+        param_groups(term.inner())
+    } else if let Some(expr) = syn::SubExpression::cast(node) {
+        param_groups(expr.inner())
+    } else if let Some(op) =
+        syn::VariadicOp::cast(node).filter(|op| op.operator() == atom::VariadicOperator::Join)
+    {
+        op.operands().flat_map(param_groups).collect()
+    } else {
+        vec![node]
+    }
+}
+
+/// Evaluates the parameters of a transfer, merging the parameter groups,
+/// and returns them along with the names of the parameters from named groups.
+///
+/// Groups may declare the same parameter more than once, as long as identically.
+fn eval_params<'a>(ctx: &mut Context<'a>, node: NRef<'a>) -> Result<(Object, Vec<atom::Text>)> {
+    let mut props: Vec<(Property, Option<Span>)> = Vec::new();
+    let mut shared = Vec::new();
+    for group in param_groups(node) {
+        let span = group.span();
+        let named = syn::Variable::cast(group).is_some();
+        let Some(params) = cast_headers(eval_any(ctx, group, AnnRef::default())?) else {
+            return Err(Error::new(Kind::InvalidType, "ill-formed parameters").at(span));
+        };
+        for prop in params.props {
+            check_param_style(&prop, false, span.clone())?;
+            match props.iter().find(|(p, _)| p.name == prop.name) {
+                Some((p, _)) if *p == prop => {}
+                Some((_, first)) => {
+                    return Err(Error::new(
+                        Kind::Conflict,
+                        format!("parameter '{}' is already defined differently", prop.name),
+                    )
+                    .at(span)
+                    .with_label(first.clone(), "first defined here"))
+                }
+                None => props.push((prop.clone(), span.clone())),
+            }
+            if named && !shared.contains(&prop.name) {
+                shared.push(prop.name);
+            }
+        }
+    }
+    let params = Object {
+        props: props.into_iter().map(|(p, _)| p).collect(),
+        ..Default::default()
+    };
+    Ok((params, shared))
+}
+
+/// Evaluates the headers of a content, merging the header groups,
+/// and returns them along with the names of the headers from named groups.
+fn eval_headers<'a>(
+    ctx: &mut Context<'a>,
+    node: NRef<'a>,
+) -> Result<Option<(Object, Vec<atom::Text>)>> {
+    let mut props = Vec::new();
+    let mut shared = Vec::new();
+    for group in param_groups(node) {
+        let named = syn::Variable::cast(group).is_some();
+        let Some(headers) = cast_headers(eval_any(ctx, group, AnnRef::default())?) else {
+            return Ok(None);
+        };
+        for prop in headers.props {
+            if named && !shared.contains(&prop.name) {
+                shared.push(prop.name.clone());
+            }
+            props.push(prop);
+        }
+    }
+    let headers = Object {
+        props,
+        ..Default::default()
+    };
+    Ok(Some((headers, shared)))
 }
 
 pub fn eval_transfer<'a>(
     ctx: &mut Context<'a>,
     transfer: syn::Transfer<'a, Core>,
     ann: AnnRef,
+) -> Result<(Expr<'a>, AnnRef)> {
+    eval_transfer_with(ctx, transfer, ann, AnnRef::default())
+}
+
+/// Evaluates a transfer, with additional annotations for its range.
+fn eval_transfer_with<'a>(
+    ctx: &mut Context<'a>,
+    transfer: syn::Transfer<'a, Core>,
+    ann: AnnRef,
+    range_ann: AnnRef,
 ) -> Result<(Expr<'a>, AnnRef)> {
     let desc = ann.get_string("description");
+    let translations = ann.get_translations("description");
     let summary = ann.get_string("summary");
+    let deprecated = ann.get_bool("deprecated");
+    let stability = ann.get_str(STABILITY).and_then(|s| s.parse().ok());
     let tags = ann.get_enum("tags").unwrap_or_default();
     let id = ann.get_string("operationId");
+    let verb = match ann.get_str("x-http-method") {
+        Some(verb) if is_custom_method(verb) => Some(verb.to_lowercase()),
+        Some(_) => {
+            return Err(
+                Error::new(Kind::InvalidLiteral, "not a valid non-standard HTTP method")
+                    .at(transfer.node().span()),
+            )
+        }
+        None => None,
+    };
+    let extensions = transfer_extensions(&ann).map_err(|err| err.at(transfer.node().span()))?;
+    let callbacks = ann
+        .get_callbacks("callbacks")
+        .map_err(|msg| Error::new(Kind::InvalidLiteral, msg).at(transfer.node().span()))?;
 
     let mut methods = EnumMap::default();
     for m in transfer.methods() {
         methods[m] = true;
     }
 
-    let domain = match transfer.domain() {
-        Some(term) => cast_content(eval_terminal(ctx, term, AnnRef::default())?),
-        None => Content::default(),
+    // A domain of several contents, e.g. `(<media="a", x> :: <media="b", y>)`,
+    // describes the media types accepted for the request body.
+    let (domain, alt_domains) = match transfer.domain() {
+        Some(term) => {
+            let ranges = cast_ranges(eval_terminal(ctx, term, AnnRef::default())?);
+            let mut contents = ranges.into_values();
+            let domain = contents.next().unwrap_or_default();
+            (domain, contents.collect())
+        }
+        None => (Content::default(), Vec::new()),
     };
 
-    let ranges = cast_ranges(eval_any(ctx, transfer.range(), AnnRef::default())?);
+    let ranges = cast_ranges(eval_any(ctx, transfer.range(), range_ann)?);
+    check_response_headers(&ranges, transfer.range().span())?;
+    check_default_headers(ctx, &ranges, transfer.range().span());
 
-    let params = match transfer.params() {
-        Some(object) => Some(cast_object(eval_object(ctx, object, AnnRef::default())?)),
-        None => None,
+    let (params, shared_params) = match transfer.params() {
+        Some(node) => {
+            let (params, shared) = eval_params(ctx, node)?;
+            (Some(params), shared)
+        }
+        None => (None, Vec::new()),
     };
 
     let xfer = Transfer {
         methods,
         domain,
+        alt_domains,
         ranges,
         params,
+        shared_params,
         desc,
+        translations,
         summary,
         tags,
         id,
+        verb,
+        servers: ann.get_servers("servers"),
+        security: ann.get_security("security"),
+        extensions,
+        deprecated,
+        stability,
+        callbacks,
+        span: transfer.node().span(),
     };
 
     let expr = Expr::Transfer(Box::new(xfer));
     Ok((expr, ann))
 }
 
+/// The annotations of operations, as opposed to the annotations of their range.
+const OPERATION_ANNOTATIONS: &[&str] = &[
+    "summary",
+    "tags",
+    "operationId",
+    "deprecated",
+    STABILITY,
+    "servers",
+    "security",
+    "callbacks",
+    "maxBodyBytes",
+    "x-http-method",
+    "x-max-body-bytes",
+    "x-timeout-ms",
+];
+
+/// Evaluates an item of a transfer list.
+///
+/// The inline annotation ending a transfer applies to the operation for operation annotations
+/// such as `tags`, and to the range otherwise, e.g. for a `description` of the response.
+fn eval_xfer_item<'a>(
+    ctx: &mut Context<'a>,
+    item: syn::Terminal<'a, Core>,
+) -> Result<(Expr<'a>, AnnRef)> {
+    let (Some(transfer), Some(suffix)) =
+        (syn::Transfer::cast(item.inner()), item.suffix_annotation())
+    else {
+        return eval_terminal(ctx, item, AnnRef::default());
+    };
+    let mut ann = compose_cached_annotations(&mut ctx.annotations, item.prefix_annotations())?;
+    let mut range_ann = compose_cached_annotations(&mut ctx.annotations, std::iter::once(suffix))?;
+    ann.extend(range_ann.take(OPERATION_ANNOTATIONS));
+    get_stability(&ann, item.annotations())?;
+    eval_transfer_with(ctx, transfer, AnnRef::new(ann), AnnRef::new(range_ann))
+}
+
 pub fn eval_relation<'a>(
     ctx: &mut Context<'a>,
     relation: syn::Relation<'a, Core>,
@@ -344,30 +956,499 @@ pub fn eval_relation<'a>(
 ) -> Result<(Expr<'a>, AnnRef)> {
     let uri = cast_uri(eval_terminal(ctx, relation.uri(), AnnRef::default())?);
 
+    // Servers and security requirements of the relation apply to transfers without their own.
+    let servers = ann.get_servers("servers");
+    let security = ann.get_security("security");
+
     let mut xfers = Transfers::default();
-    for x in relation.transfers() {
-        let xfer = cast_transfer(eval_any(ctx, x, AnnRef::default())?);
+    let mut custom_xfers = CustomTransfers::default();
+    // The transfer item defining each method, for reporting conflicts.
+    let mut methods: EnumMap<atom::Method, Option<Span>> = EnumMap::default();
+    let mut verbs: HashMap<String, Option<Span>> = HashMap::new();
+    for x in relation.items() {
+        let span = x.node().span();
+        let mut xfer = cast_transfer(eval_xfer_item(ctx, x)?);
+        if xfer.servers.is_none() {
+            xfer.servers.clone_from(&servers);
+        }
+        if xfer.security.is_none() {
+            xfer.security.clone_from(&security);
+        }
+        if let Some(verb) = &xfer.verb {
+            if let Some(first) = verbs.get(verb) {
+                return Err(conflicting_method(verb, span, first));
+            }
+            verbs.insert(verb.clone(), span);
+            custom_xfers.insert(verb.clone(), xfer);
+            continue;
+        }
         for (m, b) in xfer.methods {
             if b {
+                if xfers[m].is_some() {
+                    let name = format!("{m:?}").to_lowercase();
+                    return Err(conflicting_method(&name, span, &methods[m]));
+                }
                 xfers[m] = Some(xfer.clone());
+                methods[m] = span.clone();
             }
         }
     }
 
-    let rel = Relation { uri, xfers };
+    let rel = Relation {
+        uri,
+        xfers,
+        custom_xfers,
+        webhook: ann.get_string("webhook"),
+        callback: ann.get_string("callback"),
+        tag_group: ann.get_string("tagGroup"),
+        module: relation.node().span().map(|s| s.locator().clone()),
+        extensions: ann.get_extensions(),
+    };
     let expr = Expr::Relation(Box::new(rel));
     Ok((expr, ann))
 }
 
+/// Returns the name of a schema component, replacing the characters not allowed by OpenAPI.
+fn component_name(name: &str) -> Result<String> {
+    if name.is_empty() {
+        return Err(Error::new(
+            Kind::InvalidLiteral,
+            "component name cannot be empty",
+        ));
+    }
+    let name = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || "._-".contains(c) {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    Ok(name)
+}
+
+/// Checks that no two references share a schema component name,
+/// returning the names of the renamed references.
+fn check_component_names(
+    ctx: &Context,
+    refs: &IndexMap<atom::Ident, Reference>,
+) -> Result<IndexMap<atom::Ident, String>> {
+    let mut names: HashMap<String, (&atom::Ident, Option<Span>)> = HashMap::new();
+    for ident in refs.keys() {
+        let (name, span) = match ctx.component_names.get(ident) {
+            Some((name, span)) => (name.clone(), span.clone()),
+            None => (ident.untagged(), None),
+        };
+        if let Some((other, first)) = names.get(&name) {
+            let err = Error::new(
+                Kind::Conflict,
+                format!("schema component {name} is already defined by {other}"),
+            );
+            return Err(match span {
+                Some(_) => err.at(span).with_label(first.clone(), "first defined here"),
+                None => err.at(first.clone()),
+            });
+        }
+        names.insert(name, (ident, span));
+    }
+    let renamed = ctx
+        .component_names
+        .iter()
+        .filter(|(ident, _)| refs.contains_key(*ident))
+        .map(|(ident, (name, _))| (ident.clone(), name.clone()))
+        .collect();
+    Ok(renamed)
+}
+
+/// Collects the references of a schema, without following them.
+fn schema_refs<'s>(schema: &'s Schema, out: &mut Vec<&'s atom::Ident>) {
+    match &schema.expr {
+        SchemaExpr::Ref(ident) => out.push(ident),
+        SchemaExpr::Object(obj) => object_refs(obj, out),
+        SchemaExpr::Array(array) => schema_refs(&array.item, out),
+        SchemaExpr::Op(op) => op.schemas.iter().for_each(|s| schema_refs(s, out)),
+        SchemaExpr::Uri(uri) => uri_refs(uri, out),
+        SchemaExpr::Rel(rel) => uri_refs(&rel.uri, out),
+        _ => {}
+    }
+}
+
+fn object_refs<'s>(obj: &'s Object, out: &mut Vec<&'s atom::Ident>) {
+    obj.props.iter().for_each(|p| schema_refs(&p.schema, out));
+}
+
+fn uri_refs<'s>(uri: &'s Uri, out: &mut Vec<&'s atom::Ident>) {
+    for segment in uri.path.iter() {
+        if let UriSegment::Variable(prop) = segment {
+            schema_refs(&prop.schema, out);
+        }
+    }
+    uri.params.iter().for_each(|p| object_refs(p, out));
+}
+
+fn content_refs<'s>(content: &'s Content, out: &mut Vec<&'s atom::Ident>) {
+    content.schema.iter().for_each(|s| schema_refs(s, out));
+    content.headers.iter().for_each(|h| object_refs(h, out));
+}
+
+fn transfer_refs(xfer: &Transfer) -> Vec<&atom::Ident> {
+    let mut out = Vec::new();
+    content_refs(&xfer.domain, &mut out);
+    xfer.alt_domains
+        .iter()
+        .for_each(|c| content_refs(c, &mut out));
+    xfer.ranges.values().for_each(|c| content_refs(c, &mut out));
+    xfer.params.iter().for_each(|p| object_refs(p, &mut out));
+    out
+}
+
+/// Removes the recursive URI definitions that no schema refers to.
+///
+/// A URI definition is recursive if referenced from its own parameters,
+/// but is inlined wherever used as a URI, e.g. as the argument of `concat`.
+fn prune_recursive_uris(rels: &[Relation], refs: &mut IndexMap<atom::Ident, Reference>) {
+    let mut used = HashSet::new();
+    for rel in rels {
+        let mut out = Vec::new();
+        uri_refs(&rel.uri, &mut out);
+        let xfers = rel.xfers.values().flatten();
+        out.extend(
+            xfers
+                .chain(rel.custom_xfers.values())
+                .flat_map(transfer_refs),
+        );
+        used.extend(out.into_iter().cloned());
+    }
+    for Reference::Schema(schema) in refs.values() {
+        let mut out = Vec::new();
+        schema_refs(schema, &mut out);
+        used.extend(out.into_iter().cloned());
+    }
+    refs.retain(|ident, Reference::Schema(schema)| {
+        ident.is_reference() || !matches!(schema.expr, SchemaExpr::Uri(_)) || used.contains(ident)
+    });
+}
+
+/// Returns the least stable schema component reachable from the given references, if any.
+fn least_stable<'s>(
+    refs: &'s IndexMap<atom::Ident, Reference>,
+    mut stack: Vec<&'s atom::Ident>,
+) -> Option<(&'s atom::Ident, Stability)> {
+    let mut visited = HashSet::new();
+    let mut least: Option<(&atom::Ident, Stability)> = None;
+    while let Some(ident) = stack.pop() {
+        if !visited.insert(ident) {
+            continue;
+        }
+        let Some(Reference::Schema(schema)) = refs.get(ident) else {
+            continue;
+        };
+        if let Some(stability) = schema.stability {
+            if least.is_none_or(|(_, s)| stability < s) {
+                least = Some((ident, stability));
+            }
+        }
+        schema_refs(schema, &mut stack);
+    }
+    least
+}
+
+/// Warns about the operations and schema components referencing less stable schema components,
+/// directly or through other components.
+fn check_stability(
+    ctx: &mut Context,
+    rels: &[Relation],
+    origins: &HashMap<RelationKey, RelationOrigins>,
+    refs: &IndexMap<atom::Ident, Reference>,
+) {
+    let mut warnings = Vec::new();
+    let mut check = |what: String, stability: Option<Stability>, roots, span: &Option<Span>| {
+        let Some(stability) = stability else {
+            return;
+        };
+        if let Some((ident, least)) = least_stable(refs, roots).filter(|(_, l)| *l < stability) {
+            let name = ident.untagged();
+            let msg = format!("{stability} {what} references the {least} schema component {name}");
+            let label = ctx.ref_spans.get(ident).cloned().flatten();
+            let err = Error::new(Kind::UnstableReference, msg)
+                .at(span.clone())
+                .with_label(label, format!("declared {least} here"));
+            warnings.push(err);
+        }
+    };
+
+    let mut origins = origins.values().collect::<Vec<_>>();
+    origins.sort_by_key(|o| o.index);
+    for origin in origins {
+        let rel = &rels[origin.index];
+        for (m, xfer) in rel.xfers.iter() {
+            if let Some(xfer) = xfer {
+                let what = "operation".to_owned();
+                check(
+                    what,
+                    xfer.stability,
+                    transfer_refs(xfer),
+                    &origin.methods[m],
+                );
+            }
+        }
+        for (verb, xfer) in rel.custom_xfers.iter() {
+            let span = origin.verbs.get(verb).cloned().flatten();
+            check(
+                "operation".to_owned(),
+                xfer.stability,
+                transfer_refs(xfer),
+                &span,
+            );
+        }
+    }
+    for (ident, Reference::Schema(schema)) in refs.iter() {
+        let mut roots = Vec::new();
+        schema_refs(schema, &mut roots);
+        let span = ctx.ref_spans.get(ident).cloned().flatten();
+        let what = format!("schema component {}", ident.untagged());
+        check(what, schema.stability, roots, &span);
+    }
+
+    for warning in warnings {
+        ctx.warn(warning);
+    }
+}
+
+/// Returns whether a schema has enough structure to be worth sharing, unlike primitives.
+fn is_structured(schema: &Schema) -> bool {
+    match &schema.expr {
+        SchemaExpr::Object(obj) => !obj.props.is_empty(),
+        SchemaExpr::Array(_) | SchemaExpr::Op(_) => true,
+        _ => false,
+    }
+}
+
+/// Warns about the schema components declared with the same structure as a previous one,
+/// suggesting to declare them once.
+///
+/// Components of the standard library are left out.
+fn check_duplicates(ctx: &mut Context, refs: &IndexMap<atom::Ident, Reference>) {
+    let mut seen: Vec<(&atom::Ident, &Schema, Option<Span>)> = Vec::new();
+    let mut warnings = Vec::new();
+    for (ident, Reference::Schema(schema)) in refs.iter() {
+        if !ident.is_reference() || !is_structured(schema) {
+            continue;
+        }
+        let span = ctx.ref_spans.get(ident).cloned().flatten();
+        if span
+            .as_ref()
+            .is_some_and(|s| library::source(s.locator()).is_some())
+        {
+            continue;
+        }
+        match seen.iter().find(|(_, s, _)| s.expr == schema.expr) {
+            Some((first, _, label)) => {
+                let msg = format!(
+                    "schema component {} has the same structure as {}, consider declaring it once",
+                    ident.untagged(),
+                    first.untagged()
+                );
+                let err = Error::new(Kind::DuplicateSchema, msg)
+                    .at(span)
+                    .with_label(label.clone(), "first declared here");
+                warnings.push(err);
+            }
+            None => seen.push((ident, schema, span)),
+        }
+    }
+    for warning in warnings {
+        ctx.warn(warning);
+    }
+}
+
+/// The resources defining each method of a relation, for reporting conflicts.
+#[derive(Default)]
+struct RelationOrigins {
+    index: usize,
+    methods: EnumMap<atom::Method, Option<Span>>,
+    verbs: HashMap<String, Option<Span>>,
+    tag_group: Option<Span>,
+}
+
+/// The webhook, callback and URI pattern identifying a relation.
+type RelationKey = (Option<String>, Option<String>, String);
+
+/// Checks that the callbacks of the operations are declared by relations, other than callbacks.
+fn check_callbacks(
+    rels: &[Relation],
+    origins: &HashMap<RelationKey, RelationOrigins>,
+) -> Result<()> {
+    let declared = rels
+        .iter()
+        .filter_map(|r| r.callback.as_deref())
+        .collect::<HashSet<_>>();
+    let mut origins = origins.values().collect::<Vec<_>>();
+    origins.sort_by_key(|o| o.index);
+    for origin in origins {
+        let rel = &rels[origin.index];
+        let xfers = rel.xfers.iter().filter_map(|(m, x)| {
+            let span = origin.methods[m].clone();
+            x.as_ref().map(|x| (x, span))
+        });
+        let custom = rel.custom_xfers.iter().map(|(verb, x)| {
+            let span = origin.verbs.get(verb).cloned().flatten();
+            (x, span)
+        });
+        for (xfer, span) in xfers.chain(custom) {
+            // Callbacks of callbacks would allow cycles between relations.
+            if rel.callback.is_some() && !xfer.callbacks.is_empty() {
+                return Err(Error::new(
+                    Kind::InvalidLiteral,
+                    "operations of a callback cannot declare callbacks",
+                )
+                .at(span));
+            }
+            if let Some(name) = xfer
+                .callbacks
+                .keys()
+                .find(|n| !declared.contains(n.as_str()))
+            {
+                return Err(Error::new(
+                    Kind::NotInScope,
+                    format!("callback {name} is not declared by any relation"),
+                )
+                .at(span));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn conflicting_method(name: &str, span: Option<Span>, first: &Option<Span>) -> Error {
+    Error::new(
+        Kind::Conflict,
+        format!("method {name} already defined for the same URI"),
+    )
+    .at(span)
+    .with_label(first.clone(), "first defined here")
+}
+
 pub fn eval_program<'a>(
     ctx: &mut Context<'a>,
     program: syn::Program<'a, Core>,
     ann: AnnRef,
 ) -> Result<(Expr<'a>, AnnRef)> {
-    let mut rels = Vec::new();
+    let doc = compose_cached_annotations(&mut ctx.annotations, program.annotations())?;
+    // The span of the first document annotation mentioning the given name.
+    let doc_span = |name: &str| {
+        program
+            .annotations()
+            .find(|a| a.as_str().contains(name))
+            .and_then(|a| a.node().span())
+    };
+    if let Some(status) = doc.get_int(EMPTY_STATUS) {
+        ctx.empty_status = u64::try_from(status)
+            .ok()
+            .and_then(|s| atom::HttpStatus::try_from(s).ok())
+            .ok_or_else(|| {
+                Error::new(Kind::InvalidLiteral, "not a valid HTTP status")
+                    .at(doc_span(EMPTY_STATUS))
+            })?;
+    }
+    let security_schemes = doc
+        .get_security_schemes(SECURITY_SCHEMES)
+        .map_err(|msg| Error::new(Kind::InvalidLiteral, msg).at(doc_span(SECURITY_SCHEMES)))?;
+    let security = doc.get_security("security");
+    let info = doc
+        .get_info(INFO)
+        .map_err(|msg| Error::new(Kind::InvalidLiteral, msg).at(doc_span(INFO)))?;
+    let servers = doc.get_servers("servers");
+    let tags = doc.get_tags("tags").unwrap_or_default();
+
+    let mut rels: Vec<Relation> = Vec::new();
+    // The index of each relation by webhook and URI pattern,
+    // along with the resource defining each method.
+    let mut origins: HashMap<RelationKey, RelationOrigins> = HashMap::new();
+    // The URI pattern of each webhook, as webhooks are identified by name only.
+    let mut webhooks: HashMap<String, (String, Option<Span>)> = HashMap::new();
     for res in program.resources() {
-        let rel = cast_relation(eval_any(ctx, res.relation(), AnnRef::default())?);
-        rels.push(rel);
+        let mut rel = cast_relation(eval_any(ctx, res.relation(), AnnRef::default())?);
+        let span = res.node().span();
+        if rel.module.is_none() {
+            rel.module = span.as_ref().map(|s| s.locator().clone());
+        }
+        let key = (rel.webhook.clone(), rel.callback.clone(), rel.uri.pattern());
+        if let Some(name) = &rel.webhook {
+            let (pattern, first) = webhooks
+                .entry(name.clone())
+                .or_insert_with(|| (key.2.clone(), span.clone()));
+            if *pattern != key.2 {
+                return Err(Error::new(
+                    Kind::Conflict,
+                    format!("webhook {name} already defined for another URI"),
+                )
+                .at(span)
+                .with_label(first.clone(), "first defined here"));
+            }
+        }
+        match origins.entry(key) {
+            Entry::Vacant(e) => {
+                let mut origin = RelationOrigins {
+                    index: rels.len(),
+                    ..Default::default()
+                };
+                for (m, x) in rel.xfers.iter() {
+                    if x.is_some() {
+                        origin.methods[m] = span.clone();
+                    }
+                }
+                for verb in rel.custom_xfers.keys() {
+                    origin.verbs.insert(verb.clone(), span.clone());
+                }
+                if rel.tag_group.is_some() {
+                    origin.tag_group = span.clone();
+                }
+                e.insert(origin);
+                rels.push(rel);
+            }
+            // Relations sharing the same webhook, callback and URI pattern are merged into a single relation.
+            Entry::Occupied(mut e) => {
+                let origin = e.get_mut();
+                let prev = &mut rels[origin.index];
+                match (&prev.tag_group, rel.tag_group) {
+                    (Some(first), Some(group)) if *first != group => {
+                        return Err(Error::new(
+                            Kind::Conflict,
+                            format!("tag group {group} differs from {first} for the same URI"),
+                        )
+                        .at(span)
+                        .with_label(origin.tag_group.clone(), "first defined here"));
+                    }
+                    (None, Some(group)) => {
+                        prev.tag_group = Some(group);
+                        origin.tag_group = span.clone();
+                    }
+                    _ => {}
+                }
+                prev.extensions.extend(rel.extensions);
+                for (m, x) in rel.xfers.into_iter() {
+                    if x.is_none() {
+                        continue;
+                    }
+                    if prev.xfers[m].is_some() {
+                        let name = format!("{m:?}").to_lowercase();
+                        return Err(conflicting_method(&name, span, &origin.methods[m]));
+                    }
+                    prev.xfers[m] = x;
+                    origin.methods[m] = span.clone();
+                }
+                for (verb, x) in rel.custom_xfers.into_iter() {
+                    if let Some(first) = origin.verbs.get(&verb) {
+                        return Err(conflicting_method(&verb, span, first));
+                    }
+                    origin.verbs.insert(verb.clone(), span.clone());
+                    prev.custom_xfers.insert(verb, x);
+                }
+            }
+        }
     }
 
     let mut refs = IndexMap::new();
@@ -381,12 +1462,43 @@ pub fn eval_program<'a>(
         }
     }
 
-    let spec = Spec { rels, refs };
+    prune_recursive_uris(&rels, &mut refs);
+    check_callbacks(&rels, &origins)?;
+    let component_names = check_component_names(ctx, &refs)?;
+    check_stability(ctx, &rels, &origins, &refs);
+    check_duplicates(ctx, &refs);
+
+    let spec = Spec {
+        rels,
+        refs,
+        security_schemes,
+        security,
+        component_names,
+        info,
+        servers,
+        tags,
+        module: program.node().span().map(|s| s.locator().clone()),
+    };
 
     let expr = Expr::Spec(Box::new(spec));
     Ok((expr, ann))
 }
 
+/// Checks that the style of a parameter applies to its location.
+fn check_param_style(prop: &Property, in_path: bool, span: Option<Span>) -> Result<()> {
+    match prop.style {
+        Some(style) if style.is_path() != in_path => {
+            let location = if in_path { "path" } else { "query" };
+            let msg = format!(
+                "style {style:?} does not apply to {location} parameter '{}'",
+                prop.name
+            );
+            Err(Error::new(Kind::InvalidLiteral, msg).at(span))
+        }
+        _ => Ok(()),
+    }
+}
+
 pub fn eval_uri_template<'a>(
     ctx: &mut Context<'a>,
     template: syn::UriTemplate<'a, Core>,
@@ -403,6 +1515,7 @@ pub fn eval_uri_template<'a>(
             }
             syn::UriSegment::Variable(var) => {
                 let p = cast_property(eval_any(ctx, var.inner(), AnnRef::default())?);
+                check_param_style(&p, true, var.node().span())?;
                 let s = UriSegment::Variable(Box::new(p));
                 path.push(s);
             }
@@ -410,7 +1523,14 @@ pub fn eval_uri_template<'a>(
     }
 
     let params = match template.params() {
-        Some(p) => Some(cast_object(eval_object(ctx, p, AnnRef::default())?)),
+        Some(p) => {
+            let span = p.node().span();
+            let params = cast_object(eval_object(ctx, p, AnnRef::default())?);
+            for prop in params.props.iter() {
+                check_param_style(prop, false, span.clone())?;
+            }
+            Some(params)
+        }
         None => None,
     };
 
@@ -433,13 +1553,16 @@ pub fn eval_declaration<'a>(
         let expr = Expr::Lambda(Lambda::External(decl));
         Ok((expr, ann))
     } else {
-        let mut rhs_ann = compose_annotations(decl.annotations())?;
-        rhs_ann.extend(ann.as_ref().clone());
+        let mut rhs_ann = compose_cached_annotations(&mut ctx.annotations, decl.annotations())?;
+        get_stability(&rhs_ann, decl.annotations())?;
+        let example = rhs_ann.props.get(EXAMPLE).cloned();
+        let renamed = rhs_ann.get_string(COMPONENT_NAME);
+        rhs_ann.extend_usage(ann.as_ref().clone());
         let rhs_ann = AnnRef::new(rhs_ann);
 
         let mut ident = decl.ident();
 
-        if ident.is_reference() || decl.node().syntax().core_ref().is_recursive {
+        let value = if ident.is_reference() || decl.node().syntax().core_ref().is_recursive {
             if !ident.is_reference() {
                 // As declarations only appear at the global scope,
                 // The identifier does not depend on the scope of evaluation.
@@ -447,9 +1570,15 @@ pub fn eval_declaration<'a>(
             }
             // Make sure we evaluate the reference or recursive declaration only once.
             let expr = if !ctx.refs.contains_key(&ident) {
+                if let Some(name) = renamed {
+                    let span = decl.node().span();
+                    let name = component_name(&name).map_err(|err| err.at(span.clone()))?;
+                    ctx.component_names.insert(ident.clone(), (name, span));
+                }
                 // Insert an empty reference to signal recursion
                 // before evaluating the right-hand side.
                 ctx.refs.insert(ident.clone(), None);
+                ctx.ref_spans.insert(ident.clone(), decl.node().span());
                 let value = eval_any(ctx, decl.rhs(), rhs_ann.clone())?;
                 // Overwrite the reference with the actual value.
                 ctx.refs.insert(ident.clone(), Some(value.clone()));
@@ -462,25 +1591,109 @@ pub fn eval_declaration<'a>(
                     None => Expr::Recursion(ident),
                 }
             };
-            Ok((expr, rhs_ann))
+            (expr, rhs_ann)
         } else {
             // Non-reference and non-recursive declarations are inlined.
-            eval_any(ctx, decl.rhs(), rhs_ann)
+            eval_any(ctx, decl.rhs(), rhs_ann)?
+        };
+        if let Some(example) = example {
+            check_example(ctx, &value, &example, example_span(decl.annotations()))?;
         }
+        Ok(value)
+    }
+}
+
+/// Returns the number of nodes of a value, i.e. of schemas, properties, contents and transfers.
+fn expr_nodes(expr: &Expr) -> usize {
+    1 + match expr {
+        Expr::Spec(_) => 0,
+        Expr::Uri(uri) => uri_nodes(uri),
+        Expr::Relation(rel) => relation_nodes(rel),
+        Expr::Transfer(xfer) => transfer_nodes(xfer),
+        Expr::Content(cnt) => content_nodes(cnt),
+        Expr::Object(obj) => object_nodes(obj),
+        Expr::Ranges(ranges) => ranges.values().map(content_nodes).sum(),
+        Expr::Property(prop) => schema_nodes(&prop.schema),
+        Expr::VariadicOp(op) => op.schemas.iter().map(schema_nodes).sum(),
+        Expr::Reference(_, value) => expr_nodes(&value.0),
+        Expr::Array(array) => schema_nodes(&array.item),
+        Expr::PrimInteger(_)
+        | Expr::PrimNumber(_)
+        | Expr::PrimString(_)
+        | Expr::PrimBoolean(_)
+        | Expr::String(_)
+        | Expr::Number(_)
+        | Expr::HttpStatus(_)
+        | Expr::Lambda(_)
+        | Expr::Recursion(_) => 0,
     }
 }
 
+fn schema_nodes(schema: &Schema) -> usize {
+    1 + match &schema.expr {
+        SchemaExpr::Rel(rel) => relation_nodes(rel),
+        SchemaExpr::Uri(uri) => uri_nodes(uri),
+        SchemaExpr::Array(array) => schema_nodes(&array.item),
+        SchemaExpr::Object(obj) => object_nodes(obj),
+        SchemaExpr::Op(op) => op.schemas.iter().map(schema_nodes).sum(),
+        SchemaExpr::Num(_)
+        | SchemaExpr::Str(_)
+        | SchemaExpr::Bool(_)
+        | SchemaExpr::Int(_)
+        | SchemaExpr::Ref(_) => 0,
+    }
+}
+
+fn object_nodes(obj: &Object) -> usize {
+    obj.props.iter().map(|p| 1 + schema_nodes(&p.schema)).sum()
+}
+
+fn uri_nodes(uri: &Uri) -> usize {
+    let vars = uri.path.iter().map(|s| match s {
+        UriSegment::Literal(_) => 1,
+        UriSegment::Variable(p) => 1 + schema_nodes(&p.schema),
+    });
+    vars.sum::<usize>() + uri.params.as_ref().map_or(0, object_nodes)
+}
+
+fn content_nodes(cnt: &Content) -> usize {
+    1 + cnt.schema.as_deref().map_or(0, schema_nodes) + cnt.headers.as_ref().map_or(0, object_nodes)
+}
+
+fn transfer_nodes(xfer: &Transfer) -> usize {
+    1 + content_nodes(&xfer.domain)
+        + xfer.alt_domains.iter().map(content_nodes).sum::<usize>()
+        + xfer.ranges.values().map(content_nodes).sum::<usize>()
+        + xfer.params.as_ref().map_or(0, object_nodes)
+}
+
+fn relation_nodes(rel: &Relation) -> usize {
+    let xfers = rel
+        .xfers
+        .values()
+        .flatten()
+        .chain(rel.custom_xfers.values());
+    uri_nodes(&rel.uri) + xfers.map(transfer_nodes).sum::<usize>()
+}
+
 pub fn eval_binding<'a>(
     ctx: &mut Context<'a>,
     binding: syn::Binding<'a, Core>,
     ann: AnnRef,
 ) -> Result<(Expr<'a>, AnnRef)> {
     let ident = binding.ident();
-    let Some((expr, prev_ann)) = ctx.lookup_binding(&ident) else {
+    let Some(value) = ctx.lookup_binding(&ident) else {
         panic!("binding '{}' should exist", ident)
     };
+    // Values are copied on each use, so the nodes are accounted for before copying them.
+    let nodes = expr_nodes(&value.0);
+    ctx.charge(nodes, binding.node().span())?;
+    let (expr, prev_ann) = ctx
+        .lookup_binding(&ident)
+        .cloned()
+        .expect("binding should exist");
     let mut next_ann = prev_ann.as_ref().clone();
-    next_ann.extend(ann.as_ref().clone());
+    next_ann.extend_usage(ann.as_ref().clone());
     Ok((expr, AnnRef::new(next_ann)))
 }
 
@@ -504,13 +1717,31 @@ pub fn eval_variable<'a>(
     }
 }
 
+/// Returns true if the name is a valid content coding, e.g. `gzip`.
+fn is_content_coding(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+}
+
 pub fn eval_content<'a>(
     ctx: &mut Context<'a>,
     content: syn::Content<'a, Core>,
     ann: AnnRef,
 ) -> Result<(Expr<'a>, AnnRef)> {
     let desc = ann.get_string("description");
-    let examples = ann.get_props("examples");
+    let translations = ann.get_translations("description");
+    let examples = ann.get_examples("examples");
+    let encodings = ann.get_enum("content-encoding").unwrap_or_default();
+    let extensions = ann.get_extensions();
+    if let Some(e) = encodings.iter().find(|e| !is_content_coding(e)) {
+        return Err(Error::new(
+            Kind::InvalidLiteral,
+            format!("not a valid content coding: {e}"),
+        )
+        .at(content.node().span()));
+    }
 
     let schema = match content.body() {
         Some(body) => {
@@ -521,34 +1752,71 @@ pub fn eval_content<'a>(
     };
 
     let mut status = if schema.is_none() {
-        Some(atom::HttpStatus::try_from(204).unwrap())
+        Some(ctx.empty_status)
     } else {
         None
     };
     let mut media = None;
     let mut headers = None;
+    let mut shared_headers = Vec::new();
     for meta in content.meta().into_iter().flatten() {
-        let rhs = eval_any(ctx, meta.rhs(), AnnRef::default())?;
         match meta.kind() {
-            syn::ContentTagKind::Media => media = Some(cast_string(rhs)),
-            syn::ContentTagKind::Headers => headers = Some(cast_object(rhs)),
+            syn::ContentTagKind::Media => {
+                let rhs = eval_any(ctx, meta.rhs(), AnnRef::default())?;
+                let m = cast_string(rhs).parse::<MediaType>().map_err(|err| {
+                    err.at(meta.rhs().span())
+                        .with_label(ctx.value_span(meta.rhs()), "value defined here")
+                })?;
+                media = Some(m)
+            }
+            syn::ContentTagKind::Headers => {
+                let span = meta.rhs().span();
+                let label = ctx.value_span(meta.rhs());
+                let Some((object, shared)) = eval_headers(ctx, meta.rhs())? else {
+                    return Err(Error::new(
+                        Kind::InvalidType,
+                        "headers must be an object or a join of objects",
+                    )
+                    .at(span)
+                    .with_label(label, "value defined here"));
+                };
+                headers = Some(dedup_headers(ctx, object, span, label));
+                shared_headers = shared;
+            }
             syn::ContentTagKind::Status => {
+                let rhs = eval_any(ctx, meta.rhs(), AnnRef::default())?;
                 let s = cast_http_status(rhs).map_err(|_| {
                     Error::new(Kind::InvalidLiteral, "not a valid HTTP status")
                         .at(meta.rhs().span())
+                        .with_label(ctx.value_span(meta.rhs()), "value defined here")
                 })?;
                 status = Some(s)
             }
         }
     }
 
+    let no_content = atom::HttpStatus::try_from(204).unwrap();
+    if schema.is_some() && status == Some(no_content) {
+        ctx.warn(
+            Error::new(
+                Kind::UnexpectedContent,
+                "a response with status 204 must not carry a schema",
+            )
+            .at(content.node().span()),
+        );
+    }
+
     let cnt = Content {
         schema,
         status,
         media,
         headers,
+        shared_headers,
         desc,
+        translations,
         examples,
+        encodings,
+        extensions,
     };
 
     let expr = Expr::Content(Box::new(cnt));
@@ -564,7 +1832,26 @@ pub fn eval_object<'a>(
     for prop in object.properties() {
         props.push(cast_property(eval_any(ctx, prop, AnnRef::default())?));
     }
-    let obj = Object { props };
+    let additional_props = match ann.props.get("additionalProperties") {
+        Some(serde_yaml::Value::Bool(b)) => Some(AdditionalProperties::Allowed(*b)),
+        Some(value @ serde_yaml::Value::Mapping(_)) => {
+            Some(AdditionalProperties::Schema(value.clone()))
+        }
+        Some(_) => {
+            return Err(Error::new(
+                Kind::InvalidLiteral,
+                "additional properties must be either a boolean or a schema",
+            )
+            .at(object.node().span()))
+        }
+        None => None,
+    };
+    let obj = Object {
+        props,
+        additional_props,
+        min_props: ann.get_size("minProperties"),
+        max_props: ann.get_size("maxProperties"),
+    };
     let expr = Expr::Object(Box::new(obj));
     Ok((expr, ann))
 }
@@ -579,21 +1866,231 @@ pub fn eval_variadic_operation<'a>(
         let mut ranges = Ranges::new();
         for operand in operation.operands() {
             let r = cast_ranges(eval_any(ctx, operand, AnnRef::default())?);
-            ranges.extend(r.into_iter());
+            ranges.extend(r);
         }
         Expr::Ranges(Box::new(ranges))
     } else {
-        let mut schemas = Vec::new();
+        let mut schemas: Vec<Schema> = Vec::new();
+        // The values of an alternative between literals, gathered by type.
+        let mut strings: Vec<String> = Vec::new();
+        let mut integers: Vec<i64> = Vec::new();
+        // The kind of each property of a join along with the operand defining it first.
+        let mut kinds: HashMap<atom::Text, (&str, Option<Span>)> = HashMap::new();
+        let discriminator = get_discriminator(&ann, operation.node().span())?;
+        if discriminator.is_some() && op != atom::VariadicOperator::Sum {
+            let msg = "a discriminator only applies to a sum";
+            return Err(Error::new(Kind::InvalidDiscriminator, msg).at(operation.node().span()));
+        }
         for operand in operation.operands() {
-            let s = cast_schema(eval_any(ctx, operand, AnnRef::default())?);
-            schemas.push(s);
+            let value = eval_any(ctx, operand, AnnRef::default())?;
+            if let Some(d) = discriminator.as_ref() {
+                check_discriminated(ctx, d, operand, &value.0)?;
+            }
+            match value.0 {
+                Expr::String(s) => {
+                    if !strings.contains(&s) {
+                        strings.push(s);
+                    }
+                    continue;
+                }
+                Expr::Number(n) => {
+                    let n = i64::try_from(n).map_err(|_| {
+                        Error::new(Kind::InvalidLiteral, "integer out of range").at(operand.span())
+                    })?;
+                    if !integers.contains(&n) {
+                        integers.push(n);
+                    }
+                    continue;
+                }
+                _ => {}
+            }
+            if op == atom::VariadicOperator::Join {
+                for prop in joined_properties(&value.0) {
+                    let Some(kind) = schema_kind(&prop.schema.expr) else {
+                        continue;
+                    };
+                    match kinds.entry(prop.name.clone()) {
+                        Entry::Vacant(e) => {
+                            e.insert((kind, operand.span()));
+                        }
+                        Entry::Occupied(e) if e.get().0 != kind => {
+                            let (first, span) = e.remove();
+                            let msg =
+                                format!("property {} cannot be both {first} and {kind}", prop.name);
+                            ctx.warn(
+                                Error::new(Kind::Unsatisfiable, msg)
+                                    .at(operation.node().span())
+                                    .with_label(span, format!("{first} defined here"))
+                                    .with_label(operand.span(), format!("{kind} defined here")),
+                            );
+                        }
+                        Entry::Occupied(_) => {}
+                    }
+                }
+            }
+            let s = cast_schema(value);
+            // Nested operations of the same kind are flattened, unless annotated.
+            let flattened = match s.expr {
+                SchemaExpr::Op(ref inner)
+                    if inner.op == op && s.is_bare() && inner.discriminator.is_none() =>
+                {
+                    inner.schemas.clone()
+                }
+                _ => vec![s],
+            };
+            for s in flattened {
+                if !schemas.contains(&s) {
+                    schemas.push(s);
+                }
+            }
+        }
+        let mut literals = Vec::new();
+        if !strings.is_empty() {
+            let p = PrimString {
+                enumeration: strings,
+                ..Default::default()
+            };
+            literals.push(Expr::PrimString(Box::new(p)));
+        }
+        if !integers.is_empty() {
+            let p = PrimInteger {
+                enumeration: integers,
+                ..Default::default()
+            };
+            literals.push(Expr::PrimInteger(Box::new(p)));
+        }
+        // Literals of a single type make up an enumeration rather than an alternative.
+        if schemas.is_empty() && literals.len() == 1 {
+            literals.pop().unwrap()
+        } else {
+            schemas.extend(
+                literals
+                    .into_iter()
+                    .map(|e| cast_schema((e, AnnRef::default()))),
+            );
+            // Schema components of the mapping must be alternatives of the sum.
+            let mapped = discriminator.iter().flat_map(|d| d.mapping.values());
+            for name in mapped.map(|s| atom::Ident::from(s.as_str())) {
+                let alternative = SchemaExpr::Ref(name.clone());
+                if name.is_reference() && !schemas.iter().any(|s| s.expr == alternative) {
+                    let msg =
+                        format!("discriminator maps to {name}, not an alternative of the sum");
+                    return Err(
+                        Error::new(Kind::InvalidDiscriminator, msg).at(operation.node().span())
+                    );
+                }
+            }
+            let var_op = VariadicOp {
+                op,
+                schemas,
+                discriminator,
+            };
+            Expr::VariadicOp(Box::new(var_op))
         }
-        let var_op = VariadicOp { op, schemas };
-        Expr::VariadicOp(Box::new(var_op))
     };
     Ok((expr, ann))
 }
 
+/// Checks that an alternative of a sum declares the discriminator property as required.
+fn check_discriminated(ctx: &Context, d: &Discriminator, operand: NRef, expr: &Expr) -> Result<()> {
+    let props = joined_properties(expr);
+    let Some(prop) = props.iter().find(|p| p.name == d.property_name.as_str()) else {
+        let msg = format!(
+            "alternative does not declare the discriminator property {}",
+            d.property_name
+        );
+        return Err(Error::new(Kind::InvalidDiscriminator, msg).at(operand.span()));
+    };
+    if prop.required.or(prop.schema.required).unwrap_or(false) {
+        return Ok(());
+    }
+    let msg = format!(
+        "discriminator property {} must be required",
+        d.property_name
+    );
+    let mut err = Error::new(Kind::InvalidDiscriminator, msg).at(operand.span());
+    if let Some(fix) = fix::require_property(ctx.mods, operand, &d.property_name) {
+        err = err.with_fix(fix);
+    }
+    Err(err)
+}
+
+/// Returns the discriminator of a sum, given either as a property name
+/// or as a `propertyName` with an optional `mapping` of values to schemas.
+fn get_discriminator(ann: &Annotation, span: Option<Span>) -> Result<Option<Discriminator>> {
+    let Some(value) = ann.props.get(DISCRIMINATOR) else {
+        return Ok(None);
+    };
+    let invalid = || {
+        let msg = "discriminator must be a property name or have a propertyName";
+        Error::new(Kind::InvalidLiteral, msg).at(span.clone())
+    };
+    if let Some(name) = value.as_str() {
+        return Ok(Some(Discriminator {
+            property_name: name.to_owned(),
+            mapping: IndexMap::new(),
+        }));
+    }
+    let property_name = value
+        .get("propertyName")
+        .and_then(|v| v.as_str())
+        .ok_or_else(invalid)?
+        .to_owned();
+    let mut mapping = IndexMap::new();
+    if let Some(values) = value.get("mapping") {
+        let values = values.as_mapping().ok_or_else(invalid)?;
+        for (k, v) in values.iter() {
+            let (Some(k), Some(v)) = (k.as_str(), v.as_str()) else {
+                return Err(invalid());
+            };
+            mapping.insert(k.to_owned(), v.to_owned());
+        }
+    }
+    Ok(Some(Discriminator {
+        property_name,
+        mapping,
+    }))
+}
+
+/// Returns the properties of the objects joined by an expression.
+fn joined_properties<'e>(expr: &'e Expr) -> Vec<&'e Property> {
+    match expr {
+        Expr::Object(o) => o.props.iter().collect(),
+        Expr::VariadicOp(op) => joined_schema_properties(&op.op, &op.schemas),
+        Expr::Reference(_, v) => joined_properties(&v.0),
+        _ => Vec::new(),
+    }
+}
+
+fn joined_schema_properties<'e>(
+    op: &atom::VariadicOperator,
+    schemas: &'e [Schema],
+) -> Vec<&'e Property> {
+    if *op != atom::VariadicOperator::Join {
+        return Vec::new();
+    }
+    schemas
+        .iter()
+        .flat_map(|s| match &s.expr {
+            SchemaExpr::Object(o) => o.props.iter().collect(),
+            SchemaExpr::Op(inner) => joined_schema_properties(&inner.op, &inner.schemas),
+            _ => Vec::new(),
+        })
+        .collect()
+}
+
+/// Returns the kind of value described by a schema, if known.
+fn schema_kind(expr: &SchemaExpr) -> Option<&'static str> {
+    match expr {
+        SchemaExpr::Int(_) | SchemaExpr::Num(_) => Some("a number"),
+        SchemaExpr::Str(_) | SchemaExpr::Uri(_) | SchemaExpr::Rel(_) => Some("a string"),
+        SchemaExpr::Bool(_) => Some("a boolean"),
+        SchemaExpr::Object(_) => Some("an object"),
+        SchemaExpr::Array(_) => Some("an array"),
+        SchemaExpr::Op(_) | SchemaExpr::Ref(_) => None,
+    }
+}
+
 pub fn eval_unary_operation<'a>(
     ctx: &mut Context<'a>,
     operation: syn::UnaryOp<'a, Core>,
@@ -640,56 +2137,252 @@ pub fn eval_property<'a>(
     ann: AnnRef,
 ) -> Result<(Expr<'a>, AnnRef)> {
     let desc = ann.get_string("description");
+    let translations = ann.get_translations("description");
     let required = ann.get_bool("required").or_else(|| property.required());
 
+    // Styles other than those of OpenAPI were once ignored, so they are reported as warnings.
+    let style = ann
+        .get_string("style")
+        .and_then(|s| match s.parse::<ParamStyle>() {
+            Ok(style) => Some(style),
+            Err(err) => {
+                ctx.warn(err.at(property.node().span()));
+                None
+            }
+        });
+    let explode = ann.get_bool("explode");
+    let content_type = ann.get_string("contentType");
+    if let Some(media) = content_type.as_deref() {
+        for m in media.split(',') {
+            m.parse::<MediaType>()
+                .map_err(|err| err.at(property.node().span()))?;
+        }
+    }
+    let deprecated = ann.get_bool("deprecated");
+
     let name = property.name();
-    let schema = cast_schema(eval_any(ctx, property.rhs(), AnnRef::default())?);
+    let mut schema = cast_schema(eval_any(ctx, property.rhs(), AnnRef::default())?);
+    // The vendor extensions and custom keywords of a property go along with its schema.
+    schema.extensions.extend(ann.get_extensions());
+    schema.keywords.extend(ann.get_keywords());
+    if let Some(default) = ann.props.get("default") {
+        schema.default = Some(default.clone());
+    }
+    if let Some(constant) = ann.props.get("const") {
+        schema.constant = Some(constant.clone());
+    }
 
     let prop = Property {
         name,
         schema,
         desc,
+        translations,
         required,
+        style,
+        explode,
+        content_type,
+        deprecated,
     };
 
     let expr = Expr::Property(Box::new(prop));
     Ok((expr, ann))
 }
 
+/// Returns true if the text is made of the given number of ASCII digits.
+fn is_digits(text: &str, len: usize) -> bool {
+    text.len() == len && text.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// Returns true if the text is a full date, e.g. `2024-02-29`.
+fn is_date(text: &str) -> bool {
+    let mut parts = text.splitn(3, '-');
+    let (Some(y), Some(m), Some(d)) = (parts.next(), parts.next(), parts.next()) else {
+        return false;
+    };
+    is_digits(y, 4)
+        && is_digits(m, 2)
+        && is_digits(d, 2)
+        && (1..=12).contains(&m.parse::<u8>().unwrap_or(0))
+        && (1..=31).contains(&d.parse::<u8>().unwrap_or(0))
+}
+
+/// Returns true if the text is a time with a mandatory offset, e.g. `12:30:00.5Z`.
+fn is_time(text: &str) -> bool {
+    let (time, offset) = match text.find(['Z', 'z', '+', '-']) {
+        Some(i) => text.split_at(i),
+        None => return false,
+    };
+    let (time, fraction) = time.split_once('.').unwrap_or((time, "0"));
+    let hms = time.split(':').collect::<Vec<_>>();
+    let offset_ok = match offset.strip_prefix(['+', '-']) {
+        Some(o) => matches!(o.split_once(':'), Some((h, m)) if is_digits(h, 2) && is_digits(m, 2)),
+        None => offset.len() == 1,
+    };
+    matches!(hms[..], [h, m, s] if is_digits(h, 2) && is_digits(m, 2) && is_digits(s, 2))
+        && !fraction.is_empty()
+        && fraction.bytes().all(|b| b.is_ascii_digit())
+        && offset_ok
+}
+
+/// Returns whether the text conforms to the given string format, if the format is known.
+fn conforms_to_format(format: &str, text: &str) -> Option<bool> {
+    let ok = match format {
+        "uuid" => {
+            let groups = text.split('-').map(str::len).collect::<Vec<_>>();
+            groups == [8, 4, 4, 4, 12] && text.chars().all(|c| c == '-' || c.is_ascii_hexdigit())
+        }
+        "date" => is_date(text),
+        "date-time" => match text.split_once(['T', 't']) {
+            Some((date, time)) => is_date(date) && is_time(time),
+            None => false,
+        },
+        "slug" => {
+            !text.is_empty()
+                && text.split('-').all(|w| {
+                    !w.is_empty()
+                        && w.bytes()
+                            .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit())
+                })
+        }
+        _ => return None,
+    };
+    Some(ok)
+}
+
+/// Returns a numeric bound and whether it is exclusive.
+///
+/// The exclusive annotation either qualifies the bound as a boolean, as in OpenAPI 3.0,
+/// or is the bound itself, as in OpenAPI 3.1.
+fn eval_bound<T, F>(
+    ann: &Annotation,
+    bound: &str,
+    exclusive: &str,
+    get: F,
+    span: Option<Span>,
+) -> Result<(Option<T>, bool)>
+where
+    F: Fn(&Annotation, &str) -> Option<T>,
+{
+    let value = get(ann, bound);
+    let Some(flag) = ann.props.get(exclusive) else {
+        return Ok((value, false));
+    };
+    if let Some(flag) = flag.as_bool() {
+        if flag && value.is_none() {
+            let msg = format!("{exclusive} requires a {bound}");
+            return Err(Error::new(Kind::InvalidLiteral, msg).at(span));
+        }
+        return Ok((value, flag));
+    }
+    match get(ann, exclusive) {
+        Some(v) if value.is_none() => Ok((Some(v), true)),
+        Some(_) => {
+            let msg = format!("{exclusive} cannot be a number along with a {bound}");
+            Err(Error::new(Kind::InvalidLiteral, msg).at(span))
+        }
+        None => {
+            let msg = format!("{exclusive} must be a boolean or a number");
+            Err(Error::new(Kind::InvalidLiteral, msg).at(span))
+        }
+    }
+}
+
+/// Evaluates the members of an `enum` annotation, each of which must convert to the primitive type.
+fn eval_enum<T, F>(ann: &Annotation, ty: &str, convert: F, span: Option<Span>) -> Result<Vec<T>>
+where
+    F: Fn(&serde_yaml::Value) -> Option<T>,
+{
+    let Some(value) = ann.props.get("enum") else {
+        return Ok(Vec::new());
+    };
+    let Some(members) = value.as_sequence() else {
+        let msg = "enum must be a sequence";
+        return Err(Error::new(Kind::InvalidLiteral, msg).at(span));
+    };
+    members
+        .iter()
+        .enumerate()
+        .map(|(index, member)| {
+            convert(member).ok_or_else(|| {
+                let msg = format!(
+                    "enum member {index} is {} instead of {ty}",
+                    value_kind(member)
+                );
+                Error::new(Kind::InvalidLiteral, msg).at(span.clone())
+            })
+        })
+        .collect()
+}
+
 pub fn eval_primitive<'a>(
     _ctx: &mut Context<'a>,
     primitive: syn::Primitive<'a, Core>,
     ann: AnnRef,
 ) -> Result<(Expr<'a>, AnnRef)> {
     let expr = match primitive.kind() {
-        syn::PrimitiveKind::Bool => Expr::PrimBoolean(Box::new(PrimBoolean {})),
+        syn::PrimitiveKind::Bool => {
+            let span = primitive.node().span();
+            let p = PrimBoolean {
+                enumeration: eval_enum(&ann, "a boolean", serde_yaml::Value::as_bool, span)?,
+            };
+            Expr::PrimBoolean(Box::new(p))
+        }
         syn::PrimitiveKind::Int => {
+            let span = primitive.node().span();
+            let get = Annotation::get_int;
+            let (minimum, exclusive_minimum) =
+                eval_bound(&ann, "minimum", "exclusiveMinimum", get, span.clone())?;
+            let (maximum, exclusive_maximum) =
+                eval_bound(&ann, "maximum", "exclusiveMaximum", get, span.clone())?;
             let p = PrimInteger {
-                minimum: ann.get_int("minimum"),
-                maximum: ann.get_int("maximum"),
+                minimum,
+                maximum,
+                exclusive_minimum,
+                exclusive_maximum,
                 multiple_of: ann.get_int("multipleOf"),
                 example: ann.get_int("example"),
+                enumeration: eval_enum(&ann, "an integer", serde_yaml::Value::as_i64, span)?,
             };
             Expr::PrimInteger(Box::new(p))
         }
         syn::PrimitiveKind::Num => {
+            let span = primitive.node().span();
+            let get = Annotation::get_num;
+            let (minimum, exclusive_minimum) =
+                eval_bound(&ann, "minimum", "exclusiveMinimum", get, span.clone())?;
+            let (maximum, exclusive_maximum) =
+                eval_bound(&ann, "maximum", "exclusiveMaximum", get, span.clone())?;
             let p = PrimNumber {
-                minimum: ann.get_num("minimum"),
-                maximum: ann.get_num("maximum"),
+                minimum,
+                maximum,
+                exclusive_minimum,
+                exclusive_maximum,
                 multiple_of: ann.get_num("multipleOf"),
                 example: ann.get_num("example"),
+                enumeration: eval_enum(&ann, "a number", serde_yaml::Value::as_f64, span)?,
             };
             Expr::PrimNumber(Box::new(p))
         }
         syn::PrimitiveKind::Str => {
+            let span = primitive.node().span();
+            let as_string = |v: &serde_yaml::Value| v.as_str().map(ToOwned::to_owned);
             let p = PrimString {
                 pattern: ann.get_string("pattern"),
-                enumeration: ann.get_enum("enum").unwrap_or_default(),
+                enumeration: eval_enum(&ann, "a string", as_string, span.clone())?,
                 format: ann.get_string("format"),
                 example: ann.get_string("example"),
                 min_length: ann.get_size("minLength"),
                 max_length: ann.get_size("maxLength"),
             };
+            if let (Some(format), Some(example)) = (&p.format, &p.example) {
+                if conforms_to_format(format, example) == Some(false) {
+                    return Err(Error::new(
+                        Kind::InvalidLiteral,
+                        format!("example is not a valid {format}"),
+                    )
+                    .at(span));
+                }
+            }
             Expr::PrimString(Box::new(p))
         }
         syn::PrimitiveKind::Uri => {
@@ -710,7 +2403,12 @@ pub fn eval_array<'a>(
     ann: AnnRef,
 ) -> Result<(Expr<'a>, AnnRef)> {
     let schema = cast_schema(eval_any(ctx, array.inner(), AnnRef::default())?);
-    let array = Array { item: schema };
+    let array = Array {
+        item: schema,
+        min_items: ann.get_size("minItems"),
+        max_items: ann.get_size("maxItems"),
+        unique_items: ann.get_bool("uniqueItems"),
+    };
     let expr = Expr::Array(Box::new(array));
     Ok((expr, ann))
 }
@@ -726,20 +2424,32 @@ pub fn eval_application<'a>(
                 .arguments()
                 .map(|a| eval_terminal(ctx, a, AnnRef::default()))
                 .collect::<Result<Vec<_>>>()?;
-            internal.eval(args, ann)
+            // Internal definitions do not know where they are applied.
+            let value = internal.eval(args, ann).map_err(|err| match err.span() {
+                Some(_) => err,
+                None => err.at(app.node().span()),
+            })?;
+            ctx.charge(expr_nodes(&value.0), app.node().span())?;
+            Ok(value)
         }
         Lambda::External(decl) => {
             let mut scope = HashMap::new();
+            let mut origins = HashMap::new();
             for (binding, argument) in decl.bindings().zip(app.arguments()) {
+                let node = argument.node();
+                if let Some(span) = ctx.value_span(node).or(node.span()) {
+                    origins.insert(binding.ident(), span);
+                }
                 let value = eval_terminal(ctx, argument, AnnRef::default())?;
                 scope.insert(binding.ident(), value);
             }
 
-            let mut app_ann = compose_annotations(decl.annotations())?;
-            app_ann.extend(ann.as_ref().clone());
+            let mut app_ann = compose_cached_annotations(&mut ctx.annotations, decl.annotations())?;
+            get_stability(&app_ann, decl.annotations())?;
+            app_ann.extend_usage(ann.as_ref().clone());
             let app_ann = AnnRef::new(app_ann);
 
-            ctx.push_scope(scope);
+            ctx.push_scope(scope, origins);
             let (expr, next_ann) = eval_any(ctx, decl.rhs(), app_ann)?;
             ctx.pop_scope();
 
@@ -765,7 +2475,7 @@ pub fn eval_recursion<'a>(
     let mut scope = HashMap::new();
     let recursion = (Expr::Recursion(ident.clone()), AnnRef::default());
     scope.insert(rec.binding().ident(), recursion);
-    ctx.push_scope(scope);
+    ctx.push_scope(scope, Origins::new());
     let rhs = eval_any(ctx, rec.rhs(), ann)?;
     ctx.pop_scope();
     ctx.refs.insert(ident.clone(), Some(rhs.clone()));
@@ -773,11 +2483,46 @@ pub fn eval_recursion<'a>(
     Ok((expr, AnnRef::default()))
 }
 
+/// Counts an evaluation step, failing beyond the limit of steps.
+fn count_step(ctx: &mut Context, span: Option<Span>) -> Result<()> {
+    ctx.steps += 1;
+    match ctx.limits.max_steps {
+        Some(max) if ctx.steps > max => Err(Error::new(
+            Kind::ResourceExceeded,
+            format!("the evaluation takes more than the limit of {max} steps"),
+        )
+        .at(span)),
+        _ => Ok(()),
+    }
+}
+
 pub fn eval_any<'a>(
     ctx: &mut Context<'a>,
     node: NRef<'a>,
     ann: AnnRef,
 ) -> Result<(Expr<'a>, AnnRef)> {
+    if ctx.depth >= ctx.limits.max_depth.unwrap_or(MAX_EVAL_DEPTH) {
+        return Err(
+            Error::new(Kind::DepthExceeded, "maximum evaluation depth exceeded").at(node.span()),
+        );
+    }
+    ctx.steps += 1;
+    if let Some(max) = ctx.limits.max_steps {
+        if ctx.steps > max {
+            return Err(Error::new(
+                Kind::ResourceExceeded,
+                format!("the evaluation takes more than the limit of {max} steps"),
+            )
+            .at(node.span()));
+        }
+    }
+    ctx.depth += 1;
+    let result = eval_node(ctx, node, ann);
+    ctx.depth -= 1;
+    result
+}
+
+fn eval_node<'a>(ctx: &mut Context<'a>, node: NRef<'a>, ann: AnnRef) -> Result<(Expr<'a>, AnnRef)> {
     if let Some(program) = syn::Program::cast(node) {
         eval_program(ctx, program, ann)
     } else if let Some(relation) = syn::Relation::cast(node) {
@@ -821,12 +2566,80 @@ pub fn eval_any<'a>(
     }
 }
 
+/// The value of a declaration evaluated on its own.
+#[derive(Clone, Debug)]
+pub enum Inspection {
+    Schema(Schema),
+    Relation(Box<Relation>),
+    Transfer(Box<Transfer>),
+    Content(Content),
+    Property(Property),
+    /// A value without a representation in the specification, e.g. a function.
+    Other,
+}
+
+/// Evaluates the program along with a declaration of the main module, e.g. for interactive use.
+///
+/// The references reached from the declaration are included in the specification.
+/// Returns `None` for the declaration if the main module does not declare the identifier.
+pub fn eval_inspection(
+    mods: &ModuleSet,
+    ident: &atom::Ident,
+) -> Result<(Spec, Option<Inspection>)> {
+    let ctx = &mut Context::new(mods);
+    let (expr, _) = eval_any(ctx, mods.main().root(), AnnRef::default())?;
+    let Expr::Spec(mut spec) = expr else {
+        panic!("expected a specification")
+    };
+    let program = syn::Program::cast(mods.main().root()).expect("expected a program");
+    let Some(decl) = program.declarations().find(|d| d.ident() == *ident) else {
+        return Ok((*spec, None));
+    };
+
+    let mut value = eval_any(ctx, decl.node(), AnnRef::default())?;
+    // A reference is inspected for its value rather than as a pointer to itself.
+    if let (Expr::Reference(_, v), _) = value {
+        value = *v;
+    }
+    let inspection = match value.0 {
+        Expr::Relation(_) => Inspection::Relation(Box::new(cast_relation(value))),
+        Expr::Transfer(_) => Inspection::Transfer(Box::new(cast_transfer(value))),
+        Expr::Content(_) => Inspection::Content(cast_content(value)),
+        Expr::Property(_) => Inspection::Property(cast_property(value)),
+        ref e if e.is_schema_like() => Inspection::Schema(cast_schema(value)),
+        _ => Inspection::Other,
+    };
+
+    for (ident, value) in ctx.refs.iter() {
+        if let Some((expr, ann)) = value {
+            if !spec.refs.contains_key(ident) {
+                let schema = cast_schema((expr.clone(), ann.clone()));
+                spec.refs.insert(ident.clone(), Reference::Schema(schema));
+            }
+        }
+    }
+
+    Ok((*spec, Some(inspection)))
+}
+
 pub fn eval(mods: &ModuleSet) -> Result<Spec> {
+    eval_with_warnings(mods).map(|(spec, _)| spec)
+}
+
+/// Evaluates a program, returning the specification along with the warnings raised.
+pub fn eval_with_warnings(mods: &ModuleSet) -> Result<(Spec, Vec<Error>)> {
+    eval_with_limits(mods, &EvalLimits::default())
+}
+
+/// Evaluates a program within the given limits, returning the specification
+/// along with the warnings raised.
+pub fn eval_with_limits(mods: &ModuleSet, limits: &EvalLimits) -> Result<(Spec, Vec<Error>)> {
     let ctx = &mut Context::new(mods);
+    ctx.limits = *limits;
     let ann = AnnRef::default();
     let (expr, _) = eval_any(ctx, mods.main().root(), ann)?;
     let Expr::Spec(spec) = expr else {
         panic!("expected a specification")
     };
-    Ok(*spec)
+    Ok((*spec, std::mem::take(&mut ctx.warnings)))
 }
@@ -1,10 +1,10 @@
 use crate::errors;
 use crate::inference::{check_complete, constrain, substitute, tag};
 use crate::resolve::resolve;
-use crate::spec::{Object, Reference, SchemaExpr, Spec, UriSegment};
+use crate::spec::{Object, ParamStyle, Reference, Schema, SchemaExpr, Spec, Stability, UriSegment};
 use crate::tests::mods_from;
 use crate::typecheck::{cycles_check, type_check};
-use oal_syntax::atom::{HttpStatus, Method, VariadicOperator};
+use oal_syntax::atom::{HttpStatus, Ident, Method, VariadicOperator};
 
 fn eval(code: &str, check: bool) -> anyhow::Result<Spec> {
     let mods = mods_from(code)?;
@@ -114,6 +114,84 @@ fn eval_composed_annotation() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[test]
+fn eval_annotation_inheritance() -> anyhow::Result<()> {
+    let s = eval_check(
+        r#"
+        # description: "a record"
+        # title: "a record type"
+        let r = {};
+        res / on get -> {
+            'inherited r `description: "some record"`,
+            'overridden r `inherit: false, description: "other record"`
+        };
+    "#,
+    )?;
+
+    let p = s.rels.first().unwrap();
+    let x = p.xfers[Method::Get]
+        .as_ref()
+        .expect("expected transfer on HTTP GET");
+    let c = x.ranges.values().next().unwrap();
+    let SchemaExpr::Object(ref o) = c.schema.as_ref().unwrap().expr else {
+        panic!("expected an object")
+    };
+    let mut props = o.props.iter().map(|p| &p.schema);
+
+    let s = props.next().unwrap();
+    assert_eq!(s.desc.as_ref().unwrap(), "some record");
+    assert_eq!(s.title.as_ref().unwrap(), "a record type");
+
+    let s = props.next().unwrap();
+    assert_eq!(s.desc.as_ref().unwrap(), "other record");
+    assert!(s.title.is_none());
+
+    Ok(())
+}
+
+#[test]
+fn eval_translated_descriptions() -> anyhow::Result<()> {
+    let s = eval_check(
+        r#"
+        # description: "a record"
+        # description@fr: "un enregistrement"
+        let r = {};
+        res / on get -> {
+            'inherited r `description@es: "un registro"`,
+            'overridden r `inherit: false, description: "other record"`
+        };
+    "#,
+    )?;
+
+    let p = s.rels.first().unwrap();
+    let x = p.xfers[Method::Get]
+        .as_ref()
+        .expect("expected transfer on HTTP GET");
+    let c = x.ranges.values().next().unwrap();
+    let SchemaExpr::Object(ref o) = c.schema.as_ref().unwrap().expr else {
+        panic!("expected an object")
+    };
+    let mut props = o.props.iter().map(|p| &p.schema);
+
+    let s = props.next().unwrap();
+    assert_eq!(s.desc.as_deref(), Some("a record"));
+    let langs: Vec<_> = s.translations.iter().collect();
+    assert_eq!(
+        langs,
+        [
+            (&"fr".to_owned(), &"un enregistrement".to_owned()),
+            (&"es".to_owned(), &"un registro".to_owned())
+        ]
+    );
+
+    // Translations are discarded along with the description they translate.
+    let s = props.next().unwrap();
+    assert_eq!(s.desc.as_deref(), Some("other record"));
+    assert!(s.translations.is_empty());
+
+    Ok(())
+}
+
 #[test]
 fn eval_invalid_annotation() -> anyhow::Result<()> {
     let code = r#"
@@ -134,6 +212,162 @@ fn eval_invalid_annotation() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[test]
+fn eval_enum_annotation() -> anyhow::Result<()> {
+    let s = eval_check(
+        r#"
+        res / on get -> {
+            'i int `enum: [1, 2, 3]`,
+            'n num `enum: [0.5, 1]`,
+            'b bool `enum: [true]`
+        };
+    "#,
+    )?;
+
+    let p = s.rels.first().unwrap();
+    let x = p.xfers[Method::Get]
+        .as_ref()
+        .expect("expected transfer on HTTP GET");
+    let c = x.ranges.values().next().unwrap();
+    let SchemaExpr::Object(ref o) = c.schema.as_ref().unwrap().expr else {
+        panic!("expected an object")
+    };
+    let mut props = o.props.iter().map(|p| &p.schema.expr);
+
+    let Some(SchemaExpr::Int(i)) = props.next() else {
+        panic!("expected an integer")
+    };
+    assert_eq!(i.enumeration, vec![1, 2, 3]);
+
+    let Some(SchemaExpr::Num(n)) = props.next() else {
+        panic!("expected a number")
+    };
+    assert_eq!(n.enumeration, vec![0.5, 1.0]);
+
+    let Some(SchemaExpr::Bool(b)) = props.next() else {
+        panic!("expected a boolean")
+    };
+    assert_eq!(b.enumeration, vec![true]);
+
+    let cases = [
+        "int `enum: [1, two]`",
+        "num `enum: [0.5, true]`",
+        "bool `enum: [yes]`",
+        "str `enum: [a, 1]`",
+        "int `enum: 1`",
+    ];
+    for schema in cases {
+        let code = format!("res / on get -> {{ 'p {schema} }};");
+        let err = eval_check(&code).expect_err("expected an error");
+        let err = err
+            .downcast_ref::<errors::Error>()
+            .expect("expected compiler error");
+        assert!(matches!(err.kind, errors::Kind::InvalidLiteral), "{schema}");
+    }
+
+    Ok(())
+}
+
+#[test]
+fn eval_literal_values() -> anyhow::Result<()> {
+    let s = eval_check(
+        r#"
+        let state = "active" | "inactive" | "active";
+        res / on get -> {
+            'state state,
+            'code (1 | "other"),
+            'kind str `const: circle`
+        };
+    "#,
+    )?;
+
+    let p = s.rels.first().unwrap();
+    let x = p.xfers[Method::Get]
+        .as_ref()
+        .expect("expected transfer on HTTP GET");
+    let c = x.ranges.values().next().unwrap();
+    let SchemaExpr::Object(ref o) = c.schema.as_ref().unwrap().expr else {
+        panic!("expected an object")
+    };
+    let mut props = o.props.iter().map(|p| &p.schema);
+
+    let Some(SchemaExpr::Str(s)) = props.next().map(|s| &s.expr) else {
+        panic!("expected a string")
+    };
+    assert_eq!(s.enumeration, vec!["active", "inactive"]);
+
+    let Some(SchemaExpr::Op(op)) = props.next().map(|s| &s.expr) else {
+        panic!("expected an operation")
+    };
+    assert_eq!(op.op, VariadicOperator::Sum);
+    let [Schema {
+        expr: SchemaExpr::Str(s),
+        ..
+    }, Schema {
+        expr: SchemaExpr::Int(i),
+        ..
+    }] = op.schemas.as_slice()
+    else {
+        panic!("expected a string and an integer")
+    };
+    assert_eq!(s.enumeration, vec!["other"]);
+    assert_eq!(i.enumeration, vec![1]);
+
+    let kind = props.next().unwrap();
+    assert_eq!(kind.constant, Some(serde_yaml::Value::from("circle")));
+
+    Ok(())
+}
+
+#[test]
+fn eval_discriminator() -> anyhow::Result<()> {
+    let s = eval_check(
+        r#"
+        let @cat = { 'kind! str, 'lives int };
+        let @dog = { 'kind! str, 'bark bool };
+        # discriminator: { propertyName: kind, mapping: { cat: "@cat", dog: "@dog" } }
+        let @pet = @cat | @dog;
+        res / on get -> @pet;
+    "#,
+    )?;
+    let Some(Reference::Schema(pet)) = s.refs.get(&Ident::from("@pet")) else {
+        panic!("expected a reference")
+    };
+    let SchemaExpr::Op(ref op) = pet.expr else {
+        panic!("expected an operation")
+    };
+    let d = op.discriminator.as_ref().expect("expected a discriminator");
+    assert_eq!(d.property_name, "kind");
+    assert_eq!(d.mapping.get("cat").map(String::as_str), Some("@cat"));
+
+    let err = eval_check("let a = ({} | {}) `discriminator: [kind]`; res / on get -> a;")
+        .expect_err("expected an error");
+    let err = err
+        .downcast_ref::<errors::Error>()
+        .expect("expected compiler error");
+    assert!(matches!(err.kind, errors::Kind::InvalidLiteral));
+
+    let cases = [
+        "let a = ({ 'kind! str } & {}) `discriminator: kind`;",
+        r#"let @c = { 'kind! str }; let a = (@c | { 'kind! str }) `discriminator: { propertyName: kind, mapping: { x: "@b" } }`;"#,
+        "let a = ({ 'kind! str } | { 'kind str }) `discriminator: kind`;",
+        "let a = ({ 'kind! str } | { 'name str }) `discriminator: kind`;",
+    ];
+    for code in cases {
+        let code = format!("{code} res / on get -> a;");
+        let err = eval_check(&code).expect_err("expected an error");
+        let err = err
+            .downcast_ref::<errors::Error>()
+            .expect("expected compiler error");
+        assert!(
+            matches!(err.kind, errors::Kind::InvalidDiscriminator),
+            "{code}: {err}"
+        );
+    }
+
+    Ok(())
+}
+
 #[test]
 fn eval_content() -> anyhow::Result<()> {
     let s = eval_check(
@@ -190,12 +424,18 @@ fn eval_ranges() -> anyhow::Result<()> {
         *s.as_ref().expect("expected HTTP status"),
         HttpStatus::try_from(500).unwrap()
     );
-    assert_eq!(*m.as_ref().expect("expected media type"), "text/plain");
+    assert_eq!(
+        m.as_ref().expect("expected media type").to_string(),
+        "text/plain"
+    );
     assert_eq!(
         c.schema.as_ref().unwrap().expr,
         SchemaExpr::Object(Object::default())
     );
-    assert_eq!(c.media.as_ref().expect("expected media"), "text/plain");
+    assert_eq!(
+        c.media.as_ref().expect("expected media").to_string(),
+        "text/plain"
+    );
     assert_eq!(
         c.status.expect("expected status"),
         HttpStatus::try_from(500).unwrap()
@@ -244,6 +484,105 @@ fn eval_invalid_status() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[test]
+fn eval_invalid_status_variable() -> anyhow::Result<()> {
+    let code = r#"
+        let t = 999;
+        let s = (t);
+        let f x = <status=x, {}>;
+        res / on get -> f s;
+    "#;
+
+    let err = eval_check(code).expect_err("expected an error");
+    let err = err
+        .downcast_ref::<errors::Error>()
+        .expect("expected compiler error");
+    assert!(matches!(err.kind, errors::Kind::InvalidLiteral));
+    let [(span, _)] = err.labels() else {
+        panic!("expected a single label")
+    };
+    let start = code.find("999").unwrap();
+    assert_eq!(
+        span.range(),
+        start..start + 3,
+        "expected a label on the argument"
+    );
+
+    let code = r#"
+        let f x = <status=x, {}>;
+        let g y = f y;
+        res / on get -> g 600;
+    "#;
+    let err = eval_check(code).expect_err("expected an error");
+    let err = err
+        .downcast_ref::<errors::Error>()
+        .expect("expected compiler error");
+    let [(span, _)] = err.labels() else {
+        panic!("expected a single label")
+    };
+    let start = code.find("600").unwrap();
+    assert_eq!(
+        span.range(),
+        start..start + 3,
+        "expected a label on the argument"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn eval_ranges_media_type() -> anyhow::Result<()> {
+    let s = eval_check(
+        r#"
+        res / on get -> <status=200, media="application/vnd.acme+json; version=2; charset=UTF-8", {}>
+                     :: <status=200, media="Application/VND.Acme+JSON;charset=utf-8;Version=2", {}>;
+    "#,
+    )?;
+
+    let p = s.rels.first().unwrap();
+    let x = p.xfers[Method::Get]
+        .as_ref()
+        .expect("expected transfer on HTTP GET");
+
+    assert_eq!(
+        x.ranges.len(),
+        1,
+        "expected equal media types to be deduplicated"
+    );
+    let (_, m) = x.ranges.keys().next().unwrap();
+    assert_eq!(
+        m.as_ref().expect("expected media type").to_string(),
+        "application/vnd.acme+json; charset=utf-8; version=2"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn eval_invalid_media_type() -> anyhow::Result<()> {
+    let code = r#"
+        let m = "json";
+        res / on get -> <media=m, {}>;
+    "#;
+
+    let err = eval_check(code).expect_err("expected an error");
+    let err = err
+        .downcast_ref::<errors::Error>()
+        .expect("expected compiler error");
+    assert!(matches!(err.kind, errors::Kind::InvalidLiteral));
+    let [(span, _)] = err.labels() else {
+        panic!("expected a single label")
+    };
+    let start = code.find(r#""json""#).unwrap();
+    assert_eq!(
+        span.range(),
+        start..start + 6,
+        "expected a label on the value"
+    );
+
+    Ok(())
+}
+
 #[test]
 fn eval_content_schema() -> anyhow::Result<()> {
     let s = eval_check(
@@ -317,6 +656,148 @@ fn eval_operation_any() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[test]
+fn eval_exclusive_bounds() -> anyhow::Result<()> {
+    let code = r#"
+        res / on get -> {
+            'a int `minimum: 0, exclusiveMinimum: true`,
+            'b num `exclusiveMaximum: 1.5`,
+            'c int `minimum: 0, exclusiveMinimum: false`
+        };
+    "#;
+    let s = eval_check(code)?;
+    let x = s.rels[0].xfers[Method::Get].as_ref().unwrap();
+    let r = x.ranges.values().next().unwrap().schema.as_ref().unwrap();
+    let SchemaExpr::Object(o) = &r.expr else {
+        panic!("expected an object")
+    };
+    let SchemaExpr::Int(a) = &o.props[0].schema.expr else {
+        panic!("expected an integer")
+    };
+    assert_eq!((a.minimum, a.exclusive_minimum), (Some(0), true));
+    let SchemaExpr::Num(b) = &o.props[1].schema.expr else {
+        panic!("expected a number")
+    };
+    assert_eq!((b.maximum, b.exclusive_maximum), (Some(1.5), true));
+    let SchemaExpr::Int(c) = &o.props[2].schema.expr else {
+        panic!("expected an integer")
+    };
+    assert_eq!((c.minimum, c.exclusive_minimum), (Some(0), false));
+
+    for ann in [
+        "exclusiveMinimum: true",
+        "minimum: 0, exclusiveMinimum: 1",
+        "exclusiveMaximum: 0.5",
+        "exclusiveMaximum: high",
+    ] {
+        let code = format!("res / on get -> {{ 'a int `{ann}` }};");
+        let err = eval_check(&code).expect_err("expected an error");
+        let err = err
+            .downcast_ref::<errors::Error>()
+            .expect("expected compiler error");
+        assert!(matches!(err.kind, errors::Kind::InvalidLiteral), "{ann}");
+    }
+
+    Ok(())
+}
+
+#[test]
+fn eval_array_facets() -> anyhow::Result<()> {
+    let code = r#"
+        # minItems: 1, uniqueItems: true
+        let ids = [int];
+        res / on get -> { 'ids ids, 'tags [str] `maxItems: 10` };
+    "#;
+    let s = eval_check(code)?;
+    let x = s.rels[0].xfers[Method::Get].as_ref().unwrap();
+    let r = x.ranges.values().next().unwrap().schema.as_ref().unwrap();
+    let SchemaExpr::Object(o) = &r.expr else {
+        panic!("expected an object")
+    };
+
+    let SchemaExpr::Array(a) = &o.props[0].schema.expr else {
+        panic!("expected an array")
+    };
+    assert_eq!(a.min_items, Some(1));
+    assert_eq!(a.max_items, None);
+    assert_eq!(a.unique_items, Some(true));
+
+    let SchemaExpr::Array(a) = &o.props[1].schema.expr else {
+        panic!("expected an array")
+    };
+    assert_eq!(a.min_items, None);
+    assert_eq!(a.max_items, Some(10));
+    assert_eq!(a.unique_items, None);
+
+    Ok(())
+}
+
+#[test]
+fn eval_operation_normalized() -> anyhow::Result<()> {
+    let code = r#"
+        let ab = num | str;
+        # description: "annotated"
+        let cd = bool | int;
+        res / on get -> < (ab | str) | bool | cd | num >;
+    "#;
+    let s = eval_check(code)?;
+    let x = s.rels[0].xfers[Method::Get].as_ref().unwrap();
+    let r = x.ranges.values().next().unwrap().schema.as_ref().unwrap();
+    let SchemaExpr::Op(op) = &r.expr else {
+        panic!("expected an operation")
+    };
+    assert_eq!(op.op, VariadicOperator::Sum);
+    // Flattened and deduplicated, except for the annotated alternatives.
+    assert_eq!(op.schemas.len(), 4);
+    assert!(matches!(op.schemas[0].expr, SchemaExpr::Num(_)));
+    assert!(matches!(op.schemas[1].expr, SchemaExpr::Str(_)));
+    assert!(matches!(op.schemas[2].expr, SchemaExpr::Bool(_)));
+    let SchemaExpr::Op(inner) = &op.schemas[3].expr else {
+        panic!("expected an annotated operation")
+    };
+    assert_eq!(inner.schemas.len(), 2);
+    assert_eq!(op.schemas[3].desc.as_deref(), Some("annotated"));
+
+    // Operations of a different kind are not flattened.
+    let s = eval_check("res / on get -> < ({ 'a num } & { 'b int }) | { 'c str } >;")?;
+    let x = s.rels[0].xfers[Method::Get].as_ref().unwrap();
+    let r = x.ranges.values().next().unwrap().schema.as_ref().unwrap();
+    let SchemaExpr::Op(op) = &r.expr else {
+        panic!("expected an operation")
+    };
+    assert_eq!(op.schemas.len(), 2);
+    assert!(matches!(op.schemas[0].expr, SchemaExpr::Op(_)));
+
+    Ok(())
+}
+
+#[test]
+fn eval_operation_unsatisfiable() -> anyhow::Result<()> {
+    let code = r#"
+        let @a = { 'a str, 'b bool };
+        let c = { 'c [str] };
+        res /a on get -> <{ 'a int } & @a>;
+        res /b on get -> <c & { 'b num } & { 'c { 'd num } }>;
+        res /c on get -> <{ 'a num } & { 'a int, 'b uri } & { 'b str }>;
+    "#;
+    let mods = mods_from(code)?;
+    crate::compile::compile(&mods, mods.base())?;
+    let (_, warnings) = crate::eval::eval_with_warnings(&mods)?;
+    assert_eq!(warnings.len(), 2);
+    for w in warnings.iter() {
+        assert!(matches!(w.kind, errors::Kind::Unsatisfiable));
+        assert_eq!(w.labels().len(), 2);
+    }
+    assert!(warnings[0]
+        .to_string()
+        .contains("property a cannot be both a number and a string"));
+    assert!(warnings[1]
+        .to_string()
+        .contains("property c cannot be both an array and an object"));
+
+    Ok(())
+}
+
 #[test]
 fn eval_operation_sum() -> anyhow::Result<()> {
     let s = eval_check(r#"res / on get -> < num | str >;"#)?;
@@ -391,33 +872,318 @@ fn eval_uri() -> anyhow::Result<()> {
 }
 
 #[test]
-fn eval_uri_params() -> anyhow::Result<()> {
-    let s = eval_check(r#"res / on patch, put { 'n num } : {} -> <>;"#)?;
-
-    assert_eq!(s.rels.len(), 1);
+fn eval_merged_relations() -> anyhow::Result<()> {
+    let s = eval_check(
+        r#"
+        res /a on get -> {};
+        res /b on get -> {};
+        res /a on put : {} -> {};
+    "#,
+    )?;
 
+    assert_eq!(s.rels.len(), 2);
     let r = s.rels.first().unwrap();
-
+    assert_eq!(r.uri.pattern(), "/a");
+    assert!(r.xfers[Method::Get].is_some());
     assert!(r.xfers[Method::Put].is_some());
-    let x = r.xfers[Method::Patch]
-        .as_ref()
-        .expect("expected transfer on HTTP PATCH");
 
-    let o = x.params.as_ref().expect("expected transfer params");
-    assert_eq!(o.props.len(), 1);
-    let p = &o.props[0];
-    assert_eq!(p.name, "n");
-    assert!(matches!(p.schema.expr, SchemaExpr::Num(_)));
+    let code = r#"
+        res /a on get -> {};
+        res /a on get -> str;
+    "#;
+    let err = eval_check(code).expect_err("expected an error");
+    let err = err
+        .downcast_ref::<errors::Error>()
+        .expect("expected compiler error");
+    assert!(matches!(err.kind, errors::Kind::Conflict));
+    assert_eq!(err.labels().len(), 1, "expected the first definition");
 
     Ok(())
 }
 
 #[test]
-fn eval_reference() -> anyhow::Result<()> {
+fn eval_webhook_relations() -> anyhow::Result<()> {
     let s = eval_check(
         r#"
-        let @a = {};
-        res /one on get -> @a;
+        # webhook: added
+        let hook = /a on post : {} -> <status=204>;
+        res /a on post : {} -> {};
+        res hook;
+    "#,
+    )?;
+
+    assert_eq!(s.rels.len(), 2, "expected webhooks not to merge with paths");
+    assert!(s.rels[0].webhook.is_none());
+    assert_eq!(s.rels[1].webhook.as_deref(), Some("added"));
+    assert!(s.rels[1].xfers[Method::Post].is_some());
+
+    let code = r#"
+        # webhook: added
+        let a = /a on post : {} -> <status=204>;
+        # webhook: added
+        let b = /b on put : {} -> <status=204>;
+        res a;
+        res b;
+    "#;
+    let err = eval_check(code).expect_err("expected an error");
+    let err = err
+        .downcast_ref::<errors::Error>()
+        .expect("expected compiler error");
+    assert!(matches!(err.kind, errors::Kind::Conflict));
+    assert_eq!(err.labels().len(), 1, "expected the first definition");
+
+    Ok(())
+}
+
+#[test]
+fn eval_callback_relations() -> anyhow::Result<()> {
+    let s = eval_check(
+        r#"
+        # callback: paid
+        let paid = /a on post : {} -> <status=204>;
+        res /a on (post : {} -> {}) `callbacks: { paid: "{$request.body#/callbackUrl}" }`;
+        res paid;
+    "#,
+    )?;
+
+    assert_eq!(
+        s.rels.len(),
+        2,
+        "expected callbacks not to merge with paths"
+    );
+    assert!(s.rels[0].callback.is_none());
+    let x = s.rels[0].xfers[Method::Post].as_ref().unwrap();
+    assert_eq!(x.callbacks["paid"], "{$request.body#/callbackUrl}");
+    assert_eq!(s.rels[1].callback.as_deref(), Some("paid"));
+
+    let code = r#"res /a on (post : {} -> {}) `callbacks: { paid: "{$request.body#/url}" }`;"#;
+    let err = eval_check(code).expect_err("expected an undeclared callback");
+    let err = err
+        .downcast_ref::<errors::Error>()
+        .expect("expected compiler error");
+    assert!(matches!(err.kind, errors::Kind::NotInScope));
+    assert!(err.span().is_some());
+
+    let code = r#"
+        # callback: paid
+        let paid = /a on (post : {} -> <status=204>) `callbacks: { paid: "{$request.body#/url}" }`;
+        res paid;
+    "#;
+    let err = eval_check(code).expect_err("expected a nested callback");
+    let err = err
+        .downcast_ref::<errors::Error>()
+        .expect("expected compiler error");
+    assert!(matches!(err.kind, errors::Kind::InvalidLiteral));
+
+    Ok(())
+}
+
+#[test]
+fn eval_xfer_annotations() -> anyhow::Result<()> {
+    let s = eval_check(
+        r#"
+        let x = get -> {};
+        res /a on
+            # tags: [admin]
+            # summary: "update"
+            put : {} -> {},
+            x `operationId: read`,
+            (delete -> {}) `summary: "remove"`,
+            patch -> <{}> `tags: [admin], description: "patched"`;
+    "#,
+    )?;
+
+    let r = s.rels.first().unwrap();
+
+    let x = r.xfers[Method::Put].as_ref().unwrap();
+    assert_eq!(x.tags, vec!["admin".to_owned()]);
+    assert_eq!(x.summary, Some("update".to_owned()));
+
+    let x = r.xfers[Method::Get].as_ref().unwrap();
+    assert_eq!(x.id, Some("read".to_owned()));
+    assert!(x.tags.is_empty());
+
+    let x = r.xfers[Method::Delete].as_ref().unwrap();
+    assert_eq!(x.summary, Some("remove".to_owned()));
+
+    let x = r.xfers[Method::Patch].as_ref().unwrap();
+    assert_eq!(x.tags, vec!["admin".to_owned()]);
+    assert!(x.desc.is_none());
+    let c = x.ranges.values().next().unwrap();
+    assert_eq!(c.desc, Some("patched".to_owned()));
+
+    Ok(())
+}
+
+#[test]
+fn eval_custom_methods() -> anyhow::Result<()> {
+    let s = eval_check(
+        r#"
+        res /a on
+            trace -> <>,
+            # x-http-method: PURGE
+            delete -> <>;
+        res /a on (post -> <>) `x-http-method: link`;
+    "#,
+    )?;
+
+    let r = s.rels.first().unwrap();
+    assert!(r.xfers[Method::Trace].is_some());
+    assert!(r.xfers[Method::Delete].is_none());
+    let verbs: Vec<_> = r.custom_xfers.keys().collect();
+    assert_eq!(verbs, vec!["purge", "link"]);
+
+    // The method name is only a keyword in method position.
+    let s = eval_check(
+        r#"
+        let trace = { 'id str };
+        let log trace = trace;
+        res /traces on get, trace -> log trace;
+    "#,
+    )?;
+    let r = s.rels.first().unwrap();
+    assert!(r.xfers[Method::Get].is_some());
+    assert!(r.xfers[Method::Trace].is_some());
+
+    let code = r#"res /a on (get -> <>) `x-http-method: put`;"#;
+    let err = eval_check(code).expect_err("expected an error");
+    let err = err
+        .downcast_ref::<errors::Error>()
+        .expect("expected compiler error");
+    assert!(matches!(err.kind, errors::Kind::InvalidLiteral));
+
+    // Methods are defined once per URI, across resources and within a resource.
+    for code in [
+        r#"
+        res /a on (get -> <>) `x-http-method: purge`;
+        res /a on (get -> <>) `x-http-method: purge`;
+    "#,
+        r#"res /a on (get -> <>) `x-http-method: purge`, (put -> <>) `x-http-method: purge`;"#,
+        r#"res /a on get -> <>, get -> <status=201>;"#,
+    ] {
+        let err = eval_check(code).expect_err("expected an error");
+        let err = err
+            .downcast_ref::<errors::Error>()
+            .expect("expected compiler error");
+        assert!(matches!(err.kind, errors::Kind::Conflict));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn eval_merged_tag_groups() -> anyhow::Result<()> {
+    let s = eval_check(
+        r#"
+        # tagGroup: admin
+        let a1 = /a on put -> <>;
+        # tagGroup: admin
+        let a2 = /a on delete -> <>;
+        res /a on get -> <>;
+        res a1;
+        res a2;
+    "#,
+    )?;
+    assert_eq!(s.rels.len(), 1);
+    assert_eq!(s.rels[0].tag_group.as_deref(), Some("admin"));
+
+    let code = r#"
+        # tagGroup: admin
+        let a1 = /a on get -> <>;
+        # tagGroup: users
+        let a2 = /a on put -> <>;
+        res a1;
+        res a2;
+    "#;
+    let err = eval_check(code).expect_err("expected an error");
+    let err = err
+        .downcast_ref::<errors::Error>()
+        .expect("expected compiler error");
+    assert!(matches!(err.kind, errors::Kind::Conflict));
+
+    Ok(())
+}
+
+#[test]
+fn eval_servers_security_scoping() -> anyhow::Result<()> {
+    let s = eval_check(
+        r#"
+        # servers: [ "https://a.com", { url: "https://b.com", description: b } ]
+        # security: [ key ]
+        let r = /a on
+            get -> <>,
+            # servers: [ "https://c.com" ]
+            # security: [ { oauth: [read, write] } ]
+            put -> <>,
+            # servers: []
+            # security: []
+            delete -> <>;
+        res r;
+        res /b on get -> <>;
+    "#,
+    )?;
+
+    let r = s.rels.first().unwrap();
+
+    // Inherited from the relation.
+    let x = r.xfers[Method::Get].as_ref().unwrap();
+    let servers = x.servers.as_ref().expect("expected servers");
+    assert_eq!(servers.len(), 2);
+    assert_eq!(servers[1].url, "https://b.com");
+    assert_eq!(servers[1].desc.as_deref(), Some("b"));
+    let security = x.security.as_ref().expect("expected security");
+    assert_eq!(security[0]["key"], Vec::<String>::new());
+
+    // Overridden by the transfer.
+    let x = r.xfers[Method::Put].as_ref().unwrap();
+    assert_eq!(x.servers.as_ref().unwrap()[0].url, "https://c.com");
+    assert_eq!(
+        x.security.as_ref().unwrap()[0]["oauth"],
+        vec!["read", "write"]
+    );
+
+    // Cleared by the transfer.
+    let x = r.xfers[Method::Delete].as_ref().unwrap();
+    assert_eq!(x.servers, Some(vec![]));
+    assert_eq!(x.security, Some(vec![]));
+
+    // Left to the document.
+    let r = s.rels.get(1).unwrap();
+    let x = r.xfers[Method::Get].as_ref().unwrap();
+    assert!(x.servers.is_none());
+    assert!(x.security.is_none());
+
+    Ok(())
+}
+
+#[test]
+fn eval_uri_params() -> anyhow::Result<()> {
+    let s = eval_check(r#"res / on patch, put { 'n num } : {} -> <>;"#)?;
+
+    assert_eq!(s.rels.len(), 1);
+
+    let r = s.rels.first().unwrap();
+
+    assert!(r.xfers[Method::Put].is_some());
+    let x = r.xfers[Method::Patch]
+        .as_ref()
+        .expect("expected transfer on HTTP PATCH");
+
+    let o = x.params.as_ref().expect("expected transfer params");
+    assert_eq!(o.props.len(), 1);
+    let p = &o.props[0];
+    assert_eq!(p.name, "n");
+    assert!(matches!(p.schema.expr, SchemaExpr::Num(_)));
+
+    Ok(())
+}
+
+#[test]
+fn eval_reference() -> anyhow::Result<()> {
+    let s = eval_check(
+        r#"
+        let @a = {};
+        res /one on get -> @a;
         res /two on get -> @a;
     "#,
     )?;
@@ -769,23 +1535,26 @@ fn eval_unique_recursive_identifiers() -> anyhow::Result<()> {
     Ok(())
 }
 
-// TODO: uri with recursive schemas in query string
 #[test]
-#[ignore = "not implemented"]
 fn eval_recursive_uri() -> anyhow::Result<()> {
-    let code = r#"
+    let cases = [
+        r#"
         let a = concat /a a;
         res a;
-    "#;
-
-    assert!(matches!(
-        eval_check(code)
-            .expect_err(format!("expected error evaluating: {}", code).as_str())
-            .downcast_ref::<errors::Error>()
-            .expect("expected compiler error")
-            .kind,
-        errors::Kind::InvalidType
-    ));
+    "#,
+        "res rec x (concat /a x);",
+    ];
+
+    for code in cases {
+        assert!(matches!(
+            eval_check(code)
+                .expect_err(format!("expected error evaluating: {}", code).as_str())
+                .downcast_ref::<errors::Error>()
+                .expect("expected compiler error")
+                .kind,
+            errors::Kind::InvalidType
+        ));
+    }
 
     let s = eval_check(
         r#"
@@ -869,3 +1638,907 @@ fn eval_internal() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn eval_depth_exceeded() -> anyhow::Result<()> {
+    let mut code = String::from("let a0 = {};\n");
+    for i in 1..100 {
+        code.push_str(&format!("let a{i} = a{};\n", i - 1));
+    }
+    code.push_str("res / on get -> a99;\n");
+
+    let mods = mods_from(&code)?;
+    crate::compile::compile(&mods, mods.base())?;
+
+    // A long chain of declarations is within the default limit.
+    crate::eval::eval(&mods)?;
+
+    let limits = crate::eval::EvalLimits {
+        max_depth: Some(64),
+        ..Default::default()
+    };
+    let err = crate::eval::eval_with_limits(&mods, &limits).expect_err("expected an error");
+    assert!(matches!(err.kind, errors::Kind::DepthExceeded));
+
+    Ok(())
+}
+
+#[test]
+fn eval_steps_exceeded() -> anyhow::Result<()> {
+    let code = r#"
+        let f x = { 'a x, 'b x };
+        res / on get -> f (f (f {}));
+    "#;
+    let mods = mods_from(code)?;
+    crate::compile::compile(&mods, mods.base())?;
+
+    let limits = crate::eval::EvalLimits::default();
+    crate::eval::eval_with_limits(&mods, &limits)?;
+
+    let limits = crate::eval::EvalLimits {
+        max_steps: Some(10),
+        ..Default::default()
+    };
+    let err = crate::eval::eval_with_limits(&mods, &limits).expect_err("expected an error");
+    assert!(matches!(err.kind, errors::Kind::ResourceExceeded));
+    assert!(err.span().is_some());
+
+    Ok(())
+}
+
+#[test]
+fn eval_nodes_exceeded() -> anyhow::Result<()> {
+    // Values double in size on each application, in a few steps only.
+    let mut arg = "{}".to_owned();
+    for _ in 0..64 {
+        arg = format!("(f {arg})");
+    }
+    let code = format!("let f x = {{ 'a x, 'b x }};\nres / on get -> {arg};");
+    let mods = mods_from(&code)?;
+    crate::compile::compile(&mods, mods.base())?;
+
+    let limits = crate::eval::EvalLimits {
+        max_nodes: Some(1 << 12),
+        ..Default::default()
+    };
+    let err = crate::eval::eval_with_limits(&mods, &limits).expect_err("expected an error");
+    assert!(matches!(err.kind, errors::Kind::ResourceExceeded));
+    assert!(err.to_string().contains("nodes"), "{err}");
+    assert!(err.span().is_some());
+
+    Ok(())
+}
+
+#[test]
+fn eval_empty_status() -> anyhow::Result<()> {
+    let status = |s: &Spec| {
+        let x = s.rels[0].xfers[Method::Get].as_ref().unwrap();
+        x.ranges.first().unwrap().1.status
+    };
+
+    let s = eval_check("res /a on get -> <>;")?;
+    assert_eq!(status(&s), Some(HttpStatus::try_from(204)?));
+
+    let code = r#"
+        #! empty-status: 200
+        res /a on get -> <>;
+    "#;
+    let s = eval_check(code)?;
+    assert_eq!(status(&s), Some(HttpStatus::try_from(200)?));
+
+    let code = r#"
+        #! empty-status: 999
+        res /a on get -> <>;
+    "#;
+    let err = eval_check(code).expect_err("expected an invalid status");
+    let err = err
+        .downcast_ref::<errors::Error>()
+        .expect("expected compiler error");
+    assert!(matches!(err.kind, errors::Kind::InvalidLiteral));
+    assert!(err.span().is_some());
+
+    Ok(())
+}
+
+#[test]
+fn eval_security_schemes() -> anyhow::Result<()> {
+    let code = r#"
+        #! securitySchemes: { key: { type: apiKey, in: header, name: X-API-Key } }
+        #! securitySchemes: { bearer: { type: http, scheme: bearer, bearerFormat: JWT } }
+        #! security: [ key ]
+        res /a on get -> <>;
+    "#;
+    let s = eval_check(code)?;
+    assert_eq!(
+        s.security_schemes.keys().collect::<Vec<_>>(),
+        ["key", "bearer"]
+    );
+    let security = s.security.expect("expected document security");
+    assert_eq!(security[0]["key"], Vec::<String>::new());
+
+    let code = r#"
+        #! securitySchemes: { key: { type: apiKey, in: body, name: key } }
+        res /a on get -> <>;
+    "#;
+    let err = eval_check(code).expect_err("expected an invalid scheme");
+    let err = err
+        .downcast_ref::<errors::Error>()
+        .expect("expected compiler error");
+    assert!(matches!(err.kind, errors::Kind::InvalidLiteral));
+    assert!(err.to_string().contains("security scheme key"));
+    assert!(err.span().is_some());
+
+    Ok(())
+}
+
+#[test]
+fn eval_document_metadata() -> anyhow::Result<()> {
+    let code = r#"
+        #! info: { title: Pet store, version: 1.2 }
+        #! info: { description: The pet store API }
+        #! servers: [ https://api.example.com, { url: /, description: Local } ]
+        #! tags: [ pets, { name: users, description: User accounts } ]
+        res /a on get -> <>;
+    "#;
+    let s = eval_check(code)?;
+    assert_eq!(s.info.title.as_deref(), Some("Pet store"));
+    assert_eq!(s.info.version.as_deref(), Some("1.2"));
+    assert_eq!(s.info.desc.as_deref(), Some("The pet store API"));
+    let servers = s.servers.expect("expected document servers");
+    assert_eq!(servers[0].url, "https://api.example.com");
+    assert_eq!(servers[1].desc.as_deref(), Some("Local"));
+    assert_eq!(s.tags[0].name, "pets");
+    assert_eq!(s.tags[1].desc.as_deref(), Some("User accounts"));
+
+    let code = r#"
+        #! info: { title: Pet store, contact: me }
+        res /a on get -> <>;
+    "#;
+    let err = eval_check(code).expect_err("expected an invalid information field");
+    let err = err
+        .downcast_ref::<errors::Error>()
+        .expect("expected compiler error");
+    assert!(matches!(err.kind, errors::Kind::InvalidLiteral));
+    assert!(err.to_string().contains("contact"));
+    assert!(err.span().is_some());
+
+    Ok(())
+}
+
+#[test]
+fn eval_no_content_with_schema() -> anyhow::Result<()> {
+    let code = r#"
+        let r = <status=204, {}>;
+        res /a on get -> r;
+        res /b on get -> r, put -> <status=204>;
+    "#;
+    let mods = mods_from(code)?;
+    crate::compile::compile(&mods, mods.base())?;
+    let (_, warnings) = crate::eval::eval_with_warnings(&mods)?;
+    assert_eq!(warnings.len(), 1, "expected a single warning for r");
+    assert!(matches!(warnings[0].kind, errors::Kind::UnexpectedContent));
+    assert_eq!(warnings[0].kind.lint(), Some("content"));
+    assert!(warnings[0].span().is_some());
+
+    Ok(())
+}
+
+#[test]
+fn eval_duplicate_headers() -> anyhow::Result<()> {
+    let code = r#"
+        let h = { 'ETag str, 'X-Rate int, 'etag int };
+        res /a on get -> <headers=h, {}>;
+    "#;
+    let mods = mods_from(code)?;
+    crate::compile::compile(&mods, mods.base())?;
+    let (spec, warnings) = crate::eval::eval_with_warnings(&mods)?;
+    assert_eq!(warnings.len(), 1, "expected a single warning for etag");
+    assert!(matches!(warnings[0].kind, errors::Kind::DuplicateHeader));
+    assert_eq!(warnings[0].kind.lint(), Some("headers"));
+    assert_eq!(
+        warnings[0].labels().len(),
+        1,
+        "expected a label on the binding"
+    );
+
+    let x = spec.rels[0].xfers[Method::Get]
+        .as_ref()
+        .expect("expected transfer on HTTP GET");
+    let c = x.ranges.values().next().expect("expected a range");
+    let names: Vec<_> = c
+        .headers
+        .as_ref()
+        .unwrap()
+        .props
+        .iter()
+        .map(|p| p.name.as_ref())
+        .collect();
+    assert_eq!(names, ["ETag", "X-Rate"]);
+    assert_eq!(
+        c.headers.as_ref().unwrap().props[0].schema.expr,
+        SchemaExpr::Str(Default::default())
+    );
+
+    Ok(())
+}
+
+#[test]
+fn eval_duplicate_headers_same_span() -> anyhow::Result<()> {
+    let code = r#"
+        res /a on get -> <headers={ 'ETag str, 'etag int, 'X-Rate int, 'x-rate str }, {}>;
+    "#;
+    let mods = mods_from(code)?;
+    crate::compile::compile(&mods, mods.base())?;
+    let (_, warnings) = crate::eval::eval_with_warnings(&mods)?;
+    assert_eq!(warnings.len(), 2, "expected a warning for each header");
+    assert!(warnings
+        .iter()
+        .all(|w| matches!(w.kind, errors::Kind::DuplicateHeader)));
+    assert_eq!(warnings[0].span(), warnings[1].span());
+    assert!(warnings[0].to_string().contains("etag"));
+    assert!(warnings[1].to_string().contains("x-rate"));
+
+    Ok(())
+}
+
+#[test]
+fn eval_reserved_headers() -> anyhow::Result<()> {
+    let code = r#"res /a on put : <headers={ 'Authorization str }, {}> -> <status=204>;"#;
+    eval_check(code)?;
+
+    for header in ["Content-Type", "authorization"] {
+        let code = format!("res /a on get -> <headers={{ '{header} str }}, {{}}>;");
+        let err = eval_check(&code).expect_err("expected an error");
+        let err = err
+            .downcast_ref::<errors::Error>()
+            .expect("expected compiler error");
+        assert!(matches!(err.kind, errors::Kind::ReservedHeader));
+        assert!(err.span().is_some());
+    }
+
+    Ok(())
+}
+
+#[test]
+fn eval_patch_of() -> anyhow::Result<()> {
+    let code = r#"
+        let @address = { 'city! str };
+        let @user = { 'id! int, 'name str `required: true`, 'address @address, 'meta { 'tag! str } };
+        res /a on patch : (patch_of @user) -> @user;
+    "#;
+    let s = eval_check(code)?;
+    let x = s.rels[0].xfers[Method::Patch].as_ref().unwrap();
+    let media = x.domain.media.as_ref().expect("expected a media type");
+    assert_eq!(media.to_string(), "application/merge-patch+json");
+    let schema = x.domain.schema.as_ref().expect("expected a schema");
+    let SchemaExpr::Object(o) = &schema.expr else {
+        panic!("expected an object")
+    };
+    assert_eq!(o.props.len(), 4);
+    for p in o.props.iter() {
+        assert_eq!(p.required, Some(false));
+        assert_eq!(p.schema.required, None);
+        assert_eq!(p.schema.nullable, Some(true));
+    }
+    assert!(matches!(o.props[2].schema.expr, SchemaExpr::Ref(_)));
+    let SchemaExpr::Object(meta) = &o.props[3].schema.expr else {
+        panic!("expected a nested object")
+    };
+    assert_eq!(meta.props[0].required, Some(false));
+
+    // The original schema is left untouched.
+    let Reference::Schema(user) = s.refs.get(&Ident::from("@user")).unwrap();
+    let SchemaExpr::Object(o) = &user.expr else {
+        panic!("expected an object")
+    };
+    assert_eq!(o.props[0].required, Some(true));
+    assert_eq!(o.props[0].schema.nullable, None);
+
+    Ok(())
+}
+
+#[test]
+fn eval_default_headers() -> anyhow::Result<()> {
+    let code = r#"
+        let h = { 'X-Request-Id str, 'ETag str };
+        let r = /a on get -> <status=200, headers={ 'etag int }, {}> :: <{}>, put -> <status=204>;
+        res with_headers h r;
+    "#;
+    let mods = mods_from(code)?;
+    crate::compile::compile(&mods, mods.base())?;
+    let (spec, warnings) = crate::eval::eval_with_warnings(&mods)?;
+    assert!(warnings.is_empty());
+
+    let names = |x: &crate::spec::Transfer| -> Vec<Vec<String>> {
+        x.ranges
+            .values()
+            .map(|c| {
+                let props = c.headers.as_ref().map_or(&[][..], |h| &h.props[..]);
+                props.iter().map(|p| p.name.as_ref().to_owned()).collect()
+            })
+            .collect()
+    };
+    let rel = &spec.rels[0];
+    let get = rel.xfers[Method::Get].as_ref().unwrap();
+    assert_eq!(
+        names(get),
+        [vec!["etag", "X-Request-Id"], vec!["X-Request-Id", "ETag"]]
+    );
+    let put = rel.xfers[Method::Put].as_ref().unwrap();
+    assert_eq!(names(put), [vec!["X-Request-Id", "ETag"]]);
+
+    let code = r#"res with_headers { 'Content-Type str } (/a on get -> {});"#;
+    let err = eval_check(code).expect_err("expected an error");
+    let err = err
+        .downcast_ref::<errors::Error>()
+        .expect("expected compiler error");
+    assert!(matches!(err.kind, errors::Kind::ReservedHeader));
+    assert!(err.span().is_some());
+
+    let code = r#"
+        res /a on get -> <status=200, {}> :: <headers={ 'X-Request-Id str }, {}>;
+    "#;
+    let mods = mods_from(code)?;
+    crate::compile::compile(&mods, mods.base())?;
+    let (_, warnings) = crate::eval::eval_with_warnings(&mods)?;
+    assert_eq!(
+        warnings.len(),
+        1,
+        "expected a warning for the default response"
+    );
+    assert!(matches!(warnings[0].kind, errors::Kind::DefaultHeaders));
+    assert_eq!(warnings[0].kind.lint(), Some("headers"));
+
+    Ok(())
+}
+
+#[test]
+fn eval_conditional() -> anyhow::Result<()> {
+    let code = r#"
+        let r = /a on get -> <status=200, {}> :: <status=404>, put : {} -> <status=204>;
+        res conditional r;
+        res conditional (/b on get : <headers={ 'if-none-match str }> -> <status=304, headers={ 'X-Cache str }>);
+    "#;
+    let s = eval_check(code)?;
+    let headers = |c: &crate::spec::Content| -> Vec<String> {
+        let props = c.headers.as_ref().map_or(&[][..], |h| &h.props[..]);
+        props.iter().map(|p| p.name.as_ref().to_owned()).collect()
+    };
+
+    let get = s.rels[0].xfers[Method::Get].as_ref().unwrap();
+    assert_eq!(headers(&get.domain), ["If-None-Match"]);
+    let ranges = get.ranges.values().map(headers).collect::<Vec<_>>();
+    assert_eq!(ranges, [vec!["ETag"], vec![], vec!["ETag"]]);
+    let not_modified = get.ranges.values().last().unwrap();
+    assert!(matches!(not_modified.status, Some(HttpStatus::Code(c)) if c.get() == 304));
+    assert!(not_modified.schema.is_none());
+    let put = s.rels[0].xfers[Method::Put].as_ref().unwrap();
+    assert!(put.domain.headers.is_none());
+    assert_eq!(put.ranges.len(), 1);
+
+    let get = s.rels[1].xfers[Method::Get].as_ref().unwrap();
+    assert_eq!(headers(&get.domain), ["if-none-match"]);
+    assert_eq!(get.ranges.len(), 1);
+    let ranges = get.ranges.values().map(headers).collect::<Vec<_>>();
+    assert_eq!(ranges, [vec!["X-Cache", "ETag"]]);
+
+    let s = eval_check("res conditional (/c on get -> {});")?;
+    let get = s.rels[0].xfers[Method::Get].as_ref().unwrap();
+    let ranges = get.ranges.values().map(headers).collect::<Vec<_>>();
+    assert_eq!(
+        ranges,
+        [vec!["ETag"], vec!["ETag"]],
+        "expected the default response too"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn eval_combinators() -> anyhow::Result<()> {
+    let code = r#"
+        let user = { 'id! int, 'name! str, 'email str, 'meta { 'tag str, 'rank int } };
+        let page = with_params { 'limit int, 'id str } /users?{ 'id int };
+        res page on get -> (pick ("id" | "email") user);
+        res /a on get -> (omit "meta" user);
+        res /b on get -> (merge user { 'email int, 'meta! { 'rank str, 'note str } `title: "Meta"` });
+        res /c on patch : (optional user) -> {};
+    "#;
+    let s = eval_check(code)?;
+    let props = |o: &Object| -> Vec<String> {
+        o.props.iter().map(|p| p.name.as_ref().to_owned()).collect()
+    };
+    let object = |rel: usize, method: Method| -> Object {
+        let x = s.rels[rel].xfers[method].as_ref().unwrap();
+        let content = if method == Method::Patch {
+            &x.domain
+        } else {
+            x.ranges.values().next().unwrap()
+        };
+        let SchemaExpr::Object(o) = &content.schema.as_ref().unwrap().expr else {
+            panic!("expected an object")
+        };
+        o.clone()
+    };
+
+    let params = s.rels[0].uri.params.as_ref().unwrap();
+    assert_eq!(props(params), ["id", "limit"]);
+    assert!(matches!(params.props[0].schema.expr, SchemaExpr::Str(_)));
+
+    assert_eq!(props(&object(0, Method::Get)), ["id", "email"]);
+    assert_eq!(props(&object(1, Method::Get)), ["id", "name", "email"]);
+
+    let merged = object(2, Method::Get);
+    assert_eq!(props(&merged), ["id", "name", "email", "meta"]);
+    assert!(matches!(merged.props[2].schema.expr, SchemaExpr::Int(_)));
+    let SchemaExpr::Object(meta) = &merged.props[3].schema.expr else {
+        panic!("expected a nested object")
+    };
+    assert_eq!(props(meta), ["tag", "rank", "note"]);
+    assert!(matches!(meta.props[1].schema.expr, SchemaExpr::Str(_)));
+    assert_eq!(merged.props[3].required, Some(true));
+    assert_eq!(merged.props[3].schema.title.as_deref(), Some("Meta"));
+
+    let optional = object(3, Method::Patch);
+    assert!(optional.props.iter().all(|p| p.required == Some(false)));
+
+    let code = r#"
+        let optional = { 'id int };
+        let merge x = x;
+        res /a on get -> merge optional;
+    "#;
+    eval_check(code)?;
+
+    let code = r#"res /a on get -> (pick "missing" { 'id int });"#;
+    let err = eval_check(code).expect_err("expected an error");
+    let err = err
+        .downcast_ref::<errors::Error>()
+        .expect("expected compiler error");
+    assert!(matches!(err.kind, errors::Kind::InvalidLiteral));
+
+    let code = r#"res /a on get -> (pick 42 { 'id int });"#;
+    let err = eval_check(code).expect_err("expected an error");
+    let err = err
+        .downcast_ref::<errors::Error>()
+        .expect("expected compiler error");
+    assert!(matches!(err.kind, errors::Kind::InvalidType));
+
+    Ok(())
+}
+
+#[test]
+fn eval_component_names() -> anyhow::Result<()> {
+    let code = r#"
+        # componentName: "User Account"
+        let @user = { 'id int };
+        let @other = {};
+        res /a on get -> @user, put -> @other;
+    "#;
+    let s = eval_check(code)?;
+    assert_eq!(s.component_names.len(), 1);
+    assert_eq!(s.component_names[&Ident::from("@user")], "User_Account");
+
+    let code = r#"
+        # componentName: other
+        let @user = {};
+        let @other = {};
+        res /a on get -> @user, put -> @other;
+    "#;
+    let err = eval_check(code).expect_err("expected a conflict");
+    let err = err
+        .downcast_ref::<errors::Error>()
+        .expect("expected compiler error");
+    assert!(matches!(err.kind, errors::Kind::Conflict));
+    assert!(err.span().is_some());
+
+    let code = r#"
+        # componentName: ""
+        let @user = {};
+        res /a on get -> @user;
+    "#;
+    let err = eval_check(code).expect_err("expected an error");
+    let err = err
+        .downcast_ref::<errors::Error>()
+        .expect("expected compiler error");
+    assert!(matches!(err.kind, errors::Kind::InvalidLiteral));
+
+    Ok(())
+}
+
+#[test]
+fn eval_property_style() -> anyhow::Result<()> {
+    let s = eval_check(
+        r#"
+        res /a?{
+            # style: deepObject
+            # explode: true
+            'filter { 'x str }
+        } on get -> {};
+    "#,
+    )?;
+    let p = &s.rels[0]
+        .uri
+        .params
+        .as_ref()
+        .expect("expected parameters")
+        .props[0];
+    assert_eq!(p.style, Some(ParamStyle::DeepObject));
+    assert_eq!(p.explode, Some(true));
+
+    let code = r#"
+        res /a?{
+            # style: matrix
+            'filter str
+        } on get -> {};
+    "#;
+    let err = eval_check(code).expect_err("expected an error");
+    let err = err
+        .downcast_ref::<errors::Error>()
+        .expect("expected compiler error");
+    assert!(matches!(err.kind, errors::Kind::InvalidLiteral));
+    assert!(err.span().is_some());
+
+    let s = eval_check(
+        r#"
+        # style: matrix
+        # explode: true
+        let id = 'id str;
+        res /a/{id} on get -> {};
+    "#,
+    )?;
+    let UriSegment::Variable(p) = &s.rels[0].uri.path[1] else {
+        panic!("expected a variable")
+    };
+    assert_eq!(p.style, Some(ParamStyle::Matrix));
+    assert_eq!(p.explode, Some(true));
+
+    let code = r#"
+        # style: deepObject
+        let id = 'id str;
+        res /a/{id} on get -> {};
+    "#;
+    let err = eval_check(code).expect_err("expected an error");
+    let err = err
+        .downcast_ref::<errors::Error>()
+        .expect("expected compiler error");
+    assert!(matches!(err.kind, errors::Kind::InvalidLiteral));
+    assert!(err.span().is_some());
+
+    let code = r#"
+        let form = {
+            # style: csv
+            'ids [str]
+        };
+        res /a on post : <media="application/x-www-form-urlencoded", form> -> {};
+    "#;
+    let mods = mods_from(code)?;
+    crate::compile::compile(&mods, mods.base())?;
+    let (_, warnings) = crate::eval::eval_with_warnings(&mods)?;
+    assert_eq!(warnings.len(), 1, "expected a warning for the style");
+    assert!(matches!(warnings[0].kind, errors::Kind::InvalidLiteral));
+    assert!(warnings[0].to_string().contains("csv"));
+    assert!(warnings[0].span().is_some());
+
+    Ok(())
+}
+
+#[test]
+fn eval_content_encoding() -> anyhow::Result<()> {
+    let s = eval_check(
+        r#"
+        res / on put : <{}> `content-encoding: [gzip]`
+                     -> <status=200, {}> `content-encoding: [gzip, br]`;
+    "#,
+    )?;
+    let x = s.rels[0].xfers[Method::Put]
+        .as_ref()
+        .expect("expected transfer on HTTP PUT");
+    assert_eq!(x.domain.encodings, ["gzip"]);
+    let c = x.ranges.values().next().expect("expected a range");
+    assert_eq!(c.encodings, ["gzip", "br"]);
+
+    let code = r#"res / on get -> <{}> `content-encoding: [GZIP]`;"#;
+    let err = eval_check(code).expect_err("expected an error");
+    let err = err
+        .downcast_ref::<errors::Error>()
+        .expect("expected compiler error");
+    assert!(matches!(err.kind, errors::Kind::InvalidLiteral));
+
+    Ok(())
+}
+
+#[test]
+fn eval_joined_headers() -> anyhow::Result<()> {
+    let s = eval_check(
+        r#"
+        let h = { 'A str } & { 'B int };
+        res / on get -> <headers=h & { 'C str }, {}>;
+    "#,
+    )?;
+    let x = s.rels[0].xfers[Method::Get]
+        .as_ref()
+        .expect("expected transfer on HTTP GET");
+    let c = x.ranges.values().next().expect("expected a range");
+    let names: Vec<_> = c
+        .headers
+        .as_ref()
+        .unwrap()
+        .props
+        .iter()
+        .map(|p| p.name.as_ref())
+        .collect();
+    assert_eq!(names, ["A", "B", "C"]);
+
+    Ok(())
+}
+
+#[test]
+fn eval_transfer_extensions() -> anyhow::Result<()> {
+    let s = eval_check(
+        r#"
+        res /a on
+            # x-timeout-ms: 5000
+            # maxBodyBytes: 1048576
+            # x-gateway: { pool: upload }
+            post -> <>,
+            # x-http-method: purge
+            delete -> <>;
+    "#,
+    )?;
+    let r = &s.rels[0];
+    let x = r.xfers[Method::Post]
+        .as_ref()
+        .expect("expected transfer on HTTP POST");
+    let names: Vec<_> = x.extensions.keys().collect();
+    assert_eq!(names, ["x-timeout-ms", "x-gateway", "x-max-body-bytes"]);
+    assert_eq!(x.extensions["x-timeout-ms"].as_u64(), Some(5000));
+    assert_eq!(x.extensions["x-max-body-bytes"].as_u64(), Some(1048576));
+    let x = r
+        .custom_xfers
+        .values()
+        .next()
+        .expect("expected custom transfer");
+    assert!(x.extensions.is_empty());
+
+    let code = r#"res /a on (get -> <>) `x-timeout-ms: -1`;"#;
+    let err = eval_check(code).expect_err("expected an error");
+    let err = err
+        .downcast_ref::<errors::Error>()
+        .expect("expected compiler error");
+    assert!(matches!(err.kind, errors::Kind::InvalidLiteral));
+
+    Ok(())
+}
+
+#[test]
+fn eval_schema_example() -> anyhow::Result<()> {
+    let s = eval_check(
+        r#"
+        # example: { tags: [a] }
+        let r = {
+            'tags [str] `example: [a, b]`,
+            'meta { 'k str } `example: { k: v }`,
+            'count int `example: 3, enum: [1, 2, 3]`
+        };
+        res /a on get -> <r>;
+    "#,
+    )?;
+    let x = s.rels[0].xfers[Method::Get]
+        .as_ref()
+        .expect("expected transfer on HTTP GET");
+    let c = x.ranges.values().next().expect("expected a range");
+    let r = c.schema.as_ref().unwrap();
+    assert_eq!(r.example, Some(serde_yaml::from_str("{ tags: [a] }")?));
+    let SchemaExpr::Object(ref o) = r.expr else {
+        panic!("expected an object")
+    };
+    let mut props = o.props.iter().map(|p| &p.schema);
+    let tags = props.next().unwrap();
+    assert_eq!(tags.example, Some(serde_yaml::from_str("[a, b]")?));
+    let meta = props.next().unwrap();
+    assert_eq!(meta.example, Some(serde_yaml::from_str("{ k: v }")?));
+    let count = props.next().unwrap();
+    assert!(count.example.is_none(), "expected a typed example instead");
+    let SchemaExpr::Int(ref i) = count.expr else {
+        panic!("expected an integer")
+    };
+    assert_eq!(i.example, Some(3));
+
+    Ok(())
+}
+
+#[test]
+fn eval_invalid_example() -> anyhow::Result<()> {
+    let code = r#"
+        let @user = { 'id! int, 'name str, 'tags [str] };
+        # example: { id: 1, tags: [a, 2] }
+        let a = @user;
+        let b = { 'user @user } `example: { user: { name: x } }`;
+        let c = (int ~ { 'x bool }) `example: { x: 1 }`;
+        let d = [num] `example: [1, 2.5]`;
+        let e = (@user & { 'extra str }) `example: { id: 1, extra: y }`;
+        let f = { 'id! int `nullable: true`, 'name str } `example: { id: null, name: null }`;
+        let g = { 'id! int } `example: { id: null }`;
+        res /a on get -> a, put : b -> c, post : d -> e;
+        res /b on get -> f, put : g -> {};
+    "#;
+    let mods = mods_from(code)?;
+    crate::compile::compile(&mods, mods.base())?;
+    let (_, warnings) = crate::eval::eval_with_warnings(&mods)?;
+    let msgs: Vec<_> = warnings.iter().map(|w| w.to_string()).collect();
+    assert_eq!(msgs.len(), 4, "{msgs:?}");
+    for w in warnings.iter() {
+        assert!(matches!(w.kind, errors::Kind::InvalidExample));
+        assert_eq!(w.kind.lint(), Some("examples"));
+        assert!(w.span().is_some(), "expected the span of the annotation");
+    }
+    assert!(msgs[0].contains("example at /tags/1 is an integer instead of a string"));
+    assert!(msgs[1].contains("example at /user is missing the required property id"));
+    assert!(msgs[2].contains("example matches none of the alternatives"));
+    assert!(msgs[3].contains("example at /id is null instead of an integer"));
+
+    Ok(())
+}
+
+#[test]
+fn eval_example_alternatives() -> anyhow::Result<()> {
+    // Each level of the example could match any of the three alternatives.
+    let example = format!("{}1{}", "[".repeat(25), "]".repeat(25));
+    let code = format!(
+        "let @a = [@a] | [@a `title: x`] | [@a `title: y`];\n\
+        res /a on get -> <@a `example: {example}`>;"
+    );
+    let mods = mods_from(&code)?;
+    crate::compile::compile(&mods, mods.base())?;
+    crate::eval::eval_with_warnings(&mods)?;
+
+    let limits = crate::eval::EvalLimits {
+        max_steps: Some(1 << 10),
+        ..Default::default()
+    };
+    let err = crate::eval::eval_with_limits(&mods, &limits).expect_err("expected an error");
+    assert!(matches!(err.kind, errors::Kind::ResourceExceeded));
+
+    Ok(())
+}
+
+#[test]
+fn eval_format_example() -> anyhow::Result<()> {
+    let valid = [
+        ("uuid", "123e4567-e89b-12d3-a456-426614174000"),
+        ("date", "2024-02-29"),
+        ("date-time", "2024-02-29T12:30:00Z"),
+        ("date-time", "2024-02-29T12:30:00.250+01:00"),
+        ("slug", "hello-world-2"),
+        ("email", "anything"),
+    ];
+    for (format, example) in valid {
+        let code =
+            format!("res /a on get -> {{ 'x str `format: {format}, example: \"{example}\"` }};");
+        eval_check(&code)?;
+    }
+
+    let invalid = [
+        ("uuid", "123e4567-e89b-12d3-a456"),
+        ("date", "2024-13-01"),
+        ("date-time", "2024-02-29 12:30:00"),
+        ("date-time", "2024-02-29T12:30:00"),
+        ("slug", "Hello--world"),
+    ];
+    for (format, example) in invalid {
+        let code =
+            format!("res /a on get -> {{ 'x str `format: {format}, example: \"{example}\"` }};");
+        let err = eval_check(&code).expect_err("expected an error");
+        let err = err
+            .downcast_ref::<errors::Error>()
+            .expect("expected compiler error");
+        assert!(
+            matches!(err.kind, errors::Kind::InvalidLiteral),
+            "{example}"
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn eval_param_groups() -> anyhow::Result<()> {
+    let code = r#"
+        let filters = { 'limit int, 'offset int };
+        res /a on get (filters & { 'q str, 'limit int }) -> {};
+    "#;
+    let s = eval_check(code)?;
+    let xfer = s.rels[0].xfers[Method::Get].as_ref().unwrap();
+    let names = xfer.params.as_ref().unwrap().props.iter();
+    let names = names.map(|p| p.name.as_ref()).collect::<Vec<_>>();
+    assert_eq!(names, ["limit", "offset", "q"]);
+    assert_eq!(xfer.shared_params, ["limit", "offset"]);
+
+    let code = r#"
+        let filters = { 'limit int };
+        res /a on get (filters & { 'limit str }) -> {};
+    "#;
+    let err = eval_check(code).expect_err("expected a conflict");
+    let err = err
+        .downcast_ref::<errors::Error>()
+        .expect("expected compiler error");
+    assert!(matches!(err.kind, errors::Kind::Conflict));
+
+    Ok(())
+}
+
+#[test]
+fn eval_stability() -> anyhow::Result<()> {
+    let code = r#"
+        # stability: alpha
+        let @draft = { 'id int };
+        # stability: stable
+        let @page = { 'items [@wrapper] };
+        let @wrapper = { 'draft @draft };
+        res /a on (get -> @page) `stability: stable`;
+        res /b on (get -> @wrapper) `stability: beta`, (put -> @draft) `stability: alpha`;
+    "#;
+    let mods = mods_from(code)?;
+    crate::compile::compile(&mods, mods.base())?;
+    let (spec, warnings) = crate::eval::eval_with_warnings(&mods)?;
+    let msgs: Vec<_> = warnings.iter().map(|w| w.to_string()).collect();
+    assert_eq!(msgs.len(), 3, "{msgs:?}");
+    for w in warnings.iter() {
+        assert!(matches!(w.kind, errors::Kind::UnstableReference));
+        assert_eq!(w.kind.lint(), Some("stability"));
+        assert!(w.span().is_some());
+        assert_eq!(
+            w.labels().len(),
+            1,
+            "expected the declaration of the component"
+        );
+    }
+    assert!(msgs[0].contains("stable operation references the alpha schema component draft"));
+    assert!(msgs[1].contains("beta operation references the alpha schema component draft"));
+    assert!(msgs[2]
+        .contains("stable schema component page references the alpha schema component draft"));
+
+    let x = spec.rels[1].xfers[Method::Put].as_ref().unwrap();
+    assert_eq!(x.stability, Some(Stability::Alpha));
+
+    let code = "res /a on (get -> {}) `stability: experimental`;";
+    let err = eval_check(code).expect_err("expected an error");
+    let err = err
+        .downcast_ref::<errors::Error>()
+        .expect("expected compiler error");
+    assert!(matches!(err.kind, errors::Kind::InvalidLiteral));
+
+    Ok(())
+}
+
+#[test]
+fn eval_duplicate_schemas() -> anyhow::Result<()> {
+    let code = r#"
+        let @user = { 'id int, 'name str };
+        # description: an account
+        let @account = { 'id int, 'name str };
+        let @member = { 'id int, 'name str };
+        let @id = int;
+        let @count = int;
+        let @empty = {};
+        let @none = {};
+        let @alias = @user;
+        res /users on get -> [@user], post : @account -> @member;
+        res /counts on get -> { 'id @id, 'count @count, 'e @empty, 'n @none, 'a @alias };
+    "#;
+    let mods = mods_from(code)?;
+    crate::compile::compile(&mods, mods.base())?;
+    let (_, warnings) = crate::eval::eval_with_warnings(&mods)?;
+    let msgs: Vec<_> = warnings.iter().map(|w| w.to_string()).collect();
+    assert_eq!(msgs.len(), 2, "{msgs:?}");
+    for w in warnings.iter() {
+        assert!(matches!(w.kind, errors::Kind::DuplicateSchema));
+        assert_eq!(w.kind.lint(), Some("duplicates"));
+        assert!(w.span().is_some());
+        assert_eq!(w.labels().len(), 1, "expected the first declaration");
+    }
+    assert!(msgs[0].contains("schema component account has the same structure as user"));
+    assert!(msgs[1].contains("schema component member has the same structure as user"));
+
+    Ok(())
+}
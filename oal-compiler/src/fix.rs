@@ -0,0 +1,201 @@
+//! Machine-applicable fixes attached to diagnostics, e.g. to be offered as code actions.
+
+use crate::definition::Definition;
+use crate::module::ModuleSet;
+use crate::tree::{Core, NRef};
+use oal_model::grammar::AbstractSyntaxNode;
+use oal_model::locator::Locator;
+use oal_model::span::Span;
+use oal_syntax::atom;
+use oal_syntax::lexer::TokenKind;
+use oal_syntax::parser as syn;
+
+/// A text edit, replacing a span of a source file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Edit {
+    pub span: Span,
+    pub text: String,
+}
+
+/// A fix for a diagnostic, as a set of edits to apply together.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Fix {
+    pub title: String,
+    pub edits: Vec<Edit>,
+}
+
+impl Fix {
+    /// Creates a fix inserting text at the given position.
+    pub fn insert<S: Into<String>>(title: S, loc: Locator, at: usize, text: &str) -> Self {
+        Fix {
+            title: title.into(),
+            edits: vec![Edit {
+                span: Span::new(loc, at..at),
+                text: text.to_owned(),
+            }],
+        }
+    }
+}
+
+fn error_start(err: &oal_syntax::errors::Error) -> Option<usize> {
+    match err {
+        oal_syntax::errors::Error::Grammar(err) => Some(err.span().start()),
+        _ => None,
+    }
+}
+
+/// Returns true if a token can only start a statement at the top level of a program.
+fn is_statement_start(kind: TokenKind) -> bool {
+    matches!(
+        kind,
+        TokenKind::KeywordLet
+            | TokenKind::KeywordRes
+            | TokenKind::KeywordUse
+            | TokenKind::AnnotationLine
+            | TokenKind::AnnotationDocument
+    )
+}
+
+/// Returns a fix for a syntax error, if any.
+///
+/// When the parser fails at the start of a statement, a semicolon is inserted
+/// after the last token before the next statement, outside of any brackets.
+pub fn syntax_fix(loc: &Locator, input: &str, err: &oal_syntax::errors::Error) -> Option<Fix> {
+    let start = error_start(err)?;
+    let (Some(tokens), _) = oal_syntax::lexer::tokenize(loc.clone(), input) else {
+        return None;
+    };
+    let mut depth = 0usize;
+    let mut last = None;
+    let mut s = tokens.head();
+    while s.is_valid() {
+        let kind = tokens.kind(s);
+        let (_, span) = tokens.token_span(s);
+        s = tokens.advance(s);
+        if kind.is_trivia() || span.start() < start {
+            continue;
+        }
+        let Some(end) = last else {
+            if !is_statement_start(kind) {
+                return None;
+            }
+            last = Some(span.end());
+            continue;
+        };
+        match kind {
+            TokenKind::ControlBraceLeft
+            | TokenKind::ControlParenLeft
+            | TokenKind::ControlBracketLeft
+            | TokenKind::ControlChevronLeft => depth += 1,
+            TokenKind::ControlBraceRight
+            | TokenKind::ControlParenRight
+            | TokenKind::ControlBracketRight
+            | TokenKind::ControlChevronRight => depth = depth.checked_sub(1)?,
+            TokenKind::ControlSemicolon if depth == 0 => return None,
+            k if depth == 0 && is_statement_start(k) => {
+                return Some(missing_semicolon(loc, end));
+            }
+            _ => {}
+        }
+        last = Some(span.end());
+    }
+    // A statement left open at the end of the input.
+    match last {
+        Some(end) if depth == 0 => Some(missing_semicolon(loc, end)),
+        _ => None,
+    }
+}
+
+fn missing_semicolon(loc: &Locator, at: usize) -> Fix {
+    Fix::insert("insert missing semicolon", loc.clone(), at, ";")
+}
+
+/// Returns a fix marking a property as required, given the expression declaring it.
+///
+/// Variables are followed into their declarations and joins into their operands.
+pub fn require_property(mods: &ModuleSet, node: NRef, name: &str) -> Option<Fix> {
+    let prop = declared_property(mods, node, name, MAX_DEPTH)?;
+    let name_span = prop.node().first().span()?;
+    let edit = match prop
+        .node()
+        .children()
+        .nth(1)
+        .and_then(syn::OptionMark::cast)
+    {
+        Some(mark) => Edit {
+            span: mark.node().span()?,
+            text: "!".to_owned(),
+        },
+        None => Edit {
+            span: Span::new(
+                name_span.locator().clone(),
+                name_span.end()..name_span.end(),
+            ),
+            text: "!".to_owned(),
+        },
+    };
+    Some(Fix {
+        title: "add missing !".to_owned(),
+        edits: vec![edit],
+    })
+}
+
+/// The maximum number of declarations and operations followed to find a property.
+const MAX_DEPTH: usize = 16;
+
+fn declared_property<'a>(
+    mods: &'a ModuleSet,
+    node: NRef<'a>,
+    name: &str,
+    depth: usize,
+) -> Option<syn::Property<'a, Core>> {
+    let depth = depth.checked_sub(1)?;
+    if let Some(prop) = syn::Property::cast(node) {
+        return (prop.name() == name).then_some(prop);
+    }
+    if let Some(term) = syn::Terminal::cast(node) {
+        return declared_property(mods, term.inner(), name, depth);
+    }
+    if let Some(var) = syn::Variable::cast(node) {
+        let ext = match var.node().syntax().core_ref().definition() {
+            Some(Definition::External(ext)) => ext.clone(),
+            _ => return None,
+        };
+        let decl = syn::Declaration::cast(ext.node(mods))?;
+        return declared_property(mods, decl.rhs(), name, depth);
+    }
+    if let Some(obj) = syn::Object::cast(node) {
+        return obj
+            .properties()
+            .find_map(|p| declared_property(mods, p, name, depth));
+    }
+    match syn::VariadicOp::cast(node) {
+        Some(op) if op.operator() == atom::VariadicOperator::Join => op
+            .operands()
+            .find_map(|o| declared_property(mods, o, name, depth)),
+        _ => None,
+    }
+}
+
+/// Applies the edits to a source text, skipping the edits overlapping previous ones.
+///
+/// Returns the edited text and the number of edits applied.
+pub fn apply(input: &str, edits: &[&Edit]) -> (String, usize) {
+    let mut edits = edits.to_vec();
+    edits.sort_by_key(|e| (e.span.start(), e.span.end()));
+    let mut output = String::with_capacity(input.len());
+    let mut pos = 0;
+    let mut count = 0;
+    for edit in edits {
+        let range = edit.span.range();
+        if range.start < pos || range.end > input.len() {
+            continue;
+        }
+        output.push_str(&input[pos..range.start]);
+        output.push_str(&edit.text);
+        pos = range.end;
+        count += 1;
+    }
+    output.push_str(&input[pos..]);
+    (output, count)
+}
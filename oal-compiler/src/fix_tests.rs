@@ -0,0 +1,98 @@
+use crate::errors;
+use crate::fix::{apply, syntax_fix, Edit};
+use crate::tests::mods_from;
+use oal_model::locator::Locator;
+use oal_model::span::Span;
+
+fn fixed(input: &str) -> Option<String> {
+    let loc = Locator::try_from("file:///main.oal").unwrap();
+    let (_, errs) = oal_syntax::parse::<_, crate::tree::Core>(loc.clone(), input);
+    let err = errs.first().expect("expected a syntax error");
+    let fix = syntax_fix(&loc, input, err)?;
+    assert_eq!(fix.title, "insert missing semicolon");
+    let edits = fix.edits.iter().collect::<Vec<_>>();
+    Some(apply(input, &edits).0)
+}
+
+#[test]
+fn fix_missing_semicolon() {
+    let code = "let a = {}\nlet b = a;\nres /;\n";
+    assert_eq!(
+        fixed(code).as_deref(),
+        Some("let a = {};\nlet b = a;\nres /;\n")
+    );
+
+    let code = "let a = { 'p str } // no end\nres /;\n";
+    assert_eq!(
+        fixed(code).as_deref(),
+        Some("let a = { 'p str }; // no end\nres /;\n")
+    );
+
+    assert_eq!(fixed("let a = {;\n"), None);
+}
+
+#[test]
+fn fix_missing_semicolon_at_end() {
+    assert_eq!(
+        fixed("res / on get -> {} // no end\n").as_deref(),
+        Some("res / on get -> {}; // no end\n")
+    );
+    assert_eq!(fixed("let a = { 'p [str };\nres /;\n"), None);
+}
+
+/// Returns the code fixed by the single fix of a compiler error.
+fn compile_fixed(code: &str) -> anyhow::Result<String> {
+    let mods = mods_from(code)?;
+    let err = crate::compile::compile(&mods, mods.base())
+        .and_then(|_| crate::eval::eval(&mods).map(|_| ()))
+        .expect_err("expected an error");
+    let [fix] = err.fixes() else {
+        panic!("expected a single fix, got {:?}", err.fixes())
+    };
+    assert_eq!(fix.title, "add missing !");
+    let edits = fix.edits.iter().collect::<Vec<_>>();
+    Ok(apply(code, &edits).0)
+}
+
+#[test]
+fn fix_missing_required() -> anyhow::Result<()> {
+    let code = r#"
+        let @cat = { 'kind str, 'lives int };
+        let @dog = {} & { 'kind? str };
+        let @pet = (@cat | @dog | { 'kind! str }) `discriminator: kind`;
+        res / on get -> @pet;
+    "#;
+    let fixed = compile_fixed(code)?;
+    assert!(fixed.contains("{ 'kind! str, 'lives int }"), "{fixed}");
+    let fixed = compile_fixed(&fixed)?;
+    assert!(fixed.contains("{} & { 'kind! str }"), "{fixed}");
+
+    let mods = mods_from(&fixed)?;
+    crate::compile::compile(&mods, mods.base())?;
+    crate::eval::eval(&mods)?;
+
+    let code = "let p = 'kind str; let a = ({ p } | {}) `discriminator: kind`; res / on get -> a;";
+    let mods = mods_from(code)?;
+    crate::compile::compile(&mods, mods.base())?;
+    let err = crate::eval::eval(&mods).expect_err("expected an error");
+    assert!(matches!(err.kind, errors::Kind::InvalidDiscriminator));
+    assert_eq!(
+        err.fixes().len(),
+        1,
+        "expected a fix on the property declaration"
+    );
+    Ok(())
+}
+
+#[test]
+fn fix_apply_edits() {
+    let loc = Locator::try_from("file:///main.oal").unwrap();
+    let edit = |range, text: &str| Edit {
+        span: Span::new(loc.clone(), range),
+        text: text.to_owned(),
+    };
+    let edits = [edit(4..5, "b"), edit(0..0, "x"), edit(4..6, "y")];
+    let (text, count) = apply("let a = 1;", &edits.iter().collect::<Vec<_>>());
+    assert_eq!(text, "xlet b = 1;");
+    assert_eq!(count, 2, "expected overlapping edits to be skipped");
+}
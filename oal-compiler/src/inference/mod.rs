@@ -7,13 +7,12 @@ mod tests;
 #[cfg(test)]
 mod union_tests;
 
-#[cfg(test)]
 use crate::errors::{Error, Kind};
 
 use crate::definition::Definition;
 use crate::errors::Result;
 use crate::module::ModuleSet;
-use crate::tree::{get_tag, set_tag};
+use crate::tree::{get_tag, set_tag, NRef};
 use oal_model::grammar::AbstractSyntaxNode;
 use oal_model::locator::Locator;
 use oal_syntax::atom;
@@ -31,6 +30,13 @@ fn literal_tag(t: &lex::TokenValue) -> Tag {
     }
 }
 
+/// Returns true if the node is a literal string or number, as an operand of a literal alternative.
+fn is_literal_value(node: NRef) -> bool {
+    syn::Terminal::cast(node)
+        .and_then(|term| syn::Literal::cast(term.inner()))
+        .is_some_and(|lit| lit.kind() != syn::LiteralKind::HttpStatus)
+}
+
 /// Assigns type tags to all expressions in the given module.
 /// Returns the number of tag variables allocated.
 pub fn tag(mods: &ModuleSet, loc: &Locator) -> Result<usize> {
@@ -66,6 +72,7 @@ pub fn tag(mods: &ModuleSet, loc: &Locator) -> Result<usize> {
             let tag = match operator {
                 atom::VariadicOperator::Join => Tag::Object,
                 atom::VariadicOperator::Any => Tag::Any,
+                atom::VariadicOperator::Sum if op.operands().all(is_literal_value) => Tag::Literal,
                 atom::VariadicOperator::Sum => Tag::Var(seq.next()),
                 atom::VariadicOperator::Range => Tag::Content,
             };
@@ -136,16 +143,21 @@ pub fn constrain(mods: &ModuleSet, loc: &Locator) -> Result<InferenceSet> {
             }
         } else if let Some(xfer) = syn::Transfer::cast(node) {
             if let Some(params) = xfer.params() {
-                set.push(get_tag(params.node()), Tag::Object, params.node().span());
+                set.push(get_tag(params), Tag::Object, params.span());
             }
         } else if let Some(op) = syn::VariadicOp::cast(node) {
             for operand in op.operands() {
-                if let Some(t) = match op.operator() {
+                if let Some((t, operation)) = match op.operator() {
                     atom::VariadicOperator::Range | atom::VariadicOperator::Any => None,
-                    atom::VariadicOperator::Join => Some(Tag::Object),
-                    atom::VariadicOperator::Sum => Some(get_tag(node)),
+                    atom::VariadicOperator::Sum if get_tag(node) == Tag::Literal => None,
+                    atom::VariadicOperator::Join => {
+                        Some((Tag::Object, unify::Operation::Join(node.span())))
+                    }
+                    atom::VariadicOperator::Sum => {
+                        Some((get_tag(node), unify::Operation::Sum(node.span())))
+                    }
                 } {
-                    set.push(get_tag(operand), t, operand.span());
+                    set.push_operand(get_tag(operand), t, operand.span(), operation);
                 }
             }
         } else if let Some(op) = syn::UnaryOp::cast(node) {
@@ -199,7 +211,6 @@ pub fn substitute(mods: &ModuleSet, loc: &Locator, sets: &union::UnionFind) -> R
     Ok(())
 }
 
-#[cfg(test)]
 fn has_variable(tag: &Tag) -> bool {
     match tag {
         Tag::Var(_) => true,
@@ -209,7 +220,6 @@ fn has_variable(tag: &Tag) -> bool {
     }
 }
 
-#[cfg(test)]
 /// Returns an error if there is at least one remaining tag variable.
 pub fn check_complete(mods: &ModuleSet, loc: &Locator) -> Result<()> {
     let module = mods.get(loc).expect("module not found");
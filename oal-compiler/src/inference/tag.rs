@@ -35,6 +35,8 @@ pub enum Tag {
     Array,
     Uri,
     Any,
+    /// The alternative between literal values, e.g. `"on" | "off"`.
+    Literal,
     Property(Box<Tag>),
     Func(FuncTag),
     Var(TagId),
@@ -54,6 +56,7 @@ impl Display for Tag {
             Tag::Array => write!(f, "array"),
             Tag::Uri => write!(f, "uri"),
             Tag::Any => write!(f, "any"),
+            Tag::Literal => write!(f, "literal"),
             Tag::Property(t) => write!(f, "property[{t}]"),
             Tag::Func(t) => write!(f, "function[{t}]"),
             Tag::Var(_) => write!(f, "<unknown>"),
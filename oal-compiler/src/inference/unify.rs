@@ -66,17 +66,48 @@ fn unify(sets: &mut union::UnionFind, left: &Tag, right: &Tag) -> Result<()> {
     }
 }
 
+/// The operation constraining the type of an operand, for explaining mismatches.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Operation {
+    /// A join of objects, spanning the given range.
+    Join(Option<Span>),
+    /// A sum of alternatives, spanning the given range.
+    Sum(Option<Span>),
+}
+
 #[derive(Clone, Debug, PartialEq)]
 struct TypeEquation {
     pub left: Tag,
     pub right: Tag,
     pub span: Option<Span>,
+    pub operation: Option<Operation>,
 }
 
 impl TypeEquation {
     fn unify(&self, sets: &mut union::UnionFind) -> Result<()> {
         unify(sets, &self.left, &self.right)
     }
+
+    /// Explains a mismatch in terms of the operation constraining the operand, if any.
+    fn explain(&self, sets: &union::UnionFind, err: Error) -> Error {
+        let found = union::reduce(sets, &self.left);
+        let expected = union::reduce(sets, &self.right);
+        match &self.operation {
+            None => err.at(self.span.clone()),
+            Some(Operation::Join(op)) => Error::new(
+                Kind::InvalidType,
+                format!("a join only combines objects, not {found}"),
+            )
+            .at(self.span.clone())
+            .with_label(op.clone(), "in this join"),
+            Some(Operation::Sum(op)) => Error::new(
+                Kind::InvalidType,
+                format!("alternative of kind {found} in a sum of kind {expected}"),
+            )
+            .at(self.span.clone())
+            .with_label(op.clone(), "in this sum"),
+        }
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Default)]
@@ -88,13 +119,31 @@ impl InferenceSet {
     }
 
     pub fn push(&mut self, left: Tag, right: Tag, span: Option<Span>) {
-        self.0.push(TypeEquation { left, right, span });
+        self.0.push(TypeEquation {
+            left,
+            right,
+            span,
+            operation: None,
+        });
+    }
+
+    /// Adds an equation constraining the operand of an operation.
+    pub fn push_operand(&mut self, left: Tag, right: Tag, span: Option<Span>, op: Operation) {
+        self.0.push(TypeEquation {
+            left,
+            right,
+            span,
+            operation: Some(op),
+        });
     }
 
     pub fn unify(&self) -> Result<union::UnionFind> {
         let mut sets = union::UnionFind::new();
-        for eq in self.0.iter() {
-            eq.unify(&mut sets).map_err(|err| err.at(eq.span.clone()))?;
+        // Operands are constrained last, so that mismatches are explained by their operation.
+        let (operands, others): (Vec<_>, Vec<_>) =
+            self.0.iter().partition(|eq| eq.operation.is_some());
+        for eq in others.into_iter().chain(operands) {
+            eq.unify(&mut sets).map_err(|err| eq.explain(&sets, err))?;
         }
         Ok(sets)
     }
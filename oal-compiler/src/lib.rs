@@ -1,24 +1,46 @@
-mod annotation;
+pub mod annotation;
 pub mod compile;
 pub mod definition;
+pub mod diff;
 mod env;
+pub mod envelope;
 pub mod errors;
 pub mod eval;
+pub mod fix;
 mod inference;
+pub mod library;
+pub mod limits;
+pub mod lint;
 pub mod module;
+mod print;
+pub mod query;
 mod resolve;
 pub mod spec;
-mod stdlib;
+pub mod stdlib;
 pub mod tree;
 mod typecheck;
 
 #[cfg(test)]
 mod compile_tests;
 #[cfg(test)]
+mod diff_tests;
+#[cfg(test)]
+mod envelope_tests;
+#[cfg(test)]
 mod eval_tests;
 #[cfg(test)]
+mod fix_tests;
+#[cfg(test)]
+mod limits_tests;
+#[cfg(test)]
+mod lint_tests;
+#[cfg(test)]
 mod module_tests;
 #[cfg(test)]
+mod print_tests;
+#[cfg(test)]
+mod query_tests;
+#[cfg(test)]
 mod resolve_tests;
 #[cfg(test)]
 mod spec_tests;
@@ -0,0 +1,39 @@
+use oal_model::locator::Locator;
+
+/// The prefix of imports from the library of standard modules.
+const IMPORT_PREFIX: &str = "std/";
+
+/// The scheme of standard module locators.
+const SCHEME: &str = "oal";
+
+/// The standard modules by name.
+const MODULES: &[(&str, &str)] = &[
+    ("problem+json", include_str!("library/problem.oal")),
+    ("pagination", include_str!("library/pagination.oal")),
+    ("ratelimit", include_str!("library/ratelimit.oal")),
+];
+
+/// Returns the locator of the standard module imported by the given path, if any.
+pub fn locator(import: &str) -> Option<Locator> {
+    let name = import.strip_prefix(IMPORT_PREFIX)?;
+    MODULES.iter().find(|(n, _)| *n == name)?;
+    Locator::try_from(format!("{SCHEME}:{IMPORT_PREFIX}{name}").as_str()).ok()
+}
+
+/// Returns true if imports through the given path prefix would name standard modules, e.g. `std`.
+pub fn is_reserved(prefix: &str) -> bool {
+    let prefix = prefix.trim_end_matches('/');
+    IMPORT_PREFIX.trim_end_matches('/') == prefix || prefix.starts_with(IMPORT_PREFIX)
+}
+
+/// Returns the source code of the standard module identified by the given locator, if any.
+pub fn source(loc: &Locator) -> Option<&'static str> {
+    let url = loc.url();
+    if url.scheme() != SCHEME {
+        return None;
+    }
+    let name = url.path().strip_prefix(IMPORT_PREFIX)?;
+    MODULES
+        .iter()
+        .find_map(|(n, code)| (*n == name).then_some(*code))
+}
@@ -0,0 +1,185 @@
+use crate::definition::{Definition, External};
+use crate::errors::{Error, Kind};
+use crate::module::ModuleSet;
+use crate::spec::Spec;
+use crate::tree::{Core, NRef};
+use oal_model::grammar::AbstractSyntaxNode;
+use oal_model::span::Span;
+use oal_syntax::parser as syn;
+use std::collections::HashMap;
+
+/// Limits on the shape of schemas, for downstream tooling that chokes on pathological ones.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Limits {
+    /// The maximum nesting depth of objects and arrays within a schema.
+    pub max_depth: Option<usize>,
+    /// The maximum number of schema components.
+    pub max_components: Option<usize>,
+}
+
+/// The nesting depth of an expression along with the chain of declarations reaching the deepest point.
+#[derive(Clone, Default)]
+struct Depth {
+    value: usize,
+    chain: Vec<Span>,
+}
+
+struct Context<'a> {
+    mods: &'a ModuleSet,
+    depths: HashMap<External, Depth>,
+    /// The depths of the arguments bound by the functions being applied.
+    scopes: Vec<HashMap<External, Depth>>,
+}
+
+/// Returns the external definition of a variable, if any.
+fn definition(var: syn::Variable<Core>) -> Option<External> {
+    match var.node().syntax().core_ref().definition() {
+        Some(Definition::External(ext)) => Some(ext.clone()),
+        _ => None,
+    }
+}
+
+/// Computes the nesting depth of an expression, following variables into their declarations
+/// and function applications into the function body.
+///
+/// References are emitted as separate components and therefore break the chain.
+fn depth(ctx: &mut Context, node: NRef) -> Depth {
+    if let Some(app) = syn::Application::cast(node) {
+        return application_depth(ctx, app);
+    }
+    if let Some(var) = syn::Variable::cast(node) {
+        if var.ident().is_reference() {
+            return Depth::default();
+        }
+        let Some(ext) = definition(var) else {
+            return Depth::default();
+        };
+        if syn::Binding::cast(ext.node(ctx.mods)).is_some() {
+            let scope = ctx.scopes.iter().rev().find_map(|s| s.get(&ext));
+            return scope.cloned().unwrap_or_default();
+        }
+        return declaration_depth(ctx, ext);
+    }
+    let mut deepest = node
+        .children()
+        .map(|child| depth(ctx, child))
+        .max_by_key(|d| d.value)
+        .unwrap_or_default();
+    if syn::Object::cast(node).is_some() || syn::Array::cast(node).is_some() {
+        deepest.value += 1;
+    }
+    deepest
+}
+
+/// Computes the nesting depth of a function application, binding the depths of its arguments.
+///
+/// Functions of the standard library are assumed to nest no deeper than their arguments.
+fn application_depth(ctx: &mut Context, app: syn::Application<Core>) -> Depth {
+    let args: Vec<_> = app.arguments().map(|a| depth(ctx, a.node())).collect();
+    let decl = definition(app.lambda())
+        .and_then(|ext| syn::Declaration::cast(ext.node(ctx.mods)))
+        .filter(|decl| decl.has_bindings());
+    let Some(decl) = decl else {
+        return args.into_iter().max_by_key(|d| d.value).unwrap_or_default();
+    };
+    let scope = decl
+        .bindings()
+        .map(|b| External::new(b.node()))
+        .zip(args)
+        .collect();
+    ctx.scopes.push(scope);
+    let mut d = depth(ctx, decl.rhs());
+    ctx.scopes.pop();
+    if let Some(span) = decl.identifier().node().span() {
+        d.chain.insert(0, span);
+    }
+    d
+}
+
+fn declaration_depth(ctx: &mut Context, ext: External) -> Depth {
+    if let Some(d) = ctx.depths.get(&ext) {
+        return d.clone();
+    }
+    // Guards against cycles while the declaration is being visited.
+    ctx.depths.insert(ext.clone(), Depth::default());
+    let defn = ext.node(ctx.mods);
+    let d = match syn::Declaration::cast(defn) {
+        Some(decl) if !decl.has_bindings() => {
+            let mut d = depth(ctx, decl.rhs());
+            if let Some(span) = decl.identifier().node().span() {
+                d.chain.insert(0, span);
+            }
+            d
+        }
+        _ => Depth::default(),
+    };
+    ctx.depths.insert(ext, d.clone());
+    d
+}
+
+fn depth_warning(root: Option<Span>, depth: Depth, max: usize) -> Error {
+    let mut err = Error::new(
+        Kind::LimitExceeded,
+        format!("schema depth of {} exceeds the limit of {max}", depth.value),
+    )
+    .at(root);
+    for span in depth.chain {
+        err = err.with_label(Some(span), "nested through this declaration");
+    }
+    err
+}
+
+/// Checks the program and its evaluated specification against the given limits.
+///
+/// Returns the warnings for each limit being exceeded.
+pub fn check_limits(mods: &ModuleSet, spec: &Spec, limits: &Limits) -> Vec<Error> {
+    let mut warnings = Vec::new();
+
+    if let Some(max) = limits.max_depth {
+        let ctx = &mut Context {
+            mods,
+            depths: HashMap::new(),
+            scopes: Vec::new(),
+        };
+        let main = syn::Program::cast(mods.main().root()).expect("expected a program");
+        for res in main.resources() {
+            let d = depth(ctx, res.relation());
+            if d.value > max {
+                warnings.push(depth_warning(res.node().span(), d, max));
+            }
+        }
+        // Each reference is a distinct component with its own depth.
+        for module in mods.sorted_modules() {
+            let prog = syn::Program::cast(module.root()).expect("expected a program");
+            for decl in prog.declarations() {
+                if decl.ident().is_reference() && !decl.has_bindings() {
+                    let d = depth(ctx, decl.rhs());
+                    if d.value > max {
+                        warnings.push(depth_warning(decl.identifier().node().span(), d, max));
+                    }
+                }
+            }
+        }
+    }
+
+    if let Some(max) = limits.max_components {
+        if let Some((ident, _)) = spec.refs.get_index(max) {
+            // Points at the declaration of the first component beyond the limit.
+            let span = mods
+                .sorted_modules()
+                .into_iter()
+                .filter_map(|m| syn::Program::cast(m.root()))
+                .flat_map(|p| p.declarations())
+                .find(|d| d.ident() == *ident)
+                .and_then(|d| d.identifier().node().span());
+            let err = Error::new(
+                Kind::LimitExceeded,
+                format!("{} components exceed the limit of {max}", spec.refs.len()),
+            )
+            .at(span);
+            warnings.push(err);
+        }
+    }
+
+    warnings
+}
@@ -0,0 +1,105 @@
+use crate::compile::compile;
+use crate::errors::Kind;
+use crate::limits::{check_limits, Limits};
+use crate::tests::mods_from;
+
+#[test]
+fn limits_schema_depth() -> anyhow::Result<()> {
+    let code = r#"
+        let c = { 'd [str] };
+        let b = { 'c c };
+        let @r = { 'x { 'y {} } };
+        res / on get -> { 'b b, 'r @r };
+    "#;
+    let mods = mods_from(code)?;
+    compile(&mods, mods.base())?;
+    let spec = crate::eval::eval(&mods)?;
+
+    let limits = Limits {
+        max_depth: Some(4),
+        ..Default::default()
+    };
+    assert!(check_limits(&mods, &spec, &limits).is_empty());
+
+    let limits = Limits {
+        max_depth: Some(2),
+        ..Default::default()
+    };
+    let warnings = check_limits(&mods, &spec, &limits);
+    assert_eq!(warnings.len(), 2, "expected the resource and the reference");
+    let err = &warnings[0];
+    assert!(matches!(err.kind, Kind::LimitExceeded));
+    assert_eq!(
+        err.to_string(),
+        "limit exceeded: schema depth of 4 exceeds the limit of 2"
+    );
+    assert_eq!(err.labels().len(), 2, "expected the declaration chain");
+    assert_eq!(warnings[1].labels().len(), 0);
+
+    Ok(())
+}
+
+#[test]
+fn limits_components() -> anyhow::Result<()> {
+    let code = r#"
+        let @a = {};
+        let @b = {};
+        res / on get -> @a, put -> @b;
+    "#;
+    let mods = mods_from(code)?;
+    compile(&mods, mods.base())?;
+    let spec = crate::eval::eval(&mods)?;
+
+    let limits = Limits {
+        max_components: Some(1),
+        ..Default::default()
+    };
+    let warnings = check_limits(&mods, &spec, &limits);
+    assert_eq!(warnings.len(), 1);
+    assert!(
+        warnings[0].span().is_some(),
+        "expected the second component"
+    );
+
+    let limits = Limits {
+        max_components: Some(2),
+        ..Default::default()
+    };
+    assert!(check_limits(&mods, &spec, &limits).is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn limits_schema_depth_applications() -> anyhow::Result<()> {
+    let code = r#"
+        let f x = { 'a x };
+        let g y = { 'b [y] };
+        res / on get -> g (f {});
+    "#;
+    let mods = mods_from(code)?;
+    compile(&mods, mods.base())?;
+    let spec = crate::eval::eval(&mods)?;
+
+    let limits = Limits {
+        max_depth: Some(4),
+        ..Default::default()
+    };
+    assert!(check_limits(&mods, &spec, &limits).is_empty());
+
+    let limits = Limits {
+        max_depth: Some(3),
+        ..Default::default()
+    };
+    let warnings = check_limits(&mods, &spec, &limits);
+    let [err] = warnings.as_slice() else {
+        panic!("expected a single warning")
+    };
+    assert_eq!(
+        err.to_string(),
+        "limit exceeded: schema depth of 4 exceeds the limit of 3"
+    );
+    assert_eq!(err.labels().len(), 2, "expected the function chain");
+
+    Ok(())
+}
@@ -0,0 +1,170 @@
+//! Checks of the program and its evaluated specification for likely mistakes,
+//! reported as warnings, e.g. by `oal-cli lint`.
+
+use crate::definition::{Definition, External};
+use crate::errors::{Error, Kind};
+use crate::library;
+use crate::module::ModuleSet;
+use crate::query::{method_label, status};
+use crate::spec::{Content, Relation, Spec, Transfer, UriSegment};
+use indexmap::IndexMap;
+use oal_model::grammar::AbstractSyntaxNode;
+use oal_model::span::Span;
+use oal_syntax::parser as syn;
+use std::collections::{HashMap, HashSet};
+
+/// Returns the operations of a relation along with their labels, e.g. `get /users`.
+fn operations(rel: &Relation) -> Vec<(String, &Transfer)> {
+    let path = rel.uri.pattern();
+    let standard = rel
+        .xfers
+        .iter()
+        .filter_map(|(m, x)| x.as_ref().map(|x| (method_label(m), x)));
+    let custom = rel.custom_xfers.iter().map(|(v, x)| (v.as_str(), x));
+    standard
+        .chain(custom)
+        .map(|(method, xfer)| (format!("{method} {path}"), xfer))
+        .collect()
+}
+
+/// Reports operation ids given to more than one operation.
+fn check_operation_ids(spec: &Spec, warnings: &mut Vec<Error>) {
+    let mut ids: HashMap<&str, (String, Option<Span>)> = HashMap::new();
+    for rel in spec.rels.iter() {
+        for (op, xfer) in operations(rel) {
+            let Some(id) = xfer.id.as_deref() else {
+                continue;
+            };
+            match ids.get(id) {
+                Some((first, span)) => {
+                    let msg = format!("operation id {id} of {op} is already used by {first}");
+                    let err = Error::new(Kind::DuplicateOperationId, msg)
+                        .at(xfer.span.clone())
+                        .with_label(span.clone(), "first used here");
+                    warnings.push(err);
+                }
+                None => {
+                    ids.insert(id, (op, xfer.span.clone()));
+                }
+            }
+        }
+    }
+}
+
+/// Reports path parameters appearing more than once in a path,
+/// sharing their name with a query parameter, or absent from the emitted URI of a webhook.
+fn check_path_parameters(spec: &Spec, warnings: &mut Vec<Error>) {
+    for rel in spec.rels.iter() {
+        let path = rel.uri.pattern();
+        let mut names = HashSet::new();
+        for segment in rel.uri.path.iter() {
+            if let UriSegment::Variable(prop) = segment {
+                // Webhooks are emitted by name, without the URI declaring their path parameters.
+                if let Some(webhook) = rel.webhook.as_ref() {
+                    let msg = format!(
+                        "path parameter {} of webhook {webhook} is not present in any URI",
+                        prop.name
+                    );
+                    warnings.push(Error::new(Kind::PathParameter, msg));
+                }
+                if !names.insert(prop.name.as_ref()) {
+                    let msg = format!(
+                        "path parameter {} appears more than once in {path}",
+                        prop.name
+                    );
+                    warnings.push(Error::new(Kind::PathParameter, msg));
+                }
+            }
+        }
+        let uri_params = rel.uri.params.iter();
+        let xfer_params = operations(rel)
+            .into_iter()
+            .filter_map(|(_, x)| x.params.as_ref());
+        let query = uri_params.chain(xfer_params).flat_map(|o| o.props.iter());
+        let mut reported = HashSet::new();
+        for prop in query {
+            let name = prop.name.as_ref();
+            if names.contains(name) && reported.insert(name) {
+                let msg =
+                    format!("query parameter {name} has the name of a path parameter of {path}");
+                warnings.push(Error::new(Kind::PathParameter, msg));
+            }
+        }
+    }
+}
+
+/// Reports media types of contents without a schema, which are not emitted.
+fn check_media_type(op: &str, what: &str, content: &Content, warnings: &mut Vec<Error>) {
+    if let (Some(media), None) = (&content.media, &content.schema) {
+        let msg =
+            format!("media type {media} of the {what} of {op} has no schema and is not emitted");
+        warnings.push(Error::new(Kind::UnreachableMediaType, msg));
+    }
+}
+
+/// Reports responses without a description, which OpenAPI requires.
+fn check_responses(spec: &Spec, warnings: &mut Vec<Error>) {
+    for rel in spec.rels.iter() {
+        for (op, xfer) in operations(rel) {
+            for content in xfer.domains() {
+                check_media_type(&op, "request", content, warnings);
+            }
+            // Contents of the same status share the description of the first one.
+            let mut described = IndexMap::new();
+            for ((code, _), content) in xfer.ranges.iter() {
+                check_media_type(&op, "response", content, warnings);
+                let has_desc = content.desc.as_ref().is_some_and(|d| !d.trim().is_empty());
+                *described.entry(*code).or_insert(false) |= has_desc;
+            }
+            for (code, has_desc) in described {
+                if !has_desc {
+                    let code = code.as_ref().map_or_else(|| "default".to_owned(), status);
+                    let msg = format!("response {code} of {op} has no description");
+                    warnings.push(Error::new(Kind::MissingDescription, msg));
+                }
+            }
+        }
+    }
+}
+
+/// Reports the declarations of the program modules that are never referred to.
+fn check_unused(mods: &ModuleSet, warnings: &mut Vec<Error>) {
+    let mut used = HashSet::new();
+    for module in mods.modules() {
+        for node in module.root().descendants() {
+            if let Some(var) = syn::Variable::cast(node) {
+                if let Some(Definition::External(ext)) = var.node().syntax().core_ref().definition()
+                {
+                    used.insert(ext.clone());
+                }
+            }
+        }
+    }
+    for module in mods.sorted_modules() {
+        // Standard modules are not expected to be used in full.
+        if library::source(module.locator()).is_some() {
+            continue;
+        }
+        let prog = syn::Program::cast(module.root()).expect("expected a program");
+        for decl in prog.declarations() {
+            if !used.contains(&External::new(decl.node())) {
+                let msg = format!("{} is declared but never used", decl.ident());
+                let err =
+                    Error::new(Kind::UnusedDeclaration, msg).at(decl.identifier().node().span());
+                warnings.push(err);
+            }
+        }
+    }
+}
+
+/// Checks the program and its evaluated specification for likely mistakes.
+///
+/// Returns the warnings in the order of the checks, each controlled by its own lint.
+pub fn lint(mods: &ModuleSet, spec: &Spec) -> Vec<Error> {
+    let mut warnings = Vec::new();
+    check_operation_ids(spec, &mut warnings);
+    check_path_parameters(spec, &mut warnings);
+    check_responses(spec, &mut warnings);
+    check_unused(mods, &mut warnings);
+    warnings
+}
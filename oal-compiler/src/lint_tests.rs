@@ -0,0 +1,127 @@
+use crate::compile::compile;
+use crate::errors::Kind;
+use crate::lint::lint;
+use crate::tests::mods_from;
+
+fn lint_code(code: &str) -> anyhow::Result<Vec<crate::errors::Error>> {
+    let mods = mods_from(code)?;
+    compile(&mods, mods.base())?;
+    let spec = crate::eval::eval(&mods)?;
+    Ok(lint(&mods, &spec))
+}
+
+#[test]
+fn lint_clean() -> anyhow::Result<()> {
+    let warnings = lint_code(
+        r#"
+        let user = { 'id int };
+        res /users/{ 'id str } on (get -> <status=200, user> `description: "The user"`) `operationId: getUser`;
+    "#,
+    )?;
+    assert!(warnings.is_empty(), "unexpected warnings: {warnings:?}");
+    Ok(())
+}
+
+#[test]
+fn lint_operation_ids() -> anyhow::Result<()> {
+    let code = r#"
+        res /a on (get -> <status=200, {}> `description: "ok"`) `operationId: read`;
+        res /b on (get, put -> <status=200, {}> `description: "ok"`) `operationId: read`;
+    "#;
+    let warnings = lint_code(code)?;
+    assert_eq!(warnings.len(), 2, "expected two duplicates: {warnings:?}");
+    assert!(warnings
+        .iter()
+        .all(|w| matches!(w.kind, Kind::DuplicateOperationId)));
+    assert_eq!(
+        warnings[0].to_string(),
+        "duplicate operation id: operation id read of get /b is already used by get /a"
+    );
+    let span = warnings[0].span().expect("expected a span");
+    assert_eq!(span.start(), code.find("get, put").unwrap());
+    let [(first, label)] = warnings[0].labels() else {
+        panic!("expected a label")
+    };
+    assert_eq!(first.start(), code.find("get ->").unwrap());
+    assert_eq!(label, "first used here");
+    Ok(())
+}
+
+#[test]
+fn lint_path_parameters() -> anyhow::Result<()> {
+    let warnings = lint_code(
+        r#"
+        res /a/{ 'id str }/b/{ 'id str } on get -> <status=200, {}> `description: "ok"`;
+        res /c/{ 'id str }?{ 'id str } on get -> <status=200, {}> `description: "ok"`;
+        # webhook: created
+        let hook = /d/{ 'key str } on post -> <status=200, {}> `description: "ok"`;
+        res hook;
+    "#,
+    )?;
+    assert_eq!(warnings.len(), 3, "unexpected warnings: {warnings:?}");
+    assert!(warnings
+        .iter()
+        .all(|w| matches!(w.kind, Kind::PathParameter)));
+    assert_eq!(
+        warnings[0].to_string(),
+        "invalid path parameter: path parameter id appears more than once in /a/{id}/b/{id}"
+    );
+    assert_eq!(
+        warnings[1].to_string(),
+        "invalid path parameter: query parameter id has the name of a path parameter of /c/{id}"
+    );
+    assert_eq!(
+        warnings[2].to_string(),
+        "invalid path parameter: path parameter key of webhook created is not present in any URI"
+    );
+    Ok(())
+}
+
+#[test]
+fn lint_responses() -> anyhow::Result<()> {
+    let warnings = lint_code(
+        r#"
+        res /a on get -> <status=200, {}> `description: "ok"`
+                      :: <status=404, {}>
+                      :: <status=500, media="application/xml">;
+    "#,
+    )?;
+    let kinds = warnings
+        .iter()
+        .map(|w| w.kind.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        kinds,
+        vec![
+            "unreachable media type",
+            "missing description",
+            "missing description"
+        ]
+    );
+    assert_eq!(
+        warnings[1].to_string(),
+        "missing description: response 404 of get /a has no description"
+    );
+    Ok(())
+}
+
+#[test]
+fn lint_unused() -> anyhow::Result<()> {
+    let warnings = lint_code(
+        r#"
+        let used = { 'id int };
+        let unused = { 'name str };
+        let f x = x;
+        res /a on get -> <status=200, f used> `description: "ok"`;
+    "#,
+    )?;
+    assert_eq!(warnings.len(), 1, "unexpected warnings: {warnings:?}");
+    let err = &warnings[0];
+    assert!(matches!(err.kind, Kind::UnusedDeclaration));
+    assert_eq!(
+        err.to_string(),
+        "unused declaration: unused is declared but never used"
+    );
+    assert!(err.span().is_some());
+    Ok(())
+}
@@ -1,4 +1,5 @@
-use crate::errors::{Error, Kind};
+use crate::errors::{Error, Kind, Result};
+use crate::library;
 use crate::tree::Tree;
 use oal_model::grammar::AbstractSyntaxNode;
 use oal_model::locator::Locator;
@@ -6,11 +7,14 @@ use oal_model::span::Span;
 use oal_syntax::parser::Program;
 use petgraph::algo::toposort;
 use petgraph::prelude::*;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+
 #[derive(Debug)]
 pub struct ModuleSet {
     base: Locator,
     mods: HashMap<Locator, Tree>,
+    /// The locators of the imports resolved by the loader, by importing module and import path.
+    resolved: HashMap<(Locator, String), Locator>,
 }
 
 impl ModuleSet {
@@ -18,6 +22,7 @@ impl ModuleSet {
         ModuleSet {
             base: main.locator().clone(),
             mods: HashMap::from([(main.locator().clone(), main)]),
+            resolved: HashMap::new(),
         }
     }
 
@@ -52,6 +57,72 @@ impl ModuleSet {
     pub fn modules(&self) -> impl Iterator<Item = &Tree> {
         self.mods.values()
     }
+
+    /// Returns the modules in locator order, e.g. to report diagnostics deterministically.
+    pub fn sorted_modules(&self) -> Vec<&Tree> {
+        let mut mods = self.modules().collect::<Vec<_>>();
+        mods.sort_by(|a, b| a.locator().url().cmp(b.locator().url()));
+        mods
+    }
+
+    /// Returns the locator of a module imported from the given module,
+    /// as resolved by the loader or else relative to the importing module.
+    pub fn resolve_import(&self, loc: &Locator, import: &str) -> Result<Locator> {
+        match self.resolved.get(&(loc.clone(), import.to_owned())) {
+            Some(target) => Ok(target.clone()),
+            None => import_locator(loc, import),
+        }
+    }
+
+    /// Returns the locators of the modules imported by the given module, in import order.
+    pub fn imports(&self, loc: &Locator) -> Vec<Locator> {
+        let Some(module) = self.get(loc) else {
+            return Vec::new();
+        };
+        let prog = Program::cast(module.root()).expect("expected a program");
+        prog.imports()
+            .filter_map(|import| self.resolve_import(loc, import.module()).ok())
+            .filter(|target| self.mods.contains_key(target))
+            .collect()
+    }
+
+    /// Returns the importers of each module, in locator order.
+    fn reverse_imports(&self) -> HashMap<Locator, Vec<Locator>> {
+        let mut locs = self.locators().collect::<Vec<_>>();
+        locs.sort_by(|a, b| a.url().cmp(b.url()));
+        let mut reverse = HashMap::<Locator, Vec<Locator>>::new();
+        for loc in locs {
+            for target in self.imports(loc) {
+                let importers = reverse.entry(target).or_default();
+                if !importers.contains(loc) {
+                    importers.push(loc.clone());
+                }
+            }
+        }
+        reverse
+    }
+
+    /// Returns the locators of the modules importing the given module, in locator order.
+    pub fn importers(&self, loc: &Locator) -> Vec<Locator> {
+        self.reverse_imports().remove(loc).unwrap_or_default()
+    }
+
+    /// Returns the locators of the modules to recompile after a change of the given module,
+    /// i.e. the module itself followed by all the modules importing it directly or transitively.
+    pub fn invalidated(&self, loc: &Locator) -> Vec<Locator> {
+        let reverse = self.reverse_imports();
+        let mut affected = vec![loc.clone()];
+        let mut i = 0;
+        while let Some(l) = affected.get(i) {
+            for importer in reverse.get(l).into_iter().flatten() {
+                if !affected.contains(importer) {
+                    affected.push(importer.clone());
+                }
+            }
+            i += 1;
+        }
+        affected
+    }
 }
 
 pub trait Loader<E: From<Error>> {
@@ -63,71 +134,353 @@ pub trait Loader<E: From<Error>> {
     fn parse(&mut self, loc: Locator, input: String) -> std::result::Result<Tree, E>;
     /// Compiles a module.
     fn compile(&mut self, mods: &ModuleSet, loc: &Locator) -> std::result::Result<(), E>;
+    /// Returns the locator of a module imported from the given module.
+    ///
+    /// By default, imports are resolved relative to the importing module.
+    fn resolve(&mut self, loc: &Locator, import: &str) -> Result<Locator> {
+        import_locator(loc, import)
+    }
+    /// Returns the canonical locator of a valid source file.
+    ///
+    /// Distinct locators of the same file, e.g. on case-insensitive file systems,
+    /// must have the same canonical locator. By default, locators are canonical.
+    fn canonicalize(&mut self, loc: &Locator) -> Locator {
+        loc.clone()
+    }
+}
+
+/// Returns the locator of a module imported from the given module.
+pub fn import_locator(loc: &Locator, import: &str) -> Result<Locator> {
+    match library::locator(import) {
+        Some(target) => Ok(target),
+        None => {
+            let target = loc.join(import)?;
+            check_import(loc, &target)?;
+            Ok(target)
+        }
+    }
+}
+
+/// Checks that a module can import another, i.e. that remote modules, fetched over HTTPS,
+/// only import other remote modules and the standard modules, rather than local files.
+pub fn check_import(loc: &Locator, target: &Locator) -> Result<()> {
+    const REMOTE_SCHEME: &str = "https";
+    if loc.url().scheme() == REMOTE_SCHEME
+        && target.url().scheme() != REMOTE_SCHEME
+        && library::source(target).is_none()
+    {
+        let msg = format!("remote module {loc} can only import modules over HTTPS");
+        return Err(Error::new(Kind::InvalidModule(target.clone()), msg));
+    }
+    Ok(())
+}
+
+/// Limits on the resources used to load a program, to keep interactive tools responsive.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LoadLimits {
+    /// The maximum size of a module source in bytes.
+    pub max_input_size: Option<usize>,
+    /// The maximum number of modules, including the main one.
+    pub max_modules: Option<usize>,
+}
+
+impl LoadLimits {
+    /// The default limits of editors and browsers.
+    pub const INTERACTIVE: LoadLimits = LoadLimits {
+        max_input_size: Some(1 << 20),
+        max_modules: Some(256),
+    };
+
+    /// The limits of programs from untrusted sources, e.g. submitted to a public service.
+    pub const UNTRUSTED: LoadLimits = LoadLimits {
+        max_input_size: Some(256 << 10),
+        max_modules: Some(32),
+    };
+
+    /// Returns the stricter of each limit.
+    pub fn stricter(&self, other: &LoadLimits) -> LoadLimits {
+        let min = |a: Option<usize>, b: Option<usize>| match (a, b) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        LoadLimits {
+            max_input_size: min(self.max_input_size, other.max_input_size),
+            max_modules: min(self.max_modules, other.max_modules),
+        }
+    }
+
+    fn check_input(&self, loc: &Locator, input: &str, span: Option<Span>) -> Result<()> {
+        match self.max_input_size {
+            Some(max) if input.len() > max => Err(Error::new(
+                Kind::ResourceExceeded,
+                format!(
+                    "{loc} has {} bytes, more than the limit of {max} bytes",
+                    input.len()
+                ),
+            )
+            .at(span.or_else(|| Some(Span::new(loc.clone(), 0..0))))),
+            _ => Ok(()),
+        }
+    }
+
+    fn check_modules(&self, count: usize, span: Option<Span>) -> Result<()> {
+        match self.max_modules {
+            Some(max) if count > max => Err(Error::new(
+                Kind::ResourceExceeded,
+                format!("the program imports more than the limit of {max} modules"),
+            )
+            .at(span)),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// The graph of module dependencies, from imported modules to their importers,
+/// with the spans of the import statements.
+type DependencyGraph = Graph<Locator, Option<Span>>;
+
+/// Returns the import statements along a cycle of module dependencies through the given module,
+/// starting from the import of the module itself.
+fn import_cycle(graph: &DependencyGraph, start: NodeIndex) -> Vec<EdgeIndex> {
+    // Searches breadth-first for the shortest chain of imports leading back to the module.
+    let mut parents = HashMap::new();
+    let mut queue = VecDeque::from([start]);
+    while let Some(n) = queue.pop_front() {
+        for edge in graph.edges_directed(n, Incoming) {
+            let imported = edge.source();
+            if imported == start {
+                let mut chain = vec![edge.id()];
+                let mut importer = n;
+                while importer != start {
+                    let e = parents[&importer];
+                    chain.push(e);
+                    importer = graph.edge_endpoints(e).unwrap().1;
+                }
+                chain.reverse();
+                return chain;
+            }
+            if imported != n && !parents.contains_key(&imported) {
+                parents.insert(imported, edge.id());
+                queue.push_back(imported);
+            }
+        }
+    }
+    Vec::new()
+}
+
+/// Returns the error for a cycle of module dependencies through the given module,
+/// pointing at each of the import statements along the cycle.
+fn import_cycle_error(graph: &DependencyGraph, start: NodeIndex) -> Error {
+    let mut chain = import_cycle(graph, start);
+    // Starts from the module loaded first, e.g. the main one, for the chain to read as loaded.
+    let importer = |e: &EdgeIndex| graph.edge_endpoints(*e).unwrap().1;
+    if let Some(first) = chain
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, e)| importer(e))
+        .map(|(i, _)| i)
+    {
+        chain.rotate_left(first);
+    }
+    let start = chain.first().map_or(start, importer);
+    let start_loc = graph.node_weight(start).unwrap();
+    let mut locs = vec![start_loc.to_string()];
+    let mut spans = Vec::new();
+    for e in chain.iter() {
+        let (imported, importer) = graph.edge_endpoints(*e).unwrap();
+        let importer = graph.node_weight(importer).unwrap();
+        locs.push(graph.node_weight(imported).unwrap().to_string());
+        spans.push(
+            graph
+                .edge_weight(*e)
+                .unwrap()
+                .clone()
+                .unwrap_or_else(|| Span::new(importer.clone(), 0..0)),
+        );
+    }
+    let msg = format!("module imports form a cycle: {}", locs.join(" -> "));
+    let mut spans = spans.into_iter();
+    let first = spans
+        .next()
+        .unwrap_or_else(|| Span::new(start_loc.clone(), 0..0));
+    let mut err = Error::new(Kind::CycleDetected, msg).at(Some(first));
+    for span in spans {
+        err = err.with_label(Some(span), "continues through this import");
+    }
+    err
 }
 
 /// Loads and compiles the set of modules for a main program.
 pub fn load<E, L>(loader: &mut L, base: &Locator) -> std::result::Result<ModuleSet, E>
+where
+    E: From<Error>,
+    L: Loader<E>,
+{
+    load_with_limits(loader, base, &LoadLimits::default())
+}
+
+/// Compiled modules kept across programs, e.g. to build several programs sharing imports.
+#[derive(Debug, Default)]
+pub struct ModuleCache {
+    mods: HashMap<Locator, Tree>,
+}
+
+impl ModuleCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keeps the modules of a program for the programs loaded next.
+    pub fn release(&mut self, mods: ModuleSet) {
+        self.mods.extend(mods.mods);
+    }
+
+    pub fn len(&self) -> usize {
+        self.mods.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.mods.is_empty()
+    }
+}
+
+/// Loads and compiles the set of modules for a main program, within the given limits.
+pub fn load_with_limits<E, L>(
+    loader: &mut L,
+    base: &Locator,
+    limits: &LoadLimits,
+) -> std::result::Result<ModuleSet, E>
+where
+    E: From<Error>,
+    L: Loader<E>,
+{
+    load_cached(loader, base, limits, &mut ModuleCache::new())
+}
+
+/// Loads and compiles the set of modules for a main program, within the given limits,
+/// taking the modules already compiled for a previous program from the cache.
+///
+/// The modules taken from the cache are neither loaded nor compiled again.
+/// They can be returned to the cache with [`ModuleCache::release`].
+pub fn load_cached<E, L>(
+    loader: &mut L,
+    base: &Locator,
+    limits: &LoadLimits,
+    cache: &mut ModuleCache,
+) -> std::result::Result<ModuleSet, E>
 where
     E: From<Error>,
     L: Loader<E>,
 {
     let mut deps = HashMap::new();
-    let mut graph = Graph::new();
+    let mut graph = DependencyGraph::new();
     let mut queue = Vec::new();
+    // The modules compiled for a previous program.
+    let mut reused = Vec::new();
 
-    let input = loader.load(base)?;
-    let main = loader.parse(base.clone(), input)?;
+    let main = match cache.mods.remove(base) {
+        Some(tree) => {
+            reused.push(base.clone());
+            tree
+        }
+        None => {
+            let input = loader.load(base)?;
+            limits.check_input(base, &input, None)?;
+            loader.parse(base.clone(), input)?
+        }
+    };
     let mut mods = ModuleSet::new(main);
 
     let root = graph.add_node(base.clone());
     deps.insert(base.clone(), root);
     queue.push(root);
 
+    // The first locator and import of each canonical file.
+    let mut canonicals = HashMap::new();
+    canonicals.insert(loader.canonicalize(base), (base.clone(), None));
+
     while let Some(n) = queue.pop() {
         let loc = graph.node_weight(n).unwrap();
         let module = mods.get(loc).unwrap();
 
         let mut imports = Vec::new();
+        let mut resolved = Vec::new();
         let prog = Program::cast(module.root()).expect("expected a program");
         for import in prog.imports() {
             let span = import.node().span();
-            let target = loc
-                .join(import.module())
-                .map_err(|err| Error::from(err).at(span.clone()))?;
-            if !loader.is_valid(&target) {
+            let target = loader
+                .resolve(loc, import.module())
+                .map_err(|err| err.at(span.clone()))?;
+            resolved.push((import.module().to_owned(), target.clone()));
+            // Standard modules are embedded rather than loaded.
+            if library::source(&target).is_none() && !loader.is_valid(&target) {
                 return Err(
                     Error::new(Kind::InvalidModule(target), "cannot load import")
                         .at(span)
                         .into(),
                 );
             }
-            imports.push(target);
+            if library::source(&target).is_none() {
+                let canonical = loader.canonicalize(&target);
+                match canonicals.get(&canonical) {
+                    Some((first, first_span)) if *first != target => {
+                        let msg = format!("{target} is the same file as {first}");
+                        let label = first_span
+                            .clone()
+                            .or_else(|| Some(Span::new(first.clone(), 0..0)));
+                        return Err(Error::new(Kind::ModuleCollision, msg)
+                            .at(span)
+                            .with_label(label, "first loaded here")
+                            .into());
+                    }
+                    Some(_) => {}
+                    None => {
+                        canonicals.insert(canonical, (target.clone(), span.clone()));
+                    }
+                }
+            }
+            imports.push((target, span));
         }
 
-        for import in imports {
+        for (path, target) in resolved {
+            mods.resolved.insert((loc.clone(), path), target);
+        }
+
+        for (import, span) in imports {
             if let Some(m) = deps.get(&import) {
-                graph.add_edge(*m, n, ());
+                graph.add_edge(*m, n, span);
             } else {
-                let input = loader.load(&import)?;
-                let module = loader.parse(import.clone(), input)?;
+                limits.check_modules(mods.len() + 1, span.clone())?;
+                let module = match cache.mods.remove(&import) {
+                    Some(tree) => {
+                        reused.push(import.clone());
+                        tree
+                    }
+                    None => {
+                        let input = match library::source(&import) {
+                            Some(code) => code.to_owned(),
+                            None => loader.load(&import)?,
+                        };
+                        limits.check_input(&import, &input, span.clone())?;
+                        loader.parse(import.clone(), input)?
+                    }
+                };
                 mods.insert(module);
 
                 let m = graph.add_node(import.clone());
-                graph.add_edge(m, n, ());
+                graph.add_edge(m, n, span);
                 deps.insert(import, m);
                 queue.push(m);
             }
         }
     }
 
-    let topo = toposort(&graph, None).map_err(|err| {
-        let loc = graph.node_weight(err.node_id()).unwrap();
-        Error::new(Kind::CycleDetected, "cycle in module dependencies")
-            .at(Some(Span::new(loc.clone(), 0..0)))
-    })?;
+    let topo = toposort(&graph, None).map_err(|err| import_cycle_error(&graph, err.node_id()))?;
     for node in topo {
         let loc = graph.node_weight(node).unwrap();
-        loader.compile(&mods, loc)?;
+        if !reused.contains(loc) {
+            loader.compile(&mods, loc)?;
+        }
     }
 
     Ok(mods)
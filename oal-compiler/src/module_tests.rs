@@ -1,5 +1,7 @@
 use crate::errors::{Error, Kind};
-use crate::module::{load, Loader, ModuleSet};
+use crate::module::{
+    import_locator, load, load_cached, load_with_limits, LoadLimits, Loader, ModuleCache, ModuleSet,
+};
 use crate::tree::Tree;
 use oal_model::locator::Locator;
 use std::cell::RefCell;
@@ -48,13 +50,21 @@ fn module_cycle() -> anyhow::Result<()> {
     };
 
     let err = load(&mut ctx, &base).expect_err("expected an error");
+    let err = err
+        .downcast_ref::<Error>()
+        .expect("expected compiler error");
 
-    assert!(matches!(
-        err.downcast_ref::<Error>()
-            .expect("expected compiler error")
-            .kind,
-        Kind::CycleDetected
-    ));
+    assert!(matches!(err.kind, Kind::CycleDetected));
+    assert_eq!(
+        err.to_string(),
+        "cycle detected: module imports form a cycle: \
+        file:///base.oal -> file:///module.oal -> file:///base.oal"
+    );
+    let span = err.span().expect("expected a span");
+    assert_eq!(*span.locator(), base);
+    assert_eq!(span.range(), 0..17);
+    assert_eq!(err.labels().len(), 1);
+    assert_eq!(*err.labels()[0].0.locator(), ctx.module);
 
     Ok(())
 }
@@ -174,3 +184,322 @@ fn module_invalid() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+struct ContextLibrary {
+    base: Locator,
+}
+
+impl Loader<anyhow::Error> for ContextLibrary {
+    fn is_valid(&mut self, loc: &Locator) -> bool {
+        *loc == self.base
+    }
+
+    fn load(&mut self, loc: &Locator) -> anyhow::Result<String> {
+        assert_eq!(*loc, self.base, "standard modules should not be loaded");
+        let code = r#"
+            use "std/pagination" as pg;
+            use "std/problem+json" as p;
+            res /items?{ pg.cursor, pg.limit } on get -> <status=200, pg.page str> :: p.errors;
+            res /users on get -> <status=200, pg.page str> :: <status=4XX, media=p.media_type, p.@problem>;
+            "#;
+        Ok(code.to_owned())
+    }
+
+    fn parse(&mut self, loc: Locator, input: String) -> anyhow::Result<Tree> {
+        let (tree, errs) = oal_syntax::parse(loc, input);
+        assert!(errs.is_empty());
+        let tree = tree.expect("parsing failed");
+        Ok(tree)
+    }
+
+    fn compile(&mut self, mods: &ModuleSet, loc: &Locator) -> anyhow::Result<()> {
+        crate::compile::compile(mods, loc)?;
+        Ok(())
+    }
+}
+
+#[test]
+fn module_library() -> anyhow::Result<()> {
+    let base = Locator::try_from("file:///base.oal")?;
+    let mut ctx = ContextLibrary { base: base.clone() };
+
+    let mods = load(&mut ctx, &base)?;
+    assert_eq!(mods.len(), 3);
+
+    let spec = crate::eval::eval(&mods)?;
+    assert!(spec
+        .refs
+        .contains_key(&oal_syntax::atom::Ident::from("@problem")));
+
+    Ok(())
+}
+
+/// A case-insensitive file system with a base module and a common module.
+struct ContextCollision(&'static str);
+
+impl Loader<anyhow::Error> for ContextCollision {
+    fn is_valid(&mut self, _loc: &Locator) -> bool {
+        true
+    }
+
+    fn load(&mut self, loc: &Locator) -> anyhow::Result<String> {
+        let code = match loc.url().path().to_lowercase().as_str() {
+            "/base.oal" => self.0,
+            "/common.oal" => "let a = {};",
+            "/lib/module.oal" => r#"use "../Common.oal" as c;"#,
+            _ => unreachable!(),
+        };
+        Ok(code.to_owned())
+    }
+
+    fn parse(&mut self, loc: Locator, input: String) -> anyhow::Result<Tree> {
+        let (tree, errs) = oal_syntax::parse(loc, input);
+        assert!(errs.is_empty());
+        let tree = tree.expect("parsing failed");
+        Ok(tree)
+    }
+
+    fn compile(&mut self, _mods: &ModuleSet, _loc: &Locator) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn canonicalize(&mut self, loc: &Locator) -> Locator {
+        let lower = loc.url().as_str().to_lowercase();
+        Locator::try_from(lower.as_str()).unwrap()
+    }
+}
+
+#[test]
+fn module_collision() -> anyhow::Result<()> {
+    let base = Locator::try_from("file:///base.oal")?;
+
+    let mut ctx = ContextCollision(r#"use "common.oal" as a; use "./common.oal" as b;"#);
+    let mods = load(&mut ctx, &base)?;
+    assert_eq!(mods.len(), 2);
+
+    for code in [
+        r#"use "common.oal" as a; use "Common.oal" as b;"#,
+        r#"use "common.oal" as a; use "lib/module.oal" as m;"#,
+        r#"use "BASE.oal" as b;"#,
+    ] {
+        let mut ctx = ContextCollision(code);
+        let err = load(&mut ctx, &base).expect_err("expected an error");
+        let err = err
+            .downcast_ref::<Error>()
+            .expect("expected compiler error");
+        assert!(matches!(err.kind, Kind::ModuleCollision), "{code}");
+        assert!(err.span().is_some());
+        assert_eq!(
+            err.labels().len(),
+            1,
+            "expected a label on the first import"
+        );
+    }
+
+    Ok(())
+}
+
+/// A program of a base module importing a chain of modules.
+struct ContextChain {
+    modules: usize,
+}
+
+impl Loader<anyhow::Error> for ContextChain {
+    fn is_valid(&mut self, _loc: &Locator) -> bool {
+        true
+    }
+
+    fn load(&mut self, loc: &Locator) -> anyhow::Result<String> {
+        let path = loc.url().path();
+        let n = path
+            .trim_start_matches("/m")
+            .trim_end_matches(".oal")
+            .parse::<usize>()
+            .unwrap_or(0);
+        let code = if n + 1 < self.modules {
+            format!(r#"use "m{}.oal"; // padding"#, n + 1)
+        } else {
+            String::new()
+        };
+        Ok(code)
+    }
+
+    fn parse(&mut self, loc: Locator, input: String) -> anyhow::Result<Tree> {
+        let (tree, errs) = oal_syntax::parse(loc, input);
+        assert!(errs.is_empty());
+        let tree = tree.expect("parsing failed");
+        Ok(tree)
+    }
+
+    fn compile(&mut self, _mods: &ModuleSet, _loc: &Locator) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn module_load_limits() -> anyhow::Result<()> {
+    let base = Locator::try_from("file:///m0.oal")?;
+    let limits = LoadLimits {
+        max_input_size: Some(32),
+        max_modules: Some(3),
+    };
+
+    let mods = load_with_limits(&mut ContextChain { modules: 3 }, &base, &limits)?;
+    assert_eq!(mods.len(), 3);
+
+    let err = load_with_limits(&mut ContextChain { modules: 4 }, &base, &limits)
+        .expect_err("expected an error");
+    let err = err
+        .downcast_ref::<Error>()
+        .expect("expected compiler error");
+    assert!(matches!(err.kind, Kind::ResourceExceeded));
+    assert!(err.span().is_some(), "expected the span of the import");
+
+    let limits = LoadLimits {
+        max_input_size: Some(8),
+        max_modules: None,
+    };
+    let err = load_with_limits(&mut ContextChain { modules: 2 }, &base, &limits)
+        .expect_err("expected an error");
+    let err = err
+        .downcast_ref::<Error>()
+        .expect("expected compiler error");
+    assert!(matches!(err.kind, Kind::ResourceExceeded));
+    assert!(err.to_string().contains("8 bytes"));
+
+    let mods = load(&mut ContextChain { modules: 10 }, &base)?;
+    assert_eq!(mods.len(), 10);
+
+    Ok(())
+}
+
+/// A program of a base module importing two modules, both importing a shared one.
+struct ContextDiamond;
+
+impl Loader<anyhow::Error> for ContextDiamond {
+    fn is_valid(&mut self, _loc: &Locator) -> bool {
+        true
+    }
+
+    fn load(&mut self, loc: &Locator) -> anyhow::Result<String> {
+        let code = match loc.url().path() {
+            "/base.oal" => r#"use "left.oal"; use "right.oal"; use "std/pagination";"#,
+            "/left.oal" | "/right.oal" => r#"use "shared.oal";"#,
+            _ => "",
+        };
+        Ok(code.to_owned())
+    }
+
+    fn parse(&mut self, loc: Locator, input: String) -> anyhow::Result<Tree> {
+        let (tree, errs) = oal_syntax::parse(loc, input);
+        assert!(errs.is_empty());
+        let tree = tree.expect("parsing failed");
+        Ok(tree)
+    }
+
+    fn compile(&mut self, _mods: &ModuleSet, _loc: &Locator) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn module_importers() -> anyhow::Result<()> {
+    let loc = |path: &str| Locator::try_from(format!("file:///{path}").as_str());
+    let (base, left, right, shared) = (
+        loc("base.oal")?,
+        loc("left.oal")?,
+        loc("right.oal")?,
+        loc("shared.oal")?,
+    );
+    let mods = load(&mut ContextDiamond, &base)?;
+
+    assert_eq!(mods.imports(&left), std::slice::from_ref(&shared));
+    assert_eq!(mods.imports(&base)[..2], [left.clone(), right.clone()]);
+    assert_eq!(mods.importers(&shared), [left.clone(), right.clone()]);
+    assert!(mods.importers(&base).is_empty());
+
+    assert_eq!(
+        mods.invalidated(&shared),
+        [shared.clone(), left.clone(), right.clone(), base.clone()]
+    );
+    assert_eq!(mods.invalidated(&left), [left, base.clone()]);
+    assert_eq!(mods.invalidated(&base), [base]);
+
+    Ok(())
+}
+
+/// The diamond program, counting the modules loaded and compiled.
+#[derive(Default)]
+struct ContextCount {
+    loaded: Vec<String>,
+    compiled: Vec<String>,
+}
+
+impl Loader<anyhow::Error> for ContextCount {
+    fn is_valid(&mut self, _loc: &Locator) -> bool {
+        true
+    }
+
+    fn load(&mut self, loc: &Locator) -> anyhow::Result<String> {
+        self.loaded.push(loc.url().path().to_owned());
+        match loc.url().path() {
+            "/other.oal" => Ok(r#"use "shared.oal";"#.to_owned()),
+            _ => ContextDiamond.load(loc),
+        }
+    }
+
+    fn parse(&mut self, loc: Locator, input: String) -> anyhow::Result<Tree> {
+        ContextDiamond.parse(loc, input)
+    }
+
+    fn compile(&mut self, _mods: &ModuleSet, loc: &Locator) -> anyhow::Result<()> {
+        self.compiled.push(loc.url().path().to_owned());
+        Ok(())
+    }
+}
+
+#[test]
+fn module_cache() -> anyhow::Result<()> {
+    let loc = |path: &str| Locator::try_from(format!("file:///{path}").as_str());
+    let limits = LoadLimits::default();
+    let mut loader = ContextCount::default();
+    let mut cache = ModuleCache::new();
+
+    let mods = load_cached(&mut loader, &loc("base.oal")?, &limits, &mut cache)?;
+    assert_eq!(loader.loaded.len(), 4);
+    assert_eq!(loader.compiled.len(), 5);
+    cache.release(mods);
+    assert_eq!(cache.len(), 5);
+
+    loader = ContextCount::default();
+    let mods = load_cached(&mut loader, &loc("other.oal")?, &limits, &mut cache)?;
+    assert_eq!(loader.loaded, ["/other.oal"]);
+    assert_eq!(loader.compiled, ["/other.oal"]);
+    assert_eq!(mods.len(), 2);
+    cache.release(mods);
+
+    loader = ContextCount::default();
+    let mods = load_cached(&mut loader, &loc("left.oal")?, &limits, &mut cache)?;
+    assert!(loader.loaded.is_empty());
+    assert!(loader.compiled.is_empty());
+    assert_eq!(mods.len(), 2);
+
+    Ok(())
+}
+
+#[test]
+fn module_remote_import() -> anyhow::Result<()> {
+    let remote = Locator::try_from("https://example.com/api/main.oal")?;
+
+    let loc = import_locator(&remote, "common.oal")?;
+    assert_eq!(loc.url().as_str(), "https://example.com/api/common.oal");
+    assert!(import_locator(&remote, "std/pagination").is_ok());
+
+    let err = import_locator(&remote, "file:///etc/secret.oal").expect_err("expected an error");
+    assert!(matches!(err.kind, Kind::InvalidModule(_)));
+
+    let local = Locator::try_from("file:///api/main.oal")?;
+    assert!(import_locator(&local, "https://example.com/api/common.oal").is_ok());
+
+    Ok(())
+}
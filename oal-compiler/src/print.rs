@@ -0,0 +1,467 @@
+//! A stable, human-readable text format of the evaluated specification, e.g. for debugging.
+//!
+//! Schemas are printed with the syntax of the language, followed by their annotations,
+//! and relations as a tree of transfers, e.g.:
+//! ```text
+//! ref @user = { 'name! str } `description: "a user"`
+//! rel /users/{ 'id int }
+//!   get
+//!     response: <status=200, media=application/json, @user>
+//! ```
+//! Fields without a value are omitted, and everything else is printed in the order of the specification.
+
+use crate::annotation::EXAMPLE_TAG;
+use crate::query::{method_label, status};
+use crate::spec::{
+    AdditionalProperties, ApiKeyLocation, Array, Content, Example, Examples, Extensions, Info,
+    OAuthFlowKind, Object, PrimBoolean, PrimInteger, PrimNumber, PrimString, Property, Reference,
+    Relation, Schema, SchemaExpr, SecurityScheme, SecuritySchemeKind, Spec, Transfer, Translations,
+    Uri, VariadicOp,
+};
+use oal_syntax::atom;
+use serde_yaml::Value;
+use std::fmt::{Debug, Display, Formatter, Result};
+
+/// Formats a YAML value on a single line.
+fn inline_value(value: &Value) -> String {
+    match value {
+        Value::Null => "null".to_owned(),
+        Value::Bool(b) => b.to_string(),
+        Value::Number(n) => n.to_string(),
+        Value::String(s) => format!("{s:?}"),
+        Value::Sequence(items) => {
+            let items = items.iter().map(inline_value).collect::<Vec<_>>();
+            format!("[{}]", items.join(", "))
+        }
+        Value::Mapping(m) => {
+            let entries = m
+                .iter()
+                .map(|(k, v)| format!("{}: {}", inline_value(k), inline_value(v)))
+                .collect::<Vec<_>>();
+            format!("{{{}}}", entries.join(", "))
+        }
+        Value::Tagged(t) => format!("{} {}", t.tag, inline_value(&t.value)),
+    }
+}
+
+/// The annotations of an element, printed after it between backticks.
+#[derive(Default)]
+struct Annotations(Vec<String>);
+
+impl Annotations {
+    fn add<T: Debug>(&mut self, key: &str, value: Option<T>) -> &mut Self {
+        if let Some(value) = value {
+            self.0.push(format!("{key}: {value:?}"));
+        }
+        self
+    }
+
+    fn add_list<T: Debug>(&mut self, key: &str, values: &[T]) -> &mut Self {
+        self.add(key, Some(values).filter(|v| !v.is_empty()))
+    }
+
+    fn add_values(&mut self, values: &Extensions) -> &mut Self {
+        for (key, value) in values.iter() {
+            self.add(key, Some(Raw(inline_value(value))));
+        }
+        self
+    }
+
+    fn add_translations(&mut self, key: &str, translations: &Translations) -> &mut Self {
+        for (lang, text) in translations.iter() {
+            self.add(&format!("{key}@{lang}"), Some(text));
+        }
+        self
+    }
+}
+
+impl Display for Annotations {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        if self.0.is_empty() {
+            Ok(())
+        } else {
+            write!(f, " `{}`", self.0.join(", "))
+        }
+    }
+}
+
+impl Display for PrimNumber {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let mut ann = Annotations::default();
+        ann.add("minimum", self.minimum)
+            .add("maximum", self.maximum)
+            .add("exclusiveMinimum", self.exclusive_minimum.then_some(true))
+            .add("exclusiveMaximum", self.exclusive_maximum.then_some(true))
+            .add("multipleOf", self.multiple_of)
+            .add("example", self.example)
+            .add_list("enum", &self.enumeration);
+        write!(f, "num{ann}")
+    }
+}
+
+impl Display for PrimInteger {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let mut ann = Annotations::default();
+        ann.add("minimum", self.minimum)
+            .add("maximum", self.maximum)
+            .add("exclusiveMinimum", self.exclusive_minimum.then_some(true))
+            .add("exclusiveMaximum", self.exclusive_maximum.then_some(true))
+            .add("multipleOf", self.multiple_of)
+            .add("example", self.example)
+            .add_list("enum", &self.enumeration);
+        write!(f, "int{ann}")
+    }
+}
+
+impl Display for PrimString {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let mut ann = Annotations::default();
+        ann.add("pattern", self.pattern.as_ref())
+            .add_list("enum", &self.enumeration)
+            .add("format", self.format.as_ref())
+            .add("example", self.example.as_ref())
+            .add("minLength", self.min_length)
+            .add("maxLength", self.max_length);
+        write!(f, "str{ann}")
+    }
+}
+
+impl Display for PrimBoolean {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let mut ann = Annotations::default();
+        ann.add_list("enum", &self.enumeration);
+        write!(f, "bool{ann}")
+    }
+}
+
+impl Display for Uri {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        if self.path.is_empty() {
+            write!(f, "uri")?;
+        } else {
+            write!(f, "{}", self.pattern_with(|p| format!("{{ {p} }}")))?;
+        }
+        if let Some(params) = &self.params {
+            write!(f, "?{params}")?;
+        }
+        let mut ann = Annotations::default();
+        ann.add("example", self.example.as_ref());
+        write!(f, "{ann}")
+    }
+}
+
+impl Display for Object {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let mut ann = Annotations::default();
+        ann.add(
+            "additionalProperties",
+            self.additional_props.as_ref().map(|a| match a {
+                AdditionalProperties::Allowed(b) => Raw(b.to_string()),
+                AdditionalProperties::Schema(s) => Raw(inline_value(s)),
+            }),
+        )
+        .add("minProperties", self.min_props)
+        .add("maxProperties", self.max_props);
+        if self.props.is_empty() {
+            return write!(f, "{{}}{ann}");
+        }
+        let props = self.props.iter().map(ToString::to_string);
+        write!(f, "{{ {} }}{ann}", props.collect::<Vec<_>>().join(", "))
+    }
+}
+
+impl Display for Array {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let mut ann = Annotations::default();
+        ann.add("minItems", self.min_items)
+            .add("maxItems", self.max_items)
+            .add("uniqueItems", self.unique_items);
+        write!(f, "[{}]{ann}", self.item)
+    }
+}
+
+impl Display for VariadicOp {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let sep = match self.op {
+            atom::VariadicOperator::Join => " & ",
+            atom::VariadicOperator::Any => " ~ ",
+            atom::VariadicOperator::Sum => " | ",
+            atom::VariadicOperator::Range => " :: ",
+        };
+        let operands = self.schemas.iter().map(|s| match s.expr {
+            // Nested operations are parenthesized to keep their grouping.
+            SchemaExpr::Op(_) => format!("({s})"),
+            _ => s.to_string(),
+        });
+        write!(f, "{}", operands.collect::<Vec<_>>().join(sep))?;
+        if let Some(ref d) = self.discriminator {
+            let mut ann = Annotations::default();
+            ann.add("discriminator", Some(&d.property_name))
+                .add("mapping", Some(&d.mapping).filter(|m| !m.is_empty()));
+            write!(f, "{ann}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for SchemaExpr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            SchemaExpr::Num(p) => write!(f, "{p}"),
+            SchemaExpr::Str(p) => write!(f, "{p}"),
+            SchemaExpr::Bool(p) => write!(f, "{p}"),
+            SchemaExpr::Int(p) => write!(f, "{p}"),
+            SchemaExpr::Rel(r) => write!(f, "rel {}", r.uri),
+            SchemaExpr::Uri(u) => write!(f, "{u}"),
+            SchemaExpr::Array(a) => write!(f, "{a}"),
+            SchemaExpr::Object(o) => write!(f, "{o}"),
+            SchemaExpr::Op(op) => write!(f, "{op}"),
+            SchemaExpr::Ref(ident) => write!(f, "{ident}"),
+        }
+    }
+}
+
+impl Display for Schema {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let mut ann = Annotations::default();
+        ann.add("title", self.title.as_ref())
+            .add("description", self.desc.as_ref())
+            .add_translations("description", &self.translations)
+            .add("required", self.required)
+            .add("deprecated", self.deprecated)
+            .add("nullable", self.nullable)
+            .add("examples", self.examples.as_ref().map(inline_examples))
+            .add("example", self.example.as_ref().map(inline_value).map(Raw))
+            .add("default", self.default.as_ref().map(inline_value).map(Raw))
+            .add("const", self.constant.as_ref().map(inline_value).map(Raw))
+            .add("stability", self.stability.map(|s| s.as_str()))
+            .add_values(&self.extensions)
+            .add_values(&self.keywords);
+        write!(f, "{}{ann}", self.expr)
+    }
+}
+
+impl Display for Property {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let marker = match self.required {
+            Some(true) => "!",
+            Some(false) => "?",
+            None => "",
+        };
+        let mut ann = Annotations::default();
+        ann.add("description", self.desc.as_ref())
+            .add_translations("description", &self.translations)
+            .add("style", self.style)
+            .add("explode", self.explode)
+            .add("contentType", self.content_type.as_ref())
+            .add("deprecated", self.deprecated);
+        write!(f, "'{}{marker} {}{ann}", self.name, self.schema)
+    }
+}
+
+impl Display for Content {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let mut meta = Vec::new();
+        if let Some(s) = &self.status {
+            meta.push(format!("status={}", status(s)));
+        }
+        if let Some(m) = &self.media {
+            meta.push(format!("media={m}"));
+        }
+        if let Some(h) = &self.headers {
+            meta.push(format!("headers={h}"));
+        }
+        if let Some(s) = &self.schema {
+            meta.push(s.to_string());
+        }
+        let mut ann = Annotations::default();
+        ann.add("description", self.desc.as_ref())
+            .add_translations("description", &self.translations)
+            .add("examples", self.examples.as_ref().map(inline_examples))
+            .add_list("content-encoding", &self.encodings)
+            .add_values(&self.extensions);
+        write!(f, "<{}>{ann}", meta.join(", "))
+    }
+}
+
+/// Formats named examples on a single line, with the fields of example objects other than URLs and inline values.
+fn inline_examples(examples: &Examples) -> Raw {
+    let entries = examples
+        .iter()
+        .map(|(name, e)| {
+            let value = match e {
+                Example {
+                    summary: None,
+                    desc: None,
+                    value: None,
+                    external: Some(url),
+                } => format!("{url:?}"),
+                Example {
+                    summary: None,
+                    desc: None,
+                    value: Some(value),
+                    external: None,
+                } if !value.is_string() => inline_value(value),
+                e => {
+                    let fields = [
+                        ("summary", e.summary.as_ref().map(|s| format!("{s:?}"))),
+                        ("description", e.desc.as_ref().map(|s| format!("{s:?}"))),
+                        ("value", e.value.as_ref().map(inline_value)),
+                        (
+                            "externalValue",
+                            e.external.as_ref().map(|s| format!("{s:?}")),
+                        ),
+                    ];
+                    let fields = fields
+                        .into_iter()
+                        .filter_map(|(k, v)| v.map(|v| format!("{k}: {v}")))
+                        .collect::<Vec<_>>();
+                    format!("!{EXAMPLE_TAG} {{{}}}", fields.join(", "))
+                }
+            };
+            format!("{name:?}: {value}")
+        })
+        .collect::<Vec<_>>();
+    Raw(format!("{{{}}}", entries.join(", ")))
+}
+
+/// A value printed as is, e.g. an example already formatted.
+struct Raw(String);
+
+impl Debug for Raw {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Writes a transfer on the given method, indented under its relation.
+fn write_transfer(f: &mut Formatter<'_>, method: &str, xfer: &Transfer) -> Result {
+    writeln!(f, "  {method}")?;
+    let mut lines = Annotations::default();
+    lines
+        .add("summary", xfer.summary.as_ref())
+        .add("description", xfer.desc.as_ref())
+        .add_translations("description", &xfer.translations)
+        .add_list("tags", &xfer.tags)
+        .add("operationId", xfer.id.as_ref())
+        .add("deprecated", xfer.deprecated)
+        .add("stability", xfer.stability.map(|s| s.as_str()))
+        .add(
+            "servers",
+            xfer.servers
+                .as_ref()
+                .map(|s| s.iter().map(|s| s.url.as_str()).collect::<Vec<_>>()),
+        )
+        .add("security", xfer.security.as_ref())
+        .add("callbacks", Some(&xfer.callbacks).filter(|c| !c.is_empty()))
+        .add_values(&xfer.extensions);
+    for line in lines.0.iter() {
+        writeln!(f, "    {line}")?;
+    }
+    if let Some(params) = &xfer.params {
+        writeln!(f, "    params: {params}")?;
+    }
+    if xfer.domain != Content::default() {
+        writeln!(f, "    request: {}", xfer.domain)?;
+    }
+    for content in xfer.alt_domains.iter() {
+        writeln!(f, "    request: {content}")?;
+    }
+    for content in xfer.ranges.values() {
+        writeln!(f, "    response: {content}")?;
+    }
+    Ok(())
+}
+
+impl Display for Relation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let mut ann = Annotations::default();
+        ann.add("webhook", self.webhook.as_ref())
+            .add("callback", self.callback.as_ref())
+            .add("tagGroup", self.tag_group.as_ref())
+            .add_values(&self.extensions);
+        writeln!(f, "rel {}{ann}", self.uri)?;
+        for (method, xfer) in self.xfers.iter() {
+            if let Some(xfer) = xfer {
+                write_transfer(f, method_label(method), xfer)?;
+            }
+        }
+        for (method, xfer) in self.custom_xfers.iter() {
+            write_transfer(f, method, xfer)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for SecurityScheme {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match &self.kind {
+            SecuritySchemeKind::ApiKey { name, location } => {
+                let location = match location {
+                    ApiKeyLocation::Query => "query",
+                    ApiKeyLocation::Header => "header",
+                    ApiKeyLocation::Cookie => "cookie",
+                };
+                write!(f, "apiKey {name:?} in {location}")?
+            }
+            SecuritySchemeKind::Http {
+                scheme,
+                bearer_format,
+            } => {
+                write!(f, "http {scheme:?}")?;
+                if let Some(format) = bearer_format {
+                    write!(f, " bearerFormat {format:?}")?;
+                }
+            }
+            SecuritySchemeKind::OAuth2 { flows } => {
+                let kinds = flows.keys().map(|k| match k {
+                    OAuthFlowKind::Implicit => "implicit",
+                    OAuthFlowKind::Password => "password",
+                    OAuthFlowKind::ClientCredentials => "clientCredentials",
+                    OAuthFlowKind::AuthorizationCode => "authorizationCode",
+                });
+                write!(f, "oauth2 [{}]", kinds.collect::<Vec<_>>().join(", "))?
+            }
+            SecuritySchemeKind::OpenIdConnect { url } => write!(f, "openIdConnect {url:?}")?,
+        }
+        let mut ann = Annotations::default();
+        ann.add("description", self.desc.as_ref());
+        write!(f, "{ann}")
+    }
+}
+
+impl Display for Spec {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let info = &self.info;
+        if *info != Info::default() {
+            let mut ann = Annotations::default();
+            ann.add("title", info.title.as_ref())
+                .add("version", info.version.as_ref())
+                .add("description", info.desc.as_ref());
+            writeln!(f, "info{ann}")?;
+        }
+        for server in self.servers.iter().flatten() {
+            let mut ann = Annotations::default();
+            ann.add("description", server.desc.as_ref());
+            writeln!(f, "server {:?}{ann}", server.url)?;
+        }
+        for tag in self.tags.iter() {
+            let mut ann = Annotations::default();
+            ann.add("description", tag.desc.as_ref());
+            writeln!(f, "tag {:?}{ann}", tag.name)?;
+        }
+        for (name, scheme) in self.security_schemes.iter() {
+            writeln!(f, "scheme {name} = {scheme}")?;
+        }
+        if let Some(security) = &self.security {
+            writeln!(f, "security {security:?}")?;
+        }
+        for (name, Reference::Schema(schema)) in self.refs.iter() {
+            writeln!(f, "ref {name} = {schema}")?;
+        }
+        for rel in self.rels.iter() {
+            write!(f, "{rel}")?;
+        }
+        Ok(())
+    }
+}
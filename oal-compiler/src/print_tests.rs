@@ -0,0 +1,75 @@
+use crate::compile::compile;
+use crate::tests::mods_from;
+
+#[test]
+fn print_spec() -> anyhow::Result<()> {
+    let code = r#"
+        # description: a user, description@fr: un utilisateur
+        let @user = { 'id! int `minimum: 1`, 'name str, 'tags [str `enum: [a, b]`] };
+        let notfound = <status=404, media="application/problem+json", {}>;
+        # summary: get a user
+        let op = get -> <status=200, headers={ 'ETag str }, @user> :: notfound;
+        res /users/{ 'id int }?{ 'expand bool } on op, put : @user -> <status=204>;
+        # webhook: created
+        let hook = /created on post : @user -> <status=200>;
+        res hook;
+    "#;
+    let mods = mods_from(code)?;
+    compile(&mods, mods.base())?;
+    let spec = crate::eval::eval(&mods)?;
+    let expected = r#"ref @user = { 'id! int `minimum: 1`, 'name str, 'tags [str `enum: ["a", "b"]`] } `description: "a user", description@fr: "un utilisateur"`
+rel /users/{ 'id int }?{ 'expand bool }
+  get
+    summary: "get a user"
+    response: <status=200, headers={ 'ETag str }, @user `description: "a user", description@fr: "un utilisateur"`>
+    response: <status=404, media=application/problem+json, {}>
+  put
+    request: <@user `description: "a user", description@fr: "un utilisateur"`> `description: "a user", description@fr: "un utilisateur"`
+    response: <status=204>
+rel /created `webhook: "created"`
+  post
+    request: <@user `description: "a user", description@fr: "un utilisateur"`> `description: "a user", description@fr: "un utilisateur"`
+    response: <status=200>
+"#;
+    assert_eq!(spec.to_string(), expected);
+
+    Ok(())
+}
+
+#[test]
+fn print_schemas() -> anyhow::Result<()> {
+    let code = r#"
+        let a = (num `maximum: 9.5` | str) ~ { 'b? uri `example: "/x"` } `example: { b: "/x" }`;
+        let b = <status=2XX, media="application/json; charset=UTF-8", a> `content-encoding: [gzip]`;
+        res /a on patch -> b;
+    "#;
+    let mods = mods_from(code)?;
+    compile(&mods, mods.base())?;
+    let spec = crate::eval::eval(&mods)?;
+    let xfer = spec.rels[0].xfers.values().flatten().next().unwrap();
+    let content = xfer.ranges.values().next().unwrap();
+    assert_eq!(
+        content.to_string(),
+        r#"<status=2XX, media=application/json; charset=utf-8, (num `maximum: 9.5` | str) ~ { 'b? uri `example: "/x"` } `example: {"b": "/x"}`> `content-encoding: ["gzip"]`"#
+    );
+
+    Ok(())
+}
+
+#[test]
+fn print_examples() -> anyhow::Result<()> {
+    let code = r#"
+        res /a on get -> {} `examples: { a: a.json, b: [1], c: !example { summary: x, value: y }, d: { value: z }, e: !example { value: e } }`;
+    "#;
+    let mods = mods_from(code)?;
+    compile(&mods, mods.base())?;
+    let spec = crate::eval::eval(&mods)?;
+    let xfer = spec.rels[0].xfers.values().flatten().next().unwrap();
+    let content = xfer.ranges.values().next().unwrap();
+    assert_eq!(
+        content.to_string(),
+        r#"<{} `examples: {"a": "a.json", "b": [1], "c": !example {summary: "x", value: "y"}, "d": {"value": "z"}, "e": !example {value: "e"}}`>"#
+    );
+
+    Ok(())
+}
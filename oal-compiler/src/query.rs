@@ -0,0 +1,221 @@
+//! Queries over the evaluated specification, for ad-hoc inspection and scripted checks.
+//!
+//! A query selects either relations by URI pattern, optionally narrowed to a method:
+//! ```text
+//! paths(/users/*)
+//! paths(/users/**).get
+//! ```
+//! or schema components by the name of a property they define, at any depth:
+//! ```text
+//! schemas(email)
+//! ```
+//! In patterns, `*` matches any characters but `/` and `**` matches any characters.
+
+use crate::spec::{Reference, Relation, Schema, SchemaExpr, Spec, Transfer};
+use oal_syntax::atom;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// An error in the text of a query.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+#[error("invalid query: {0}")]
+pub struct QueryError(String);
+
+/// A query over a specification.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Query {
+    /// Relations whose URI pattern matches, or their transfers on the given method.
+    Paths {
+        pattern: String,
+        method: Option<String>,
+    },
+    /// Schema components defining a property whose name matches.
+    Schemas { property: String },
+}
+
+/// A result of a query.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Match<'a> {
+    Relation(&'a Relation),
+    Transfer {
+        relation: &'a Relation,
+        method: &'a str,
+        xfer: &'a Transfer,
+    },
+    Schema {
+        name: &'a atom::Ident,
+        schema: &'a Schema,
+    },
+}
+
+impl Display for Match<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Match::Relation(rel) => write!(f, "{}", rel.uri.pattern()),
+            Match::Transfer {
+                relation, method, ..
+            } => write!(f, "{} {}", method, relation.uri.pattern()),
+            Match::Schema { name, .. } => write!(f, "{}", name.untagged()),
+        }
+    }
+}
+
+/// Returns whether the text matches the pattern.
+fn glob(pattern: &[u8], text: &[u8]) -> bool {
+    match pattern {
+        [] => text.is_empty(),
+        [b'*', b'*', rest @ ..] => (0..=text.len()).any(|i| glob(rest, &text[i..])),
+        [b'*', rest @ ..] => {
+            let end = text.iter().position(|c| *c == b'/').unwrap_or(text.len());
+            (0..=end).any(|i| glob(rest, &text[i..]))
+        }
+        [c, rest @ ..] => text.first() == Some(c) && glob(rest, &text[1..]),
+    }
+}
+
+/// Returns the label of a standard method.
+pub fn method_label(method: atom::Method) -> &'static str {
+    match method {
+        atom::Method::Get => "get",
+        atom::Method::Put => "put",
+        atom::Method::Post => "post",
+        atom::Method::Patch => "patch",
+        atom::Method::Delete => "delete",
+        atom::Method::Options => "options",
+        atom::Method::Head => "head",
+        atom::Method::Trace => "trace",
+    }
+}
+
+/// Returns the label of an HTTP status code or range, e.g. `404` or `4XX`.
+pub fn status(status: &atom::HttpStatus) -> String {
+    match status {
+        atom::HttpStatus::Code(code) => code.to_string(),
+        atom::HttpStatus::Range(range) => match range {
+            atom::HttpStatusRange::Info => "1XX",
+            atom::HttpStatusRange::Success => "2XX",
+            atom::HttpStatusRange::Redirect => "3XX",
+            atom::HttpStatusRange::ClientError => "4XX",
+            atom::HttpStatusRange::ServerError => "5XX",
+        }
+        .to_owned(),
+    }
+}
+
+/// Returns whether the schema defines a property whose name matches, at any depth.
+///
+/// References are not followed, as the referenced components are matched on their own.
+fn defines_property(schema: &Schema, pattern: &str) -> bool {
+    match &schema.expr {
+        SchemaExpr::Object(o) => o.props.iter().any(|p| {
+            glob(pattern.as_bytes(), p.name.as_ref().as_bytes())
+                || defines_property(&p.schema, pattern)
+        }),
+        SchemaExpr::Array(a) => defines_property(&a.item, pattern),
+        SchemaExpr::Op(op) => op.schemas.iter().any(|s| defines_property(s, pattern)),
+        _ => false,
+    }
+}
+
+impl Query {
+    /// Selects the relations whose URI pattern matches.
+    pub fn paths<S: Into<String>>(pattern: S) -> Self {
+        Query::Paths {
+            pattern: pattern.into(),
+            method: None,
+        }
+    }
+
+    /// Selects the schema components defining a property whose name matches.
+    pub fn schemas<S: Into<String>>(property: S) -> Self {
+        Query::Schemas {
+            property: property.into(),
+        }
+    }
+
+    /// Narrows a selection of relations to their transfers on the given method.
+    pub fn method<S: Into<String>>(self, name: S) -> Self {
+        match self {
+            Query::Paths { pattern, .. } => Query::Paths {
+                pattern,
+                method: Some(name.into().to_lowercase()),
+            },
+            q => q,
+        }
+    }
+
+    /// Runs the query, returning the results in the order of the specification.
+    pub fn run<'a>(&self, spec: &'a Spec) -> Vec<Match<'a>> {
+        match self {
+            Query::Paths { pattern, method } => {
+                let rels = spec
+                    .rels
+                    .iter()
+                    .filter(|r| glob(pattern.as_bytes(), r.uri.pattern().as_bytes()));
+                let Some(method) = method else {
+                    return rels.map(Match::Relation).collect();
+                };
+                let mut matches = Vec::new();
+                for relation in rels {
+                    let standard = relation.xfers.iter().find_map(|(m, x)| {
+                        let label = method_label(m);
+                        x.as_ref().filter(|_| label == method).map(|x| (label, x))
+                    });
+                    let custom = || {
+                        relation
+                            .custom_xfers
+                            .get_key_value(method)
+                            .map(|(k, x)| (k.as_str(), x))
+                    };
+                    if let Some((method, xfer)) = standard.or_else(custom) {
+                        matches.push(Match::Transfer {
+                            relation,
+                            method,
+                            xfer,
+                        });
+                    }
+                }
+                matches
+            }
+            Query::Schemas { property } => spec
+                .refs
+                .iter()
+                .map(|(name, Reference::Schema(schema))| (name, schema))
+                .filter(|(_, s)| defines_property(s, property))
+                .map(|(name, schema)| Match::Schema { name, schema })
+                .collect(),
+        }
+    }
+}
+
+/// Returns the argument of a selector, e.g. `/a` for `paths(/a)`, along with the remaining text.
+fn argument<'a>(s: &'a str, selector: &str) -> Option<(&'a str, &'a str)> {
+    let rest = s.strip_prefix(selector)?.strip_prefix('(')?;
+    let (arg, rest) = rest.split_once(')')?;
+    Some((arg.trim(), rest))
+}
+
+impl FromStr for Query {
+    type Err = QueryError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (query, rest) = if let Some((pattern, rest)) = argument(s, "paths") {
+            (Query::paths(pattern), rest)
+        } else if let Some((property, rest)) = argument(s, "schemas") {
+            (Query::schemas(property), rest)
+        } else {
+            let msg = "expected paths(<pattern>) or schemas(<property>)";
+            return Err(QueryError(msg.to_owned()));
+        };
+        match (query, rest.strip_prefix('.')) {
+            (q, None) if rest.is_empty() => Ok(q),
+            (q @ Query::Paths { .. }, Some(method))
+                if !method.is_empty() && method.chars().all(|c| c.is_ascii_alphabetic()) =>
+            {
+                Ok(q.method(method))
+            }
+            _ => Err(QueryError(format!("unexpected trailing input '{rest}'"))),
+        }
+    }
+}
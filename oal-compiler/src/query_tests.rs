@@ -0,0 +1,65 @@
+use crate::compile::compile;
+use crate::query::{Match, Query};
+use crate::tests::mods_from;
+
+#[test]
+fn query_paths() -> anyhow::Result<()> {
+    let code = r#"
+        res /users on get -> {}, post : {} -> {};
+        res /users/{ 'id str } on get -> {}, delete -> {};
+        res /users/{ 'id str }/posts on get -> {};
+        res /status on get -> {};
+    "#;
+    let mods = mods_from(code)?;
+    compile(&mods, mods.base())?;
+    let spec = crate::eval::eval(&mods)?;
+
+    let run = |q: &str| -> anyhow::Result<Vec<String>> {
+        let query = q.parse::<Query>()?;
+        Ok(query.run(&spec).iter().map(ToString::to_string).collect())
+    };
+
+    assert_eq!(run("paths(/users/*)")?, ["/users/{id}"]);
+    assert_eq!(
+        run("paths(/users/**)")?,
+        ["/users/{id}", "/users/{id}/posts"]
+    );
+    assert_eq!(run("paths(/users/**).delete")?, ["delete /users/{id}"]);
+    assert_eq!(run("paths(**).GET")?.len(), 4);
+    assert!(run("paths(/none)")?.is_empty());
+
+    let matches = Query::paths("/users").method("post").run(&spec);
+    assert!(matches!(
+        matches[..],
+        [Match::Transfer { method: "post", .. }]
+    ));
+
+    assert!("users".parse::<Query>().is_err());
+    assert!("paths(/a).".parse::<Query>().is_err());
+    assert!("schemas(a).get".parse::<Query>().is_err());
+
+    Ok(())
+}
+
+#[test]
+fn query_schemas() -> anyhow::Result<()> {
+    let code = r#"
+        let @contact = { 'email str, 'phone str };
+        let @user = { 'name str, 'contacts [@contact] };
+        let @team = { 'members [{ 'email str }] };
+        res /users on get -> @user;
+        res /teams on get -> @team;
+    "#;
+    let mods = mods_from(code)?;
+    compile(&mods, mods.base())?;
+    let spec = crate::eval::eval(&mods)?;
+
+    let names =
+        |q: Query| -> Vec<String> { q.run(&spec).iter().map(ToString::to_string).collect() };
+
+    assert_eq!(names(Query::schemas("email")), ["contact", "team"]);
+    assert_eq!(names(Query::schemas("*s")), ["user", "team"]);
+    assert!(names(Query::schemas("id")).is_empty());
+
+    Ok(())
+}
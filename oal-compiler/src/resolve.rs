@@ -1,12 +1,14 @@
+use crate::annotation::compose_annotations;
 use crate::definition::{Definition, External};
 use crate::env::{Entry, Env};
 use crate::errors::{Error, Kind, Result};
+use crate::fix::Fix;
 use crate::module::ModuleSet;
 use crate::stdlib;
 use crate::tree::Core;
 use oal_model::grammar::{AbstractSyntaxNode, NodeCursor};
 use oal_model::locator::Locator;
-use oal_syntax::parser::{Declaration, Import, Program, Recursion, Variable};
+use oal_syntax::parser::{Binding, Declaration, Import, Program, Recursion, Variable};
 use petgraph::graph::NodeIndex;
 use petgraph::stable_graph::StableDiGraph;
 use std::collections::{hash_map, HashMap};
@@ -69,9 +71,25 @@ fn define_variable(env: &mut Env, defg: &mut Builder, var: Variable<'_, Core>) -
         }
         Ok(())
     } else {
-        Err(Error::new(Kind::NotInScope, "variable is not defined")
+        let mut err = Error::new(Kind::NotInScope, "variable is not defined")
             .with(&var.ident())
-            .at(var.node().span()))
+            .at(var.node().span());
+        // Offers to qualify a variable only declared by qualified imports.
+        if var.qualifier().is_none() {
+            if let Some(span) = var.node().span() {
+                for q in env.qualifiers(&var.ident()) {
+                    let title = format!("qualify as {q}.{}", var.ident());
+                    let fix = Fix::insert(
+                        title,
+                        span.locator().clone(),
+                        span.start(),
+                        &format!("{q}."),
+                    );
+                    err = err.with_fix(fix);
+                }
+            }
+        }
+        Err(err)
     }
 }
 
@@ -81,7 +99,7 @@ fn declare_import(
     loc: &Locator,
     import: Import<'_, Core>,
 ) -> Result<()> {
-    let other = loc.join(import.module())?;
+    let other = mods.resolve_import(loc, import.module())?;
     // All modules that are to be imported must be present in the module-set.
     let Some(module) = mods.get(&other) else {
         panic!("unknown module: {other}")
@@ -95,21 +113,78 @@ fn declare_import(
     Ok(())
 }
 
-fn declare_variable(env: &mut Env, decl: Declaration<'_, Core>) -> Result<()> {
+fn declare_variable(env: &mut Env, mods: &ModuleSet, decl: Declaration<'_, Core>) -> Result<()> {
     let defn = Definition::External(External::new(decl.node()));
     let entry = Entry::from(decl.ident());
-    if env.declare(entry, defn).is_some() {
+    if let Some(previous) = env.declare(entry, defn) {
         let span = decl.identifier().node().span();
-        Err(Error::new(Kind::InvalidIdentifier, "identifier already exists").at(span))
+        let mut err = Error::new(Kind::InvalidIdentifier, "identifier already exists").at(span);
+        if let Definition::External(ext) = previous {
+            let first = match Declaration::cast(ext.node(mods)) {
+                Some(d) => d.identifier().node().span(),
+                None => ext.node(mods).span(),
+            };
+            err = err.with_label(first, "first defined here");
+        }
+        Err(err)
     } else {
         Ok(())
     }
 }
 
-fn open_declaration(env: &mut Env, defg: &mut Builder, decl: Declaration<'_, Core>) -> Result<()> {
+/// The annotation listing the bindings of a declaration that intentionally shadow other identifiers.
+const SHADOWS: &str = "shadows";
+
+/// The state of shadowing checks.
+#[derive(Default)]
+struct Shadowing {
+    /// The identifiers allowed to be shadowed within the current declaration.
+    allowed: Vec<String>,
+    /// The warnings about shadowed identifiers.
+    warnings: Vec<Error>,
+}
+
+/// Checks whether a binding shadows an identifier in scope, before declaring the binding.
+fn check_shadowing(
+    env: &Env,
+    mods: &ModuleSet,
+    shadowing: &mut Shadowing,
+    binding: &Binding<'_, Core>,
+) {
+    let ident = binding.ident();
+    let Some(previous) = env.lookup(&Entry::from(ident.clone())) else {
+        return;
+    };
+    if shadowing.allowed.iter().any(|a| ident == a.as_str()) {
+        return;
+    }
+    let mut err = Error::new(Kind::Shadowed, "binding shadows an existing identifier")
+        .with(&ident)
+        .at(binding.node().span());
+    if let Definition::External(ext) = previous {
+        let span = match Declaration::cast(ext.node(mods)) {
+            Some(d) => d.identifier().node().span(),
+            None => ext.node(mods).span(),
+        };
+        err = err.with_label(span, "shadowed definition");
+    }
+    shadowing.warnings.push(err);
+}
+
+fn open_declaration(
+    env: &mut Env,
+    mods: &ModuleSet,
+    defg: &mut Builder,
+    shadowing: &mut Shadowing,
+    decl: Declaration<'_, Core>,
+) -> Result<()> {
+    shadowing.allowed = compose_annotations(decl.annotations())?
+        .get_enum(SHADOWS)
+        .unwrap_or_default();
     env.open();
     defg.open(External::new(decl.node()));
     for binding in decl.bindings() {
+        check_shadowing(env, mods, shadowing, &binding);
         let defn = Definition::External(External::new(binding.node()));
         let entry = Entry::from(binding.ident());
         env.declare(entry, defn);
@@ -123,9 +198,15 @@ fn close_declaration(env: &mut Env, defg: &mut Builder) -> Result<()> {
     Ok(())
 }
 
-fn open_recursion(env: &mut Env, rec: Recursion<'_, Core>) -> Result<()> {
-    env.open();
+fn open_recursion(
+    env: &mut Env,
+    mods: &ModuleSet,
+    shadowing: &mut Shadowing,
+    rec: Recursion<'_, Core>,
+) -> Result<()> {
     let binding = rec.binding();
+    check_shadowing(env, mods, shadowing, &binding);
+    env.open();
     let defn = Definition::External(External::new(binding.node()));
     let entry = Entry::from(binding.ident());
     env.declare(entry, defn);
@@ -137,11 +218,20 @@ fn close_recursion(env: &mut Env) -> Result<()> {
     Ok(())
 }
 
+#[cfg(test)]
 pub fn resolve(mods: &ModuleSet, loc: &Locator) -> Result<Graph> {
+    resolve_with_warnings(mods, loc).map(|(graph, _)| graph)
+}
+
+/// Resolves variable references, also returning the warnings about shadowed identifiers.
+pub fn resolve_with_warnings(mods: &ModuleSet, loc: &Locator) -> Result<(Graph, Vec<Error>)> {
     let mut defg = Builder::default();
+    let shadowing = &mut Shadowing::default();
 
     let env = &mut Env::new();
     stdlib::import(env)?;
+    // Declarations of the module shadow the standard library rather than colliding with it.
+    env.open();
 
     let tree = mods.get(loc).unwrap();
     let prog = Program::cast(tree.root()).expect("root should be a program");
@@ -149,23 +239,24 @@ pub fn resolve(mods: &ModuleSet, loc: &Locator) -> Result<Graph> {
         declare_import(env, mods, loc, import)?;
     }
     for decl in prog.declarations() {
-        declare_variable(env, decl)?;
+        declare_variable(env, mods, decl)?;
     }
 
     for cursor in tree.root().traverse() {
         match cursor {
             NodeCursor::Start(node) => {
                 if let Some(decl) = Declaration::cast(node) {
-                    open_declaration(env, &mut defg, decl)?;
+                    open_declaration(env, mods, &mut defg, shadowing, decl)?;
                 } else if let Some(var) = Variable::cast(node) {
                     define_variable(env, &mut defg, var)?;
                 } else if let Some(rec) = Recursion::cast(node) {
-                    open_recursion(env, rec)?;
+                    open_recursion(env, mods, shadowing, rec)?;
                 }
             }
             NodeCursor::End(node) => {
                 if Declaration::cast(node).is_some() {
                     close_declaration(env, &mut defg)?;
+                    shadowing.allowed.clear();
                 } else if Recursion::cast(node).is_some() {
                     close_recursion(env)?;
                 }
@@ -173,5 +264,6 @@ pub fn resolve(mods: &ModuleSet, loc: &Locator) -> Result<Graph> {
         }
     }
 
-    Ok(defg.graph())
+    let warnings = std::mem::take(&mut shadowing.warnings);
+    Ok((defg.graph(), warnings))
 }
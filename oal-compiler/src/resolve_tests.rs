@@ -1,10 +1,11 @@
 use crate::definition::Definition;
 use crate::errors::Kind;
 use crate::module::ModuleSet;
-use crate::resolve::resolve;
+use crate::resolve::{resolve, resolve_with_warnings};
 use crate::tests::mods_from;
 use crate::tree::NRef;
 use oal_model::grammar::AbstractSyntaxNode;
+use oal_model::locator::Locator;
 use oal_syntax::parser as syn;
 use oal_syntax::parser::{
     Application, Binding, Declaration, Primitive, Program, Terminal, Variable,
@@ -150,3 +151,60 @@ fn resolve_graph() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn resolve_duplicate_across_modules() -> anyhow::Result<()> {
+    let mut mods = mods_from(
+        r#"
+    use "module.oal";
+    let a = str;
+"#,
+    )?;
+
+    let loc = Locator::try_from("file:module.oal")?;
+    let (module, errs) = oal_syntax::parse(loc.clone(), "let a = {};");
+    assert!(errs.is_empty());
+    mods.insert(module.expect("parsing failed"));
+
+    let err = resolve(&mods, mods.base()).expect_err("expected an error");
+    assert!(matches!(err.kind, Kind::InvalidIdentifier));
+
+    let [(span, msg)] = err.labels() else {
+        panic!("expected a single label")
+    };
+    assert_eq!(msg, "first defined here");
+    assert_eq!(*span.locator(), loc);
+    assert_eq!(span.range(), 4..5);
+
+    Ok(())
+}
+
+#[test]
+fn resolve_shadowing() -> anyhow::Result<()> {
+    let mods = mods_from(
+        r#"
+    let id = num;
+    let f id x = { 'id id, 'x x };
+    # shadows: [id]
+    let g id = id;
+    let h = rec id [id];
+    let k = rec x [x];
+"#,
+    )?;
+
+    let (_, warnings) = resolve_with_warnings(&mods, mods.base())?;
+
+    assert_eq!(warnings.len(), 2, "expected warnings for f and h only");
+    for warning in warnings.iter() {
+        assert!(matches!(warning.kind, Kind::Shadowed));
+        assert_eq!(
+            warning.labels().len(),
+            1,
+            "expected the shadowed definition"
+        );
+    }
+    let first = warnings[0].span().expect("expected a span");
+    assert_eq!(first.range(), 29..31);
+
+    Ok(())
+}
@@ -1,8 +1,12 @@
+use crate::errors::{Error, Kind};
 use enum_map::EnumMap;
 use indexmap::IndexMap;
+use oal_model::locator::Locator;
+use oal_model::span::Span;
 use oal_syntax::atom;
-use std::collections::HashMap;
-use std::fmt::Debug;
+use std::collections::BTreeMap;
+use std::fmt::{Debug, Display, Formatter};
+use std::str::FromStr;
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum UriSegment {
@@ -68,29 +72,99 @@ impl Uri {
 #[derive(Clone, Debug, PartialEq)]
 pub struct Array {
     pub item: Schema,
+    pub min_items: Option<usize>,
+    pub max_items: Option<usize>,
+    pub unique_items: Option<bool>,
+}
+
+/// The property distinguishing the alternatives of a sum.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Discriminator {
+    pub property_name: String,
+    /// The schema of each value of the property, either a schema component like `@cat`
+    /// or a reference given as is.
+    pub mapping: IndexMap<String, String>,
 }
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct VariadicOp {
     pub op: atom::VariadicOperator,
     pub schemas: Vec<Schema>,
+    pub discriminator: Option<Discriminator>,
+}
+
+/// Translations of a text by language tag, e.g. `fr`.
+pub type Translations = IndexMap<String, String>;
+
+/// A named example, either given inline or hosted separately.
+#[derive(Clone, Debug, PartialEq, Default)]
+pub struct Example {
+    pub summary: Option<String>,
+    pub desc: Option<String>,
+    /// The inline value of the example, as an arbitrary value.
+    pub value: Option<serde_yaml::Value>,
+    /// The URL of the example hosted separately.
+    pub external: Option<String>,
 }
 
+/// Examples by name.
+pub type Examples = IndexMap<String, Example>;
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct Schema {
     pub expr: SchemaExpr,
     pub desc: Option<String>,
+    /// The description in other languages, from annotations such as `description@fr`.
+    pub translations: Translations,
     pub title: Option<String>,
     pub required: Option<bool>,
-    pub examples: Option<HashMap<String, String>>,
+    pub examples: Option<Examples>,
+    /// The example of an object, array or operation schema, as an arbitrary value.
+    ///
+    /// Primitive schemas carry a typed example of their own instead.
+    pub example: Option<serde_yaml::Value>,
+    /// The vendor extensions, from annotations such as `x-internal`.
+    pub extensions: Extensions,
+    /// The custom keywords, from the annotations unknown to the compiler.
+    pub keywords: Keywords,
+    pub deprecated: Option<bool>,
+    /// Whether the schema also accepts `null`.
+    pub nullable: Option<bool>,
+    /// The value assumed when none is given, as an arbitrary value.
+    pub default: Option<serde_yaml::Value>,
+    /// The single value allowed by the schema, as an arbitrary value.
+    pub constant: Option<serde_yaml::Value>,
+    pub stability: Option<Stability>,
+}
+
+impl Schema {
+    /// Returns true if the schema carries no annotation of its own.
+    pub fn is_bare(&self) -> bool {
+        self.desc.is_none()
+            && self.translations.is_empty()
+            && self.title.is_none()
+            && self.required.is_none()
+            && self.examples.is_none()
+            && self.example.is_none()
+            && self.extensions.is_empty()
+            && self.keywords.is_empty()
+            && self.deprecated.is_none()
+            && self.nullable.is_none()
+            && self.default.is_none()
+            && self.constant.is_none()
+            && self.stability.is_none()
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Default)]
 pub struct PrimNumber {
     pub minimum: Option<f64>,
     pub maximum: Option<f64>,
+    pub exclusive_minimum: bool,
+    pub exclusive_maximum: bool,
     pub multiple_of: Option<f64>,
     pub example: Option<f64>,
+    pub enumeration: Vec<f64>,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Default)]
@@ -104,14 +178,19 @@ pub struct PrimString {
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Default)]
-pub struct PrimBoolean {}
+pub struct PrimBoolean {
+    pub enumeration: Vec<bool>,
+}
 
 #[derive(Clone, Debug, PartialEq, Eq, Default)]
 pub struct PrimInteger {
     pub minimum: Option<i64>,
     pub maximum: Option<i64>,
+    pub exclusive_minimum: bool,
+    pub exclusive_maximum: bool,
     pub multiple_of: Option<i64>,
     pub example: Option<i64>,
+    pub enumeration: Vec<i64>,
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -128,20 +207,238 @@ pub enum SchemaExpr {
     Ref(atom::Ident),
 }
 
+/// The serialization style of a path or query parameter, or of a form field.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ParamStyle {
+    Form,
+    SpaceDelimited,
+    PipeDelimited,
+    DeepObject,
+    Simple,
+    Label,
+    Matrix,
+}
+
+impl ParamStyle {
+    /// Returns true if the style applies to path parameters, as opposed to query parameters.
+    pub fn is_path(&self) -> bool {
+        matches!(
+            self,
+            ParamStyle::Simple | ParamStyle::Label | ParamStyle::Matrix
+        )
+    }
+}
+
+impl FromStr for ParamStyle {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        match s {
+            "form" => Ok(ParamStyle::Form),
+            "spaceDelimited" => Ok(ParamStyle::SpaceDelimited),
+            "pipeDelimited" => Ok(ParamStyle::PipeDelimited),
+            "deepObject" => Ok(ParamStyle::DeepObject),
+            "simple" => Ok(ParamStyle::Simple),
+            "label" => Ok(ParamStyle::Label),
+            "matrix" => Ok(ParamStyle::Matrix),
+            _ => Err(Error::new(
+                Kind::InvalidLiteral,
+                format!("not a valid parameter style: {s}"),
+            )),
+        }
+    }
+}
+
+/// The maturity of an operation or a schema, from the least to the most stable.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Stability {
+    Alpha,
+    Beta,
+    Stable,
+}
+
+impl Stability {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Stability::Alpha => "alpha",
+            Stability::Beta => "beta",
+            Stability::Stable => "stable",
+        }
+    }
+}
+
+impl Display for Stability {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for Stability {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        match s {
+            "alpha" => Ok(Stability::Alpha),
+            "beta" => Ok(Stability::Beta),
+            "stable" => Ok(Stability::Stable),
+            _ => Err(Error::new(
+                Kind::InvalidLiteral,
+                format!("not a valid stability: {s}, expected alpha, beta or stable"),
+            )),
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct Property {
     pub name: atom::Text,
     pub schema: Schema,
     pub desc: Option<String>,
+    /// The description in other languages.
+    pub translations: Translations,
     pub required: Option<bool>,
+    pub style: Option<ParamStyle>,
+    pub explode: Option<bool>,
+    /// The media types of a form field, e.g. `image/png, image/jpeg`.
+    pub content_type: Option<String>,
+    pub deprecated: Option<bool>,
+}
+
+/// Whether an object accepts properties it does not declare.
+#[derive(Clone, Debug, PartialEq)]
+pub enum AdditionalProperties {
+    Allowed(bool),
+    /// The schema of undeclared properties, as given by the annotation.
+    Schema(serde_yaml::Value),
 }
 
 #[derive(Clone, Debug, PartialEq, Default)]
 pub struct Object {
     pub props: Vec<Property>,
+    pub additional_props: Option<AdditionalProperties>,
+    pub min_props: Option<usize>,
+    pub max_props: Option<usize>,
 }
 
-pub type MediaType = String;
+/// A media type, e.g. `application/vnd.acme+json; version=2`.
+///
+/// Type, subtype, suffix and parameter names are case-insensitive and kept in lowercase,
+/// as is the value of the `charset` parameter. Parameters are ordered by name,
+/// so that media types differing only by the order of their parameters compare equal.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct MediaType {
+    /// The top-level type, e.g. `application`.
+    pub top: String,
+    /// The subtype without its suffix, e.g. `vnd.acme`.
+    pub sub: String,
+    /// The structured syntax suffix, e.g. `json`.
+    pub suffix: Option<String>,
+    pub params: BTreeMap<String, String>,
+}
+
+fn is_token_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(c)
+}
+
+fn is_token(s: &str) -> bool {
+    !s.is_empty() && s.chars().all(is_token_char)
+}
+
+/// Parses a parameter value, either a token or a quoted string, returning the rest of the input.
+fn parse_param_value(s: &str) -> Option<(String, &str)> {
+    if let Some(quoted) = s.strip_prefix('"') {
+        let mut value = String::new();
+        let mut chars = quoted.char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '"' => return Some((value, &quoted[i + 1..])),
+                '\\' => value.push(chars.next()?.1),
+                c => value.push(c),
+            }
+        }
+        None
+    } else {
+        let end = s.find(|c| !is_token_char(c)).unwrap_or(s.len());
+        let (value, rest) = s.split_at(end);
+        is_token(value).then(|| (value.to_owned(), rest))
+    }
+}
+
+impl MediaType {
+    /// Returns the essence of the media type, i.e. without parameters.
+    pub fn essence(&self) -> String {
+        match &self.suffix {
+            Some(suffix) => format!("{}/{}+{}", self.top, self.sub, suffix),
+            None => format!("{}/{}", self.top, self.sub),
+        }
+    }
+}
+
+impl FromStr for MediaType {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        let invalid = || Error::new(Kind::InvalidLiteral, "not a valid media type");
+
+        let mut rest = s.trim();
+        let end = rest.find(';').unwrap_or(rest.len());
+        let (essence, params) = rest.split_at(end);
+        let (top, sub) = essence.trim_end().split_once('/').ok_or_else(invalid)?;
+        if !is_token(top) || !is_token(sub) {
+            return Err(invalid());
+        }
+        let top = top.to_ascii_lowercase();
+        let sub = sub.to_ascii_lowercase();
+        let (sub, suffix) = match sub.rsplit_once('+') {
+            Some((s, x)) if !s.is_empty() && !x.is_empty() => (s.to_owned(), Some(x.to_owned())),
+            _ => (sub, None),
+        };
+
+        let mut media = MediaType {
+            top,
+            sub,
+            suffix,
+            params: BTreeMap::new(),
+        };
+        rest = params;
+        while let Some(param) = rest.strip_prefix(';') {
+            let param = param.trim_start();
+            let (name, value) = param.split_once('=').ok_or_else(invalid)?;
+            if !is_token(name) {
+                return Err(invalid());
+            }
+            let name = name.to_ascii_lowercase();
+            let (mut value, tail) = parse_param_value(value).ok_or_else(invalid)?;
+            if name == "charset" {
+                value.make_ascii_lowercase();
+            }
+            if media.params.insert(name, value).is_some() {
+                return Err(invalid());
+            }
+            rest = tail.trim_start();
+        }
+        if rest.is_empty() {
+            Ok(media)
+        } else {
+            Err(invalid())
+        }
+    }
+}
+
+impl Display for MediaType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.essence())?;
+        for (name, value) in self.params.iter() {
+            if is_token(value) {
+                write!(f, "; {name}={value}")?;
+            } else {
+                let escaped = value.replace('\\', "\\\\").replace('"', "\\\"");
+                write!(f, "; {name}=\"{escaped}\"")?;
+            }
+        }
+        Ok(())
+    }
+}
 
 #[derive(Clone, Debug, PartialEq, Default)]
 pub struct Content {
@@ -149,49 +446,216 @@ pub struct Content {
     pub status: Option<atom::HttpStatus>,
     pub media: Option<MediaType>,
     pub headers: Option<Object>,
+    /// The names of the headers from named groups, shared as components.
+    pub shared_headers: Vec<atom::Text>,
     pub desc: Option<String>,
-    pub examples: Option<HashMap<String, String>>,
+    /// The description in other languages.
+    pub translations: Translations,
+    pub examples: Option<Examples>,
+    /// The expected content codings of the body, e.g. `gzip` or `br`.
+    pub encodings: Vec<String>,
+    /// The vendor extensions of the media type, from annotations such as `x-amazon-apigateway-integration`.
+    pub extensions: Extensions,
 }
 
 impl From<Schema> for Content {
     fn from(s: Schema) -> Self {
         let desc = s.desc.clone();
+        let translations = s.translations.clone();
         let schema = Some(s.into());
         let status = None;
         let media = None;
         let headers = None;
+        let shared_headers = Default::default();
         let examples = Default::default();
+        let encodings = Default::default();
+        let extensions = Default::default();
         Content {
             schema,
             status,
             media,
             headers,
+            shared_headers,
             desc,
+            translations,
             examples,
+            encodings,
+            extensions,
         }
     }
 }
 
 pub type Ranges = IndexMap<(Option<atom::HttpStatus>, Option<MediaType>), Content>;
 
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Server {
+    pub url: String,
+    pub desc: Option<String>,
+}
+
+pub type Servers = Vec<Server>;
+
+/// The general information of the document, as declared by the program.
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub struct Info {
+    pub title: Option<String>,
+    pub version: Option<String>,
+    pub desc: Option<String>,
+}
+
+/// A tag of the document, along with its description.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Tag {
+    pub name: String,
+    pub desc: Option<String>,
+}
+
+/// Vendor extensions by name, starting with `x-`.
+pub type Extensions = IndexMap<String, serde_yaml::Value>;
+
+/// Custom JSON Schema keywords by name, e.g. for internal validation tooling.
+pub type Keywords = IndexMap<String, serde_yaml::Value>;
+
+/// The scopes required for each security scheme by name.
+pub type SecurityRequirement = IndexMap<String, Vec<String>>;
+
+/// The location of an API key.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ApiKeyLocation {
+    Query,
+    Header,
+    Cookie,
+}
+
+/// The kind of an OAuth 2.0 flow.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum OAuthFlowKind {
+    Implicit,
+    Password,
+    ClientCredentials,
+    AuthorizationCode,
+}
+
+/// An OAuth 2.0 flow, with the URLs required by its kind.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OAuthFlow {
+    pub authorization_url: Option<String>,
+    pub token_url: Option<String>,
+    pub refresh_url: Option<String>,
+    /// The descriptions of the available scopes by name.
+    pub scopes: IndexMap<String, String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SecuritySchemeKind {
+    ApiKey {
+        name: String,
+        location: ApiKeyLocation,
+    },
+    Http {
+        scheme: String,
+        bearer_format: Option<String>,
+    },
+    OAuth2 {
+        flows: IndexMap<OAuthFlowKind, OAuthFlow>,
+    },
+    OpenIdConnect {
+        url: String,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SecurityScheme {
+    pub kind: SecuritySchemeKind,
+    pub desc: Option<String>,
+}
+
+/// Security schemes by name.
+pub type SecuritySchemes = IndexMap<String, SecurityScheme>;
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct Transfer {
     pub methods: EnumMap<atom::Method, bool>,
     pub domain: Content,
+    /// The contents of the request body in other media types than that of the domain.
+    pub alt_domains: Vec<Content>,
     pub ranges: Ranges,
     pub params: Option<Object>,
+    /// The names of the parameters from named groups, shared as components.
+    pub shared_params: Vec<atom::Text>,
     pub desc: Option<String>,
+    /// The description in other languages.
+    pub translations: Translations,
     pub summary: Option<String>,
     pub tags: Vec<String>,
     pub id: Option<String>,
+    /// The non-standard HTTP method replacing the declared methods, if any.
+    pub verb: Option<String>,
+    /// The servers of the transfer, if overriding those of the document.
+    pub servers: Option<Servers>,
+    /// The security requirements of the transfer, if overriding those of the document.
+    pub security: Option<Vec<SecurityRequirement>>,
+    /// The vendor extensions of the operation.
+    pub extensions: Extensions,
+    pub deprecated: Option<bool>,
+    pub stability: Option<Stability>,
+    /// The callbacks of the operation, as the runtime expressions of their URLs by callback name.
+    pub callbacks: Callbacks,
+    /// The span of the transfer in the program, if any.
+    pub span: Option<Span>,
 }
 
+impl Transfer {
+    /// Returns the contents of the request body, the domain first, then those in other media types.
+    pub fn domains(&self) -> impl Iterator<Item = &Content> {
+        std::iter::once(&self.domain).chain(self.alt_domains.iter())
+    }
+
+    /// Returns the request headers of all the contents of the request body,
+    /// each header name, compared without regard to case, only once.
+    pub fn request_headers(&self) -> Vec<&Property> {
+        let mut headers: Vec<&Property> = Vec::new();
+        for p in self
+            .domains()
+            .filter_map(|d| d.headers.as_ref())
+            .flat_map(|o| o.props.iter())
+        {
+            let name = p.name.as_ref();
+            if !headers
+                .iter()
+                .any(|h| h.name.as_ref().eq_ignore_ascii_case(name))
+            {
+                headers.push(p);
+            }
+        }
+        headers
+    }
+}
+
+/// The runtime expressions of callback URLs by callback name, e.g. `{$request.body#/callbackUrl}`.
+pub type Callbacks = IndexMap<String, String>;
+
 pub type Transfers = EnumMap<atom::Method, Option<Transfer>>;
 
+/// Transfers on non-standard HTTP methods, indexed by lowercase method name.
+pub type CustomTransfers = IndexMap<String, Transfer>;
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct Relation {
     pub uri: Uri,
     pub xfers: Transfers,
+    pub custom_xfers: CustomTransfers,
+    /// The name of the webhook, if the relation describes requests sent by the API.
+    pub webhook: Option<String>,
+    /// The name of the callback, if the relation describes requests sent by the API
+    /// in response to an operation declaring that callback.
+    pub callback: Option<String>,
+    /// The name of the documentation group of the relation, from the `tagGroup` annotation.
+    pub tag_group: Option<String>,
+    /// The module declaring the relation, if any.
+    pub module: Option<Locator>,
+    /// The vendor extensions of the path item.
+    pub extensions: Extensions,
 }
 
 impl From<Uri> for Relation {
@@ -199,6 +663,12 @@ impl From<Uri> for Relation {
         Relation {
             uri,
             xfers: Transfers::default(),
+            custom_xfers: CustomTransfers::default(),
+            webhook: None,
+            callback: None,
+            tag_group: None,
+            module: None,
+            extensions: Default::default(),
         }
     }
 }
@@ -215,4 +685,18 @@ pub type References = IndexMap<atom::Ident, Reference>;
 pub struct Spec {
     pub rels: Relations,
     pub refs: References,
+    /// The security schemes declared by the program.
+    pub security_schemes: SecuritySchemes,
+    /// The security requirements of the document, if any.
+    pub security: Option<Vec<SecurityRequirement>>,
+    /// The schema component names of the references renamed by a `componentName` annotation.
+    pub component_names: IndexMap<atom::Ident, String>,
+    /// The general information of the document, overriding that of the base.
+    pub info: Info,
+    /// The servers of the document, if overriding those of the base.
+    pub servers: Option<Servers>,
+    /// The tags of the document, replacing those of the same name in the base.
+    pub tags: Vec<Tag>,
+    /// The main module of the program, if any.
+    pub module: Option<Locator>,
 }
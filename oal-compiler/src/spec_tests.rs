@@ -1,4 +1,4 @@
-use crate::spec::{Object, PrimNumber, Property, Schema, SchemaExpr, Uri, UriSegment};
+use crate::spec::{MediaType, Object, PrimNumber, Property, Schema, SchemaExpr, Uri, UriSegment};
 
 #[test]
 fn uri_pattern() {
@@ -29,12 +29,26 @@ fn uri_pattern() {
                             schema: Schema {
                                 expr: SchemaExpr::Int(Default::default()),
                                 desc: None,
+                                translations: Default::default(),
                                 title: None,
                                 required: None,
                                 examples: None,
+                                example: None,
+                                extensions: Default::default(),
+                                keywords: Default::default(),
+                                deprecated: None,
+                                nullable: None,
+                                default: None,
+                                constant: None,
+                                stability: None,
                             },
                             desc: None,
+                            translations: Default::default(),
                             required: None,
+                            style: None,
+                            explode: None,
+                            content_type: None,
+                            deprecated: None,
                         }
                         .into(),
                     ),
@@ -59,13 +73,28 @@ fn make_param(name: &str) -> Object {
             schema: Schema {
                 expr: SchemaExpr::Num(PrimNumber::default()),
                 desc: None,
+                translations: Default::default(),
                 title: None,
                 required: None,
                 examples: None,
+                example: None,
+                extensions: Default::default(),
+                keywords: Default::default(),
+                deprecated: None,
+                nullable: None,
+                default: None,
+                constant: None,
+                stability: None,
             },
             desc: None,
+            translations: Default::default(),
             required: None,
+            style: None,
+            explode: None,
+            content_type: None,
+            deprecated: None,
         }],
+        ..Default::default()
     }
 }
 
@@ -120,3 +149,75 @@ fn uri_append() {
         assert_eq!(left, exp);
     }
 }
+
+#[test]
+fn media_type_parse() {
+    let m: MediaType = "application/vnd.acme+json; version=2"
+        .parse()
+        .expect("expected a media type");
+    assert_eq!(m.top, "application");
+    assert_eq!(m.sub, "vnd.acme");
+    assert_eq!(m.suffix.as_deref(), Some("json"));
+    assert_eq!(m.params.get("version").map(String::as_str), Some("2"));
+    assert_eq!(m.essence(), "application/vnd.acme+json");
+
+    let m: MediaType = r#"text/plain;title="a \"b\"; c""#
+        .parse()
+        .expect("expected a media type");
+    assert_eq!(m.suffix, None);
+    assert_eq!(
+        m.params.get("title").map(String::as_str),
+        Some(r#"a "b"; c"#)
+    );
+
+    for invalid in [
+        "",
+        "json",
+        "application/",
+        "/json",
+        "application/json;",
+        "application/json; version",
+        "application/json; a=1; A=2",
+        "application/json; title=\"open",
+        "text/plain garbage",
+    ] {
+        assert!(invalid.parse::<MediaType>().is_err(), "{invalid:?}");
+    }
+}
+
+#[test]
+fn media_type_round_trip() {
+    for (input, output) in [
+        ("application/json", "application/json"),
+        ("*/*", "*/*"),
+        ("application/problem+json", "application/problem+json"),
+        (
+            "Application/Vnd.Acme+JSON ; Version=2",
+            "application/vnd.acme+json; version=2",
+        ),
+        (
+            "text/plain; format=flowed; charset=UTF-8",
+            "text/plain; charset=utf-8; format=flowed",
+        ),
+        (r#"text/plain; title="a b""#, r#"text/plain; title="a b""#),
+    ] {
+        let m: MediaType = input.parse().expect("expected a media type");
+        assert_eq!(m.to_string(), output);
+        assert_eq!(output.parse::<MediaType>().unwrap(), m);
+    }
+}
+
+#[test]
+fn media_type_compare() {
+    let parse = |s: &str| s.parse::<MediaType>().unwrap();
+    assert_eq!(
+        parse("application/vnd.acme+json; a=1; b=2"),
+        parse("APPLICATION/vnd.ACME+json;B=2;A=1")
+    );
+    assert_ne!(
+        parse("application/vnd.acme+json; a=x"),
+        parse("application/vnd.acme+json; a=X")
+    );
+    assert_ne!(parse("application/json"), parse("application/json; a=1"));
+    assert_ne!(parse("application/json"), parse("application/problem+json"));
+}
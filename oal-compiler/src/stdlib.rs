@@ -1,14 +1,29 @@
-use crate::definition::{Definition, Internal};
+use crate::definition::{Definition, Internal, InternalRef};
 use crate::env::Env;
 use crate::errors::Result;
-use crate::eval::{cast_uri, AnnRef, Expr, Value};
+use crate::errors::{Error, Kind};
+use crate::eval::{
+    cast_headers, cast_relation, cast_schema, cast_uri, check_response_headers, AnnRef, Expr, Value,
+};
 use crate::inference::tag;
-use oal_syntax::atom::Ident;
+use crate::spec::{Content, Object, PrimString, Property, Relation, SchemaExpr};
+use oal_model::locator::Locator;
+use oal_syntax::atom::{HttpStatus, Ident, Method};
+use std::fmt::{Display, Formatter};
+use std::num::NonZeroU16;
 use std::rc::Rc;
 
 #[repr(u32)]
 enum Identifier {
     Concat,
+    WithHeaders,
+    PatchOf,
+    Conditional,
+    WithParams,
+    Pick,
+    Omit,
+    Merge,
+    Optional,
 }
 
 #[derive(Debug)]
@@ -25,6 +40,10 @@ impl Internal for Concat {
 
     fn eval<'a>(&self, mut args: Vec<Value<'a>>, ann: AnnRef) -> Result<Value<'a>> {
         assert_eq!(args.len(), 2);
+        // A URI cannot be built from a point of recursion of its own definition.
+        if args.iter().any(|(e, _)| matches!(e, Expr::Recursion(_))) {
+            return Err(Error::new(Kind::InvalidType, "ill-formed recursion"));
+        }
         let right = cast_uri(args.pop().unwrap());
         let mut left = cast_uri(args.pop().unwrap());
         left.append(right);
@@ -39,14 +58,611 @@ impl Internal for Concat {
     fn id(&self) -> u32 {
         Identifier::Concat as u32
     }
+
+    fn name(&self) -> &'static str {
+        "concat"
+    }
+
+    fn signature(&self) -> &'static str {
+        "concat /left /right"
+    }
+
+    fn doc(&self) -> &'static str {
+        "Appends the path of the right URI to the path of the left URI. \
+        The parameters of the right URI replace those of the left URI."
+    }
+}
+
+/// Adds the default headers to a response, unless it declares a header of the same name.
+fn add_default_headers(content: &mut Content, defaults: &Object) {
+    let headers = content.headers.get_or_insert_with(Object::default);
+    for prop in defaults.props.iter() {
+        let name = prop.name.as_ref();
+        if !headers
+            .props
+            .iter()
+            .any(|p| p.name.as_ref().eq_ignore_ascii_case(name))
+        {
+            headers.props.push(prop.clone());
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct WithHeaders;
+
+impl Internal for WithHeaders {
+    fn tag(&self, _seq: &mut tag::Seq) -> tag::Tag {
+        let f = tag::FuncTag {
+            bindings: vec![tag::Tag::Object, tag::Tag::Relation],
+            range: Box::new(tag::Tag::Relation),
+        };
+        tag::Tag::Func(f)
+    }
+
+    fn eval<'a>(&self, mut args: Vec<Value<'a>>, ann: AnnRef) -> Result<Value<'a>> {
+        assert_eq!(args.len(), 2);
+        let mut rel = cast_relation(args.pop().unwrap());
+        let Some(defaults) = cast_headers(args.pop().unwrap()) else {
+            return Err(Error::new(Kind::InvalidType, "ill-formed headers"));
+        };
+        let xfers = rel.xfers.values_mut().flatten();
+        for xfer in xfers.chain(rel.custom_xfers.values_mut()) {
+            for content in xfer.ranges.values_mut() {
+                add_default_headers(content, &defaults);
+            }
+            check_response_headers(&xfer.ranges, None)?;
+        }
+        let expr = Expr::Relation(Box::new(rel));
+        Ok((expr, ann))
+    }
+
+    fn has_bindings(&self) -> bool {
+        true
+    }
+
+    fn id(&self) -> u32 {
+        Identifier::WithHeaders as u32
+    }
+
+    fn name(&self) -> &'static str {
+        "with_headers"
+    }
+
+    fn signature(&self) -> &'static str {
+        "with_headers {headers} /relation"
+    }
+
+    fn doc(&self) -> &'static str {
+        "Adds the headers to all the responses of the relation. \
+        The headers declared by a response take precedence over those of the same name."
+    }
+}
+
+/// The media type of JSON Merge Patch documents, see RFC 7396.
+const MERGE_PATCH_MEDIA_TYPE: &str = "application/merge-patch+json";
+
+/// Makes all the properties of an object optional and nullable, down into inline objects.
+fn merge_patch(obj: &mut Object) {
+    for prop in obj.props.iter_mut() {
+        prop.required = Some(false);
+        prop.schema.required = None;
+        prop.schema.nullable = Some(true);
+        if let SchemaExpr::Object(o) = &mut prop.schema.expr {
+            merge_patch(o);
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct PatchOf;
+
+impl Internal for PatchOf {
+    fn tag(&self, _seq: &mut tag::Seq) -> tag::Tag {
+        let f = tag::FuncTag {
+            bindings: vec![tag::Tag::Object],
+            range: Box::new(tag::Tag::Content),
+        };
+        tag::Tag::Func(f)
+    }
+
+    fn eval<'a>(&self, mut args: Vec<Value<'a>>, ann: AnnRef) -> Result<Value<'a>> {
+        assert_eq!(args.len(), 1);
+        let Some(mut obj) = cast_headers(args.pop().unwrap()) else {
+            return Err(Error::new(Kind::InvalidType, "ill-formed object"));
+        };
+        merge_patch(&mut obj);
+        let schema = cast_schema((Expr::Object(Box::new(obj)), AnnRef::default()));
+        let mut content = Content::from(schema);
+        content.media = Some(
+            MERGE_PATCH_MEDIA_TYPE
+                .parse()
+                .expect("merge patch media type should be valid"),
+        );
+        let expr = Expr::Content(Box::new(content));
+        Ok((expr, ann))
+    }
+
+    fn has_bindings(&self) -> bool {
+        true
+    }
+
+    fn id(&self) -> u32 {
+        Identifier::PatchOf as u32
+    }
+
+    fn name(&self) -> &'static str {
+        "patch_of"
+    }
+
+    fn signature(&self) -> &'static str {
+        "patch_of {object}"
+    }
+
+    fn doc(&self) -> &'static str {
+        "Derives the JSON Merge Patch content of the object, as `application/merge-patch+json`. \
+        All the properties become optional and nullable, including those of inline nested objects."
+    }
+}
+
+/// The request header carrying the entity tags known to the client, see RFC 9110.
+const IF_NONE_MATCH: &str = "If-None-Match";
+
+/// The response header carrying the entity tag of the representation, see RFC 9110.
+const ETAG: &str = "ETag";
+
+/// The status of a response to a conditional request whose condition does not hold.
+const NOT_MODIFIED: u16 = 304;
+
+/// Returns an optional string header of the given name and description.
+fn string_header(name: &str, desc: &str) -> Property {
+    property(name, string_primitive(), desc, false)
+}
+
+/// Adds conditional request support to the `get` transfer of a relation, if any.
+fn add_conditional(rel: &mut Relation) {
+    let Some(xfer) = rel.xfers[Method::Get].as_mut() else {
+        return;
+    };
+    let etag = Object {
+        props: vec![string_header(ETAG, "The entity tag of the representation.")],
+        ..Default::default()
+    };
+    let if_none_match = Object {
+        props: vec![string_header(
+            IF_NONE_MATCH,
+            "The entity tags of the representations known to the client.",
+        )],
+        ..Default::default()
+    };
+    add_default_headers(&mut xfer.domain, &if_none_match);
+    for ((status, _), content) in xfer.ranges.iter_mut() {
+        // The default response, without a status, stands for success.
+        if status.is_none_or(|s| s.is_success()) {
+            add_default_headers(content, &etag);
+        }
+    }
+    let code = NonZeroU16::new(NOT_MODIFIED).expect("status should not be zero");
+    let status = Some(HttpStatus::Code(code));
+    let not_modified = xfer
+        .ranges
+        .entry((status, None))
+        .or_insert_with(|| Content {
+            status,
+            desc: Some("Not Modified".to_owned()),
+            ..Default::default()
+        });
+    add_default_headers(not_modified, &etag);
+}
+
+#[derive(Debug)]
+pub struct Conditional;
+
+impl Internal for Conditional {
+    fn tag(&self, _seq: &mut tag::Seq) -> tag::Tag {
+        let f = tag::FuncTag {
+            bindings: vec![tag::Tag::Relation],
+            range: Box::new(tag::Tag::Relation),
+        };
+        tag::Tag::Func(f)
+    }
+
+    fn eval<'a>(&self, mut args: Vec<Value<'a>>, ann: AnnRef) -> Result<Value<'a>> {
+        assert_eq!(args.len(), 1);
+        let mut rel = cast_relation(args.pop().unwrap());
+        add_conditional(&mut rel);
+        let expr = Expr::Relation(Box::new(rel));
+        Ok((expr, ann))
+    }
+
+    fn has_bindings(&self) -> bool {
+        true
+    }
+
+    fn id(&self) -> u32 {
+        Identifier::Conditional as u32
+    }
+
+    fn name(&self) -> &'static str {
+        "conditional"
+    }
+
+    fn signature(&self) -> &'static str {
+        "conditional /relation"
+    }
+
+    fn doc(&self) -> &'static str {
+        "Supports conditional requests on the get transfer of the relation. \
+        The request accepts an optional `If-None-Match` header, the success and default responses \
+        declare an `ETag` header and a `304 Not Modified` response is added."
+    }
+}
+
+/// Adds the properties to an object, replacing those of the same name.
+fn replace_properties(obj: &mut Object, props: Vec<Property>) {
+    for prop in props {
+        match obj.props.iter_mut().find(|p| p.name == prop.name) {
+            Some(existing) => *existing = prop,
+            None => obj.props.push(prop),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct WithParams;
+
+impl Internal for WithParams {
+    fn tag(&self, _seq: &mut tag::Seq) -> tag::Tag {
+        let f = tag::FuncTag {
+            bindings: vec![tag::Tag::Object, tag::Tag::Uri],
+            range: Box::new(tag::Tag::Uri),
+        };
+        tag::Tag::Func(f)
+    }
+
+    fn eval<'a>(&self, mut args: Vec<Value<'a>>, ann: AnnRef) -> Result<Value<'a>> {
+        assert_eq!(args.len(), 2);
+        // A URI cannot be built from a point of recursion of its own definition.
+        if matches!(args[1].0, Expr::Recursion(_)) {
+            return Err(Error::new(Kind::InvalidType, "ill-formed recursion"));
+        }
+        let mut uri = cast_uri(args.pop().unwrap());
+        let Some(params) = cast_headers(args.pop().unwrap()) else {
+            return Err(Error::new(Kind::InvalidType, "ill-formed parameters"));
+        };
+        replace_properties(uri.params.get_or_insert_with(Object::default), params.props);
+        let expr = Expr::Uri(Box::new(uri));
+        Ok((expr, ann))
+    }
+
+    fn has_bindings(&self) -> bool {
+        true
+    }
+
+    fn id(&self) -> u32 {
+        Identifier::WithParams as u32
+    }
+
+    fn name(&self) -> &'static str {
+        "with_params"
+    }
+
+    fn signature(&self) -> &'static str {
+        "with_params {params} /uri"
+    }
+
+    fn doc(&self) -> &'static str {
+        "Adds the query parameters to the URI. \
+        The parameters replace those of the URI with the same name."
+    }
+}
+
+/// Returns the property names given as a string or an alternative of strings, e.g. `"id" | "name"`.
+fn property_names(arg: Value) -> Result<Vec<String>> {
+    match arg.0 {
+        Expr::String(name) => Ok(vec![name]),
+        Expr::PrimString(s) if !s.enumeration.is_empty() => Ok(s.enumeration),
+        Expr::Reference(_, v) => property_names(*v),
+        _ => Err(Error::new(
+            Kind::InvalidType,
+            "expected property names, e.g. \"id\" | \"name\"",
+        )),
+    }
+}
+
+/// Keeps or removes the named properties of an object, which must all be defined.
+fn project(args: Vec<Value>, keep: bool) -> Result<Object> {
+    let [names, obj] = <[Value; 2]>::try_from(args).expect("expected two arguments");
+    let names = property_names(names)?;
+    let Some(mut obj) = cast_headers(obj) else {
+        return Err(Error::new(Kind::InvalidType, "ill-formed object"));
+    };
+    if let Some(name) = names
+        .iter()
+        .find(|n| !obj.props.iter().any(|p| p.name.as_ref() == n.as_str()))
+    {
+        return Err(Error::new(
+            Kind::InvalidLiteral,
+            format!("unknown property {name}"),
+        ));
+    }
+    obj.props
+        .retain(|p| names.iter().any(|n| n.as_str() == p.name.as_ref()) == keep);
+    Ok(obj)
+}
+
+#[derive(Debug)]
+pub struct Pick;
+
+impl Internal for Pick {
+    fn tag(&self, seq: &mut tag::Seq) -> tag::Tag {
+        let f = tag::FuncTag {
+            bindings: vec![tag::Tag::Var(seq.next()), tag::Tag::Object],
+            range: Box::new(tag::Tag::Object),
+        };
+        tag::Tag::Func(f)
+    }
+
+    fn eval<'a>(&self, args: Vec<Value<'a>>, ann: AnnRef) -> Result<Value<'a>> {
+        let obj = project(args, true)?;
+        Ok((Expr::Object(Box::new(obj)), ann))
+    }
+
+    fn has_bindings(&self) -> bool {
+        true
+    }
+
+    fn id(&self) -> u32 {
+        Identifier::Pick as u32
+    }
+
+    fn name(&self) -> &'static str {
+        "pick"
+    }
+
+    fn signature(&self) -> &'static str {
+        "pick (\"a\" | \"b\") {object}"
+    }
+
+    fn doc(&self) -> &'static str {
+        "Keeps the named properties of the object only. \
+        Each name must be that of a property of the object."
+    }
+}
+
+#[derive(Debug)]
+pub struct Omit;
+
+impl Internal for Omit {
+    fn tag(&self, seq: &mut tag::Seq) -> tag::Tag {
+        let f = tag::FuncTag {
+            bindings: vec![tag::Tag::Var(seq.next()), tag::Tag::Object],
+            range: Box::new(tag::Tag::Object),
+        };
+        tag::Tag::Func(f)
+    }
+
+    fn eval<'a>(&self, args: Vec<Value<'a>>, ann: AnnRef) -> Result<Value<'a>> {
+        let obj = project(args, false)?;
+        Ok((Expr::Object(Box::new(obj)), ann))
+    }
+
+    fn has_bindings(&self) -> bool {
+        true
+    }
+
+    fn id(&self) -> u32 {
+        Identifier::Omit as u32
+    }
+
+    fn name(&self) -> &'static str {
+        "omit"
+    }
+
+    fn signature(&self) -> &'static str {
+        "omit (\"a\" | \"b\") {object}"
+    }
+
+    fn doc(&self) -> &'static str {
+        "Removes the named properties from the object. \
+        Each name must be that of a property of the object."
+    }
+}
+
+/// Merges the properties of the right object into the left one,
+/// recursively into the inline objects of properties defined by both.
+///
+/// A property defined by both is taken from the right object, flags and annotations included,
+/// only its inline object being merged with that of the left object.
+fn merge_objects(left: &mut Object, right: Object) {
+    for mut prop in right.props {
+        let Some(existing) = left.props.iter_mut().find(|p| p.name == prop.name) else {
+            left.props.push(prop);
+            continue;
+        };
+        if let (SchemaExpr::Object(l), SchemaExpr::Object(r)) =
+            (&mut existing.schema.expr, &mut prop.schema.expr)
+        {
+            let mut merged = std::mem::take(l);
+            merge_objects(&mut merged, std::mem::take(r));
+            prop.schema.expr = SchemaExpr::Object(merged);
+        }
+        *existing = prop;
+    }
+    if right.additional_props.is_some() {
+        left.additional_props = right.additional_props;
+    }
+}
+
+#[derive(Debug)]
+pub struct Merge;
+
+impl Internal for Merge {
+    fn tag(&self, _seq: &mut tag::Seq) -> tag::Tag {
+        let f = tag::FuncTag {
+            bindings: vec![tag::Tag::Object, tag::Tag::Object],
+            range: Box::new(tag::Tag::Object),
+        };
+        tag::Tag::Func(f)
+    }
+
+    fn eval<'a>(&self, mut args: Vec<Value<'a>>, ann: AnnRef) -> Result<Value<'a>> {
+        assert_eq!(args.len(), 2);
+        let right = cast_headers(args.pop().unwrap());
+        let left = cast_headers(args.pop().unwrap());
+        let (Some(mut left), Some(right)) = (left, right) else {
+            return Err(Error::new(Kind::InvalidType, "ill-formed object"));
+        };
+        merge_objects(&mut left, right);
+        Ok((Expr::Object(Box::new(left)), ann))
+    }
+
+    fn has_bindings(&self) -> bool {
+        true
+    }
+
+    fn id(&self) -> u32 {
+        Identifier::Merge as u32
+    }
+
+    fn name(&self) -> &'static str {
+        "merge"
+    }
+
+    fn signature(&self) -> &'static str {
+        "merge {left} {right}"
+    }
+
+    fn doc(&self) -> &'static str {
+        "Merges the properties of the right object into the left object. \
+        Properties defined by both are taken from the right object, \
+        except inline objects which are merged in turn."
+    }
+}
+
+#[derive(Debug)]
+pub struct Optional;
+
+impl Internal for Optional {
+    fn tag(&self, _seq: &mut tag::Seq) -> tag::Tag {
+        let f = tag::FuncTag {
+            bindings: vec![tag::Tag::Object],
+            range: Box::new(tag::Tag::Object),
+        };
+        tag::Tag::Func(f)
+    }
+
+    fn eval<'a>(&self, mut args: Vec<Value<'a>>, ann: AnnRef) -> Result<Value<'a>> {
+        assert_eq!(args.len(), 1);
+        let Some(mut obj) = cast_headers(args.pop().unwrap()) else {
+            return Err(Error::new(Kind::InvalidType, "ill-formed object"));
+        };
+        for prop in obj.props.iter_mut() {
+            prop.required = Some(false);
+            prop.schema.required = None;
+        }
+        Ok((Expr::Object(Box::new(obj)), ann))
+    }
+
+    fn has_bindings(&self) -> bool {
+        true
+    }
+
+    fn id(&self) -> u32 {
+        Identifier::Optional as u32
+    }
+
+    fn name(&self) -> &'static str {
+        "optional"
+    }
+
+    fn signature(&self) -> &'static str {
+        "optional {object}"
+    }
+
+    fn doc(&self) -> &'static str {
+        "Makes all the properties of the object optional."
+    }
+}
+
+/// Returns a property of the given name, schema and description.
+fn property(name: &str, expr: Expr, desc: &str, required: bool) -> Property {
+    Property {
+        name: name.into(),
+        schema: cast_schema((expr, AnnRef::default())),
+        desc: Some(desc.to_owned()),
+        translations: Default::default(),
+        required: Some(required),
+        style: None,
+        explode: None,
+        content_type: None,
+        deprecated: None,
+    }
+}
+
+/// Returns the expression of a string primitive, i.e. `str`.
+fn string_primitive<'a>() -> Expr<'a> {
+    Expr::PrimString(Box::<PrimString>::default())
+}
+
+/// Returns all the internal definitions of the standard library.
+pub fn internals() -> Vec<InternalRef> {
+    vec![
+        Rc::new(Concat {}),
+        Rc::new(WithHeaders {}),
+        Rc::new(PatchOf {}),
+        Rc::new(Conditional {}),
+        Rc::new(WithParams {}),
+        Rc::new(Pick {}),
+        Rc::new(Omit {}),
+        Rc::new(Merge {}),
+        Rc::new(Optional {}),
+    ]
+}
+
+/// The documentation of an internal definition.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Documentation {
+    pub name: &'static str,
+    pub signature: &'static str,
+    pub tag: String,
+    pub doc: &'static str,
+}
+
+impl Documentation {
+    pub fn new(internal: &dyn Internal) -> Result<Self> {
+        let loc = Locator::try_from("oal:std").map_err(oal_model::locator::Error::from)?;
+        let tag = internal.tag(&mut tag::Seq::new(loc)).to_string();
+        Ok(Documentation {
+            name: internal.name(),
+            signature: internal.signature(),
+            tag,
+            doc: internal.doc(),
+        })
+    }
+}
+
+impl Display for Documentation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{}: {}", self.name, self.tag)?;
+        writeln!(f, "    {}", self.signature)?;
+        write!(f, "{}", self.doc)
+    }
+}
+
+/// Returns the documentation of all the internal definitions of the standard library.
+pub fn documentation() -> Result<Vec<Documentation>> {
+    internals()
+        .iter()
+        .map(|i| Documentation::new(i.as_ref()))
+        .collect()
 }
 
 /// Imports the standard library into the given environment.
 pub fn import(env: &mut Env) -> Result<()> {
-    let internals = [("concat", Rc::new(Concat {}))];
-    for i in internals.into_iter() {
-        let entry = Ident::from(i.0).into();
-        env.declare(entry, Definition::Internal(i.1));
+    for i in internals().into_iter() {
+        let entry = Ident::from(i.name()).into();
+        env.declare(entry, Definition::Internal(i));
     }
     Ok(())
 }
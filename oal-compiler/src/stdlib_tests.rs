@@ -26,3 +26,18 @@ fn concat() {
     };
     assert_eq!(uri.pattern(), "/a/b");
 }
+
+#[test]
+fn documentation() {
+    let docs = stdlib::documentation().expect("documentation should be available");
+    let concat = docs
+        .iter()
+        .find(|d| d.name == "concat")
+        .expect("expected concat documentation");
+    assert_eq!(concat.tag, "function[uri -> uri -> uri]");
+    assert!(concat.signature.starts_with("concat "));
+    assert!(!concat.doc.is_empty());
+    assert!(concat
+        .to_string()
+        .starts_with("concat: function[uri -> uri -> uri]\n"));
+}
@@ -1,3 +1,4 @@
+use crate::definition::value_span;
 use crate::errors::{Error, Kind, Result};
 use crate::inference::tag::Tag;
 use crate::module::ModuleSet;
@@ -26,6 +27,7 @@ impl TagWrap {
                 | Tag::Array
                 | Tag::Uri
                 | Tag::Any
+                | Tag::Literal
                 | Tag::Var(_)
         )
     }
@@ -74,12 +76,18 @@ fn get_tag(n: NRef) -> TagWrap {
 fn check_variadic_operation(op: syn::VariadicOp<Core>) -> Result<()> {
     match op.operator() {
         atom::VariadicOperator::Join => {
-            if !op.operands().all(|o| get_tag(o).is_object()) {
-                return Err(Error::new(Kind::InvalidType, "ill-formed join").with(&op));
+            if let Some(operand) = op.operands().find(|o| !get_tag(*o).is_object()) {
+                let msg = format!("a join only combines objects, not {}", get_tag(operand).0);
+                return Err(Error::new(Kind::InvalidType, msg)
+                    .with(&op)
+                    .at(operand.span())
+                    .with_label(op.node().span(), "in this join"));
             }
         }
         atom::VariadicOperator::Any | atom::VariadicOperator::Sum => {
-            if !op.operands().all(|o| get_tag(o).is_schema()) {
+            // The operands of an alternative between literal values are the values themselves.
+            let literal = get_tag(op.node()).0 == Tag::Literal;
+            if !literal && !op.operands().all(|o| get_tag(o).is_schema()) {
                 return Err(Error::new(Kind::InvalidType, "ill-formed alternative").with(&op));
             }
         }
@@ -103,25 +111,20 @@ fn check_unary_operation(op: syn::UnaryOp<Core>) -> Result<()> {
     Ok(())
 }
 
-fn check_content(content: syn::Content<Core>) -> Result<()> {
+fn check_content(mods: &ModuleSet, content: syn::Content<Core>) -> Result<()> {
     for meta in content.meta().into_iter().flatten() {
-        match meta.kind() {
-            syn::ContentTagKind::Media => {
-                if !get_tag(meta.rhs()).is_text() {
-                    return Err(Error::new(Kind::InvalidType, "ill-formed media").with(&meta));
-                }
-            }
-            syn::ContentTagKind::Headers => {
-                if !get_tag(meta.rhs()).is_schema() {
-                    return Err(Error::new(Kind::InvalidType, "ill-formed headers").with(&meta));
-                }
-            }
-            syn::ContentTagKind::Status => {
-                if !get_tag(meta.rhs()).is_status_like() {
-                    return Err(Error::new(Kind::InvalidType, "ill-formed status").with(&meta));
-                }
-            }
-        }
+        let rhs = get_tag(meta.rhs());
+        let msg = match meta.kind() {
+            syn::ContentTagKind::Media if !rhs.is_text() => "ill-formed media",
+            syn::ContentTagKind::Headers if !rhs.is_schema() => "ill-formed headers",
+            syn::ContentTagKind::Status if !rhs.is_status_like() => "ill-formed status",
+            _ => continue,
+        };
+        // Point at the meta value rather than the whole content.
+        return Err(Error::new(Kind::InvalidType, msg)
+            .with(&meta)
+            .at(meta.rhs().span())
+            .with_label(value_span(mods, meta.rhs()), "value defined here"));
     }
     if let Some(body) = content.body() {
         if !get_tag(body).is_schema() {
@@ -203,8 +206,7 @@ fn check_resource(res: syn::Resource<Core>) -> Result<()> {
 
 fn check_recursion(rec: syn::Recursion<Core>) -> Result<()> {
     let tag = get_tag(rec.node());
-    // TODO: support for recursive URI definitions (i.e. self-reference via query string)
-    if !tag.is_schema() || tag.is_uri() {
+    if !tag.is_schema() {
         return Err(Error::new(Kind::InvalidType, "ill-formed recursion, not a schema").with(&rec));
     }
     Ok(())
@@ -220,7 +222,7 @@ pub fn type_check(mods: &ModuleSet, loc: &Locator) -> Result<()> {
         } else if let Some(operation) = syn::UnaryOp::cast(node) {
             check_unary_operation(operation)
         } else if let Some(content) = syn::Content::cast(node) {
-            check_content(content)
+            check_content(mods, content)
         } else if let Some(xfer) = syn::Transfer::cast(node) {
             check_transfer(xfer)
         } else if let Some(relation) = syn::Relation::cast(node) {
@@ -242,7 +244,10 @@ pub fn type_check(mods: &ModuleSet, loc: &Locator) -> Result<()> {
         } else {
             Ok(())
         }
-        .map_err(|err| err.at(node.span()))?;
+        .map_err(|err| match err.span() {
+            Some(_) => err,
+            None => err.at(node.span()),
+        })?;
     }
 
     Ok(())
@@ -274,8 +279,7 @@ pub fn cycles_check(mut graph: Graph, mods: &ModuleSet) -> Result<()> {
                 let tag = get_tag(node);
                 // Flag incoming edges to referential definitions for removal,
                 // as those definitions evaluate to references.
-                // TODO: support for recursive URI definitions (i.e. self-reference via query string)
-                if tag.is_schema() && !tag.is_uri() {
+                if tag.is_schema() {
                     node.syntax().core_mut().is_recursive = true;
                     for e in graph.edges_directed(*index, Incoming) {
                         inbounds.push(e.id())
@@ -36,6 +36,8 @@ fn typecheck_ok() {
         "let a = ('prop str) !;",
         "let a = (<> :: <>) :: <>;",
         "res (rec x (/ on get -> { 'self x }));",
+        "let a = rec x (/a?{ 'next x });",
+        r#"let a = { 'status ("on" | "off" | 0) };"#,
     ];
 
     for c in cases {
@@ -59,7 +61,8 @@ fn typecheck_error() {
         "let a = str !;",
         "res / on (rec x (get -> { 'self uri }));",
         "let f a = {} & (f { 'p a });",
-        "let a = rec x (concat /a x);",
+        r#"let a = "on" | str;"#,
+        r#"let a = <media=("text/plain" | "text/html"), {}>;"#,
     ];
 
     for c in cases {
@@ -73,3 +76,57 @@ fn typecheck_error() {
         ));
     }
 }
+
+#[test]
+fn typecheck_error_value_span() -> anyhow::Result<()> {
+    let code = r#"let s = "x"; let a = <status=s, {}>;"#;
+    let err = compile(code).expect_err("expected an error");
+    let err = err
+        .downcast_ref::<errors::Error>()
+        .expect("expected compiler error");
+
+    assert!(matches!(err.kind, errors::Kind::InvalidType));
+    assert_eq!(err.span().expect("expected a span").range(), 29..30);
+    let [(span, _)] = err.labels() else {
+        panic!("expected a single label")
+    };
+    assert_eq!(span.range(), 8..11);
+
+    Ok(())
+}
+
+#[test]
+fn typecheck_error_operands() -> anyhow::Result<()> {
+    let cases = [
+        ("let a = num & {};", "objects, not primitive", 8..11, 8..16),
+        (
+            "let b = int; let a = {} & b;",
+            "objects, not primitive",
+            26..27,
+            21..27,
+        ),
+        ("let a = int & str;", "objects, not primitive", 8..11, 8..17),
+        (
+            "let a = int | {};",
+            "object in a sum of kind primitive",
+            14..16,
+            8..16,
+        ),
+    ];
+
+    for (code, msg, range, op) in cases {
+        let err = compile(code).expect_err("expected an error");
+        let err = err
+            .downcast_ref::<errors::Error>()
+            .expect("expected compiler error");
+        assert!(matches!(err.kind, errors::Kind::InvalidType));
+        assert!(err.to_string().ends_with(msg), "unexpected error: {err}");
+        assert_eq!(err.span().expect("expected a span").range(), range);
+        let [(span, _)] = err.labels() else {
+            panic!("expected the operation as label")
+        };
+        assert_eq!(span.range(), op);
+    }
+
+    Ok(())
+}
@@ -74,13 +74,13 @@ impl<T: Core, G: Grammar> SyntaxNode<T, G> {
         self.1.borrow().is_some()
     }
 
-    pub fn core_ref(&self) -> Ref<T> {
+    pub fn core_ref(&self) -> Ref<'_, T> {
         Ref::map(self.1.borrow(), |r| {
             r.as_ref().expect("core should exist").as_ref()
         })
     }
 
-    pub fn core_mut(&self) -> RefMut<T> {
+    pub fn core_mut(&self) -> RefMut<'_, T> {
         RefMut::map(self.1.borrow_mut(), |r| {
             r.get_or_insert_with(Box::default).as_mut()
         })
@@ -133,7 +133,7 @@ impl<T: Core, G: Grammar> SyntaxTree<T, G> {
         self
     }
 
-    pub fn root(&self) -> NodeRef<T, G> {
+    pub fn root(&self) -> NodeRef<'_, T, G> {
         NodeRef::from(self, self.root.unwrap())
     }
 
@@ -470,6 +470,9 @@ pub enum ParserMatch<G: Grammar> {
 /// A result from a parser function.
 pub type ParserResult<G> = std::result::Result<(Cursor, ParserMatch<G>), ParserError>;
 
+/// The default maximum nesting depth of parsing expressions.
+pub const DEFAULT_MAX_DEPTH: usize = 256;
+
 /// A syntax analysis context.
 pub struct Context<T: Core, G: Grammar> {
     tree: SyntaxTree<T, G>,
@@ -477,6 +480,10 @@ pub struct Context<T: Core, G: Grammar> {
     hits: Cell<usize>,
     reads: Cell<usize>,
     no_cache: bool,
+    depth: usize,
+    max_depth: usize,
+    overflow: Option<ParserError>,
+    bound: Option<Cursor>,
 }
 
 impl<T: Core, G: Grammar> Context<T, G> {
@@ -487,6 +494,10 @@ impl<T: Core, G: Grammar> Context<T, G> {
             hits: Cell::new(0),
             reads: Cell::new(0),
             no_cache: false,
+            depth: 0,
+            max_depth: DEFAULT_MAX_DEPTH,
+            overflow: None,
+            bound: None,
         }
     }
 
@@ -495,6 +506,67 @@ impl<T: Core, G: Grammar> Context<T, G> {
         self
     }
 
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Enters a nested production, failing if the maximum depth is reached.
+    ///
+    /// The first such failure is retained as it cannot be recovered from by backtracking.
+    pub fn enter(&mut self, s: Cursor) -> std::result::Result<(), ParserError> {
+        if self.depth >= self.max_depth {
+            let err = ParserError::new("maximum nesting depth exceeded", self.span(s));
+            self.overflow.get_or_insert_with(|| err.clone());
+            Err(err)
+        } else {
+            self.depth += 1;
+            Ok(())
+        }
+    }
+
+    /// Leaves a nested production.
+    pub fn leave(&mut self) {
+        self.depth -= 1;
+    }
+
+    /// Returns the error raised when the maximum nesting depth was exceeded, if any.
+    pub fn overflow(&self) -> Option<&ParserError> {
+        self.overflow.as_ref()
+    }
+
+    /// Runs a production as if the input ended at the given cursor.
+    ///
+    /// Bounded results depend on the bound so they bypass the cache.
+    pub fn bounded<R, F>(&mut self, end: Cursor, f: F) -> R
+    where
+        F: FnOnce(&mut Self) -> R,
+    {
+        let prev = self.bound.replace(end);
+        let r = f(self);
+        self.bound = prev;
+        r
+    }
+
+    /// Returns the last significant token between two cursors, the end excluded.
+    pub fn last_token(&self, mut start: Cursor, end: Cursor) -> Option<TokenAlias<G::Lex>> {
+        let mut last = None;
+        while start.is_valid() && start != end {
+            last = self.peek(start);
+            start = self.skip_trivia(self.tree.tokens.advance(start));
+        }
+        last
+    }
+
+    /// Returns the text of the token at the given cursor.
+    pub fn text(&self, s: Cursor) -> &str {
+        self.tree
+            .tokens
+            .reference(s)
+            .value()
+            .as_str(&self.tree.tokens)
+    }
+
     pub fn head(&self) -> Cursor {
         self.skip_trivia(self.tree.tokens.head())
     }
@@ -513,13 +585,13 @@ impl<T: Core, G: Grammar> Context<T, G> {
     }
 
     pub fn cache(&mut self, p: G::Tag, s: Cursor, r: ParserResult<G>) {
-        if !self.no_cache {
+        if !self.no_cache && self.bound.is_none() {
             self.cache.insert((s, p), r);
         }
     }
 
     pub fn lookup(&mut self, p: G::Tag, s: Cursor) -> Option<ParserResult<G>> {
-        if self.no_cache {
+        if self.no_cache || self.bound.is_some() {
             return None;
         }
         let hit = self.cache.get(&(s, p)).cloned();
@@ -562,7 +634,7 @@ impl<T: Core, G: Grammar> Context<T, G> {
     }
 
     fn peek(&self, s: Cursor) -> Option<TokenAlias<G::Lex>> {
-        if s.is_valid() {
+        if s.is_valid() && self.bound != Some(s) {
             Some(self.tree.tokens.alias(s))
         } else {
             None
@@ -584,6 +656,7 @@ impl<T: Core, G: Grammar> Debug for Context<T, G> {
             .field("input_reads", &self.reads.get())
             .field("cache_hits", &self.hits.get())
             .field("cache_size", &self.cache.len())
+            .field("max_depth", &self.max_depth)
             .finish()
     }
 }
@@ -677,3 +750,21 @@ pub fn parse_token<T: Core, G: Grammar>(
 ) -> ParserResult<G> {
     parse_token_with(c, s, |k| *k == kind)
 }
+
+/// Parses a token of the given kind and text, e.g. a contextual keyword lexed as an identifier.
+pub fn parse_word<T: Core, G: Grammar>(
+    c: &mut Context<T, G>,
+    s: Cursor,
+    kind: <G::Lex as Lexeme>::Kind,
+    word: &str,
+) -> ParserResult<G> {
+    match c.pop(s) {
+        Some((next, t)) if t.kind() == kind && c.text(s) == word => {
+            Ok((next, ParserMatch::Token(t)))
+        }
+        _ => Err(ParserError::new(
+            "unexpected token or end of input",
+            c.span(s),
+        )),
+    }
+}
@@ -12,11 +12,86 @@ pub enum Error {
 }
 
 /// A file locator backed by a URL.
+///
+/// URLs are normalized on construction so that equal locations compare equal:
+/// percent-encoded unreserved characters are decoded, other percent-encodings use
+/// uppercase hexadecimal digits, and Windows drive letters in file URLs are uppercase.
 #[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Locator {
     url: Arc<Url>,
 }
 
+fn is_unreserved(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b"-._~".contains(&b)
+}
+
+/// Normalizes the percent-encodings of a URL path.
+fn normalize_encoding(path: &str) -> String {
+    let bytes = path.as_bytes();
+    let mut out = String::with_capacity(path.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes.get(i + 1..i + 3).filter(|_| bytes[i] == b'%');
+        let decoded = hex
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match decoded {
+            Some(b) if is_unreserved(b) => {
+                out.push(b as char);
+                i += 3;
+            }
+            Some(_) => {
+                out.push('%');
+                out.push_str(&path[i + 1..i + 3].to_ascii_uppercase());
+                i += 3;
+            }
+            None => {
+                let c = path[i..]
+                    .chars()
+                    .next()
+                    .expect("index should be a char boundary");
+                out.push(c);
+                i += c.len_utf8();
+            }
+        }
+    }
+    out
+}
+
+/// Uppercases the Windows drive letter of a file URL path, e.g. `/c%3A/a` becomes `/C:/a`.
+fn normalize_drive(path: &str) -> Option<String> {
+    let rest = path.strip_prefix('/')?;
+    let letter = rest.chars().next().filter(char::is_ascii_alphabetic)?;
+    let rest = &rest[1..];
+    let rest = rest
+        .strip_prefix(':')
+        .or_else(|| rest.strip_prefix("%3A"))?;
+    if rest.is_empty() || rest.starts_with('/') {
+        Some(format!("/{}:{rest}", letter.to_ascii_uppercase()))
+    } else {
+        None
+    }
+}
+
+fn normalize(mut url: Url) -> Url {
+    let mut path = normalize_encoding(url.path());
+    if url.scheme() == "file" {
+        if let Some(p) = normalize_drive(&path) {
+            path = p;
+        }
+    }
+    if path != url.path() {
+        url.set_path(&path);
+    }
+    url
+}
+
+/// Returns true if the path is an absolute Windows path, e.g. `C:\a` or `C:/a`.
+fn is_windows_absolute(path: &str) -> bool {
+    let b = path.as_bytes();
+    b.len() >= 3 && b[0].is_ascii_alphabetic() && b[1] == b':' && (b[2] == b'\\' || b[2] == b'/')
+}
+
 impl Locator {
     /// Returns a reference to the underlying URL.
     pub fn url(&self) -> &Url {
@@ -31,13 +106,37 @@ impl Locator {
         Locator { url: Arc::new(url) }
     }
 
-    /// Appends a relative path to the locator base.
+    /// Resolves a path against the locator base.
+    ///
+    /// The path is either relative, e.g. `./common.oal` or `../shared/x.oal`,
+    /// absolute, e.g. `/shared/x.oal` or `C:\\shared\\x.oal`, or a complete URL.
+    /// Dot segments are removed and backslashes separate segments in file locators.
     pub fn join(&self, path: &str) -> Result<Self, Error> {
         if path.is_empty() {
             Err(Error::EmptyPath)
+        } else if is_windows_absolute(path) {
+            let root = Url::parse("file:///")?;
+            let url = root.join(&format!("/{}", path.replace('\\', "/")))?;
+            Ok(Locator::from(url))
         } else {
-            let url = self.url.join(path).map(Arc::new)?;
-            Ok(Locator { url })
+            let url = self.url.join(path)?;
+            Ok(Locator::from(url))
+        }
+    }
+
+    /// Returns the relative path from the given base to this locator, if any.
+    ///
+    /// The base is a directory if it ends with a separator, otherwise a file.
+    /// Joining the resulting path to the base gives this locator back.
+    pub fn relative_to(&self, base: &Locator) -> Option<String> {
+        let rel = base.url.make_relative(&self.url)?;
+        if !rel.is_empty() {
+            return Some(rel);
+        }
+        // The URL crate returns an empty path for equal URLs, which cannot be joined.
+        match self.url.path_segments()?.next_back() {
+            Some(name) if !name.is_empty() => Some(name.to_owned()),
+            _ => Some("./".to_owned()),
         }
     }
 }
@@ -50,7 +149,9 @@ impl Debug for Locator {
 
 impl From<Url> for Locator {
     fn from(url: Url) -> Self {
-        Locator { url: Arc::new(url) }
+        Locator {
+            url: Arc::new(normalize(url)),
+        }
     }
 }
 
@@ -58,8 +159,8 @@ impl TryFrom<&str> for Locator {
     type Error = url::ParseError;
 
     fn try_from(s: &str) -> Result<Self, Self::Error> {
-        let url = Url::parse(s).map(Arc::new)?;
-        Ok(Locator { url })
+        let url = Url::parse(s)?;
+        Ok(Locator::from(url))
     }
 }
 
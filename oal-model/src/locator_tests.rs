@@ -21,3 +21,76 @@ fn locator_join() {
     let err = loc.join("").expect_err("expected an error");
     assert!(matches!(err, Error::EmptyPath));
 }
+
+#[test]
+fn locator_join_relative() {
+    let loc = Locator::try_from("file:///a/b/main.oal").expect("expected a locator");
+    for (path, expected) in [
+        ("common.oal", "file:///a/b/common.oal"),
+        ("./common.oal", "file:///a/b/common.oal"),
+        ("../shared/x.oal", "file:///a/shared/x.oal"),
+        ("../../../x.oal", "file:///x.oal"),
+        ("./lib/../common.oal", "file:///a/b/common.oal"),
+        ("/shared/x.oal", "file:///shared/x.oal"),
+        ("lib\\common.oal", "file:///a/b/lib/common.oal"),
+        ("..\\shared\\x.oal", "file:///a/shared/x.oal"),
+        ("my file.oal", "file:///a/b/my%20file.oal"),
+        ("my%20file.oal", "file:///a/b/my%20file.oal"),
+        ("%7Euser/%61.oal", "file:///a/b/~user/a.oal"),
+        ("a%2fb.oal", "file:///a/b/a%2Fb.oal"),
+        ("C:\\shared\\x.oal", "file:///C:/shared/x.oal"),
+        ("d:/shared/x.oal", "file:///D:/shared/x.oal"),
+        ("https://example.com/x.oal", "https://example.com/x.oal"),
+    ] {
+        let joined = loc.join(path).expect("expected a locator");
+        assert_eq!(joined.url().as_str(), expected, "joining {path:?}");
+    }
+}
+
+#[test]
+fn locator_normalization() {
+    for (left, right) in [
+        ("file:///a/%7Eb.oal", "file:///a/~b.oal"),
+        ("file:///a/%2fb.oal", "file:///a/%2Fb.oal"),
+        ("file:///c%3A/a.oal", "file:///C:/a.oal"),
+        ("file:///c:/a.oal", "file:///C:/a.oal"),
+        ("file:///a/./b/../c.oal", "file:///a/c.oal"),
+    ] {
+        let left = Locator::try_from(left).expect("expected a locator");
+        let right = Locator::try_from(right).expect("expected a locator");
+        assert_eq!(left, right);
+        assert_eq!(left.url().as_str(), right.url().as_str());
+    }
+
+    let loc = Locator::try_from("file:///a/b:c/d%3Ae.oal").expect("expected a locator");
+    assert_eq!(loc.url().as_str(), "file:///a/b:c/d%3Ae.oal");
+}
+
+#[test]
+fn locator_relative_to() {
+    let base = Locator::try_from("file:///a/b/main.oal").expect("expected a locator");
+    let dir = Locator::try_from("file:///a/b/").expect("expected a locator");
+    for path in [
+        "common.oal",
+        "lib/common.oal",
+        "../shared/x.oal",
+        "../../x.oal",
+        "my%20file.oal",
+        "C:/x.oal",
+    ] {
+        for base in [&base, &dir] {
+            let loc = base.join(path).expect("expected a locator");
+            let rel = loc.relative_to(base).expect("expected a relative path");
+            assert_eq!(base.join(&rel).unwrap(), loc, "round-trip of {path:?}");
+        }
+    }
+
+    let loc = base.join("../shared/x.oal").unwrap();
+    assert_eq!(loc.relative_to(&base).as_deref(), Some("../shared/x.oal"));
+    assert_eq!(base.relative_to(&base).as_deref(), Some("main.oal"));
+    assert_eq!(dir.relative_to(&dir).as_deref(), Some("./"));
+    assert_eq!(dir.join("./").unwrap(), dir);
+
+    let other = Locator::try_from("https://example.com/x.oal").expect("expected a locator");
+    assert_eq!(other.relative_to(&base), None);
+}
@@ -0,0 +1,73 @@
+use indexmap::IndexMap;
+use openapiv3::ReferenceOr;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+/// Returns a valid component name for a parameter, header or example name.
+fn component_name(name: &str) -> String {
+    let name = name
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '.' | '-' | '_' => c,
+            _ => '_',
+        })
+        .collect::<String>();
+    if name.is_empty() {
+        "param".to_owned()
+    } else {
+        name
+    }
+}
+
+/// Returns the key of an item, as identical items have identical serializations.
+pub(crate) fn key<T: Serialize>(item: &T) -> String {
+    serde_json::to_string(item).expect("component should serialize")
+}
+
+/// The components of a kind shared by identical items.
+///
+/// Components are named after their items, with a numeric suffix
+/// to avoid conflicts with the existing names.
+pub(crate) struct Shared<'a, T> {
+    kind: &'static str,
+    existing: &'a mut IndexMap<String, ReferenceOr<T>>,
+    taken: HashSet<String>,
+    names: HashMap<String, String>,
+}
+
+impl<'a, T: Clone> Shared<'a, T> {
+    /// Shares items as the components of the given kind, e.g. `parameters`.
+    pub(crate) fn new(
+        kind: &'static str,
+        existing: &'a mut IndexMap<String, ReferenceOr<T>>,
+    ) -> Self {
+        let taken = existing.keys().cloned().collect();
+        Shared {
+            kind,
+            existing,
+            taken,
+            names: HashMap::new(),
+        }
+    }
+
+    /// Returns a reference to the component of an item of the given key,
+    /// adding the component under the given name on first use.
+    pub(crate) fn reference(&mut self, key: String, name: &str, item: &T) -> ReferenceOr<T> {
+        let name = self.names.entry(key).or_insert_with(|| {
+            let base = component_name(name);
+            let mut component = base.clone();
+            let mut n = 1;
+            while self.taken.contains(&component) {
+                n += 1;
+                component = format!("{base}_{n}");
+            }
+            self.taken.insert(component.clone());
+            self.existing
+                .insert(component.clone(), ReferenceOr::Item(item.clone()));
+            component
+        });
+        ReferenceOr::Reference {
+            reference: format!("#/components/{}/{name}", self.kind),
+        }
+    }
+}
@@ -0,0 +1,69 @@
+use crate::components::{self, Shared};
+use crate::params::for_each_operation;
+use indexmap::IndexMap;
+use openapiv3::{Example, MediaType, Operation, PathItem, ReferenceOr};
+use std::collections::HashMap;
+
+type Contents = IndexMap<String, MediaType>;
+
+/// Applies a function to the contents of every request body and response of a path item.
+fn for_each_contents<F: FnMut(&mut Contents)>(item: &mut PathItem, f: &mut F) {
+    for_each_operation(item, &mut |op: &mut Operation| {
+        if let Some(ReferenceOr::Item(body)) = op.request_body.as_mut() {
+            f(&mut body.content);
+        }
+        let responses = op.responses.default.iter_mut();
+        for res in responses.chain(op.responses.responses.values_mut()) {
+            if let ReferenceOr::Item(res) = res {
+                f(&mut res.content);
+            }
+        }
+    });
+}
+
+/// Returns the key of a named example, as identical examples have identical serializations.
+fn key(name: &str, e: &Example) -> String {
+    format!("{name}:{}", components::key(e))
+}
+
+/// Replaces named examples occurring more than once by references to shared components,
+/// e.g. the examples of a schema used by both a request and a response.
+///
+/// Components are named after their examples, with a numeric suffix
+/// to avoid conflicts with the given existing names.
+pub(crate) fn share<'a, I>(items: I, existing: &mut IndexMap<String, ReferenceOr<Example>>)
+where
+    I: IntoIterator<Item = &'a mut PathItem>,
+{
+    let mut items = items.into_iter().collect::<Vec<_>>();
+
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for item in items.iter_mut() {
+        for_each_contents(item, &mut |contents: &mut Contents| {
+            for media in contents.values() {
+                for (name, e) in media.examples.iter() {
+                    if let ReferenceOr::Item(e) = e {
+                        *counts.entry(key(name, e)).or_default() += 1;
+                    }
+                }
+            }
+        });
+    }
+
+    let mut shared = Shared::new("examples", existing);
+    for item in items.iter_mut() {
+        for_each_contents(item, &mut |contents: &mut Contents| {
+            for media in contents.values_mut() {
+                for (name, e) in media.examples.iter_mut() {
+                    let ReferenceOr::Item(example) = e else {
+                        continue;
+                    };
+                    let k = key(name, example);
+                    if counts.get(&k).copied().unwrap_or(0) >= 2 {
+                        *e = shared.reference(k, name, example);
+                    }
+                }
+            }
+        });
+    }
+}
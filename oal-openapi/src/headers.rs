@@ -0,0 +1,51 @@
+use crate::components::{self, Shared};
+use crate::params::for_each_operation;
+use indexmap::IndexMap;
+use openapiv3::{Header, Operation, PathItem, ReferenceOr};
+use std::collections::HashSet;
+
+type Headers = IndexMap<String, ReferenceOr<Header>>;
+
+/// Applies a function to the headers of every response of a path item.
+fn for_each_headers<F: FnMut(&mut Headers)>(item: &mut PathItem, f: &mut F) {
+    for_each_operation(item, &mut |op: &mut Operation| {
+        let responses = op.responses.default.iter_mut();
+        for res in responses.chain(op.responses.responses.values_mut()) {
+            if let ReferenceOr::Item(res) = res {
+                f(&mut res.headers);
+            }
+        }
+    });
+}
+
+/// Returns the key of a response header, as identical headers have identical serializations.
+pub(crate) fn key(name: &str, h: &Header) -> String {
+    format!("{name}:{}", components::key(h))
+}
+
+/// Replaces the response headers of the given keys by references to shared components.
+///
+/// Components are named after their headers, with a numeric suffix
+/// to avoid conflicts with the given existing names.
+pub(crate) fn share<'a, I>(
+    items: I,
+    existing: &mut IndexMap<String, ReferenceOr<Header>>,
+    keys: &HashSet<String>,
+) where
+    I: IntoIterator<Item = &'a mut PathItem>,
+{
+    let mut shared = Shared::new("headers", existing);
+    for item in items {
+        for_each_headers(item, &mut |headers: &mut Headers| {
+            for (name, h) in headers.iter_mut() {
+                let ReferenceOr::Item(header) = h else {
+                    continue;
+                };
+                let k = key(name, header);
+                if keys.contains(&k) {
+                    *h = shared.reference(k, name, header);
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,97 @@
+use std::str::FromStr;
+
+/// The case of the path segments in labels derived from URIs, e.g. default operation ids.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LabelCase {
+    /// Lowercases all letters, including non-ASCII ones.
+    #[default]
+    Lower,
+    /// Lowercases ASCII letters only, leaving other characters untouched.
+    AsciiLower,
+    /// Keeps segments as written.
+    Preserve,
+}
+
+impl LabelCase {
+    /// Returns the label of a path segment in this case.
+    pub fn apply(&self, segment: &str) -> String {
+        match self {
+            LabelCase::Lower => segment.to_lowercase(),
+            LabelCase::AsciiLower => segment.to_ascii_lowercase(),
+            LabelCase::Preserve => segment.to_owned(),
+        }
+    }
+}
+
+impl FromStr for LabelCase {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "lower" => Ok(LabelCase::Lower),
+            "ascii-lower" => Ok(LabelCase::AsciiLower),
+            "preserve" => Ok(LabelCase::Preserve),
+            _ => Err(format!(
+                "unsupported label case {s}, expected lower, ascii-lower or preserve"
+            )),
+        }
+    }
+}
+
+/// The scheme of default operation ids, joining the method and the labels of path segments.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum IdScheme {
+    /// Joins the labels with hyphens, e.g. `get-users-id`.
+    #[default]
+    Kebab,
+    /// Joins the words of the labels in camel case, e.g. `getUserProfilesId`.
+    Camel,
+    /// Joins the words of the labels with underscores, e.g. `get_user_profiles_id`.
+    Snake,
+}
+
+/// Returns the word with its first character in uppercase.
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+impl IdScheme {
+    /// Returns the operation id made of the labels in this scheme.
+    ///
+    /// Labels are split into words on non-alphanumeric characters, except in kebab case.
+    pub fn join(&self, labels: &[String]) -> String {
+        let words = || {
+            labels
+                .iter()
+                .flat_map(|l| l.split(|c: char| !c.is_alphanumeric()))
+                .filter(|w| !w.is_empty())
+        };
+        match self {
+            IdScheme::Kebab => labels.join("-"),
+            IdScheme::Snake => words().collect::<Vec<_>>().join("_"),
+            IdScheme::Camel => words()
+                .enumerate()
+                .map(|(i, w)| if i == 0 { w.to_owned() } else { capitalize(w) })
+                .collect(),
+        }
+    }
+}
+
+impl FromStr for IdScheme {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "kebab" => Ok(IdScheme::Kebab),
+            "camel" => Ok(IdScheme::Camel),
+            "snake" => Ok(IdScheme::Snake),
+            _ => Err(format!(
+                "unsupported operation id scheme {s}, expected kebab, camel or snake"
+            )),
+        }
+    }
+}
@@ -1,30 +1,371 @@
+mod components;
+mod examples;
+mod headers;
+mod label;
 mod oas;
+mod params;
+pub mod sample;
+mod version;
+
+pub use crate::label::{IdScheme, LabelCase};
+pub use crate::version::OpenApiVersion;
 
 use crate::oas::into_box_ref;
+use crate::version::WEBHOOKS_EXTENSION;
 use indexmap::{indexmap, IndexMap};
+use oal_compiler::errors::{Error, Kind, Result};
 use oal_compiler::spec;
 use oal_compiler::spec::SchemaExpr;
 use oal_syntax::atom;
 use openapiv3::*;
+use std::cell::{Cell, RefCell};
+use std::collections::HashSet;
 use std::iter::once;
 
 pub struct Builder {
     spec: spec::Spec,
     base: Option<OpenAPI>,
+    media_type: Option<String>,
+    uri_examples: bool,
+    version: OpenApiVersion,
+    parameter_components: bool,
+    example_components: bool,
+    schema_ref: Option<String>,
+    schema_components: bool,
+    language: Option<String>,
+    translation_extensions: bool,
+    custom_keywords: bool,
+    tag_groups: bool,
+    label_case: LabelCase,
+    id_scheme: IdScheme,
+    id_params: bool,
+    sample_examples: bool,
+    example_seed: u64,
+    max_depth: usize,
+    max_schemas: Option<usize>,
+    /// The current depth of nested schemas.
+    depth: Cell<usize>,
+    /// The number of schemas generated so far, including inlined references.
+    schemas: Cell<usize>,
+    /// The first error raised while generating the description, if any.
+    error: RefCell<Option<Error>>,
 }
 
+/// The default maximum depth of nested schemas before giving up.
+pub const MAX_SCHEMA_DEPTH: usize = 512;
+
+/// The maximum number of schemas generated for programs from untrusted sources,
+/// as inlined references can grow descriptions exponentially.
+pub const MAX_UNTRUSTED_SCHEMAS: usize = 1 << 18;
+
+/// The placeholder of the component name in schema reference templates.
+const SCHEMA_NAME_PLACEHOLDER: &str = "{name}";
+
+/// The dialect of JSON Schema bundles.
+const JSON_SCHEMA_DIALECT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+/// The template of references to the definitions of JSON Schema bundles.
+const JSON_SCHEMA_REF: &str = "#/$defs/{name}";
+
+/// The media type of URL-encoded form bodies.
+const FORM_MEDIA_TYPE: &str = "application/x-www-form-urlencoded";
+
+/// The media type of multipart form bodies.
+const MULTIPART_MEDIA_TYPE: &str = "multipart/form-data";
+
+/// The header of the content coding of a body.
+const CONTENT_ENCODING_HEADER: &str = "Content-Encoding";
+
+/// The vendor extension of documents grouping the tags of operations, e.g. for Redoc.
+const TAG_GROUPS_EXTENSION: &str = "x-tagGroups";
+
+/// The vendor extension of operations and schemas giving their maturity.
+const STABILITY_EXTENSION: &str = "x-stability";
+
+/// The vendor extension of media types listing the expected content codings.
+const CONTENT_ENCODING_EXTENSION: &str = "x-content-encoding";
+
+/// The placeholders of URI variables by string format, conforming to the format.
+pub(crate) const FORMAT_EXAMPLES: &[(&str, &str)] = &[
+    ("uuid", "00000000-0000-0000-0000-000000000000"),
+    ("date", "2000-01-01"),
+    ("date-time", "2000-01-01T00:00:00Z"),
+    ("slug", "example-slug"),
+];
+
 type Headers = IndexMap<String, ReferenceOr<Header>>;
 type Examples = IndexMap<String, ReferenceOr<Example>>;
 
-impl From<Builder> for OpenAPI {
-    fn from(b: Builder) -> Self {
+/// Returns the path items of the paths and webhooks of a description, other than references.
+fn path_items<'a>(
+    paths: &'a mut Paths,
+    webhooks: &'a mut IndexMap<String, PathItem>,
+) -> impl Iterator<Item = &'a mut PathItem> {
+    paths
+        .paths
+        .values_mut()
+        .filter_map(|p| match p {
+            ReferenceOr::Item(item) => Some(item),
+            ReferenceOr::Reference { .. } => None,
+        })
+        .chain(webhooks.values_mut())
+}
+
+/// Replaces the OpenAPI `example` keyword of a schema and its subschemas
+/// with the JSON Schema `examples` keyword.
+fn json_schema_examples(schema: &mut serde_yaml::Value) {
+    let Some(obj) = schema.as_mapping_mut() else {
+        return;
+    };
+    if let Some(example) = obj.shift_remove("example") {
+        obj.insert(
+            "examples".into(),
+            serde_yaml::Value::Sequence(vec![example]),
+        );
+    }
+    for (key, value) in obj.iter_mut() {
+        match key.as_str() {
+            Some("properties") => value
+                .as_mapping_mut()
+                .into_iter()
+                .flat_map(|m| m.values_mut())
+                .for_each(json_schema_examples),
+            Some("allOf" | "oneOf" | "anyOf") => value
+                .as_sequence_mut()
+                .into_iter()
+                .flatten()
+                .for_each(json_schema_examples),
+            Some("items" | "additionalProperties" | "not") => json_schema_examples(value),
+            _ => {}
+        }
+    }
+}
+
+fn query_style(style: spec::ParamStyle) -> Option<QueryStyle> {
+    match style {
+        spec::ParamStyle::Form => Some(QueryStyle::Form),
+        spec::ParamStyle::SpaceDelimited => Some(QueryStyle::SpaceDelimited),
+        spec::ParamStyle::PipeDelimited => Some(QueryStyle::PipeDelimited),
+        spec::ParamStyle::DeepObject => Some(QueryStyle::DeepObject),
+        _ => None,
+    }
+}
+
+fn path_style(style: spec::ParamStyle) -> Option<PathStyle> {
+    match style {
+        spec::ParamStyle::Simple => Some(PathStyle::Simple),
+        spec::ParamStyle::Label => Some(PathStyle::Label),
+        spec::ParamStyle::Matrix => Some(PathStyle::Matrix),
+        _ => None,
+    }
+}
+
+/// Qualifies a schema, wrapping a reference whose siblings would be ignored.
+fn qualify_schema<F>(schema: ReferenceOr<Schema>, qualify: F) -> ReferenceOr<Schema>
+where
+    F: FnOnce(&mut SchemaData),
+{
+    let mut schema = match schema {
+        ReferenceOr::Item(s) => s,
+        reference => Schema {
+            schema_data: Default::default(),
+            schema_kind: SchemaKind::AllOf {
+                all_of: vec![reference],
+            },
+        },
+    };
+    qualify(&mut schema.schema_data);
+    ReferenceOr::Item(schema)
+}
+
+/// Returns an untyped schema equivalent to a schema of a kind without enumeration, if any.
+fn any_schema(kind: &SchemaKind) -> Option<AnySchema> {
+    let any = match kind.clone() {
+        SchemaKind::Type(Type::Object(o)) => AnySchema {
+            typ: Some("object".to_owned()),
+            properties: o.properties,
+            required: o.required,
+            additional_properties: o.additional_properties,
+            min_properties: o.min_properties,
+            max_properties: o.max_properties,
+            ..Default::default()
+        },
+        SchemaKind::Type(Type::Array(a)) => AnySchema {
+            typ: Some("array".to_owned()),
+            items: a.items,
+            min_items: a.min_items,
+            max_items: a.max_items,
+            unique_items: a.unique_items.then_some(true),
+            ..Default::default()
+        },
+        SchemaKind::OneOf { one_of } => AnySchema {
+            one_of,
+            ..Default::default()
+        },
+        SchemaKind::AllOf { all_of } => AnySchema {
+            all_of,
+            ..Default::default()
+        },
+        SchemaKind::AnyOf { any_of } => AnySchema {
+            any_of,
+            ..Default::default()
+        },
+        SchemaKind::Not { not } => AnySchema {
+            not: Some(not),
+            ..Default::default()
+        },
+        _ => return None,
+    };
+    Some(any)
+}
+
+fn oauth_flow(flow: &spec::OAuthFlow) -> serde_json::Value {
+    let mut value = serde_json::json!({ "scopes": flow.scopes });
+    let urls = [
+        ("authorizationUrl", &flow.authorization_url),
+        ("tokenUrl", &flow.token_url),
+        ("refreshUrl", &flow.refresh_url),
+    ];
+    for (key, url) in urls {
+        if let Some(url) = url {
+            value[key] = url.as_str().into();
+        }
+    }
+    value
+}
+
+fn security_scheme(scheme: &spec::SecurityScheme) -> SecurityScheme {
+    let description = scheme.desc.clone();
+    let extensions = Default::default();
+    match &scheme.kind {
+        spec::SecuritySchemeKind::ApiKey { name, location } => SecurityScheme::APIKey {
+            location: match location {
+                spec::ApiKeyLocation::Query => APIKeyLocation::Query,
+                spec::ApiKeyLocation::Header => APIKeyLocation::Header,
+                spec::ApiKeyLocation::Cookie => APIKeyLocation::Cookie,
+            },
+            name: name.clone(),
+            description,
+            extensions,
+        },
+        spec::SecuritySchemeKind::Http {
+            scheme,
+            bearer_format,
+        } => SecurityScheme::HTTP {
+            scheme: scheme.clone(),
+            bearer_format: bearer_format.clone(),
+            description,
+            extensions,
+        },
+        spec::SecuritySchemeKind::OAuth2 { flows } => {
+            let mut value = serde_json::Map::new();
+            for (kind, flow) in flows.iter() {
+                let key = match kind {
+                    spec::OAuthFlowKind::Implicit => "implicit",
+                    spec::OAuthFlowKind::Password => "password",
+                    spec::OAuthFlowKind::ClientCredentials => "clientCredentials",
+                    spec::OAuthFlowKind::AuthorizationCode => "authorizationCode",
+                };
+                value.insert(key.to_owned(), oauth_flow(flow));
+            }
+            // The fields of flows are private, they can only be deserialized.
+            let flows = serde_json::from_value(value.into())
+                .expect("flows should have the URLs required by their kind");
+            SecurityScheme::OAuth2 {
+                flows,
+                description,
+                extensions,
+            }
+        }
+        spec::SecuritySchemeKind::OpenIdConnect { url } => SecurityScheme::OpenIDConnect {
+            open_id_connect_url: url.clone(),
+            description,
+            extensions,
+        },
+    }
+}
+
+impl TryFrom<Builder> for OpenAPI {
+    type Error = Error;
+
+    fn try_from(b: Builder) -> Result<Self> {
         b.into_openapi()
     }
 }
 
 impl Builder {
     pub fn new(spec: spec::Spec) -> Builder {
-        Builder { spec, base: None }
+        Builder {
+            spec,
+            base: None,
+            media_type: None,
+            uri_examples: true,
+            version: OpenApiVersion::default(),
+            parameter_components: false,
+            example_components: false,
+            schema_ref: None,
+            schema_components: false,
+            language: None,
+            translation_extensions: false,
+            custom_keywords: false,
+            tag_groups: false,
+            label_case: LabelCase::default(),
+            id_scheme: IdScheme::default(),
+            id_params: true,
+            sample_examples: false,
+            example_seed: 0,
+            max_depth: MAX_SCHEMA_DEPTH,
+            max_schemas: None,
+            depth: Cell::new(0),
+            schemas: Cell::new(0),
+            error: RefCell::new(None),
+        }
+    }
+
+    /// Sets the maximum depth of nested schemas, beyond which generation fails.
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Sets the maximum number of schemas in the description, beyond which generation fails.
+    pub fn with_max_schemas(mut self, max_schemas: usize) -> Self {
+        self.max_schemas = Some(max_schemas);
+        self
+    }
+
+    /// Retains the first error raised while generating the description.
+    fn fail(&self, err: Error) {
+        self.error.borrow_mut().get_or_insert(err);
+    }
+
+    /// Converts an annotation value to JSON, failing on values without a JSON equivalent,
+    /// e.g. a mapping with a sequence as key.
+    fn json_value(&self, name: &str, value: &serde_yaml::Value) -> serde_json::Value {
+        serde_json::to_value(value).unwrap_or_else(|err| {
+            let msg = format!("{name} is not a valid JSON value: {err}");
+            self.fail(Error::new(Kind::InvalidLiteral, msg));
+            serde_json::Value::Null
+        })
+    }
+
+    /// Returns the vendor extensions as JSON values.
+    fn vendor_extensions(
+        &self,
+        extensions: &spec::Extensions,
+    ) -> IndexMap<String, serde_json::Value> {
+        extensions
+            .iter()
+            .map(|(k, v)| (k.clone(), self.json_value(k, v)))
+            .collect()
+    }
+
+    /// Returns the generated value, unless an error was raised while generating it.
+    fn checked<T>(&self, value: T) -> Result<T> {
+        match self.error.take() {
+            Some(err) => Err(err),
+            None => Ok(value),
+        }
     }
 
     pub fn with_base(mut self, base: OpenAPI) -> Self {
@@ -32,21 +373,442 @@ impl Builder {
         self
     }
 
-    pub fn into_openapi(self) -> OpenAPI {
-        let paths = self.all_paths();
+    /// Sets the media type of contents without an explicit one.
+    pub fn with_media_type(mut self, media_type: String) -> Self {
+        self.media_type = Some(media_type);
+        self
+    }
+
+    /// Enables or disables the generation of URI examples without an explicit one.
+    pub fn with_uri_examples(mut self, enabled: bool) -> Self {
+        self.uri_examples = enabled;
+        self
+    }
+
+    /// Sets the case of the path segments in default operation ids.
+    pub fn with_label_case(mut self, case: LabelCase) -> Self {
+        self.label_case = case;
+        self
+    }
+
+    /// Sets the scheme of default operation ids.
+    pub fn with_id_scheme(mut self, scheme: IdScheme) -> Self {
+        self.id_scheme = scheme;
+        self
+    }
+
+    /// Enables or disables the names of path variables in default operation ids.
+    pub fn with_id_params(mut self, enabled: bool) -> Self {
+        self.id_params = enabled;
+        self
+    }
+
+    /// Sets the version of the OpenAPI specification targeted by [`Builder::into_document`].
+    pub fn with_version(mut self, version: OpenApiVersion) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// Enables or disables sharing identical parameters as components instead of inlining them.
+    pub fn with_parameter_components(mut self, enabled: bool) -> Self {
+        self.parameter_components = enabled;
+        self
+    }
+
+    /// Enables or disables the synthesis of examples for contents without any,
+    /// from the schemas of their payloads.
+    pub fn with_sample_examples(mut self, enabled: bool) -> Self {
+        self.sample_examples = enabled;
+        self
+    }
+
+    /// Sets the seed of synthesized examples, the same seed always giving the same examples.
+    pub fn with_example_seed(mut self, seed: u64) -> Self {
+        self.example_seed = seed;
+        self
+    }
+
+    /// Enables or disables sharing named examples used more than once as components,
+    /// e.g. those of a schema in both a request and a response.
+    pub fn with_example_components(mut self, enabled: bool) -> Self {
+        self.example_components = enabled;
+        self
+    }
+
+    /// Sets the template of references to schema components, where `{name}` is the component name.
+    ///
+    /// By default, components are referenced within the document, i.e. `#/components/schemas/{name}`.
+    /// With an external template, e.g. `./schemas/{name}.yaml` or `https://example.com/schemas/{name}.json`,
+    /// components are hosted separately and omitted from the document, unless kept with
+    /// [`Builder::with_schema_components`].
+    pub fn with_schema_ref(mut self, template: String) -> Self {
+        self.schema_ref = Some(template);
+        self
+    }
+
+    /// Keeps the schema components in the document even when referenced outside of it,
+    /// e.g. to publish the document along with the canonical schema files it references.
+    pub fn with_schema_components(mut self, enabled: bool) -> Self {
+        self.schema_components = enabled;
+        self
+    }
+
+    /// Selects the language of descriptions, e.g. `fr` for `description@fr` annotations.
+    ///
+    /// Descriptions without a translation in the language fall back to the untagged annotation.
+    pub fn with_language(mut self, lang: String) -> Self {
+        self.language = Some(lang);
+        self
+    }
+
+    /// Enables or disables emitting the descriptions in the other languages
+    /// as `x-description-{lang}` vendor extensions.
+    pub fn with_translation_extensions(mut self, enabled: bool) -> Self {
+        self.translation_extensions = enabled;
+        self
+    }
+
+    /// Enables or disables emitting the annotations unknown to the compiler
+    /// as custom JSON Schema keywords of schemas.
+    ///
+    /// Custom keywords are only emitted when targeting OpenAPI 3.1,
+    /// as the schema objects of OpenAPI 3.0 do not allow them.
+    pub fn with_custom_keywords(mut self, enabled: bool) -> Self {
+        self.custom_keywords = enabled;
+        self
+    }
+
+    /// Enables or disables grouping the tags of operations by the modules declaring them,
+    /// as the `x-tagGroups` vendor extension of the document.
+    ///
+    /// Untagged operations are tagged after their group.
+    pub fn with_tag_groups(mut self, enabled: bool) -> Self {
+        self.tag_groups = enabled;
+        self
+    }
+
+    /// Returns the description in the selected language.
+    fn description(
+        &self,
+        desc: &Option<String>,
+        translations: &spec::Translations,
+    ) -> Option<String> {
+        self.language
+            .as_ref()
+            .and_then(|lang| translations.get(lang))
+            .or(desc.as_ref())
+            .cloned()
+    }
+
+    /// Returns the vendor extensions of the descriptions in the other languages, if enabled.
+    fn translations(
+        &self,
+        translations: &spec::Translations,
+    ) -> IndexMap<String, serde_json::Value> {
+        if !self.translation_extensions {
+            return Default::default();
+        }
+        translations
+            .iter()
+            .filter(|(lang, _)| self.language.as_ref() != Some(*lang))
+            .map(|(lang, text)| (format!("x-description-{lang}"), text.as_str().into()))
+            .collect()
+    }
+
+    /// Returns the vendor extensions and, if enabled, the custom keywords of a schema.
+    fn schema_extensions(&self, s: &spec::Schema) -> IndexMap<String, serde_json::Value> {
+        let keywords = if self.custom_keywords && self.version == OpenApiVersion::V3_1 {
+            Some(&s.keywords)
+        } else {
+            None
+        };
+        let mut extensions: IndexMap<String, serde_json::Value> = s
+            .extensions
+            .iter()
+            .chain(keywords.into_iter().flatten())
+            .map(|(k, v)| (k.clone(), self.json_value(k, v)))
+            .collect();
+        if let Some(stability) = s.stability {
+            extensions.insert(STABILITY_EXTENSION.to_owned(), stability.as_str().into());
+        }
+        extensions
+    }
+
+    /// Restricts a schema to a single value.
+    ///
+    /// OpenAPI 3.0 has no `const` keyword, for which a single-valued `enum` is equivalent.
+    /// Schemas without an enumeration of their own are given as untyped schemas instead.
+    fn constrain(&self, sch: &mut Schema, constant: &serde_yaml::Value) {
+        let value = self.json_value("const", constant);
+        if self.version == OpenApiVersion::V3_1 {
+            sch.schema_data.extensions.insert("const".to_owned(), value);
+            return;
+        }
+        match (&mut sch.schema_kind, &value) {
+            (SchemaKind::Type(Type::String(t)), serde_json::Value::String(v)) => {
+                t.enumeration = vec![Some(v.clone())];
+            }
+            (SchemaKind::Type(Type::Integer(t)), serde_json::Value::Number(v)) if v.is_i64() => {
+                t.enumeration = vec![v.as_i64()];
+            }
+            (SchemaKind::Type(Type::Number(t)), serde_json::Value::Number(v)) => {
+                t.enumeration = vec![v.as_f64()];
+            }
+            (SchemaKind::Type(Type::Boolean(t)), serde_json::Value::Bool(v)) => {
+                t.enumeration = vec![Some(*v)];
+            }
+            (SchemaKind::Any(any), _) => any.enumeration = vec![value],
+            (kind, _) => match any_schema(kind) {
+                Some(mut any) => {
+                    any.enumeration = vec![value];
+                    *kind = SchemaKind::Any(any);
+                }
+                // A constant of another type than its schema, which no value satisfies anyway.
+                None => {
+                    let value = serde_json::Value::Array(vec![value]);
+                    sch.schema_data.extensions.insert("enum".to_owned(), value);
+                }
+            },
+        }
+    }
+
+    /// Returns the name of the schema component of a reference.
+    fn component_name(&self, name: &atom::Ident) -> String {
+        match self.spec.component_names.get(name) {
+            Some(renamed) => renamed.clone(),
+            None => name.untagged(),
+        }
+    }
+
+    /// Returns the keys of the query parameters from named groups, always shared as components.
+    fn shared_params(&self) -> HashSet<String> {
+        let mut keys = HashSet::new();
+        for rel in self.spec.rels.iter() {
+            let xfers = rel.xfers.values().flatten();
+            for xfer in xfers.chain(rel.custom_xfers.values()) {
+                let props = xfer.params.iter().flat_map(|o| o.props.iter());
+                for p in props.filter(|p| xfer.shared_params.contains(&p.name)) {
+                    keys.insert(components::key(&self.prop_query_param(p)));
+                }
+            }
+        }
+        keys
+    }
+
+    /// Returns the keys of the response headers from named groups, always shared as components.
+    fn shared_headers(&self) -> HashSet<String> {
+        let mut keys = HashSet::new();
+        for rel in self.spec.rels.iter() {
+            let xfers = rel.xfers.values().flatten();
+            for xfer in xfers.chain(rel.custom_xfers.values()) {
+                for content in xfer.ranges.values() {
+                    let props = content.headers.iter().flat_map(|o| o.props.iter());
+                    for p in props.filter(|p| content.shared_headers.contains(&p.name)) {
+                        keys.insert(headers::key(p.name.as_ref(), &self.prop_header(p)));
+                    }
+                }
+            }
+        }
+        keys
+    }
+
+    /// Returns whether schema components are referenced outside of the document.
+    fn external_schemas(&self) -> bool {
+        self.schema_ref
+            .as_ref()
+            .is_some_and(|t| !t.starts_with('#'))
+    }
+
+    /// Returns the description in the 3.0 data model.
+    ///
+    /// Webhooks are emitted as the `x-webhooks` vendor extension of the document.
+    pub fn into_openapi(self) -> Result<OpenAPI> {
+        let mut paths = self.all_paths();
+        let mut webhooks = self.all_webhooks();
         let components = self.all_components();
+        let shared = self.shared_params();
+        let shared_headers = self.shared_headers();
+        let groups = self.all_tag_groups();
         let mut definition = if let Some(base) = self.base {
             base
         } else {
             self.default_base()
         };
-        definition.paths = paths;
         // Keep non-schema components
-        definition
-            .components
-            .get_or_insert(Default::default())
-            .schemas = components.schemas;
-        definition
+        let defined = definition.components.get_or_insert(Default::default());
+        defined.schemas = components.schemas;
+        // Schemes declared by the program take precedence over those of the base.
+        defined.security_schemes.extend(components.security_schemes);
+        if self.parameter_components || !shared.is_empty() {
+            let items = path_items(&mut paths, &mut webhooks);
+            params::share(
+                items,
+                &mut defined.parameters,
+                self.parameter_components,
+                &shared,
+            );
+        }
+        if !shared_headers.is_empty() {
+            let items = path_items(&mut paths, &mut webhooks);
+            headers::share(items, &mut defined.headers, &shared_headers);
+        }
+        if self.example_components {
+            let items = path_items(&mut paths, &mut webhooks);
+            examples::share(items, &mut defined.examples);
+        }
+        let used = path_items(&mut paths, &mut webhooks)
+            .flat_map(|item| {
+                let mut tags = Vec::new();
+                params::for_each_operation(item, &mut |op: &mut Operation| {
+                    tags.extend(op.tags.iter().cloned())
+                });
+                tags
+            })
+            .collect();
+        definition.paths = paths;
+        Self::document_metadata(&self.spec, &mut definition);
+        let base = std::mem::take(&mut definition.tags);
+        definition.tags = Self::document_tags(&self.spec, base, used);
+        if self.spec.security.is_some() {
+            definition.security.clone_from(&self.spec.security);
+        }
+        if !groups.is_empty() {
+            let groups = groups
+                .into_iter()
+                .map(|(name, tags)| serde_json::json!({ "name": name, "tags": tags }))
+                .collect();
+            definition.extensions.insert(
+                TAG_GROUPS_EXTENSION.to_owned(),
+                serde_json::Value::Array(groups),
+            );
+        }
+        if !webhooks.is_empty() {
+            let value = serde_json::to_value(webhooks).expect("webhooks should serialize");
+            definition
+                .extensions
+                .insert(WEBHOOKS_EXTENSION.to_owned(), value);
+        }
+        match self.error.take() {
+            Some(err) => Err(err),
+            None => Ok(definition),
+        }
+    }
+
+    /// Returns the serialized description, conforming to the targeted version.
+    ///
+    /// Unlike JSON values, the document keeps the order of the fields of the description.
+    pub fn into_document(self) -> Result<serde_yaml::Value> {
+        let version = self.version;
+        let api = self.into_openapi()?;
+        let mut doc = serde_yaml::to_value(api).expect("description should serialize");
+        if version == OpenApiVersion::V3_1 {
+            version::upgrade(&mut doc);
+        }
+        Ok(doc)
+    }
+
+    /// Returns the schema components as a standalone JSON Schema (draft 2020-12) bundle,
+    /// with one definition under `$defs` per reference and without the OpenAPI wrapper.
+    pub fn into_json_schema(mut self) -> Result<serde_yaml::Value> {
+        self.schema_ref = Some(JSON_SCHEMA_REF.to_owned());
+        self.version = OpenApiVersion::V3_1;
+        let defs: IndexMap<String, ReferenceOr<Schema>> = self
+            .spec
+            .refs
+            .iter()
+            .map(|(name, spec::Reference::Schema(s))| (self.component_name(name), self.schema(s)))
+            .collect();
+        let mut defs = serde_yaml::to_value(defs).expect("schemas should serialize");
+        version::upgrade_named_schemas(&mut defs);
+        if let Some(defs) = defs.as_mapping_mut() {
+            defs.values_mut().for_each(json_schema_examples);
+        }
+        let mut bundle = serde_yaml::Mapping::new();
+        bundle.insert("$schema".into(), JSON_SCHEMA_DIALECT.into());
+        bundle.insert("$defs".into(), defs);
+        self.checked(serde_yaml::Value::Mapping(bundle))
+    }
+
+    /// Returns the OpenAPI schema of a schema, e.g. to inspect a single declaration.
+    pub fn schema_object(&self, s: &spec::Schema) -> Result<ReferenceOr<Schema>> {
+        self.checked(self.schema(s))
+    }
+
+    /// Returns the OpenAPI path item of a relation.
+    pub fn path_item(&self, rel: &spec::Relation) -> Result<PathItem> {
+        self.checked(self.relation_path_item(rel))
+    }
+
+    /// Returns the OpenAPI operation of a transfer, as if on the root path.
+    pub fn operation(&self, xfer: &spec::Transfer) -> Result<Operation> {
+        let uri = spec::Uri {
+            path: Vec::new(),
+            params: None,
+            example: None,
+        };
+        let method = match &xfer.verb {
+            Some(verb) => verb.as_str(),
+            None => xfer
+                .methods
+                .iter()
+                .find(|(_, enabled)| **enabled)
+                .map_or("get", |(m, _)| self.method_label(m)),
+        };
+        self.checked(self.xfer_operation(xfer, method, &uri))
+    }
+
+    /// Applies the information and servers declared by the program to the description,
+    /// taking precedence over those of the base.
+    fn document_metadata(spec: &spec::Spec, definition: &mut OpenAPI) {
+        let info = &spec.info;
+        if let Some(title) = &info.title {
+            definition.info.title.clone_from(title);
+        }
+        if let Some(version) = &info.version {
+            definition.info.version.clone_from(version);
+        }
+        if info.desc.is_some() {
+            definition.info.description.clone_from(&info.desc);
+        }
+        if let Some(servers) = &spec.servers {
+            definition.servers = servers
+                .iter()
+                .map(|s| Server {
+                    url: s.url.clone(),
+                    description: s.desc.clone(),
+                    ..Default::default()
+                })
+                .collect();
+        }
+    }
+
+    /// Returns the tags of the description, given the tags used by the operations in order of first use.
+    ///
+    /// Tags declared by the program come first, in declaration order, replacing those of the same name
+    /// in the base. Other tags of the base follow, then the remaining tags used by the operations.
+    fn document_tags(spec: &spec::Spec, base: Vec<Tag>, used: Vec<String>) -> Vec<Tag> {
+        let mut tags: IndexMap<String, Tag> = spec
+            .tags
+            .iter()
+            .map(|t| {
+                let tag = Tag {
+                    name: t.name.clone(),
+                    description: t.desc.clone(),
+                    ..Default::default()
+                };
+                (t.name.clone(), tag)
+            })
+            .collect();
+        for tag in base {
+            tags.entry(tag.name.clone()).or_insert(tag);
+        }
+        for name in used {
+            tags.entry(name.clone()).or_insert_with(|| Tag {
+                name,
+                ..Default::default()
+            });
+        }
+        tags.into_values().collect()
     }
 
     fn default_base(&self) -> OpenAPI {
@@ -66,11 +828,42 @@ impl Builder {
     }
 
     fn media_type(&self) -> String {
-        "application/json".to_owned()
+        self.media_type
+            .clone()
+            .unwrap_or_else(|| "application/json".to_owned())
+    }
+
+    /// Returns the example of a primitive schema, falling back to the first enumerated value.
+    fn prim_example(&self, schema: &spec::Schema) -> Option<String> {
+        match &schema.expr {
+            SchemaExpr::Num(p) => p
+                .example
+                .or_else(|| p.enumeration.first().cloned())
+                .map(|v| v.to_string()),
+            SchemaExpr::Str(p) => p.example.clone().or_else(|| p.enumeration.first().cloned()),
+            SchemaExpr::Bool(p) => p.enumeration.first().map(|v| v.to_string()),
+            SchemaExpr::Int(p) => p
+                .example
+                .or_else(|| p.enumeration.first().cloned())
+                .map(|v| v.to_string()),
+            _ => None,
+        }
     }
 
     fn uri_example_default(&self, uri: &spec::Uri) -> String {
         uri.pattern_with(|p| {
+            if let Some(example) = self.prim_example(&p.schema) {
+                return example;
+            }
+            if let SchemaExpr::Str(spec::PrimString {
+                format: Some(ref f),
+                ..
+            }) = p.schema.expr
+            {
+                if let Some((_, example)) = FORMAT_EXAMPLES.iter().find(|(n, _)| n == f) {
+                    return example.to_string();
+                }
+            }
             let t = match p.schema.expr {
                 SchemaExpr::Num(_) => "number",
                 SchemaExpr::Str(_) => "string",
@@ -83,7 +876,10 @@ impl Builder {
     }
 
     fn number_schema(&self, p: &spec::PrimNumber) -> Schema {
-        let example = p.example.map(Into::into);
+        let example = p
+            .example
+            .or_else(|| p.enumeration.first().cloned())
+            .map(Into::into);
         Schema {
             schema_data: SchemaData {
                 example,
@@ -92,7 +888,10 @@ impl Builder {
             schema_kind: SchemaKind::Type(Type::Number(NumberType {
                 minimum: p.minimum,
                 maximum: p.maximum,
+                exclusive_minimum: p.exclusive_minimum,
+                exclusive_maximum: p.exclusive_maximum,
                 multiple_of: p.multiple_of,
+                enumeration: p.enumeration.iter().map(|n| Some(*n)).collect(),
                 ..Default::default()
             })),
         }
@@ -123,15 +922,20 @@ impl Builder {
         }
     }
 
-    fn boolean_schema(&self, _: &spec::PrimBoolean) -> Schema {
+    fn boolean_schema(&self, p: &spec::PrimBoolean) -> Schema {
         Schema {
             schema_data: Default::default(),
-            schema_kind: SchemaKind::Type(Type::Boolean(BooleanType::default())),
+            schema_kind: SchemaKind::Type(Type::Boolean(BooleanType {
+                enumeration: p.enumeration.iter().map(|b| Some(*b)).collect(),
+            })),
         }
     }
 
     fn integer_schema(&self, p: &spec::PrimInteger) -> Schema {
-        let example = p.example.map(Into::into);
+        let example = p
+            .example
+            .or_else(|| p.enumeration.first().cloned())
+            .map(Into::into);
         Schema {
             schema_data: SchemaData {
                 example,
@@ -140,7 +944,10 @@ impl Builder {
             schema_kind: SchemaKind::Type(Type::Integer(IntegerType {
                 minimum: p.minimum,
                 maximum: p.maximum,
+                exclusive_minimum: p.exclusive_minimum,
+                exclusive_maximum: p.exclusive_maximum,
                 multiple_of: p.multiple_of,
+                enumeration: p.enumeration.iter().map(|i| Some(*i)).collect(),
                 ..Default::default()
             })),
         }
@@ -155,7 +962,7 @@ impl Builder {
             .example
             .clone()
             .or_else(|| {
-                if uri.path.is_empty() {
+                if uri.path.is_empty() || !self.uri_examples {
                     None
                 } else {
                     Some(self.uri_example_default(uri))
@@ -189,8 +996,11 @@ impl Builder {
             .iter()
             .map(|p| {
                 let ident = p.name.as_ref().into();
-                let expr = into_box_ref(self.schema(&p.schema));
-                (ident, expr)
+                let mut schema = self.schema(&p.schema);
+                if p.deprecated == Some(true) {
+                    schema = qualify_schema(schema, |s| s.deprecated = true);
+                }
+                (ident, into_box_ref(schema))
             })
             .collect();
         let required = obj
@@ -204,10 +1014,27 @@ impl Builder {
                 }
             })
             .collect();
+        let additional_properties = match &obj.additional_props {
+            Some(spec::AdditionalProperties::Allowed(b)) => Some(AdditionalProperties::Any(*b)),
+            Some(spec::AdditionalProperties::Schema(s)) => {
+                let value = self.json_value("additionalProperties", s);
+                match serde_json::from_value(value) {
+                    Ok(s) => Some(AdditionalProperties::Schema(Box::new(s))),
+                    Err(err) => {
+                        let msg = format!("additionalProperties is not a valid schema: {err}");
+                        self.fail(Error::new(Kind::InvalidLiteral, msg));
+                        None
+                    }
+                }
+            }
+            None => None,
+        };
         Type::Object(ObjectType {
             properties,
             required,
-            ..Default::default()
+            additional_properties,
+            min_properties: obj.min_props,
+            max_properties: obj.max_props,
         })
     }
 
@@ -223,16 +1050,23 @@ impl Builder {
             schema_data: Default::default(),
             schema_kind: SchemaKind::Type(Type::Array(ArrayType {
                 items: Some(into_box_ref(self.schema(&array.item))),
-                min_items: None,
-                max_items: None,
-                unique_items: false,
+                min_items: array.min_items,
+                max_items: array.max_items,
+                unique_items: array.unique_items.unwrap_or(false),
             })),
         }
     }
 
-    fn sum_schema(&self, schemas: &[spec::Schema]) -> Schema {
+    fn sum_schema(
+        &self,
+        schemas: &[spec::Schema],
+        discriminator: Option<&spec::Discriminator>,
+    ) -> Schema {
         Schema {
-            schema_data: Default::default(),
+            schema_data: SchemaData {
+                discriminator: discriminator.map(|d| self.discriminator(d)),
+                ..Default::default()
+            },
             schema_kind: SchemaKind::OneOf {
                 one_of: schemas.iter().map(|s| self.schema(s)).collect(),
             },
@@ -248,8 +1082,31 @@ impl Builder {
         }
     }
 
+    /// Returns the discriminator object of a sum, referencing the schema components of the mapping.
+    fn discriminator(&self, d: &spec::Discriminator) -> Discriminator {
+        let mapping = d
+            .mapping
+            .iter()
+            .map(|(value, schema)| {
+                let ident = atom::Ident::from(schema.as_str());
+                let target = if ident.is_reference() {
+                    self.component_reference(&ident)
+                } else {
+                    schema.clone()
+                };
+                (value.clone(), target)
+            })
+            .collect();
+        Discriminator {
+            property_name: d.property_name.clone(),
+            mapping,
+            extensions: Default::default(),
+        }
+    }
+
     fn maybe_inline(&self, name: &atom::Ident) -> Option<&spec::Schema> {
-        // Implicit and atomic references should be inlined.
+        // Implicit and atomic references should be inlined,
+        // except for recursive URIs which refer to themselves through their parameters.
         if name.is_reference() {
             return None;
         }
@@ -259,8 +1116,7 @@ impl Builder {
             | spec::SchemaExpr::Str(_)
             | spec::SchemaExpr::Bool(_)
             | spec::SchemaExpr::Int(_)
-            | spec::SchemaExpr::Rel(_)
-            | spec::SchemaExpr::Uri(_) => Some(s),
+            | spec::SchemaExpr::Rel(_) => Some(s),
             _ => None,
         }
     }
@@ -269,9 +1125,17 @@ impl Builder {
         if let Some(s) = self.maybe_inline(name) {
             self.value_schema(s)
         } else {
-            ReferenceOr::Reference {
-                reference: format!("#/components/schemas/{}", name.untagged()),
-            }
+            let reference = self.component_reference(name);
+            ReferenceOr::Reference { reference }
+        }
+    }
+
+    /// Returns the reference to the schema component of an identifier.
+    fn component_reference(&self, name: &atom::Ident) -> String {
+        let name = self.component_name(name);
+        match self.schema_ref {
+            Some(ref template) => template.replace(SCHEMA_NAME_PLACEHOLDER, &name),
+            None => format!("#/components/schemas/{name}"),
         }
     }
 
@@ -287,20 +1151,80 @@ impl Builder {
             spec::SchemaExpr::Array(array) => self.array_schema(array),
             spec::SchemaExpr::Op(operation) => match operation.op {
                 atom::VariadicOperator::Join => self.join_schema(&operation.schemas),
-                atom::VariadicOperator::Sum => self.sum_schema(&operation.schemas),
+                atom::VariadicOperator::Sum => {
+                    self.sum_schema(&operation.schemas, operation.discriminator.as_ref())
+                }
                 atom::VariadicOperator::Any => self.any_schema(&operation.schemas),
                 atom::VariadicOperator::Range => unreachable!(),
             },
             spec::SchemaExpr::Ref(_) => unreachable!(),
         };
-        sch.schema_data.description = s.desc.clone();
+        sch.schema_data.description = self.description(&s.desc, &s.translations);
+        sch.schema_data
+            .extensions
+            .extend(self.translations(&s.translations));
+        sch.schema_data.extensions.extend(self.schema_extensions(s));
         sch.schema_data.title = s.title.clone();
+        sch.schema_data.deprecated = s.deprecated.unwrap_or(false);
+        sch.schema_data.nullable = s.nullable.unwrap_or(false);
+        if let Some(example) = &s.example {
+            sch.schema_data.example = Some(self.json_value("example", example));
+        }
+        if let Some(default) = &s.default {
+            sch.schema_data.default = Some(self.json_value("default", default));
+        }
+        if let Some(constant) = &s.constant {
+            self.constrain(&mut sch, constant);
+        }
         ReferenceOr::Item(sch)
     }
 
     fn schema(&self, s: &spec::Schema) -> ReferenceOr<Schema> {
+        let any = || {
+            ReferenceOr::Item(Schema {
+                schema_data: Default::default(),
+                schema_kind: SchemaKind::Any(Default::default()),
+            })
+        };
+        let depth = self.depth.get();
+        if depth >= self.max_depth {
+            let msg = format!("schemas nest deeper than the limit of {}", self.max_depth);
+            self.fail(Error::new(Kind::DepthExceeded, msg));
+            return any();
+        }
+        let count = self.schemas.get() + 1;
+        self.schemas.set(count);
+        if let Some(max) = self.max_schemas.filter(|max| count > *max) {
+            let msg = format!("the description has more than the limit of {max} schemas");
+            self.fail(Error::new(Kind::ResourceExceeded, msg));
+            return any();
+        }
+        self.depth.set(depth + 1);
+        let schema = self.nested_schema(s);
+        self.depth.set(depth);
+        schema
+    }
+
+    fn nested_schema(&self, s: &spec::Schema) -> ReferenceOr<Schema> {
         if let spec::SchemaExpr::Ref(name) = &s.expr {
-            self.reference_schema(name)
+            let mut schema = self.reference_schema(name);
+            if s.nullable == Some(true) {
+                schema = qualify_schema(schema, |s| s.nullable = true);
+            }
+            if let Some(default) = &s.default {
+                let value = self.json_value("default", default);
+                schema = qualify_schema(schema, |s| s.default = Some(value));
+            }
+            if let Some(constant) = &s.constant {
+                schema = match qualify_schema(schema, |_| {}) {
+                    ReferenceOr::Item(mut sch) => {
+                        self.constrain(&mut sch, constant);
+                        ReferenceOr::Item(sch)
+                    }
+                    reference => reference,
+                };
+            }
+            schema
         } else {
             self.value_schema(s)
         }
@@ -309,21 +1233,21 @@ impl Builder {
     fn prop_param_data(&self, prop: &spec::Property, required: bool) -> ParameterData {
         ParameterData {
             name: prop.name.as_ref().into(),
-            description: prop.desc.clone(),
+            description: self.description(&prop.desc, &prop.translations),
             required,
-            deprecated: None,
+            deprecated: prop.deprecated,
             format: ParameterSchemaOrContent::Schema(self.schema(&prop.schema)),
             example: None,
             examples: Default::default(),
-            explode: None,
-            extensions: Default::default(),
+            explode: prop.explode,
+            extensions: self.translations(&prop.translations),
         }
     }
 
     fn prop_path_param(&self, prop: &spec::Property) -> Parameter {
         Parameter::Path {
             parameter_data: self.prop_param_data(prop, true),
-            style: Default::default(),
+            style: prop.style.and_then(path_style).unwrap_or_default(),
         }
     }
 
@@ -331,7 +1255,7 @@ impl Builder {
         Parameter::Query {
             parameter_data: self.prop_param_data(prop, prop.required.unwrap_or(false)),
             allow_reserved: false,
-            style: Default::default(),
+            style: prop.style.and_then(query_style).unwrap_or_default(),
             allow_empty_value: None,
         }
     }
@@ -345,14 +1269,14 @@ impl Builder {
 
     fn prop_header(&self, prop: &spec::Property) -> Header {
         Header {
-            description: prop.desc.clone(),
+            description: self.description(&prop.desc, &prop.translations),
             style: Default::default(),
             required: prop.required.unwrap_or(false),
-            deprecated: None,
+            deprecated: prop.deprecated,
             format: ParameterSchemaOrContent::Schema(self.schema(&prop.schema)),
             example: None,
             examples: Default::default(),
-            extensions: Default::default(),
+            extensions: self.translations(&prop.translations),
         }
     }
 
@@ -363,10 +1287,9 @@ impl Builder {
                 params.push(ReferenceOr::Item(self.prop_query_param(p)));
             }
         }
-        if let Some(o) = xfer.domain.headers.as_ref() {
-            for p in o.props.iter() {
-                params.push(ReferenceOr::Item(self.prop_header_param(p)));
-            }
+        // The request headers of contents in several media types are declared once.
+        for p in xfer.request_headers() {
+            params.push(ReferenceOr::Item(self.prop_header_param(p)));
         }
         params
     }
@@ -386,23 +1309,123 @@ impl Builder {
         params
     }
 
-    fn domain_request(&self, domain: &spec::Content) -> Option<ReferenceOr<RequestBody>> {
-        let media = domain.media.clone().unwrap_or_else(|| self.media_type());
+    /// Returns the media type of a request body content, if it has a schema.
+    fn domain_media_type(&self, domain: &spec::Content) -> Option<(String, MediaType)> {
+        let media = domain
+            .media
+            .as_ref()
+            .map_or_else(|| self.media_type(), |m| m.to_string());
+        let essence = media.parse::<spec::MediaType>().map(|m| m.essence());
         domain.schema.as_ref().map(|schema| {
-            ReferenceOr::Item(RequestBody {
-                content: indexmap! { media => MediaType {
-                    schema: Some(self.schema(schema)),
-                    examples: self.content_examples(domain),
+            let encoding = match essence.as_deref() {
+                Ok(FORM_MEDIA_TYPE) => self.form_encoding(schema, false),
+                Ok(MULTIPART_MEDIA_TYPE) => self.form_encoding(schema, true),
+                _ => Default::default(),
+            };
+            let media_type = MediaType {
+                schema: Some(self.schema(schema)),
+                example: self.content_sample(domain),
+                examples: self.content_examples(domain),
+                encoding,
+                extensions: self.content_extensions(domain),
+            };
+            (media, media_type)
+        })
+    }
+
+    /// Returns the object schema of a form, following references.
+    fn form_object<'a>(&'a self, schema: &'a spec::Schema) -> Option<&'a spec::Object> {
+        match &schema.expr {
+            SchemaExpr::Object(o) => Some(o),
+            SchemaExpr::Ref(ident) => match self.spec.refs.get(ident) {
+                Some(spec::Reference::Schema(s)) => self.form_object(s),
+                None => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Returns the default media type of a form field, which the library always emits.
+    fn field_content_type(&self, schema: &spec::Schema) -> String {
+        match &schema.expr {
+            SchemaExpr::Str(p) if p.format.as_deref() == Some("binary") => {
+                "application/octet-stream".to_owned()
+            }
+            SchemaExpr::Num(_) | SchemaExpr::Str(_) | SchemaExpr::Bool(_) | SchemaExpr::Int(_) => {
+                "text/plain".to_owned()
+            }
+            SchemaExpr::Array(a) => self.field_content_type(&a.item),
+            SchemaExpr::Ref(ident) => match self.spec.refs.get(ident) {
+                Some(spec::Reference::Schema(s)) => self.field_content_type(s),
+                None => "application/json".to_owned(),
+            },
+            _ => "application/json".to_owned(),
+        }
+    }
+
+    /// Returns the encoding of the fields of a form with an explicit content type,
+    /// or with an explicit style or explode flag unless the form is multipart.
+    fn form_encoding(&self, schema: &spec::Schema, multipart: bool) -> IndexMap<String, Encoding> {
+        let Some(object) = self.form_object(schema) else {
+            return IndexMap::new();
+        };
+        object
+            .props
+            .iter()
+            .filter(|p| {
+                p.content_type.is_some()
+                    || (!multipart && (p.style.is_some() || p.explode.is_some()))
+            })
+            .map(|p| {
+                let content_type = p
+                    .content_type
+                    .clone()
+                    .unwrap_or_else(|| self.field_content_type(&p.schema));
+                // The style and explode flag of fields only apply to URL-encoded forms.
+                let (style, explode) = if multipart {
+                    (None, None)
+                } else {
+                    (p.style, p.explode)
+                };
+                let style = style.and_then(query_style);
+                let mut encoding = Encoding {
+                    content_type: Some(content_type),
+                    style: style.clone(),
+                    explode: explode.unwrap_or(false),
                     ..Default::default()
-                }},
-                description: domain.desc.clone(),
-                ..Default::default()
+                };
+                // The explode flag defaults to true for the form style, the default style of
+                // fields, but the library never serializes a false flag. Only in that case is
+                // the flag written as an inline key, which serializes in place of the field.
+                let is_form = style.is_none_or(|s| s == QueryStyle::Form);
+                if explode == Some(false) && is_form {
+                    encoding.extensions.insert("explode".into(), false.into());
+                }
+                (p.name.as_ref().to_owned(), encoding)
             })
-        })
+            .collect()
     }
 
+    /// Returns the request body of a transfer, with an entry per media type of its domain.
+    ///
+    /// The description is that of the first content with a schema.
     fn xfer_request(&self, xfer: &spec::Transfer) -> Option<ReferenceOr<RequestBody>> {
-        self.domain_request(&xfer.domain)
+        let mut first = None;
+        let mut content = IndexMap::new();
+        for domain in xfer.domains() {
+            if let Some((media, media_type)) = self.domain_media_type(domain) {
+                first.get_or_insert(domain);
+                content.entry(media).or_insert(media_type);
+            }
+        }
+        first.map(|domain| {
+            ReferenceOr::Item(RequestBody {
+                content,
+                description: self.description(&domain.desc, &domain.translations),
+                extensions: self.translations(&domain.translations),
+                ..Default::default()
+            })
+        })
     }
 
     fn http_status_code(&self, status: &atom::HttpStatus) -> StatusCode {
@@ -419,7 +1442,7 @@ impl Builder {
     }
 
     fn content_headers(&self, content: &spec::Content) -> Headers {
-        content.headers.as_ref().map_or_else(Headers::default, |h| {
+        let mut headers = content.headers.as_ref().map_or_else(Headers::default, |h| {
             h.props
                 .iter()
                 .map(|p| {
@@ -429,7 +1452,81 @@ impl Builder {
                     )
                 })
                 .collect()
-        })
+        });
+        let declared = headers
+            .keys()
+            .any(|k| k.eq_ignore_ascii_case(CONTENT_ENCODING_HEADER));
+        if !content.encodings.is_empty() && !declared {
+            let prop = self.content_encoding_header(&content.encodings);
+            headers.insert(
+                CONTENT_ENCODING_HEADER.to_owned(),
+                ReferenceOr::Item(self.prop_header(&prop)),
+            );
+        }
+        headers
+    }
+
+    /// Returns the header documenting the expected content codings of a response.
+    fn content_encoding_header(&self, encodings: &[String]) -> spec::Property {
+        let schema = spec::Schema {
+            expr: SchemaExpr::Str(spec::PrimString {
+                enumeration: encodings.to_vec(),
+                ..Default::default()
+            }),
+            desc: None,
+            translations: Default::default(),
+            title: None,
+            required: None,
+            examples: None,
+            example: None,
+            extensions: Default::default(),
+            keywords: Default::default(),
+            deprecated: None,
+            nullable: None,
+            default: None,
+            constant: None,
+            stability: None,
+        };
+        spec::Property {
+            name: CONTENT_ENCODING_HEADER.into(),
+            schema,
+            desc: Some("The content coding of the body.".to_owned()),
+            translations: Default::default(),
+            required: None,
+            style: None,
+            explode: None,
+            content_type: None,
+            deprecated: None,
+        }
+    }
+
+    /// Returns the vendor extensions of a media type with the expected content codings, if any.
+    fn content_extensions(&self, content: &spec::Content) -> IndexMap<String, serde_json::Value> {
+        let mut extensions = if content.encodings.is_empty() {
+            IndexMap::new()
+        } else {
+            indexmap! { CONTENT_ENCODING_EXTENSION.to_owned() => content.encodings.clone().into() }
+        };
+        extensions.extend(self.vendor_extensions(&content.extensions));
+        extensions
+    }
+
+    /// Returns a synthesized example of a content without any, if enabled.
+    fn content_sample(&self, content: &spec::Content) -> Option<serde_json::Value> {
+        if !self.sample_examples || content.examples.is_some() {
+            return None;
+        }
+        let schema = content.schema.as_ref()?;
+        if schema.examples.is_some() || schema.example.is_some() {
+            return None;
+        }
+        match sample::sample(&self.spec, schema, self.example_seed) {
+            Ok(value) => Some(value),
+            Err(err) => {
+                self.fail(err);
+                None
+            }
+        }
     }
 
     fn content_examples(&self, content: &spec::Content) -> Examples {
@@ -441,9 +1538,15 @@ impl Builder {
             None => Default::default(),
             Some(examples) => examples
                 .iter()
-                .map(|(name, url)| {
+                .map(|(name, e)| {
                     let example = Example {
-                        external_value: Some(url.clone()),
+                        summary: e.summary.clone(),
+                        description: e.desc.clone(),
+                        value: e
+                            .value
+                            .as_ref()
+                            .map(|v| self.json_value(&format!("example {name}"), v)),
+                        external_value: e.external.clone(),
                         ..Default::default()
                     };
                     (name.clone(), ReferenceOr::Item(example))
@@ -466,16 +1569,29 @@ impl Builder {
             };
             if let ReferenceOr::Item(res) = response {
                 if let Some(schema) = content.schema.as_ref() {
-                    let media_type = media.clone().unwrap_or_else(|| self.media_type());
+                    let media_type = media
+                        .as_ref()
+                        .map_or_else(|| self.media_type(), |m| m.to_string());
                     let media_schema = MediaType {
                         schema: Some(self.schema(schema)),
+                        example: self.content_sample(content),
                         examples: self.content_examples(content),
+                        extensions: self.content_extensions(content),
                         ..Default::default()
                     };
                     res.content.insert(media_type, media_schema);
                 }
-                res.headers = self.content_headers(content);
-                res.description = content.desc.clone().unwrap_or_else(|| "".to_owned());
+                // Contents of the same status with different media types share their headers.
+                for (name, header) in self.content_headers(content) {
+                    if !res.headers.keys().any(|k| k.eq_ignore_ascii_case(&name)) {
+                        res.headers.insert(name, header);
+                    }
+                }
+                res.description = self
+                    .description(&content.desc, &content.translations)
+                    .unwrap_or_default();
+                res.extensions
+                    .extend(self.translations(&content.translations));
             } else {
                 unreachable!();
             }
@@ -497,6 +1613,7 @@ impl Builder {
             atom::Method::Delete => "delete",
             atom::Method::Options => "options",
             atom::Method::Head => "head",
+            atom::Method::Trace => "trace",
         }
     }
 
@@ -507,33 +1624,113 @@ impl Builder {
                 if l.is_empty() {
                     "root".to_owned()
                 } else {
-                    l.to_lowercase()
+                    self.label_case.apply(l)
                 }
             }
-            spec::UriSegment::Variable(t) => t.name.as_ref().to_lowercase(),
+            spec::UriSegment::Variable(t) => self.label_case.apply(t.name.as_ref()),
         }
     }
 
-    fn xfer_id(
-        &self,
-        xfer: &spec::Transfer,
-        method: atom::Method,
-        uri: &spec::Uri,
-    ) -> Option<String> {
+    fn xfer_id(&self, xfer: &spec::Transfer, method: &str, uri: &spec::Uri) -> Option<String> {
         if xfer.id.is_some() {
             return xfer.id.clone();
         }
-        let prefix = self.method_label(method).to_owned();
-        let label = once(prefix)
-            .chain(uri.path.iter().map(|s| self.uri_segment_label(s)))
-            .collect::<Vec<_>>()
-            .join("-");
-        Some(label)
+        let prefix = method.to_owned();
+        let labels = once(prefix)
+            .chain(
+                uri.path
+                    .iter()
+                    .filter(|s| self.id_params || matches!(s, spec::UriSegment::Literal(_)))
+                    .map(|s| self.uri_segment_label(s)),
+            )
+            .collect::<Vec<_>>();
+        Some(self.id_scheme.join(&labels))
+    }
+
+    fn xfer_operation(&self, xfer: &spec::Transfer, method: &str, uri: &spec::Uri) -> Operation {
+        let operation_id = self.xfer_id(xfer, method, uri);
+        let description = self.description(&xfer.desc, &xfer.translations);
+        let summary = xfer
+            .summary
+            .clone()
+            .or_else(|| description.clone())
+            .or_else(|| operation_id.clone());
+        let mut extensions = self.translations(&xfer.translations);
+        extensions.extend(self.vendor_extensions(&xfer.extensions));
+        if let Some(stability) = xfer.stability {
+            extensions.insert(STABILITY_EXTENSION.to_owned(), stability.as_str().into());
+        }
+
+        Operation {
+            summary,
+            description,
+            operation_id,
+            parameters: self.xfer_params(xfer),
+            request_body: self.xfer_request(xfer),
+            responses: self.xfer_responses(xfer),
+            tags: xfer.tags.clone(),
+            servers: self.xfer_servers(xfer),
+            // An empty list of requirements is kept, as it clears those of the document.
+            security: xfer.security.clone(),
+            callbacks: self.xfer_callbacks(xfer),
+            extensions,
+            deprecated: xfer.deprecated.unwrap_or(false),
+            ..Default::default()
+        }
+    }
+
+    /// Returns the callbacks of a transfer by name, with the path items of the relations
+    /// declaring each callback, keyed by the runtime expression followed by their URI pattern.
+    fn xfer_callbacks(&self, xfer: &spec::Transfer) -> IndexMap<String, Callback> {
+        xfer.callbacks
+            .iter()
+            .map(|(name, expr)| {
+                let callback = self
+                    .spec
+                    .rels
+                    .iter()
+                    .filter(|rel| rel.callback.as_ref() == Some(name))
+                    .map(|rel| {
+                        let pattern = rel.uri.pattern();
+                        let key = if pattern == "/" {
+                            expr.clone()
+                        } else {
+                            format!("{expr}{pattern}")
+                        };
+                        (key, self.relation_path_item(rel))
+                    })
+                    .collect();
+                (name.clone(), callback)
+            })
+            .collect()
+    }
+
+    /// Returns the servers of a transfer, if overriding those of the document.
+    ///
+    /// An explicit empty list clears the servers of the document, which OpenAPI expresses
+    /// as the default server of URL `/`, as an empty list would not be serialized.
+    fn xfer_servers(&self, xfer: &spec::Transfer) -> Vec<Server> {
+        match &xfer.servers {
+            Some(servers) if servers.is_empty() => vec![Server {
+                url: "/".to_owned(),
+                ..Default::default()
+            }],
+            servers => servers
+                .iter()
+                .flatten()
+                .map(|s| Server {
+                    url: s.url.clone(),
+                    description: s.desc.clone(),
+                    ..Default::default()
+                })
+                .collect(),
+        }
     }
 
     fn relation_path_item(&self, rel: &spec::Relation) -> PathItem {
         let mut path_item = PathItem {
             parameters: self.uri_params(&rel.uri),
+            extensions: self.vendor_extensions(&rel.extensions),
             ..Default::default()
         };
 
@@ -543,25 +1740,7 @@ impl Builder {
             .filter_map(|(m, x)| x.as_ref().map(|x| (m, x)));
 
         for (method, xfer) in xfers {
-            let operation_id = self.xfer_id(xfer, method, &rel.uri);
-            let summary = xfer
-                .summary
-                .clone()
-                .or_else(|| xfer.desc.clone())
-                .or_else(|| operation_id.clone());
-            let description = xfer.desc.clone();
-
-            let op = Operation {
-                summary,
-                description,
-                operation_id,
-                parameters: self.xfer_params(xfer),
-                request_body: self.xfer_request(xfer),
-                responses: self.xfer_responses(xfer),
-                tags: xfer.tags.clone(),
-                ..Default::default()
-            };
-
+            let op = self.relation_operation(rel, xfer, self.method_label(method));
             match method {
                 atom::Method::Get => path_item.get = Some(op),
                 atom::Method::Put => path_item.put = Some(op),
@@ -570,17 +1749,81 @@ impl Builder {
                 atom::Method::Delete => path_item.delete = Some(op),
                 atom::Method::Options => path_item.options = Some(op),
                 atom::Method::Head => path_item.head = Some(op),
+                atom::Method::Trace => path_item.trace = Some(op),
             }
         }
 
+        // Non-standard methods are emitted as vendor extensions of the path item.
+        for (verb, xfer) in rel.custom_xfers.iter() {
+            let op = self.relation_operation(rel, xfer, verb);
+            let value = serde_json::to_value(op).expect("operation should serialize");
+            path_item.extensions.insert(format!("x-{verb}"), value);
+        }
+
         path_item
     }
 
+    fn relation_operation(
+        &self,
+        rel: &spec::Relation,
+        xfer: &spec::Transfer,
+        method: &str,
+    ) -> Operation {
+        let mut op = self.xfer_operation(xfer, method, &rel.uri);
+        if op.tags.is_empty() {
+            op.tags.extend(self.tag_group(rel));
+        }
+        op
+    }
+
+    /// Returns the documentation group of a relation if enabled,
+    /// from its annotation or else named after the path of its module relative to the main module,
+    /// e.g. `users` or `admin/users`, so that modules of the same name in different folders do not collide.
+    fn tag_group(&self, rel: &spec::Relation) -> Option<String> {
+        if !self.tag_groups {
+            return None;
+        }
+        rel.tag_group.clone().or_else(|| {
+            let module = rel.module.as_ref()?;
+            let name = match self.spec.module.as_ref() {
+                Some(main) => module.relative_to(main)?,
+                None => module.url().path_segments()?.next_back()?.to_owned(),
+            };
+            Some(name.strip_suffix(".oal").unwrap_or(&name).to_owned())
+        })
+    }
+
+    /// Returns the tags of operations by documentation group, in order of declaration.
+    fn all_tag_groups(&self) -> IndexMap<String, Vec<String>> {
+        let mut groups: IndexMap<String, Vec<String>> = IndexMap::new();
+        for rel in self.spec.rels.iter() {
+            let Some(group) = self.tag_group(rel) else {
+                continue;
+            };
+            let xfers = rel.xfers.values().flatten();
+            for xfer in xfers.chain(rel.custom_xfers.values()) {
+                let tags = if xfer.tags.is_empty() {
+                    std::slice::from_ref(&group)
+                } else {
+                    xfer.tags.as_slice()
+                };
+                let group = groups.entry(group.clone()).or_default();
+                for tag in tags {
+                    if !group.contains(tag) {
+                        group.push(tag.clone());
+                    }
+                }
+            }
+        }
+        groups
+    }
+
     fn all_paths(&self) -> Paths {
         let paths = self
             .spec
             .rels
             .iter()
+            .filter(|rel| rel.webhook.is_none() && rel.callback.is_none())
             .map(|rel| {
                 (
                     rel.uri.pattern(),
@@ -594,16 +1837,36 @@ impl Builder {
         }
     }
 
+    /// Returns the path items of the relations describing requests sent by the API, by name.
+    fn all_webhooks(&self) -> IndexMap<String, PathItem> {
+        self.spec
+            .rels
+            .iter()
+            .filter_map(|rel| {
+                let name = rel.webhook.as_ref()?;
+                Some((name.clone(), self.relation_path_item(rel)))
+            })
+            .collect()
+    }
+
     fn all_components(&self) -> Components {
         let mut schemas = IndexMap::new();
         for (name, spec::Reference::Schema(s)) in self.spec.refs.iter() {
-            // Only keep components that couldn't be inlined.
-            if self.maybe_inline(name).is_none() {
-                schemas.insert(name.untagged(), self.schema(s));
+            // Only keep components that couldn't be inlined, unless hosted separately.
+            let hosted = self.external_schemas() && !self.schema_components;
+            if self.maybe_inline(name).is_none() && !hosted {
+                schemas.insert(self.component_name(name), self.schema(s));
             }
         }
+        let security_schemes = self
+            .spec
+            .security_schemes
+            .iter()
+            .map(|(name, s)| (name.clone(), ReferenceOr::Item(security_scheme(s))))
+            .collect();
         Components {
             schemas,
+            security_schemes,
             ..Default::default()
         }
     }
@@ -0,0 +1,80 @@
+use crate::components::{key, Shared};
+use indexmap::IndexMap;
+use openapiv3::{Operation, Parameter, PathItem, ReferenceOr};
+use std::collections::{HashMap, HashSet};
+
+type Parameters = Vec<ReferenceOr<Parameter>>;
+
+/// Applies a function to every operation of a path item.
+pub(crate) fn for_each_operation<F: FnMut(&mut Operation)>(item: &mut PathItem, f: &mut F) {
+    let ops = [
+        &mut item.get,
+        &mut item.put,
+        &mut item.post,
+        &mut item.delete,
+        &mut item.options,
+        &mut item.head,
+        &mut item.patch,
+        &mut item.trace,
+    ];
+    for op in ops.into_iter().flatten() {
+        f(op);
+    }
+    // Operations on non-standard methods are already serialized as vendor extensions.
+    for value in item.extensions.values_mut() {
+        if let Ok(mut op) = serde_json::from_value::<Operation>(value.clone()) {
+            f(&mut op);
+            *value = serde_json::to_value(op).expect("operation should serialize");
+        }
+    }
+}
+
+/// Applies a function to every parameter list of a path item and its operations.
+fn for_each_list<F: FnMut(&mut Parameters)>(item: &mut PathItem, f: &mut F) {
+    f(&mut item.parameters);
+    for_each_operation(item, &mut |op: &mut Operation| f(&mut op.parameters));
+}
+
+/// Replaces parameters by references to shared components,
+/// either those occurring more than once if `repeated` is set, or those of the given keys.
+///
+/// Components are named after their parameters, with a numeric suffix
+/// to avoid conflicts with the given existing names.
+pub(crate) fn share<'a, I>(
+    items: I,
+    existing: &mut IndexMap<String, ReferenceOr<Parameter>>,
+    repeated: bool,
+    keys: &HashSet<String>,
+) where
+    I: IntoIterator<Item = &'a mut PathItem>,
+{
+    let mut items = items.into_iter().collect::<Vec<_>>();
+
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for item in items.iter_mut() {
+        for_each_list(item, &mut |params: &mut Parameters| {
+            for p in params.iter() {
+                if let ReferenceOr::Item(p) = p {
+                    *counts.entry(key(p)).or_default() += 1;
+                }
+            }
+        });
+    }
+
+    let mut shared = Shared::new("parameters", existing);
+    for item in items.iter_mut() {
+        for_each_list(item, &mut |params: &mut Parameters| {
+            for p in params.iter_mut() {
+                let ReferenceOr::Item(param) = p else {
+                    continue;
+                };
+                let k = key(param);
+                let is_shared =
+                    keys.contains(&k) || repeated && counts.get(&k).copied().unwrap_or(0) >= 2;
+                if is_shared {
+                    *p = shared.reference(k, &param.parameter_data_ref().name, param);
+                }
+            }
+        });
+    }
+}
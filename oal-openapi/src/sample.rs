@@ -0,0 +1,497 @@
+//! Synthesis of sample payloads from schemas, e.g. to give examples to contents without any.
+//!
+//! Samples respect enumerations, patterns, bounds and array sizes, and are reproducible:
+//! the same seed always gives the same sample of the same schema.
+//! Explicit constants, examples and default values are used as is.
+//!
+//! Sizes are capped whatever the schema, so that samples stay small even when the bounds
+//! of a schema, e.g. `minItems: 1000000`, could not be met.
+
+use crate::FORMAT_EXAMPLES;
+use oal_compiler::errors::{Error, Kind, Result};
+use oal_compiler::spec::{
+    self, Object, PrimInteger, PrimNumber, PrimString, Schema, SchemaExpr, Spec,
+};
+use oal_syntax::atom::VariadicOperator;
+use serde_json::{Map, Number, Value};
+use std::cell::RefCell;
+
+/// The nesting depth beyond which optional properties and array items are left out,
+/// so that recursive schemas give finite samples.
+const MAX_DEPTH: usize = 4;
+
+/// The nesting depth beyond which nothing is sampled anymore.
+const MAX_HARD_DEPTH: usize = 16;
+
+/// The length of sampled strings without bounds.
+const STRING_LENGTH: usize = 8;
+
+/// The span of sampled numbers without bounds.
+const NUMBER_SPAN: i64 = 100;
+
+/// The additional repetitions of unbounded pattern quantifiers, e.g. `+` or `*`.
+const PATTERN_REPEAT: usize = 2;
+
+/// The maximum repetitions of pattern quantifiers, e.g. `{1000}`.
+const MAX_REPEAT: usize = 16;
+
+/// The maximum length of sampled strings, patterns included.
+const MAX_LENGTH: usize = 64;
+
+/// The maximum number of items of sampled arrays.
+const MAX_ITEMS: usize = 8;
+
+/// The maximum number of values of a sample, nested values included,
+/// beyond which nothing is sampled anymore.
+const MAX_VALUES: usize = 4096;
+
+/// Samples of strings by format, besides the placeholders of URI variables.
+const FORMAT_SAMPLES: &[(&str, &str)] = &[
+    ("time", "00:00:00Z"),
+    ("email", "user@example.com"),
+    ("uri", "https://example.com/"),
+    ("url", "https://example.com/"),
+    ("hostname", "example.com"),
+    ("ipv4", "192.0.2.1"),
+    ("ipv6", "2001:db8::1"),
+];
+
+const LOWERCASE: &[char] = &[
+    'a', 'b', 'c', 'd', 'e', 'f', 'g', 'h', 'i', 'j', 'k', 'l', 'm', 'n', 'o', 'p', 'q', 'r', 's',
+    't', 'u', 'v', 'w', 'x', 'y', 'z',
+];
+
+/// A deterministic pseudo-random generator (SplitMix64), good enough for samples.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a number in the inclusive range.
+    fn range(&mut self, min: i64, max: i64) -> i64 {
+        if max <= min {
+            return min;
+        }
+        let span = (max as i128 - min as i128 + 1) as u128;
+        (min as i128 + (self.next() as u128 % span) as i128) as i64
+    }
+
+    fn index(&mut self, len: usize) -> usize {
+        self.range(0, len as i64 - 1) as usize
+    }
+
+    fn pick<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.index(items.len())]
+    }
+}
+
+/// A node of a regular expression, in the subset supported by the synthesis.
+enum Node {
+    Chars(Vec<char>),
+    Seq(Vec<Node>),
+    Alt(Vec<Node>),
+    Repeat(Box<Node>, usize, usize),
+}
+
+/// Returns the characters of a class escape, e.g. `\d`, or else the escaped character.
+fn escape_chars(c: char) -> Vec<char> {
+    match c {
+        'd' => ('0'..='9').collect(),
+        'w' => ('a'..='z')
+            .chain('0'..='9')
+            .chain(std::iter::once('_'))
+            .collect(),
+        's' => vec![' '],
+        'n' => vec!['\n'],
+        't' => vec!['\t'],
+        c => vec![c],
+    }
+}
+
+/// Parses a regular expression, returning `None` for unsupported constructs.
+struct PatternParser<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+}
+
+impl PatternParser<'_> {
+    fn alternation(&mut self) -> Option<Node> {
+        let mut alts = vec![self.sequence()?];
+        while self.chars.peek() == Some(&'|') {
+            self.chars.next();
+            alts.push(self.sequence()?);
+        }
+        Some(if alts.len() == 1 {
+            alts.pop().unwrap()
+        } else {
+            Node::Alt(alts)
+        })
+    }
+
+    fn sequence(&mut self) -> Option<Node> {
+        let mut seq = Vec::new();
+        while let Some(&c) = self.chars.peek() {
+            if c == '|' || c == ')' {
+                break;
+            }
+            let atom = self.atom()?;
+            seq.push(self.quantifier(atom)?);
+        }
+        Some(Node::Seq(seq))
+    }
+
+    fn atom(&mut self) -> Option<Node> {
+        let c = self.chars.next()?;
+        let node = match c {
+            '^' | '$' => Node::Seq(Vec::new()),
+            '.' => Node::Chars(LOWERCASE.to_vec()),
+            '\\' => Node::Chars(escape_chars(self.chars.next()?)),
+            '(' => {
+                if self.chars.peek() == Some(&'?') {
+                    self.chars.next();
+                    if self.chars.next()? != ':' {
+                        return None;
+                    }
+                }
+                let node = self.alternation()?;
+                if self.chars.next()? != ')' {
+                    return None;
+                }
+                node
+            }
+            '[' => self.class()?,
+            '*' | '+' | '?' | '{' | ')' => return None,
+            c => Node::Chars(vec![c]),
+        };
+        Some(node)
+    }
+
+    fn class(&mut self) -> Option<Node> {
+        if self.chars.peek() == Some(&'^') {
+            // Negated classes are not supported.
+            return None;
+        }
+        let mut chars = Vec::new();
+        loop {
+            let c = self.chars.next()?;
+            match c {
+                ']' if !chars.is_empty() => break,
+                '\\' => chars.extend(escape_chars(self.chars.next()?)),
+                c => {
+                    if self.chars.peek() == Some(&'-') {
+                        self.chars.next();
+                        match self.chars.next()? {
+                            ']' => {
+                                chars.extend([c, '-']);
+                                break;
+                            }
+                            end if end >= c => chars.extend(c..=end),
+                            _ => return None,
+                        }
+                    } else {
+                        chars.push(c);
+                    }
+                }
+            }
+        }
+        Some(Node::Chars(chars))
+    }
+
+    fn number(&mut self) -> Option<usize> {
+        let mut digits = String::new();
+        while let Some(c) = self.chars.peek().filter(|c| c.is_ascii_digit()) {
+            digits.push(*c);
+            self.chars.next();
+        }
+        digits.parse().ok()
+    }
+
+    fn quantifier(&mut self, atom: Node) -> Option<Node> {
+        let (min, max) = match self.chars.peek() {
+            Some('*') => (0, PATTERN_REPEAT),
+            Some('+') => (1, 1 + PATTERN_REPEAT),
+            Some('?') => (0, 1),
+            Some('{') => {
+                self.chars.next();
+                let min = self.number()?;
+                let (max, closed) = match self.chars.next()? {
+                    '}' => (min, true),
+                    ',' => match self.chars.peek() {
+                        Some('}') => (min.saturating_add(PATTERN_REPEAT), false),
+                        _ => (self.number()?, false),
+                    },
+                    _ => return None,
+                };
+                if (!closed && self.chars.next()? != '}') || max < min {
+                    return None;
+                }
+                let (min, max) = (min.min(MAX_REPEAT), max.min(MAX_REPEAT));
+                return Some(Node::Repeat(atom.into(), min, max));
+            }
+            _ => return Some(atom),
+        };
+        self.chars.next();
+        // Lazy quantifiers repeat the same.
+        if self.chars.peek() == Some(&'?') {
+            self.chars.next();
+        }
+        Some(Node::Repeat(atom.into(), min, max))
+    }
+}
+
+fn parse_pattern(pattern: &str) -> Option<Node> {
+    let mut parser = PatternParser {
+        chars: pattern.chars().peekable(),
+    };
+    let node = parser.alternation()?;
+    parser.chars.next().is_none().then_some(node)
+}
+
+/// Synthesizes sample values from the schemas of a specification.
+pub struct Sampler<'a> {
+    spec: &'a Spec,
+    rng: Rng,
+    /// The number of values sampled so far.
+    values: usize,
+}
+
+impl<'a> Sampler<'a> {
+    pub fn new(spec: &'a Spec, seed: u64) -> Self {
+        Sampler {
+            spec,
+            rng: Rng(seed),
+            values: 0,
+        }
+    }
+
+    /// Returns a sample of the schema.
+    pub fn sample(&mut self, schema: &Schema) -> Result<Value> {
+        self.values = 0;
+        self.schema(schema, 0)
+    }
+
+    fn schema(&mut self, schema: &Schema, depth: usize) -> Result<Value> {
+        let given = schema
+            .constant
+            .as_ref()
+            .or(schema.example.as_ref())
+            .or(schema.default.as_ref());
+        if let Some(value) = given {
+            return serde_json::to_value(value).map_err(|err| {
+                let msg = format!("cannot convert the example to JSON: {err}");
+                Error::new(Kind::InvalidLiteral, msg)
+            });
+        }
+        self.values += 1;
+        if depth > MAX_HARD_DEPTH || self.values > MAX_VALUES {
+            return Ok(Value::Null);
+        }
+        let value = match &schema.expr {
+            SchemaExpr::Num(p) => self.number(p),
+            SchemaExpr::Str(p) => Value::String(self.string(p)),
+            SchemaExpr::Bool(p) => match p.enumeration.as_slice() {
+                [] => Value::Bool(self.rng.next().is_multiple_of(2)),
+                values => Value::Bool(*self.rng.pick(values)),
+            },
+            SchemaExpr::Int(p) => self.integer(p).map_or(Value::Null, Value::from),
+            SchemaExpr::Uri(uri) => Value::String(self.uri(uri)?),
+            SchemaExpr::Rel(rel) => Value::String(self.uri(&rel.uri)?),
+            SchemaExpr::Array(array) => self.array(array, depth)?,
+            SchemaExpr::Object(obj) => Value::Object(self.object(obj, depth)?),
+            SchemaExpr::Op(op) => match op.op {
+                VariadicOperator::Join => {
+                    let mut joined = Map::new();
+                    for s in op.schemas.iter() {
+                        if let Value::Object(obj) = self.schema(s, depth)? {
+                            joined.extend(obj);
+                        }
+                    }
+                    Value::Object(joined)
+                }
+                // The first alternative is sampled, the others being as valid.
+                _ => match op.schemas.first() {
+                    Some(s) => self.schema(s, depth)?,
+                    None => Value::Null,
+                },
+            },
+            SchemaExpr::Ref(ident) => match self.spec.refs.get(ident) {
+                Some(spec::Reference::Schema(s)) => self.schema(s, depth + 1)?,
+                None => Value::Null,
+            },
+        };
+        Ok(value)
+    }
+
+    fn string(&mut self, p: &PrimString) -> String {
+        if let Some(example) = &p.example {
+            return example.clone();
+        }
+        if !p.enumeration.is_empty() {
+            return self.rng.pick(&p.enumeration).clone();
+        }
+        if let Some(node) = p.pattern.as_deref().and_then(parse_pattern) {
+            let mut s = String::new();
+            self.pattern(&node, &mut s);
+            return s;
+        }
+        if let Some(format) = &p.format {
+            let mut samples = FORMAT_EXAMPLES.iter().chain(FORMAT_SAMPLES.iter());
+            if let Some((_, sample)) = samples.find(|(f, _)| f == format) {
+                return sample.to_string();
+            }
+        }
+        let min = p.min_length.unwrap_or(0).min(MAX_LENGTH);
+        let max = p.max_length.unwrap_or(usize::MAX).max(min);
+        let len = STRING_LENGTH.clamp(min, max);
+        (0..len).map(|_| *self.rng.pick(LOWERCASE)).collect()
+    }
+
+    fn pattern(&mut self, node: &Node, out: &mut String) {
+        // Nested quantifiers multiply, e.g. `(a{16}){16}`, so the length is capped as a whole.
+        if out.chars().count() >= MAX_LENGTH {
+            return;
+        }
+        match node {
+            Node::Chars(chars) => out.push(*self.rng.pick(chars)),
+            Node::Seq(nodes) => nodes.iter().for_each(|n| self.pattern(n, out)),
+            Node::Alt(nodes) => {
+                let n = self.rng.pick(nodes);
+                self.pattern(n, out)
+            }
+            Node::Repeat(node, min, max) => {
+                let count = self.rng.range(*min as i64, *max as i64);
+                for _ in 0..count {
+                    self.pattern(node, out);
+                }
+            }
+        }
+    }
+
+    /// Returns a sample integer, if any lies within the bounds.
+    fn integer(&mut self, p: &PrimInteger) -> Option<i64> {
+        if let Some(example) = p.example {
+            return Some(example);
+        }
+        if !p.enumeration.is_empty() {
+            return Some(*self.rng.pick(&p.enumeration));
+        }
+        let min = match p.minimum {
+            Some(m) => Some(m.checked_add(p.exclusive_minimum as i64)?),
+            None => None,
+        };
+        let max = match p.maximum {
+            Some(m) => Some(m.checked_sub(p.exclusive_maximum as i64)?),
+            None => None,
+        };
+        let (min, max) = match (min, max) {
+            (Some(min), Some(max)) => (min, max.max(min)),
+            (Some(min), None) => (min, min.saturating_add(NUMBER_SPAN)),
+            (None, Some(max)) => (max.saturating_sub(NUMBER_SPAN).max(0).min(max), max),
+            (None, None) => (0, NUMBER_SPAN),
+        };
+        let value = self.rng.range(min, max);
+        match p.multiple_of.filter(|m| *m > 0) {
+            Some(m) => {
+                // The multiple closest to the value within bounds, if any.
+                let down = value.checked_sub(value.rem_euclid(m))?;
+                if down >= min {
+                    Some(down)
+                } else {
+                    down.checked_add(m)
+                }
+            }
+            None => Some(value),
+        }
+    }
+
+    fn number(&mut self, p: &PrimNumber) -> Value {
+        if let Some(example) = p.example {
+            return Number::from_f64(example).map_or(Value::Null, Value::Number);
+        }
+        if !p.enumeration.is_empty() {
+            let value = *self.rng.pick(&p.enumeration);
+            return Number::from_f64(value).map_or(Value::Null, Value::Number);
+        }
+        let span = NUMBER_SPAN as f64;
+        let (min, max) = match (p.minimum, p.maximum) {
+            (Some(min), Some(max)) => (min, max.max(min)),
+            (Some(min), None) => (min, min + span),
+            (None, Some(max)) => ((max - span).max(0.0).min(max), max),
+            (None, None) => (0.0, span),
+        };
+        let ratio = (self.rng.next() % 1000) as f64 / 1000.0;
+        let mut value = min + (max - min) * ratio;
+        // Values are rounded to cents, within bounds, unless they must be multiples.
+        value = (value * 100.0).round() / 100.0;
+        if let Some(m) = p.multiple_of.filter(|m| *m > 0.0) {
+            value = (value / m).ceil() * m;
+        }
+        if (p.exclusive_minimum && value <= min) || (p.exclusive_maximum && value >= max) {
+            value = (min + max) / 2.0;
+        }
+        Number::from_f64(value).map_or(Value::Null, Value::Number)
+    }
+
+    fn uri(&mut self, uri: &spec::Uri) -> Result<String> {
+        if let Some(example) = &uri.example {
+            return Ok(example.clone());
+        }
+        // Path variables are primitives, sampled in place.
+        let mut values = Vec::new();
+        for s in uri.path.iter() {
+            if let spec::UriSegment::Variable(p) = s {
+                let value = match self.schema(&p.schema, MAX_HARD_DEPTH)? {
+                    Value::String(s) => s,
+                    v => v.to_string(),
+                };
+                values.push(value);
+            }
+        }
+        let values = RefCell::new(values.into_iter());
+        Ok(uri.pattern_with(|_| values.borrow_mut().next().unwrap_or_default()))
+    }
+
+    fn array(&mut self, array: &spec::Array, depth: usize) -> Result<Value> {
+        let min = array.min_items.unwrap_or(0).min(MAX_ITEMS);
+        let max = array.max_items.unwrap_or(usize::MAX).max(min);
+        let len = if depth >= MAX_DEPTH {
+            min
+        } else {
+            1.clamp(min, max)
+        };
+        let mut items: Vec<Value> = Vec::with_capacity(len);
+        // Unique items are retried a few times before giving up on uniqueness.
+        let mut attempts = len * 4;
+        while items.len() < len {
+            let item = self.schema(&array.item, depth + 1)?;
+            if array.unique_items == Some(true) && items.contains(&item) && attempts > 0 {
+                attempts -= 1;
+                continue;
+            }
+            items.push(item);
+        }
+        Ok(Value::Array(items))
+    }
+
+    fn object(&mut self, obj: &Object, depth: usize) -> Result<Map<String, Value>> {
+        let mut map = Map::new();
+        for p in obj.props.iter() {
+            if depth >= MAX_DEPTH && p.required != Some(true) {
+                continue;
+            }
+            let value = self.schema(&p.schema, depth + 1)?;
+            map.insert(p.name.as_ref().to_owned(), value);
+        }
+        Ok(map)
+    }
+}
+
+/// Returns a sample of a schema of the specification, reproducible from the seed.
+pub fn sample(spec: &Spec, schema: &Schema, seed: u64) -> Result<Value> {
+    Sampler::new(spec, seed).sample(schema)
+}
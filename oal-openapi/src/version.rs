@@ -0,0 +1,187 @@
+use serde_yaml::{Mapping, Value};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// The version of the OpenAPI specification targeted by the generated document.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OpenApiVersion {
+    #[default]
+    V3_0,
+    V3_1,
+}
+
+impl OpenApiVersion {
+    /// Returns the value of the `openapi` field of documents targeting this version.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OpenApiVersion::V3_0 => "3.0.3",
+            OpenApiVersion::V3_1 => "3.1.0",
+        }
+    }
+}
+
+impl Display for OpenApiVersion {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for OpenApiVersion {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "3.0" | "3.0.3" => Ok(OpenApiVersion::V3_0),
+            "3.1" | "3.1.0" => Ok(OpenApiVersion::V3_1),
+            _ => Err(format!(
+                "unsupported OpenAPI version {s}, expected 3.0 or 3.1"
+            )),
+        }
+    }
+}
+
+/// The vendor extension holding webhooks in 3.0 documents.
+pub(crate) const WEBHOOKS_EXTENSION: &str = "x-webhooks";
+
+/// Removes a boolean keyword from a schema, leaving properties of the same name untouched.
+fn take_flag(obj: &mut Mapping, key: &str) -> Option<bool> {
+    let flag = obj.get(key)?.as_bool()?;
+    obj.shift_remove(key);
+    Some(flag)
+}
+
+/// Converts the 3.0 keywords of a schema to their JSON Schema equivalents.
+fn upgrade_schema(obj: &mut Mapping) {
+    if take_flag(obj, "nullable") == Some(true) {
+        if let Some(Value::String(t)) = obj.get("type") {
+            let types = Value::Sequence(vec![t.as_str().into(), "null".into()]);
+            obj.insert("type".into(), types);
+            // An enumeration restricts the values of every type, so it must allow null as well.
+            if let Some(Value::Sequence(values)) = obj.get_mut("enum") {
+                if !values.contains(&Value::Null) {
+                    values.push(Value::Null);
+                }
+            }
+        } else if ["allOf", "oneOf", "anyOf"]
+            .iter()
+            .any(|k| obj.contains_key(*k))
+        {
+            // Compositions, e.g. a wrapped reference, accept null as an alternative.
+            let schema = Value::Mapping(std::mem::take(obj));
+            let null = Value::Mapping(Mapping::from_iter([("type".into(), "null".into())]));
+            obj.insert("anyOf".into(), Value::Sequence(vec![schema, null]));
+        }
+    }
+    for (exclusive, bound) in [
+        ("exclusiveMinimum", "minimum"),
+        ("exclusiveMaximum", "maximum"),
+    ] {
+        // A boolean qualifies the bound, whereas a number is the bound itself in 3.1.
+        if take_flag(obj, exclusive) == Some(true) {
+            if let Some(value) = obj.shift_remove(bound) {
+                obj.insert(exclusive.into(), value);
+            }
+        }
+    }
+}
+
+/// The keywords of a schema holding a schema, or a sequence of schemas.
+const SUBSCHEMAS: [&str; 8] = [
+    "items",
+    "additionalProperties",
+    "not",
+    "allOf",
+    "oneOf",
+    "anyOf",
+    "prefixItems",
+    "contains",
+];
+
+/// The keywords of a schema holding schemas by name.
+const NAMED_SUBSCHEMAS: [&str; 3] = ["properties", "patternProperties", "$defs"];
+
+/// The keywords of OpenAPI objects holding objects by name, e.g. headers by header name,
+/// so that names are never mistaken for keywords.
+const NAMED_OBJECTS: [&str; 10] = [
+    "paths",
+    "webhooks",
+    "callbacks",
+    "responses",
+    "content",
+    "headers",
+    "encoding",
+    "parameters",
+    "requestBodies",
+    "links",
+];
+
+/// The keywords of OpenAPI objects holding literal values rather than OpenAPI objects.
+fn is_literal(key: &str) -> bool {
+    matches!(key, "example" | "examples" | "default") || key.starts_with("x-")
+}
+
+/// Converts the 3.0 keywords of a schema and of its subschemas to their JSON Schema equivalents,
+/// leaving literal values such as examples, defaults and enumerations untouched.
+fn upgrade_schemas(value: &mut Value) {
+    match value {
+        Value::Mapping(obj) => {
+            upgrade_schema(obj);
+            for (key, value) in obj.iter_mut() {
+                let Some(key) = key.as_str() else { continue };
+                if SUBSCHEMAS.contains(&key) {
+                    upgrade_schemas(value);
+                } else if NAMED_SUBSCHEMAS.contains(&key) {
+                    if let Some(schemas) = value.as_mapping_mut() {
+                        schemas.values_mut().for_each(upgrade_schemas);
+                    }
+                }
+            }
+        }
+        Value::Sequence(items) => items.iter_mut().for_each(upgrade_schemas),
+        _ => {}
+    }
+}
+
+/// Converts the 3.0 keywords of the schemas of a map of schemas by name, e.g. components.
+pub(crate) fn upgrade_named_schemas(value: &mut Value) {
+    if let Some(schemas) = value.as_mapping_mut() {
+        schemas.values_mut().for_each(upgrade_schemas);
+    }
+}
+
+/// Converts the 3.0 keywords of the schemas of an OpenAPI object, found at schema positions only.
+fn upgrade_object(value: &mut Value) {
+    match value {
+        Value::Mapping(obj) => {
+            for (key, value) in obj.iter_mut() {
+                let Some(key) = key.as_str() else { continue };
+                if key == "schema" {
+                    upgrade_schemas(value);
+                } else if key == "schemas" {
+                    upgrade_named_schemas(value);
+                } else if NAMED_OBJECTS.contains(&key) && value.is_mapping() {
+                    value
+                        .as_mapping_mut()
+                        .into_iter()
+                        .flat_map(|m| m.values_mut())
+                        .for_each(upgrade_object);
+                } else if !is_literal(key) {
+                    upgrade_object(value);
+                }
+            }
+        }
+        Value::Sequence(items) => items.iter_mut().for_each(upgrade_object),
+        _ => {}
+    }
+}
+
+/// Upgrades a serialized 3.0 document to 3.1.
+pub(crate) fn upgrade(doc: &mut Value) {
+    if let Some(obj) = doc.as_mapping_mut() {
+        obj.insert("openapi".into(), OpenApiVersion::V3_1.as_str().into());
+        if let Some(webhooks) = obj.shift_remove(WEBHOOKS_EXTENSION) {
+            obj.insert("webhooks".into(), webhooks);
+        }
+    }
+    upgrade_object(doc);
+}
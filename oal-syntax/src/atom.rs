@@ -121,6 +121,16 @@ pub enum HttpStatus {
     Range(HttpStatusRange),
 }
 
+impl HttpStatus {
+    /// Returns whether the status, or the range of statuses, is that of a success response.
+    pub fn is_success(&self) -> bool {
+        match self {
+            HttpStatus::Code(code) => (200..300).contains(&code.get()),
+            HttpStatus::Range(range) => *range == HttpStatusRange::Success,
+        }
+    }
+}
+
 impl TryFrom<u64> for HttpStatus {
     type Error = Error;
 
@@ -152,6 +162,7 @@ pub enum Method {
     Delete,
     Options,
     Head,
+    Trace,
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
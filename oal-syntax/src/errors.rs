@@ -12,4 +12,15 @@ pub enum Error {
     Domain,
 }
 
+impl Error {
+    /// Returns the stable diagnostic code of the error.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Error::Grammar(_) => "E0001",
+            Error::Lexicon(_) => "E0002",
+            Error::Domain => "E0003",
+        }
+    }
+}
+
 pub type Result<T> = std::result::Result<T, Error>;
@@ -25,7 +25,7 @@ pub enum TokenKind {
     PrimitiveInt,
     #[token("/")]
     PathElementRoot,
-    #[regex("/[0-9a-zA-Z%~_.-]+")]
+    #[regex(r"/[\p{L}\p{N}%~_.-]+")]
     PathElementSegment,
     #[token("get")]
     MethodGet,
@@ -113,6 +113,8 @@ pub enum TokenKind {
     OperatorArrow,
     #[regex(r"#[^\r\n]*[\r\n]*")]
     AnnotationLine,
+    #[regex(r"#![^\r\n]*[\r\n]*")]
+    AnnotationDocument,
     #[regex("`[^`]*`")]
     AnnotationInline,
 }
@@ -131,8 +133,10 @@ fn test_lexer() {
         (" \t\r\n", TokenKind::Space),
         ("`annotation`", TokenKind::AnnotationInline),
         ("# annotation", TokenKind::AnnotationLine),
+        ("#! annotation", TokenKind::AnnotationDocument),
         ("/", TokenKind::PathElementRoot),
         ("/abc", TokenKind::PathElementSegment),
+        ("/café", TokenKind::PathElementSegment),
     ];
 
     for (input, token) in cases {
@@ -340,6 +344,9 @@ pub fn tokenize(loc: Locator, input: &str) -> (Option<TokenList<Token>>, Vec<Par
                     TokenKind::AnnotationLine => {
                         TokenValue::Symbol(list.register(parse_prefixed_string(slice)))
                     }
+                    TokenKind::AnnotationDocument => TokenValue::Symbol(
+                        list.register(parse_prefixed_string(parse_prefixed_string(slice))),
+                    ),
                     TokenKind::AnnotationInline => {
                         TokenValue::Symbol(list.register(parse_quoted_string(slice)))
                     }
@@ -21,7 +21,13 @@ pub fn parse<I: AsRef<str>, T: Core>(
     if let Some(tokens) = tokens {
         let mut ctx = Context::new(tokens);
         let cursor = ctx.head();
-        match crate::parser::parse_program(&mut ctx, cursor) {
+        let result = crate::parser::parse_program(&mut ctx, cursor);
+        if let Some(err) = ctx.overflow() {
+            // Reports the root cause rather than the subsequent backtracking failures.
+            errs.push(err.clone().into());
+            return (None, errs);
+        }
+        match result {
             Ok((s, root)) => {
                 if s.is_valid() {
                     errs.push(ParserError::new("cannot parse remaining input", ctx.span(s)).into());
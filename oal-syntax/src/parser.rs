@@ -84,7 +84,30 @@ impl<T: Core> PropertyName<'_, T> {
     }
 }
 
-terminal_node!(Gram, Method, k if k.is_method());
+/// The name of the contextual keyword of the TRACE method, an identifier elsewhere.
+const TRACE: &str = "trace";
+
+/// A method of a transfer.
+#[derive(Debug)]
+pub struct Method<'a, T: Core>(NodeRef<'a, T, Gram>);
+
+impl<'a, T: Core> AbstractSyntaxNode<'a, T, Gram> for Method<'a, T> {
+    fn cast(node: NodeRef<'a, T, Gram>) -> Option<Self> {
+        match node.syntax().trunk() {
+            SyntaxTrunk::Leaf(t) if t.kind().is_method() => Some(Method(node)),
+            SyntaxTrunk::Leaf(t)
+                if t.kind() == TokenKind::IdentifierValue && node.as_str() == TRACE =>
+            {
+                Some(Method(node))
+            }
+            _ => None,
+        }
+    }
+
+    fn node(&self) -> NodeRef<'a, T, Gram> {
+        self.0
+    }
+}
 
 impl<T: Core> Method<'_, T> {
     pub fn method(&self) -> atom::Method {
@@ -96,6 +119,7 @@ impl<T: Core> Method<'_, T> {
             TokenKind::MethodDelete => atom::Method::Delete,
             TokenKind::MethodOptions => atom::Method::Options,
             TokenKind::MethodHead => atom::Method::Head,
+            TokenKind::IdentifierValue => atom::Method::Trace,
             _ => unreachable!(),
         }
     }
@@ -190,7 +214,7 @@ impl<T: Core> OptionMark<'_, T> {
 terminal_node!(
     Gram,
     Annotation,
-    TokenKind::AnnotationLine | TokenKind::AnnotationInline
+    TokenKind::AnnotationLine | TokenKind::AnnotationInline | TokenKind::AnnotationDocument
 );
 
 impl<'a, T: Core> Annotation<'a, T> {
@@ -199,7 +223,6 @@ impl<'a, T: Core> Annotation<'a, T> {
     }
 }
 
-// TODO: add support for document attributes
 syntax_nodes!(
     Gram,
     Terminal,
@@ -250,6 +273,11 @@ impl<'a, T: Core> Program<'a, T> {
     pub fn imports(&self) -> impl Iterator<Item = Import<'a, T>> {
         self.node().children().filter_map(Import::cast)
     }
+
+    /// Returns the annotations of the whole document, e.g. `#! key: value`.
+    pub fn annotations(&self) -> impl Iterator<Item = Annotation<'a, T>> {
+        self.node().children().filter_map(Annotation::cast)
+    }
 }
 
 impl<'a, T: Core> Resource<'a, T> {
@@ -498,12 +526,10 @@ impl<'a, T: Core> XferMethods<'a, T> {
 impl<'a, T: Core> XferParams<'a, T> {
     const INNER_POS: usize = 0;
 
+    /// Returns either an object, a variable or a subexpression, e.g. a join of parameter groups.
     #[allow(clippy::iter_nth_zero)]
-    pub fn inner(&self) -> Option<Object<'a, T>> {
-        self.node()
-            .children()
-            .nth(Self::INNER_POS)
-            .map(|inner| Object::cast(inner).expect("transfer parameters must be an object"))
+    pub fn inner(&self) -> Option<NodeRef<'a, T, Gram>> {
+        self.node().children().nth(Self::INNER_POS)
     }
 }
 
@@ -530,7 +556,7 @@ impl<'a, T: Core> Transfer<'a, T> {
             .methods()
     }
 
-    pub fn params(&self) -> Option<Object<'a, T>> {
+    pub fn params(&self) -> Option<NodeRef<'a, T, Gram>> {
         XferParams::cast(self.node().nth(Self::PARAMS_POS))
             .expect("expected transfer parameters")
             .inner()
@@ -629,8 +655,12 @@ impl<'a, T: Core> Application<'a, T> {
 }
 
 impl<'a, T: Core> XferList<'a, T> {
-    pub fn items(&self) -> impl Iterator<Item = NodeRef<'a, T, Gram>> {
-        self.node().children().step_by(2)
+    /// Returns the items of the list, as terminals carrying the annotations of each item.
+    pub fn items(&self) -> impl Iterator<Item = Terminal<'a, T>> {
+        self.node()
+            .children()
+            .step_by(2)
+            .map(|n| Terminal::cast(n).expect("expected a terminal"))
     }
 }
 
@@ -643,6 +673,11 @@ impl<'a, T: Core> Relation<'a, T> {
     }
 
     pub fn transfers(&self) -> impl Iterator<Item = NodeRef<'a, T, Gram>> {
+        self.items().map(|t| t.inner())
+    }
+
+    /// Returns the transfer items along with their annotations.
+    pub fn items(&self) -> impl Iterator<Item = Terminal<'a, T>> {
         XferList::cast(self.node().nth(Self::XFERS_POS))
             .expect("expected a transfer list")
             .items()
@@ -688,7 +723,8 @@ pub fn parse_program<T: Core>(c: &mut Context<T>, s: Cursor) -> ParserResult {
 }
 
 pub fn parse_statement<T: Core>(c: &mut Context<T>, s: Cursor) -> ParserResult {
-    parse_import(c, s)
+    parse_token(c, s, TokenKind::AnnotationDocument)
+        .or_else(|_| parse_import(c, s))
         .or_else(|_| parse_declaration(c, s))
         .or_else(|_| parse_resource(c, s))
 }
@@ -738,9 +774,30 @@ pub fn parse_recursion<T: Core>(c: &mut Context<T>, s: Cursor) -> ParserResult {
     Ok((s, c.compose(SyntaxKind::Recursion, &[n0, n1, n2])))
 }
 
+pub fn parse_xfer_item<T: Core>(c: &mut Context<T>, s: Cursor) -> ParserResult {
+    let (s0, n0) = parse_line_annotations(c, s)?;
+    let (s, n1) = parse_expression(c, s0)?;
+    if let Ok((s, n2)) = parse_token(c, s, TokenKind::AnnotationInline) {
+        return Ok((s, c.compose(SyntaxKind::Terminal, &[n0, n1, n2])));
+    }
+    // The inline annotation ending a transfer belongs to the item rather than to its range.
+    if let Some(ann) = c.last_token(s0, s) {
+        if ann.kind() == TokenKind::AnnotationInline {
+            let end = ann.cursor();
+            if let Ok((e, n1)) = c.bounded(end, |c| parse_transfer(c, s0)) {
+                if e == end {
+                    let n2 = ParserMatch::Token(ann);
+                    return Ok((s, c.compose(SyntaxKind::Terminal, &[n0, n1, n2])));
+                }
+            }
+        }
+    }
+    Ok((s, c.compose(SyntaxKind::Terminal, &[n0, n1])))
+}
+
 pub fn parse_xfer_list<T: Core>(c: &mut Context<T>, s: Cursor) -> ParserResult {
     let ns = &mut Vec::new();
-    let s = intersperse(c, s, ns, parse_expression, |c, s| {
+    let s = intersperse(c, s, ns, parse_xfer_item, |c, s| {
         parse_token(c, s, TokenKind::ControlComma)
     })?;
     Ok((s, c.compose(SyntaxKind::XferList, ns)))
@@ -1059,13 +1116,18 @@ pub fn parse_xfer_domain<T: Core>(c: &mut Context<T>, s: Cursor) -> ParserResult
 }
 
 pub fn parse_xfer_params<T: Core>(c: &mut Context<T>, s: Cursor) -> ParserResult {
-    let (s, n) = parse_object(c, s)?;
+    let (s, n) = parse_object(c, s)
+        .or_else(|_| parse_variable(c, s))
+        .or_else(|_| parse_subexpr(c, s))?;
     Ok((s, c.compose(SyntaxKind::XferParams, &[n])))
 }
 
 pub fn parse_xfer_methods<T: Core>(c: &mut Context<T>, s: Cursor) -> ParserResult {
     let ns = &mut Vec::new();
-    let method: ParserFn<T> = |c, s| parse_token_with(c, s, TokenKind::is_method);
+    let method: ParserFn<T> = |c, s| {
+        parse_token_with(c, s, TokenKind::is_method)
+            .or_else(|_| parse_word(c, s, TokenKind::IdentifierValue, TRACE))
+    };
     let s = intersperse(c, s, ns, method, parse_comma)?;
     Ok((s, c.compose(SyntaxKind::XferMethods, ns)))
 }
@@ -1117,9 +1179,12 @@ pub fn parse_relation_kind<T: Core>(c: &mut Context<T>, s: Cursor) -> ParserResu
 }
 
 pub fn parse_expression<T: Core>(c: &mut Context<T>, s: Cursor) -> ParserResult {
-    memoize(ParserTag::Expression, c, s, |c, s| {
+    c.enter(s)?;
+    let r = memoize(ParserTag::Expression, c, s, |c, s| {
         parse_recursion(c, s).or_else(|_| parse_relation_kind(c, s))
-    })
+    });
+    c.leave();
+    r
 }
 
 pub fn parse_declaration<T: Core>(c: &mut Context<T>, s: Cursor) -> ParserResult {
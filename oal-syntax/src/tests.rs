@@ -1,8 +1,8 @@
 use super::lexer as lex;
 use super::parser::{
     Application, Array, Content, Declaration, Gram, Literal, Object, PathElement, Primitive,
-    Program, Property, Recursion, Relation, Terminal, Transfer, UnaryOp, UriSegment, UriTemplate,
-    Variable, VariadicOp,
+    Program, Property, Recursion, Relation, SubExpression, Terminal, Transfer, UnaryOp, UriSegment,
+    UriTemplate, Variable, VariadicOp,
 };
 use crate::atom;
 use crate::parser::{ContentTagKind, LiteralKind, PrimitiveKind};
@@ -183,6 +183,7 @@ fn parse_decl_transfer() {
         assert_eq!(methods, vec![atom::Method::Get, atom::Method::Put]);
 
         let params = xfer.params().expect("expected parameters");
+        let params = Object::cast(params).expect("expected an object");
         let props = &mut params.properties();
 
         let prop = assert_next_prop(props);
@@ -192,6 +193,19 @@ fn parse_decl_transfer() {
         assert!(xfer.domain().is_some(), "expected a domain");
         assert_term(xfer.range());
     });
+    parse("let a = get filters -> {};", |p: Prog| {
+        let xfer = Transfer::cast(assert_decl(p, "a").rhs()).expect("expected transfer");
+        let params = xfer.params().expect("expected parameters");
+        assert!(Variable::cast(params).is_some(), "expected a variable");
+    });
+    parse("let a = get (filters & { 'q str }) -> {};", |p: Prog| {
+        let xfer = Transfer::cast(assert_decl(p, "a").rhs()).expect("expected transfer");
+        let params = xfer.params().expect("expected parameters");
+        assert!(
+            SubExpression::cast(params).is_some(),
+            "expected a subexpression"
+        );
+    });
     parse("let a = get -> <{}> :: <{}>;", |p: Prog| {
         let xfer = Transfer::cast(assert_decl(p, "a").rhs()).expect("expected transfer");
         let op = VariadicOp::cast(xfer.range()).expect("expected an operation");
@@ -287,6 +301,22 @@ fn parse_import() {
     })
 }
 
+#[test]
+fn parse_document_annotations() {
+    let code = r#"
+        #! empty-status: 200
+        # description: "not a document annotation"
+        let a = num;
+        #! title: "API"
+    "#;
+    parse(code, |p: Prog| {
+        let anns = p.annotations().map(|a| a.as_str()).collect::<Vec<_>>();
+        assert_eq!(anns, [" empty-status: 200\n", " title: \"API\"\n"]);
+        let d = assert_decl(p, "a");
+        assert_eq!(d.annotations().count(), 1);
+    })
+}
+
 #[test]
 fn parse_terminal_annotations() {
     parse(r#"let a = num `title: "number"`;"#, |p: Prog| {
@@ -516,6 +546,49 @@ let a = /p on
     });
 }
 
+#[test]
+fn parse_xfer_annotations() {
+    parse(
+        r#"
+let a = /p on
+    # tags: [admin]
+    put : <{}> -> <{}>,
+    get -> <{}> `summary: "read"` `operationId: read`,
+    delete -> <{}> `tags: [admin]`,
+    patch -> x `tags: [admin]`
+;
+"#,
+        |p: Prog| {
+            let decl = assert_decl(p, "a");
+            let rel = Relation::cast(decl.rhs()).expect("expected a relation");
+            let items = &mut rel.items();
+            let anns = |t: &Terminal<_>| -> Vec<_> {
+                t.annotations().map(|a| a.as_str().to_owned()).collect()
+            };
+
+            let term = items.next().expect("expected a transfer");
+            assert_eq!(anns(&term), vec![" tags: [admin]\n"]);
+            Transfer::cast(term.inner()).expect("expected a transfer");
+
+            let term = items.next().expect("expected a transfer");
+            assert_eq!(anns(&term), vec!["operationId: read"]);
+            let xfer = Transfer::cast(term.inner()).expect("expected a transfer");
+            let range = Terminal::cast(xfer.range()).expect("expected a terminal");
+            assert_eq!(anns(&range), vec![r#"summary: "read""#]);
+
+            for _ in 0..2 {
+                let term = items.next().expect("expected a transfer");
+                assert_eq!(anns(&term), vec!["tags: [admin]"]);
+                let xfer = Transfer::cast(term.inner()).expect("expected a transfer");
+                let range = Terminal::cast(xfer.range()).expect("expected a terminal");
+                assert!(anns(&range).is_empty(), "expected no range annotation");
+            }
+
+            assert!(items.next().is_none(), "expected no more transfer");
+        },
+    )
+}
+
 #[test]
 fn parse_decl_annotations() {
     parse(
@@ -596,3 +669,17 @@ fn parse_lexicon_error() {
         "expected a lexicon error"
     );
 }
+
+#[test]
+fn parse_nesting_error() {
+    let loc = Locator::try_from("file:///test.oal").unwrap();
+    let depth = 10_000;
+    let code = format!("let a = {}num{};", "(".repeat(depth), ")".repeat(depth));
+    let (tree, errs) = crate::parse::<_, ()>(loc, code);
+    assert!(tree.is_none(), "expected no tree");
+    assert_eq!(errs.len(), 1, "expected an error");
+    assert!(
+        matches!(errs[0], crate::errors::Error::Grammar(_)),
+        "expected a grammar error"
+    );
+}
@@ -1,9 +1,13 @@
 use anyhow::anyhow;
 use ariadne::{Config, Label, Report, ReportKind, Source};
-use oal_compiler::module::{Loader, ModuleSet};
+use oal_compiler::errors::Category;
+use oal_compiler::eval::EvalLimits;
+use oal_compiler::module::{LoadLimits, Loader, ModuleSet};
 use oal_compiler::tree::Tree;
 use oal_model::locator::Locator;
 use oal_model::span::Span;
+use std::fmt::{Display, Formatter};
+use std::panic::AssertUnwindSafe;
 use wasm_bindgen::prelude::*;
 extern crate console_error_panic_hook;
 
@@ -13,39 +17,81 @@ const INPUT: &str = "file:///main.oal";
 /// The default error message if something goes very wrong.
 const INTERNAL_ERRROR: &str = "internal error";
 
+/// The maximum size of the generated description in bytes.
+const MAX_OUTPUT_SIZE: usize = 4 * 1024 * 1024;
+
 /// The result of a compilation for interfacing with JavaScript.
 #[wasm_bindgen(getter_with_clone)]
 pub struct CompilationResult {
     pub api: String,
     pub error: String,
+    /// The category of the error, e.g. `syntax`, or empty if unknown or without error.
+    pub category: String,
+}
+
+/// An error report along with the category of the error.
+#[derive(Debug)]
+struct Failure {
+    category: Category,
+    report: String,
+}
+
+impl Display for Failure {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.report)
+    }
+}
+
+impl std::error::Error for Failure {}
+
+/// Returns the category of an error, if known.
+fn category(err: &anyhow::Error) -> Option<Category> {
+    if let Some(f) = err.downcast_ref::<Failure>() {
+        Some(f.category)
+    } else {
+        err.downcast_ref::<oal_compiler::errors::Error>()
+            .map(|e| e.category())
+    }
 }
 
 /// The compiler interface with JavaScript.
 #[wasm_bindgen]
 pub fn compile(input: &str) -> CompilationResult {
     console_error_panic_hook::set_once();
-    match process(input) {
+    // Sources are untrusted, so a panic is reported as an error where unwinding is supported.
+    let result = std::panic::catch_unwind(AssertUnwindSafe(|| process(input)))
+        .unwrap_or_else(|_| Err(anyhow!(INTERNAL_ERRROR)));
+    match result {
         Ok(api) => CompilationResult {
             api,
             error: String::default(),
+            category: String::default(),
         },
         Err(err) => CompilationResult {
             api: String::default(),
             error: err.to_string(),
+            category: category(&err)
+                .map(|c| c.as_str().to_owned())
+                .unwrap_or_default(),
         },
     }
 }
 
+/// Returns the locator of the unique source.
+fn input_locator() -> Locator {
+    Locator::try_from(INPUT).expect("input should be a valid locator")
+}
+
 /// The web loader type for a unique source and no I/O.
 struct WebLoader<'a>(&'a str);
 
 impl Loader<anyhow::Error> for WebLoader<'_> {
     fn is_valid(&mut self, loc: &Locator) -> bool {
-        loc.url().as_str() == INPUT
+        *loc == input_locator()
     }
 
     fn load(&mut self, loc: &Locator) -> anyhow::Result<String> {
-        assert_eq!(loc.url().as_str(), INPUT);
+        assert_eq!(*loc, input_locator());
         Ok(self.0.to_owned())
     }
 
@@ -57,8 +103,13 @@ impl Loader<anyhow::Error> for WebLoader<'_> {
                 oal_syntax::errors::Error::Lexicon(ref err) => err.span(),
                 _ => Span::new(loc, 0..0),
             };
-            let err = report(&input, span, err).unwrap_or(INTERNAL_ERRROR.to_owned());
-            Err(anyhow!(err))
+            let report =
+                report(&input, span, err.code(), err, &[]).unwrap_or(INTERNAL_ERRROR.to_owned());
+            Err(Failure {
+                category: Category::Syntax,
+                report,
+            }
+            .into())
         } else {
             Ok(tree.unwrap())
         }
@@ -70,8 +121,13 @@ impl Loader<anyhow::Error> for WebLoader<'_> {
                 Some(s) => s.clone(),
                 None => Span::new(loc.clone(), 0..0),
             };
-            let err = report(self.0, span, err).unwrap_or(INTERNAL_ERRROR.to_owned());
-            Err(anyhow!(err))
+            let report = report(self.0, span, err.code(), &err, err.labels())
+                .unwrap_or(INTERNAL_ERRROR.to_owned());
+            Err(Failure {
+                category: err.category(),
+                report,
+            }
+            .into())
         } else {
             Ok(())
         }
@@ -81,24 +137,46 @@ impl Loader<anyhow::Error> for WebLoader<'_> {
 /// Runs the end-to-end compilation process on a single input.
 fn process(input: &str) -> anyhow::Result<String> {
     let loader = &mut WebLoader(input);
-    let main = Locator::try_from(INPUT).unwrap();
-    let mods = oal_compiler::module::load(loader, &main)?;
-    let spec = oal_compiler::eval::eval(&mods)?;
-    let builder = oal_openapi::Builder::new(spec);
-    let api = builder.into_openapi();
+    let main = input_locator();
+    let mods = oal_compiler::module::load_with_limits(loader, &main, &LoadLimits::UNTRUSTED)?;
+    let (spec, _) = oal_compiler::eval::eval_with_limits(&mods, &EvalLimits::UNTRUSTED)?;
+    let builder =
+        oal_openapi::Builder::new(spec).with_max_schemas(oal_openapi::MAX_UNTRUSTED_SCHEMAS);
+    let api = builder.into_openapi()?;
     let api_yaml = serde_yaml::to_string(&api)?;
+    if api_yaml.len() > MAX_OUTPUT_SIZE {
+        return Err(anyhow!(
+            "the description has {} bytes, more than the limit of {MAX_OUTPUT_SIZE} bytes",
+            api_yaml.len()
+        ));
+    }
     Ok(api_yaml)
 }
 
 /// Generates an error report.
-fn report<M: ToString>(input: &str, span: Span, msg: M) -> anyhow::Result<String> {
+fn report<M: ToString>(
+    input: &str,
+    span: Span,
+    code: &str,
+    msg: M,
+    labels: &[(Span, String)],
+) -> anyhow::Result<String> {
     let char_span = CharSpan::from(input, span);
     let mut builder = Report::build(ReportKind::Error, char_span.clone())
         .with_config(Config::default().with_color(false))
+        .with_code(code)
         .with_message(msg);
     if !ariadne::Span::is_empty(&char_span) {
         builder.add_label(Label::new(char_span))
     }
+    // Secondary labels can only point within the unique source.
+    for (span, msg) in labels
+        .iter()
+        .filter(|(s, _)| *s.locator() == input_locator())
+    {
+        let char_span = CharSpan::from(input, span.clone());
+        builder.add_label(Label::new(char_span).with_message(msg))
+    }
     let mut buf = Vec::new();
     builder
         .finish()
@@ -145,6 +223,10 @@ fn test_compile_error() {
     let res = compile("res a on get -> {};");
     assert!(res
         .error
-        .starts_with("Error: not in scope: variable is not defined"));
+        .starts_with("[E0006] Error: not in scope: variable is not defined"));
+    assert_eq!(res.category, "type");
     assert!(res.api.is_empty());
+
+    let res = compile("res / on get ->");
+    assert_eq!(res.category, "syntax");
 }